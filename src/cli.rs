@@ -26,6 +26,30 @@ pub struct Cli {
     /// Specify configuration file path
     #[arg(short, long, global = true)]
     pub config: Option<PathBuf>,
+
+    /// READ timeout in seconds (default 300)
+    #[arg(long = "read-timeout", value_name = "SECS", global = true, value_parser = clap::value_parser!(u32).range(1..))]
+    pub read_timeout: Option<u32>,
+
+    /// WRITE and WRITE FILEMARKS timeout in seconds (default 600)
+    #[arg(long = "write-timeout", value_name = "SECS", global = true, value_parser = clap::value_parser!(u32).range(1..))]
+    pub write_timeout: Option<u32>,
+
+    /// LOCATE, REWIND, LOAD/UNLOAD and READ POSITION timeout in seconds (default 600)
+    #[arg(long = "locate-timeout", value_name = "SECS", global = true, value_parser = clap::value_parser!(u32).range(1..))]
+    pub locate_timeout: Option<u32>,
+
+    /// SPACE timeout in seconds (default 600)
+    #[arg(long = "space-timeout", value_name = "SECS", global = true, value_parser = clap::value_parser!(u32).range(1..))]
+    pub space_timeout: Option<u32>,
+
+    /// MODE SENSE/SELECT and other drive query timeout in seconds (default 30)
+    #[arg(long = "mode-sense-timeout", value_name = "SECS", global = true, value_parser = clap::value_parser!(u32).range(1..))]
+    pub mode_sense_timeout: Option<u32>,
+
+    /// INQUIRY and TEST UNIT READY timeout in seconds (default 30)
+    #[arg(long = "inquiry-timeout", value_name = "SECS", global = true, value_parser = clap::value_parser!(u32).range(1..))]
+    pub inquiry_timeout: Option<u32>,
 }
 
 #[derive(Subcommand)]
@@ -35,24 +59,102 @@ pub enum Commands {
     /// 将本地文件或目录写入到LTFS磁带，写入完成后自动更新索引
     Write {
         /// Tape device path (e.g. \\.\TAPE0)
-        #[arg(short = 't', long = "tape", value_name = "DEVICE")]
+        #[arg(short = 't', long = "tape", visible_alias = "device", value_name = "DEVICE")]
         device: String,
 
-        /// Target tape path
-        #[arg(short = 'o', long = "output", value_name = "DESTINATION")]
+        /// Target tape path; a single file written to a directory (ending in `/` or
+        /// already on tape) keeps its name, and missing directories are created
+        #[arg(short = 'o', long = "output", visible_alias = "destination", value_name = "DESTINATION")]
         destination: PathBuf,
 
         /// Source file or folder path (if not provided, read from stdin)
         #[arg(value_name = "SOURCE")]
         source: Option<PathBuf>,
 
-        /// Verify written data using hash comparison
+        /// Re-read each file after writing and compare its SHA-256 with the data sent to the drive
         #[arg(long)]
         verify: bool,
 
+        /// Read each index back from tape right after writing it and fail if it does not parse or match
+        #[arg(long = "append-only-verify")]
+        append_only_verify: bool,
+
+        /// Do not write; reconcile SOURCE against files already on tape (present/missing/orphans)
+        #[arg(long = "verify-only", requires = "source", conflicts_with = "verify")]
+        verify_only: bool,
+
+        /// Do not write; walk SOURCE, build the would-be index and check it fits on the tape
+        #[arg(long = "dry-run", requires = "source", conflicts_with_all = ["verify", "verify_only", "journal"])]
+        dry_run: bool,
+
+        /// Disable drive hardware compression for this write (MODE SELECT page 0x0F)
+        #[arg(long = "no-compression")]
+        no_compression: bool,
+
+        /// Logical block protection while writing: the drive verifies a CRC on every block (crc32c or off)
+        #[arg(long = "lbp", value_name = "METHOD", default_value = "off")]
+        lbp: crate::scsi::LbpMethod,
+
+        /// Write holes of sparse files as zeros instead of skipping them
+        #[arg(long = "no-sparse")]
+        no_sparse: bool,
+
+        /// Write an intermediate index after this much data (e.g. 10G, 500M, or off) [default: 36G]
+        #[arg(long = "index-interval", value_name = "SIZE", value_parser = parse_index_interval)]
+        index_interval: Option<u64>,
+
+        /// Also write an intermediate index once this many minutes have passed since the last one
+        #[arg(long = "index-interval-minutes", value_name = "MINUTES", value_parser = clap::value_parser!(u64).range(1..))]
+        index_interval_minutes: Option<u64>,
+
         /// Show detailed progress information
         #[arg(short, long)]
         progress: bool,
+
+        /// Record directory write progress to a journal file so an interrupted job can be resumed
+        #[arg(long, value_name = "FILE", num_args = 0..=1, default_missing_value = crate::tape_ops::journal::DEFAULT_JOURNAL_FILE)]
+        journal: Option<PathBuf>,
+
+        /// Resume an interrupted directory write from --journal, skipping files already indexed on tape
+        #[arg(long, requires = "journal")]
+        resume: bool,
+
+        /// Skip source paths matching this glob, relative to SOURCE (e.g. '**/node_modules/**', '*.tmp'); repeatable
+        #[arg(long, value_name = "GLOB", value_parser = parse_glob)]
+        exclude: Vec<glob::Pattern>,
+
+        /// Read exclude globs from a file, one per line ('#' starts a comment)
+        #[arg(long = "exclude-from", value_name = "FILE")]
+        exclude_from: Option<PathBuf>,
+
+        /// Descend at most this many directory levels below SOURCE (0 writes only its own files)
+        #[arg(long = "max-depth", value_name = "N", requires = "source")]
+        max_depth: Option<usize>,
+
+        /// Write the files and directories symlinks point to instead of the links; loops are skipped
+        #[arg(long = "follow-symlinks", requires = "source")]
+        follow_symlinks: bool,
+
+        /// Queue at most this many files of one directory; larger directories are written in
+        /// batches (still in name order), re-reading the directory for each batch [default: 100000]
+        #[arg(long = "max-queued-files", value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+        max_queued_files: Option<u64>,
+
+        /// Span the write over several cartridges, recording per-cartridge contents in this manifest
+        #[arg(long = "span-manifest", value_name = "FILE", requires = "source", conflicts_with_all = ["dry_run", "verify_only"])]
+        span_manifest: Option<PathBuf>,
+
+        /// Block size for new data (e.g. 512K); by default the tape's own LTFS block size is used
+        #[arg(long = "block-size", value_name = "SIZE", value_parser = parse_block_size)]
+        block_size: Option<u32>,
+
+        /// Append even if --block-size differs from the block size the tape was formatted with
+        #[arg(long, requires = "block_size")]
+        force: bool,
+
+        /// Write even though the drive reports that it needs cleaning (risks unreadable data)
+        #[arg(long = "ignore-cleaning")]
+        ignore_cleaning: bool,
     },
 
     /// Read from tape
@@ -60,25 +162,401 @@ pub enum Commands {
     /// 从LTFS磁带读取目录和文件列表
     Read {
         /// Tape device path (e.g. \\.\TAPE0)
-        #[arg(short = 't', long = "tape", value_name = "DEVICE")]
+        #[arg(short = 't', long = "tape", visible_alias = "device", value_name = "DEVICE")]
         device: String,
 
         /// Source path in tape (optional - if not provided, list root directory)
         #[arg(value_name = "SOURCE")]
         source: Option<PathBuf>,
+
+        /// Extract SOURCE (file or directory, default root) to this local path instead of listing
+        #[arg(short = 'x', long = "extract", value_name = "DEST")]
+        extract: Option<PathBuf>,
+
+        /// Show a one-line progress status while extracting
+        #[arg(short, long, requires = "extract")]
+        progress: bool,
+
+        /// Span manifest of a multi-cartridge write: extract split files piece by piece
+        #[arg(long = "span-manifest", value_name = "FILE", requires = "extract")]
+        span_manifest: Option<PathBuf>,
+
+        /// List the whole subtree under SOURCE, one path per line relative to SOURCE
+        #[arg(short = 'r', long, conflicts_with_all = ["extract", "export"])]
+        recursive: bool,
+
+        /// With --recursive, also show each entry's UID and modification time
+        #[arg(short = 'l', long, requires = "recursive")]
+        long: bool,
+
+        /// Export the file list under SOURCE to this file instead of listing ("-" for stdout)
+        #[arg(short = 'e', long = "export", value_name = "FILE", conflicts_with = "extract")]
+        export: Option<PathBuf>,
+
+        /// Export format: json, csv or tsv
+        #[arg(long, value_name = "FORMAT", default_value = "csv", requires = "export")]
+        format: crate::ltfs_index::ExportFormat,
+
+        /// Comma-separated columns to export (path,name,uid,size,extents,modifytime,creationtime,hashes)
+        #[arg(long, value_name = "FIELDS", value_delimiter = ',', requires = "export")]
+        fields: Vec<crate::ltfs_index::ExportField>,
+
+        /// Match SOURCE case-insensitively (LTFS names are case-sensitive by default)
+        #[arg(short = 'i', long)]
+        ignore_case: bool,
+
+        /// Read the file with this UID (raw bytes to stdout, or to --extract DEST)
+        #[arg(long, value_name = "N", conflicts_with_all = ["source", "recursive", "export"])]
+        uid: Option<u64>,
+
+        /// Byte offset into the file for --uid
+        #[arg(long, value_name = "BYTES", default_value_t = 0, requires = "uid")]
+        offset: u64,
+
+        /// Number of bytes to read for --uid (default: to end of file)
+        #[arg(long, value_name = "BYTES", requires = "uid")]
+        length: Option<u64>,
+
+        /// Preview the start of file SOURCE: text as is, binary content as a hex dump
+        #[arg(long, requires = "source", conflicts_with_all = ["extract", "export", "recursive", "uid"])]
+        preview: bool,
+
+        /// Number of bytes to preview
+        #[arg(long = "preview-bytes", value_name = "BYTES", default_value_t = 1024)]
+        preview_bytes: u64,
+
+        /// Always preview as a hex dump (implies --preview)
+        #[arg(long, requires = "source", conflicts_with_all = ["extract", "export", "recursive", "uid"])]
+        hex: bool,
+    },
+
+    /// Stream one file from tape to stdout (binary-safe, e.g. `| gzip > file.gz`)
+    ///
+    /// 按路径在索引中查找文件，逐块输出到标准输出；路径是目录时报错
+    #[command(visible_alias = "read-to-stdout")]
+    Cat {
+        /// Tape device path (e.g. \\.\TAPE0)
+        #[arg(short = 't', long = "tape", visible_alias = "device", value_name = "DEVICE")]
+        device: String,
+
+        /// Path of the file on tape
+        #[arg(short = 'p', long = "path", value_name = "TAPE_PATH")]
+        path: String,
+
+        /// Match the path case-insensitively (LTFS names are case-sensitive by default)
+        #[arg(short = 'i', long)]
+        ignore_case: bool,
+    },
+
+    /// Restore the tape index from a local backup (.schema / .xml)
+    ///
+    /// 从本地索引备份恢复磁带索引：校验extent后作为当前索引写回磁带
+    RestoreIndex {
+        /// Tape device path (e.g. \\.\TAPE0)
+        #[arg(short = 't', long = "tape", visible_alias = "device", value_name = "DEVICE")]
+        device: String,
+
+        /// Local index backup file
+        #[arg(value_name = "SCHEMA_FILE")]
+        schema_file: PathBuf,
+    },
+
+    /// Recover the index from the copies in the data partition and save it locally
+    ///
+    /// 索引分区损坏时从数据分区的索引副本恢复索引（选择generation最大的副本）
+    RecoverIndex {
+        /// Tape device path (e.g. \\.\TAPE0)
+        #[arg(short = 't', long = "tape", visible_alias = "device", value_name = "DEVICE")]
+        device: String,
+
+        /// Local file to write the recovered index XML to
+        #[arg(short = 'o', long = "output", value_name = "FILE")]
+        output: PathBuf,
+    },
+
+    /// Compare two saved index files (.schema / .xml) and report the file changes
+    ///
+    /// 比较两个索引文件（如不同generation的备份），列出新增、删除、修改和移动的文件
+    IndexDiff {
+        /// Older index file
+        #[arg(value_name = "OLD")]
+        old: PathBuf,
+
+        /// Newer index file
+        #[arg(value_name = "NEW")]
+        new: PathBuf,
+    },
+
+    /// Dump a whole partition block-by-block to a raw image file
+    ///
+    /// 按块原样转储整个分区（不解析LTFS结构），同时写出 <image>.manifest.json 记录块大小和FileMark位置
+    DumpPartition {
+        /// Tape device path (e.g. \\.\TAPE0)
+        #[arg(short = 't', long = "tape", visible_alias = "device", value_name = "DEVICE")]
+        device: String,
+
+        /// Partition to dump: a (0, index) or b (1, data)
+        #[arg(short = 'p', long, value_name = "PARTITION", value_parser = parse_partition)]
+        partition: u8,
+
+        /// Raw image file to write
+        #[arg(short = 'o', long = "output", value_name = "FILE")]
+        output: PathBuf,
+
+        /// Show a one-line progress status while dumping
+        #[arg(long)]
+        progress: bool,
     },
 
-    /// Show tape space information (free/total)
+    /// Recover data without an index: split a partition on filemarks into numbered files
     ///
-    /// 显示磁带的可用空间和总空间信息
+    /// 最后手段的数据恢复：不读取LTFS索引，顺序读取分区，每个FileMark区间保存为一个文件
+    Scavenge {
+        /// Tape device path (e.g. \\.\TAPE0)
+        #[arg(short = 't', long = "tape", visible_alias = "device", value_name = "DEVICE")]
+        device: String,
+
+        /// Partition to scan: a (0, index) or b (1, data)
+        #[arg(short = 'p', long, value_name = "PARTITION", value_parser = parse_partition, default_value = "b")]
+        partition: u8,
+
+        /// Block of the partition to start reading at
+        #[arg(long = "start-block", value_name = "N", default_value_t = 0)]
+        start_block: u64,
+
+        /// Directory for the recovered files (created if missing)
+        #[arg(short = 'o', long = "output-dir", value_name = "DIR")]
+        output_dir: PathBuf,
+
+        /// Show a one-line progress status while scanning
+        #[arg(long)]
+        progress: bool,
+    },
+
+    /// Unload and eject the tape
+    ///
+    /// 卸载并弹出磁带
+    Eject {
+        /// Tape device path (e.g. \\.\TAPE0)
+        #[arg(short = 't', long = "tape", visible_alias = "device", value_name = "DEVICE")]
+        device: String,
+    },
+
+    /// List the tape drives attached to this system
+    ///
+    /// 枚举系统中的磁带驱动器（设备路径、厂商、型号、序列号、已装载磁带条码）
+    ListDrives {
+        /// Only print the device path of the drive holding the tape with this barcode
+        #[arg(short = 'b', long = "barcode", value_name = "BARCODE")]
+        barcode: Option<String>,
+    },
+
+    /// Remove a file from the tape index (its data blocks stay on tape)
+    ///
+    /// 从索引中删除文件；磁带只能追加，数据块仍保留在磁带上
+    Rm {
+        /// Tape device path (e.g. \\.\TAPE0)
+        #[arg(short = 't', long = "tape", visible_alias = "device", value_name = "DEVICE")]
+        device: String,
+
+        /// Path of the file on tape
+        #[arg(short = 'p', long = "path", value_name = "TAPE_PATH")]
+        path: String,
+    },
+
+    /// Rename or move a file or directory in the tape index (no data is moved)
+    ///
+    /// 在索引中重命名或移动文件/目录；目标为已存在的目录时移入其中
+    Mv {
+        /// Tape device path (e.g. \\.\TAPE0)
+        #[arg(short = 't', long = "tape", visible_alias = "device", value_name = "DEVICE")]
+        device: String,
+
+        /// Current path on tape
+        #[arg(value_name = "SOURCE")]
+        source: String,
+
+        /// New path, or an existing directory to move SOURCE into
+        #[arg(value_name = "DESTINATION")]
+        destination: String,
+    },
+
+    /// Erase the tape (short: mark EOD at the beginning, long: overwrite the whole tape)
+    ///
+    /// 擦除磁带，需要用 --confirm-barcode 核对条码
+    Erase {
+        /// Tape device path (e.g. \\.\TAPE0)
+        #[arg(short = 't', long = "tape", visible_alias = "device", value_name = "DEVICE")]
+        device: String,
+
+        /// Barcode of the cartridge to erase, must match the cartridge memory
+        #[arg(long = "confirm-barcode", value_name = "BARCODE")]
+        confirm_barcode: String,
+
+        /// Long erase: overwrite the whole tape instead of writing EOD at the beginning
+        #[arg(long)]
+        long: bool,
+
+        /// Return as soon as the drive accepts the command (erase continues in the background)
+        #[arg(long)]
+        immediate: bool,
+    },
+
+    /// Format the tape as an LTFS volume (partition, label and empty index)
+    ///
+    /// 格式化为LTFS卷（对应LTFSCopyGUI的mkltfs），磁带上原有数据全部丢失
+    Format {
+        /// Tape device path (e.g. \\.\TAPE0)
+        #[arg(short = 't', long = "tape", visible_alias = "device", value_name = "DEVICE")]
+        device: String,
+
+        /// Barcode to write to the cartridge memory (first 6 characters become the volume serial)
+        #[arg(long, value_name = "BARCODE")]
+        barcode: String,
+
+        /// Volume name stored in the cartridge memory text label and the index
+        #[arg(long = "volume-label", value_name = "NAME")]
+        volume_label: Option<String>,
+
+        /// Format a single partition instead of index + data partitions
+        #[arg(long = "single-partition")]
+        single_partition: bool,
+
+        /// Index partition (P0) size, e.g. 100G, or 'max'; the data partition takes the rest
+        #[arg(long = "p0-size", value_name = "SIZE", value_parser = parse_partition_size)]
+        p0_size: Option<u64>,
+
+        /// Data partition (P1) size, e.g. 2000G, or 'max' (default); only one of --p0-size and --p1-size may be 'max'
+        #[arg(long = "p1-size", value_name = "SIZE", value_parser = parse_partition_size)]
+        p1_size: Option<u64>,
+
+        /// LTFS block size recorded in the partition labels
+        #[arg(long = "block-size", value_name = "SIZE", default_value = "512K", value_parser = parse_block_size)]
+        block_size: u32,
+
+        /// File with the 256-bit drive encryption key (64 hex digits or 32 raw bytes)
+        #[arg(long = "encryption-key-file", value_name = "FILE")]
+        encryption_key_file: Option<PathBuf>,
+    },
+
+    /// Write a new barcode to the cartridge memory, optionally renaming the volume
+    ///
+    /// 更新卡带存储器中的条码；指定 --volume-label 时同时更新卷名并提交新一代索引
+    SetBarcode {
+        /// Tape device path (e.g. \\.\TAPE0)
+        #[arg(short = 't', long = "tape", visible_alias = "device", value_name = "DEVICE")]
+        device: String,
+
+        /// Barcode to write (1-32 printable ASCII characters)
+        #[arg(long, value_name = "BARCODE")]
+        barcode: String,
+
+        /// New volume name for the cartridge memory text label and the index
+        #[arg(long = "volume-label", value_name = "NAME")]
+        volume_label: Option<String>,
+    },
+
+    /// Show drive vendor, product, firmware revision and serial number
+    ///
+    /// 显示驱动器信息（INQUIRY），无需装入磁带；加 --medium 时汇总已装入磁带的诊断信息
+    Info {
+        /// Tape device path (e.g. \\.\TAPE0)
+        #[arg(short = 't', long = "tape", visible_alias = "device", value_name = "DEVICE")]
+        device: String,
+
+        /// Report on the loaded cartridge: barcode, media type, LTFS volume, capacity, compression, TapeAlert
+        #[arg(short = 'm', long)]
+        medium: bool,
+    },
+
+    /// Check every file on tape against the hash stored in the index
+    ///
+    /// 整盘校验：读回每个文件并与索引中记录的哈希比对，任一失败则返回非零退出码
+    Verify {
+        /// Tape device path (e.g. \\.\TAPE0)
+        #[arg(short = 't', long = "tape", visible_alias = "device", value_name = "DEVICE")]
+        device: String,
+
+        /// Use a local index file (.schema / .xml) instead of reading it from tape
+        #[arg(short = 'i', long = "index", value_name = "INDEX_FILE")]
+        index: Option<PathBuf>,
+
+        /// Instead of hashing data, check every extent lies before EOD of a data partition
+        #[arg(long)]
+        extents: bool,
+    },
+
+    /// Quick integrity check of a tape without reading file data in full
+    ///
+    /// 快速检查：索引规则、分区标签卷UUID、抽样读取文件extent、各分区EOD可达；任一失败则返回非零退出码
+    Check {
+        /// Tape device path (e.g. \\.\TAPE0)
+        #[arg(short = 't', long = "tape", visible_alias = "device", value_name = "DEVICE")]
+        device: String,
+
+        /// Number of files whose first extent is located and read
+        #[arg(short = 'n', long, default_value_t = 16)]
+        samples: usize,
+    },
+
+    /// Show tape space information (free/total), or position the tape explicitly
+    ///
+    /// 显示磁带的可用空间和总空间信息；指定 --to-* 时改为定位磁带并输出位置
     Space {
         /// Tape device path (e.g. \\.\TAPE0)
-        #[arg(short = 't', long = "tape", value_name = "DEVICE")]
+        #[arg(short = 't', long = "tape", visible_alias = "device", value_name = "DEVICE")]
         device: String,
 
         /// Show detailed space breakdown
         #[arg(short = 'd', long = "detailed")]
         detailed: bool,
+
+        /// Move to just after the Nth filemark of the partition
+        #[arg(long = "to-filemark", value_name = "N", group = "target")]
+        to_filemark: Option<u64>,
+
+        /// Move to logical block N of the partition
+        #[arg(long = "to-block", value_name = "N", group = "target")]
+        to_block: Option<u64>,
+
+        /// Move to the end of data of the partition
+        #[arg(long = "to-eod", group = "target")]
+        to_eod: bool,
+
+        /// Partition to position in: a (0, index) or b (1, data)
+        #[arg(
+            short = 'p',
+            long,
+            value_name = "PARTITION",
+            value_parser = parse_partition,
+            default_value = "a",
+            requires = "target"
+        )]
+        partition: u8,
+    },
+
+    /// Drive diagnostics for tuning
+    ///
+    /// 驱动器诊断工具（定位耗时测试等）
+    Diag {
+        #[command(subcommand)]
+        command: DiagCommands,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum DiagCommands {
+    /// Measure LOCATE latency by seeking to random blocks of the data partition
+    ///
+    /// 在数据分区内随机定位，统计最小/最大/平均/p95耗时
+    Seek {
+        /// Tape device path (e.g. \\.\TAPE0)
+        #[arg(short = 't', long = "tape", visible_alias = "device", value_name = "DEVICE")]
+        device: String,
+
+        /// Number of random seeks to time
+        #[arg(short = 'n', long, default_value_t = 20, value_parser = clap::value_parser!(u64).range(1..))]
+        samples: u64,
     },
 }
 
@@ -86,4 +564,122 @@ impl Cli {
     pub fn parse_args() -> Self {
         Self::parse()
     }
+
+    /// SCSI timeouts: defaults, then `RUSTLTFS_TIMEOUT_*` variables, then the `--*-timeout` options
+    pub fn scsi_timeouts(&self) -> crate::scsi::Timeouts {
+        let mut timeouts = crate::scsi::Timeouts::default().with_env_overrides();
+        if let Some(secs) = self.read_timeout {
+            timeouts = timeouts.with_read(secs);
+        }
+        if let Some(secs) = self.write_timeout {
+            timeouts = timeouts.with_write(secs);
+        }
+        if let Some(secs) = self.locate_timeout {
+            timeouts = timeouts.with_locate(secs);
+        }
+        if let Some(secs) = self.space_timeout {
+            timeouts = timeouts.with_space(secs);
+        }
+        if let Some(secs) = self.mode_sense_timeout {
+            timeouts = timeouts.with_mode_sense(secs);
+        }
+        if let Some(secs) = self.inquiry_timeout {
+            timeouts = timeouts.with_inquiry(secs);
+        }
+        timeouts
+    }
+}
+
+/// Parse an index write interval: a size such as `10G` or `500M`, or `off` to disable it
+fn parse_index_interval(s: &str) -> Result<u64, String> {
+    if s.eq_ignore_ascii_case("off") {
+        return Ok(u64::MAX);
+    }
+    match crate::utils::parse_byte_size(s)? {
+        0 => Err("index interval must be greater than 0 (use 'off' to disable)".to_string()),
+        bytes => Ok(bytes),
+    }
+}
+
+/// Parse an exclude glob pattern
+fn parse_glob(s: &str) -> Result<glob::Pattern, String> {
+    glob::Pattern::new(s).map_err(|e| format!("invalid glob '{}': {}", s, e))
+}
+
+/// Parse a partition size such as `100G`, or `max` for the remaining capacity (`u64::MAX`)
+fn parse_partition_size(s: &str) -> Result<u64, String> {
+    if s.eq_ignore_ascii_case("max") {
+        return Ok(u64::MAX);
+    }
+    crate::utils::parse_byte_size(s)
+}
+
+/// Parse a block size such as `512K`
+fn parse_block_size(s: &str) -> Result<u32, String> {
+    let bytes = crate::utils::parse_byte_size(s)?;
+    u32::try_from(bytes).map_err(|_| format!("block size '{}' is too large", s))
+}
+
+/// Parse a partition given as an LTFS letter (a/b) or number (0/1)
+fn parse_partition(s: &str) -> Result<u8, String> {
+    match s.to_ascii_lowercase().as_str() {
+        "a" | "0" => Ok(0),
+        "b" | "1" => Ok(1),
+        _ => Err(format!("invalid partition '{}' (expected a, b, 0 or 1)", s)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    /// 解析命令行并返回子命令中的设备路径
+    fn device_of(args: &[&str]) -> String {
+        match Cli::try_parse_from(args).unwrap().command {
+            Commands::Read { device, .. }
+            | Commands::Format { device, .. }
+            | Commands::SetBarcode { device, .. }
+            | Commands::Eject { device }
+            | Commands::Info { device, .. }
+            | Commands::Erase { device, .. }
+            | Commands::Diag { command: DiagCommands::Seek { device, .. } } => device,
+            _ => unreachable!("unexpected subcommand"),
+        }
+    }
+
+    #[test]
+    fn device_flag_and_alias_parse_for_every_subcommand() {
+        Cli::command().debug_assert();
+
+        let cases: [&[&str]; 7] = [
+            &["read"],
+            &["format", "--barcode", "ABC123L8"],
+            &["set-barcode", "--barcode", "ABC123L8"],
+            &["eject"],
+            &["info"],
+            &["erase", "--confirm-barcode", "ABC123L8"],
+            &["diag", "seek"],
+        ];
+        for case in cases {
+            for flag in ["-t", "--tape", "--device"] {
+                let mut args = vec!["rustltfs"];
+                args.extend_from_slice(case);
+                args.extend_from_slice(&[flag, "/dev/nst0"]);
+                assert_eq!(device_of(&args), "/dev/nst0", "{:?}", args);
+            }
+        }
+
+        // 所有子命令的设备参数都以同一形式显示别名
+        let command = Cli::command();
+        let devices: Vec<_> = command
+            .get_subcommands()
+            .flat_map(|c| std::iter::once(c).chain(c.get_subcommands()))
+            .filter_map(|c| c.get_arguments().find(|arg| arg.get_id() == "device").map(|arg| (c.get_name(), arg)))
+            .collect();
+        assert!(devices.len() >= 18, "{}", devices.len());
+        for (name, arg) in devices {
+            assert_eq!(arg.get_visible_aliases(), Some(vec!["device"]), "{}", name);
+        }
+    }
 }
@@ -0,0 +1,44 @@
+//! Cat Command Handler
+//!
+//! Handles the `cat` subcommand for streaming one tape file to stdout.
+
+use crate::error::Result;
+use crate::ltfs_index::PathNormalize;
+use crate::tape_ops;
+use std::io::Write;
+use std::sync::Arc;
+use tokio_stream::StreamExt;
+use tracing::info;
+
+pub async fn execute(device: String, path: String, ignore_case: bool) -> Result<()> {
+    info!("Streaming {} from {} to stdout", path, device);
+
+    let mut ops = tape_ops::TapeOperations::new(&device);
+    if ignore_case {
+        ops.set_path_normalize(PathNormalize::CaseInsensitive);
+    }
+    ops.initialize(Some(tape_ops::core::OperationType::Read))
+        .await?;
+
+    // 原样输出字节，不做任何文本转换；日志和提示都在stderr
+    let ops = Arc::new(ops);
+    let mut stream = std::pin::pin!(ops.read_path_stream(&path)?);
+    let mut stdout = std::io::stdout().lock();
+    let mut total = 0u64;
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk?;
+        if let Err(e) = stdout.write_all(&chunk) {
+            // 下游提前退出（如 `| head`）不算失败
+            if e.kind() == std::io::ErrorKind::BrokenPipe {
+                info!("stdout closed after {} bytes of {}", total, path);
+                return Ok(());
+            }
+            return Err(e.into());
+        }
+        total += chunk.len() as u64;
+    }
+    stdout.flush()?;
+
+    info!("Wrote {} bytes of {} to stdout", total, path);
+    Ok(())
+}
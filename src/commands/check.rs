@@ -0,0 +1,45 @@
+//! Check Command Handler
+//!
+//! Handles the `check` subcommand for a quick integrity scan of a tape.
+
+use crate::error::{Result, RustLtfsError};
+use crate::tape_ops;
+use crate::tape_ops::check::CheckStatus;
+use crate::utils;
+use tracing::info;
+
+pub async fn execute(device: String, samples: usize) -> Result<()> {
+    info!("Checking tape in {}", device);
+
+    let mut ops = tape_ops::TapeOperations::new(&device);
+    ops.initialize(Some(tape_ops::core::OperationType::Read))
+        .await?;
+    utils::cancel_on_ctrl_c(ops.cancel_flag());
+
+    println!("\n🔍 Checking tape integrity");
+    println!("  Device: {}", device);
+
+    let report = ops.check_tape(samples).await?;
+
+    println!("\n📋 Check Results:");
+    for item in &report.items {
+        let icon = match item.status {
+            CheckStatus::Pass => "✅",
+            CheckStatus::Warn => "⚠️ ",
+            CheckStatus::Fail => "❌",
+        };
+        println!("  {} {}: {}", icon, item.name, item.detail);
+    }
+
+    if report.passed() {
+        println!("\n✅ PASS");
+        Ok(())
+    } else {
+        println!("\n❌ FAIL");
+        Err(RustLtfsError::verification(format!(
+            "{} of {} checks failed",
+            report.failures(),
+            report.items.len()
+        )))
+    }
+}
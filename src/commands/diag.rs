@@ -0,0 +1,44 @@
+//! Diag Command Handler
+//!
+//! Handles the `diag` subcommands: drive measurements used for tuning,
+//! currently `diag seek` for LOCATE latency.
+
+use crate::error::Result;
+use crate::tape_ops;
+use tracing::info;
+
+/// Handle `diag seek`: time random LOCATEs across the data partition
+pub async fn execute_seek(device: String, samples: usize) -> Result<()> {
+    info!("Seek benchmark on {} ({} samples)", device, samples);
+
+    let mut ops = tape_ops::TapeOperations::new(&device);
+
+    // Seeking does not need the LTFS index
+    ops.initialize(Some(tape_ops::core::OperationType::Space))
+        .await?;
+
+    println!("\n⏱️  Seek Benchmark: {}", device);
+    let stats = ops.seek_benchmark(samples)?;
+
+    println!(
+        "  • Partition: {} (blocks 0..{})",
+        stats.partition, stats.eod_block
+    );
+    println!("  • Samples: {}", stats.samples.len());
+    println!("  • Min: {:.3} s", stats.min.as_secs_f64());
+    println!("  • Max: {:.3} s", stats.max.as_secs_f64());
+    println!("  • Avg: {:.3} s", stats.avg.as_secs_f64());
+    println!("  • p95: {:.3} s", stats.p95.as_secs_f64());
+    println!(
+        "  • Command: {}",
+        if stats.locate16 { "LOCATE(16)" } else { "LOCATE(10)" }
+    );
+    if stats.locate10_fallbacks > 0 {
+        println!(
+            "  • LOCATE(10) fallbacks: {} (LOCATE(16) was rejected and retried)",
+            stats.locate10_fallbacks
+        );
+    }
+
+    Ok(())
+}
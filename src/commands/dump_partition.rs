@@ -0,0 +1,66 @@
+//! Dump-Partition Command Handler
+//!
+//! Handles the `dump-partition` subcommand for raw block-by-block imaging of a partition.
+
+use crate::error::Result;
+use crate::tape_ops;
+use crate::utils;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::info;
+
+/// Minimum interval between progress line updates
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+pub async fn execute(device: String, partition: u8, output: PathBuf, progress: bool) -> Result<()> {
+    info!("Dumping partition {} of {} -> {:?}", partition, device, output);
+
+    let mut ops = tape_ops::TapeOperations::new(&device);
+
+    // Raw imaging does not depend on the LTFS structure, so do not load the index
+    ops.initialize(Some(tape_ops::core::OperationType::Space))
+        .await?;
+
+    println!("\n💾 Dumping partition {} -> {:?}", partition, output);
+
+    let callback: Option<tape_ops::DumpProgressCallback> = if progress {
+        let last_update = Arc::new(Mutex::new(None::<Instant>));
+        Some(Arc::new(move |p: &tape_ops::DumpProgress| {
+            let mut last = last_update.lock().unwrap_or_else(|e| e.into_inner());
+            if last.is_some_and(|t| t.elapsed() < PROGRESS_INTERVAL) {
+                return;
+            }
+            *last = Some(Instant::now());
+
+            print!(
+                "\r  {} blocks, {}, {} filemarks\x1b[K",
+                p.blocks,
+                utils::format_bytes(p.bytes),
+                p.filemarks
+            );
+            let _ = std::io::stdout().flush();
+        }))
+    } else {
+        None
+    };
+
+    utils::cancel_on_ctrl_c(ops.cancel_flag());
+    let result = ops.dump_partition(partition, &output, callback);
+    if progress {
+        println!();
+    }
+    let manifest = result?;
+
+    println!("✅ Partition dumped");
+    println!("  • Data blocks: {}", manifest.data_blocks);
+    println!("  • Size: {}", utils::format_bytes(manifest.bytes));
+    println!("  • Filemarks: {}", manifest.filemarks.len());
+    println!(
+        "  • Manifest: {:?}",
+        tape_ops::dump::manifest_path(&output)
+    );
+
+    Ok(())
+}
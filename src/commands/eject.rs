@@ -0,0 +1,23 @@
+//! Eject Command Handler
+//!
+//! Handles the `eject` subcommand for unloading the tape from the drive.
+
+use crate::error::Result;
+use crate::tape_ops;
+use tracing::info;
+
+pub async fn execute(device: String) -> Result<()> {
+    info!("Ejecting tape: {}", device);
+
+    let mut ops = tape_ops::TapeOperations::new(&device);
+
+    // No index is needed to unload the medium
+    ops.initialize(Some(tape_ops::core::OperationType::Space))
+        .await?;
+
+    println!("⏏️  Unloading tape from {}...", device);
+    ops.eject().await?;
+    println!("✅ Tape ejected");
+
+    Ok(())
+}
@@ -0,0 +1,38 @@
+//! Erase Command Handler
+//!
+//! Handles the `erase` subcommand for erasing a tape after barcode confirmation.
+
+use crate::error::Result;
+use crate::tape_ops;
+use tracing::info;
+
+pub async fn execute(
+    device: String,
+    confirm_barcode: String,
+    long: bool,
+    immediate: bool,
+) -> Result<()> {
+    info!("Erasing tape: {} (barcode {})", device, confirm_barcode);
+
+    let mut ops = tape_ops::TapeOperations::new(&device);
+
+    // No index is needed to erase the medium
+    ops.initialize(Some(tape_ops::core::OperationType::Space))
+        .await?;
+
+    println!(
+        "🧹 {} erase of cartridge {} on {}...",
+        if long { "Long" } else { "Short" },
+        confirm_barcode,
+        device
+    );
+    ops.erase_tape(long, immediate, &confirm_barcode).await?;
+
+    if immediate {
+        println!("✅ Erase started, the drive continues in the background");
+    } else {
+        println!("✅ Tape erased");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,95 @@
+//! Format Command Handler
+//!
+//! Handles the `format` subcommand for turning a cartridge into an empty LTFS volume.
+
+use crate::error::{Result, RustLtfsError};
+use crate::tape_ops;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::info;
+use zeroize::Zeroizing;
+
+pub async fn execute(
+    device: String,
+    barcode: String,
+    volume_label: String,
+    single_partition: bool,
+    p0_size: Option<u64>,
+    p1_size: Option<u64>,
+    block_size: u32,
+    encryption_key_file: Option<PathBuf>,
+) -> Result<()> {
+    info!("Formatting tape: {} (barcode {})", device, barcode);
+
+    let mut params = tape_ops::MkltfsParams::new(&barcode, &volume_label);
+    params.block_size = block_size;
+    if single_partition {
+        params.extra_partition_count = 0;
+    }
+    if let Some(bytes) = p0_size {
+        params.set_p0_size(bytes);
+    }
+    if let Some(bytes) = p1_size {
+        params.set_p1_size(bytes);
+    }
+    if let Some(path) = &encryption_key_file {
+        params.encryption_key = Some(load_encryption_key(path)?);
+    }
+
+    // 先校验参数，避免连接驱动器后才报错
+    if let Err(e) = params.validate() {
+        println!("❌ Invalid format parameters: {}", e);
+        return Err(e);
+    }
+
+    let mut ops = tape_ops::TapeOperations::new(&device);
+
+    // The medium is about to be rewritten, so do not load the index
+    ops.initialize(Some(tape_ops::core::OperationType::Space))
+        .await?;
+
+    println!(
+        "💽 Formatting cartridge {} on {} ({}, {} blocks{})",
+        params.barcode,
+        device,
+        if single_partition { "single partition" } else { "index + data partitions" },
+        crate::utils::format_bytes(block_size as u64),
+        if params.encryption_key.is_some() { ", encrypted" } else { "" }
+    );
+
+    let progress: tape_ops::FormatProgressCallback = Arc::new(|step: &str| {
+        println!("  • {}", step);
+    });
+    let index = ops.mkltfs(&params, Some(progress)).await?;
+
+    println!("✅ LTFS volume created");
+    println!("  • Volume UUID: {}", index.volumeuuid);
+    println!("  • Barcode: {}", params.barcode);
+    if !params.volume_label.is_empty() {
+        println!("  • Volume label: {}", params.volume_label);
+    }
+
+    Ok(())
+}
+
+/// 读取加密密钥文件：64个十六进制字符，或32字节原始密钥
+fn load_encryption_key(path: &Path) -> Result<Zeroizing<Vec<u8>>> {
+    let contents = Zeroizing::new(std::fs::read(path).map_err(|e| {
+        RustLtfsError::file_operation(format!("Cannot read encryption key file {:?}: {}", path, e))
+    })?);
+
+    let text = std::str::from_utf8(&contents).map(str::trim).unwrap_or_default();
+    if text.len() == 64 && text.bytes().all(|b| b.is_ascii_hexdigit()) {
+        let key = hex::decode(text).map_err(|e| {
+            RustLtfsError::parameter_validation(format!("Invalid hex key in {:?}: {}", path, e))
+        })?;
+        return Ok(Zeroizing::new(key));
+    }
+    if contents.len() == 32 {
+        return Ok(Zeroizing::new(contents.to_vec()));
+    }
+    Err(RustLtfsError::parameter_validation(format!(
+        "Encryption key file {:?} must contain 64 hex digits or 32 raw bytes",
+        path
+    )))
+}
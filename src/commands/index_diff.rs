@@ -0,0 +1,83 @@
+//! Index-Diff Command Handler
+//!
+//! Handles the `index-diff` subcommand for comparing two saved index files.
+
+use crate::error::{Result, RustLtfsError};
+use crate::ltfs_index::{FileChange, LtfsIndex};
+use crate::utils;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+fn load_index(path: &Path) -> Result<LtfsIndex> {
+    let file = std::fs::File::open(path).map_err(|e| {
+        RustLtfsError::file_operation(format!("Cannot read index file {:?}: {}", path, e))
+    })?;
+    LtfsIndex::from_xml_reader(std::io::BufReader::new(file))
+}
+
+fn signed_bytes(delta: i64) -> String {
+    let sign = if delta < 0 { "-" } else { "+" };
+    format!("{}{}", sign, utils::format_bytes(delta.unsigned_abs()))
+}
+
+fn describe_change(change: &FileChange) -> String {
+    match change {
+        FileChange::Length => "length".to_string(),
+        FileChange::ModifyTime => "modifytime".to_string(),
+        FileChange::Hash(algorithm) => algorithm.clone(),
+    }
+}
+
+pub async fn execute(old: PathBuf, new: PathBuf) -> Result<()> {
+    info!("Comparing indexes: {:?} -> {:?}", old, new);
+
+    let old_index = load_index(&old)?;
+    let new_index = load_index(&new)?;
+    let diff = old_index.diff(&new_index);
+
+    println!("\n📑 Index Diff");
+    println!("  Old: {:?} (generation {})", old, diff.old_generation);
+    println!("  New: {:?} (generation {})", new, diff.new_generation);
+    println!("  Generation gap: {}", diff.generation_gap());
+    if old_index.volumeuuid != new_index.volumeuuid {
+        println!(
+            "  ⚠️  Different volumes: {} vs {}",
+            old_index.volumeuuid, new_index.volumeuuid
+        );
+    }
+
+    if diff.is_empty() {
+        println!("\n✅ No file changes");
+        return Ok(());
+    }
+
+    for file in &diff.added {
+        println!("  + {} ({})", file.path, utils::format_bytes(file.size));
+    }
+    for file in &diff.removed {
+        println!("  - {} ({})", file.path, utils::format_bytes(file.size));
+    }
+    for file in &diff.modified {
+        let changes: Vec<String> = file.changes.iter().map(describe_change).collect();
+        println!(
+            "  ~ {} ({} -> {}, {}): {}",
+            file.new.path,
+            utils::format_bytes(file.old.size),
+            utils::format_bytes(file.new.size),
+            signed_bytes(file.size_delta()),
+            changes.join(", ")
+        );
+    }
+    for file in &diff.moved {
+        println!("  > {} -> {} (UID {})", file.old_path, file.new_path, file.uid);
+    }
+
+    println!("\n📋 Summary:");
+    println!("  Added: {}", diff.added.len());
+    println!("  Removed: {}", diff.removed.len());
+    println!("  Modified: {}", diff.modified.len());
+    println!("  Moved: {}", diff.moved.len());
+    println!("  Size change: {}", signed_bytes(diff.size_delta()));
+
+    Ok(())
+}
@@ -0,0 +1,173 @@
+//! Info Command Handler
+//!
+//! Handles the `info` subcommand for showing drive identification (INQUIRY),
+//! and with `--medium` a diagnostic report on the loaded cartridge.
+
+use crate::error::Result;
+use crate::tape_ops;
+use crate::utils;
+use tracing::info;
+
+const UNAVAILABLE: &str = "unavailable";
+
+pub async fn execute(device: String, medium: bool) -> Result<()> {
+    info!("Querying drive information: {}", device);
+
+    let mut ops = tape_ops::TapeOperations::new(&device);
+
+    // INQUIRY does not need a tape, so only open the device
+    ops.open_drive()?;
+    let drive = ops.identify_drive()?;
+
+    println!("\n🖴  Drive Information: {}", device);
+    println!("  • Vendor: {}", drive.vendor_id);
+    println!("  • Product: {}", drive.product_id);
+    println!("  • Revision: {}", drive.product_revision);
+    println!(
+        "  • Serial Number: {}",
+        drive.serial_number.as_deref().unwrap_or("(not reported)")
+    );
+
+    if medium {
+        let report = ops.collect_medium_report().await?;
+        print_medium_report(&report);
+    }
+
+    Ok(())
+}
+
+fn print_medium_report(report: &tape_ops::MediumReport) {
+    println!("\n📼 Medium Information:");
+    println!(
+        "  • Barcode: {}",
+        report.barcode.as_deref().unwrap_or(UNAVAILABLE)
+    );
+    println!(
+        "  • Media Type: {}",
+        report
+            .media_type
+            .map(|media| format!("{:?}", media))
+            .unwrap_or_else(|| UNAVAILABLE.to_string())
+    );
+
+    match &report.ltfs_label {
+        Some(label) => {
+            println!("  • LTFS Format: formatted ({}-byte blocks)", label.blocksize);
+            if let Some(ref format_time) = label.format_time {
+                println!("  • Format Time: {}", format_time);
+            }
+        }
+        None => println!("  • LTFS Format: not detected"),
+    }
+    match &report.index {
+        Some(stats) => {
+            println!("  • Volume UUID: {}", stats.volume_uuid);
+            println!("  • Generation Number: {}", stats.generation_number);
+            println!(
+                "  • Contents: {} files, {} directories, {}",
+                stats.total_files,
+                stats.total_directories,
+                utils::format_bytes(stats.total_size)
+            );
+        }
+        None => {
+            let uuid = report
+                .ltfs_label
+                .as_ref()
+                .and_then(|label| label.volume_uuid.as_deref())
+                .unwrap_or(UNAVAILABLE);
+            println!("  • Volume UUID: {}", uuid);
+            println!("  • Generation Number: {}", UNAVAILABLE);
+        }
+    }
+
+    println!(
+        "  • Partitions: {}",
+        report
+            .extra_partition_count
+            .map(|extra| (extra + 1).to_string())
+            .unwrap_or_else(|| UNAVAILABLE.to_string())
+    );
+    match &report.capacity {
+        Some(capacity) => {
+            // 容量以KB为单位
+            println!(
+                "  • Partition 0 Remaining: {} of {}",
+                utils::format_bytes(capacity.p0_remaining * 1024),
+                utils::format_bytes(capacity.p0_maximum * 1024)
+            );
+            if report.extra_partition_count.unwrap_or(0) > 0 {
+                println!(
+                    "  • Partition 1 Remaining: {} of {}",
+                    utils::format_bytes(capacity.p1_remaining * 1024),
+                    utils::format_bytes(capacity.p1_maximum * 1024)
+                );
+            }
+        }
+        None => println!("  • Remaining Capacity: {}", UNAVAILABLE),
+    }
+
+    match report.compression {
+        Some(state) => println!(
+            "  • Hardware Compression: {} (drive capable: {})",
+            if state.dce { "enabled" } else { "disabled" },
+            if state.dcc { "yes" } else { "no" }
+        ),
+        None => println!("  • Hardware Compression: {}", UNAVAILABLE),
+    }
+
+    match report.encryption {
+        Some(status) if status.is_encrypting() => println!(
+            "  • Encryption: enabled (algorithm index {}, key instance {})",
+            status.algorithm_index, status.key_instance_counter
+        ),
+        Some(_) => println!("  • Encryption: disabled"),
+        None => println!("  • Encryption: {}", UNAVAILABLE),
+    }
+
+    match &report.tape_alerts {
+        Some(flags) if flags.is_empty() => println!("  • TapeAlert: none"),
+        Some(flags) => {
+            println!("  • TapeAlert:");
+            for flag in flags {
+                println!("      ⚠️  {:?}", flag);
+            }
+        }
+        None => println!("  • TapeAlert: {}", UNAVAILABLE),
+    }
+
+    match &report.error_counters {
+        Some(counters) => {
+            print_error_counters("Write Errors", &counters.write, "rewrites");
+            print_error_counters("Read Errors", &counters.read, "rereads");
+        }
+        None => println!("  • Error Counters: {}", UNAVAILABLE),
+    }
+
+    match &report.cartridge_memory {
+        Some(attributes) => {
+            println!("  • Cartridge Memory:");
+            for attribute in attributes {
+                println!(
+                    "      0x{:04X} {}: {}",
+                    attribute.id,
+                    attribute.name().unwrap_or("Unknown"),
+                    attribute.decoded_value()
+                );
+            }
+        }
+        None => println!("  • Cartridge Memory: {}", UNAVAILABLE),
+    }
+}
+
+fn print_error_counters(label: &str, counters: &tape_ops::drive_health::ErrorCounters, retries: &str) {
+    println!(
+        "  • {}: {} corrected, {} uncorrected, {} {} ({} processed)",
+        label,
+        counters.corrected,
+        counters.uncorrected,
+        counters.retries,
+        retries,
+        utils::format_bytes(counters.bytes_processed)
+    );
+}
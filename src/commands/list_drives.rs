@@ -0,0 +1,55 @@
+//! List-Drives Command Handler
+//!
+//! Handles the `list-drives` subcommand for enumerating the tape drives on the system.
+
+use crate::error::{Result, RustLtfsError};
+use crate::scsi;
+use tracing::info;
+
+pub async fn execute(barcode: Option<String>) -> Result<()> {
+    if let Some(barcode) = barcode {
+        return execute_find(barcode).await;
+    }
+    info!("Discovering tape drives");
+
+    // Opening each candidate and issuing INQUIRY blocks, keep it off the async workers
+    let drives = tokio::task::spawn_blocking(scsi::discover_tape_drives)
+        .await
+        .map_err(|e| RustLtfsError::system(format!("Drive discovery failed: {}", e)))?;
+
+    if drives.is_empty() {
+        println!("No tape drives found (drives in use by another program are skipped)");
+        return Ok(());
+    }
+
+    println!("\n🖴  Tape Drives:");
+    for drive in &drives {
+        println!(
+            "  • {}  {} {} (rev {})  S/N {}  Tape: {}",
+            drive.path,
+            drive.info.vendor_id,
+            drive.info.product_id,
+            drive.info.product_revision,
+            drive.info.serial_number.as_deref().unwrap_or("-"),
+            drive.barcode.as_deref().unwrap_or("-")
+        );
+    }
+
+    Ok(())
+}
+
+/// Print the device path of the drive holding `barcode` (for use in scripts)
+async fn execute_find(barcode: String) -> Result<()> {
+    info!("Looking for the drive holding tape {}", barcode);
+
+    let search = barcode.clone();
+    let drive = tokio::task::spawn_blocking(move || scsi::find_drive_by_barcode(&search))
+        .await
+        .map_err(|e| RustLtfsError::system(format!("Drive discovery failed: {}", e)))?
+        .ok_or_else(|| {
+            RustLtfsError::tape_device(format!("No drive has tape {} loaded", barcode))
+        })?;
+
+    println!("{}", drive.path);
+    Ok(())
+}
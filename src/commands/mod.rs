@@ -2,6 +2,23 @@
 //!
 //! This module contains handlers for all CLI subcommands.
 
+pub mod cat;
+pub mod check;
+pub mod dump_partition;
+pub mod eject;
+pub mod diag;
+pub mod erase;
+pub mod format;
+pub mod index_diff;
+pub mod info;
+pub mod list_drives;
+pub mod mv;
 pub mod read;
+pub mod recover_index;
+pub mod restore_index;
+pub mod rm;
+pub mod scavenge;
+pub mod set_barcode;
 pub mod space;
+pub mod verify;
 pub mod write;
@@ -0,0 +1,32 @@
+//! Mv Command Handler
+//!
+//! Handles the `mv` subcommand for renaming or moving files and directories in the tape index.
+
+use crate::error::Result;
+use crate::tape_ops;
+use tracing::info;
+
+pub async fn execute(device: String, source: String, destination: String) -> Result<()> {
+    info!("Moving {} -> {} in the index of {}", source, destination, device);
+
+    let mut ops = tape_ops::TapeOperations::new(&device);
+
+    // Moving an entry edits the index, so it has to be loaded like for a write
+    ops.initialize(Some(tape_ops::core::OperationType::Write))
+        .await?;
+
+    // Like mv(1): an existing directory as destination receives the entry under its own name
+    let new_path = if ops.is_index_directory(&destination) {
+        ops.move_entry(&source, &destination)?
+    } else {
+        ops.rename(&source, &destination)?
+    };
+    println!("📝 Moved {} -> {}", source, new_path);
+
+    println!("🔄 Updating LTFS index...");
+    ops.update_index_on_tape_with_options_dual_partition(false)
+        .await?;
+    println!("✅ Index updated; no data was moved on tape");
+
+    Ok(())
+}
@@ -2,16 +2,38 @@
 //!
 //! Handles the `read` subcommand for reading tape index and listing contents.
 
-use crate::error::Result;
+use crate::error::{Result, RustLtfsError};
+use crate::ltfs_index::{ExportField, ExportFormat, LtfsIndex, PathNormalize};
 use crate::tape_ops;
+use crate::utils;
+use std::io::Write;
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio_stream::StreamExt;
 use tracing::info;
 
-pub async fn execute(device: String, source: Option<PathBuf>) -> Result<()> {
+/// Minimum interval between progress line updates
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Maximum number of index consistency warnings printed before an extract
+const MAX_PRINTED_WARNINGS: usize = 20;
+
+/// Name comparison for tape paths given on the command line (`--ignore-case`)
+fn path_normalize(ignore_case: bool) -> PathNormalize {
+    if ignore_case {
+        PathNormalize::CaseInsensitive
+    } else {
+        PathNormalize::CaseSensitive
+    }
+}
+
+pub async fn execute(device: String, source: Option<PathBuf>, ignore_case: bool) -> Result<()> {
     info!("Starting read operation: {} -> {:?}", device, source);
 
     // Create tape operations instance (never skip index for read operations)
     let mut ops = tape_ops::TapeOperations::new(&device);
+    ops.set_path_normalize(path_normalize(ignore_case));
 
     // Initialize tape device with auto index reading
     ops.initialize(Some(tape_ops::core::OperationType::Read))
@@ -26,6 +48,9 @@ pub async fn execute(device: String, source: Option<PathBuf>) -> Result<()> {
             if let Some(stats) = ops.get_index_statistics() {
                 println!("\n📊 Tape Index Information:");
                 println!("  • Volume UUID: {}", stats.volume_uuid);
+                if !stats.volume_name.is_empty() {
+                    println!("  • Volume Name: {}", stats.volume_name);
+                }
                 println!("  • Generation Number: {}", stats.generation_number);
                 println!("  • Update Time: {}", stats.update_time);
                 println!("  • Total Files: {}", stats.total_files);
@@ -44,3 +69,325 @@ pub async fn execute(device: String, source: Option<PathBuf>) -> Result<()> {
 
     Ok(())
 }
+
+/// Handle `read --recursive`: print every path under SOURCE relative to it
+/// (with `long`, followed by the entry's UID and modify time)
+pub async fn execute_recursive(
+    device: String,
+    source: Option<PathBuf>,
+    ignore_case: bool,
+    long: bool,
+) -> Result<()> {
+    let tape_path = source
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "/".to_string());
+    info!("Listing recursively: {}:{}", device, tape_path);
+
+    let mut ops = tape_ops::TapeOperations::new(&device);
+    ops.set_path_normalize(path_normalize(ignore_case));
+    ops.initialize(Some(tape_ops::core::OperationType::Read))
+        .await?;
+
+    let entries = ops.list_recursive(&tape_path)?;
+    // 返回的是索引中的实际路径（大小写可能与输入不同），按层数去掉查询路径部分
+    let depth = LtfsIndex::split_path(&tape_path).len();
+    let (mut files, mut total_size) = (0u64, 0u64);
+
+    for (path, info) in &entries {
+        let mut parts = path.splitn(depth + 2, '/').skip(depth + 1);
+        // 查询路径本身是文件时，显示文件名
+        let relative = parts
+            .next()
+            .filter(|p| !p.is_empty())
+            .unwrap_or(path.rsplit('/').next().unwrap_or(path));
+
+        let details = if long {
+            format!("  [UID {}, modified {}]", info.uid, info.modify_time)
+        } else {
+            String::new()
+        };
+        if info.is_directory {
+            println!("📁 {}/{}", relative, details);
+        } else {
+            files += 1;
+            total_size += info.size;
+            println!("📄 {} ({} bytes){}", relative, info.size, details);
+        }
+    }
+
+    println!(
+        "\n{} entries, {} files, {}",
+        entries.len(),
+        files,
+        utils::format_bytes(total_size)
+    );
+    Ok(())
+}
+
+/// Handle `read --export`: write the file list under SOURCE as JSON/CSV/TSV
+pub async fn execute_export(
+    device: String,
+    source: Option<PathBuf>,
+    output: PathBuf,
+    format: ExportFormat,
+    fields: Vec<ExportField>,
+    ignore_case: bool,
+) -> Result<()> {
+    let tape_path = source
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "/".to_string());
+    info!("Exporting file list: {}:{} -> {:?}", device, tape_path, output);
+
+    let mut ops = tape_ops::TapeOperations::new(&device);
+    ops.set_path_normalize(path_normalize(ignore_case));
+    ops.initialize(Some(tape_ops::core::OperationType::Read))
+        .await?;
+
+    if output.as_os_str() == "-" {
+        let stdout = std::io::stdout();
+        ops.export_file_list(&tape_path, stdout.lock(), format, &fields)?;
+    } else {
+        let file = std::fs::File::create(&output).map_err(|e| {
+            RustLtfsError::file_operation(format!("Cannot create {:?}: {}", output, e))
+        })?;
+        let count =
+            ops.export_file_list(&tape_path, std::io::BufWriter::new(file), format, &fields)?;
+        println!("📄 Exported {} files under {} to {:?}", count, tape_path, output);
+    }
+
+    Ok(())
+}
+
+/// Handle `read --uid`: read `[offset, offset + length)` of one file, to DEST or raw to stdout
+pub async fn execute_range(
+    device: String,
+    uid: u64,
+    offset: u64,
+    length: Option<u64>,
+    dest: Option<PathBuf>,
+) -> Result<()> {
+    info!(
+        "Reading file UID {} from {} (offset {}, length {:?})",
+        uid, device, offset, length
+    );
+
+    let mut ops = tape_ops::TapeOperations::new(&device);
+    ops.initialize(Some(tape_ops::core::OperationType::Read))
+        .await?;
+
+    if offset == 0 && length.is_none() {
+        return stream_whole_file(Arc::new(ops), uid, dest).await;
+    }
+
+    let data = ops.read_file_range(uid, offset, length.unwrap_or(u64::MAX))?;
+
+    match dest {
+        Some(path) => {
+            std::fs::write(&path, &data).map_err(|e| {
+                RustLtfsError::file_operation(format!("Cannot write {:?}: {}", path, e))
+            })?;
+            println!(
+                "📄 Read {} from UID {} (offset {}) to {:?}",
+                utils::format_bytes(data.len() as u64),
+                uid,
+                offset,
+                path
+            );
+        }
+        None => {
+            let mut stdout = std::io::stdout().lock();
+            stdout.write_all(&data)?;
+            stdout.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Handle `read --preview` / `--hex`: show the first bytes of one file as text or a hex dump
+pub async fn execute_preview(
+    device: String,
+    source: Option<PathBuf>,
+    bytes: u64,
+    hex: bool,
+    ignore_case: bool,
+) -> Result<()> {
+    let path = source
+        .map(|s| s.to_string_lossy().into_owned())
+        .ok_or_else(|| RustLtfsError::parameter_validation("--preview needs a file path"))?;
+    info!("Previewing {} bytes of {} on {}", bytes, path, device);
+
+    let mut ops = tape_ops::TapeOperations::new(&device);
+    ops.set_path_normalize(path_normalize(ignore_case));
+    ops.initialize(Some(tape_ops::core::OperationType::Read))
+        .await?;
+
+    let uid = ops.find_file_uid(&path)?;
+    // 只读取预览所需的块
+    let data = ops.read_file_range(uid, 0, bytes)?;
+    let size = ops
+        .list_recursive(&path)?
+        .first()
+        .map(|(_, info)| info.size)
+        .unwrap_or(data.len() as u64);
+
+    let binary = utils::looks_binary(&data);
+    println!(
+        "📄 {} ({}, UID {}) - first {} as {}",
+        path,
+        utils::format_bytes(size),
+        uid,
+        utils::format_bytes(data.len() as u64),
+        if hex || binary { "hex dump" } else { "text" }
+    );
+    println!();
+    if hex || binary {
+        print!("{}", utils::hex_dump(&data));
+    } else {
+        let text = String::from_utf8_lossy(&data);
+        println!("{}", text.trim_end_matches('\n'));
+    }
+    if size > data.len() as u64 {
+        println!("... ({} more)", utils::format_bytes(size - data.len() as u64));
+    }
+
+    Ok(())
+}
+
+/// Copy a whole file to DEST (or stdout) block by block instead of reading it into memory
+async fn stream_whole_file(
+    ops: Arc<tape_ops::TapeOperations>,
+    uid: u64,
+    dest: Option<PathBuf>,
+) -> Result<()> {
+    let mut out: Box<dyn Write + Send> = match &dest {
+        Some(path) => Box::new(std::io::BufWriter::new(std::fs::File::create(path).map_err(
+            |e| RustLtfsError::file_operation(format!("Cannot create {:?}: {}", path, e)),
+        )?)),
+        None => Box::new(std::io::stdout()),
+    };
+
+    let mut stream = std::pin::pin!(ops.read_file_stream(uid)?);
+    let mut total = 0u64;
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk?;
+        out.write_all(&chunk)?;
+        total += chunk.len() as u64;
+    }
+    out.flush()?;
+
+    if let Some(path) = dest {
+        println!(
+            "📄 Read {} from UID {} to {:?}",
+            utils::format_bytes(total),
+            uid,
+            path
+        );
+    }
+    Ok(())
+}
+
+/// Handle `read --extract`: copy a tape file or directory to a local path
+pub async fn execute_extract(
+    device: String,
+    source: Option<PathBuf>,
+    dest: PathBuf,
+    progress: bool,
+    ignore_case: bool,
+    span_manifest: Option<PathBuf>,
+) -> Result<()> {
+    let tape_path = source
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "/".to_string());
+    info!("Starting extract: {}:{} -> {:?}", device, tape_path, dest);
+
+    let mut ops = tape_ops::TapeOperations::new(&device);
+    ops.set_path_normalize(path_normalize(ignore_case));
+    ops.initialize(Some(tape_ops::core::OperationType::Read))
+        .await?;
+
+    // 提取前报告索引中的损坏项（不阻止提取，损坏的文件会在结果中失败）
+    let warnings = ops.check_index_consistency().await?;
+    if !warnings.is_empty() {
+        println!("⚠️  Index consistency check found {} problems:", warnings.len());
+        for warning in warnings.iter().take(MAX_PRINTED_WARNINGS) {
+            println!("    {}", warning);
+        }
+        if warnings.len() > MAX_PRINTED_WARNINGS {
+            println!("    ... and {} more", warnings.len() - MAX_PRINTED_WARNINGS);
+        }
+    }
+
+    if let Some(ref manifest_path) = span_manifest {
+        let manifest = ops.load_span_manifest(manifest_path)?;
+        println!(
+            "📚 Span manifest {:?}: {} cartridges",
+            manifest_path,
+            manifest.cartridges.len()
+        );
+    }
+
+    println!("\n📥 Extracting {} -> {:?}", tape_path, dest);
+
+    let callback: Option<tape_ops::ExtractProgressCallback> = if progress {
+        let last_update = Arc::new(Mutex::new(None::<Instant>));
+        Some(Arc::new(move |p: &tape_ops::ExtractProgress| {
+            let mut last = last_update.lock().unwrap_or_else(|e| e.into_inner());
+            let finished = p.files_done >= p.total_files;
+            if !finished && last.is_some_and(|t| t.elapsed() < PROGRESS_INTERVAL) {
+                return;
+            }
+            *last = Some(Instant::now());
+
+            print!(
+                "\r  {}/{} files, {}/{} - {}\x1b[K",
+                p.files_done,
+                p.total_files,
+                utils::format_bytes(p.bytes_done),
+                utils::format_bytes(p.total_bytes),
+                p.current_file
+            );
+            let _ = std::io::stdout().flush();
+            if finished {
+                println!();
+            }
+        }))
+    } else {
+        None
+    };
+
+    utils::cancel_on_ctrl_c(ops.cancel_flag());
+    let start = Instant::now();
+    let result = ops.extract_from_tape(&tape_path, &dest, callback)?;
+    let duration = start.elapsed();
+
+    println!("\n📋 Extract Summary:");
+    println!("  Files extracted: {}", result.files_extracted);
+    println!("  Bytes extracted: {}", utils::format_bytes(result.bytes_extracted));
+    println!("  Directories created: {}", result.directories_created);
+    if !result.split_files.is_empty() {
+        println!("  Split files (pieces from this cartridge written in place):");
+        for (path, others) in &result.split_files {
+            println!("    📼 {}", path);
+            for (barcode, piece) in others {
+                println!("       bytes {}-{} on cartridge {}", piece.offset, piece.end(), barcode);
+            }
+        }
+    }
+    println!(
+        "  Duration: {}",
+        utils::format_duration(duration.as_secs_f64())
+    );
+    if !result.files_failed.is_empty() {
+        println!("  Files failed: {}", result.files_failed.len());
+        for (path, reason) in &result.files_failed {
+            println!("    ❌ {}: {}", path, reason);
+        }
+        return Err(RustLtfsError::file_operation(format!(
+            "{} files could not be extracted",
+            result.files_failed.len()
+        )));
+    }
+
+    Ok(())
+}
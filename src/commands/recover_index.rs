@@ -0,0 +1,41 @@
+//! Recover-Index Command Handler
+//!
+//! Handles the `recover-index` subcommand for recovering the index from the data partition.
+
+use crate::error::{Result, RustLtfsError};
+use crate::tape_ops;
+use std::path::PathBuf;
+use tracing::info;
+
+pub async fn execute(device: String, output: PathBuf) -> Result<()> {
+    info!("Recovering index: {} -> {:?}", device, output);
+
+    let mut ops = tape_ops::TapeOperations::new(&device);
+
+    // The index partition may be corrupt, so do not try to load the index
+    ops.initialize(Some(tape_ops::core::OperationType::Space))
+        .await?;
+
+    println!("\n🔎 Looking for the latest index copy in the data partition...");
+    let recovered = ops.recover_index().await?;
+
+    std::fs::write(&output, &recovered.xml).map_err(|e| {
+        RustLtfsError::file_operation(format!("Cannot write recovered index {:?}: {}", output, e))
+    })?;
+
+    println!("✅ Index recovered");
+    if recovered.from_vci {
+        println!("  • Located through Volume Coherency Information");
+    } else {
+        println!("  • Candidates found: {}", recovered.candidates);
+    }
+    println!("  • Generation Number: {}", recovered.index.generationnumber);
+    println!("  • Volume UUID: {}", recovered.index.volumeuuid);
+    println!(
+        "  • Location: partition {} after FileMark {}",
+        recovered.partition, recovered.file_mark
+    );
+    println!("  • Saved to: {:?}", output);
+
+    Ok(())
+}
@@ -0,0 +1,30 @@
+//! Restore-Index Command Handler
+//!
+//! Handles the `restore-index` subcommand for rewriting the tape index from a local backup.
+
+use crate::error::Result;
+use crate::tape_ops;
+use std::path::PathBuf;
+use tracing::info;
+
+pub async fn execute(device: String, schema_file: PathBuf) -> Result<()> {
+    info!("Restoring index: {:?} -> {}", schema_file, device);
+
+    let mut ops = tape_ops::TapeOperations::new(&device);
+
+    // The on-tape index may be unreadable, so do not try to load it
+    ops.initialize(Some(tape_ops::core::OperationType::Space))
+        .await?;
+
+    println!("\n🛠️  Restoring index from {:?}", schema_file);
+    ops.restore_index_from_file(&schema_file).await?;
+
+    if let Some(stats) = ops.get_index_statistics() {
+        println!("✅ Index restored");
+        println!("  • Volume UUID: {}", stats.volume_uuid);
+        println!("  • Generation Number: {}", stats.generation_number);
+        println!("  • Total Files: {}", stats.total_files);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,32 @@
+//! Rm Command Handler
+//!
+//! Handles the `rm` subcommand for removing a file from the tape index.
+
+use crate::error::Result;
+use crate::tape_ops;
+use crate::utils;
+use tracing::info;
+
+pub async fn execute(device: String, path: String) -> Result<()> {
+    info!("Removing {} from the index of {}", path, device);
+
+    let mut ops = tape_ops::TapeOperations::new(&device);
+
+    // Removing an entry edits the index, so it has to be loaded like for a write
+    ops.initialize(Some(tape_ops::core::OperationType::Write))
+        .await?;
+
+    let file = ops.remove_file(&path)?;
+    println!(
+        "🗑️  Removed {} ({}) from the index",
+        path,
+        utils::format_bytes(file.length)
+    );
+
+    println!("🔄 Updating LTFS index...");
+    ops.update_index_on_tape_with_options_dual_partition(false)
+        .await?;
+    println!("✅ Index updated; the file's data blocks remain on tape");
+
+    Ok(())
+}
@@ -0,0 +1,95 @@
+//! Scavenge Command Handler
+//!
+//! Handles the `scavenge` subcommand: last-resort recovery that splits a partition on
+//! filemarks into numbered files without consulting any LTFS index.
+
+use crate::error::{Result, RustLtfsError};
+use crate::tape_ops;
+use crate::utils;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::info;
+
+/// Minimum interval between progress line updates
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+pub async fn execute(
+    device: String,
+    partition: u8,
+    start_block: u64,
+    output_dir: PathBuf,
+    progress: bool,
+) -> Result<()> {
+    info!(
+        "Scavenging partition {} of {} from block {} -> {:?}",
+        partition, device, start_block, output_dir
+    );
+
+    let mut ops = tape_ops::TapeOperations::new(&device);
+
+    // The index is what is broken, so do not try to load it
+    ops.initialize(Some(tape_ops::core::OperationType::Space))
+        .await?;
+
+    println!(
+        "\n🛟 Scavenging partition {} from block {} -> {:?}",
+        partition, start_block, output_dir
+    );
+
+    let callback: Option<tape_ops::DumpProgressCallback> = if progress {
+        let last_update = Arc::new(Mutex::new(None::<Instant>));
+        Some(Arc::new(move |p: &tape_ops::DumpProgress| {
+            let mut last = last_update.lock().unwrap_or_else(|e| e.into_inner());
+            if last.is_some_and(|t| t.elapsed() < PROGRESS_INTERVAL) {
+                return;
+            }
+            *last = Some(Instant::now());
+
+            print!(
+                "\r  {} blocks, {}, {} filemarks\x1b[K",
+                p.blocks,
+                utils::format_bytes(p.bytes),
+                p.filemarks
+            );
+            let _ = std::io::stdout().flush();
+        }))
+    } else {
+        None
+    };
+
+    utils::cancel_on_ctrl_c(ops.cancel_flag());
+    let result = ops.scan_raw(partition, start_block, &output_dir, callback);
+    if progress {
+        println!();
+    }
+    let report = result?;
+
+    for file in &report.files {
+        println!(
+            "  • {:?}: {} (blocks {}..{})",
+            file.path,
+            utils::format_bytes(file.bytes),
+            file.start_block,
+            file.start_block + file.blocks
+        );
+    }
+    println!(
+        "  Recovered {} files from {} filemark regions, stopped at block {}",
+        report.files.len(),
+        report.regions,
+        report.end_block
+    );
+
+    match report.error {
+        Some(e) => Err(RustLtfsError::tape_device(format!(
+            "Scan stopped at block {} before EOD: {}",
+            report.end_block, e
+        ))),
+        None => {
+            println!("✅ Reached end of data");
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,32 @@
+//! Set-Barcode Command Handler
+//!
+//! Handles the `set-barcode` subcommand for relabelling a cartridge after format.
+
+use crate::error::Result;
+use crate::tape_ops;
+use tracing::info;
+
+pub async fn execute(device: String, barcode: String, volume_label: Option<String>) -> Result<()> {
+    info!("Setting barcode on {}: {}", device, barcode);
+
+    let mut ops = tape_ops::TapeOperations::new(&device);
+
+    // Renaming the volume rewrites the index, so load it; the barcode alone only touches MAM
+    let op_type = if volume_label.is_some() {
+        tape_ops::core::OperationType::Write
+    } else {
+        tape_ops::core::OperationType::Space
+    };
+    ops.initialize(Some(op_type)).await?;
+
+    ops.set_mam_barcode(&barcode)?;
+    println!("✅ Barcode set to {}", barcode.trim());
+
+    if let Some(name) = volume_label {
+        ops.set_volume_name(&name)?;
+        ops.update_index_on_tape_with_options_dual_partition(true).await?;
+        println!("✅ Volume label set to {:?}", name);
+    }
+
+    Ok(())
+}
@@ -1,6 +1,7 @@
 //! Space Command Handler
 //!
-//! Handles the `space` subcommand for querying tape capacity information.
+//! Handles the `space` subcommand for querying tape capacity information and
+//! for explicit positioning (`--to-filemark`, `--to-block`, `--to-eod`).
 
 use crate::error::Result;
 use crate::tape_ops;
@@ -32,6 +33,14 @@ pub async fn execute(device: String, detailed: bool) -> Result<()> {
         "  Available Space: {} GB",
         space_info.available_space / (1024 * 1024 * 1024)
     );
+    match space_info.compression {
+        Some(state) => println!(
+            "  Hardware Compression: {} (drive capable: {})",
+            if state.dce { "enabled" } else { "disabled" },
+            if state.dcc { "yes" } else { "no" }
+        ),
+        None => println!("  Hardware Compression: unknown"),
+    }
 
     if detailed {
         println!("  Detailed information would be shown here");
@@ -39,3 +48,33 @@ pub async fn execute(device: String, detailed: bool) -> Result<()> {
 
     Ok(())
 }
+
+/// Move the tape to `target` in `partition` and print the resulting position
+///
+/// The position line is `key=value` pairs on stdout so scripts can parse it.
+pub async fn execute_position(
+    device: String,
+    partition: u8,
+    target: tape_ops::PositionTarget,
+) -> Result<()> {
+    info!("Positioning {} to {:?} in partition {}", device, target, partition);
+
+    let mut ops = tape_ops::TapeOperations::new(&device);
+
+    // Positioning does not need the LTFS index
+    ops.initialize(Some(tape_ops::core::OperationType::Space))
+        .await?;
+
+    let position = ops.position_to(partition, target)?;
+
+    println!(
+        "partition={} block={} filemark={} eod={} bop={}",
+        position.partition,
+        position.block_number,
+        position.file_number,
+        position.end_of_data,
+        position.beginning_of_partition
+    );
+
+    Ok(())
+}
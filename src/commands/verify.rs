@@ -0,0 +1,96 @@
+//! Verify Command Handler
+//!
+//! Handles the `verify` subcommand for checking tape data against the hashes stored in the index.
+
+use crate::error::{Result, RustLtfsError};
+use crate::tape_ops;
+use crate::utils;
+use std::path::PathBuf;
+use tracing::info;
+
+pub async fn execute(device: String, index_file: Option<PathBuf>, extents: bool) -> Result<()> {
+    info!("Starting hash verification on {}", device);
+
+    let mut ops = tape_ops::TapeOperations::new(&device);
+
+    match &index_file {
+        Some(path) => {
+            // Use the supplied index instead of reading it from tape
+            ops.initialize(Some(tape_ops::core::OperationType::Space))
+                .await?;
+            ops.load_index_from_file(path)?;
+        }
+        None => {
+            ops.initialize(Some(tape_ops::core::OperationType::Read))
+                .await?;
+        }
+    }
+
+    if extents {
+        return audit_extents(&ops, &device, index_file.as_ref());
+    }
+
+    println!("\n🔍 Verifying tape data against stored hashes");
+    println!("  Device: {}", device);
+    if let Some(path) = &index_file {
+        println!("  Index: {:?}", path);
+    }
+
+    let report = ops.verify_tape_hashes()?;
+
+    println!("\n📋 Verify Summary:");
+    println!("  Files checked: {}", report.checked());
+    println!("  Passed: {}", report.passed.len());
+    println!("  Failed: {}", report.failed.len());
+    for (path, reason) in &report.failed {
+        println!("    ❌ {}: {}", path, reason);
+    }
+    println!("  Unverifiable (no stored hash): {}", report.unverifiable.len());
+    for path in &report.unverifiable {
+        println!("    ❓ {}", path);
+    }
+
+    if report.failed.is_empty() {
+        println!("\n✅ All files with a stored hash match the data on tape");
+        Ok(())
+    } else {
+        Err(RustLtfsError::verification(format!(
+            "{} of {} checked files failed hash verification",
+            report.failed.len(),
+            report.checked()
+        )))
+    }
+}
+
+/// Handle `verify --extents`: check index extents against EOD without reading file data
+fn audit_extents(ops: &tape_ops::TapeOperations, device: &str, index_file: Option<&PathBuf>) -> Result<()> {
+    println!("\n🔍 Auditing index extents against the written region of the tape");
+    println!("  Device: {}", device);
+    if let Some(path) = index_file {
+        println!("  Index: {:?}", path);
+    }
+
+    utils::cancel_on_ctrl_c(ops.cancel_flag());
+    let report = ops.audit_extents()?;
+
+    println!("\n📋 Extent Audit Summary:");
+    println!("  Files checked: {}", report.files_checked);
+    for (partition, eod) in &report.eod {
+        println!("  Partition {} EOD: block {}", partition, eod);
+    }
+    println!("  Suspect files: {}", report.suspects.len());
+    for (path, reason) in &report.suspects {
+        println!("    ❌ {}: {}", path, reason);
+    }
+
+    if report.is_clean() {
+        println!("\n✅ All extents lie in the written region of a data partition");
+        Ok(())
+    } else {
+        Err(RustLtfsError::verification(format!(
+            "{} of {} files reference blocks outside the written data region",
+            report.suspects.len(),
+            report.files_checked
+        )))
+    }
+}
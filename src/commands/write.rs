@@ -5,16 +5,23 @@
 use crate::error::{Result, RustLtfsError};
 use crate::tape_ops;
 use crate::utils;
-use std::io::{self, BufRead, BufReader};
+use std::io::{self, BufRead, BufReader, Write};
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tracing::{error, info, warn};
 
+/// Minimum time between two updates of the live progress line
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
 pub async fn execute(
     source: Option<PathBuf>,
     device: String,
     destination: PathBuf,
-    verify: bool,
+    write_options: tape_ops::WriteOptions,
     progress: bool,
+    journal: Option<(PathBuf, bool)>,
+    span_manifest: Option<PathBuf>,
 ) -> Result<()> {
     info!(
         "Starting write operation: {:?} -> {}:{:?}",
@@ -30,9 +37,17 @@ pub async fn execute(
     let mut ops = tape_ops::TapeOperations::new(&device);
 
     // Configure advanced write options
-    let mut write_options = tape_ops::WriteOptions::default();
-    write_options.verify = verify;
-
+    let verify = write_options.verify;
+    let verify_index = write_options.verify_index;
+    let ignore_cleaning = write_options.ignore_cleaning;
+    let compression = write_options.compression;
+    let lbp = write_options.logical_block_protection;
+    let sparse = write_options.sparse;
+    let excluded = write_options.excluded_patterns.len();
+    let max_depth = write_options.max_depth;
+    let follow_symlinks = write_options.follow_symlinks;
+    let index_interval = write_options.index_write_interval;
+    let index_interval_time = write_options.index_write_interval_time;
     ops.set_write_options(write_options);
 
     // Display progress if requested
@@ -105,6 +120,14 @@ pub async fn execute(
         }
     };
 
+    // 写入单个文件到目录（如 `/backups/2024/`）时，索引路径为 目录/源文件名
+    let destination = match (&source, operation_mode) {
+        (Some(source_path), "file") => {
+            PathBuf::from(ops.resolve_file_target(source_path, &destination.to_string_lossy()))
+        }
+        _ => destination,
+    };
+
     let source_display = match &source {
         Some(path) => format!("{:?}", path),
         None => "<stdin>".to_string(),
@@ -118,8 +141,50 @@ pub async fn execute(
 
     let mut options = Vec::new();
     if verify {
-        options.push("Hash verification enabled".to_string())
+        options.push("Read-back verification enabled".to_string())
+    };
+    if verify_index {
+        options.push("Index read-back verification enabled".to_string())
+    };
+    if ignore_cleaning {
+        options.push("Drive cleaning requests ignored".to_string())
+    };
+    if !compression {
+        options.push("Hardware compression disabled".to_string())
+    };
+    if lbp != crate::scsi::LbpMethod::None {
+        options.push(format!("Logical block protection: {:?}", lbp))
+    };
+    if !sparse {
+        options.push("Sparse file holes written as zeros".to_string())
+    };
+    if index_interval == u64::MAX {
+        options.push("Size-based index interval off".to_string())
+    } else if index_interval != tape_ops::WriteOptions::default().index_write_interval {
+        options.push(format!("Index every {}", utils::format_bytes(index_interval)))
+    };
+    if excluded > 0 {
+        options.push(format!("{} exclude pattern(s)", excluded))
+    };
+    if let Some(depth) = max_depth {
+        options.push(format!("Max depth {}", depth))
+    };
+    if follow_symlinks {
+        options.push("Following symlinks".to_string())
     };
+    if let Some(interval) = index_interval_time {
+        options.push(format!("Index every {} min", interval.as_secs() / 60))
+    };
+    if let Some((ref journal_path, resume)) = journal {
+        options.push(format!(
+            "{} journal {:?}",
+            if resume { "Resuming from" } else { "Recording" },
+            journal_path
+        ));
+    }
+    if let Some(ref manifest_path) = span_manifest {
+        options.push(format!("Spanning cartridges via {:?}", manifest_path));
+    }
 
     if !options.is_empty() {
         let options_str: Vec<&str> = options.iter().map(|s| s.as_str()).collect();
@@ -142,60 +207,159 @@ pub async fn execute(
 
     // Execute write operation with enhanced progress reporting
     let write_start = std::time::Instant::now();
+    let mut session_had_failures = false;
 
-    match operation_mode {
-        "directory" => {
-            // Directory mode - use existing directory write logic
-            if let Some(ref source_path) = source {
-                if show_progress {
-                    println!("\n📁 Writing directory to tape...");
-                }
+    // Ctrl+C stops the write after the current block; files already written are still indexed
+    utils::cancel_on_ctrl_c(ops.cancel_flag());
 
-                ops.write_directory_to_tape(source_path, &destination.to_string_lossy())
-                    .await?;
+    let last_progress_line = Arc::new(Mutex::new(None::<Instant>));
+    if show_progress {
+        let last_update = last_progress_line.clone();
+        ops.set_write_progress_callback(Some(Arc::new(move |p: &tape_ops::WriteProgress| {
+            let mut last = last_update.lock().unwrap_or_else(|e| e.into_inner());
+            if last.is_some_and(|t| t.elapsed() < PROGRESS_INTERVAL) {
+                return;
+            }
+            *last = Some(Instant::now());
+            print!("\r  {}\x1b[K", progress_line(p));
+            let _ = io::stdout().flush();
+        })));
+    }
+
+    let write_result: Result<()> = async {
+        if let (Some(ref manifest_path), Some(ref source_path)) = (&span_manifest, &source) {
+            let manifest = ops.start_span_manifest(manifest_path, source_path, &destination.to_string_lossy())?;
+            if let Some(ref continuation) = manifest.continuation {
+                println!(
+                    "  Continuing {} from byte {} of {}",
+                    continuation.path, continuation.offset, continuation.total_size
+                );
             }
         }
-        "file" => {
-            // File mode - use existing file-based method
-            if let Some(ref source_path) = source {
+
+        match operation_mode {
+            "directory" => {
+                // Directory mode - use existing directory write logic
+                if let Some(ref source_path) = source {
+                    if let Some((ref journal_path, resume)) = journal {
+                        let target = destination.to_string_lossy();
+                        if resume {
+                            let resumed = ops.resume_from_journal(journal_path, source_path, &target)?;
+                            println!(
+                                "  Resuming: {} files already on tape, {} to rewrite",
+                                resumed.committed.len(),
+                                resumed.pending.len()
+                            );
+                        } else {
+                            ops.start_write_journal(journal_path, source_path, &target)?;
+                        }
+                    }
+
+                    if show_progress {
+                        println!("\n📁 Writing directory to tape...");
+                    }
+
+                    let session = ops
+                        .write_directory_to_tape(source_path, &destination.to_string_lossy())
+                        .await?;
+
+                    println!("\n📋 Write Session Summary:");
+                    println!("  Files written: {}", session.files_written);
+                    println!(
+                        "  Bytes written: {}",
+                        utils::format_bytes(session.bytes_written)
+                    );
+                    println!("  Files skipped: {}", session.files_skipped);
+                    if let Some(generation) = session.index_generation {
+                        println!("  Index generation: {}", generation);
+                    }
+                    if let Some(ref position) = session.final_position {
+                        println!(
+                            "  Final position: P{} B{}",
+                            position.partition, position.block_number
+                        );
+                    }
+                    if !session.files_failed.is_empty() {
+                        session_had_failures = true;
+                        println!("  Files failed: {}", session.files_failed.len());
+                        for (path, reason) in &session.files_failed {
+                            println!("    ❌ {:?}: {}", path, reason);
+                        }
+                    }
+                }
+            }
+            "file" => {
+                // File mode - use existing file-based method
+                if let Some(ref source_path) = source {
+                    if show_progress {
+                        println!("\n📄 Writing file to tape...");
+                    }
+                    ops.write_file_to_tape_streaming(source_path, &destination.to_string_lossy())
+                        .await
+                        .map(|_| ())?;
+                }
+            }
+            "stdin" => {
+                // Stdin mode - stream from stdin to tape (IMPORTANT: Don't read_to_end - streams are huge!)
                 if show_progress {
-                    println!("\n📄 Writing file to tape...");
+                    println!("\n📄 Writing from stdin to tape...");
                 }
-                ops.write_file_to_tape_streaming(source_path, &destination.to_string_lossy())
+
+                // Create a buffered reader directly from stdin for true streaming
+                // This avoids loading the entire tar stream (potentially 200GB+) into memory
+                let stdin = io::stdin();
+                let reader: Box<dyn BufRead + Send> = Box::new(BufReader::with_capacity(
+                    8 * 1024 * 1024, // 8MB buffer - good balance for tape write performance
+                    stdin,
+                ));
+
+                ops.write_reader_to_tape(reader, &destination.to_string_lossy(), estimated_size)
                     .await
                     .map(|_| ())?;
             }
+            _ => {
+                return Err(RustLtfsError::parameter_validation(
+                    "Invalid operation mode".to_string(),
+                ));
+            }
         }
-        "stdin" => {
-            // Stdin mode - stream from stdin to tape (IMPORTANT: Don't read_to_end - streams are huge!)
-            if show_progress {
-                println!("\n📄 Writing from stdin to tape...");
+        Ok(())
+    }
+    .await;
+    if last_progress_line.lock().unwrap_or_else(|e| e.into_inner()).is_some() {
+        println!();
+    }
+    // Cancellation and end of media both stop the session cleanly: files written so far
+    // are still committed to the index below
+    let stopped = match write_result {
+        Err(e) if e.is_cancelled() => {
+            println!("\n⏹  Write cancelled: {}", e);
+            Some(e)
+        }
+        Err(e) if e.is_end_of_media() => {
+            println!("\n📼 {}", e);
+            if span_manifest.is_some() {
+                println!("   The file being written was split; load the next cartridge and run the same command to continue");
+            } else {
+                println!("   The file being written when the tape filled is not included; continue on another cartridge");
             }
-
-            // Create a buffered reader directly from stdin for true streaming
-            // This avoids loading the entire tar stream (potentially 200GB+) into memory
-            let stdin = io::stdin();
-            let reader: Box<dyn BufRead + Send> = Box::new(BufReader::with_capacity(
-                8 * 1024 * 1024, // 8MB buffer - good balance for tape write performance
-                stdin,
-            ));
-
-            ops.write_reader_to_tape(reader, &destination.to_string_lossy(), estimated_size)
-                .await
-                .map(|_| ())?;
+            Some(e)
         }
-        _ => {
-            return Err(RustLtfsError::parameter_validation(
-                "Invalid operation mode".to_string(),
-            ));
+        other => {
+            other?;
+            None
         }
-    }
+    };
 
     let write_duration = write_start.elapsed();
 
     // Show final progress
     let final_progress = ops.get_write_progress();
-    println!("\n✅ Write Operation Completed");
+    if stopped.is_some() {
+        println!("\n⏹  Write Operation Stopped");
+    } else {
+        println!("\n✅ Write Operation Completed");
+    }
     println!(
         "  Files written: {}",
         final_progress.current_files_processed
@@ -204,6 +368,9 @@ pub async fn execute(
         "  Bytes written: {}",
         utils::format_bytes(final_progress.current_bytes_processed)
     );
+    if verify {
+        println!("  Files verified: {}", final_progress.files_verified);
+    }
     println!(
         "  Duration: {}",
         utils::format_duration(write_duration.as_secs_f64())
@@ -218,6 +385,9 @@ pub async fn execute(
             )
         );
     }
+    if let Some(mbps) = final_progress.throughput_mbps {
+        println!("  Drive Throughput: {:.2} MB/s", mbps);
+    }
 
     info!(
         "Write operation completed in {:.2}s",
@@ -247,6 +417,12 @@ pub async fn execute(
         }
     }
 
+    // Mark journaled files as committed; remove the journal once everything is on tape
+    // (a cancelled or tape-full session keeps it so the write can be resumed)
+    if let Err(e) = ops.finish_write_journal(!session_had_failures && stopped.is_none()) {
+        warn!("Write journal update failed: {}", e);
+    }
+
     // Save index to local file for backup
     if device_initialized {
         let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S").to_string();
@@ -282,6 +458,177 @@ pub async fn execute(
         }
     }
 
+    if let Some(e) = stopped {
+        return Err(e);
+    }
+
     println!("\n🎉 Write operation completed successfully!");
     Ok(())
 }
+
+/// Handle `write --dry-run`: plan the write (skip rules, would-be index, space) without writing
+pub async fn execute_dry_run(
+    source: Option<PathBuf>,
+    device: String,
+    destination: PathBuf,
+    write_options: tape_ops::WriteOptions,
+) -> Result<()> {
+    let source = source.ok_or_else(|| {
+        RustLtfsError::parameter_validation("--dry-run requires a SOURCE path".to_string())
+    })?;
+    if !source.exists() {
+        return Err(RustLtfsError::file_operation(format!(
+            "Source path does not exist: {:?}",
+            source
+        )));
+    }
+
+    info!(
+        "Starting dry run: {:?} -> {}:{:?}",
+        source, device, destination
+    );
+
+    let mut ops = tape_ops::TapeOperations::new(&device);
+    ops.set_write_options(write_options);
+    ops.initialize(Some(tape_ops::core::OperationType::Write))
+        .await?;
+
+    println!("\n🧪 Dry Run (nothing will be written to tape)");
+    println!("  Source: {:?}", source);
+    println!("  Device: {}", device);
+    println!("  Target: {:?}", destination);
+
+    let report = ops
+        .plan_write(&source, &destination.to_string_lossy())
+        .await?;
+
+    println!("\n📋 Planned Write:");
+    println!("  Files to write: {}", report.session.files_written);
+    println!(
+        "  Bytes to write: {}",
+        utils::format_bytes(report.session.bytes_written)
+    );
+    println!(
+        "  Tape space needed: {} ({} blocks incl. file marks)",
+        utils::format_bytes(report.tape_bytes_planned),
+        report.blocks_planned
+    );
+    println!("  Files skipped: {}", report.session.files_skipped);
+    if !report.session.files_failed.is_empty() {
+        println!("  Files that would fail: {}", report.session.files_failed.len());
+        for (path, reason) in &report.session.files_failed {
+            println!("    ❌ {:?}: {}", path, reason);
+        }
+    }
+    if let Some(generation) = report.session.index_generation {
+        println!("  Index generation after write: {}", generation);
+    }
+
+    match (report.remaining_capacity, report.fits()) {
+        (Some(remaining), Some(true)) => {
+            println!("\n✅ Fits in remaining capacity ({})", utils::format_bytes(remaining));
+            Ok(())
+        }
+        (Some(remaining), _) => Err(RustLtfsError::tape_device(format!(
+            "Planned write needs {} but only {} remains on tape",
+            utils::format_bytes(report.tape_bytes_planned),
+            utils::format_bytes(remaining)
+        ))),
+        (None, _) => {
+            println!("\n⚠️  Remaining capacity unknown, cannot check whether the write fits");
+            Ok(())
+        }
+    }
+}
+
+/// Handle `write --verify-only`: reconcile the source tree against the tape without writing
+pub async fn execute_verify_only(
+    source: Option<PathBuf>,
+    device: String,
+    destination: PathBuf,
+) -> Result<()> {
+    let source = source.ok_or_else(|| {
+        RustLtfsError::parameter_validation("--verify-only requires a SOURCE path".to_string())
+    })?;
+    if !source.exists() {
+        return Err(RustLtfsError::file_operation(format!(
+            "Source path does not exist: {:?}",
+            source
+        )));
+    }
+
+    info!(
+        "Starting verify-only reconcile: {:?} <-> {}:{:?}",
+        source, device, destination
+    );
+
+    let mut ops = tape_ops::TapeOperations::new(&device);
+    ops.initialize(Some(tape_ops::core::OperationType::Read))
+        .await?;
+
+    println!("\n🔍 Verifying tape contents against source");
+    println!("  Source: {:?}", source);
+    println!("  Device: {}", device);
+    println!("  Target: {:?}", destination);
+
+    let report: tape_ops::VerifyReport = ops.verify_against_source(&source, &destination.to_string_lossy())?;
+
+    println!("\n📋 Verify Summary:");
+    println!("  Matched: {}", report.matched.len());
+    println!("  Mismatched: {}", report.mismatched.len());
+    for (path, reason) in &report.mismatched {
+        println!("    ⚠️  {}: {}", path, reason);
+    }
+    println!("  Missing on tape: {}", report.missing.len());
+    for path in &report.missing {
+        println!("    ❌ {}", path);
+    }
+    println!("  Orphans on tape: {}", report.orphans.len());
+    for path in &report.orphans {
+        println!("    ❓ {}", path);
+    }
+    if !report.errors.is_empty() {
+        println!("  Errors: {}", report.errors.len());
+        for (path, reason) in &report.errors {
+            println!("    ❌ {}: {}", path, reason);
+        }
+    }
+
+    if report.is_clean() {
+        println!("\n✅ All source files are present and matching on tape");
+        Ok(())
+    } else {
+        Err(RustLtfsError::file_operation(format!(
+            "Verification found {} mismatched, {} missing, {} orphaned, {} errored files",
+            report.mismatched.len(),
+            report.missing.len(),
+            report.orphans.len(),
+            report.errors.len()
+        )))
+    }
+}
+
+/// One-line write progress: files, bytes (of the total when known), rolling rate and ETA
+fn progress_line(p: &tape_ops::WriteProgress) -> String {
+    let bytes = match p.total_bytes {
+        Some(total) if total > 0 => format!(
+            "{} / {} ({:.1}%)",
+            utils::format_bytes(p.current_bytes_processed),
+            utils::format_bytes(total),
+            p.current_bytes_processed.min(total) as f64 * 100.0 / total as f64
+        ),
+        _ => utils::format_bytes(p.current_bytes_processed),
+    };
+    let rate = p
+        .bytes_per_sec
+        .map(|rate| format!("{}/s", utils::format_bytes(rate as u64)))
+        .unwrap_or_else(|| "-".to_string());
+    let eta = p
+        .eta()
+        .map(|eta| utils::format_duration(eta.as_secs_f64()))
+        .unwrap_or_else(|| "-".to_string());
+    format!(
+        "{} files | {} | {} | ETA {}",
+        p.current_files_processed, bytes, rate, eta
+    )
+}
@@ -9,6 +9,14 @@ pub enum RustLtfsError {
     
     #[error("SCSI operation error: {0}")]
     Scsi(String),
+
+    /// A command the drive completed with CHECK CONDITION, or that failed in transport
+    /// (no sense data returned)
+    #[error("SCSI operation error: {message}: {sense}")]
+    ScsiSense {
+        message: String,
+        sense: crate::scsi::SenseData,
+    },
     
     #[error("Tape device error: {0}")]
     TapeDevice(String),
@@ -25,6 +33,9 @@ pub enum RustLtfsError {
 
 
     
+    #[error("Unsupported operation: {0}")]
+    Unsupported(String),
+    
     #[error("System error: {0}")]
     System(String),
     
@@ -45,6 +56,27 @@ pub enum RustLtfsError {
     
     #[error("Parameter validation error: {0}")]
     ParameterValidation(String),
+
+    #[error("Verification failed: {0}")]
+    Verification(String),
+
+    #[error("Operation would take too long: {0}")]
+    OperationTooLong(String),
+
+    #[error("Operation cancelled: {0}")]
+    OperationCancelled(String),
+
+    #[error("End of media: {0}")]
+    EndOfMedia(String),
+
+    #[error("Blank tape: {0}")]
+    BlankTape(String),
+
+    #[error("Not an LTFS tape: {0}")]
+    NonLtfsFormat(String),
+
+    #[error("Corrupted LTFS index: {0}")]
+    CorruptedIndex(String),
     
 
 
@@ -61,6 +93,13 @@ impl RustLtfsError {
         Self::Scsi(msg.into())
     }
     
+    pub fn scsi_sense<T: Into<String>>(msg: T, sense: crate::scsi::SenseData) -> Self {
+        Self::ScsiSense {
+            message: msg.into(),
+            sense,
+        }
+    }
+
     pub fn tape_device<T: Into<String>>(msg: T) -> Self {
         Self::TapeDevice(msg.into())
     }
@@ -75,6 +114,10 @@ impl RustLtfsError {
     
 
     
+    pub fn unsupported<T: Into<String>>(msg: T) -> Self {
+        Self::Unsupported(msg.into())
+    }
+    
     pub fn system<T: Into<String>>(msg: T) -> Self {
         Self::System(msg.into())
     }
@@ -96,8 +139,117 @@ impl RustLtfsError {
     pub fn parameter_validation<T: Into<String>>(msg: T) -> Self {
         Self::ParameterValidation(msg.into())
     }
+
+    pub fn verification<T: Into<String>>(msg: T) -> Self {
+        Self::Verification(msg.into())
+    }
+
+    pub fn operation_too_long<T: Into<String>>(msg: T) -> Self {
+        Self::OperationTooLong(msg.into())
+    }
+
+    pub fn operation_cancelled<T: Into<String>>(msg: T) -> Self {
+        Self::OperationCancelled(msg.into())
+    }
+
+    pub fn end_of_media<T: Into<String>>(msg: T) -> Self {
+        Self::EndOfMedia(msg.into())
+    }
+
+    pub fn blank_tape<T: Into<String>>(msg: T) -> Self {
+        Self::BlankTape(msg.into())
+    }
+
+    pub fn non_ltfs_format<T: Into<String>>(msg: T) -> Self {
+        Self::NonLtfsFormat(msg.into())
+    }
+
+    pub fn corrupted_index<T: Into<String>>(msg: T) -> Self {
+        Self::CorruptedIndex(msg.into())
+    }
+
+    /// Whether the error is a user cancellation rather than a failure
+    pub fn is_cancelled(&self) -> bool {
+        matches!(self, Self::OperationCancelled(_))
+    }
+
+    /// Whether the drive reported early-warning end of medium or volume overflow
+    pub fn is_end_of_media(&self) -> bool {
+        matches!(self, Self::EndOfMedia(_))
+    }
+
+    /// Whether the tape holds no data at all
+    pub fn is_blank_tape(&self) -> bool {
+        matches!(self, Self::BlankTape(_))
+    }
+
+    /// Whether format detection classified the tape (blank, not LTFS, or an
+    /// LTFS tape whose index cannot be parsed) rather than failing on the device
+    pub fn is_format_error(&self) -> bool {
+        matches!(
+            self,
+            Self::BlankTape(_) | Self::NonLtfsFormat(_) | Self::CorruptedIndex(_)
+        )
+    }
     
 
     
 
+
+    /// Whether the error is likely a transient hardware/transport hiccup
+    /// (bus reset, unit attention, command timeout) that is worth retrying,
+    /// as opposed to a failure that repeats on retry: MEDIUM ERROR, BLANK CHECK,
+    /// ILLEGAL REQUEST, end of media, or an unparseable or missing index.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::Io(_) => true,
+            Self::ScsiSense { sense, .. } => sense.is_transient(),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scsi::SenseData;
+
+    /// 定长格式sense：sense key与ASC/ASCQ
+    fn sense(key: u8, asc: u8, ascq: u8) -> SenseData {
+        let mut data = [0u8; 18];
+        data[0] = 0x70;
+        data[2] = key;
+        data[12] = asc;
+        data[13] = ascq;
+        SenseData::parse(&data)
+    }
+
+    #[test]
+    fn only_recoverable_sense_is_transient() {
+        let transient = [
+            sense(0x06, 0x29, 0x00), // UNIT ATTENTION: power on / reset
+            sense(0x0B, 0x00, 0x00), // ABORTED COMMAND
+            sense(0x02, 0x04, 0x01), // NOT READY: becoming ready
+            SenseData::parse(&[0u8; 18]), // 命令失败但没有sense：传输层故障
+        ];
+        for sense in transient {
+            assert!(RustLtfsError::scsi_sense("read", sense).is_transient(), "{}", sense);
+        }
+
+        let permanent = [
+            sense(0x03, 0x11, 0x00), // MEDIUM ERROR
+            sense(0x08, 0x00, 0x05), // BLANK CHECK
+            sense(0x05, 0x24, 0x00), // ILLEGAL REQUEST
+            sense(0x02, 0x3A, 0x00), // NOT READY: medium not present
+            sense(0x0D, 0x00, 0x02), // VOLUME OVERFLOW
+            sense(0x00, 0x00, 0x01), // 带sense的NO SENSE (filemark)
+        ];
+        for sense in permanent {
+            assert!(!RustLtfsError::scsi_sense("read", sense).is_transient(), "{}", sense);
+        }
+
+        assert!(RustLtfsError::Io(std::io::ErrorKind::TimedOut.into()).is_transient());
+        assert!(!RustLtfsError::scsi("Device not opened").is_transient());
+        assert!(!RustLtfsError::end_of_media("early warning").is_transient());
+    }
 }
@@ -10,11 +10,12 @@ pub mod ltfs_index;
 pub mod scsi;
 pub mod tape_ops;
 pub mod utils;
+pub mod virtual_tape;
 
 // Re-export key types for easier use
 pub use error::{Result, RustLtfsError};
 pub use ltfs_index::{File, FileExtent, LtfsIndex};
 pub use scsi::{
-    DriveType, MediaType, ScsiInterface, SpaceType, TapePosition,
+    DriveType, MediaType, ScsiInterface, SpaceType, TapeBackend, TapePosition,
 };
 pub use scsi::types::LocateDestType;
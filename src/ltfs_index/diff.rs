@@ -0,0 +1,260 @@
+//! LTFS Index Diff
+//!
+//! Compares two generations of an index file by file. Files are paired by UID first,
+//! so a renamed or moved file is reported as a move instead of a removal plus an
+//! addition; files left over on both sides are then paired by path (an overwritten
+//! file gets a new UID). Paired files are modified when their length, modify time or
+//! a hash stored in both indexes differs.
+
+use super::export::{collect_records, FileRecord};
+use super::types::LtfsIndex;
+use std::collections::{BTreeMap, HashMap};
+
+/// 文件的一项变化
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileChange {
+    Length,
+    ModifyTime,
+    /// 两个索引都记录了该算法的哈希但值不同 (sha1sum, md5sum, ...)
+    Hash(String),
+}
+
+/// 两个索引中配对成功但内容不同的文件
+#[derive(Debug)]
+pub struct ModifiedFile {
+    pub old: FileRecord,
+    pub new: FileRecord,
+    pub changes: Vec<FileChange>,
+}
+
+impl ModifiedFile {
+    /// 新旧长度之差（字节）
+    pub fn size_delta(&self) -> i64 {
+        self.new.size as i64 - self.old.size as i64
+    }
+}
+
+/// UID相同但路径改变的文件
+#[derive(Debug)]
+pub struct MovedFile {
+    pub uid: u64,
+    pub old_path: String,
+    pub new_path: String,
+}
+
+/// Result of `LtfsIndex::diff`, every list sorted by path
+#[derive(Debug)]
+pub struct IndexDiff {
+    pub old_generation: u64,
+    pub new_generation: u64,
+    pub added: Vec<FileRecord>,
+    pub removed: Vec<FileRecord>,
+    pub modified: Vec<ModifiedFile>,
+    pub moved: Vec<MovedFile>,
+}
+
+impl IndexDiff {
+    /// 两个索引的generation之差；新索引的generation更小时为负
+    pub fn generation_gap(&self) -> i64 {
+        self.new_generation as i64 - self.old_generation as i64
+    }
+
+    /// 新增、删除和修改的文件带来的总长度变化（字节）
+    pub fn size_delta(&self) -> i64 {
+        let added: i64 = self.added.iter().map(|file| file.size as i64).sum();
+        let removed: i64 = self.removed.iter().map(|file| file.size as i64).sum();
+        let modified: i64 = self.modified.iter().map(ModifiedFile::size_delta).sum();
+        added - removed + modified
+    }
+
+    /// Whether the two indexes hold the same files
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.modified.is_empty()
+            && self.moved.is_empty()
+    }
+}
+
+fn records_by_uid(index: &LtfsIndex) -> HashMap<u64, FileRecord> {
+    let mut records = Vec::new();
+    collect_records(&index.root_directory, "", &mut records);
+    records.into_iter().map(|record| (record.uid, record)).collect()
+}
+
+fn file_changes(old: &FileRecord, new: &FileRecord) -> Vec<FileChange> {
+    let mut changes = Vec::new();
+    if old.size != new.size {
+        changes.push(FileChange::Length);
+    }
+    if old.modifytime != new.modifytime {
+        changes.push(FileChange::ModifyTime);
+    }
+    for (algorithm, old_value) in &old.hashes {
+        if let Some(new_value) = new.hashes.get(algorithm) {
+            if !old_value.eq_ignore_ascii_case(new_value) {
+                changes.push(FileChange::Hash(algorithm.clone()));
+            }
+        }
+    }
+    changes
+}
+
+impl LtfsIndex {
+    /// Compare this (older) index with `other` (newer)
+    pub fn diff(&self, other: &LtfsIndex) -> IndexDiff {
+        let mut old_by_uid = records_by_uid(self);
+        let mut new_by_uid = records_by_uid(other);
+
+        let mut pairs = Vec::new();
+        let shared_uids: Vec<u64> = old_by_uid
+            .keys()
+            .filter(|uid| new_by_uid.contains_key(uid))
+            .copied()
+            .collect();
+        for uid in shared_uids {
+            if let (Some(old), Some(new)) = (old_by_uid.remove(&uid), new_by_uid.remove(&uid)) {
+                pairs.push((old, new));
+            }
+        }
+
+        // UID未配对的文件按路径配对
+        let mut new_by_path: BTreeMap<String, FileRecord> = new_by_uid
+            .into_values()
+            .map(|record| (record.path.clone(), record))
+            .collect();
+        let mut removed = Vec::new();
+        for old in old_by_uid.into_values() {
+            match new_by_path.remove(&old.path) {
+                Some(new) => pairs.push((old, new)),
+                None => removed.push(old),
+            }
+        }
+        let added: Vec<FileRecord> = new_by_path.into_values().collect();
+
+        let mut modified = Vec::new();
+        let mut moved = Vec::new();
+        for (old, new) in pairs {
+            if old.path != new.path {
+                moved.push(MovedFile {
+                    uid: new.uid,
+                    old_path: old.path.clone(),
+                    new_path: new.path.clone(),
+                });
+            }
+            let changes = file_changes(&old, &new);
+            if !changes.is_empty() {
+                modified.push(ModifiedFile { old, new, changes });
+            }
+        }
+
+        removed.sort_by(|a, b| a.path.cmp(&b.path));
+        modified.sort_by(|a, b| a.new.path.cmp(&b.new.path));
+        moved.sort_by(|a, b| a.new_path.cmp(&b.new_path));
+
+        IndexDiff {
+            old_generation: self.generationnumber,
+            new_generation: other.generationnumber,
+            added,
+            removed,
+            modified,
+            moved,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ltfs_index::{ExtendedAttribute, ExtendedAttributes, File};
+
+    const TIME: &str = "2024-01-01T00:00:00.000000000Z";
+
+    fn file(name: &str, uid: u64, length: u64, sha1: Option<&str>) -> File {
+        File {
+            name: name.to_string(),
+            uid,
+            length,
+            creation_time: TIME.to_string(),
+            change_time: TIME.to_string(),
+            modify_time: TIME.to_string(),
+            access_time: TIME.to_string(),
+            backup_time: TIME.to_string(),
+            read_only: false,
+            openforwrite: false,
+            symlink: None,
+            extent_info: Default::default(),
+            extended_attributes: sha1.map(|value| ExtendedAttributes {
+                attributes: vec![ExtendedAttribute {
+                    key: "ltfs.hash.sha1sum".to_string(),
+                    value: value.to_string(),
+                }],
+            }),
+        }
+    }
+
+    fn index(generation: u64, files: Vec<(&str, File)>) -> LtfsIndex {
+        let xml = format!(
+            "<ltfsindex version=\"2.4.0\"><creator>test</creator><volumeuuid>v</volumeuuid>\
+             <generationnumber>{generation}</generationnumber><updatetime>{TIME}</updatetime>\
+             <location><partition>a</partition><startblock>5</startblock></location>\
+             <directory><name></name><fileuid>1</fileuid><creationtime>{TIME}</creationtime>\
+             <changetime>{TIME}</changetime><modifytime>{TIME}</modifytime><accesstime>{TIME}</accesstime>\
+             <backuptime>{TIME}</backuptime><readonly>false</readonly><contents></contents></directory></ltfsindex>"
+        );
+        let mut index = LtfsIndex::from_xml_streaming(&xml).unwrap();
+        for (dir, file) in files {
+            index.get_or_create_directory(dir).contents.files.push(file);
+        }
+        index
+    }
+
+    #[test]
+    fn reports_added_removed_modified_and_moved_files() {
+        let old = index(
+            3,
+            vec![
+                ("a", file("kept.txt", 10, 5, Some("AA"))),
+                ("a", file("gone.txt", 11, 7, None)),
+                ("a", file("rehashed.bin", 12, 9, Some("aa"))),
+                ("a", file("moved.txt", 13, 1, None)),
+                ("", file("overwritten.log", 14, 4, None)),
+            ],
+        );
+        let new = index(
+            6,
+            vec![
+                ("a", file("kept.txt", 10, 5, Some("aa"))),
+                ("a", file("rehashed.bin", 12, 9, Some("bb"))),
+                ("b", file("moved.txt", 13, 1, None)),
+                ("", file("overwritten.log", 20, 40, None)),
+                ("b", file("new.txt", 21, 100, None)),
+            ],
+        );
+
+        let diff = old.diff(&new);
+        assert_eq!(diff.generation_gap(), 3);
+        assert_eq!(diff.added.iter().map(|f| f.path.as_str()).collect::<Vec<_>>(), ["/b/new.txt"]);
+        assert_eq!(diff.removed.iter().map(|f| f.path.as_str()).collect::<Vec<_>>(), ["/a/gone.txt"]);
+        assert_eq!(diff.moved.len(), 1);
+        assert_eq!((diff.moved[0].old_path.as_str(), diff.moved[0].new_path.as_str()), ("/a/moved.txt", "/b/moved.txt"));
+
+        let modified: Vec<_> = diff.modified.iter().map(|m| (m.new.path.as_str(), m.changes.clone())).collect();
+        assert_eq!(
+            modified,
+            [
+                ("/a/rehashed.bin", vec![FileChange::Hash("sha1sum".to_string())]),
+                ("/overwritten.log", vec![FileChange::Length]),
+            ]
+        );
+        assert_eq!(diff.size_delta(), 100 - 7 + 36);
+    }
+
+    #[test]
+    fn identical_indexes_have_no_changes() {
+        let old = index(1, vec![("x", file("same.txt", 10, 3, Some("ab")))]);
+        let diff = old.diff(&old);
+        assert!(diff.is_empty());
+        assert_eq!(diff.generation_gap(), 0);
+    }
+}
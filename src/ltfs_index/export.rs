@@ -0,0 +1,269 @@
+//! LTFS Index File List Export
+//!
+//! This module exports the files recorded in an LTFS index as JSON, CSV or TSV,
+//! with full tape paths reconstructed from the directory tree.
+
+use super::tree::{IndexEntry, PathNormalize};
+use super::types::{Directory, File, LtfsIndex};
+use crate::error::{Result, RustLtfsError};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::Write;
+
+/// 扩展属性中存放文件哈希的键前缀 (ltfs.hash.sha1sum 等)
+const HASH_XATTR_PREFIX: &str = "ltfs.hash.";
+
+/// Output format for a file list export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+    Tsv,
+}
+
+impl std::str::FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "csv" => Ok(Self::Csv),
+            "tsv" => Ok(Self::Tsv),
+            _ => Err(format!("unknown export format '{}' (expected json, csv or tsv)", s)),
+        }
+    }
+}
+
+/// A column of the exported file list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportField {
+    Path,
+    Name,
+    Uid,
+    Size,
+    Extents,
+    ModifyTime,
+    CreationTime,
+    Hashes,
+}
+
+impl ExportField {
+    /// All columns, in default export order
+    pub const ALL: [ExportField; 8] = [
+        ExportField::Path,
+        ExportField::Name,
+        ExportField::Uid,
+        ExportField::Size,
+        ExportField::Extents,
+        ExportField::ModifyTime,
+        ExportField::CreationTime,
+        ExportField::Hashes,
+    ];
+
+    /// Column name, also the JSON key
+    pub fn name(self) -> &'static str {
+        match self {
+            ExportField::Path => "path",
+            ExportField::Name => "name",
+            ExportField::Uid => "uid",
+            ExportField::Size => "size",
+            ExportField::Extents => "extents",
+            ExportField::ModifyTime => "modifytime",
+            ExportField::CreationTime => "creationtime",
+            ExportField::Hashes => "hashes",
+        }
+    }
+}
+
+impl std::str::FromStr for ExportField {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let s = s.trim().to_ascii_lowercase();
+        Self::ALL
+            .into_iter()
+            .find(|field| field.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Self::ALL.iter().map(|f| f.name()).collect();
+                format!("unknown field '{}' (expected one of: {})", s, names.join(", "))
+            })
+    }
+}
+
+/// One extent of an exported file
+#[derive(Debug, Serialize)]
+pub struct ExportedExtent {
+    pub partition: String,
+    pub startblock: u64,
+    pub byteoffset: u64,
+    pub bytecount: u64,
+    pub fileoffset: u64,
+}
+
+/// One file of the index, addressed by its full tape path
+#[derive(Debug, Serialize)]
+pub struct FileRecord {
+    pub path: String,
+    pub name: String,
+    pub uid: u64,
+    pub size: u64,
+    pub extents: Vec<ExportedExtent>,
+    pub modifytime: String,
+    pub creationtime: String,
+    /// 存储在扩展属性中的哈希，键去掉 `ltfs.hash.` 前缀 (sha1sum, md5sum, ...)
+    pub hashes: BTreeMap<String, String>,
+}
+
+impl FileRecord {
+    fn new(parent_path: &str, file: &File) -> Self {
+        let hashes = file
+            .extended_attributes
+            .iter()
+            .flat_map(|xattrs| xattrs.attributes.iter())
+            .filter_map(|attr| {
+                attr.key
+                    .strip_prefix(HASH_XATTR_PREFIX)
+                    .map(|algorithm| (algorithm.to_string(), attr.value.clone()))
+            })
+            .collect();
+
+        Self {
+            path: format!("{}/{}", parent_path, file.name),
+            name: file.name.clone(),
+            uid: file.uid,
+            size: file.length,
+            extents: file
+                .extent_info
+                .extents
+                .iter()
+                .map(|extent| ExportedExtent {
+                    partition: extent.partition.clone(),
+                    startblock: extent.start_block,
+                    byteoffset: extent.byte_offset,
+                    bytecount: extent.byte_count,
+                    fileoffset: extent.file_offset,
+                })
+                .collect(),
+            modifytime: file.modify_time.clone(),
+            creationtime: file.creation_time.clone(),
+            hashes,
+        }
+    }
+
+    /// 单元格文本（CSV/TSV）：extent 为 `partition:startblock:byteoffset:bytecount:fileoffset`，
+    /// 多个值以 `;` 分隔
+    fn cell(&self, field: ExportField) -> String {
+        match field {
+            ExportField::Path => self.path.clone(),
+            ExportField::Name => self.name.clone(),
+            ExportField::Uid => self.uid.to_string(),
+            ExportField::Size => self.size.to_string(),
+            ExportField::Extents => self
+                .extents
+                .iter()
+                .map(|e| {
+                    format!(
+                        "{}:{}:{}:{}:{}",
+                        e.partition, e.startblock, e.byteoffset, e.bytecount, e.fileoffset
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(";"),
+            ExportField::ModifyTime => self.modifytime.clone(),
+            ExportField::CreationTime => self.creationtime.clone(),
+            ExportField::Hashes => self
+                .hashes
+                .iter()
+                .map(|(algorithm, value)| format!("{}={}", algorithm, value))
+                .collect::<Vec<_>>()
+                .join(";"),
+        }
+    }
+}
+
+pub(super) fn collect_records(dir: &Directory, dir_path: &str, records: &mut Vec<FileRecord>) {
+    for file in &dir.contents.files {
+        records.push(FileRecord::new(dir_path, file));
+    }
+    for subdir in &dir.contents.directories {
+        collect_records(subdir, &format!("{}/{}", dir_path, subdir.name), records);
+    }
+}
+
+impl LtfsIndex {
+    /// Collect every file at or below `root_path` with its full tape path
+    pub fn file_records(&self, root_path: &str) -> Result<Vec<FileRecord>> {
+        let (path, entry) = self
+            .find_path(root_path, PathNormalize::CaseSensitive)
+            .ok_or_else(|| {
+                RustLtfsError::ltfs_index(format!("Path not found in index: {}", root_path))
+            })?;
+        let mut records = Vec::new();
+
+        match entry {
+            IndexEntry::Directory(dir) => {
+                collect_records(dir, path.trim_end_matches('/'), &mut records)
+            }
+            IndexEntry::File(file) => {
+                let parent = path.rsplit_once('/').map_or("", |(parent, _)| parent);
+                records.push(FileRecord::new(parent, file));
+            }
+        }
+        Ok(records)
+    }
+
+    /// Export the files at or below `root_path` in the given format, limited to `fields`
+    ///
+    /// Returns the number of exported files.
+    pub fn export_file_list<W: Write>(
+        &self,
+        root_path: &str,
+        writer: W,
+        format: ExportFormat,
+        fields: &[ExportField],
+    ) -> Result<usize> {
+        let fields = if fields.is_empty() {
+            &ExportField::ALL[..]
+        } else {
+            fields
+        };
+        let records = self.file_records(root_path)?;
+
+        match format {
+            ExportFormat::Json => {
+                let rows = records
+                    .iter()
+                    .map(|record| {
+                        let mut value = serde_json::to_value(record)?;
+                        if let serde_json::Value::Object(ref mut map) = value {
+                            map.retain(|key, _| fields.iter().any(|f| f.name() == key));
+                        }
+                        Ok(value)
+                    })
+                    .collect::<std::result::Result<Vec<_>, serde_json::Error>>()
+                    .map_err(|e| RustLtfsError::system(format!("JSON export failed: {}", e)))?;
+                serde_json::to_writer_pretty(writer, &rows)
+                    .map_err(|e| RustLtfsError::system(format!("JSON export failed: {}", e)))?;
+            }
+            ExportFormat::Csv | ExportFormat::Tsv => {
+                let delimiter = if format == ExportFormat::Tsv { b'\t' } else { b',' };
+                let mut csv_writer = csv::WriterBuilder::new()
+                    .delimiter(delimiter)
+                    .from_writer(writer);
+                let map_err = |e: csv::Error| RustLtfsError::system(format!("CSV export failed: {}", e));
+
+                csv_writer
+                    .write_record(fields.iter().map(|f| f.name()))
+                    .map_err(map_err)?;
+                for record in &records {
+                    csv_writer
+                        .write_record(fields.iter().map(|f| record.cell(*f)))
+                        .map_err(map_err)?;
+                }
+                csv_writer.flush()?;
+            }
+        }
+
+        Ok(records.len())
+    }
+}
@@ -6,12 +6,22 @@
 //! - `types`: Core data structure definitions
 //! - `parser`: XML parsing functionality
 //! - `serializer`: XML serialization functionality
-//! - `validator`: Index validation logic
+//! - `validator`: Index validation logic and the non-fatal consistency check
+//! - `uid`: UID allocation and reserved UID handling
+//! - `name`: Unicode (NFC) name normalization, percent-encoding and non-UTF-8 names
+//! - `tree`: Path resolution (`find_path`) and directory tree navigation
+//! - `export`: File list export (JSON / CSV / TSV)
+//! - `diff`: File-level comparison of two index generations
 
 pub mod types;
 pub mod parser;
 pub mod validator;
 pub mod serializer;
+pub mod uid;
+pub mod name;
+pub mod tree;
+pub mod export;
+pub mod diff;
 
 // Re-export public types for convenience
 pub use types::{
@@ -25,3 +35,7 @@ pub use types::{
     ExtendedAttributes,
     ExtendedAttribute,
 };
+pub use export::{ExportField, ExportFormat};
+pub use diff::FileChange;
+pub use tree::{IndexEntry, PathNormalize};
+pub use validator::{ConsistencyLimits, ValidationWarning};
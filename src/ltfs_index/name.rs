@@ -0,0 +1,156 @@
+//! LTFS Index Name Normalization
+//!
+//! File and directory names are stored and compared in Unicode Normalization
+//! Form C (NFC). Sources such as macOS (which produces NFD names) and Linux
+//! (usually NFC) are normalized on write, on index parse and on path lookup, so
+//! equivalent names always refer to the same index entry.
+//!
+//! Names holding characters that XML cannot carry (control characters, U+FFFE,
+//! U+FFFF) are written percent-encoded with `percentencoded="true"`, as the LTFS
+//! Format Specification 2.4.0 requires; `%` itself is then encoded too. XML-special
+//! characters such as `&` and `<` are escaped by the XML writer as usual.
+//!
+//! Source names that are not valid UTF-8 (possible on Unix) keep their raw bytes:
+//! in memory each undecodable byte is held as U+F700 + byte (U+F780..=U+F7FF, in the
+//! Private Use Area), in XML it is percent-encoded as that byte, and extraction writes
+//! the original bytes back.
+
+use super::types::{Directory, LtfsIndex};
+use std::ffi::{OsStr, OsString};
+use unicode_normalization::{is_nfc, UnicodeNormalization};
+
+/// 无法解码的字节在内存中表示为 U+F700 + 字节值
+const ESCAPED_BYTE_BASE: u32 = 0xF700;
+
+/// Normalize a file/directory name (or a `/` separated path) to NFC
+pub fn normalize_name(name: &str) -> String {
+    if is_nfc(name) {
+        name.to_string()
+    } else {
+        name.nfc().collect()
+    }
+}
+
+/// 名称中表示原始字节（非UTF-8源文件名）的字符
+fn escaped_byte(c: char) -> Option<u8> {
+    match c as u32 {
+        code @ 0xF780..=0xF7FF => Some((code - ESCAPED_BYTE_BASE) as u8),
+        _ => None,
+    }
+}
+
+/// Index name for raw name bytes; bytes that are not valid UTF-8 are kept as escaped bytes
+pub fn name_from_bytes(bytes: &[u8]) -> String {
+    let mut name = String::with_capacity(bytes.len());
+    for chunk in bytes.utf8_chunks() {
+        name.push_str(chunk.valid());
+        for &byte in chunk.invalid() {
+            name.extend(char::from_u32(ESCAPED_BYTE_BASE + byte as u32));
+        }
+    }
+    name
+}
+
+/// Raw bytes of an index name (inverse of `name_from_bytes`)
+pub fn name_to_bytes(name: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(name.len());
+    for c in name.chars() {
+        match escaped_byte(c) {
+            Some(byte) => bytes.push(byte),
+            None => bytes.extend_from_slice(c.encode_utf8(&mut [0u8; 4]).as_bytes()),
+        }
+    }
+    bytes
+}
+
+/// NFC index name for a source file name, keeping non-UTF-8 bytes on Unix
+pub fn name_from_os_str(name: &OsStr) -> String {
+    #[cfg(unix)]
+    let name = name_from_bytes(std::os::unix::ffi::OsStrExt::as_bytes(name));
+    #[cfg(not(unix))]
+    let name = name.to_string_lossy().into_owned();
+    normalize_name(&name)
+}
+
+/// Local file name for an index name, restoring non-UTF-8 bytes on Unix
+pub fn name_to_os_string(name: &str) -> OsString {
+    #[cfg(unix)]
+    return std::os::unix::ffi::OsStringExt::from_vec(name_to_bytes(name));
+    #[cfg(not(unix))]
+    return OsString::from(name);
+}
+
+/// XML 1.0中不能出现（或会被解析器规范化）的字符，以及表示原始字节的字符
+fn needs_percent_encoding(c: char) -> bool {
+    c.is_ascii_control() || c == '\u{FFFE}' || c == '\u{FFFF}' || escaped_byte(c).is_some()
+}
+
+/// Percent-encode `name` for `<name percentencoded="true">`; None if it can be written as is
+pub(super) fn percent_encode_name(name: &str) -> Option<String> {
+    if !name.chars().any(needs_percent_encoding) {
+        return None;
+    }
+
+    let mut encoded = String::with_capacity(name.len() + 8);
+    for c in name.chars() {
+        if c == '%' || needs_percent_encoding(c) {
+            for byte in name_to_bytes(c.encode_utf8(&mut [0u8; 4])) {
+                encoded.push_str(&format!("%{:02X}", byte));
+            }
+        } else {
+            encoded.push(c);
+        }
+    }
+    Some(encoded)
+}
+
+fn hex_digit(byte: u8) -> Option<u8> {
+    (byte as char).to_digit(16).map(|digit| digit as u8)
+}
+
+/// Decode a `percentencoded="true"` name; malformed escapes are kept literally and
+/// decoded bytes that are not valid UTF-8 become escaped bytes
+pub(super) fn percent_decode_name(encoded: &str) -> String {
+    let bytes = encoded.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = match bytes.get(i + 1..i + 3) {
+            Some(&[high, low]) if bytes[i] == b'%' => hex_digit(high).zip(hex_digit(low)),
+            _ => None,
+        };
+        match escaped {
+            Some((high, low)) => {
+                decoded.push(high << 4 | low);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    name_from_bytes(&decoded)
+}
+
+impl LtfsIndex {
+    /// Normalize every file and directory name in the index to NFC
+    pub(super) fn normalize_names(&mut self) {
+        normalize_directory_names(&mut self.root_directory);
+    }
+}
+
+fn normalize_directory_names(directory: &mut Directory) {
+    for file in &mut directory.contents.files {
+        if !is_nfc(&file.name) {
+            file.name = normalize_name(&file.name);
+        }
+    }
+
+    for subdir in &mut directory.contents.directories {
+        if !is_nfc(&subdir.name) {
+            subdir.name = normalize_name(&subdir.name);
+        }
+        normalize_directory_names(subdir);
+    }
+}
@@ -6,6 +6,9 @@ use crate::error::Result;
 use super::types::*;
 use tracing::{debug, info, warn};
 
+/// 解析后最多记录的一致性警告条数
+const MAX_LOGGED_WARNINGS: usize = 20;
+
 impl LtfsIndex {
     /// Parse LTFS index from XML content with enhanced error handling
     pub fn from_xml(xml_content: &str) -> Result<Self> {
@@ -36,9 +39,47 @@ impl LtfsIndex {
             crate::error::RustLtfsError::parse(error_msg)
         })?;
 
+        Self::finish_parsed_index(index)
+    }
+
+    /// 流式解析LTFS索引：基于quick-xml事件直接构建Directory/File树，
+    /// 不提取索引片段副本，也不做整串的结构预扫描。
+    ///
+    /// 数十万文件的索引使用此路径；解析结果与 `from_xml` 相同。
+    pub fn from_xml_streaming(xml_content: &str) -> Result<Self> {
+        debug!("Streaming parse of LTFS index XML, length: {}", xml_content.len());
+        Self::from_xml_reader(xml_content.as_bytes())
+    }
+
+    /// 从任意 `BufRead` 源流式解析LTFS索引（索引文件、磁带读回的字节缓冲）
+    pub fn from_xml_reader<R: std::io::BufRead>(source: R) -> Result<Self> {
+        let index = StreamingIndexParser::new().parse(source)?;
+        Self::finish_parsed_index(index)
+    }
+
+    /// 两种解析路径共用的后处理：名称规范化、保留UID修复和一致性校验
+    fn finish_parsed_index(mut index: LtfsIndex) -> Result<Self> {
+        // Names are kept in NFC so lookups match regardless of the writer's platform
+        index.normalize_names();
+
+        // Repair entries that collide with the reserved root UID before validation
+        index.repair_reserved_uids();
+
         // Post-validation of parsed index
         Self::validate_parsed_index(&index)?;
 
+        // 扩展/UID损坏只报告不拒绝，便于在提取前检查轻微损坏的索引
+        let warnings = index.check_consistency(&super::validator::ConsistencyLimits::default());
+        for warning in warnings.iter().take(MAX_LOGGED_WARNINGS) {
+            warn!("Index consistency: {}", warning);
+        }
+        if warnings.len() > MAX_LOGGED_WARNINGS {
+            warn!(
+                "Index consistency: {} more warnings not shown",
+                warnings.len() - MAX_LOGGED_WARNINGS
+            );
+        }
+
         info!(
             "Successfully parsed LTFS index, version: {}, generation: {}, files: {}",
             index.version,
@@ -123,3 +164,468 @@ impl LtfsIndex {
         }
     }
 }
+
+/// 流式解析器的元素栈帧：每个帧对应一个正在构建的索引节点
+enum Frame {
+    Index(Box<IndexFields>),
+    Location(LocationFields),
+    Directory(Box<DirectoryFields>),
+    Contents(DirectoryContents),
+    File(Box<FileFields>),
+    ExtentInfo(ExtentInfo),
+    Extent(ExtentFields),
+    ExtendedAttributes(Vec<ExtendedAttribute>),
+    Xattr(XattrFields),
+    /// 叶子元素（纯文本字段），文本收集在解析器的共享缓冲区中
+    Leaf(String),
+    /// 未知元素子树，忽略其全部内容（与serde路径忽略未知字段一致）
+    Skip,
+}
+
+#[derive(Default)]
+struct IndexFields {
+    version: Option<String>,
+    creator: Option<String>,
+    volumeuuid: Option<String>,
+    generationnumber: Option<u64>,
+    updatetime: Option<String>,
+    location: Option<Location>,
+    previousgenerationlocation: Option<Location>,
+    allowpolicyupdate: Option<bool>,
+    volumelockstate: Option<String>,
+    highestfileuid: Option<u64>,
+    root_directory: Option<Directory>,
+}
+
+#[derive(Default)]
+struct LocationFields {
+    previous: bool,
+    partition: Option<String>,
+    startblock: Option<u64>,
+}
+
+#[derive(Default)]
+struct DirectoryFields {
+    name: Option<String>,
+    uid: Option<u64>,
+    creation_time: Option<String>,
+    change_time: Option<String>,
+    modify_time: Option<String>,
+    access_time: Option<String>,
+    backup_time: Option<String>,
+    read_only: Option<bool>,
+    contents: Option<DirectoryContents>,
+}
+
+#[derive(Default)]
+struct FileFields {
+    name: Option<String>,
+    uid: Option<u64>,
+    length: Option<u64>,
+    creation_time: Option<String>,
+    change_time: Option<String>,
+    modify_time: Option<String>,
+    access_time: Option<String>,
+    backup_time: Option<String>,
+    read_only: Option<bool>,
+    openforwrite: Option<bool>,
+    symlink: Option<String>,
+    extent_info: ExtentInfo,
+    extended_attributes: Option<ExtendedAttributes>,
+}
+
+#[derive(Default)]
+struct ExtentFields {
+    partition: Option<String>,
+    start_block: Option<u64>,
+    byte_count: Option<u64>,
+    file_offset: Option<u64>,
+    byte_offset: Option<u64>,
+}
+
+#[derive(Default)]
+struct XattrFields {
+    key: Option<String>,
+    value: Option<String>,
+}
+
+fn required<T>(value: Option<T>, field: &str, element: &str) -> Result<T> {
+    value.ok_or_else(|| {
+        crate::error::RustLtfsError::parse(format!(
+            "missing field `{}` in <{}>",
+            field, element
+        ))
+    })
+}
+
+fn parse_number(field: &str, text: &str) -> Result<u64> {
+    text.parse::<u64>().map_err(|e| {
+        crate::error::RustLtfsError::parse(format!(
+            "invalid value for <{}>: '{}' ({})",
+            field, text, e
+        ))
+    })
+}
+
+/// 与quick-xml serde一致：接受 true/false/1/0
+fn parse_bool(field: &str, text: &str) -> Result<bool> {
+    match text {
+        "true" | "1" => Ok(true),
+        "false" | "0" => Ok(false),
+        _ => Err(crate::error::RustLtfsError::parse(format!(
+            "invalid boolean for <{}>: '{}'",
+            field, text
+        ))),
+    }
+}
+
+impl Frame {
+    /// 根据父帧决定子元素对应的帧类型
+    fn child(&self, name: &str) -> Frame {
+        match (self, name) {
+            (Frame::Index(_), "location") => Frame::Location(LocationFields::default()),
+            (Frame::Index(_), "previousgenerationlocation") => Frame::Location(LocationFields {
+                previous: true,
+                ..Default::default()
+            }),
+            (Frame::Index(_), "directory") => Frame::Directory(Box::default()),
+            (Frame::Directory(_), "contents") => Frame::Contents(DirectoryContents::default()),
+            (Frame::Contents(_), "directory") => Frame::Directory(Box::default()),
+            (Frame::Contents(_), "file") => Frame::File(Box::default()),
+            (Frame::File(_), "extentinfo") => Frame::ExtentInfo(ExtentInfo::default()),
+            (Frame::File(_), "extendedattributes") => Frame::ExtendedAttributes(Vec::new()),
+            (Frame::ExtentInfo(_), "extent") => Frame::Extent(ExtentFields::default()),
+            (Frame::ExtendedAttributes(_), "xattr") => Frame::Xattr(XattrFields::default()),
+            (
+                Frame::Index(_)
+                | Frame::Location(_)
+                | Frame::Directory(_)
+                | Frame::File(_)
+                | Frame::Extent(_)
+                | Frame::Xattr(_),
+                _,
+            ) => Frame::Leaf(name.to_string()),
+            _ => Frame::Skip,
+        }
+    }
+
+    /// 设置叶子字段的值（字段名及别名与types.rs中的serde定义一致）
+    fn set_field(&mut self, field: &str, text: String) -> Result<()> {
+        match self {
+            Frame::Index(f) => match field {
+                "creator" => f.creator = Some(text),
+                "volumeuuid" => f.volumeuuid = Some(text),
+                "generationnumber" => f.generationnumber = Some(parse_number(field, &text)?),
+                "updatetime" => f.updatetime = Some(text),
+                "allowpolicyupdate" if !text.is_empty() => {
+                    f.allowpolicyupdate = Some(parse_bool(field, &text)?)
+                }
+                "volumelockstate" => f.volumelockstate = Some(text),
+                "highestfileuid" if !text.is_empty() => {
+                    f.highestfileuid = Some(parse_number(field, &text)?)
+                }
+                _ => {}
+            },
+            Frame::Location(f) => match field {
+                "partition" => f.partition = Some(text),
+                "startblock" | "startBlock" | "start_block" | "block" => {
+                    f.startblock = Some(parse_number(field, &text)?)
+                }
+                _ => {}
+            },
+            Frame::Directory(f) => match field {
+                "name" => f.name = Some(text),
+                "fileuid" => f.uid = Some(parse_number(field, &text)?),
+                "creationtime" => f.creation_time = Some(text),
+                "changetime" => f.change_time = Some(text),
+                "modifytime" => f.modify_time = Some(text),
+                "accesstime" => f.access_time = Some(text),
+                "backuptime" => f.backup_time = Some(text),
+                "readonly" => f.read_only = Some(parse_bool(field, &text)?),
+                _ => {}
+            },
+            Frame::File(f) => match field {
+                "name" => f.name = Some(text),
+                "fileuid" => f.uid = Some(parse_number(field, &text)?),
+                "length" => f.length = Some(parse_number(field, &text)?),
+                "creationtime" => f.creation_time = Some(text),
+                "changetime" => f.change_time = Some(text),
+                "modifytime" => f.modify_time = Some(text),
+                "accesstime" => f.access_time = Some(text),
+                "backuptime" => f.backup_time = Some(text),
+                "readonly" => f.read_only = Some(parse_bool(field, &text)?),
+                "openforwrite" => f.openforwrite = Some(parse_bool(field, &text)?),
+                // 空的 <symlink/> 表示不是符号链接
+                "symlink" => f.symlink = Some(text).filter(|t| !t.is_empty()),
+                _ => {}
+            },
+            Frame::Extent(f) => match field {
+                "partition" => f.partition = Some(text),
+                "startblock" | "startBlock" | "start_block" | "block" => {
+                    f.start_block = Some(parse_number(field, &text)?)
+                }
+                "bytecount" => f.byte_count = Some(parse_number(field, &text)?),
+                "fileoffset" => f.file_offset = Some(parse_number(field, &text)?),
+                "byteoffset" => f.byte_offset = Some(parse_number(field, &text)?),
+                _ => {}
+            },
+            Frame::Xattr(f) => match field {
+                "key" => f.key = Some(text),
+                "value" => f.value = Some(text),
+                _ => {}
+            },
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl DirectoryFields {
+    fn build(self) -> Result<Directory> {
+        Ok(Directory {
+            name: required(self.name, "name", "directory")?,
+            uid: required(self.uid, "fileuid", "directory")?,
+            creation_time: required(self.creation_time, "creationtime", "directory")?,
+            change_time: required(self.change_time, "changetime", "directory")?,
+            modify_time: required(self.modify_time, "modifytime", "directory")?,
+            access_time: required(self.access_time, "accesstime", "directory")?,
+            backup_time: required(self.backup_time, "backuptime", "directory")?,
+            read_only: required(self.read_only, "readonly", "directory")?,
+            contents: required(self.contents, "contents", "directory")?,
+        })
+    }
+}
+
+impl FileFields {
+    fn build(self) -> Result<File> {
+        Ok(File {
+            name: required(self.name, "name", "file")?,
+            uid: required(self.uid, "fileuid", "file")?,
+            length: required(self.length, "length", "file")?,
+            creation_time: required(self.creation_time, "creationtime", "file")?,
+            change_time: required(self.change_time, "changetime", "file")?,
+            modify_time: required(self.modify_time, "modifytime", "file")?,
+            access_time: required(self.access_time, "accesstime", "file")?,
+            backup_time: required(self.backup_time, "backuptime", "file")?,
+            read_only: required(self.read_only, "readonly", "file")?,
+            openforwrite: required(self.openforwrite, "openforwrite", "file")?,
+            symlink: self.symlink,
+            extent_info: self.extent_info,
+            extended_attributes: self.extended_attributes,
+        })
+    }
+}
+
+impl ExtentFields {
+    fn build(self) -> Result<FileExtent> {
+        Ok(FileExtent {
+            partition: required(self.partition, "partition", "extent")?,
+            start_block: required(self.start_block, "startblock", "extent")?,
+            byte_count: required(self.byte_count, "bytecount", "extent")?,
+            file_offset: required(self.file_offset, "fileoffset", "extent")?,
+            byte_offset: required(self.byte_offset, "byteoffset", "extent")?,
+        })
+    }
+}
+
+impl IndexFields {
+    fn build(self) -> Result<LtfsIndex> {
+        Ok(LtfsIndex {
+            version: required(self.version, "@version", "ltfsindex")?,
+            creator: required(self.creator, "creator", "ltfsindex")?,
+            volumeuuid: required(self.volumeuuid, "volumeuuid", "ltfsindex")?,
+            generationnumber: required(self.generationnumber, "generationnumber", "ltfsindex")?,
+            updatetime: required(self.updatetime, "updatetime", "ltfsindex")?,
+            location: required(self.location, "location", "ltfsindex")?,
+            previousgenerationlocation: self.previousgenerationlocation,
+            allowpolicyupdate: self.allowpolicyupdate,
+            volumelockstate: self
+                .volumelockstate
+                .unwrap_or_else(|| "unlocked".to_string()),
+            highestfileuid: self.highestfileuid,
+            root_directory: required(self.root_directory, "directory", "ltfsindex")?,
+        })
+    }
+}
+
+/// 基于事件的LTFS索引解析器：逐个XML事件构建目录树，
+/// 工作内存只有元素栈（深度与目录层数成正比）和当前叶子文本
+struct StreamingIndexParser {
+    stack: Vec<Frame>,
+    text: String,
+    /// 当前叶子元素带有 `percentencoded="true"`
+    percent_encoded: bool,
+    result: Option<LtfsIndex>,
+}
+
+impl StreamingIndexParser {
+    fn new() -> Self {
+        Self {
+            stack: Vec::new(),
+            text: String::new(),
+            percent_encoded: false,
+            result: None,
+        }
+    }
+
+    fn start(&mut self, element: &quick_xml::events::BytesStart) -> Result<()> {
+        let name = String::from_utf8_lossy(element.local_name().as_ref()).into_owned();
+
+        let frame = match self.stack.last() {
+            None if name == "ltfsindex" => {
+                let version = element
+                    .try_get_attribute("version")
+                    .map_err(|e| crate::error::RustLtfsError::parse(e.to_string()))?
+                    .map(|attr| attr.unescape_value().map(|v| v.into_owned()))
+                    .transpose()
+                    .map_err(|e| crate::error::RustLtfsError::parse(e.to_string()))?;
+                Frame::Index(Box::new(IndexFields {
+                    version,
+                    ..Default::default()
+                }))
+            }
+            // ltfsindex之外的顶层元素（例如ltfslabel）整体跳过
+            None => Frame::Skip,
+            Some(parent) => parent.child(&name),
+        };
+
+        if matches!(frame, Frame::Leaf(_)) {
+            self.text.clear();
+            self.percent_encoded = matches!(
+                element.try_get_attribute("percentencoded"),
+                Ok(Some(attr)) if attr.value.as_ref() == b"true"
+            );
+        }
+        self.stack.push(frame);
+        Ok(())
+    }
+
+    fn text(&mut self, text: &str) {
+        if matches!(self.stack.last(), Some(Frame::Leaf(_))) {
+            self.text.push_str(text);
+        }
+    }
+
+    /// 结束当前元素，返回true表示ltfsindex根元素已完成
+    fn end(&mut self) -> Result<bool> {
+        let frame = match self.stack.pop() {
+            Some(frame) => frame,
+            None => return Ok(false),
+        };
+
+        let parent = self.stack.last_mut();
+        match (frame, parent) {
+            (Frame::Leaf(field), Some(parent)) => {
+                let mut text = self.text.trim().to_string();
+                self.text.clear();
+                if self.percent_encoded && field == "name" {
+                    text = super::name::percent_decode_name(&text);
+                }
+                parent.set_field(&field, text)?;
+            }
+            (Frame::Location(fields), Some(Frame::Index(index))) => {
+                // 空的 <previousgenerationlocation/> 视为没有上一代位置
+                if fields.previous && fields.partition.is_none() && fields.startblock.is_none() {
+                    return Ok(false);
+                }
+                let location = Location {
+                    partition: required(fields.partition, "partition", "location")?,
+                    startblock: required(fields.startblock, "startblock", "location")?,
+                };
+                if fields.previous {
+                    index.previousgenerationlocation = Some(location);
+                } else {
+                    index.location = Some(location);
+                }
+            }
+            (Frame::Directory(fields), Some(Frame::Index(index))) => {
+                index.root_directory = Some(fields.build()?);
+            }
+            (Frame::Directory(fields), Some(Frame::Contents(contents))) => {
+                contents.directories.push(fields.build()?);
+            }
+            (Frame::Contents(contents), Some(Frame::Directory(dir))) => {
+                dir.contents = Some(contents);
+            }
+            (Frame::File(fields), Some(Frame::Contents(contents))) => {
+                contents.files.push(fields.build()?);
+            }
+            (Frame::ExtentInfo(info), Some(Frame::File(file))) => {
+                file.extent_info = info;
+            }
+            (Frame::Extent(fields), Some(Frame::ExtentInfo(info))) => {
+                info.extents.push(fields.build()?);
+            }
+            (Frame::ExtendedAttributes(attributes), Some(Frame::File(file))) => {
+                file.extended_attributes = Some(ExtendedAttributes { attributes });
+            }
+            (Frame::Xattr(fields), Some(Frame::ExtendedAttributes(attributes))) => {
+                attributes.push(ExtendedAttribute {
+                    key: required(fields.key, "key", "xattr")?,
+                    value: fields.value.unwrap_or_default(),
+                });
+            }
+            (Frame::Index(fields), None) => {
+                self.result = Some(fields.build()?);
+                return Ok(true);
+            }
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    fn parse<R: std::io::BufRead>(mut self, source: R) -> Result<LtfsIndex> {
+        use quick_xml::events::Event;
+
+        let mut reader = quick_xml::Reader::from_reader(source);
+        let mut buf = Vec::new();
+
+        loop {
+            let event = reader.read_event_into(&mut buf).map_err(|e| {
+                crate::error::RustLtfsError::parse(format!(
+                    "Failed to parse LTFS index XML at byte {}: {}",
+                    reader.buffer_position(),
+                    e
+                ))
+            })?;
+
+            let finished = match event {
+                Event::Start(ref e) => {
+                    self.start(e)?;
+                    false
+                }
+                Event::Empty(ref e) => {
+                    self.start(e)?;
+                    self.end()?
+                }
+                Event::End(_) => self.end()?,
+                Event::Text(ref e) => {
+                    let text = e.unescape().map_err(|e| {
+                        crate::error::RustLtfsError::parse(format!(
+                            "Invalid text in LTFS index XML: {}",
+                            e
+                        ))
+                    })?;
+                    self.text(&text);
+                    false
+                }
+                Event::CData(ref e) => {
+                    self.text(&String::from_utf8_lossy(e));
+                    false
+                }
+                Event::Eof => break,
+                _ => false,
+            };
+
+            // ltfsindex结束后不再读取（忽略索引之后的块填充数据）
+            if finished {
+                break;
+            }
+            buf.clear();
+        }
+
+        self.result.ok_or_else(|| {
+            crate::error::RustLtfsError::parse("No complete LTFS index found in XML content")
+        })
+    }
+}
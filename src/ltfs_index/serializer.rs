@@ -3,13 +3,23 @@
 //! This module handles serializing LTFS index to XML format.
 
 use crate::error::Result;
-use super::types::LtfsIndex;
+use super::types::{Directory, LtfsIndex};
 use tracing::debug;
 
 impl LtfsIndex {
     /// Serialize LTFS index to XML string
+    ///
+    /// Output is deterministic: directories and files are ordered by name, extents
+    /// by file offset and extended attributes by key, and the document uses LF line
+    /// endings only. Two saves of the same logical index are byte-identical.
+    ///
+    /// Elements follow the LTFS 2.4.0 schema order (see `types.rs`); unset optional
+    /// elements are left out rather than written empty.
     pub fn to_xml(&self) -> Result<String> {
-        let xml_string = quick_xml::se::to_string(self).map_err(|e| {
+        let mut normalized = self.clone();
+        normalize_directory(&mut normalized.root_directory);
+
+        let xml_string = quick_xml::se::to_string(&normalized).map_err(|e| {
             crate::error::RustLtfsError::file_operation(format!(
                 "Failed to serialize LTFS index to XML: {}",
                 e
@@ -26,3 +36,134 @@ impl LtfsIndex {
         Ok(complete_xml)
     }
 }
+
+/// Sort directory contents recursively so serialization order does not depend on insertion order
+fn normalize_directory(dir: &mut Directory) {
+    dir.contents.directories.sort_by(|a, b| a.name.cmp(&b.name));
+    dir.contents.files.sort_by(|a, b| a.name.cmp(&b.name).then(a.uid.cmp(&b.uid)));
+
+    for file in &mut dir.contents.files {
+        file.extent_info.extents.sort_by_key(|extent| extent.file_offset);
+        if let Some(ref mut xattrs) = file.extended_attributes {
+            xattrs.attributes.sort_by(|a, b| a.key.cmp(&b.key));
+        }
+    }
+
+    for subdir in &mut dir.contents.directories {
+        normalize_directory(subdir);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Hand-written index in LTFS 2.4 spec element order, tab-indented
+    /// (not captured from a tape written by another implementation)
+    const SPEC_ORDER_INDEX: &str = include_str!("../../tests/data/spec_order_index.xml");
+
+    /// 去掉元素之间的缩进和换行
+    fn strip_layout(xml: &str) -> String {
+        xml.lines().map(str::trim).collect()
+    }
+
+    #[test]
+    fn spec_order_index_round_trips_to_identical_xml() {
+        let streamed = LtfsIndex::from_xml_streaming(SPEC_ORDER_INDEX).unwrap();
+        assert_eq!(strip_layout(&streamed.to_xml().unwrap()), strip_layout(SPEC_ORDER_INDEX));
+
+        let parsed = LtfsIndex::from_xml(SPEC_ORDER_INDEX).unwrap();
+        assert_eq!(strip_layout(&parsed.to_xml().unwrap()), strip_layout(SPEC_ORDER_INDEX));
+    }
+
+    /// 递归反转目录、文件、extent和扩展属性的顺序
+    fn reverse_order(dir: &mut Directory) {
+        dir.contents.directories.reverse();
+        dir.contents.files.reverse();
+        for file in &mut dir.contents.files {
+            file.extent_info.extents.reverse();
+            if let Some(ref mut xattrs) = file.extended_attributes {
+                xattrs.attributes.reverse();
+            }
+        }
+        for subdir in &mut dir.contents.directories {
+            reverse_order(subdir);
+        }
+    }
+
+    #[test]
+    fn serialization_is_deterministic_and_round_trips() {
+        let mut index = LtfsIndex::from_xml_streaming(SPEC_ORDER_INDEX).unwrap();
+        // 第二个扩展属性，以便检验属性的排序
+        let report = &mut index.root_directory.contents.directories[0].contents.files[0];
+        report.extended_attributes.as_mut().unwrap().attributes.push(crate::ltfs_index::ExtendedAttribute {
+            key: "ltfs.hash.md5sum".to_string(),
+            value: "d41d8cd98f00b204e9800998ecf8427e".to_string(),
+        });
+        let report_uid = report.uid;
+
+        let first = index.to_xml().unwrap();
+        assert_eq!(first.as_bytes(), index.to_xml().unwrap().as_bytes());
+        assert!(!first.contains('\r'));
+
+        // 插入顺序不同的同一个索引序列化为相同的字节
+        let mut shuffled = index.clone();
+        reverse_order(&mut shuffled.root_directory);
+        assert_eq!(shuffled.to_xml().unwrap().as_bytes(), first.as_bytes());
+
+        // 解析回来得到同一个索引，再次保存的字节也相同
+        for parsed in [LtfsIndex::from_xml_streaming(&first).unwrap(), LtfsIndex::from_xml(&first).unwrap()] {
+            assert_eq!(parsed.to_xml().unwrap().as_bytes(), first.as_bytes());
+            assert_eq!(parsed.generationnumber, index.generationnumber);
+            assert_eq!(parsed.volumeuuid, index.volumeuuid);
+            assert_eq!(parsed.highestfileuid, index.highestfileuid);
+            let report = parsed.find_file_by_uid(report_uid).unwrap();
+            let keys: Vec<_> = report.extended_attributes.as_ref().unwrap().attributes.iter().map(|a| a.key.as_str()).collect();
+            assert!(keys.windows(2).all(|pair| pair[0] <= pair[1]), "{:?}", keys);
+            assert_eq!(keys.len(), 2);
+        }
+    }
+
+    #[test]
+    fn names_with_control_characters_are_percent_encoded() {
+        let index = LtfsIndex::from_xml_streaming(SPEC_ORDER_INDEX).unwrap();
+        let projects = &index.root_directory.contents.directories[0];
+        assert_eq!(projects.contents.files[1].name, "tab\tand%percent");
+        assert_eq!(projects.contents.files[0].name, "report & notes.txt");
+
+        let mut renamed = index.clone();
+        renamed.root_directory.contents.files[0].name = "line\nbreak".to_string();
+        let xml = renamed.to_xml().unwrap();
+        assert!(xml.contains("<name percentencoded=\"true\">line%0Abreak</name>"), "{}", xml);
+        let reparsed = LtfsIndex::from_xml_streaming(&xml).unwrap();
+        assert_eq!(reparsed.find_file_by_uid(5).unwrap().name, "line\nbreak");
+    }
+
+    #[test]
+    fn special_and_non_utf8_names_survive_both_parsers() {
+        use crate::ltfs_index::name::{name_from_bytes, name_to_bytes};
+
+        let raw_latin1: &[u8] = b"caf\xe9 100%.txt";
+        let names = [
+            "a<b>&c'd\".txt".to_string(),
+            "🎞️ film 🎬.mkv".to_string(),
+            name_from_bytes(raw_latin1),
+        ];
+
+        let mut index = LtfsIndex::from_xml_streaming(SPEC_ORDER_INDEX).unwrap();
+        index.root_directory.contents.files[0].name = names[0].clone();
+        index.root_directory.contents.files[1].name = names[1].clone();
+        index.root_directory.contents.directories[0].contents.files[0].name = names[2].clone();
+        let xml = index.to_xml().unwrap();
+        assert!(xml.contains("<name>a&lt;b&gt;&amp;c"), "{}", xml);
+        assert!(xml.contains("<name>🎞️ film 🎬.mkv</name>"), "{}", xml);
+        assert!(xml.contains("<name percentencoded=\"true\">caf%E9 100%25.txt</name>"), "{}", xml);
+
+        for parsed in [LtfsIndex::from_xml_streaming(&xml).unwrap(), LtfsIndex::from_xml(&xml).unwrap()] {
+            let parsed_names: Vec<_> =
+                [5, 6, 3].iter().map(|&uid| parsed.find_file_by_uid(uid).unwrap().name.clone()).collect();
+            assert_eq!(parsed_names, names);
+        }
+        assert_eq!(name_to_bytes(&names[2]), raw_latin1);
+    }
+}
@@ -0,0 +1,392 @@
+//! LTFS Index Directory Tree Helpers
+//!
+//! This module provides path-based navigation of the LTFS index directory tree.
+//!
+//! All lookups go through [`LtfsIndex::find_path`]: `/` and `\` are both accepted as
+//! separators, repeated/leading/trailing separators are ignored and names are compared
+//! in NFC. Names are case-sensitive by default, as required by the LTFS specification;
+//! [`PathNormalize::CaseInsensitive`] matches the behavior Windows users expect.
+
+use super::types::{Directory, DirectoryContents, File, LtfsIndex};
+use crate::error::{Result, RustLtfsError};
+use tracing::debug;
+
+/// How names are compared when resolving a tape path
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PathNormalize {
+    /// Exact (NFC) comparison, as required by the LTFS specification
+    #[default]
+    CaseSensitive,
+    /// Case-insensitive comparison; an exact match still wins when both exist
+    CaseInsensitive,
+}
+
+impl PathNormalize {
+    fn find<'a, T>(self, items: &'a [T], name: &str, item_name: impl Fn(&T) -> &str) -> Option<&'a T> {
+        items.iter().find(|item| item_name(item) == name).or_else(|| match self {
+            PathNormalize::CaseSensitive => None,
+            PathNormalize::CaseInsensitive => {
+                let name = name.to_lowercase();
+                items.iter().find(|item| item_name(item).to_lowercase() == name)
+            }
+        })
+    }
+}
+
+/// An index entry found by path
+#[derive(Debug, Clone, Copy)]
+pub enum IndexEntry<'a> {
+    Directory(&'a Directory),
+    File(&'a File),
+}
+
+impl Directory {
+    /// Resolve `path` relative to this directory
+    ///
+    /// Returns the entry together with its canonical path (the names as stored in the
+    /// index, `/`-separated with a leading `/`; `/` for this directory itself).
+    pub fn find_path(&self, path: &str, mode: PathNormalize) -> Option<(String, IndexEntry<'_>)> {
+        let path = super::name::normalize_name(path);
+        let parts = LtfsIndex::split_path(&path);
+        let mut current = self;
+        let mut canonical = String::new();
+
+        for (i, part) in parts.iter().enumerate() {
+            if let Some(dir) = mode.find(&current.contents.directories, part, |d| &d.name) {
+                current = dir;
+                canonical.push('/');
+                canonical.push_str(&dir.name);
+                continue;
+            }
+            // 路径的最后一段可以是文件
+            if i + 1 == parts.len() {
+                if let Some(file) = mode.find(&current.contents.files, part, |f| &f.name) {
+                    canonical.push('/');
+                    canonical.push_str(&file.name);
+                    return Some((canonical, IndexEntry::File(file)));
+                }
+            }
+            return None;
+        }
+
+        if canonical.is_empty() {
+            canonical.push('/');
+        }
+        Some((canonical, IndexEntry::Directory(current)))
+    }
+
+    /// Mutable lookup of a directory by canonical path (exact names, as returned by `find_path`)
+    fn directory_mut(&mut self, canonical: &str) -> Option<&mut Directory> {
+        let mut current = self;
+        for part in LtfsIndex::split_path(canonical) {
+            current = current
+                .contents
+                .directories
+                .iter_mut()
+                .find(|dir| dir.name == part)?;
+        }
+        Some(current)
+    }
+
+    /// Record that this directory's entries changed (LTFS `changetime`/`modifytime`)
+    fn touch(&mut self, now: &str) {
+        self.change_time = now.to_string();
+        self.modify_time = now.to_string();
+    }
+}
+
+/// An entry detached from the tree while it is being moved
+enum IndexNode {
+    Directory(Directory),
+    File(File),
+}
+
+/// Split a canonical path into its parent directory path and last name ("/a/b" -> ("/a", "b"))
+fn split_parent(canonical: &str) -> (&str, &str) {
+    match canonical.rsplit_once('/') {
+        Some(("", name)) => ("/", name),
+        Some((parent, name)) => (parent, name),
+        None => ("/", canonical),
+    }
+}
+
+impl LtfsIndex {
+    /// Resolve a tape path from the root directory (see [`Directory::find_path`])
+    pub fn find_path(&self, path: &str, mode: PathNormalize) -> Option<(String, IndexEntry<'_>)> {
+        self.root_directory.find_path(path, mode)
+    }
+
+    /// Split a tape path into its non-empty components ("/a//b/" -> ["a", "b"])
+    ///
+    /// Both `/` and `\` are separators, so lookups and edits resolve a path the same way.
+    pub fn split_path(path: &str) -> Vec<&str> {
+        path.split(['/', '\\']).filter(|part| !part.is_empty()).collect()
+    }
+
+    /// Find a file anywhere in the tree by its `fileuid`
+    pub fn find_file_by_uid(&self, uid: u64) -> Option<&File> {
+        fn find(dir: &Directory, uid: u64) -> Option<&File> {
+            dir.contents
+                .files
+                .iter()
+                .find(|file| file.uid == uid)
+                .or_else(|| dir.contents.directories.iter().find_map(|sub| find(sub, uid)))
+        }
+        find(&self.root_directory, uid)
+    }
+
+    /// Remove the file at `path` (exact names) from its directory
+    ///
+    /// Returns the canonical path of the removed file and the entry itself; `None` when
+    /// `path` does not name a file. Only the index changes, the data blocks stay on tape.
+    pub fn remove_file(&mut self, path: &str) -> Option<(String, File)> {
+        let canonical = match self.find_path(path, PathNormalize::CaseSensitive)? {
+            (canonical, IndexEntry::File(_)) => canonical,
+            (_, IndexEntry::Directory(_)) => return None,
+        };
+        let (parent_path, name) = split_parent(&canonical);
+        let parent = self.root_directory.directory_mut(parent_path)?;
+        let position = parent.contents.files.iter().position(|f| f.name == name)?;
+        let file = parent.contents.files.remove(position);
+        parent.touch(&crate::tape_ops::utils::get_current_ltfs_timestamp());
+
+        debug!("Removed file '{}' (UID {}) from index", canonical, file.uid);
+        Some((canonical, file))
+    }
+
+    /// Move the entry at `src` to the full path `dest`, renaming it to the last component
+    ///
+    /// Only the index changes: the node is reparented, its name and `changetime` are
+    /// updated and both parent directories are touched. `dest`'s parent directory must
+    /// exist and must not already hold an entry with the new name; a directory cannot be
+    /// moved into itself or one of its descendants. Returns the canonical source and
+    /// destination paths.
+    pub fn move_entry(&mut self, src: &str, dest: &str) -> Result<(String, String)> {
+        let (src_path, is_dir) = match self.find_path(src, PathNormalize::CaseSensitive) {
+            Some((path, entry)) => (path, matches!(entry, IndexEntry::Directory(_))),
+            None => {
+                return Err(RustLtfsError::ltfs_index(format!(
+                    "Source not found in index: {}",
+                    src
+                )))
+            }
+        };
+        if src_path == "/" {
+            return Err(RustLtfsError::parameter_validation(
+                "Cannot move the root directory".to_string(),
+            ));
+        }
+
+        let dest = super::name::normalize_name(dest);
+        let mut dest_parts = Self::split_path(&dest);
+        let new_name = dest_parts.pop().ok_or_else(|| {
+            RustLtfsError::parameter_validation(format!("Invalid destination path: '{}'", dest))
+        })?;
+        let dest_parent = match self.find_path(&dest_parts.join("/"), PathNormalize::CaseSensitive) {
+            Some((path, IndexEntry::Directory(dir))) => {
+                let exists = dir.contents.directories.iter().any(|d| d.name == new_name)
+                    || dir.contents.files.iter().any(|f| f.name == new_name);
+                if exists {
+                    return Err(RustLtfsError::parameter_validation(format!(
+                        "Destination already exists: {}",
+                        dest
+                    )));
+                }
+                path
+            }
+            _ => {
+                return Err(RustLtfsError::ltfs_index(format!(
+                    "Destination directory not found in index: /{}",
+                    dest_parts.join("/")
+                )))
+            }
+        };
+        if is_dir && (dest_parent == src_path || dest_parent.starts_with(&format!("{}/", src_path))) {
+            return Err(RustLtfsError::parameter_validation(format!(
+                "Cannot move directory {} into its own subdirectory {}",
+                src_path, dest_parent
+            )));
+        }
+
+        let now = crate::tape_ops::utils::get_current_ltfs_timestamp();
+        let (src_parent, src_name) = split_parent(&src_path);
+        let parent = self
+            .root_directory
+            .directory_mut(src_parent)
+            .ok_or_else(|| RustLtfsError::ltfs_index(format!("Path not found in index: {}", src_path)))?;
+        let mut detached = if is_dir {
+            let position = parent.contents.directories.iter().position(|d| d.name == src_name);
+            position.map(|i| IndexNode::Directory(parent.contents.directories.remove(i)))
+        } else {
+            let position = parent.contents.files.iter().position(|f| f.name == src_name);
+            position.map(|i| IndexNode::File(parent.contents.files.remove(i)))
+        }
+        .ok_or_else(|| RustLtfsError::ltfs_index(format!("Path not found in index: {}", src_path)))?;
+        parent.touch(&now);
+
+        // 目标父目录在源节点摘下之后仍然存在（已排除移动到自身子目录的情况）
+        let target = self
+            .root_directory
+            .directory_mut(&dest_parent)
+            .ok_or_else(|| {
+                RustLtfsError::ltfs_index(format!("Destination directory not found: {}", dest_parent))
+            })?;
+        match &mut detached {
+            IndexNode::Directory(dir) => {
+                dir.name = new_name.to_string();
+                dir.change_time = now.clone();
+            }
+            IndexNode::File(file) => {
+                file.name = new_name.to_string();
+                file.change_time = now.clone();
+            }
+        }
+        match detached {
+            IndexNode::Directory(dir) => target.contents.directories.push(dir),
+            IndexNode::File(file) => target.contents.files.push(file),
+        }
+        target.touch(&now);
+
+        let dest_path = format!("{}/{}", dest_parent.trim_end_matches('/'), new_name);
+        debug!("Moved '{}' to '{}' in index", src_path, dest_path);
+        Ok((src_path, dest_path))
+    }
+
+    /// Walk `path` from the root directory, creating missing intermediate directories,
+    /// and return the leaf directory
+    ///
+    /// New directories get fresh UIDs allocated from `highestfileuid` (never the root UID).
+    pub fn get_or_create_directory(&mut self, path: &str) -> &mut Directory {
+        let path = super::name::normalize_name(path);
+        let parts = Self::split_path(&path);
+        let LtfsIndex {
+            root_directory,
+            highestfileuid,
+            ..
+        } = self;
+        let mut current_dir = root_directory;
+
+        for part in parts {
+            let dir_index = match current_dir
+                .contents
+                .directories
+                .iter()
+                .position(|d| d.name == part)
+            {
+                Some(idx) => idx,
+                None => {
+                    let next_uid = super::uid::next_uid(highestfileuid);
+                    debug!("Creating directory '{}' with UID {}", part, next_uid);
+
+                    let now = crate::tape_ops::utils::get_current_ltfs_timestamp();
+                    current_dir.contents.directories.push(Directory {
+                        name: part.to_string(),
+                        uid: next_uid,
+                        creation_time: now.clone(),
+                        change_time: now.clone(),
+                        modify_time: now.clone(),
+                        access_time: now.clone(),
+                        backup_time: now,
+                        read_only: false,
+                        contents: DirectoryContents::default(),
+                    });
+                    current_dir.contents.directories.len() - 1
+                }
+            };
+            current_dir = &mut current_dir.contents.directories[dir_index];
+        }
+
+        current_dir
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIME: &str = "2024-01-01T00:00:00.000000000Z";
+
+    fn empty_index() -> LtfsIndex {
+        let xml = format!(
+            "<ltfsindex version=\"2.4.0\"><creator>test</creator><volumeuuid>v</volumeuuid>\
+             <generationnumber>1</generationnumber><updatetime>{TIME}</updatetime>\
+             <location><partition>b</partition><startblock>0</startblock></location>\
+             <highestfileuid>1</highestfileuid>\
+             <directory><name></name><fileuid>1</fileuid><creationtime>{TIME}</creationtime>\
+             <changetime>{TIME}</changetime><modifytime>{TIME}</modifytime><accesstime>{TIME}</accesstime>\
+             <backuptime>{TIME}</backuptime><readonly>false</readonly><contents></contents></directory></ltfsindex>"
+        );
+        LtfsIndex::from_xml_streaming(&xml).unwrap()
+    }
+
+    fn file(name: &str, uid: u64) -> File {
+        File {
+            name: name.to_string(),
+            uid,
+            length: 3,
+            creation_time: TIME.to_string(),
+            change_time: TIME.to_string(),
+            modify_time: TIME.to_string(),
+            access_time: TIME.to_string(),
+            backup_time: TIME.to_string(),
+            read_only: false,
+            openforwrite: false,
+            symlink: None,
+            extent_info: Default::default(),
+            extended_attributes: None,
+        }
+    }
+
+    #[test]
+    fn nested_target_creates_directory_chain() {
+        let mut index = empty_index();
+        index.get_or_create_directory("a/b");
+        let uid = index.allocate_uid();
+        index.get_or_create_directory("a/b").contents.files.push(file("c.txt", uid));
+
+        let root = &index.root_directory;
+        assert!(root.contents.files.is_empty());
+        assert_eq!(root.contents.directories.len(), 1);
+        let a = &root.contents.directories[0];
+        assert_eq!(a.name, "a");
+        assert!(a.contents.files.is_empty());
+        assert_eq!(a.contents.directories.len(), 1);
+        let b = &a.contents.directories[0];
+        assert_eq!(b.name, "b");
+        assert!(b.contents.directories.is_empty());
+        assert_eq!(b.contents.files.len(), 1);
+        assert_eq!(b.contents.files[0].name, "c.txt");
+
+        // 目录和文件的UID各不相同，且都来自highestfileuid
+        assert_eq!((a.uid, b.uid, uid), (2, 3, 4));
+        assert_eq!(index.highestfileuid, Some(4));
+
+        // 再次写入同一路径不会重复创建目录
+        index.get_or_create_directory("/a/b/");
+        assert_eq!(index.root_directory.contents.directories.len(), 1);
+        assert!(matches!(
+            index.find_path("a/b/c.txt", PathNormalize::CaseSensitive),
+            Some((path, IndexEntry::File(_))) if path == "/a/b/c.txt"
+        ));
+    }
+
+    #[test]
+    fn backslash_paths_resolve_like_slash_paths() {
+        let mut index = empty_index();
+        index.get_or_create_directory("a\\b");
+        index.get_or_create_directory("\\a/b\\");
+
+        // 创建的是 a/b 两级目录，而不是名为 "a\b" 的单个目录
+        let root = &index.root_directory;
+        assert_eq!(root.contents.directories.len(), 1);
+        assert_eq!(root.contents.directories[0].name, "a");
+        assert_eq!(root.contents.directories[0].contents.directories.len(), 1);
+        assert_eq!(root.contents.directories[0].contents.directories[0].name, "b");
+
+        assert!(matches!(
+            index.find_path("a\\b", PathNormalize::CaseSensitive),
+            Some((path, IndexEntry::Directory(_))) if path == "/a/b"
+        ));
+        assert_eq!(LtfsIndex::split_path("\\a//b\\"), vec!["a", "b"]);
+    }
+}
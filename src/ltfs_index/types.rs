@@ -1,6 +1,8 @@
 //! LTFS Index Type Definitions
 //!
-//! This module contains all data structure definitions for LTFS indexes.
+//! This module contains all data structure definitions for LTFS indexes. Fields are
+//! declared in the element order of the LTFS Format Specification 2.4.0, which is the
+//! order `to_xml` writes them in.
 
 use serde::{Deserialize, Serialize};
 
@@ -9,6 +11,57 @@ fn default_volumelockstate() -> String {
     "unlocked".to_string()
 }
 
+/// `<allowpolicyupdate>` is required by the spec; an unknown value is written as false
+fn serialize_allowpolicyupdate<S>(value: &Option<bool>, serializer: S) -> std::result::Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    serializer.serialize_bool(value.unwrap_or(false))
+}
+
+/// `<name>` 元素：含XML中不能出现的字符时带 `percentencoded="true"` 属性
+#[derive(Serialize, Deserialize)]
+struct NameXml {
+    #[serde(rename = "@percentencoded", default, skip_serializing_if = "Option::is_none")]
+    percent_encoded: Option<String>,
+    #[serde(rename = "$text", default)]
+    text: String,
+}
+
+fn serialize_name<S>(name: &str, serializer: S) -> std::result::Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    match super::name::percent_encode_name(name) {
+        Some(text) => NameXml {
+            percent_encoded: Some("true".to_string()),
+            text,
+        }
+        .serialize(serializer),
+        None => serializer.serialize_str(name),
+    }
+}
+
+fn deserialize_name<'de, D>(deserializer: D) -> std::result::Result<String, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let name = NameXml::deserialize(deserializer)?;
+    Ok(match name.percent_encoded.as_deref() {
+        Some("true") => super::name::percent_decode_name(&name.text),
+        _ => name.text,
+    })
+}
+
+/// Deserialize `<symlink>`: an empty element means the file is not a symlink
+fn deserialize_symlink<'de, D>(deserializer: D) -> std::result::Result<Option<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let target: Option<String> = Option::deserialize(deserializer)?;
+    Ok(target.filter(|t| !t.is_empty()))
+}
+
 /// LTFS Index structure based on LTFS specification
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename = "ltfsindex")]
@@ -20,13 +73,13 @@ pub struct LtfsIndex {
     pub generationnumber: u64,
     pub updatetime: String,
     pub location: Location,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub previousgenerationlocation: Option<Location>,
-    #[serde(default)]
+    #[serde(default, serialize_with = "serialize_allowpolicyupdate")]
     pub allowpolicyupdate: Option<bool>,
     #[serde(default = "default_volumelockstate")]
     pub volumelockstate: String,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub highestfileuid: Option<u64>,
     #[serde(rename = "directory")]
     pub root_directory: Directory,
@@ -41,9 +94,10 @@ pub struct Location {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Directory {
+    #[serde(serialize_with = "serialize_name", deserialize_with = "deserialize_name")]
     pub name: String,
-    #[serde(rename = "fileuid")]
-    pub uid: u64,
+    #[serde(rename = "readonly")]
+    pub read_only: bool,
     #[serde(rename = "creationtime")]
     pub creation_time: String,
     #[serde(rename = "changetime")]
@@ -54,8 +108,8 @@ pub struct Directory {
     pub access_time: String,
     #[serde(rename = "backuptime")]
     pub backup_time: String,
-    #[serde(rename = "readonly")]
-    pub read_only: bool,
+    #[serde(rename = "fileuid")]
+    pub uid: u64,
     #[serde(rename = "contents")]
     pub contents: DirectoryContents,
 }
@@ -74,12 +128,20 @@ pub struct ExtentInfo {
     pub extents: Vec<FileExtent>,
 }
 
+impl ExtentInfo {
+    pub fn is_empty(&self) -> bool {
+        self.extents.is_empty()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct File {
+    #[serde(serialize_with = "serialize_name", deserialize_with = "deserialize_name")]
     pub name: String,
-    #[serde(rename = "fileuid")]
-    pub uid: u64,
     pub length: u64,
+    #[serde(rename = "readonly")]
+    pub read_only: bool,
+    pub openforwrite: bool,
     #[serde(rename = "creationtime")]
     pub creation_time: String,
     #[serde(rename = "changetime")]
@@ -90,28 +152,34 @@ pub struct File {
     pub access_time: String,
     #[serde(rename = "backuptime")]
     pub backup_time: String,
-    #[serde(rename = "readonly")]
-    pub read_only: bool,
-    pub openforwrite: bool,
-    #[serde(rename = "symlink", default)]
+    #[serde(rename = "fileuid")]
+    pub uid: u64,
+    /// Link target for symlinks (no data extents); omitted from XML for regular files
+    #[serde(
+        rename = "symlink",
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_symlink"
+    )]
     pub symlink: Option<String>,
-    #[serde(rename = "extentinfo", default)]
-    pub extent_info: ExtentInfo,
-    #[serde(rename = "extendedattributes", default)]
+    #[serde(rename = "extendedattributes", default, skip_serializing_if = "Option::is_none")]
     pub extended_attributes: Option<ExtendedAttributes>,
+    /// Omitted from XML for empty files and symlinks
+    #[serde(rename = "extentinfo", default, skip_serializing_if = "ExtentInfo::is_empty")]
+    pub extent_info: ExtentInfo,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileExtent {
+    #[serde(rename = "fileoffset")]
+    pub file_offset: u64,
     pub partition: String,
     #[serde(rename = "startblock", alias = "startBlock", alias = "start_block", alias = "block")]
     pub start_block: u64,
-    #[serde(rename = "bytecount")]
-    pub byte_count: u64,
-    #[serde(rename = "fileoffset")]
-    pub file_offset: u64,
     #[serde(rename = "byteoffset")]
     pub byte_offset: u64,
+    #[serde(rename = "bytecount")]
+    pub byte_count: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
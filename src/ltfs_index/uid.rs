@@ -0,0 +1,107 @@
+//! LTFS Index UID Allocation
+//!
+//! This module handles file/directory UID allocation and reserved UIDs.
+//!
+//! UID convention (LTFS specification): UID 0 is never assigned, the root
+//! directory always owns UID 1, and every other file or directory receives a
+//! unique UID greater than 1. `highestfileuid` tracks the largest UID in use.
+
+use super::types::{Directory, LtfsIndex};
+use tracing::{debug, warn};
+
+/// UID reserved for the root directory
+pub const ROOT_DIRECTORY_UID: u64 = 1;
+
+/// Allocate the next UID from a `highestfileuid` counter, never reissuing the root UID
+pub(crate) fn next_uid(highestfileuid: &mut Option<u64>) -> u64 {
+    let new_uid = highestfileuid.unwrap_or(0).max(ROOT_DIRECTORY_UID) + 1;
+    *highestfileuid = Some(new_uid);
+    new_uid
+}
+
+impl LtfsIndex {
+    /// Allocate a new UID for a file or directory
+    pub fn allocate_uid(&mut self) -> u64 {
+        next_uid(&mut self.highestfileuid)
+    }
+
+    /// Repair reserved UID violations found in an existing index
+    ///
+    /// Some indexes in the wild reuse the root directory UID (or UID 0) for regular
+    /// entries. Such entries are given fresh UIDs so lookups by UID never resolve to
+    /// the root. `highestfileuid` is also raised to cover every UID present.
+    /// Returns the number of entries that were renumbered.
+    pub(super) fn repair_reserved_uids(&mut self) -> usize {
+        let root_uid = self.root_directory.uid;
+
+        let mut highest = self.highestfileuid.unwrap_or(0).max(root_uid);
+        Self::collect_highest_uid(&self.root_directory, &mut highest);
+        if self.highestfileuid != Some(highest) {
+            debug!(
+                "Raising highestfileuid from {:?} to {}",
+                self.highestfileuid, highest
+            );
+            self.highestfileuid = Some(highest);
+        }
+
+        let LtfsIndex {
+            root_directory,
+            highestfileuid,
+            ..
+        } = self;
+        let repaired = Self::renumber_reserved_uids(root_directory, root_uid, highestfileuid);
+
+        if repaired > 0 {
+            warn!(
+                "Renumbered {} index entries that reused reserved UID {} or 0",
+                repaired, root_uid
+            );
+        }
+        repaired
+    }
+
+    fn collect_highest_uid(directory: &Directory, highest: &mut u64) {
+        for file in &directory.contents.files {
+            *highest = (*highest).max(file.uid);
+        }
+        for subdir in &directory.contents.directories {
+            *highest = (*highest).max(subdir.uid);
+            Self::collect_highest_uid(subdir, highest);
+        }
+    }
+
+    fn renumber_reserved_uids(
+        directory: &mut Directory,
+        root_uid: u64,
+        highestfileuid: &mut Option<u64>,
+    ) -> usize {
+        let mut repaired = 0;
+
+        for file in &mut directory.contents.files {
+            if file.uid == root_uid || file.uid == 0 {
+                let new_uid = next_uid(highestfileuid);
+                warn!(
+                    "File '{}' reuses reserved UID {}, assigning UID {}",
+                    file.name, file.uid, new_uid
+                );
+                file.uid = new_uid;
+                repaired += 1;
+            }
+        }
+
+        for subdir in &mut directory.contents.directories {
+            if subdir.uid == root_uid || subdir.uid == 0 {
+                let new_uid = next_uid(highestfileuid);
+                warn!(
+                    "Directory '{}' reuses reserved UID {}, assigning UID {}",
+                    subdir.name, subdir.uid, new_uid
+                );
+                subdir.uid = new_uid;
+                repaired += 1;
+            }
+            repaired += Self::renumber_reserved_uids(subdir, root_uid, highestfileuid);
+        }
+
+        repaired
+    }
+}
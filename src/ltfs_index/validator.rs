@@ -6,6 +6,73 @@ use crate::error::Result;
 use super::types::*;
 use tracing::{debug, warn};
 
+/// Limits for `LtfsIndex::check_consistency`; `None` skips the corresponding check
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConsistencyLimits {
+    /// Volume block size, used to check extent byte offsets
+    pub block_size: Option<u64>,
+    /// Number of blocks in partition a and b, used to check extent start blocks
+    pub partition_blocks: [Option<u64>; 2],
+}
+
+/// Problem found by the consistency check; the index is still usable
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationWarning {
+    /// Extent partition is neither "a" nor "b"
+    InvalidPartition { path: String, partition: String },
+    /// Extent starts beyond the end of its partition
+    StartBlockOutOfRange { path: String, start_block: u64, partition_blocks: u64 },
+    /// Extent byte offset does not fall inside its first block
+    ByteOffsetOutOfBlock { path: String, byte_offset: u64, block_size: u64 },
+    ZeroLengthExtent { path: String },
+    /// Extents are not ordered by file offset or overlap
+    OverlappingExtents { path: String, file_offset: u64 },
+    /// Sum of extent byte counts differs from the file length
+    LengthMismatch { path: String, length: u64, extent_total: u64 },
+    DuplicateUid { uid: u64, path: String, first_path: String },
+    /// `highestfileuid` is lower than a UID in use (new files would collide)
+    HighestUidTooLow { highestfileuid: u64, max_uid: u64 },
+}
+
+impl std::fmt::Display for ValidationWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidPartition { path, partition } => {
+                write!(f, "{}: invalid extent partition '{}'", path, partition)
+            }
+            Self::StartBlockOutOfRange { path, start_block, partition_blocks } => write!(
+                f,
+                "{}: extent start block {} is beyond the partition ({} blocks)",
+                path, start_block, partition_blocks
+            ),
+            Self::ByteOffsetOutOfBlock { path, byte_offset, block_size } => write!(
+                f,
+                "{}: extent byte offset {} is not inside a {} byte block",
+                path, byte_offset, block_size
+            ),
+            Self::ZeroLengthExtent { path } => write!(f, "{}: zero-length extent", path),
+            Self::OverlappingExtents { path, file_offset } => write!(
+                f,
+                "{}: extent at file offset {} overlaps or is out of order",
+                path, file_offset
+            ),
+            Self::LengthMismatch { path, length, extent_total } => write!(
+                f,
+                "{}: length {} does not match extent total {}",
+                path, length, extent_total
+            ),
+            Self::DuplicateUid { uid, path, first_path } => {
+                write!(f, "{}: UID {} already used by {}", path, uid, first_path)
+            }
+            Self::HighestUidTooLow { highestfileuid, max_uid } => write!(
+                f,
+                "highestfileuid {} is lower than the highest UID in use ({})",
+                highestfileuid, max_uid
+            ),
+        }
+    }
+}
+
 impl LtfsIndex {
     /// Validate XML structure before parsing
     pub(super) fn validate_xml_structure(xml_content: &str) -> Result<()> {
@@ -98,17 +165,10 @@ impl LtfsIndex {
             ));
         }
 
-        // Validate root directory
-        if !index.root_directory.name.is_empty() {
-            return Err(crate::error::RustLtfsError::parse(
-                "Root directory must have empty name",
-            ));
-        }
+        // 根目录名是卷名（可以为空），不参与路径解析
 
         // Enhanced validations
         Self::validate_directory_structure(&index.root_directory)?;
-        Self::validate_file_extents(&index.root_directory)?;
-        Self::validate_uid_uniqueness(index)?;
         Self::validate_timestamps(&index.root_directory)?;
 
         debug!("Parsed index validation passed");
@@ -175,133 +235,143 @@ impl LtfsIndex {
         Ok(())
     }
 
-    /// Validate file extents for consistency
-    fn validate_file_extents(directory: &Directory) -> Result<()> {
-        debug!("Validating file extents in directory: {}", directory.name);
-
-        for file in &directory.contents.files {
-            // Skip symlinks
-            if file.symlink.is_some() {
-                continue;
+    /// Non-fatal consistency pass over a parsed index
+    ///
+    /// Damaged extents and UIDs are reported instead of rejecting the whole index,
+    /// so a slightly damaged index can still be inspected before extraction.
+    pub fn check_consistency(&self, limits: &ConsistencyLimits) -> Vec<ValidationWarning> {
+        let mut warnings = Vec::new();
+        let mut seen_uids = std::collections::HashMap::new();
+        seen_uids.insert(self.root_directory.uid, "/".to_string());
+
+        Self::check_directory(&self.root_directory, "", limits, &mut seen_uids, &mut warnings);
+
+        if let Some(max_uid) = seen_uids.keys().max().copied() {
+            let highest = self.highestfileuid.unwrap_or(0);
+            if highest < max_uid {
+                warnings.push(ValidationWarning::HighestUidTooLow {
+                    highestfileuid: highest,
+                    max_uid,
+                });
             }
+        }
 
-            // Check if file has extents when it should
-            if file.length > 0 && file.extent_info.extents.is_empty() {
-                return Err(crate::error::RustLtfsError::parse(format!(
-                    "File '{}' has size {} but no extents",
-                    file.name, file.length
-                )));
-            }
-
-            if file.length == 0 && !file.extent_info.extents.is_empty() {
-                warn!("File '{}' has zero size but contains extents", file.name);
-            }
-
-            // Validate each extent
-            let mut total_extent_size = 0u64;
-            let mut last_file_offset = 0u64;
-
-            for extent in &file.extent_info.extents {
-                // Check partition validity
-                if extent.partition != "a"
-                    && extent.partition != "b"
-                    && extent.partition != "A"
-                    && extent.partition != "B"
-                {
-                    return Err(crate::error::RustLtfsError::parse(format!(
-                        "Invalid partition '{}' in file '{}'",
-                        extent.partition, file.name
-                    )));
-                }
-
-                // Check extent size consistency
-                if extent.byte_count == 0 {
-                    return Err(crate::error::RustLtfsError::parse(format!(
-                        "Zero-size extent in file '{}'",
-                        file.name
-                    )));
-                }
-
-                // Check file offset ordering
-                if extent.file_offset < last_file_offset {
-                    return Err(crate::error::RustLtfsError::parse(format!(
-                        "Extents not ordered by file offset in file '{}'",
-                        file.name
-                    )));
-                }
+        if !warnings.is_empty() {
+            debug!("Index consistency check found {} warnings", warnings.len());
+        }
+        warnings
+    }
 
-                last_file_offset = extent.file_offset + extent.byte_count;
-                total_extent_size += extent.byte_count;
-            }
+    fn check_directory(
+        directory: &Directory,
+        dir_path: &str,
+        limits: &ConsistencyLimits,
+        seen_uids: &mut std::collections::HashMap<u64, String>,
+        warnings: &mut Vec<ValidationWarning>,
+    ) {
+        for file in &directory.contents.files {
+            let path = format!("{}/{}", dir_path, file.name);
+            Self::check_uid(file.uid, &path, seen_uids, warnings);
 
-            // Check total size consistency
-            if total_extent_size != file.length {
-                return Err(crate::error::RustLtfsError::parse(format!(
-                    "File '{}': declared size {} doesn't match extent total {}",
-                    file.name, file.length, total_extent_size
-                )));
+            // Symlinks carry no data extents
+            if file.symlink.is_some() {
+                continue;
             }
+            Self::check_file_extents(file, &path, limits, warnings);
         }
 
-        // Recursively validate subdirectories
         for subdir in &directory.contents.directories {
-            Self::validate_file_extents(subdir)?;
+            let path = format!("{}/{}", dir_path, subdir.name);
+            Self::check_uid(subdir.uid, &path, seen_uids, warnings);
+            Self::check_directory(subdir, &path, limits, seen_uids, warnings);
         }
-
-        Ok(())
     }
 
-    /// Validate UID uniqueness across the entire index
-    fn validate_uid_uniqueness(index: &LtfsIndex) -> Result<()> {
-        debug!("Validating UID uniqueness");
-
-        let mut used_uids = std::collections::HashSet::new();
-
-        // Add root directory UID
-        if !used_uids.insert(index.root_directory.uid) {
-            return Err(crate::error::RustLtfsError::parse(format!(
-                "Duplicate UID {} found",
-                index.root_directory.uid
-            )));
+    fn check_uid(
+        uid: u64,
+        path: &str,
+        seen_uids: &mut std::collections::HashMap<u64, String>,
+        warnings: &mut Vec<ValidationWarning>,
+    ) {
+        if let Some(first) = seen_uids.get(&uid) {
+            warnings.push(ValidationWarning::DuplicateUid {
+                uid,
+                path: path.to_string(),
+                first_path: first.clone(),
+            });
+        } else {
+            seen_uids.insert(uid, path.to_string());
         }
-
-        Self::collect_and_validate_uids(&index.root_directory, &mut used_uids)?;
-
-        debug!(
-            "UID validation passed, found {} unique UIDs",
-            used_uids.len()
-        );
-        Ok(())
     }
 
-    /// Recursively collect and validate UIDs
-    fn collect_and_validate_uids(
-        directory: &Directory,
-        used_uids: &mut std::collections::HashSet<u64>,
-    ) -> Result<()> {
-        // Check subdirectories
-        for subdir in &directory.contents.directories {
-            if !used_uids.insert(subdir.uid) {
-                return Err(crate::error::RustLtfsError::parse(format!(
-                    "Duplicate UID {} found in directory '{}'",
-                    subdir.uid, subdir.name
-                )));
+    fn check_file_extents(
+        file: &File,
+        path: &str,
+        limits: &ConsistencyLimits,
+        warnings: &mut Vec<ValidationWarning>,
+    ) {
+        let mut total_extent_size = 0u64;
+        let mut last_file_offset = 0u64;
+
+        for extent in &file.extent_info.extents {
+            let partition = extent.partition.to_ascii_lowercase();
+            let partition_blocks = match partition.as_str() {
+                "a" => limits.partition_blocks[0],
+                "b" => limits.partition_blocks[1],
+                _ => {
+                    warnings.push(ValidationWarning::InvalidPartition {
+                        path: path.to_string(),
+                        partition: extent.partition.clone(),
+                    });
+                    None
+                }
+            };
+
+            if let Some(blocks) = partition_blocks {
+                if extent.start_block >= blocks {
+                    warnings.push(ValidationWarning::StartBlockOutOfRange {
+                        path: path.to_string(),
+                        start_block: extent.start_block,
+                        partition_blocks: blocks,
+                    });
+                }
             }
 
-            Self::collect_and_validate_uids(subdir, used_uids)?;
-        }
+            if extent.byte_count == 0 {
+                warnings.push(ValidationWarning::ZeroLengthExtent {
+                    path: path.to_string(),
+                });
+            }
 
-        // Check files
-        for file in &directory.contents.files {
-            if !used_uids.insert(file.uid) {
-                return Err(crate::error::RustLtfsError::parse(format!(
-                    "Duplicate UID {} found in file '{}'",
-                    file.uid, file.name
-                )));
+            // byteoffset is the offset into the first block and must lie inside it
+            if let Some(block_size) = limits.block_size.filter(|&size| size > 0) {
+                if extent.byte_offset >= block_size {
+                    warnings.push(ValidationWarning::ByteOffsetOutOfBlock {
+                        path: path.to_string(),
+                        byte_offset: extent.byte_offset,
+                        block_size,
+                    });
+                }
+            }
+
+            if extent.file_offset < last_file_offset {
+                warnings.push(ValidationWarning::OverlappingExtents {
+                    path: path.to_string(),
+                    file_offset: extent.file_offset,
+                });
             }
+
+            last_file_offset = extent.file_offset.saturating_add(extent.byte_count);
+            total_extent_size = total_extent_size.saturating_add(extent.byte_count);
         }
 
-        Ok(())
+        if total_extent_size != file.length {
+            warnings.push(ValidationWarning::LengthMismatch {
+                path: path.to_string(),
+                length: file.length,
+                extent_total: total_extent_size,
+            });
+        }
     }
 
     /// Validate timestamps format and consistency
@@ -6,9 +6,11 @@ mod ltfs_index;
 mod scsi;
 mod tape_ops;
 mod utils;
+#[cfg(test)]
+mod virtual_tape;
 
-use crate::cli::{Cli, Commands};
-use crate::error::Result;
+use crate::cli::{Cli, Commands, DiagCommands};
+use crate::error::{Result, RustLtfsError};
 use tracing::{debug, error, info};
 
 #[tokio::main]
@@ -17,6 +19,7 @@ async fn main() -> Result<()> {
 
     // Initialize logging system
     logger::init(args.verbose)?;
+    args.scsi_timeouts().install();
 
     debug!("RustLTFS CLI starting");
 
@@ -27,6 +30,18 @@ async fn main() -> Result<()> {
         }
         Err(e) => {
             error!("Operation failed: {}", e);
+            match &e {
+                RustLtfsError::BlankTape(_) => {
+                    eprintln!("💡 The tape is blank; format it with `rustltfs format` first")
+                }
+                RustLtfsError::NonLtfsFormat(_) => eprintln!(
+                    "💡 The tape is not LTFS formatted; `rustltfs format` makes it usable but erases its contents"
+                ),
+                RustLtfsError::CorruptedIndex(_) => eprintln!(
+                    "💡 The index cannot be parsed; `rustltfs recover-index` reads the copies in the data partition"
+                ),
+                _ => {}
+            }
             std::process::exit(1);
         }
     }
@@ -39,11 +54,221 @@ async fn run(args: Cli) -> Result<()> {
             device,
             destination,
             verify,
+            append_only_verify,
+            verify_only,
+            dry_run,
+            no_compression,
+            lbp,
+            no_sparse,
+            index_interval,
+            index_interval_minutes,
             progress,
-        } => commands::write::execute(source, device, destination, verify, progress).await,
+            journal,
+            resume,
+            exclude,
+            exclude_from,
+            max_depth,
+            follow_symlinks,
+            max_queued_files,
+            span_manifest,
+            block_size,
+            force,
+            ignore_cleaning,
+        } => {
+            let mut excluded_patterns = exclude;
+            if let Some(ref path) = exclude_from {
+                excluded_patterns.extend(tape_ops::WriteOptions::load_exclude_patterns(path)?);
+            }
 
-        Commands::Read { device, source } => commands::read::execute(device, source).await,
+            let defaults = tape_ops::WriteOptions::default();
+            let max_queued_files = max_queued_files.map_or(defaults.max_queued_files, |n| n as usize);
 
-        Commands::Space { device, detailed } => commands::space::execute(device, detailed).await,
+            if verify_only {
+                commands::write::execute_verify_only(source, device, destination).await
+            } else if dry_run {
+                let write_options = tape_ops::WriteOptions {
+                    excluded_patterns,
+                    max_depth,
+                    follow_symlinks,
+                    max_queued_files,
+                    ..defaults
+                };
+                commands::write::execute_dry_run(source, device, destination, write_options).await
+            } else {
+                let journal = journal.map(|path| (path, resume));
+                let write_options = tape_ops::WriteOptions {
+                    verify,
+                    verify_index: append_only_verify,
+                    compression: !no_compression,
+                    logical_block_protection: lbp,
+                    sparse: !no_sparse,
+                    index_write_interval: index_interval.unwrap_or(defaults.index_write_interval),
+                    index_write_interval_time: index_interval_minutes
+                        .map(|minutes| std::time::Duration::from_secs(minutes * 60)),
+                    excluded_patterns,
+                    max_depth,
+                    follow_symlinks,
+                    max_queued_files,
+                    block_size: block_size.unwrap_or(defaults.block_size),
+                    block_size_explicit: block_size.is_some(),
+                    force_block_size: force,
+                    ignore_cleaning,
+                    ..defaults
+                };
+                commands::write::execute(
+                    source,
+                    device,
+                    destination,
+                    write_options,
+                    progress,
+                    journal,
+                    span_manifest,
+                )
+                .await
+            }
+        }
+
+        Commands::Read {
+            device,
+            source,
+            extract,
+            progress,
+            span_manifest,
+            recursive,
+            long,
+            export,
+            format,
+            fields,
+            ignore_case,
+            uid,
+            offset,
+            length,
+            preview,
+            preview_bytes,
+            hex,
+        } => match (uid, extract, export) {
+            (Some(uid), dest, _) => {
+                commands::read::execute_range(device, uid, offset, length, dest).await
+            }
+            (None, Some(dest), _) => {
+                commands::read::execute_extract(device, source, dest, progress, ignore_case, span_manifest)
+                    .await
+            }
+            (None, None, Some(output)) => {
+                commands::read::execute_export(device, source, output, format, fields, ignore_case)
+                    .await
+            }
+            (None, None, None) if preview || hex => {
+                commands::read::execute_preview(device, source, preview_bytes, hex, ignore_case).await
+            }
+            (None, None, None) if recursive => {
+                commands::read::execute_recursive(device, source, ignore_case, long).await
+            }
+            (None, None, None) => commands::read::execute(device, source, ignore_case).await,
+        },
+
+        Commands::Space {
+            device,
+            detailed,
+            to_filemark,
+            to_block,
+            to_eod,
+            partition,
+        } => {
+            let target = match (to_filemark, to_block, to_eod) {
+                (Some(n), _, _) => Some(tape_ops::PositionTarget::FileMark(n)),
+                (_, Some(n), _) => Some(tape_ops::PositionTarget::Block(n)),
+                (_, _, true) => Some(tape_ops::PositionTarget::EndOfData),
+                _ => None,
+            };
+            match target {
+                Some(target) => commands::space::execute_position(device, partition, target).await,
+                None => commands::space::execute(device, detailed).await,
+            }
+        }
+
+        Commands::Cat {
+            device,
+            path,
+            ignore_case,
+        } => commands::cat::execute(device, path, ignore_case).await,
+        Commands::RestoreIndex {
+            device,
+            schema_file,
+        } => commands::restore_index::execute(device, schema_file).await,
+
+        Commands::RecoverIndex { device, output } => {
+            commands::recover_index::execute(device, output).await
+        }
+        Commands::IndexDiff { old, new } => commands::index_diff::execute(old, new).await,
+        Commands::DumpPartition {
+            device,
+            partition,
+            output,
+            progress,
+        } => commands::dump_partition::execute(device, partition, output, progress).await,
+        Commands::Scavenge {
+            device,
+            partition,
+            start_block,
+            output_dir,
+            progress,
+        } => {
+            commands::scavenge::execute(device, partition, start_block, output_dir, progress)
+                .await
+        }
+        Commands::Eject { device } => commands::eject::execute(device).await,
+        Commands::ListDrives { barcode } => commands::list_drives::execute(barcode).await,
+        Commands::Rm { device, path } => commands::rm::execute(device, path).await,
+        Commands::Mv {
+            device,
+            source,
+            destination,
+        } => commands::mv::execute(device, source, destination).await,
+        Commands::Erase {
+            device,
+            confirm_barcode,
+            long,
+            immediate,
+        } => commands::erase::execute(device, confirm_barcode, long, immediate).await,
+        Commands::Format {
+            device,
+            barcode,
+            volume_label,
+            single_partition,
+            p0_size,
+            p1_size,
+            block_size,
+            encryption_key_file,
+        } => {
+            commands::format::execute(
+                device,
+                barcode,
+                volume_label.unwrap_or_default(),
+                single_partition,
+                p0_size,
+                p1_size,
+                block_size,
+                encryption_key_file,
+            )
+            .await
+        }
+        Commands::SetBarcode {
+            device,
+            barcode,
+            volume_label,
+        } => commands::set_barcode::execute(device, barcode, volume_label).await,
+        Commands::Info { device, medium } => commands::info::execute(device, medium).await,
+        Commands::Verify {
+            device,
+            index,
+            extents,
+        } => commands::verify::execute(device, index, extents).await,
+        Commands::Check { device, samples } => commands::check::execute(device, samples).await,
+        Commands::Diag { command } => match command {
+            DiagCommands::Seek { device, samples } => {
+                commands::diag::execute_seek(device, samples as usize).await
+            }
+        },
     }
 }
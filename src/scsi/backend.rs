@@ -0,0 +1,488 @@
+//! Tape Backend Abstraction
+//!
+//! `TapeOperations` talks to the drive only through the `TapeBackend` trait, so the
+//! LTFS logic above it can run against something other than a real SCSI device.
+//! `ScsiInterface` implements every method; other backends implement the positioning
+//! and block I/O primitives and inherit defaults for the rest, where drive-specific
+//! commands (MAM, log pages, encryption, formatting) report `Unsupported`.
+
+use crate::error::{Result, RustLtfsError};
+use super::types::{LocateDestType, MamAttribute, MamCapacity, RawBlock};
+use super::{
+    DataCompressionPage, DriveInfo, DriveType, EncryptionStatus, LbpMethod, MediaType, RetryPolicy, ScsiInterface,
+    SenseData, SpaceType, TapePosition, VolumeCoherencyInfo,
+};
+use std::time::Duration;
+
+fn unsupported<T>(command: &str) -> Result<T> {
+    Err(RustLtfsError::unsupported(format!(
+        "{} is not supported by this tape backend",
+        command
+    )))
+}
+
+/// 磁带驱动器操作接口：定位、块读写和FileMark由实现提供，其余命令有默认实现
+pub trait TapeBackend: Send + Sync {
+    // ---- 定位 ----
+
+    fn read_position(&self) -> Result<TapePosition>;
+
+    /// LOCATE到 `partition` 中的目标，返回驱动器报告的ASC/ASCQ（成功为0）
+    fn locate(
+        &self,
+        block_address: u64,
+        partition: u8,
+        dest_type: LocateDestType,
+        current_partition: Option<u8>,
+    ) -> Result<u16>;
+
+    /// SPACE：count为负数时反向移动
+    fn space(&self, space_type: SpaceType, count: i32) -> Result<()>;
+
+    fn read_position_extended(&self) -> Result<TapePosition> {
+        self.read_position()
+    }
+
+    fn locate_block(&self, partition: u8, block_number: u64) -> Result<()> {
+        self.locate(block_number, partition, LocateDestType::Block, None)
+            .map(|_| ())
+    }
+
+    fn locate_to_eod(&self, partition: u8) -> Result<()> {
+        self.locate(0, partition, LocateDestType::EOD, None).map(|_| ())
+    }
+
+    /// 定位到分区开头起第 `filemark_number` 个FileMark之后
+    fn locate_to_filemark(&self, filemark_number: u64, partition: u8) -> Result<()> {
+        self.locate(0, partition, LocateDestType::Block, None)?;
+        self.space(SpaceType::FileMarks, filemark_number as i32)
+    }
+
+    /// SCSI REWIND (0x01)：回到分区0开头并复位驱动器的文件/块计数器，不能用LOCATE 0代替
+    fn rewind(&self) -> Result<()>;
+
+    // ---- 块读写 ----
+
+    /// 读取 `block_count` 个块到 `buffer`；遇到FileMark时返回已读取的块数
+    fn read_blocks(&self, block_count: u32, buffer: &mut [u8]) -> Result<u32>;
+
+    /// 读取一个可变长度块，报告FileMark和EOD
+    fn read_raw_block(&self, buffer: &mut [u8]) -> Result<RawBlock>;
+
+    /// 写入 `block_count` 个块；到达早期警告区时返回 `RustLtfsError::EndOfMedia`
+    fn write_blocks(&self, block_count: u32, buffer: &[u8]) -> Result<u32>;
+
+    fn write_filemarks(&self, count: u32) -> Result<()>;
+
+    fn read_blocks_with_timeout(&self, block_count: u32, buffer: &mut [u8], _timeout: u32) -> Result<u32> {
+        self.read_blocks(block_count, buffer)
+    }
+
+    /// 早期警告区内仍须写入的块（索引、标签）
+    fn write_blocks_allow_early_warning(&self, block_count: u32, buffer: &[u8]) -> Result<u32> {
+        match self.write_blocks(block_count, buffer) {
+            Err(e) if e.is_end_of_media() => Ok(block_count),
+            result => result,
+        }
+    }
+
+    fn synchronize_buffer(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// 跳过当前位置的FileMark；不在FileMark上时退回读取的块并返回false
+    fn read_file_mark(&self) -> Result<bool> {
+        let mut buffer = vec![0u8; super::block_sizes::LTO_BLOCK_SIZE as usize];
+        match self.read_raw_block(&mut buffer)? {
+            RawBlock::Data(_) => {
+                let position = self.read_position()?;
+                self.locate_block(position.partition, position.block_number.saturating_sub(1))?;
+                Ok(false)
+            }
+            RawBlock::FileMark | RawBlock::EndOfData => Ok(true),
+        }
+    }
+
+    /// 读取数据块直到FileMark（或EOD），返回拼接后的数据
+    fn read_to_file_mark(&self, block_size_limit: u32) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        let mut buffer = vec![0u8; block_size_limit as usize];
+        while let RawBlock::Data(len) = self.read_raw_block(&mut buffer)? {
+            data.extend_from_slice(&buffer[..len]);
+        }
+        Ok(data)
+    }
+
+    // ---- 设备状态 ----
+
+    fn open_device(&mut self, _device_path: &str) -> Result<()> {
+        Ok(())
+    }
+
+    /// TEST UNIT READY，返回sense数据（为空表示就绪）
+    fn test_unit_ready(&self) -> Result<Vec<u8>> {
+        Ok(Vec::new())
+    }
+
+    fn parse_sense_data(&self, sense_data: &[u8]) -> String {
+        let sense = SenseData::parse(sense_data);
+        format!(
+            "Sense key 0x{:02X}, ASC 0x{:02X}, ASCQ 0x{:02X}",
+            sense.sense_key.code(),
+            sense.asc,
+            sense.ascq
+        )
+    }
+
+    fn drive_type(&self) -> DriveType {
+        DriveType::Standard
+    }
+
+    fn set_drive_type(&mut self, _drive_type: DriveType) {}
+
+    fn read_retry(&self) -> RetryPolicy {
+        RetryPolicy::new(0, Duration::ZERO)
+    }
+
+    fn test_unit_ready_retry(&self) -> RetryPolicy {
+        RetryPolicy::new(0, Duration::ZERO)
+    }
+
+    fn uses_locate16(&self) -> bool {
+        true
+    }
+
+    fn locate_fallback_count(&self) -> u64 {
+        0
+    }
+
+    fn last_throughput_mbps(&self) -> Option<f64> {
+        None
+    }
+
+    fn set_block_size(&self, _block_size: u32) -> Result<()> {
+        Ok(())
+    }
+
+    fn read_block_limits(&self) -> Result<(u32, u32)> {
+        unsupported("READ BLOCK LIMITS")
+    }
+
+    fn inquiry(&self) -> Result<DriveInfo> {
+        unsupported("INQUIRY")
+    }
+
+    fn check_media_status(&self) -> Result<MediaType> {
+        unsupported("Media status detection")
+    }
+
+    fn load_unload(&self, _load: bool, _eoj: bool, _immediate: bool) -> Result<()> {
+        unsupported("LOAD/UNLOAD")
+    }
+
+    // ---- 格式化与擦除 ----
+
+    fn format_medium(&self, _partition: bool) -> Result<()> {
+        unsupported("FORMAT MEDIUM")
+    }
+
+    fn erase(&self, _long: bool, _immediate: bool) -> Result<()> {
+        unsupported("ERASE")
+    }
+
+    fn set_partition_page(&self, _additional_partitions: u8, _partition_sizes: &[u16]) -> Result<()> {
+        unsupported("MODE SELECT (partition page)")
+    }
+
+    fn mode_sense_partition_page_0x11(&self) -> Result<Vec<u8>> {
+        unsupported("MODE SENSE (partition page)")
+    }
+
+    // ---- 压缩、加密与逻辑块保护 ----
+
+    fn get_compression(&self) -> Result<DataCompressionPage> {
+        unsupported("MODE SENSE (data compression page)")
+    }
+
+    fn set_compression(&self, _enabled: bool) -> Result<()> {
+        unsupported("MODE SELECT (data compression page)")
+    }
+
+    fn set_data_encryption(&self, _key: &[u8], _algorithm_index: u8) -> Result<()> {
+        unsupported("SECURITY PROTOCOL OUT")
+    }
+
+    fn get_encryption_status(&self) -> Result<EncryptionStatus> {
+        unsupported("SECURITY PROTOCOL IN")
+    }
+
+    fn get_logical_block_protection(&self) -> Result<LbpMethod> {
+        Ok(LbpMethod::None)
+    }
+
+    fn set_logical_block_protection(&self, method: LbpMethod) -> Result<()> {
+        if method == LbpMethod::None {
+            Ok(())
+        } else {
+            unsupported("Logical Block Protection")
+        }
+    }
+
+    // ---- MAM属性与日志页 ----
+
+    fn read_barcode(&self) -> Result<String> {
+        unsupported("READ ATTRIBUTE (barcode)")
+    }
+
+    fn read_mam_capacity(&self, _partition: u8) -> Result<MamCapacity> {
+        unsupported("READ ATTRIBUTE (capacity)")
+    }
+
+    fn read_mam_attribute_list(&self) -> Result<Vec<u16>> {
+        unsupported("READ ATTRIBUTE (attribute list)")
+    }
+
+    fn read_mam_attribute(&self, _attribute_id: u16) -> Result<MamAttribute> {
+        unsupported("READ ATTRIBUTE")
+    }
+
+    fn write_attribute(&self, _partition: u8, _attribute_id: u16, _format: u8, _value: &[u8]) -> Result<()> {
+        unsupported("WRITE ATTRIBUTE")
+    }
+
+    fn read_volume_change_reference(&self, _partition: u8) -> Result<u64> {
+        unsupported("READ ATTRIBUTE (volume change reference)")
+    }
+
+    fn read_volume_coherency_info(&self, _partition: u8) -> Result<VolumeCoherencyInfo> {
+        unsupported("READ ATTRIBUTE (volume coherency information)")
+    }
+
+    fn write_volume_coherency_info(&self, _partition: u8, _vci: &VolumeCoherencyInfo) -> Result<()> {
+        unsupported("WRITE ATTRIBUTE (volume coherency information)")
+    }
+
+    fn log_sense(&self, _page_code: u8, _page_control: u8) -> Result<Vec<u8>> {
+        unsupported("LOG SENSE")
+    }
+
+    fn log_sense_subpage(&self, _page_code: u8, _subpage: u8) -> Result<Vec<u8>> {
+        unsupported("LOG SENSE")
+    }
+}
+
+impl TapeBackend for ScsiInterface {
+    fn read_position(&self) -> Result<TapePosition> {
+        ScsiInterface::read_position(self)
+    }
+
+    fn locate(
+        &self,
+        block_address: u64,
+        partition: u8,
+        dest_type: LocateDestType,
+        current_partition: Option<u8>,
+    ) -> Result<u16> {
+        ScsiInterface::locate(self, block_address, partition, dest_type, current_partition)
+    }
+
+    fn space(&self, space_type: SpaceType, count: i32) -> Result<()> {
+        ScsiInterface::space(self, space_type, count)
+    }
+
+    fn read_position_extended(&self) -> Result<TapePosition> {
+        ScsiInterface::read_position_extended(self)
+    }
+
+    fn locate_block(&self, partition: u8, block_number: u64) -> Result<()> {
+        ScsiInterface::locate_block(self, partition, block_number)
+    }
+
+    fn locate_to_eod(&self, partition: u8) -> Result<()> {
+        ScsiInterface::locate_to_eod(self, partition)
+    }
+
+    fn locate_to_filemark(&self, filemark_number: u64, partition: u8) -> Result<()> {
+        ScsiInterface::locate_to_filemark(self, filemark_number, partition)
+    }
+
+    fn rewind(&self) -> Result<()> {
+        ScsiInterface::rewind(self)
+    }
+
+    fn read_blocks(&self, block_count: u32, buffer: &mut [u8]) -> Result<u32> {
+        ScsiInterface::read_blocks(self, block_count, buffer)
+    }
+
+    fn read_raw_block(&self, buffer: &mut [u8]) -> Result<RawBlock> {
+        ScsiInterface::read_raw_block(self, buffer)
+    }
+
+    fn write_blocks(&self, block_count: u32, buffer: &[u8]) -> Result<u32> {
+        ScsiInterface::write_blocks(self, block_count, buffer)
+    }
+
+    fn write_filemarks(&self, count: u32) -> Result<()> {
+        ScsiInterface::write_filemarks(self, count)
+    }
+
+    fn read_blocks_with_timeout(&self, block_count: u32, buffer: &mut [u8], timeout: u32) -> Result<u32> {
+        ScsiInterface::read_blocks_with_timeout(self, block_count, buffer, timeout)
+    }
+
+    fn write_blocks_allow_early_warning(&self, block_count: u32, buffer: &[u8]) -> Result<u32> {
+        ScsiInterface::write_blocks_allow_early_warning(self, block_count, buffer)
+    }
+
+    fn synchronize_buffer(&self) -> Result<()> {
+        ScsiInterface::synchronize_buffer(self)
+    }
+
+    fn read_file_mark(&self) -> Result<bool> {
+        ScsiInterface::read_file_mark(self)
+    }
+
+    fn read_to_file_mark(&self, block_size_limit: u32) -> Result<Vec<u8>> {
+        ScsiInterface::read_to_file_mark(self, block_size_limit)
+    }
+
+    fn open_device(&mut self, device_path: &str) -> Result<()> {
+        ScsiInterface::open_device(self, device_path)
+    }
+
+    fn test_unit_ready(&self) -> Result<Vec<u8>> {
+        ScsiInterface::test_unit_ready(self)
+    }
+
+    fn parse_sense_data(&self, sense_data: &[u8]) -> String {
+        ScsiInterface::parse_sense_data(self, sense_data)
+    }
+
+    fn drive_type(&self) -> DriveType {
+        self.drive_type
+    }
+
+    fn set_drive_type(&mut self, drive_type: DriveType) {
+        self.drive_type = drive_type;
+    }
+
+    fn read_retry(&self) -> RetryPolicy {
+        *ScsiInterface::read_retry(self)
+    }
+
+    fn test_unit_ready_retry(&self) -> RetryPolicy {
+        *ScsiInterface::test_unit_ready_retry(self)
+    }
+
+    fn uses_locate16(&self) -> bool {
+        ScsiInterface::uses_locate16(self)
+    }
+
+    fn locate_fallback_count(&self) -> u64 {
+        ScsiInterface::locate_fallback_count(self)
+    }
+
+    fn last_throughput_mbps(&self) -> Option<f64> {
+        ScsiInterface::last_throughput_mbps(self)
+    }
+
+    fn set_block_size(&self, block_size: u32) -> Result<()> {
+        ScsiInterface::set_block_size(self, block_size)
+    }
+
+    fn read_block_limits(&self) -> Result<(u32, u32)> {
+        ScsiInterface::read_block_limits(self)
+    }
+
+    fn inquiry(&self) -> Result<DriveInfo> {
+        ScsiInterface::inquiry(self)
+    }
+
+    fn check_media_status(&self) -> Result<MediaType> {
+        ScsiInterface::check_media_status(self)
+    }
+
+    fn load_unload(&self, load: bool, eoj: bool, immediate: bool) -> Result<()> {
+        ScsiInterface::load_unload(self, load, eoj, immediate)
+    }
+
+    fn format_medium(&self, partition: bool) -> Result<()> {
+        ScsiInterface::format_medium(self, partition)
+    }
+
+    fn erase(&self, long: bool, immediate: bool) -> Result<()> {
+        ScsiInterface::erase(self, long, immediate)
+    }
+
+    fn set_partition_page(&self, additional_partitions: u8, partition_sizes: &[u16]) -> Result<()> {
+        ScsiInterface::set_partition_page(self, additional_partitions, partition_sizes)
+    }
+
+    fn mode_sense_partition_page_0x11(&self) -> Result<Vec<u8>> {
+        ScsiInterface::mode_sense_partition_page_0x11(self)
+    }
+
+    fn get_compression(&self) -> Result<DataCompressionPage> {
+        ScsiInterface::get_compression(self)
+    }
+
+    fn set_compression(&self, enabled: bool) -> Result<()> {
+        ScsiInterface::set_compression(self, enabled)
+    }
+
+    fn set_data_encryption(&self, key: &[u8], algorithm_index: u8) -> Result<()> {
+        ScsiInterface::set_data_encryption(self, key, algorithm_index)
+    }
+
+    fn get_encryption_status(&self) -> Result<EncryptionStatus> {
+        ScsiInterface::get_encryption_status(self)
+    }
+
+    fn get_logical_block_protection(&self) -> Result<LbpMethod> {
+        ScsiInterface::get_logical_block_protection(self)
+    }
+
+    fn set_logical_block_protection(&self, method: LbpMethod) -> Result<()> {
+        ScsiInterface::set_logical_block_protection(self, method)
+    }
+
+    fn read_barcode(&self) -> Result<String> {
+        ScsiInterface::read_barcode(self)
+    }
+
+    fn read_mam_capacity(&self, partition: u8) -> Result<MamCapacity> {
+        ScsiInterface::read_mam_capacity(self, partition)
+    }
+
+    fn read_mam_attribute_list(&self) -> Result<Vec<u16>> {
+        ScsiInterface::read_mam_attribute_list(self)
+    }
+
+    fn read_mam_attribute(&self, attribute_id: u16) -> Result<MamAttribute> {
+        ScsiInterface::read_mam_attribute(self, attribute_id)
+    }
+
+    fn write_attribute(&self, partition: u8, attribute_id: u16, format: u8, value: &[u8]) -> Result<()> {
+        ScsiInterface::write_attribute(self, partition, attribute_id, format, value)
+    }
+
+    fn read_volume_change_reference(&self, partition: u8) -> Result<u64> {
+        ScsiInterface::read_volume_change_reference(self, partition)
+    }
+
+    fn read_volume_coherency_info(&self, partition: u8) -> Result<VolumeCoherencyInfo> {
+        ScsiInterface::read_volume_coherency_info(self, partition)
+    }
+
+    fn write_volume_coherency_info(&self, partition: u8, vci: &VolumeCoherencyInfo) -> Result<()> {
+        ScsiInterface::write_volume_coherency_info(self, partition, vci)
+    }
+
+    fn log_sense(&self, page_code: u8, page_control: u8) -> Result<Vec<u8>> {
+        ScsiInterface::log_sense(self, page_code, page_control)
+    }
+
+    fn log_sense_subpage(&self, page_code: u8, subpage: u8) -> Result<Vec<u8>> {
+        ScsiInterface::log_sense_subpage(self, page_code, subpage)
+    }
+}
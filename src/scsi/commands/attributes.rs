@@ -0,0 +1,367 @@
+//! SCSI Medium Auxiliary Memory Commands
+//!
+//! This module contains READ ATTRIBUTE for reading cartridge memory (MAM) attributes
+//! and WRITE ATTRIBUTE for the Volume Coherency Information written with each index.
+
+use crate::error::Result;
+use tracing::debug;
+
+use super::super::{
+    ScsiInterface,
+    constants::*,
+    types::{
+        MamAttribute, MamAttributeFormat, MamCapacity, VolumeCoherencyInfo,
+        MAM_VOLUME_CHANGE_REFERENCE, MAM_VOLUME_COHERENCY_INFORMATION,
+    },
+};
+
+/// MAM attribute: REMAINING CAPACITY IN PARTITION (MiB)
+const MAM_REMAINING_CAPACITY: u16 = 0x0000;
+/// MAM attribute: MAXIMUM CAPACITY IN PARTITION (MiB)
+const MAM_MAXIMUM_CAPACITY: u16 = 0x0001;
+/// MAM attribute: BARCODE (ASCII, 32 bytes)
+const MAM_BARCODE: u16 = 0x0806;
+
+/// READ ATTRIBUTE service action: ATTRIBUTE VALUES
+const SA_ATTRIBUTE_VALUES: u8 = 0x00;
+/// READ ATTRIBUTE service action: ATTRIBUTE LIST
+const SA_ATTRIBUTE_LIST: u8 = 0x01;
+
+/// Allocation length for attribute value reads
+const ATTRIBUTE_VALUES_ALLOC_LEN: usize = 1024;
+/// Allocation length for the attribute list (2 bytes per attribute ID)
+const ATTRIBUTE_LIST_ALLOC_LEN: usize = 4096;
+
+impl ScsiInterface {
+    /// READ ATTRIBUTE command (service action 0x00 - ATTRIBUTE VALUES)
+    /// LTFSCopyGUI: {&H8C, 0, 0, 0, 0, 0, 0, PartitionNumber, ID >> 8, ID And &HFF, AllocLen (4 bytes), 0, 0}
+    /// Returns the raw parameter data starting with the 4-byte available data length
+    pub fn read_attribute(&self, partition: u8, first_attribute_id: u16) -> Result<Vec<u8>> {
+        self.read_attribute_data(
+            SA_ATTRIBUTE_VALUES,
+            partition,
+            first_attribute_id,
+            ATTRIBUTE_VALUES_ALLOC_LEN,
+        )
+    }
+
+    /// READ ATTRIBUTE (service action 0x01 - ATTRIBUTE LIST)
+    /// Returns the IDs of all attributes present in cartridge memory
+    pub fn read_mam_attribute_list(&self) -> Result<Vec<u16>> {
+        let data = self.read_attribute_data(SA_ATTRIBUTE_LIST, 0, 0, ATTRIBUTE_LIST_ALLOC_LEN)?;
+
+        let ids: Vec<u16> = data
+            .get(4..)
+            .unwrap_or_default()
+            .chunks_exact(2)
+            .map(|id| u16::from_be_bytes([id[0], id[1]]))
+            .collect();
+
+        debug!("MAM attribute list: {} attributes", ids.len());
+        Ok(ids)
+    }
+
+    /// Read a single MAM attribute (partition 0) with its format and read-only flag
+    pub fn read_mam_attribute(&self, attribute_id: u16) -> Result<MamAttribute> {
+        let data = self.read_attribute(0, attribute_id)?;
+
+        parse_mam_attribute(&data, attribute_id).ok_or_else(|| {
+            crate::error::RustLtfsError::scsi(format!(
+                "MAM attribute 0x{:04X} not present in READ ATTRIBUTE data",
+                attribute_id
+            ))
+        })
+    }
+
+    /// Read the cartridge barcode from MAM attribute 0x0806 (trailing padding removed)
+    pub fn read_barcode(&self) -> Result<String> {
+        let attribute = self.read_mam_attribute(MAM_BARCODE)?;
+        let barcode = String::from_utf8_lossy(&attribute.value)
+            .trim_matches(|c: char| c == '\0' || c.is_whitespace())
+            .to_string();
+        debug!("Cartridge barcode: '{}'", barcode);
+        Ok(barcode)
+    }
+
+    /// Read the volume change reference of a partition (MAM attribute 0x0009)
+    pub fn read_volume_change_reference(&self, partition: u8) -> Result<u64> {
+        let data = self.read_attribute(partition, MAM_VOLUME_CHANGE_REFERENCE)?;
+        find_attribute_u64(&data, MAM_VOLUME_CHANGE_REFERENCE).ok_or_else(|| {
+            crate::error::RustLtfsError::scsi(format!(
+                "Volume change reference missing from MAM data for partition {}",
+                partition
+            ))
+        })
+    }
+
+    /// Read the Volume Coherency Information of a partition (MAM attribute 0x080C)
+    pub fn read_volume_coherency_info(&self, partition: u8) -> Result<VolumeCoherencyInfo> {
+        let data = self.read_attribute(partition, MAM_VOLUME_COHERENCY_INFORMATION)?;
+        find_attribute(&data, MAM_VOLUME_COHERENCY_INFORMATION)
+            .and_then(|(_, value)| VolumeCoherencyInfo::from_attribute_value(value))
+            .ok_or_else(|| {
+                crate::error::RustLtfsError::scsi(format!(
+                    "No LTFS volume coherency information for partition {}",
+                    partition
+                ))
+            })
+    }
+
+    /// Write the Volume Coherency Information of a partition (对应LTFSCopyGUI WriteVCI)
+    pub fn write_volume_coherency_info(&self, partition: u8, vci: &VolumeCoherencyInfo) -> Result<()> {
+        debug!(
+            "Writing VCI for partition {}: generation {}, block {}, VCR {}",
+            partition, vci.generation, vci.block, vci.volume_change_reference
+        );
+        self.write_attribute(
+            partition,
+            MAM_VOLUME_COHERENCY_INFORMATION,
+            0x00, // binary
+            &vci.to_attribute_value(),
+        )
+    }
+
+    /// WRITE ATTRIBUTE command for a single attribute (write-through cache set)
+    /// Parameter list: data length (4 bytes), then ID (2 bytes), format (1 byte), length (2 bytes), value
+    pub fn write_attribute(&self, partition: u8, attribute_id: u16, format: u8, value: &[u8]) -> Result<()> {
+        debug!(
+            "Executing WRITE ATTRIBUTE: partition={}, attribute=0x{:04X}, {} bytes",
+            partition,
+            attribute_id,
+            value.len()
+        );
+
+        let value_len = u16::try_from(value.len()).map_err(|_| {
+            crate::error::RustLtfsError::parameter_validation(format!(
+                "MAM attribute value of {} bytes is too long",
+                value.len()
+            ))
+        })?;
+
+        #[cfg(any(windows, target_os = "linux"))]
+        {
+            let mut param_list = Vec::with_capacity(9 + value.len());
+            param_list.extend_from_slice(&(5 + value_len as u32).to_be_bytes());
+            param_list.extend_from_slice(&attribute_id.to_be_bytes());
+            param_list.push(format & 0x03);
+            param_list.extend_from_slice(&value_len.to_be_bytes());
+            param_list.extend_from_slice(value);
+
+            let mut cdb = [0u8; 16];
+            cdb[0] = scsi_commands::WRITE_ATTRIBUTE;
+            cdb[1] = 0x01; // WTC: write through to cartridge memory
+            cdb[7] = partition;
+            cdb[10..14].copy_from_slice(&(param_list.len() as u32).to_be_bytes());
+
+            let mut sense_buffer = [0u8; SENSE_INFO_LEN];
+            let result = self.scsi_io_control(
+                &cdb,
+                Some(&mut param_list),
+                SCSI_IOCTL_DATA_OUT,
+                self.timeouts.mode_sense,
+                Some(&mut sense_buffer),
+            )?;
+
+            if !result {
+                return Err(crate::error::RustLtfsError::scsi(format!(
+                    "WRITE ATTRIBUTE 0x{:04X} failed: {}",
+                    attribute_id,
+                    self.parse_sense_data(&sense_buffer)
+                )));
+            }
+            Ok(())
+        }
+
+        #[cfg(not(any(windows, target_os = "linux")))]
+        {
+            let _ = (partition, attribute_id, format, value_len);
+            Err(crate::error::RustLtfsError::unsupported(
+                "Non-Windows platform",
+            ))
+        }
+    }
+
+    /// Issue READ ATTRIBUTE with the given service action
+    fn read_attribute_data(
+        &self,
+        service_action: u8,
+        partition: u8,
+        first_attribute_id: u16,
+        allocation_length: usize,
+    ) -> Result<Vec<u8>> {
+        debug!(
+            "Executing READ ATTRIBUTE: service_action=0x{:02X}, partition={}, first_attribute=0x{:04X}",
+            service_action, partition, first_attribute_id
+        );
+
+        #[cfg(any(windows, target_os = "linux"))]
+        {
+            let mut data_buffer = vec![0u8; allocation_length];
+            let allocation_length = data_buffer.len() as u32;
+
+            let mut cdb = [0u8; 16];
+            cdb[0] = scsi_commands::READ_ATTRIBUTE;
+            cdb[1] = service_action & 0x1F;
+            cdb[7] = partition;
+            cdb[8] = (first_attribute_id >> 8) as u8;
+            cdb[9] = (first_attribute_id & 0xFF) as u8;
+            cdb[10..14].copy_from_slice(&allocation_length.to_be_bytes());
+
+            let mut sense_buffer = [0u8; SENSE_INFO_LEN];
+            let result = self.scsi_io_control(
+                &cdb,
+                Some(&mut data_buffer),
+                SCSI_IOCTL_DATA_IN,
+                self.timeouts.mode_sense,
+                Some(&mut sense_buffer),
+            )?;
+
+            if !result {
+                return Err(crate::error::RustLtfsError::scsi(format!(
+                    "READ ATTRIBUTE failed: {}",
+                    self.parse_sense_data(&sense_buffer)
+                )));
+            }
+
+            let available = u32::from_be_bytes([
+                data_buffer[0],
+                data_buffer[1],
+                data_buffer[2],
+                data_buffer[3],
+            ]) as usize;
+            data_buffer.truncate(std::cmp::min(4 + available, data_buffer.len()));
+            Ok(data_buffer)
+        }
+
+        #[cfg(not(any(windows, target_os = "linux")))]
+        {
+            let _ = (service_action, partition, first_attribute_id, allocation_length);
+            Err(crate::error::RustLtfsError::unsupported(
+                "Non-Windows platform",
+            ))
+        }
+    }
+
+    /// Read remaining/maximum capacity of a partition from cartridge memory (MAM attributes 0x0000/0x0001)
+    /// Values are converted from MiB to bytes
+    pub fn read_mam_capacity(&self, partition: u8) -> Result<MamCapacity> {
+        let data = self.read_attribute(partition, MAM_REMAINING_CAPACITY)?;
+
+        let remaining = find_attribute_u64(&data, MAM_REMAINING_CAPACITY);
+        let maximum = find_attribute_u64(&data, MAM_MAXIMUM_CAPACITY);
+
+        match (remaining, maximum) {
+            (Some(remaining), Some(maximum)) => {
+                debug!(
+                    "MAM capacity for partition {}: remaining {} MiB, maximum {} MiB",
+                    partition, remaining, maximum
+                );
+                Ok(MamCapacity {
+                    remaining: remaining * 1024 * 1024,
+                    maximum: maximum * 1024 * 1024,
+                })
+            }
+            _ => Err(crate::error::RustLtfsError::scsi(format!(
+                "Capacity attributes missing from MAM data for partition {}",
+                partition
+            ))),
+        }
+    }
+}
+
+/// Find an attribute in READ ATTRIBUTE data, returning its format byte and value
+/// Attribute format: ID (2 bytes), format (1 byte), length (2 bytes), value
+fn find_attribute(data: &[u8], attribute_id: u16) -> Option<(u8, &[u8])> {
+    let mut offset = 4;
+    while offset + 5 <= data.len() {
+        let id = u16::from_be_bytes([data[offset], data[offset + 1]]);
+        let length = u16::from_be_bytes([data[offset + 3], data[offset + 4]]) as usize;
+        let value_start = offset + 5;
+        if value_start + length > data.len() {
+            return None;
+        }
+
+        if id == attribute_id {
+            return Some((data[offset + 2], &data[value_start..value_start + length]));
+        }
+
+        offset = value_start + length;
+    }
+    None
+}
+
+/// Find an attribute in READ ATTRIBUTE data with its format and read-only flag
+fn parse_mam_attribute(data: &[u8], attribute_id: u16) -> Option<MamAttribute> {
+    find_attribute(data, attribute_id).map(|(format_byte, value)| MamAttribute {
+        id: attribute_id,
+        format: MamAttributeFormat::from_format_byte(format_byte),
+        read_only: format_byte & 0x80 != 0,
+        value: value.to_vec(),
+    })
+}
+
+/// Find an attribute in READ ATTRIBUTE data and decode its value as a big-endian integer
+fn find_attribute_u64(data: &[u8], attribute_id: u16) -> Option<u64> {
+    find_attribute(data, attribute_id).map(|(_, value)| {
+        value
+            .iter()
+            .take(8)
+            .fold(0u64, |acc, &byte| (acc << 8) | byte as u64)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scsi::types::{MAM_APPLICATION_NAME, MAM_APPLICATION_VENDOR, MAM_MEDIUM_MANUFACTURE_DATE};
+
+    /// READ ATTRIBUTE (ATTRIBUTE VALUES) data of an LTFS cartridge: Load Count,
+    /// Medium Manufacturer, Manufacture Date, Application Vendor/Name and Barcode
+    const LTFS_CARTRIDGE_ATTRIBUTES: &[u8] = b"\x00\x00\x00\x7E\
+        \x00\x03\x80\x00\x08\x00\x00\x00\x00\x00\x00\x00\x2A\
+        \x04\x00\x81\x00\x08FUJIFILM\
+        \x04\x06\x81\x00\x0820190315\
+        \x08\x00\x01\x00\x08IBM     \
+        \x08\x01\x01\x00\x20LTFS                            \
+        \x08\x06\x01\x00\x20VT0001L8                        ";
+
+    fn attribute(id: u16) -> MamAttribute {
+        parse_mam_attribute(LTFS_CARTRIDGE_ATTRIBUTES, id).unwrap()
+    }
+
+    #[test]
+    fn parses_and_decodes_cartridge_attributes() {
+        assert_eq!(LTFS_CARTRIDGE_ATTRIBUTES.len(), 130);
+
+        let load_count = attribute(0x0003);
+        assert_eq!(load_count.format, MamAttributeFormat::Binary);
+        assert!(load_count.read_only);
+        assert_eq!(load_count.decoded_value(), "42");
+
+        assert_eq!(attribute(0x0400).decoded_value(), "FUJIFILM");
+        assert_eq!(attribute(MAM_MEDIUM_MANUFACTURE_DATE).decoded_value(), "2019-03-15");
+
+        let vendor = attribute(MAM_APPLICATION_VENDOR);
+        assert_eq!(vendor.format, MamAttributeFormat::Ascii);
+        assert!(!vendor.read_only);
+        assert_eq!(vendor.decoded_value(), "IBM");
+        assert_eq!(attribute(MAM_APPLICATION_NAME).decoded_value(), "LTFS");
+        assert_eq!(attribute(MAM_BARCODE).decoded_value(), "VT0001L8");
+
+        assert!(parse_mam_attribute(LTFS_CARTRIDGE_ATTRIBUTES, 0x0401).is_none());
+        // 长度字段超出数据时不越界
+        assert!(parse_mam_attribute(&LTFS_CARTRIDGE_ATTRIBUTES[..40], MAM_APPLICATION_VENDOR).is_none());
+    }
+
+    #[test]
+    fn non_ascii_manufacture_date_is_not_split() {
+        // 0xFF 有损解码为3字节的U+FFFD，整体恰为8字节但不能按字节位置切分
+        let date = MamAttribute {
+            id: MAM_MEDIUM_MANUFACTURE_DATE,
+            format: MamAttributeFormat::Text,
+            read_only: true,
+            value: b"2019\xFF1".to_vec(),
+        };
+        assert_eq!(date.decoded_value(), "2019\u{FFFD}1");
+    }
+}
@@ -5,14 +5,17 @@
 use crate::error::Result;
 use tracing::debug;
 
-use super::super::{ScsiInterface, constants::*};
+use super::super::{DriveInfo, ScsiInterface, constants::*};
+
+/// INQUIRY VPD page: Unit Serial Number
+const VPD_UNIT_SERIAL_NUMBER: u8 = 0x80;
 
 impl ScsiInterface {
     /// Test Unit Ready command - check if device is ready
     pub fn test_unit_ready(&self) -> Result<Vec<u8>> {
         debug!("Executing Test Unit Ready command");
 
-        #[cfg(windows)]
+        #[cfg(any(windows, target_os = "linux"))]
         {
             let mut cdb = [0u8; 6];
             cdb[0] = scsi_commands::TEST_UNIT_READY;
@@ -24,7 +27,7 @@ impl ScsiInterface {
                 &cdb,
                 None,
                 SCSI_IOCTL_DATA_UNSPECIFIED,
-                30, // 30 second timeout for Test Unit Ready
+                self.timeouts.inquiry,
                 Some(&mut sense_buffer),
             )?;
 
@@ -37,7 +40,7 @@ impl ScsiInterface {
             }
         }
 
-        #[cfg(not(windows))]
+        #[cfg(not(any(windows, target_os = "linux")))]
         {
             Err(crate::error::RustLtfsError::unsupported(
                 "Non-Windows platform",
@@ -45,15 +48,104 @@ impl ScsiInterface {
         }
     }
 
+    /// INQUIRY command - vendor, product, revision and serial number of the drive
+    /// 对应LTFSCopyGUI的TapeUtils.Inquiry；序列号来自VPD页0x80，不支持时为None
+    pub fn inquiry(&self) -> Result<DriveInfo> {
+        debug!("Executing INQUIRY command");
+
+        let standard = self.inquiry_data(false, 0x00, 96)?;
+        if standard.len() < 36 {
+            return Err(crate::error::RustLtfsError::scsi(format!(
+                "INQUIRY data too short: {} bytes",
+                standard.len()
+            )));
+        }
+
+        let ascii_field = |bytes: &[u8]| String::from_utf8_lossy(bytes).trim().to_string();
+
+        // Bytes 8-15: Vendor ID, 16-31: Product ID, 32-35: Product Revision
+        let mut info = DriveInfo {
+            vendor_id: ascii_field(&standard[8..16]),
+            product_id: ascii_field(&standard[16..32]),
+            product_revision: ascii_field(&standard[32..36]),
+            serial_number: None,
+        };
+
+        match self.inquiry_data(true, VPD_UNIT_SERIAL_NUMBER, 64) {
+            Ok(vpd) if vpd.len() >= 4 => {
+                let length = std::cmp::min(vpd[3] as usize, vpd.len() - 4);
+                let serial = ascii_field(&vpd[4..4 + length]);
+                if !serial.is_empty() {
+                    info.serial_number = Some(serial);
+                }
+            }
+            Ok(_) => debug!("Unit Serial Number VPD page too short"),
+            Err(e) => debug!("Unit Serial Number VPD page not available: {}", e),
+        }
+
+        debug!("INQUIRY result: {:?}", info);
+        Ok(info)
+    }
+
+    /// Issue INQUIRY for standard data (evpd = false) or a VPD page
+    fn inquiry_data(&self, evpd: bool, page_code: u8, allocation_length: u8) -> Result<Vec<u8>> {
+        #[cfg(any(windows, target_os = "linux"))]
+        {
+            let mut cdb = [0u8; 6];
+            cdb[0] = scsi_commands::INQUIRY;
+            cdb[1] = if evpd { 0x01 } else { 0x00 };
+            cdb[2] = page_code;
+            cdb[4] = allocation_length;
+
+            let mut buffer = vec![0u8; allocation_length as usize];
+            let mut sense_buffer = [0u8; SENSE_INFO_LEN];
+
+            let result = self.scsi_io_control(
+                &cdb,
+                Some(&mut buffer),
+                SCSI_IOCTL_DATA_IN,
+                self.timeouts.inquiry,
+                Some(&mut sense_buffer),
+            )?;
+
+            if result {
+                Ok(buffer)
+            } else {
+                let sense_info = self.parse_sense_data(&sense_buffer);
+                Err(crate::error::RustLtfsError::scsi(format!(
+                    "INQUIRY (page 0x{:02X}) failed: {}",
+                    page_code, sense_info
+                )))
+            }
+        }
+
+        #[cfg(not(any(windows, target_os = "linux")))]
+        {
+            let _ = (evpd, page_code, allocation_length);
+            Err(crate::error::RustLtfsError::unsupported(
+                "Non-Windows platform",
+            ))
+        }
+    }
+
     /// LOG SENSE command (based on LTFSCopyGUI implementation)
     /// LTFSCopyGUI: {&H4D, 0, PageControl << 6 Or PageCode, 0, 0, 0, 0, (PageLen + 4) >> 8 And &HFF, (PageLen + 4) And &HFF, 0}
     pub fn log_sense(&self, page_code: u8, page_control: u8) -> Result<Vec<u8>> {
+        self.log_sense_page(page_code, 0x00, page_control)
+    }
+
+    /// LOG SENSE for a page/subpage, returning current cumulative values (page control 01b)
+    pub fn log_sense_subpage(&self, page_code: u8, subpage: u8) -> Result<Vec<u8>> {
+        self.log_sense_page(page_code, subpage, 0x01)
+    }
+
+    fn log_sense_page(&self, page_code: u8, subpage: u8, page_control: u8) -> Result<Vec<u8>> {
         debug!(
-            "Executing LOG SENSE command: page_code=0x{:02X}, page_control=0x{:02X}",
-            page_code, page_control
+            "Executing LOG SENSE command: page_code=0x{:02X}, subpage=0x{:02X}, page_control=0x{:02X}",
+            page_code, subpage, page_control
         );
 
-        #[cfg(windows)]
+        #[cfg(any(windows, target_os = "linux"))]
         {
             // Step 1: Get header to determine page length
             let mut header_cdb = [0u8; 10];
@@ -62,7 +154,7 @@ impl ScsiInterface {
             header_cdb[0] = scsi_commands::LOG_SENSE;
             header_cdb[1] = 0x00;
             header_cdb[2] = (page_control << 6) | page_code;
-            header_cdb[3] = 0x00;
+            header_cdb[3] = subpage;
             header_cdb[4] = 0x00;
             header_cdb[5] = 0x00;
             header_cdb[6] = 0x00;
@@ -74,7 +166,7 @@ impl ScsiInterface {
                 &header_cdb,
                 Some(&mut header_buffer),
                 SCSI_IOCTL_DATA_IN,
-                30,
+                self.timeouts.mode_sense,
                 None,
             )?;
 
@@ -93,7 +185,7 @@ impl ScsiInterface {
             full_cdb[0] = scsi_commands::LOG_SENSE;
             full_cdb[1] = 0x00;
             full_cdb[2] = (page_control << 6) | page_code;
-            full_cdb[3] = 0x00;
+            full_cdb[3] = subpage;
             full_cdb[4] = 0x00;
             full_cdb[5] = 0x00;
             full_cdb[6] = 0x00;
@@ -105,7 +197,7 @@ impl ScsiInterface {
                 &full_cdb,
                 Some(&mut full_buffer),
                 SCSI_IOCTL_DATA_IN,
-                30,
+                self.timeouts.mode_sense,
                 None,
             )?;
 
@@ -122,9 +214,9 @@ impl ScsiInterface {
             }
         }
 
-        #[cfg(not(windows))]
+        #[cfg(not(any(windows, target_os = "linux")))]
         {
-            let _ = (page_code, page_control);
+            let _ = (page_code, subpage, page_control);
             Err(crate::error::RustLtfsError::unsupported(
                 "Non-Windows platform",
             ))
@@ -4,9 +4,17 @@
 //! such as MODE SENSE.
 
 use crate::error::Result;
-use tracing::debug;
+use tracing::{debug, warn};
 
-use super::super::{ScsiInterface, constants::*};
+use super::super::{ScsiInterface, DataCompressionPage, LbpMethod, PartitionModePage, constants::*};
+
+/// Data Compression mode page code
+const DATA_COMPRESSION_PAGE: u8 = 0x0F;
+/// Medium Partition mode page code
+const MEDIUM_PARTITION_PAGE: u8 = 0x11;
+/// Control mode page code, Control Data Protection subpage
+const CONTROL_PAGE: u8 = 0x0A;
+const CONTROL_DATA_PROTECTION_SUBPAGE: u8 = 0xF0;
 
 impl ScsiInterface {
     /// MODE SENSE command to read partition page 0x11 (对应LTFSCopyGUI的ModeSense实现)
@@ -14,7 +22,7 @@ impl ScsiInterface {
     pub fn mode_sense_partition_page_0x11(&self) -> Result<Vec<u8>> {
         debug!("Executing MODE SENSE page 0x11 for partition detection");
 
-        #[cfg(windows)]
+        #[cfg(any(windows, target_os = "linux"))]
         {
             // 第一步：获取页面头信息（对应LTFSCopyGUI的Header读取）
             let mut header_cdb = [0u8; 6];
@@ -32,7 +40,7 @@ impl ScsiInterface {
                 &header_cdb,
                 Some(&mut header_buffer),
                 SCSI_IOCTL_DATA_IN,
-                30, // 30 second timeout
+                self.timeouts.mode_sense,
                 Some(&mut sense_buffer),
             )?;
 
@@ -71,7 +79,7 @@ impl ScsiInterface {
                 &full_cdb,
                 Some(&mut full_buffer),
                 SCSI_IOCTL_DATA_IN,
-                30, // 30 second timeout
+                self.timeouts.mode_sense,
                 Some(&mut full_sense_buffer),
             )?;
 
@@ -96,7 +104,7 @@ impl ScsiInterface {
             }
         }
 
-        #[cfg(not(windows))]
+        #[cfg(not(any(windows, target_os = "linux")))]
         {
             Err(crate::error::RustLtfsError::unsupported(
                 "Non-Windows platform",
@@ -104,6 +112,413 @@ impl ScsiInterface {
         }
     }
 
+    /// 解析MODE SENSE page 0x11（已跳过mode header和block descriptor的页面数据）
+    /// Byte 2: Maximum Additional Partitions, Byte 3: Additional Partitions Defined,
+    /// Byte 4: FDP/SDP/IDP/PSUM, Byte 6: Partition Units, Byte 8起: 每个分区2字节的分区大小
+    pub fn parse_partition_mode_page(data: &[u8]) -> Result<PartitionModePage> {
+        if data.len() < 8 {
+            return Err(crate::error::RustLtfsError::scsi(format!(
+                "Partition mode page too short: {} bytes",
+                data.len()
+            )));
+        }
+
+        let page_code = data[0] & 0x3F;
+        if page_code != 0x11 {
+            return Err(crate::error::RustLtfsError::scsi(format!(
+                "Unexpected mode page 0x{:02X}, expected partition page 0x11",
+                page_code
+            )));
+        }
+
+        let page_end = std::cmp::min(2 + data[1] as usize, data.len());
+        let partition_sizes = data
+            .get(8..page_end)
+            .unwrap_or_default()
+            .chunks_exact(2)
+            .map(|size| u16::from_be_bytes([size[0], size[1]]))
+            .collect();
+
+        let page = PartitionModePage {
+            max_additional_partitions: data[2],
+            additional_partitions_defined: data[3],
+            fdp: data[4] & 0x80 != 0,
+            sdp: data[4] & 0x40 != 0,
+            idp: data[4] & 0x20 != 0,
+            psum: (data[4] >> 3) & 0x03,
+            partition_units: data[6] & 0x0F,
+            partition_sizes,
+        };
+        debug!("Parsed partition mode page: {:?}", page);
+        Ok(page)
+    }
+
+    /// MODE SENSE(6) with DBD set: returns the mode parameter header followed by the page
+    fn mode_sense_page_no_descriptors(&self, page_code: u8) -> Result<Vec<u8>> {
+        self.mode_sense_subpage_no_descriptors(page_code, 0x00)
+    }
+
+    /// MODE SENSE(6) of a subpage with DBD set: mode parameter header followed by the subpage
+    fn mode_sense_subpage_no_descriptors(&self, page_code: u8, subpage_code: u8) -> Result<Vec<u8>> {
+        #[cfg(any(windows, target_os = "linux"))]
+        {
+            let cdb = [
+                scsi_commands::MODE_SENSE_6,
+                0x08, // DBD=1: no block descriptors
+                page_code & 0x3F, // PC=00 current values
+                subpage_code,
+                0xFF, // Allocation Length
+                0x00,
+            ];
+
+            let mut buffer = vec![0u8; 0xFF];
+            let mut sense_buffer = [0u8; SENSE_INFO_LEN];
+
+            let result = self.scsi_io_control(
+                &cdb,
+                Some(&mut buffer),
+                SCSI_IOCTL_DATA_IN,
+                self.timeouts.mode_sense,
+                Some(&mut sense_buffer),
+            )?;
+
+            if !result {
+                let sense_info = self.parse_sense_data(&sense_buffer);
+                return Err(crate::error::RustLtfsError::scsi(format!(
+                    "MODE SENSE page 0x{:02X} failed: {}",
+                    page_code, sense_info
+                )));
+            }
+
+            // Byte 0: Mode Data Length (不包含自身)
+            let data_len = std::cmp::min(buffer[0] as usize + 1, buffer.len());
+            buffer.truncate(data_len);
+            Ok(buffer)
+        }
+
+        #[cfg(not(any(windows, target_os = "linux")))]
+        {
+            let _ = (page_code, subpage_code);
+            Err(crate::error::RustLtfsError::unsupported("Non-Windows platform"))
+        }
+    }
+
+    /// 解析Data Compression页（MODE SENSE page 0x0F，含4字节mode header）
+    /// Page Byte 2: DCE(bit7) DCC(bit6)
+    pub fn parse_data_compression_page(data: &[u8]) -> Result<DataCompressionPage> {
+        let page_start = 4 + data.get(3).copied().unwrap_or(0) as usize;
+        let page = data.get(page_start..).unwrap_or_default();
+        if page.len() < 4 || page[0] & 0x3F != DATA_COMPRESSION_PAGE {
+            return Err(crate::error::RustLtfsError::scsi(format!(
+                "Invalid data compression mode page ({} bytes)",
+                page.len()
+            )));
+        }
+
+        Ok(DataCompressionPage {
+            dce: page[2] & 0x80 != 0,
+            dcc: page[2] & 0x40 != 0,
+        })
+    }
+
+    /// 读取硬件压缩状态 (MODE SENSE page 0x0F)
+    pub fn get_compression(&self) -> Result<DataCompressionPage> {
+        debug!("Executing MODE SENSE page 0x0F (Data Compression)");
+        let data = self.mode_sense_page_no_descriptors(DATA_COMPRESSION_PAGE)?;
+        let page = Self::parse_data_compression_page(&data)?;
+        debug!("Data compression page: {:?}", page);
+        Ok(page)
+    }
+
+    /// 开启/关闭硬件压缩 (MODE SENSE + MODE SELECT page 0x0F 的DCE位)
+    /// 对应LTFSCopyGUI的TapeUtils.SetCompression
+    pub fn set_compression(&self, enabled: bool) -> Result<()> {
+        debug!("Setting hardware compression: {}", enabled);
+
+        let mut param_list = self.mode_sense_page_no_descriptors(DATA_COMPRESSION_PAGE)?;
+        Self::parse_data_compression_page(&param_list)?;
+
+        // MODE SELECT时Mode Data Length和Medium Type保留为0，
+        // Device-Specific Parameter只保留Buffered Mode (WP位不可设置)
+        param_list[0] = 0;
+        param_list[1] = 0;
+        param_list[2] &= 0x70;
+        let page_start = 4 + param_list[3] as usize;
+        param_list[page_start] &= 0x3F; // 清除PS位
+        if enabled {
+            param_list[page_start + 2] |= 0x80;
+        } else {
+            param_list[page_start + 2] &= !0x80;
+        }
+
+        #[cfg(any(windows, target_os = "linux"))]
+        {
+            let cdb = [
+                scsi_commands::MODE_SELECT_6,
+                0x10, // PF=1
+                0x00,
+                0x00,
+                param_list.len() as u8, // Parameter List Length
+                0x00,
+            ];
+
+            let mut sense_buffer = [0u8; SENSE_INFO_LEN];
+            let result = self.scsi_io_control(
+                &cdb,
+                Some(&mut param_list),
+                SCSI_IOCTL_DATA_OUT,
+                self.timeouts.mode_sense,
+                Some(&mut sense_buffer),
+            )?;
+
+            if result {
+                debug!("MODE SELECT (Data Compression) successful");
+                Ok(())
+            } else {
+                let sense_info = self.parse_sense_data(&sense_buffer);
+                Err(crate::error::RustLtfsError::scsi(format!(
+                    "MODE SELECT failed to set compression: {}",
+                    sense_info
+                )))
+            }
+        }
+
+        #[cfg(not(any(windows, target_os = "linux")))]
+        {
+            Err(crate::error::RustLtfsError::unsupported("Non-Windows platform"))
+        }
+    }
+
+    /// 设置分区模式页 (MODE SENSE + MODE SELECT page 0x11)，对应LTFSCopyGUI mkltfs中的ModeSelect
+    ///
+    /// IDP=1, PSUM=3, Partition Units=9：`partition_sizes` 以 10^9 字节 (GB) 为单位，
+    /// 0xFFFF 表示该分区占用剩余容量。POFM=1，分区在随后的 FORMAT MEDIUM 时才生效。
+    pub fn set_partition_page(&self, additional_partitions: u8, partition_sizes: &[u16]) -> Result<()> {
+        debug!(
+            "Setting medium partition page: {} additional partitions, sizes {:?}",
+            additional_partitions, partition_sizes
+        );
+
+        let current = self.mode_sense_page_no_descriptors(MEDIUM_PARTITION_PAGE)?;
+        let page_start = 4 + current.get(3).copied().unwrap_or(0) as usize;
+        let page = Self::parse_partition_mode_page(current.get(page_start..).unwrap_or_default())?;
+        if additional_partitions > page.max_additional_partitions {
+            return Err(crate::error::RustLtfsError::parameter_validation(format!(
+                "Drive supports at most {} additional partitions, {} requested",
+                page.max_additional_partitions, additional_partitions
+            )));
+        }
+
+        // Mode parameter header (MODE SELECT时Mode Data Length保留为0) + page 0x11
+        let mut param_list = vec![0u8; 4];
+        param_list[2] = current[2] & 0x70;
+        param_list.extend_from_slice(&[
+            MEDIUM_PARTITION_PAGE, // PS位清零
+            (6 + 2 * partition_sizes.len()) as u8, // Page Length
+            page.max_additional_partitions,
+            additional_partitions,
+            0x3C, // FDP=0 SDP=0 IDP=1 PSUM=3 POFM=1
+            current.get(page_start + 5).copied().unwrap_or(0x03), // Medium Format Recognition
+            0x09, // Partition Units: 10^9 bytes
+            0x00,
+        ]);
+        for size in partition_sizes {
+            param_list.extend_from_slice(&size.to_be_bytes());
+        }
+
+        #[cfg(any(windows, target_os = "linux"))]
+        {
+            let cdb = [
+                scsi_commands::MODE_SELECT_6,
+                0x10, // PF=1
+                0x00,
+                0x00,
+                param_list.len() as u8, // Parameter List Length
+                0x00,
+            ];
+
+            let mut sense_buffer = [0u8; SENSE_INFO_LEN];
+            let result = self.scsi_io_control(
+                &cdb,
+                Some(&mut param_list),
+                SCSI_IOCTL_DATA_OUT,
+                self.timeouts.mode_sense,
+                Some(&mut sense_buffer),
+            )?;
+
+            if result {
+                debug!("MODE SELECT (Medium Partition) successful");
+                Ok(())
+            } else {
+                let sense_info = self.parse_sense_data(&sense_buffer);
+                Err(crate::error::RustLtfsError::scsi(format!(
+                    "MODE SELECT failed to set partition page: {}",
+                    sense_info
+                )))
+            }
+        }
+
+        #[cfg(not(any(windows, target_os = "linux")))]
+        {
+            Err(crate::error::RustLtfsError::unsupported("Non-Windows platform"))
+        }
+    }
+
+    /// 读取驱动器当前的逻辑块保护方式 (MODE SENSE page 0x0A subpage 0xF0)
+    /// Subpage Byte 4: LBP Method, Byte 5: LBP Information Length, Byte 6: LBP_W(bit7) LBP_R(bit6)
+    pub fn get_logical_block_protection(&self) -> Result<LbpMethod> {
+        let data = self.mode_sense_subpage_no_descriptors(CONTROL_PAGE, CONTROL_DATA_PROTECTION_SUBPAGE)?;
+        let page = Self::control_data_protection_page(&data)?;
+        let method = match (page[6] & 0xC0, page[4]) {
+            (0, _) | (_, 0x00) => LbpMethod::None,
+            (_, 0x02) => LbpMethod::Crc32c,
+            (_, other) => {
+                // 例如LTO-5/6的Reed-Solomon CRC (0x01)：驱动器会在每块末尾附加无法校验和去除的保护信息，
+                // 所以关闭LBP；关闭失败时不能继续读写
+                let name = if other == 0x01 { "Reed-Solomon CRC" } else { "unknown" };
+                warn!(
+                    "Drive uses logical block protection method 0x{:02X} ({}), which is not supported; turning it off",
+                    other, name
+                );
+                self.set_logical_block_protection(LbpMethod::None).map_err(|e| {
+                    crate::error::RustLtfsError::unsupported(format!(
+                        "Drive uses logical block protection method 0x{:02X} ({}), which is not supported, \
+                         and it could not be turned off: {}",
+                        other, name, e
+                    ))
+                })?;
+                return Ok(LbpMethod::None);
+            }
+        };
+        debug!("Logical block protection: {:?} (flags 0x{:02X})", method, page[6]);
+        *self.lbp_method.lock().unwrap_or_else(|e| e.into_inner()) = method;
+        Ok(method)
+    }
+
+    /// 设置逻辑块保护 (MODE SELECT page 0x0A subpage 0xF0)
+    ///
+    /// 启用时同时设置LBP_W和LBP_R：之后每次WRITE附加、每次READ校验并去掉块末尾的保护信息。
+    pub fn set_logical_block_protection(&self, method: LbpMethod) -> Result<()> {
+        debug!("Setting logical block protection: {:?}", method);
+
+        let mut param_list =
+            self.mode_sense_subpage_no_descriptors(CONTROL_PAGE, CONTROL_DATA_PROTECTION_SUBPAGE)?;
+        Self::control_data_protection_page(&param_list)?;
+
+        // Mode Data Length和Medium Type保留为0，Device-Specific Parameter只保留Buffered Mode
+        param_list[0] = 0;
+        param_list[1] = 0;
+        param_list[2] &= 0x70;
+        let page_start = 4 + param_list[3] as usize;
+        param_list[page_start] &= 0x7F; // 清除PS位，保留SPF
+        param_list[page_start + 4] = method.code();
+        param_list[page_start + 5] = method.info_len() as u8;
+        param_list[page_start + 6] = if method == LbpMethod::None {
+            param_list[page_start + 6] & 0x3F
+        } else {
+            param_list[page_start + 6] | 0xC0 // LBP_W + LBP_R
+        };
+
+        #[cfg(any(windows, target_os = "linux"))]
+        {
+            let cdb = [
+                scsi_commands::MODE_SELECT_6,
+                0x10, // PF=1
+                0x00,
+                0x00,
+                param_list.len() as u8, // Parameter List Length
+                0x00,
+            ];
+
+            let mut sense_buffer = [0u8; SENSE_INFO_LEN];
+            let result = self.scsi_io_control(
+                &cdb,
+                Some(&mut param_list),
+                SCSI_IOCTL_DATA_OUT,
+                self.timeouts.mode_sense,
+                Some(&mut sense_buffer),
+            )?;
+
+            if result {
+                *self.lbp_method.lock().unwrap_or_else(|e| e.into_inner()) = method;
+                debug!("MODE SELECT (Control Data Protection) successful");
+                Ok(())
+            } else {
+                let sense_info = self.parse_sense_data(&sense_buffer);
+                Err(crate::error::RustLtfsError::scsi(format!(
+                    "MODE SELECT failed to set logical block protection: {}",
+                    sense_info
+                )))
+            }
+        }
+
+        #[cfg(not(any(windows, target_os = "linux")))]
+        {
+            Err(crate::error::RustLtfsError::unsupported("Non-Windows platform"))
+        }
+    }
+
+    /// Control Data Protection子页（含4字节mode header的MODE SENSE数据中）
+    fn control_data_protection_page(data: &[u8]) -> Result<&[u8]> {
+        let page_start = 4 + data.get(3).copied().unwrap_or(0) as usize;
+        let page = data.get(page_start..).unwrap_or_default();
+        if page.len() < 8 || page[0] & 0x3F != CONTROL_PAGE || page[1] != CONTROL_DATA_PROTECTION_SUBPAGE {
+            return Err(crate::error::RustLtfsError::scsi(format!(
+                "Invalid control data protection mode page ({} bytes)",
+                page.len()
+            )));
+        }
+        Ok(page)
+    }
+
+    /// READ BLOCK LIMITS command - returns (max, min) block length supported by the drive
+    /// 对应LTFSCopyGUI的TapeUtils.ReadBlockLimits；max为0表示驱动器未报告上限
+    pub fn read_block_limits(&self) -> Result<(u32, u32)> {
+        debug!("Executing READ BLOCK LIMITS");
+
+        #[cfg(any(windows, target_os = "linux"))]
+        {
+            let mut cdb = [0u8; 6];
+            cdb[0] = scsi_commands::READ_BLOCK_LIMITS;
+
+            // Byte 0: Granularity, Bytes 1-3: Maximum Block Length, Bytes 4-5: Minimum Block Length
+            let mut buffer = vec![0u8; 6];
+            let mut sense_buffer = [0u8; SENSE_INFO_LEN];
+
+            let result = self.scsi_io_control(
+                &cdb,
+                Some(&mut buffer),
+                SCSI_IOCTL_DATA_IN,
+                self.timeouts.mode_sense,
+                Some(&mut sense_buffer),
+            )?;
+
+            if result {
+                let max_block_length =
+                    ((buffer[1] as u32) << 16) | ((buffer[2] as u32) << 8) | buffer[3] as u32;
+                let min_block_length = ((buffer[4] as u32) << 8) | buffer[5] as u32;
+                debug!(
+                    "READ BLOCK LIMITS: max={} min={} granularity={}",
+                    max_block_length,
+                    min_block_length,
+                    buffer[0] & 0x1F
+                );
+                Ok((max_block_length, min_block_length))
+            } else {
+                let sense_info = self.parse_sense_data(&sense_buffer);
+                Err(crate::error::RustLtfsError::scsi(format!(
+                    "READ BLOCK LIMITS failed: {}",
+                    sense_info
+                )))
+            }
+        }
+
+        #[cfg(not(any(windows, target_os = "linux")))]
+        {
+            Err(crate::error::RustLtfsError::unsupported("Non-Windows platform"))
+        }
+    }
+
     /// Sets the block size for the tape drive using MODE SELECT (6).
     /// If block_size > 0, the drive is set to Fixed Block Mode with the specified size.
     /// If block_size == 0, the drive is set to Variable Block Mode.
@@ -111,7 +526,7 @@ impl ScsiInterface {
     pub fn set_block_size(&self, block_size: u32) -> Result<()> {
         debug!("Executing MODE SELECT to set block size to {}", block_size);
 
-        #[cfg(windows)]
+        #[cfg(any(windows, target_os = "linux"))]
         {
             // CDB for MODE SELECT (6)
             let mut cdb = [0u8; 6];
@@ -154,7 +569,7 @@ impl ScsiInterface {
                 &cdb,
                 Some(&mut param_list),
                 SCSI_IOCTL_DATA_OUT,
-                30,
+                self.timeouts.mode_sense,
                 Some(&mut sense_buffer),
             )?;
 
@@ -170,9 +585,42 @@ impl ScsiInterface {
             }
         }
 
-        #[cfg(not(windows))]
+        #[cfg(not(any(windows, target_os = "linux")))]
         {
             Err(crate::error::RustLtfsError::unsupported("Non-Windows platform"))
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// MODE SENSE page 0x11 (mode header stripped) from an LTO-7 drive holding an
+    /// LTFS-formatted cartridge: 1 additional partition of 4 possible, IDP, PSUM=3
+    /// with 10^9-byte units, 17 GB index partition and 5675 GB data partition
+    const LTO7_PARTITION_PAGE: [u8; 12] = [
+        0x91, 0x0A, 0x03, 0x01, 0x3C, 0x03, 0x09, 0x00, 0x00, 0x11, 0x16, 0x2B,
+    ];
+
+    #[test]
+    fn parses_lto7_partition_page() {
+        let page = ScsiInterface::parse_partition_mode_page(&LTO7_PARTITION_PAGE).unwrap();
+        assert_eq!(page.max_additional_partitions, 3);
+        assert_eq!(page.additional_partitions_defined, 1);
+        assert_eq!(page.partition_count(), 2);
+        assert!(page.is_multi_partition());
+        assert!(!page.fdp && !page.sdp && page.idp);
+        assert_eq!(page.psum, 3);
+        assert_eq!(page.partition_units, 9);
+        assert_eq!(page.partition_sizes, vec![0x0011, 0x162B]);
+    }
+
+    #[test]
+    fn rejects_other_pages_and_short_data() {
+        let mut compression_page = LTO7_PARTITION_PAGE;
+        compression_page[0] = 0x0F;
+        assert!(ScsiInterface::parse_partition_mode_page(&compression_page).is_err());
+        assert!(ScsiInterface::parse_partition_mode_page(&LTO7_PARTITION_PAGE[..6]).is_err());
+    }
+}
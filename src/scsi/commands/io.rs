@@ -5,8 +5,16 @@
 use crate::error::Result;
 use tracing::{debug, info, warn};
 
-use super::super::{ScsiInterface, constants::*, types::LocateDestType};
+use super::super::{ScsiInterface, LbpMethod, constants::*, lbp, types::{LocateDestType, RawBlock}};
 use super::super::constants::block_sizes;
+#[cfg(any(windows, target_os = "linux"))]
+use super::super::sense::{end_of_media_sense, EndOfMediaSense, SenseData};
+
+/// READ(6)/WRITE(6) transfer length is a 24-bit field (bytes in variable block mode)
+const MAX_TRANSFER_LENGTH: usize = 0xFF_FFFF;
+
+/// Pause between chunks of a chunked read so the drive is not flooded with commands
+const CHUNK_READ_INTERVAL: std::time::Duration = std::time::Duration::from_millis(10);
 
 impl ScsiInterface {
     /// Read tape blocks (enhanced implementation for large file support)
@@ -24,10 +32,10 @@ impl ScsiInterface {
         // For large block counts, break into smaller chunks to avoid SCSI timeout
         const MAX_BLOCKS_PER_READ: u32 = 256; // 16MB chunks (256 * 64KB)
 
-        if block_count <= MAX_BLOCKS_PER_READ {
+        if block_count <= MAX_BLOCKS_PER_READ && buffer.len() <= MAX_TRANSFER_LENGTH {
             // Direct read for smaller requests
             debug!("Using direct read for {} blocks", block_count);
-            self.read_blocks_direct(block_count, buffer)
+            self.read_blocks_direct(block_count, buffer, None)
         } else {
             // Chunked read for larger requests
             debug!("Using chunked read for {} blocks", block_count);
@@ -35,11 +43,44 @@ impl ScsiInterface {
         }
     }
 
+    /// Read tape blocks with an explicit per-command timeout (seconds)
+    /// Used by index reads, where the drive may spend a long time repositioning
+    pub fn read_blocks_with_timeout(
+        &self,
+        block_count: u32,
+        buffer: &mut [u8],
+        timeout: u32,
+    ) -> Result<u32> {
+        self.read_blocks_direct(block_count, buffer, Some(timeout))
+    }
+
     /// Direct block read implementation (private)
-    fn read_blocks_direct(&self, block_count: u32, buffer: &mut [u8]) -> Result<u32> {
+    fn read_blocks_direct(
+        &self,
+        block_count: u32,
+        buffer: &mut [u8],
+        timeout_override: Option<u32>,
+    ) -> Result<u32> {
         debug!("Direct reading {} blocks", block_count);
 
-        #[cfg(windows)]
+        let method = self.logical_block_protection();
+        if method != LbpMethod::None {
+            let timeout = timeout_override.unwrap_or_else(|| self.transfer_timeout(buffer.len(), self.timeouts.read));
+            return match self.read_protected_block(method, buffer, timeout)? {
+                RawBlock::Data(_) => Ok(block_count),
+                RawBlock::FileMark | RawBlock::EndOfData => Ok(0),
+            };
+        }
+
+        if buffer.len() > MAX_TRANSFER_LENGTH {
+            return Err(crate::error::RustLtfsError::parameter_validation(format!(
+                "READ(6) transfer of {} bytes exceeds the {}-byte transfer length limit",
+                buffer.len(),
+                MAX_TRANSFER_LENGTH
+            )));
+        }
+
+        #[cfg(any(windows, target_os = "linux"))]
         {
             // Use READ(6) command for tape devices (sequential access)
             // READ(10) LBA addressing is inappropriate for tape devices
@@ -67,8 +108,12 @@ impl ScsiInterface {
             // 使用实际要传输的字节数作为缓冲区大小
             let actual_buffer_size = byte_count as usize;
 
-            // Adjust timeout based on data size
-            let timeout = std::cmp::max(300u32, ((actual_buffer_size / (64 * 1024)) * 60) as u32);
+            // Adjust timeout based on data size, tightened once the drive's throughput is known
+            let timeout = timeout_override.unwrap_or_else(|| {
+                let conservative =
+                    std::cmp::max(self.timeouts.read, ((actual_buffer_size / (64 * 1024)) * 60) as u32);
+                self.transfer_timeout(actual_buffer_size, conservative)
+            });
             debug!(
                 "Using timeout: {} seconds for {} bytes",
                 timeout, actual_buffer_size
@@ -77,6 +122,7 @@ impl ScsiInterface {
             // 创建sense数据缓冲区用于分析
             let mut sense_buffer = [0u8; SENSE_INFO_LEN];
 
+            let started = std::time::Instant::now();
             let result = self.scsi_io_control(
                 &cdb,
                 Some(&mut buffer[..actual_buffer_size]),
@@ -86,6 +132,7 @@ impl ScsiInterface {
             )?;
 
             if result {
+                self.record_transfer(actual_buffer_size, started.elapsed());
                 debug!(
                     "Successfully read {} bytes directly (requested {} blocks)",
                     actual_buffer_size, block_count
@@ -96,31 +143,128 @@ impl ScsiInterface {
                 debug!("READ(6) returned error, analyzing sense data for file mark detection");
 
                 // 分析sense数据确定实际传输的数据量和是否遇到文件标记
-                let (actual_blocks_read, is_file_mark) =
-                    self.analyze_read_sense_data(&sense_buffer, byte_count)?;
+                let outcome = self.analyze_read_sense_data(&sense_buffer, byte_count)?;
 
-                if is_file_mark {
+                if outcome.file_mark {
                     info!(
                         "✅ File mark detected via sense data - read {} blocks before mark",
-                        actual_blocks_read
+                        outcome.blocks_read
                     );
-                    Ok(actual_blocks_read)
+                    Ok(outcome.blocks_read)
+                } else if outcome.short_block {
+                    Ok(outcome.blocks_read)
                 } else {
                     warn!(
                         "❌ READ(6) command failed with sense: {}",
                         self.parse_sense_data(&sense_buffer)
                     );
-                    Err(crate::error::RustLtfsError::scsi(format!(
-                        "Direct block read operation failed: {}",
-                        self.parse_sense_data(&sense_buffer)
-                    )))
+                    Err(crate::error::RustLtfsError::scsi_sense(
+                        "Direct block read operation failed",
+                        SenseData::parse(&sense_buffer),
+                    ))
                 }
             }
         }
 
-        #[cfg(not(windows))]
+        #[cfg(not(any(windows, target_os = "linux")))]
         {
-            let _ = (block_count, buffer);
+            let _ = (block_count, buffer, timeout_override);
+            Err(crate::error::RustLtfsError::unsupported(
+                "Non-Windows platform",
+            ))
+        }
+    }
+
+    /// Read exactly one block in variable block mode, reporting filemarks and EOD
+    ///
+    /// `buffer` must be at least as large as the block on tape: a longer block is an
+    /// error rather than silently truncated data. Used for raw partition imaging.
+    pub fn read_raw_block(&self, buffer: &mut [u8]) -> Result<RawBlock> {
+        let method = self.logical_block_protection();
+        if method != LbpMethod::None {
+            let timeout = self.transfer_timeout(buffer.len(), self.timeouts.read);
+            return self.read_protected_block(method, buffer, timeout);
+        }
+        let timeout = self.transfer_timeout(buffer.len(), self.timeouts.read);
+        self.read_variable_block(buffer, timeout)
+    }
+
+    /// 逻辑块保护启用时读取一个块：校验并去掉块末尾的保护信息，返回的长度不含保护信息
+    fn read_protected_block(&self, method: LbpMethod, buffer: &mut [u8], timeout: u32) -> Result<RawBlock> {
+        let mut protected = vec![0u8; buffer.len() + method.info_len()];
+        match self.read_variable_block(&mut protected, timeout)? {
+            RawBlock::Data(len) => {
+                let data_len = lbp::verify_protection(method, &protected[..len])?;
+                buffer[..data_len].copy_from_slice(&protected[..data_len]);
+                Ok(RawBlock::Data(data_len))
+            }
+            mark => Ok(mark),
+        }
+    }
+
+    /// READ(6) of one variable-length block (see `read_raw_block`)
+    fn read_variable_block(&self, buffer: &mut [u8], timeout: u32) -> Result<RawBlock> {
+        if buffer.is_empty() || buffer.len() > MAX_TRANSFER_LENGTH {
+            return Err(crate::error::RustLtfsError::parameter_validation(format!(
+                "Raw block buffer of {} bytes must be between 1 and {} bytes",
+                buffer.len(),
+                MAX_TRANSFER_LENGTH
+            )));
+        }
+
+        #[cfg(any(windows, target_os = "linux"))]
+        {
+            let byte_count = buffer.len() as u32;
+            let mut cdb = [0u8; 6];
+            cdb[0] = scsi_commands::READ_6;
+            cdb[1] = 0x00; // Variable length mode, no SILI: short blocks report ILI
+            cdb[2] = ((byte_count >> 16) & 0xFF) as u8;
+            cdb[3] = ((byte_count >> 8) & 0xFF) as u8;
+            cdb[4] = (byte_count & 0xFF) as u8;
+
+            let mut sense_buffer = [0u8; SENSE_INFO_LEN];
+            let started = std::time::Instant::now();
+            let result = self.scsi_io_control(
+                &cdb,
+                Some(buffer),
+                SCSI_IOCTL_DATA_IN,
+                timeout,
+                Some(&mut sense_buffer),
+            )?;
+            if result {
+                self.record_transfer(byte_count as usize, started.elapsed());
+                return Ok(RawBlock::Data(byte_count as usize));
+            }
+
+            let outcome = self.analyze_read_sense_data(&sense_buffer, byte_count)?;
+            if outcome.file_mark {
+                return Ok(if outcome.end_of_data {
+                    RawBlock::EndOfData
+                } else {
+                    RawBlock::FileMark
+                });
+            }
+            if outcome.short_block {
+                // 负的residue表示块比缓冲区长，计算出的长度会等于缓冲区长度
+                if outcome.bytes_read >= byte_count {
+                    return Err(crate::error::RustLtfsError::scsi(format!(
+                        "Block is larger than the {}-byte read buffer",
+                        byte_count
+                    )));
+                }
+                self.record_transfer(outcome.bytes_read as usize, started.elapsed());
+                return Ok(RawBlock::Data(outcome.bytes_read as usize));
+            }
+
+            Err(crate::error::RustLtfsError::scsi_sense(
+                "Raw block read failed",
+                SenseData::parse(&sense_buffer),
+            ))
+        }
+
+        #[cfg(not(any(windows, target_os = "linux")))]
+        {
+            let _ = timeout;
             Err(crate::error::RustLtfsError::unsupported(
                 "Non-Windows platform",
             ))
@@ -128,16 +272,27 @@ impl ScsiInterface {
     }
 
     /// Chunked block read for large files (private)
+    ///
+    /// Offsets are computed in `usize` from the caller's buffer (block length =
+    /// buffer length / block count), so transfers over 4GB do not overflow, and
+    /// each READ(6) stays within the 24-bit transfer length.
     fn read_blocks_chunked(&self, block_count: u32, buffer: &mut [u8]) -> Result<u32> {
         debug!("Chunked reading {} blocks", block_count);
 
+        if block_count == 0 {
+            return Ok(0);
+        }
+
         const CHUNK_SIZE: u32 = 128; // 8MB chunks for better performance
+        let (chunk_blocks, block_len) = read_chunk_layout(block_count, buffer.len());
+        let chunk_blocks = std::cmp::min(chunk_blocks, CHUNK_SIZE);
         let mut total_read = 0u32;
         let mut remaining = block_count;
 
         while remaining > 0 {
-            let current_chunk = std::cmp::min(remaining, CHUNK_SIZE);
-            let offset = (total_read * block_sizes::LTO_BLOCK_SIZE) as usize;
+            let current_chunk = std::cmp::min(remaining, chunk_blocks);
+            let offset = total_read as usize * block_len;
+            let end = std::cmp::min(offset + current_chunk as usize * block_len, buffer.len());
 
             debug!(
                 "Reading chunk: {} blocks (offset: {} bytes)",
@@ -145,10 +300,9 @@ impl ScsiInterface {
             );
 
             // Read current chunk
-            let chunk_buffer = &mut buffer
-                [offset..(offset + (current_chunk * block_sizes::LTO_BLOCK_SIZE) as usize)];
+            let chunk_buffer = &mut buffer[offset..end];
 
-            match self.read_blocks_direct(current_chunk, chunk_buffer) {
+            match self.read_blocks_direct(current_chunk, chunk_buffer, None) {
                 Ok(read_count) => {
                     if read_count != current_chunk {
                         warn!(
@@ -162,7 +316,7 @@ impl ScsiInterface {
                     remaining -= read_count;
 
                     // Small delay between chunks to prevent overloading the drive
-                    std::thread::sleep(std::time::Duration::from_millis(10));
+                    std::thread::sleep(CHUNK_READ_INTERVAL);
                 }
                 Err(e) => {
                     if total_read > 0 {
@@ -183,13 +337,44 @@ impl ScsiInterface {
     }
 
     /// Write tape blocks (based on LTFSCopyGUI implementation)
+    ///
+    /// Reaching the early-warning end of medium returns `RustLtfsError::EndOfMedia`
+    /// (the block itself has been written), as does a volume overflow.
     pub fn write_blocks(&self, block_count: u32, buffer: &[u8]) -> Result<u32> {
+        self.write_blocks_checked(block_count, buffer, false)
+    }
+
+    /// Write blocks that must still go to tape inside the early-warning zone (index, labels)
+    ///
+    /// Early warning is logged and treated as success; only a volume overflow is an error.
+    pub fn write_blocks_allow_early_warning(&self, block_count: u32, buffer: &[u8]) -> Result<u32> {
+        self.write_blocks_checked(block_count, buffer, true)
+    }
+
+    fn write_blocks_checked(&self, block_count: u32, buffer: &[u8], allow_early_warning: bool) -> Result<u32> {
         debug!("Writing {} blocks to tape", block_count);
 
+        // 逻辑块保护启用时，块末尾附加保护信息，驱动器在写入前校验
+        let protected;
+        let buffer = match self.logical_block_protection() {
+            LbpMethod::None => buffer,
+            method => {
+                protected = lbp::append_protection(method, buffer);
+                &protected[..]
+            }
+        };
+
         // LTFSCopyGUI compatibility: write actual buffer length, not block_count * LTO_BLOCK_SIZE
         // This allows writing 524288-byte blocks (LTFSCopyGUI's plabel.blocksize) instead of 65536
+        if buffer.len() > MAX_TRANSFER_LENGTH {
+            return Err(crate::error::RustLtfsError::parameter_validation(format!(
+                "WRITE(6) transfer of {} bytes exceeds the {}-byte transfer length limit",
+                buffer.len(),
+                MAX_TRANSFER_LENGTH
+            )));
+        }
 
-        #[cfg(windows)]
+        #[cfg(any(windows, target_os = "linux"))]
         {
             let mut cdb = [0u8; 6];
             cdb[0] = scsi_commands::WRITE_6;
@@ -205,28 +390,39 @@ impl ScsiInterface {
             cdb[4] = (byte_count & 0xFF) as u8;
             // cdb[5] is control byte, leave as 0
 
-            let data_length = buffer.len();
-            let result = self.scsi_io_control(
-                &cdb,
-                Some(&mut buffer[..data_length].to_vec().as_mut_slice()),
-                SCSI_IOCTL_DATA_OUT,
-                600, // 10 minute timeout for write operations
-                None,
-            )?;
+            // 10 minute timeout for write operations, tightened once the throughput is known
+            let timeout = self.transfer_timeout(buffer.len(), self.timeouts.write);
+            let mut sense_buffer = [0u8; SENSE_INFO_LEN];
+            let started = std::time::Instant::now();
+            let result = self.scsi_io_control_out(&cdb, buffer, timeout, Some(&mut sense_buffer))?;
 
             if result {
+                self.record_transfer(buffer.len(), started.elapsed());
                 debug!("Successfully wrote {} blocks", block_count);
-                Ok(block_count)
-            } else {
-                Err(crate::error::RustLtfsError::scsi(
+                return Ok(block_count);
+            }
+
+            match end_of_media_sense(&sense_buffer) {
+                Some(EndOfMediaSense::EarlyWarning) if allow_early_warning => {
+                    warn!("Early-warning end of medium reached, block written");
+                    Ok(block_count)
+                }
+                Some(EndOfMediaSense::EarlyWarning) => Err(crate::error::RustLtfsError::end_of_media(
+                    "early-warning end of medium reached (last block written)",
+                )),
+                Some(EndOfMediaSense::VolumeOverflow) => Err(crate::error::RustLtfsError::end_of_media(
+                    "volume overflow, block not written",
+                )),
+                None => Err(crate::error::RustLtfsError::scsi_sense(
                     "Block write operation failed",
-                ))
+                    SenseData::parse(&sense_buffer),
+                )),
             }
         }
 
-        #[cfg(not(windows))]
+        #[cfg(not(any(windows, target_os = "linux")))]
         {
-            let _ = (block_count, buffer);
+            let _ = (block_count, buffer, allow_early_warning);
             Err(crate::error::RustLtfsError::unsupported(
                 "Non-Windows platform",
             ))
@@ -241,7 +437,7 @@ impl ScsiInterface {
             block_size_limit
         );
 
-        #[cfg(windows)]
+        #[cfg(any(windows, target_os = "linux"))]
         {
             let mut buffer = Vec::new();
             let actual_block_limit = std::cmp::min(block_size_limit, block_sizes::LTO_BLOCK_SIZE);
@@ -267,24 +463,21 @@ impl ScsiInterface {
                     &cdb,
                     Some(&mut read_buffer),
                     SCSI_IOCTL_DATA_IN,
-                    300,
+                    self.timeouts.read,
                     Some(&mut sense_buffer),
                 )?;
 
                 // 🎯 精确复制LTFSCopyGUI的FileMark检测逻辑和DiffBytes计算
                 // LTFSCopyGUI: Dim Add_Key As UInt16 = CInt(sense(12)) << 8 Or sense(13)
-                let add_key = ((sense_buffer[12] as u16) << 8) | (sense_buffer[13] as u16);
+                let sense = SenseData::parse(&sense_buffer);
+                let add_key = sense.add_key();
 
                 // 🔧 关键修复：实现LTFSCopyGUI的DiffBytes计算逻辑 (Line 638-641)
                 // For i As Integer = 3 To 6: DiffBytes <<= 8: DiffBytes = DiffBytes Or sense(i)
-                let mut diff_bytes: i32 = 0;
-                for i in 3..=6 {
-                    diff_bytes <<= 8;
-                    diff_bytes |= sense_buffer[i] as i32;
-                }
+                let diff_bytes = sense.information;
 
                 debug!("🔍 Sense analysis: result={}, Add_Key=0x{:04X} (ASC=0x{:02X}, ASCQ=0x{:02X}), DiffBytes={}",
-                      result, add_key, sense_buffer[12], sense_buffer[13], diff_bytes);
+                      result, add_key, sense.asc, sense.ascq, diff_bytes);
                 debug!(
                     "🔍 Detailed sense analysis: result={}, DiffBytes={}, BlockSizeLimit={}",
                     result, diff_bytes, block_size_limit
@@ -347,6 +540,7 @@ impl ScsiInterface {
                                     current_pos.block_number - 1,
                                     current_pos.partition,
                                     LocateDestType::Block,
+                                    Some(current_pos.partition),
                                 )?;
 
                                 // 再次记录回退后位置做对比诊断
@@ -377,7 +571,7 @@ impl ScsiInterface {
                                     &cdb,
                                     Some(&mut adjusted_buffer),
                                     SCSI_IOCTL_DATA_IN,
-                                    300,
+                                    self.timeouts.read,
                                     Some(&mut sense_buffer),
                                 )?;
 
@@ -448,8 +642,7 @@ impl ScsiInterface {
                                 }
 
                                 // 重新计算add_key用于FileMark检测
-                                let reread_add_key =
-                                    ((sense_buffer[12] as u16) << 8) | (sense_buffer[13] as u16);
+                                let reread_add_key = SenseData::parse(&sense_buffer).add_key();
                                 debug!("🔍 Re-read Add_Key: 0x{:04X}", reread_add_key);
 
                                 // 🎯 使用重新读取后的add_key进行FileMark检测
@@ -499,7 +692,7 @@ impl ScsiInterface {
             Ok(buffer)
         }
 
-        #[cfg(not(windows))]
+        #[cfg(not(any(windows, target_os = "linux")))]
         {
             let _ = block_size_limit;
             Err(crate::error::RustLtfsError::unsupported(
@@ -508,3 +701,38 @@ impl ScsiInterface {
         }
     }
 }
+
+/// Split a `block_count`-block read into READ(6)-sized chunks:
+/// returns (blocks per chunk, bytes per block) for a buffer of `buffer_len` bytes
+fn read_chunk_layout(block_count: u32, buffer_len: usize) -> (u32, usize) {
+    let block_len = std::cmp::max(buffer_len / std::cmp::max(block_count, 1) as usize, 1);
+    let blocks_per_transfer = std::cmp::max(MAX_TRANSFER_LENGTH / block_len, 1);
+    let chunk_blocks = std::cmp::min(blocks_per_transfer, u32::MAX as usize) as u32;
+    (chunk_blocks, block_len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunk_layout_for_a_5gb_transfer_fits_read6() {
+        // 5GB，512KB块：缓冲区长度超过u32，块长度与每次READ的块数仍需正确
+        let block_len = 512 * 1024;
+        let block_count = 5_000_000_000u64.div_ceil(block_len as u64) as u32;
+        let buffer_len = block_count as usize * block_len;
+        assert!(buffer_len as u64 > u32::MAX as u64);
+
+        let (chunk_blocks, len) = read_chunk_layout(block_count, buffer_len);
+        assert_eq!(len, block_len);
+        assert!(chunk_blocks >= 1);
+        assert!(chunk_blocks as usize * len <= MAX_TRANSFER_LENGTH);
+        assert!((chunk_blocks as usize + 1) * len > MAX_TRANSFER_LENGTH);
+
+        // 多次READ才能读完，最后一块的偏移不溢出
+        let reads = block_count.div_ceil(chunk_blocks);
+        assert!(reads > 1);
+        let last_offset = (block_count - 1) as usize * len;
+        assert_eq!(last_offset + len, buffer_len);
+    }
+}
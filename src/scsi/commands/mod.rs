@@ -6,3 +6,5 @@ pub mod basic;
 pub mod positioning;
 pub mod io;
 pub mod config;
+pub mod attributes;
+pub mod security;
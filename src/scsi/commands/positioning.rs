@@ -7,12 +7,223 @@ use tracing::{debug, info, warn};
 
 use super::super::{ScsiInterface, constants::*, types::{SpaceType, LocateDestType, TapePosition, DriveType}};
 use super::super::constants::block_sizes; // Explicitly import block_sizes
+#[cfg(any(windows, target_os = "linux"))]
+use super::super::sense::{end_of_media_sense, EndOfMediaSense, SenseData, SenseKey};
+
+/// SPACE(6) count字段的取值范围（24位有符号数）
+pub const SPACE_COUNT_MIN: i32 = -(1 << 23);
+pub const SPACE_COUNT_MAX: i32 = (1 << 23) - 1;
+
+/// 将SPACE count编码为CDB字节2-4（24位二进制补码）
+/// LTFSCopyGUI: {&H11, Code, Count >> 16 And &HFF, Count >> 8 And &HFF, Count And &HFF, 0}
+fn encode_space_count(count: i32) -> Result<[u8; 3]> {
+    if !(SPACE_COUNT_MIN..=SPACE_COUNT_MAX).contains(&count) {
+        return Err(crate::error::RustLtfsError::parameter_validation(format!(
+            "SPACE count {} is outside the signed 24-bit range [{}, {}]",
+            count, SPACE_COUNT_MIN, SPACE_COUNT_MAX
+        )));
+    }
+    let bytes = count.to_be_bytes();
+    Ok([bytes[1], bytes[2], bytes[3]])
+}
 
 impl ScsiInterface {
+    /// Rewind tape to beginning of partition 0 (SCSI REWIND, waits for completion)
+    pub fn rewind(&self) -> Result<()> {
+        self.rewind_with_options(false)
+    }
+
+    /// SCSI REWIND command (opcode 0x01)
+    /// LTFSCopyGUI: {1, Immed, 0, 0, 0, 0}
+    /// With `immediate` set the drive returns status before the rewind completes
+    pub fn rewind_with_options(&self, immediate: bool) -> Result<()> {
+        debug!("Rewinding tape (immediate: {})", immediate);
+        #[cfg(any(windows, target_os = "linux"))]
+        {
+            let mut cdb = [0u8; 6];
+            cdb[0] = scsi_commands::REWIND;
+            if immediate {
+                cdb[1] = 0x01; // IMMED bit
+            }
+
+            let mut sense_buffer = [0u8; SENSE_INFO_LEN];
+            let result = self.scsi_io_control(
+                &cdb,
+                None,
+                SCSI_IOCTL_DATA_UNSPECIFIED,
+                self.timeouts.locate,
+                Some(&mut sense_buffer),
+            )?;
+
+            if result {
+                debug!("Rewind completed successfully");
+                Ok(())
+            } else {
+                Err(crate::error::RustLtfsError::scsi_sense(
+                    "Rewind operation failed",
+                    SenseData::parse(&sense_buffer),
+                ))
+            }
+        }
+
+        #[cfg(not(any(windows, target_os = "linux")))]
+        {
+            let _ = immediate;
+            Err(crate::error::RustLtfsError::unsupported(
+                "Non-Windows platform",
+            ))
+        }
+    }
+
+    /// SCSI ERASE command (opcode 0x19)
+    /// LTFSCopyGUI: {&H19, Long Or (Immed << 1), 0, 0, 0, 0}
+    /// Short erase writes an EOD at the current position; long erase overwrites the
+    /// whole partition from the current position to the end of the medium
+    pub fn erase(&self, long: bool, immediate: bool) -> Result<()> {
+        debug!("ERASE: long={}, immediate={}", long, immediate);
+        #[cfg(any(windows, target_os = "linux"))]
+        {
+            let mut cdb = [0u8; 6];
+            cdb[0] = scsi_commands::ERASE;
+            if long {
+                cdb[1] |= 0x01; // LONG bit
+            }
+            if immediate {
+                cdb[1] |= 0x02; // IMMED bit
+            }
+
+            // A long erase without IMMED holds the command for the whole pass over the tape
+            let timeout = if long && !immediate { 6 * 3600 } else { self.timeouts.write };
+
+            let mut sense_buffer = [0u8; SENSE_INFO_LEN];
+            let result = self.scsi_io_control(
+                &cdb,
+                None,
+                SCSI_IOCTL_DATA_UNSPECIFIED,
+                timeout,
+                Some(&mut sense_buffer),
+            )?;
+
+            if result {
+                debug!("Erase completed successfully");
+                Ok(())
+            } else {
+                Err(crate::error::RustLtfsError::scsi_sense(
+                    "Erase operation failed",
+                    SenseData::parse(&sense_buffer),
+                ))
+            }
+        }
+
+        #[cfg(not(any(windows, target_os = "linux")))]
+        {
+            let _ = (long, immediate);
+            Err(crate::error::RustLtfsError::unsupported(
+                "Non-Windows platform",
+            ))
+        }
+    }
+
+    /// SCSI FORMAT MEDIUM command (opcode 0x04)
+    /// LTFSCopyGUI: {&H4, 0, Format, 0, 0, 0}
+    /// `partition = false` formats the medium as a single partition (default format);
+    /// `partition = true` partitions it as set up by the Medium Partition mode page
+    pub fn format_medium(&self, partition: bool) -> Result<()> {
+        debug!("FORMAT MEDIUM: partition={}", partition);
+        #[cfg(any(windows, target_os = "linux"))]
+        {
+            let mut cdb = [0u8; 6];
+            cdb[0] = scsi_commands::FORMAT_MEDIUM;
+            cdb[2] = if partition { 0x01 } else { 0x00 }; // FORMAT field
+
+            let mut sense_buffer = [0u8; SENSE_INFO_LEN];
+            let result = self.scsi_io_control(
+                &cdb,
+                None,
+                SCSI_IOCTL_DATA_UNSPECIFIED,
+                1800, // partitioning rewinds and rewrites the format wraps
+                Some(&mut sense_buffer),
+            )?;
+
+            if result {
+                debug!("Format medium completed successfully");
+                Ok(())
+            } else {
+                Err(crate::error::RustLtfsError::scsi_sense(
+                    "Format medium failed",
+                    SenseData::parse(&sense_buffer),
+                ))
+            }
+        }
+
+        #[cfg(not(any(windows, target_os = "linux")))]
+        {
+            let _ = partition;
+            Err(crate::error::RustLtfsError::unsupported(
+                "Non-Windows platform",
+            ))
+        }
+    }
+
+    /// SCSI LOAD UNLOAD command (opcode 0x1B)
+    /// LTFSCopyGUI: {&H1B, Immed, 0, 0, EOT << 2 Or Load, 0}
+    /// `load = false` unloads (ejects) the medium; `eoj` positions to end-of-tape first
+    pub fn load_unload(&self, load: bool, eoj: bool, immediate: bool) -> Result<()> {
+        debug!(
+            "LOAD UNLOAD: load={}, eoj={}, immediate={}",
+            load, eoj, immediate
+        );
+        #[cfg(any(windows, target_os = "linux"))]
+        {
+            let mut cdb = [0u8; 6];
+            cdb[0] = scsi_commands::LOAD_UNLOAD;
+            if immediate {
+                cdb[1] = 0x01; // IMMED bit
+            }
+            if load {
+                cdb[4] |= 0x01; // LOAD bit
+            }
+            if eoj {
+                cdb[4] |= 0x04; // EOT bit
+            }
+
+            let mut sense_buffer = [0u8; SENSE_INFO_LEN];
+            let result = self.scsi_io_control(
+                &cdb,
+                None,
+                SCSI_IOCTL_DATA_UNSPECIFIED,
+                self.timeouts.locate,
+                Some(&mut sense_buffer),
+            )?;
+
+            if result {
+                debug!("LOAD UNLOAD completed successfully");
+                Ok(())
+            } else if SenseData::parse(&sense_buffer).sense_key == SenseKey::DataProtect {
+                Err(crate::error::RustLtfsError::tape_device(
+                    "Write protected: medium is write-protected, LOAD UNLOAD rejected",
+                ))
+            } else {
+                Err(crate::error::RustLtfsError::scsi_sense(
+                    "LOAD UNLOAD operation failed",
+                    SenseData::parse(&sense_buffer),
+                ))
+            }
+        }
+
+        #[cfg(not(any(windows, target_os = "linux")))]
+        {
+            let _ = (load, eoj, immediate);
+            Err(crate::error::RustLtfsError::unsupported(
+                "Non-Windows platform",
+            ))
+        }
+    }
+
     /// Position tape to specific block (based on SCSI LOCATE command)
     pub fn locate_block(&self, partition: u8, block_number: u64) -> Result<()> {
         debug!("Locating to partition {} block {}", partition, block_number);
-        #[cfg(windows)]
+        #[cfg(any(windows, target_os = "linux"))]
         {
             let mut cdb = [0u8; 10];
             cdb[0] = scsi_commands::LOCATE;
@@ -34,7 +245,7 @@ impl ScsiInterface {
                 &cdb,
                 None,
                 SCSI_IOCTL_DATA_UNSPECIFIED,
-                600, // 10 minute timeout for positioning
+                self.timeouts.locate,
                 None,
             )?;
 
@@ -49,7 +260,7 @@ impl ScsiInterface {
             }
         }
 
-        #[cfg(not(windows))]
+        #[cfg(not(any(windows, target_os = "linux")))]
         {
             let _ = (partition, block_number);
             Err(crate::error::RustLtfsError::unsupported(
@@ -59,33 +270,31 @@ impl ScsiInterface {
     }
 
     /// Space operation (move by specified count of objects) - LTFSCopyGUI compatible
+    ///
+    /// Blocks/FileMarks 委托给 `space6`；负数count表示反向移动，必须在24位有符号范围内。
     pub fn space(&self, space_type: SpaceType, count: i32) -> Result<()> {
         debug!(
             "Space operation (LTFSCopyGUI compatible): type={:?}, count={}",
             space_type, count
         );
 
-        #[cfg(windows)]
+        if !matches!(space_type, SpaceType::EndOfData) {
+            return self.space6(count, space_type as u8).map(|_| ());
+        }
+
+        #[cfg(any(windows, target_os = "linux"))]
         {
-            let mut cdb = [0u8; 6];
-            cdb[0] = scsi_commands::SPACE; // 0x11
-            cdb[1] = space_type as u8;
-
-            // Handle EndOfData specially - should use count=1 according to SCSI standards
-            let actual_count = match space_type {
-                SpaceType::EndOfData => {
-                    debug!("EndOfData operation: using standard count=1 for SCSI compliance");
-                    1 // SCSI standard requires count=1 for EndOfData positioning
-                }
-                _ => count,
-            };
-
-            // LTFSCopyGUI方式：直接将count作为有符号整数处理
-            // LTFSCopyGUI: {&H11, Code, Count >> 16 And &HFF, Count >> 8 And &HFF, Count And &HFF, 0}
-            cdb[2] = ((actual_count >> 16) & 0xFF) as u8;
-            cdb[3] = ((actual_count >> 8) & 0xFF) as u8;
-            cdb[4] = (actual_count & 0xFF) as u8;
-            cdb[5] = 0x00;
+            // EndOfData: SCSI standard requires count=1 for EndOfData positioning
+            debug!("EndOfData operation: using standard count=1 for SCSI compliance");
+            let count_bytes = encode_space_count(1)?;
+            let cdb = [
+                scsi_commands::SPACE, // 0x11
+                space_type as u8,
+                count_bytes[0],
+                count_bytes[1],
+                count_bytes[2],
+                0x00,
+            ];
 
             debug!("SPACE command: {:02X?}", &cdb[..]);
 
@@ -93,7 +302,7 @@ impl ScsiInterface {
                 &cdb,
                 None,
                 SCSI_IOCTL_DATA_UNSPECIFIED,
-                600, // 10 minute timeout for space operations
+                self.timeouts.space,
                 None,
             )?;
 
@@ -107,9 +316,8 @@ impl ScsiInterface {
             }
         }
 
-        #[cfg(not(windows))]
+        #[cfg(not(any(windows, target_os = "linux")))]
         {
-            let _ = (space_type, count);
             Err(crate::error::RustLtfsError::unsupported(
                 "Non-Windows platform".to_string(),
             ))
@@ -121,38 +329,32 @@ impl ScsiInterface {
     pub fn space6(&self, count: i32, code: u8) -> Result<u16> {
         debug!("🔧 Space6: count={}, code={}", count, code);
 
-        #[cfg(windows)]
+        // Count是24位有符号数，超出范围时直接报错而不是截断回绕
+        let count_bytes = encode_space_count(count)?;
+
+        #[cfg(any(windows, target_os = "linux"))]
         {
-            let mut cdb = [0u8; 6];
-            cdb[0] = scsi_commands::SPACE; // 0x11
-            cdb[1] = code; // LocateDestType: 0=Block, 1=FileMark, 2=SequentialFileMark
-
-            // Count是24位有符号数
-            if count < 0 {
-                // 对于负数，使用24位二进制补码表示
-                let abs_count = (-count) as u32;
-                let complement = (!abs_count + 1) & 0xFFFFFF; // 24位二进制补码
-                cdb[2] = ((complement >> 16) & 0xFF) as u8;
-                cdb[3] = ((complement >> 8) & 0xFF) as u8;
-                cdb[4] = (complement & 0xFF) as u8;
-            } else {
-                cdb[2] = ((count >> 16) & 0xFF) as u8;
-                cdb[3] = ((count >> 8) & 0xFF) as u8;
-                cdb[4] = (count & 0xFF) as u8;
-            }
+            let cdb = [
+                scsi_commands::SPACE, // 0x11
+                code, // LocateDestType: 0=Block, 1=FileMark, 2=SequentialFileMark
+                count_bytes[0],
+                count_bytes[1],
+                count_bytes[2],
+                0x00,
+            ];
 
             let mut sense_buffer = [0u8; SENSE_INFO_LEN];
             let result = self.scsi_io_control(
                 &cdb,
                 None,
                 SCSI_IOCTL_DATA_UNSPECIFIED,
-                600, // 10分钟超时
+                self.timeouts.space,
                 Some(&mut sense_buffer),
             )?;
 
             if result {
                 // 返回Add_Code (sense[12] << 8 | sense[13])
-                let add_code = ((sense_buffer[12] as u16) << 8) | (sense_buffer[13] as u16);
+                let add_code = SenseData::parse(&sense_buffer).add_key();
                 debug!("✅ Space6 completed with Add_Code: 0x{:04X}", add_code);
                 Ok(add_code)
             } else {
@@ -160,9 +362,9 @@ impl ScsiInterface {
             }
         }
 
-        #[cfg(not(windows))]
+        #[cfg(not(any(windows, target_os = "linux")))]
         {
-            let _ = (count, code);
+            let _ = (count_bytes, code);
             Err(crate::error::RustLtfsError::unsupported(
                 "Non-Windows platform",
             ))
@@ -174,7 +376,7 @@ impl ScsiInterface {
     pub fn read_file_mark(&self) -> Result<bool> {
         debug!("🔧 ReadFileMark: Starting FileMark detection");
 
-        #[cfg(windows)]
+        #[cfg(any(windows, target_os = "linux"))]
         {
             // 🎯 精确复制LTFSCopyGUI ReadFileMark逻辑 (Line 785-792)
             // 1. 总是尝试读取一个块 (对应 ReadBlock)
@@ -185,7 +387,7 @@ impl ScsiInterface {
                 &[scsi_commands::READ_6, 0x00, 0x00, 0x00, 0x01, 0x00], // READ(6) 1 block
                 Some(&mut test_buffer),
                 SCSI_IOCTL_DATA_IN,
-                30,
+                self.timeouts.read,
                 Some(&mut sense_buffer),
             )?;
 
@@ -225,6 +427,7 @@ impl ScsiInterface {
                         current_pos.block_number - 1,
                         current_pos.partition,
                         LocateDestType::Block,
+                        Some(current_pos.partition),
                     )?;
                 }
             } else {
@@ -243,7 +446,7 @@ impl ScsiInterface {
             Ok(false) // 返回false表示执行了回退
         }
 
-        #[cfg(not(windows))]
+        #[cfg(not(any(windows, target_os = "linux")))]
         {
             Err(crate::error::RustLtfsError::unsupported(
                 "Non-Windows platform",
@@ -254,32 +457,63 @@ impl ScsiInterface {
     /// Write file mark (end of file marker)
     pub fn write_filemarks(&self, count: u32) -> Result<()> {
         debug!("Writing {} filemarks", count);
+        self.write_filemarks_command(count, true)
+    }
 
-        #[cfg(windows)]
+    /// Flush the drive buffer to the media (WRITE FILEMARKS with count 0, Immed off)
+    ///
+    /// 返回时之前写入的所有块和FileMark都已落盘，此后读取的位置才能安全地记入索引。
+    pub fn synchronize_buffer(&self) -> Result<()> {
+        debug!("Synchronizing drive buffer");
+        self.write_filemarks_command(0, false)
+    }
+
+    /// WRITE FILEMARKS：`immediate` 置位时驱动器在命令被接受后立即返回，不等待缓冲区写入介质
+    fn write_filemarks_command(&self, count: u32, immediate: bool) -> Result<()> {
+        #[cfg(any(windows, target_os = "linux"))]
         {
             let mut cdb = [0u8; 6];
             cdb[0] = 0x10; // WRITE_FILEMARKS
-            cdb[1] = 0x01; // Immediate bit set for better performance
+            cdb[1] = if immediate { 0x01 } else { 0x00 }; // Immediate bit set for better performance
 
             // Transfer length (number of filemarks)
             cdb[2] = ((count >> 16) & 0xFF) as u8;
             cdb[3] = ((count >> 8) & 0xFF) as u8;
             cdb[4] = (count & 0xFF) as u8;
 
-            let result =
-                self.scsi_io_control(&cdb, None, SCSI_IOCTL_DATA_UNSPECIFIED, 300, None)?;
+            let mut sense_buffer = [0u8; SENSE_INFO_LEN];
+            let result = self.scsi_io_control(
+                &cdb,
+                None,
+                SCSI_IOCTL_DATA_UNSPECIFIED,
+                self.timeouts.write,
+                Some(&mut sense_buffer),
+            )?;
 
             if result {
                 debug!("Successfully wrote {} filemarks", count);
-                Ok(())
-            } else {
-                Err(crate::error::RustLtfsError::scsi("Write filemarks failed"))
+                return Ok(());
+            }
+
+            // FileMark和索引在early-warning区域内仍须写入，才能在换盘前提交已写入的数据
+            match end_of_media_sense(&sense_buffer) {
+                Some(EndOfMediaSense::EarlyWarning) => {
+                    warn!("Early-warning end of medium reached, {} filemarks written", count);
+                    Ok(())
+                }
+                Some(EndOfMediaSense::VolumeOverflow) => Err(
+                    crate::error::RustLtfsError::end_of_media("volume overflow while writing filemarks"),
+                ),
+                None => Err(crate::error::RustLtfsError::scsi_sense(
+                    "Write filemarks failed",
+                    SenseData::parse(&sense_buffer),
+                )),
             }
         }
 
-        #[cfg(not(windows))]
+        #[cfg(not(any(windows, target_os = "linux")))]
         {
-            let _ = count;
+            let _ = (count, immediate);
             Err(crate::error::RustLtfsError::unsupported(
                 "Non-Windows platform",
             ))
@@ -287,10 +521,12 @@ impl ScsiInterface {
     }
 
     /// Read tape position information (LTFSCopyGUI compatible implementation)
+    ///
+    /// 只发送READ POSITION本身，不会调用任何定位命令，因此 `locate` 可以安全地调用它。
     pub fn read_position(&self) -> Result<TapePosition> {
         debug!("Reading tape position");
 
-        #[cfg(windows)]
+        #[cfg(any(windows, target_os = "linux"))]
         {
             let mut cdb = [0u8; 10];
             let mut data_buffer = [0u8; 32];
@@ -316,7 +552,7 @@ impl ScsiInterface {
             );
 
             let result =
-                self.scsi_io_control(&cdb, Some(&mut data_buffer), SCSI_IOCTL_DATA_IN, 300, None)?;
+                self.scsi_io_control(&cdb, Some(&mut data_buffer), SCSI_IOCTL_DATA_IN, self.timeouts.locate, None)?;
 
             if result {
                 debug!(
@@ -402,6 +638,8 @@ impl ScsiInterface {
                     set_number,
                     end_of_data: (flags & 0x04) != 0, // EOD flag
                     beginning_of_partition: (flags & 0x08) != 0, // BOP flag
+                    blocks_in_buffer: None, // 长格式不含缓冲区计数，见read_position_extended
+                    bytes_in_buffer: None,
                 };
 
                 // 🔍 显示解析后的值与LTFSCopyGUI格式对比
@@ -434,7 +672,58 @@ impl ScsiInterface {
             }
         }
 
-        #[cfg(not(windows))]
+        #[cfg(not(any(windows, target_os = "linux")))]
+        {
+            Err(crate::error::RustLtfsError::unsupported(
+                "Non-Windows platform".to_string(),
+            ))
+        }
+    }
+
+    /// Read the tape position together with the drive buffer state
+    ///
+    /// 长格式 (Service Action 6) 不含缓冲区计数，因此先读取长格式位置，再用扩展格式
+    /// (Service Action 8) 读取缓冲区中尚未写入介质的块数和字节数；驱动器无法报告时
+    /// (LOCU/BYCU标志) 对应字段为None。
+    pub fn read_position_extended(&self) -> Result<TapePosition> {
+        #[cfg(any(windows, target_os = "linux"))]
+        {
+            let mut position = self.read_position()?;
+            let mut cdb = [0u8; 10];
+            let mut data_buffer = [0u8; 32];
+            cdb[0] = scsi_commands::READ_POSITION; // 0x34
+            cdb[1] = 0x08; // Service Action = 8 (extended form)
+            cdb[7] = (data_buffer.len() >> 8) as u8; // Allocation length
+            cdb[8] = data_buffer.len() as u8;
+
+            let result =
+                self.scsi_io_control(&cdb, Some(&mut data_buffer), SCSI_IOCTL_DATA_IN, self.timeouts.locate, None)?;
+            if !result {
+                return Err(crate::error::RustLtfsError::scsi(
+                    "Read position (extended form) failed".to_string(),
+                ));
+            }
+            debug!("READ POSITION extended data: {:02X?}", &data_buffer[..]);
+
+            // Byte 0: BOP(7) EOP(6) LOCU(5) BYCU(4) LOLU(2) PERR(1)
+            let flags = data_buffer[0];
+            let blocks = data_buffer[5..8]
+                .iter()
+                .fold(0u64, |acc, &b| (acc << 8) | b as u64);
+            let bytes = data_buffer[24..32]
+                .iter()
+                .fold(0u64, |acc, &b| (acc << 8) | b as u64);
+            position.blocks_in_buffer = (flags & 0x20 == 0).then_some(blocks);
+            position.bytes_in_buffer = (flags & 0x10 == 0).then_some(bytes);
+
+            debug!(
+                "Drive buffer: {:?} blocks, {:?} bytes not yet on media",
+                position.blocks_in_buffer, position.bytes_in_buffer
+            );
+            Ok(position)
+        }
+
+        #[cfg(not(any(windows, target_os = "linux")))]
         {
             Err(crate::error::RustLtfsError::unsupported(
                 "Non-Windows platform".to_string(),
@@ -444,11 +733,19 @@ impl ScsiInterface {
 
     /// Comprehensive locate method (based on LTFSCopyGUI TapeUtils.Locate)
     /// Supports block, file mark, and EOD positioning with drive-specific optimizations
+    ///
+    /// `current_partition` 是调用者已知的当前分区，用于决定LOCATE(16)的CP位：
+    /// - `Some(p)`: CP = (p != partition)，不发送额外的READ POSITION
+    /// - `None`: 发送一次READ POSITION获取当前分区；若失败则置CP=1
+    ///   （CP=1时驱动器总是定位到指定分区，即使已在该分区也正确，只是可能稍慢）
+    ///
+    /// 定位路径（包括错误重试）只会调用 `read_position` 和 `scsi_io_control`，不会递归调用自身。
     pub fn locate(
         &self,
         block_address: u64,
         partition: u8,
         dest_type: LocateDestType,
+        current_partition: Option<u8>,
     ) -> Result<u16> {
         debug!(
             "Locating to partition {} {} {:?} {}",
@@ -462,7 +759,7 @@ impl ScsiInterface {
             block_address
         );
 
-        #[cfg(windows)]
+        #[cfg(any(windows, target_os = "linux"))]
         {
             let mut sense_buffer = [0u8; SENSE_INFO_LEN];
 
@@ -470,14 +767,20 @@ impl ScsiInterface {
             // Execute locate command based on drive type
             match self.drive_type {
                 DriveType::Standard => {
-                    self.locate_standard(block_address, partition, dest_type, &mut sense_buffer)
+                    self.locate_standard(
+                        block_address,
+                        partition,
+                        dest_type,
+                        current_partition,
+                        &mut sense_buffer,
+                    )
                 }
             }
         }
 
-        #[cfg(not(windows))]
+        #[cfg(not(any(windows, target_os = "linux")))]
         {
-            let _ = (block_address, partition, dest_type);
+            let _ = (block_address, partition, dest_type, current_partition);
             Err(crate::error::RustLtfsError::unsupported(
                 "Non-Windows platform",
             ))
@@ -485,12 +788,13 @@ impl ScsiInterface {
     }
 
     /// Standard/modern drive locate implementation
-    #[cfg(windows)]
+    #[cfg(any(windows, target_os = "linux"))]
     fn locate_standard(
         &self,
         block_address: u64,
         partition: u8,
         dest_type: LocateDestType,
+        current_partition: Option<u8>,
         sense_buffer: &mut [u8; SENSE_INFO_LEN],
     ) -> Result<u16> {
         // 🎯 关键修复：FileMark定位必须使用LTFSCopyGUI逻辑 (Line 972-974)
@@ -503,12 +807,15 @@ impl ScsiInterface {
                 // 对于Block和EOD，使用标准的LOCATE(16)命令
                 if self.allow_partition || dest_type != LocateDestType::Block {
                     // Use LOCATE(16) command for modern drives with partition support
-                    let mut cp = 0u8;
-                    if let Ok(current_pos) = self.read_position() {
-                        if current_pos.partition != partition {
-                            cp = 1; // Change partition flag
-                        }
-                    }
+                    let current_partition = match current_partition {
+                        Some(current) => Some(current),
+                        None => self.read_position().ok().map(|pos| pos.partition),
+                    };
+                    // Change partition flag: 当前分区未知时置位，保证定位到正确的分区
+                    let cp = match current_partition {
+                        Some(current) if current == partition => 0u8,
+                        _ => 1u8,
+                    };
 
                     let mut cdb = [0u8; 16];
                     cdb[0] = 0x92; // LOCATE(16)
@@ -548,7 +855,7 @@ impl ScsiInterface {
     }
 
     /// Execute locate command and handle errors (based on LTFSCopyGUI error handling)
-    #[cfg(windows)]
+    #[cfg(any(windows, target_os = "linux"))]
     fn execute_locate_command(
         &self,
         cdb: &[u8],
@@ -558,18 +865,23 @@ impl ScsiInterface {
             cdb,
             None,
             SCSI_IOCTL_DATA_UNSPECIFIED,
-            600, // 10 minute timeout for positioning
+            self.timeouts.locate,
             Some(sense_buffer),
         )?;
 
-        if !result {
-            return Err(crate::error::RustLtfsError::scsi("Locate command failed"));
+        // SG_IO在CHECK CONDITION时返回false；有sense时按Windows的方式交给下面的ASC/ASCQ处理
+        if !result && !SenseData::parse(sense_buffer).is_present() {
+            return Err(crate::error::RustLtfsError::scsi_sense(
+                "Locate command failed",
+                SenseData::parse(sense_buffer),
+            ));
         }
 
         // Parse sense data for additional status code (ASC/ASCQ)
-        let asc_ascq = ((sense_buffer[12] as u16) << 8) | (sense_buffer[13] as u16);
+        let sense = SenseData::parse(sense_buffer);
+        let asc_ascq = sense.add_key();
 
-        if asc_ascq != 0 && (sense_buffer[2] & 0x0F) != 8 {
+        if asc_ascq != 0 && sense.sense_key != SenseKey::BlankCheck {
             // Error occurred, attempt recovery based on LTFSCopyGUI logic
             warn!(
                 "Locate command returned error: ASC/ASCQ = 0x{:04X}",
@@ -577,7 +889,21 @@ impl ScsiInterface {
             );
 
             // Retry with different strategy if first attempt failed
-            self.retry_locate_on_error(cdb, sense_buffer, asc_ascq)
+            let policy = self.locate_retry;
+            let mut last_error = None;
+            for retry in 1..=policy.max_retries {
+                std::thread::sleep(policy.delay_for(retry));
+                match self.retry_locate_on_error(cdb, sense_buffer, asc_ascq) {
+                    Ok(code) => return Ok(code),
+                    Err(e) => {
+                        debug!("Locate retry {}/{} failed: {}", retry, policy.max_retries, e);
+                        last_error = Some(e);
+                    }
+                }
+            }
+            Err(last_error.unwrap_or_else(|| {
+                crate::error::RustLtfsError::scsi_sense("Locate operation failed", sense)
+            }))
         } else {
             debug!("Locate command completed successfully");
             Ok(0)
@@ -585,7 +911,7 @@ impl ScsiInterface {
     }
 
     /// Retry locate operation on error (based on LTFSCopyGUI retry logic)
-    #[cfg(windows)]
+    #[cfg(any(windows, target_os = "linux"))]
     fn retry_locate_on_error(
         &self,
         original_cdb: &[u8],
@@ -622,18 +948,19 @@ impl ScsiInterface {
                     retry_cdb[9] = 0;
 
                     debug!("Retrying with LOCATE(10) command");
+                    self.locate_fallbacks
+                        .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
 
                     let result = self.scsi_io_control(
                         &retry_cdb,
                         None,
                         SCSI_IOCTL_DATA_UNSPECIFIED,
-                        600,
+                        self.timeouts.locate,
                         Some(sense_buffer),
                     )?;
 
-                    if result {
-                        let retry_asc_ascq =
-                            ((sense_buffer[12] as u16) << 8) | (sense_buffer[13] as u16);
+                    if result || SenseData::parse(sense_buffer).is_present() {
+                        let retry_asc_ascq = SenseData::parse(sense_buffer).add_key();
                         debug!("Retry result: ASC/ASCQ = 0x{:04X}", retry_asc_ascq);
                         Ok(retry_asc_ascq)
                     } else {
@@ -657,7 +984,7 @@ impl ScsiInterface {
 
     /// Convenience method: locate to file mark
     pub fn locate_to_filemark(&self, filemark_number: u64, partition: u8) -> Result<()> {
-        // 🎯 关键修复：避免无限递归，直接使用LTFSCopyGUI逻辑
+        // 🎯 直接使用LTFSCopyGUI逻辑，而不是LOCATE的FileMark目标类型
         // 对应: Locate(handle, 0, 0) + Space6(handle, Count, FileMark)
         debug!(
             "🔧 locate_to_filemark: FileMark {} in partition {} using LTFSCopyGUI method",
@@ -665,7 +992,7 @@ impl ScsiInterface {
         );
 
         // Step 1: 先定位到指定分区的开头
-        self.locate(0, partition, LocateDestType::Block)?;
+        self.locate(0, partition, LocateDestType::Block, None)?;
 
         // Step 2: 然后用Space命令移动到FileMark
         self.space(SpaceType::FileMarks, filemark_number as i32)?;
@@ -675,7 +1002,7 @@ impl ScsiInterface {
 
     /// Convenience method: locate to end of data
     pub fn locate_to_eod(&self, partition: u8) -> Result<()> {
-        self.locate(0, partition, LocateDestType::EOD)?;
+        self.locate(0, partition, LocateDestType::EOD, None)?;
         Ok(())
     }
 }
@@ -0,0 +1,164 @@
+//! SCSI Security Protocol Commands
+//!
+//! This module contains SECURITY PROTOCOL IN/OUT for tape data encryption (SSC-4 protocol 0x20).
+
+use crate::error::Result;
+use tracing::{debug, info};
+use zeroize::Zeroize;
+
+use super::super::{ScsiInterface, constants::*, types::EncryptionStatus};
+
+/// Security protocol: Tape Data Encryption
+const TAPE_DATA_ENCRYPTION_PROTOCOL: u8 = 0x20;
+/// SECURITY PROTOCOL OUT page: Set Data Encryption
+const SET_DATA_ENCRYPTION_PAGE: u16 = 0x0010;
+/// SECURITY PROTOCOL IN page: Data Encryption Status
+const DATA_ENCRYPTION_STATUS_PAGE: u16 = 0x0020;
+
+/// Set Data Encryption: SCOPE = ALL I_T NEXUS
+const SCOPE_ALL_I_T_NEXUS: u8 = 0x40;
+const ENCRYPTION_MODE_DISABLE: u8 = 0x00;
+const ENCRYPTION_MODE_ENCRYPT: u8 = 0x02;
+const DECRYPTION_MODE_DISABLE: u8 = 0x00;
+const DECRYPTION_MODE_MIXED: u8 = 0x03;
+
+impl ScsiInterface {
+    /// SECURITY PROTOCOL OUT - Set Data Encryption page
+    /// Enables encryption with `key` (32 bytes for AES-256-GCM) using `algorithm_index`;
+    /// an empty key disables encryption. The parameter buffer holding the key is
+    /// zeroized once the command completes, whether or not it succeeded.
+    pub fn set_data_encryption(&self, key: &[u8], algorithm_index: u8) -> Result<()> {
+        debug!(
+            "Executing SECURITY PROTOCOL OUT (Set Data Encryption): algorithm_index={}, key_length={}",
+            algorithm_index,
+            key.len()
+        );
+
+        // Page length covers everything after the 4-byte header: 16 fixed bytes + key
+        let page_length = u16::try_from(16 + key.len()).map_err(|_| {
+            crate::error::RustLtfsError::parameter_validation("Encryption key is too long")
+        })?;
+        let key_length = key.len() as u16;
+
+        let mut parameters = vec![0u8; 20 + key.len()];
+        parameters[0..2].copy_from_slice(&SET_DATA_ENCRYPTION_PAGE.to_be_bytes());
+        parameters[2..4].copy_from_slice(&page_length.to_be_bytes());
+        parameters[4] = SCOPE_ALL_I_T_NEXUS;
+        if key.is_empty() {
+            parameters[6] = ENCRYPTION_MODE_DISABLE;
+            parameters[7] = DECRYPTION_MODE_DISABLE;
+        } else {
+            parameters[6] = ENCRYPTION_MODE_ENCRYPT;
+            parameters[7] = DECRYPTION_MODE_MIXED;
+        }
+        parameters[8] = algorithm_index;
+        parameters[9] = 0x00; // Logical block encryption key format: plain-text key
+        parameters[18..20].copy_from_slice(&key_length.to_be_bytes());
+        parameters[20..].copy_from_slice(key);
+
+        let result = self.send_security_protocol_out(SET_DATA_ENCRYPTION_PAGE, &parameters);
+        parameters.zeroize();
+
+        if result? {
+            info!(
+                "Data encryption {}",
+                if key.is_empty() { "disabled" } else { "enabled" }
+            );
+            Ok(())
+        } else {
+            Err(crate::error::RustLtfsError::scsi(
+                "SECURITY PROTOCOL OUT (Set Data Encryption) failed",
+            ))
+        }
+    }
+
+    /// SECURITY PROTOCOL IN - Data Encryption Status page
+    pub fn get_encryption_status(&self) -> Result<EncryptionStatus> {
+        debug!("Executing SECURITY PROTOCOL IN (Data Encryption Status)");
+
+        #[cfg(any(windows, target_os = "linux"))]
+        {
+            let mut data_buffer = vec![0u8; 64];
+            let mut cdb = [0u8; 12];
+            cdb[0] = scsi_commands::SECURITY_PROTOCOL_IN;
+            cdb[1] = TAPE_DATA_ENCRYPTION_PROTOCOL;
+            cdb[2..4].copy_from_slice(&DATA_ENCRYPTION_STATUS_PAGE.to_be_bytes());
+            cdb[6..10].copy_from_slice(&(data_buffer.len() as u32).to_be_bytes());
+
+            let mut sense_buffer = [0u8; SENSE_INFO_LEN];
+            let result = self.scsi_io_control(
+                &cdb,
+                Some(&mut data_buffer),
+                SCSI_IOCTL_DATA_IN,
+                self.timeouts.mode_sense,
+                Some(&mut sense_buffer),
+            )?;
+
+            if !result {
+                return Err(crate::error::RustLtfsError::scsi(format!(
+                    "SECURITY PROTOCOL IN (Data Encryption Status) failed: {}",
+                    self.parse_sense_data(&sense_buffer)
+                )));
+            }
+
+            let page_code = u16::from_be_bytes([data_buffer[0], data_buffer[1]]);
+            if page_code != DATA_ENCRYPTION_STATUS_PAGE {
+                return Err(crate::error::RustLtfsError::scsi(format!(
+                    "Unexpected security protocol page 0x{:04X}",
+                    page_code
+                )));
+            }
+
+            let status = EncryptionStatus {
+                encryption_mode: data_buffer[5],
+                decryption_mode: data_buffer[6],
+                algorithm_index: data_buffer[7],
+                key_instance_counter: u32::from_be_bytes([
+                    data_buffer[8],
+                    data_buffer[9],
+                    data_buffer[10],
+                    data_buffer[11],
+                ]),
+            };
+            debug!("Encryption status: {:?}", status);
+            Ok(status)
+        }
+
+        #[cfg(not(any(windows, target_os = "linux")))]
+        {
+            Err(crate::error::RustLtfsError::unsupported(
+                "Non-Windows platform",
+            ))
+        }
+    }
+
+    /// Send a Tape Data Encryption page with SECURITY PROTOCOL OUT
+    fn send_security_protocol_out(&self, page: u16, parameters: &[u8]) -> Result<bool> {
+        #[cfg(any(windows, target_os = "linux"))]
+        {
+            let mut cdb = [0u8; 12];
+            cdb[0] = scsi_commands::SECURITY_PROTOCOL_OUT;
+            cdb[1] = TAPE_DATA_ENCRYPTION_PROTOCOL;
+            cdb[2..4].copy_from_slice(&page.to_be_bytes());
+            cdb[6..10].copy_from_slice(&(parameters.len() as u32).to_be_bytes());
+
+            let mut sense_buffer = [0u8; SENSE_INFO_LEN];
+            let result = self.scsi_io_control_out(&cdb, parameters, self.timeouts.mode_sense, Some(&mut sense_buffer))?;
+            if !result {
+                debug!(
+                    "SECURITY PROTOCOL OUT failed: {}",
+                    self.parse_sense_data(&sense_buffer)
+                );
+            }
+            Ok(result)
+        }
+
+        #[cfg(not(any(windows, target_os = "linux")))]
+        {
+            let _ = (page, parameters);
+            Err(crate::error::RustLtfsError::unsupported(
+                "Non-Windows platform",
+            ))
+        }
+    }
+}
@@ -16,17 +16,41 @@ pub const SCSI_IOCTL_DATA_UNSPECIFIED: u8 = 2;
 #[cfg(windows)]
 pub const IOCTL_SCSI_PASS_THROUGH_DIRECT: u32 = 0x0004D014;
 
+// Linux SG_IO ioctl and sg_io_hdr constants (from <scsi/sg.h>)
+#[cfg(target_os = "linux")]
+pub const SG_IO: u32 = 0x2285;
+#[cfg(target_os = "linux")]
+pub const SG_DXFER_NONE: i32 = -1;
+#[cfg(target_os = "linux")]
+pub const SG_DXFER_TO_DEV: i32 = -2;
+#[cfg(target_os = "linux")]
+pub const SG_DXFER_FROM_DEV: i32 = -3;
+#[cfg(target_os = "linux")]
+pub const SG_INFO_OK_MASK: u32 = 0x1;
+
 // SCSI Commands Module
 pub mod scsi_commands {
     pub const TEST_UNIT_READY: u8 = 0x00;
+    pub const REWIND: u8 = 0x01;
+    pub const FORMAT_MEDIUM: u8 = 0x04;
+    pub const READ_BLOCK_LIMITS: u8 = 0x05;
     pub const READ_6: u8 = 0x08;
     pub const WRITE_6: u8 = 0x0A;
     pub const SPACE: u8 = 0x11;
+    pub const INQUIRY: u8 = 0x12;
+    pub const MODE_SELECT_6: u8 = 0x15;
+    pub const ERASE: u8 = 0x19;
+    pub const MODE_SENSE_6: u8 = 0x1A;
+    pub const LOAD_UNLOAD: u8 = 0x1B;
 
 
     pub const LOCATE: u8 = 0x2B;
     pub const READ_POSITION: u8 = 0x34;
     pub const LOG_SENSE: u8 = 0x4D;
+    pub const READ_ATTRIBUTE: u8 = 0x8C;
+    pub const WRITE_ATTRIBUTE: u8 = 0x8D;
+    pub const SECURITY_PROTOCOL_IN: u8 = 0xA2;
+    pub const SECURITY_PROTOCOL_OUT: u8 = 0xB5;
 
 
 }
@@ -18,26 +18,136 @@ use winapi::{
 };
 
 use super::constants::*;
-use super::{DriveType, ScsiPassThroughDirect};
+#[cfg(windows)]
+use super::ScsiPassThroughDirect;
+#[cfg(target_os = "linux")]
+use super::SgIoHdr;
+use super::{DriveType, LbpMethod, RetryPolicy, ThroughputTracker, Timeouts};
 use super::device::DeviceHandle;
+use std::time::Duration;
 
 /// SCSI operation structure that encapsulates low-level SCSI commands
 pub struct ScsiInterface {
     pub(crate) device_handle: Option<DeviceHandle>,
     pub(crate) drive_type: DriveType,
     pub(crate) allow_partition: bool,
+    pub(crate) read_retry: RetryPolicy,
+    pub(crate) locate_retry: RetryPolicy,
+    pub(crate) test_unit_ready_retry: RetryPolicy,
+    pub(crate) timeouts: Timeouts,
+    pub(crate) throughput: std::sync::Mutex<ThroughputTracker>,
+    /// 当前启用的逻辑块保护方式，读写时据此附加/校验每块的CRC
+    pub(crate) lbp_method: std::sync::Mutex<LbpMethod>,
+    /// LOCATE(16)失败后改用LOCATE(10)重试的次数
+    pub(crate) locate_fallbacks: std::sync::atomic::AtomicU64,
+    /// 最近一次SG_IO头部中的超时（毫秒），供测试检查超时设置是否生效
+    #[cfg(test)]
+    pub(crate) last_sg_io_timeout: std::sync::atomic::AtomicU32,
 }
 
 impl ScsiInterface {
     /// Create new SCSI interface instance
+    ///
+    /// Retry policies default to the LTFSCopyGUI-compatible values and can be overridden
+    /// with the `RUSTLTFS_READ_*`, `RUSTLTFS_LOCATE_*` and `RUSTLTFS_TUR_*` environment variables.
+    /// Command timeouts are the defaults with `RUSTLTFS_TIMEOUT_*` overrides until set with `with_timeouts`.
     pub fn new() -> Self {
         Self {
             device_handle: None,
             drive_type: DriveType::Standard,
             allow_partition: true,
+            // 读取：块读取失败时最多重试3次
+            read_retry: RetryPolicy::new(3, Duration::from_millis(10)).with_env_overrides("READ"),
+            // 定位：LOCATE(16)失败时用LOCATE(10)重试1次
+            locate_retry: RetryPolicy::new(1, Duration::ZERO).with_env_overrides("LOCATE"),
+            // TestUnitReady：对应LTFSCopyGUI的5次尝试、200ms间隔
+            test_unit_ready_retry: RetryPolicy::new(4, Duration::from_millis(200))
+                .with_env_overrides("TUR"),
+            timeouts: Timeouts::default().with_env_overrides(),
+            throughput: std::sync::Mutex::new(ThroughputTracker::default()),
+            lbp_method: std::sync::Mutex::new(LbpMethod::None),
+            locate_fallbacks: std::sync::atomic::AtomicU64::new(0),
+            #[cfg(test)]
+            last_sg_io_timeout: std::sync::atomic::AtomicU32::new(0),
         }
     }
 
+    /// Moving-average READ/WRITE throughput in MiB/s (None until a transfer was timed)
+    pub fn last_throughput_mbps(&self) -> Option<f64> {
+        self.throughput.lock().unwrap_or_else(|e| e.into_inner()).mbps()
+    }
+
+    /// Record a successful data transfer for throughput tracking
+    pub(crate) fn record_transfer(&self, bytes: usize, elapsed: Duration) {
+        self.throughput
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .record(bytes, elapsed);
+    }
+
+    /// Logical Block Protection method currently enabled on the drive
+    pub fn logical_block_protection(&self) -> LbpMethod {
+        *self.lbp_method.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// READ/WRITE timeout for `bytes`, adapted to the observed throughput (at most `conservative`)
+    pub(crate) fn transfer_timeout(&self, bytes: usize, conservative: u32) -> u32 {
+        self.throughput
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .timeout_for(bytes, conservative)
+    }
+
+    /// Set the retry policy for block reads
+    pub fn with_read_retry(mut self, policy: RetryPolicy) -> Self {
+        self.read_retry = policy;
+        self
+    }
+
+    /// Set the retry policy for LOCATE
+    pub fn with_locate_retry(mut self, policy: RetryPolicy) -> Self {
+        self.locate_retry = policy;
+        self
+    }
+
+    /// Set the retry policy for TEST UNIT READY
+    pub fn with_test_unit_ready_retry(mut self, policy: RetryPolicy) -> Self {
+        self.test_unit_ready_retry = policy;
+        self
+    }
+
+    /// Set the per-class command timeouts
+    pub fn with_timeouts(mut self, timeouts: Timeouts) -> Self {
+        self.timeouts = timeouts;
+        self
+    }
+
+    pub fn timeouts(&self) -> &Timeouts {
+        &self.timeouts
+    }
+
+    pub fn read_retry(&self) -> &RetryPolicy {
+        &self.read_retry
+    }
+
+    pub fn locate_retry(&self) -> &RetryPolicy {
+        &self.locate_retry
+    }
+
+    /// Whether LOCATE uses the 16-byte CDB (LOCATE(10) without partition support)
+    pub fn uses_locate16(&self) -> bool {
+        self.allow_partition
+    }
+
+    /// Number of LOCATE(16) commands retried as LOCATE(10) since the device was opened
+    pub fn locate_fallback_count(&self) -> u64 {
+        self.locate_fallbacks.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    pub fn test_unit_ready_retry(&self) -> &RetryPolicy {
+        &self.test_unit_ready_retry
+    }
+
     /// Send SCSI command general interface (based on ScsiIoControl in C code)
     pub fn scsi_io_control(
         &self,
@@ -46,14 +156,49 @@ impl ScsiInterface {
         data_in: u8,
         timeout: u32,
         sense_buffer: Option<&mut [u8; SENSE_INFO_LEN]>,
+    ) -> Result<bool> {
+        let buffer_length = data_buffer.as_ref().map_or(0, |buf| buf.len());
+        let data_ptr = data_buffer
+            .as_mut()
+            .map_or(std::ptr::null_mut(), |buf| buf.as_mut_ptr() as *mut std::ffi::c_void);
+
+        self.scsi_io_control_raw(cdb, data_ptr, buffer_length, data_in, timeout, sense_buffer)
+    }
+
+    /// Send SCSI command with a read-only data-out buffer
+    /// The caller's data is handed to the driver directly, without copying it first
+    pub fn scsi_io_control_out(
+        &self,
+        cdb: &[u8],
+        data: &[u8],
+        timeout: u32,
+        sense_buffer: Option<&mut [u8; SENSE_INFO_LEN]>,
+    ) -> Result<bool> {
+        self.scsi_io_control_raw(
+            cdb,
+            data_out_ptr(data),
+            data.len(),
+            SCSI_IOCTL_DATA_OUT,
+            timeout,
+            sense_buffer,
+        )
+    }
+
+    /// Platform pass-through implementation shared by `scsi_io_control` and `scsi_io_control_out`
+    fn scsi_io_control_raw(
+        &self,
+        cdb: &[u8],
+        data_ptr: *mut std::ffi::c_void,
+        buffer_length: usize,
+        data_in: u8,
+        timeout: u32,
+        sense_buffer: Option<&mut [u8; SENSE_INFO_LEN]>,
     ) -> Result<bool> {
         #[cfg(windows)]
         {
             if let Some(ref device) = self.device_handle {
-                let buffer_length = data_buffer.as_ref().map_or(0, |buf| buf.len()) as ULONG;
-                let data_ptr = data_buffer
-                    .as_mut()
-                    .map_or(std::ptr::null_mut(), |buf| buf.as_mut_ptr() as PVOID);
+                let buffer_length = buffer_length as ULONG;
+                let data_ptr = data_ptr as PVOID;
 
                 // Create SCSI Pass Through Direct buffer
                 let mut scsi_buffer =
@@ -81,16 +226,18 @@ impl ScsiInterface {
                     );
 
                     let mut bytes_returned: DWORD = 0;
-                    let result = DeviceIoControl(
-                        device.handle,
-                        IOCTL_SCSI_PASS_THROUGH_DIRECT,
-                        scsi_buffer.as_mut_ptr() as PVOID,
-                        scsi_buffer.len() as DWORD,
-                        scsi_buffer.as_mut_ptr() as PVOID,
-                        scsi_buffer.len() as DWORD,
-                        &mut bytes_returned,
-                        std::ptr::null_mut(),
-                    ) != 0;
+                    let result = run_blocking(|| {
+                        DeviceIoControl(
+                            device.handle,
+                            IOCTL_SCSI_PASS_THROUGH_DIRECT,
+                            scsi_buffer.as_mut_ptr() as PVOID,
+                            scsi_buffer.len() as DWORD,
+                            scsi_buffer.as_mut_ptr() as PVOID,
+                            scsi_buffer.len() as DWORD,
+                            &mut bytes_returned,
+                            std::ptr::null_mut(),
+                        ) != 0
+                    });
 
                     // Copy sense buffer if provided
                     if let Some(sense_buf) = sense_buffer {
@@ -119,20 +266,183 @@ impl ScsiInterface {
             }
         }
 
-        #[cfg(not(windows))]
+        #[cfg(target_os = "linux")]
+        {
+            if let Some(ref device) = self.device_handle {
+                let mut sense = [0u8; SENSE_INFO_LEN];
+                let mut hdr = sg_io_header(cdb, data_ptr, buffer_length, data_in, timeout, &mut sense);
+                #[cfg(test)]
+                self.last_sg_io_timeout.store(hdr.timeout, std::sync::atomic::Ordering::Relaxed);
+
+                let ret = run_blocking(|| unsafe {
+                    libc::ioctl(device.fd, SG_IO as _, &mut hdr as *mut SgIoHdr)
+                });
+
+                // Copy sense buffer if provided
+                if let Some(sense_buf) = sense_buffer {
+                    sense_buf.copy_from_slice(&sense);
+                }
+
+                if ret < 0 {
+                    warn!(
+                        "SCSI command failed: SG_IO error {}, CDB: {:?}",
+                        std::io::Error::last_os_error(),
+                        cdb
+                    );
+                    return Ok(false);
+                }
+
+                if hdr.info & SG_INFO_OK_MASK != 0 {
+                    debug!(
+                        "SCSI command completed with status 0x{:02X} (host 0x{:04X}, driver 0x{:04X}), CDB: {:?}",
+                        hdr.status, hdr.host_status, hdr.driver_status, cdb
+                    );
+                    return Ok(false);
+                }
+
+                Ok(true)
+            } else {
+                Err(crate::error::RustLtfsError::scsi("Device not opened"))
+            }
+        }
+
+        #[cfg(not(any(windows, target_os = "linux")))]
         {
-            // Use parameters on non-Windows platforms to avoid warnings
-            let _ = (cdb, data_buffer, data_in, timeout, sense_buffer);
+            // Use parameters on unsupported platforms to avoid warnings
+            let _ = (cdb, data_ptr, buffer_length, data_in, timeout, sense_buffer);
             Err(crate::error::RustLtfsError::unsupported(
-                "Non-Windows platform",
+                "Unsupported platform",
             ))
         }
     }
 }
 
+/// Pointer handed to the driver for a DATA OUT buffer
+///
+/// The driver only reads from a DATA OUT buffer, so the pointer is never written through.
+fn data_out_ptr(data: &[u8]) -> *mut std::ffi::c_void {
+    data.as_ptr() as *mut std::ffi::c_void
+}
+
+/// Build the SG_IO header for one command; the data buffer is referenced, never copied
+#[cfg(target_os = "linux")]
+fn sg_io_header(
+    cdb: &[u8],
+    data_ptr: *mut std::ffi::c_void,
+    buffer_length: usize,
+    data_in: u8,
+    timeout: u32,
+    sense: &mut [u8; SENSE_INFO_LEN],
+) -> SgIoHdr {
+    let buffer_length = buffer_length as u32;
+
+    let (dxfer_direction, dxfer_len) = match data_in {
+        _ if buffer_length == 0 => (SG_DXFER_NONE, 0),
+        SCSI_IOCTL_DATA_IN => (SG_DXFER_FROM_DEV, buffer_length),
+        SCSI_IOCTL_DATA_OUT => (SG_DXFER_TO_DEV, buffer_length),
+        _ => (SG_DXFER_NONE, 0),
+    };
+
+    SgIoHdr {
+        interface_id: 'S' as i32,
+        dxfer_direction,
+        cmd_len: cdb.len() as u8,
+        mx_sb_len: SENSE_INFO_LEN as u8,
+        iovec_count: 0,
+        dxfer_len,
+        dxferp: data_ptr,
+        cmdp: cdb.as_ptr(),
+        sbp: sense.as_mut_ptr(),
+        timeout: timeout.saturating_mul(1000),
+        flags: 0,
+        pack_id: 0,
+        usr_ptr: std::ptr::null_mut(),
+        status: 0,
+        masked_status: 0,
+        msg_status: 0,
+        sb_len_wr: 0,
+        host_status: 0,
+        driver_status: 0,
+        resid: 0,
+        duration: 0,
+        info: 0,
+    }
+}
+
+/// Run a blocking device call without stalling the tokio runtime
+///
+/// A pass-through command can block for the whole command timeout (up to 10 minutes for
+/// LOCATE). Inside a multi-threaded runtime the worker thread is handed over with
+/// `block_in_place`, so other tasks (progress reporting, Ctrl+C handling) keep running.
+/// `spawn_blocking` is not an option: the device handle and caller buffers are borrowed.
+/// Outside a runtime, or on a current-thread runtime, the call simply runs in place.
+#[cfg(any(windows, target_os = "linux"))]
+fn run_blocking<T>(f: impl FnOnce() -> T) -> T {
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == tokio::runtime::RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(f)
+        }
+        _ => f(),
+    }
+}
+
 /// Implement Drop trait to ensure SCSI interface is properly cleaned up
 impl Drop for ScsiInterface {
     fn drop(&mut self) {
         debug!("SCSI interface cleanup completed");
     }
 }
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn data_out_buffer_is_handed_to_sg_io_without_copy() {
+        let data = vec![0x5Au8; 64 * 1024 * 1024];
+        let cdb = [0x0A, 0x00, 0x00, 0x00, 0x00, 0x00];
+        let mut sense = [0u8; SENSE_INFO_LEN];
+
+        let hdr = sg_io_header(&cdb, data_out_ptr(&data), data.len(), SCSI_IOCTL_DATA_OUT, 600, &mut sense);
+
+        // 驱动直接读取调用方的缓冲区：指针与长度都与原数据一致
+        assert_eq!(hdr.dxferp as *const u8, data.as_ptr());
+        assert_eq!(hdr.dxfer_len as usize, data.len());
+        assert_eq!(hdr.dxfer_direction, SG_DXFER_TO_DEV);
+        assert_eq!(hdr.cmdp, cdb.as_ptr());
+        assert_eq!(hdr.timeout, 600_000);
+    }
+
+    #[test]
+    fn configured_timeout_reaches_sg_io() {
+        use clap::Parser;
+
+        let cli = crate::cli::Cli::try_parse_from([
+            "rustltfs", "--locate-timeout", "1234", "info", "--tape", "/dev/null",
+        ])
+        .unwrap();
+        let mut scsi = ScsiInterface::new().with_timeouts(cli.scsi_timeouts());
+        assert_eq!(scsi.timeouts().locate, 1234);
+
+        // /dev/null不支持SG_IO：REWIND失败，但头部已按配置的超时构建
+        scsi.open_device("/dev/null").unwrap();
+        assert!(scsi.rewind().is_err());
+        assert_eq!(scsi.last_sg_io_timeout.load(std::sync::atomic::Ordering::Relaxed), 1_234_000);
+    }
+
+    #[test]
+    fn retry_builders_reach_tape_operations() {
+        let read = RetryPolicy::new(7, Duration::from_millis(5)).with_backoff_multiplier(2.0);
+        let locate = RetryPolicy::new(2, Duration::from_millis(50));
+        let tur = RetryPolicy::new(9, Duration::from_millis(100));
+        let scsi = ScsiInterface::new()
+            .with_read_retry(read)
+            .with_locate_retry(locate)
+            .with_test_unit_ready_retry(tur);
+        assert_eq!(*scsi.locate_retry(), locate);
+
+        let ops = crate::tape_ops::TapeOperations::with_backend("/dev/null", Box::new(scsi));
+        assert_eq!(ops.scsi.read_retry(), read);
+        assert_eq!(ops.scsi.test_unit_ready_retry(), tur);
+    }
+}
@@ -3,6 +3,7 @@
 //! This module handles device opening, closing, and media status checking.
 
 use crate::error::Result;
+#[cfg(any(windows, target_os = "linux"))]
 use std::ffi::CString;
 use tracing::{debug, warn};
 
@@ -25,6 +26,8 @@ use super::types::MediaType;
 pub struct DeviceHandle {
     #[cfg(windows)]
     pub(crate) handle: HANDLE,
+    #[cfg(target_os = "linux")]
+    pub(crate) fd: std::os::unix::io::RawFd,
     pub(crate) device_path: String,
 }
 
@@ -80,10 +83,35 @@ impl ScsiInterface {
             }
         }
 
-        #[cfg(not(windows))]
+        #[cfg(target_os = "linux")]
+        {
+            // Linux generic SCSI device, e.g. /dev/sg3 (commands are issued via SG_IO)
+            let path_cstring = CString::new(device_path).map_err(|e| {
+                crate::error::RustLtfsError::system(format!("Device path conversion error: {}", e))
+            })?;
+
+            let fd = unsafe { libc::open(path_cstring.as_ptr(), libc::O_RDWR) };
+            if fd < 0 {
+                return Err(crate::error::RustLtfsError::system(format!(
+                    "Cannot open device {}: {}",
+                    device_path,
+                    std::io::Error::last_os_error()
+                )));
+            }
+
+            self.device_handle = Some(DeviceHandle {
+                fd,
+                device_path: device_path.to_string(),
+            });
+
+            debug!("Device opened successfully: {}", device_path);
+            Ok(())
+        }
+
+        #[cfg(not(any(windows, target_os = "linux")))]
         {
             Err(crate::error::RustLtfsError::unsupported(
-                "Non-Windows platform",
+                "Unsupported platform",
             ))
         }
     }
@@ -92,7 +120,7 @@ impl ScsiInterface {
     pub fn check_media_status(&self) -> Result<MediaType> {
         debug!("Checking tape media status");
 
-        #[cfg(windows)]
+        #[cfg(any(windows, target_os = "linux"))]
         {
             // Step 1: Use READ POSITION to check if tape is present
             // "There doesn't appear to be a direct way to tell if there's anything in the drive,
@@ -109,26 +137,26 @@ impl ScsiInterface {
                 &cdb,
                 Some(&mut data_buffer),
                 SCSI_IOCTL_DATA_IN,
-                300, // 300 second timeout, based on C code
+                self.timeouts.locate,
                 Some(&mut sense_buffer),
             )?;
 
-            if !result {
-                return Err(crate::error::RustLtfsError::scsi(
-                    "read_position command failed",
-                ));
-            }
-
             // Check if sense buffer indicates no tape
             // C code: if (((senseBuffer[2] & 0x0F) == 0x02) && (senseBuffer[12] == 0x3A) && (senseBuffer[13] == 0x00))
-            if (sense_buffer[2] & 0x0F) == 0x02
-                && sense_buffer[12] == 0x3A
-                && sense_buffer[13] == 0x00
-            {
+            // SG_IO在CHECK CONDITION时返回false（Windows返回true并填充sense），所以先检查sense再判断result
+            let sense = super::SenseData::parse(&sense_buffer);
+            if sense.sense_key == super::SenseKey::NotReady && sense.add_key() == 0x3A00 {
                 debug!("No tape detected");
                 return Ok(MediaType::NoTape);
             }
 
+            if !result {
+                return Err(crate::error::RustLtfsError::scsi_sense(
+                    "read_position command failed",
+                    sense,
+                ));
+            }
+
             // Step 2: Use MODE SENSE 10 to get media type
             // "This will only tell us the *last* tape that was in the drive, which is why we have to do the above check first"
             cdb.fill(0);
@@ -141,7 +169,7 @@ impl ScsiInterface {
             cdb[8] = (data_buffer.len() & 0xFF) as u8; // Allocation Length LSB
 
             let result =
-                self.scsi_io_control(&cdb, Some(&mut data_buffer), SCSI_IOCTL_DATA_IN, 300, None)?;
+                self.scsi_io_control(&cdb, Some(&mut data_buffer), SCSI_IOCTL_DATA_IN, self.timeouts.mode_sense, None)?;
 
             if !result {
                 warn!("MODE_SENSE10 command failed, but tape may exist");
@@ -161,7 +189,7 @@ impl ScsiInterface {
             Ok(MediaType::from_media_type_code(media_type))
         }
 
-        #[cfg(not(windows))]
+        #[cfg(not(any(windows, target_os = "linux")))]
         {
             Err(crate::error::RustLtfsError::unsupported(
                 "Non-Windows platform",
@@ -180,5 +208,13 @@ impl Drop for DeviceHandle {
                 debug!("Device handle closed: {}", self.device_path);
             }
         }
+
+        #[cfg(target_os = "linux")]
+        unsafe {
+            if self.fd >= 0 {
+                libc::close(self.fd);
+                debug!("Device handle closed: {}", self.device_path);
+            }
+        }
     }
 }
@@ -0,0 +1,103 @@
+//! Tape Drive Discovery
+//!
+//! Enumerates the tape drives attached to the system so they can be picked by
+//! vendor, serial number or loaded cartridge instead of by raw device path.
+
+use super::{DriveInfo, ScsiInterface};
+use tracing::debug;
+
+/// Windows上探测的 `\\.\TAPEn` 数量上限
+#[cfg(windows)]
+const MAX_WINDOWS_TAPE_DEVICES: u32 = 32;
+
+/// A tape drive found on the system
+#[derive(Debug, Clone)]
+pub struct DiscoveredDrive {
+    /// Device path to pass to `--tape`
+    pub path: String,
+    pub info: DriveInfo,
+    /// Barcode of the loaded cartridge (None if no tape or no MAM barcode)
+    pub barcode: Option<String>,
+}
+
+/// 枚举系统中的磁带驱动器：打开每个候选设备并执行INQUIRY，读取已装载磁带的条码
+///
+/// 无法打开或INQUIRY失败的设备（不存在、被其他程序独占等）会被跳过。
+pub fn discover_tape_drives() -> Vec<DiscoveredDrive> {
+    candidate_paths()
+        .into_iter()
+        .filter_map(|path| {
+            let mut scsi = ScsiInterface::new().with_timeouts(super::Timeouts::current());
+            if let Err(e) = scsi.open_device(&path) {
+                debug!("Skipping {}: {}", path, e);
+                return None;
+            }
+            let info = match scsi.inquiry() {
+                Ok(info) => info,
+                Err(e) => {
+                    debug!("Skipping {}: INQUIRY failed: {}", path, e);
+                    return None;
+                }
+            };
+            let barcode = scsi
+                .read_barcode()
+                .ok()
+                .filter(|barcode| !barcode.is_empty());
+            Some(DiscoveredDrive {
+                path,
+                info,
+                barcode,
+            })
+        })
+        .collect()
+}
+
+/// 查找装有条码为 `barcode` 的磁带的驱动器（忽略大小写）
+pub fn find_drive_by_barcode(barcode: &str) -> Option<DiscoveredDrive> {
+    discover_tape_drives().into_iter().find(|drive| {
+        drive
+            .barcode
+            .as_deref()
+            .is_some_and(|loaded| loaded.eq_ignore_ascii_case(barcode))
+    })
+}
+
+/// Windows: `\\.\TAPE0` .. `\\.\TAPE31`（编号可能不连续，因此全部尝试）
+#[cfg(windows)]
+fn candidate_paths() -> Vec<String> {
+    (0..MAX_WINDOWS_TAPE_DEVICES)
+        .map(|n| format!(r"\\.\TAPE{}", n))
+        .collect()
+}
+
+/// Linux: 外设类型为1（顺序访问设备）的SCSI generic节点 `/dev/sgN`
+#[cfg(target_os = "linux")]
+fn candidate_paths() -> Vec<String> {
+    let entries = match std::fs::read_dir("/sys/class/scsi_generic") {
+        Ok(entries) => entries,
+        Err(e) => {
+            debug!("Cannot list /sys/class/scsi_generic: {}", e);
+            return Vec::new();
+        }
+    };
+
+    let mut paths: Vec<(u32, String)> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            std::fs::read_to_string(entry.path().join("device/type"))
+                .is_ok_and(|device_type| device_type.trim() == "1")
+        })
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let number = name.strip_prefix("sg")?.parse().ok()?;
+            Some((number, format!("/dev/{}", name)))
+        })
+        .collect();
+    paths.sort();
+    paths.into_iter().map(|(_, path)| path).collect()
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+fn candidate_paths() -> Vec<String> {
+    Vec::new()
+}
@@ -39,3 +39,32 @@ pub struct ScsiPassThroughDirect {
     pub sense_info_offset: ULONG,
     pub cdb: [UCHAR; 16],
 }
+
+/// Linux SG_IO request header (corresponds to struct sg_io_hdr in <scsi/sg.h>)
+#[cfg(target_os = "linux")]
+#[repr(C)]
+#[derive(Debug)]
+pub struct SgIoHdr {
+    pub interface_id: i32,
+    pub dxfer_direction: i32,
+    pub cmd_len: u8,
+    pub mx_sb_len: u8,
+    pub iovec_count: u16,
+    pub dxfer_len: u32,
+    pub dxferp: *mut std::ffi::c_void,
+    pub cmdp: *const u8,
+    pub sbp: *mut u8,
+    pub timeout: u32, // milliseconds
+    pub flags: u32,
+    pub pack_id: i32,
+    pub usr_ptr: *mut std::ffi::c_void,
+    pub status: u8,
+    pub masked_status: u8,
+    pub msg_status: u8,
+    pub sb_len_wr: u8,
+    pub host_status: u16,
+    pub driver_status: u16,
+    pub resid: i32,
+    pub duration: u32,
+    pub info: u32,
+}
@@ -7,11 +7,17 @@ pub mod constants;
 pub mod types;
 pub mod ffi;
 pub mod core;
-mod sense;
+pub mod backend;
+pub mod sense;
+mod lbp;
 mod device;
 mod commands;
+pub mod discovery;
 
 pub use constants::*;
-pub use types::{DriveType, MediaType, TapePosition, SpaceType};
+pub use types::{DataCompressionPage, DriveInfo, DriveType, EncryptionStatus, LbpMethod, MamAttribute, MediaType, PartitionModePage, RawBlock, RetryPolicy, ThroughputTracker, Timeouts, TapePosition, SpaceType, VolumeCoherencyInfo};
 pub use ffi::*;
 pub use core::ScsiInterface;
+pub use backend::TapeBackend;
+pub use sense::{SenseData, SenseKey};
+pub use discovery::{discover_tape_drives, find_drive_by_barcode};
@@ -1,6 +1,8 @@
 //! SCSI Sense Data Parsing
 //!
 //! This module handles parsing and interpretation of SCSI sense data.
+//! `SenseData` decodes fixed-format sense (response codes 0x70-0x73); the
+//! helpers below build on it for READ and WRITE outcomes.
 
 use crate::error::Result;
 use tracing::{debug, info};
@@ -8,6 +10,250 @@ use tracing::{debug, info};
 use super::constants::block_sizes;
 use super::ScsiInterface;
 
+/// Sense key (sense byte 2, bits 0-3)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SenseKey {
+    #[default]
+    NoSense,
+    RecoveredError,
+    NotReady,
+    MediumError,
+    HardwareError,
+    IllegalRequest,
+    UnitAttention,
+    DataProtect,
+    BlankCheck,
+    VendorSpecific,
+    CopyAborted,
+    AbortedCommand,
+    VolumeOverflow,
+    Miscompare,
+    Reserved(u8),
+}
+
+impl SenseKey {
+    pub fn from_code(code: u8) -> Self {
+        match code & 0x0F {
+            0x00 => SenseKey::NoSense,
+            0x01 => SenseKey::RecoveredError,
+            0x02 => SenseKey::NotReady,
+            0x03 => SenseKey::MediumError,
+            0x04 => SenseKey::HardwareError,
+            0x05 => SenseKey::IllegalRequest,
+            0x06 => SenseKey::UnitAttention,
+            0x07 => SenseKey::DataProtect,
+            0x08 => SenseKey::BlankCheck,
+            0x09 => SenseKey::VendorSpecific,
+            0x0A => SenseKey::CopyAborted,
+            0x0B => SenseKey::AbortedCommand,
+            0x0D => SenseKey::VolumeOverflow,
+            0x0E => SenseKey::Miscompare,
+            other => SenseKey::Reserved(other),
+        }
+    }
+
+    pub fn code(self) -> u8 {
+        match self {
+            SenseKey::NoSense => 0x00,
+            SenseKey::RecoveredError => 0x01,
+            SenseKey::NotReady => 0x02,
+            SenseKey::MediumError => 0x03,
+            SenseKey::HardwareError => 0x04,
+            SenseKey::IllegalRequest => 0x05,
+            SenseKey::UnitAttention => 0x06,
+            SenseKey::DataProtect => 0x07,
+            SenseKey::BlankCheck => 0x08,
+            SenseKey::VendorSpecific => 0x09,
+            SenseKey::CopyAborted => 0x0A,
+            SenseKey::AbortedCommand => 0x0B,
+            SenseKey::VolumeOverflow => 0x0D,
+            SenseKey::Miscompare => 0x0E,
+            SenseKey::Reserved(code) => code,
+        }
+    }
+
+    /// NO SENSE 或 RECOVERED ERROR：命令已完成，sense只携带状态信息
+    pub fn is_informational(self) -> bool {
+        matches!(self, SenseKey::NoSense | SenseKey::RecoveredError)
+    }
+}
+
+/// 常见LTO驱动器 ASC/ASCQ 的说明 (SPC-4 / SSC-4 附录)
+const ASC_DESCRIPTIONS: &[(u8, u8, &str)] = &[
+    (0x00, 0x00, "No additional sense information"),
+    (0x00, 0x01, "Filemark detected"),
+    (0x00, 0x02, "End-of-partition/medium detected"),
+    (0x00, 0x04, "Beginning-of-partition/medium detected"),
+    (0x00, 0x05, "End-of-data detected"),
+    (0x00, 0x07, "Programmable early warning detected"),
+    (0x00, 0x16, "Operation in progress"),
+    (0x04, 0x00, "Logical unit not ready, cause not reportable"),
+    (0x04, 0x01, "Logical unit is in process of becoming ready"),
+    (0x04, 0x02, "Logical unit not ready, initializing command required"),
+    (0x04, 0x03, "Logical unit not ready, manual intervention required"),
+    (0x04, 0x12, "Logical unit not ready, offline"),
+    (0x0C, 0x00, "Write error"),
+    (0x10, 0x01, "Logical block guard check failed"),
+    (0x11, 0x00, "Unrecovered read error"),
+    (0x14, 0x00, "Recorded entity not found"),
+    (0x14, 0x03, "End-of-data not found"),
+    (0x15, 0x01, "Mechanical positioning error"),
+    (0x1A, 0x00, "Parameter list length error"),
+    (0x20, 0x00, "Invalid command operation code"),
+    (0x24, 0x00, "Invalid field in CDB"),
+    (0x26, 0x00, "Invalid field in parameter list"),
+    (0x27, 0x00, "Write protected"),
+    (0x28, 0x00, "Not ready to ready change, medium may have changed"),
+    (0x29, 0x00, "Power on, reset, or bus device reset occurred"),
+    (0x2A, 0x01, "Mode parameters changed"),
+    (0x30, 0x00, "Incompatible medium installed"),
+    (0x30, 0x01, "Cannot read medium, unknown format"),
+    (0x30, 0x02, "Cannot read medium, incompatible format"),
+    (0x30, 0x03, "Cleaning cartridge installed"),
+    (0x30, 0x05, "Cannot write medium, incompatible format"),
+    (0x31, 0x00, "Medium format corrupted"),
+    (0x3A, 0x00, "Medium not present"),
+    (0x3B, 0x00, "Sequential positioning error"),
+    (0x3B, 0x08, "Reposition error"),
+    (0x3B, 0x0D, "Medium destination element full"),
+    (0x44, 0x00, "Internal target failure"),
+    (0x50, 0x00, "Write append error"),
+    (0x51, 0x00, "Erase failure"),
+    (0x52, 0x00, "Cartridge fault"),
+    (0x53, 0x00, "Media load or eject failed"),
+    (0x53, 0x02, "Medium removal prevented"),
+    (0x5D, 0x00, "Failure prediction threshold exceeded"),
+];
+
+/// 解码后的定长格式sense数据 (SPC-4 4.5.3)
+///
+/// 缓冲区过短时缺失的字段为0，因此未返回sense的成功命令解析为 NO SENSE 0x00/0x00。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SenseData {
+    /// Response code (byte 0, bits 0-6)；0x70/0x71 为定长格式
+    pub response_code: u8,
+    /// VALID位：information字段有效
+    pub valid: bool,
+    pub sense_key: SenseKey,
+    /// Additional Sense Code (byte 12)
+    pub asc: u8,
+    /// Additional Sense Code Qualifier (byte 13)
+    pub ascq: u8,
+    /// FILEMARK位 (byte 2 bit 7)
+    pub filemark: bool,
+    /// EOM位 (byte 2 bit 6)
+    pub eom: bool,
+    /// ILI位 (byte 2 bit 5)
+    pub ili: bool,
+    /// Information字段 (bytes 3-6)，READ时为有符号的residue（LTFSCopyGUI的DiffBytes）
+    pub information: i32,
+}
+
+impl SenseData {
+    /// 解析定长格式sense数据，缺失的字节按0处理
+    pub fn parse(sense_data: &[u8]) -> Self {
+        let byte = |index: usize| sense_data.get(index).copied().unwrap_or(0);
+        Self {
+            response_code: byte(0) & 0x7F,
+            valid: byte(0) & 0x80 != 0,
+            sense_key: SenseKey::from_code(byte(2)),
+            asc: byte(12),
+            ascq: byte(13),
+            filemark: byte(2) & 0x80 != 0,
+            eom: byte(2) & 0x40 != 0,
+            ili: byte(2) & 0x20 != 0,
+            information: i32::from_be_bytes([byte(3), byte(4), byte(5), byte(6)]),
+        }
+    }
+
+    /// 驱动器是否返回了定长格式sense (response code 0x70-0x73)
+    pub fn is_present(&self) -> bool {
+        (0x70..=0x73).contains(&self.response_code)
+    }
+
+    /// 重试可能成功的失败：UNIT ATTENTION、ABORTED COMMAND、NOT READY中的正在就绪 (04/01)，
+    /// 以及命令失败却没有返回sense（超时、总线复位等传输层故障）
+    pub fn is_transient(&self) -> bool {
+        match self.sense_key {
+            SenseKey::UnitAttention | SenseKey::AbortedCommand => true,
+            SenseKey::NotReady => self.add_key() == 0x0401,
+            SenseKey::NoSense => !self.is_present(),
+            _ => false,
+        }
+    }
+
+    /// ASC/ASCQ合并为一个值 (LTFSCopyGUI的Add_Key: sense(12) << 8 Or sense(13))
+    pub fn add_key(&self) -> u16 {
+        ((self.asc as u16) << 8) | self.ascq as u16
+    }
+
+    /// ASC/ASCQ的说明，不在表中时为None
+    pub fn description(&self) -> Option<&'static str> {
+        ASC_DESCRIPTIONS
+            .iter()
+            .find(|(asc, ascq, _)| *asc == self.asc && *ascq == self.ascq)
+            .map(|(_, _, description)| *description)
+    }
+}
+
+impl std::fmt::Display for SenseData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:?} (0x{:02X}), ASC/ASCQ 0x{:02X}/0x{:02X}",
+            self.sense_key,
+            self.sense_key.code(),
+            self.asc,
+            self.ascq
+        )?;
+        if let Some(description) = self.description() {
+            write!(f, ": {}", description)?;
+        }
+        Ok(())
+    }
+}
+
+/// READ命令失败时sense数据的分析结果
+pub(super) struct ReadSenseOutcome {
+    /// 实际读取的块数
+    pub blocks_read: u32,
+    /// 实际读取的字节数（请求长度减去sense中的residue）
+    pub bytes_read: u32,
+    /// 遇到FileMark或EOD/EOM，读取应停止
+    pub file_mark: bool,
+    /// 变长模式下读到比缓冲区短的块（ILI），数据有效
+    pub short_block: bool,
+    /// 停在EOD/分区末尾而不是FileMark上（BLANK CHECK、00/05 或 EOM）
+    pub end_of_data: bool,
+}
+
+/// WRITE / WRITE FILEMARKS 到达介质末端时的sense分类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum EndOfMediaSense {
+    /// 进入early-warning区域（EOM位或 00/02、00/07，sense key为NO SENSE/RECOVERED ERROR）：数据已写入
+    EarlyWarning,
+    /// VOLUME OVERFLOW (sense key 0x0D)：数据未写入
+    VolumeOverflow,
+}
+
+/// 从写命令的sense数据中识别early-warning EOM或卷溢出
+pub(super) fn end_of_media_sense(sense_data: &[u8]) -> Option<EndOfMediaSense> {
+    let sense = SenseData::parse(sense_data);
+    if sense_data.len() < 14 || sense.response_code < 0x70 {
+        return None;
+    }
+
+    match sense.sense_key {
+        SenseKey::VolumeOverflow => Some(EndOfMediaSense::VolumeOverflow),
+        key if key.is_informational()
+            && (sense.eom || (sense.asc == 0x00 && matches!(sense.ascq, 0x02 | 0x07))) =>
+        {
+            Some(EndOfMediaSense::EarlyWarning)
+        }
+        _ => None,
+    }
+}
+
 impl ScsiInterface {
     /// Parse sense data for Test Unit Ready (similar to LTFSCopyGUI's ParseSenseData)
     pub fn parse_sense_data(&self, sense_data: &[u8]) -> String {
@@ -15,72 +261,66 @@ impl ScsiInterface {
             return "Invalid sense data (too short)".to_string();
         }
 
-        let sense_key = sense_data[2] & 0x0F;
-        let asc = if sense_data.len() > 12 {
-            sense_data[12]
-        } else {
-            0
-        };
-        let ascq = if sense_data.len() > 13 {
-            sense_data[13]
-        } else {
-            0
-        };
-
+        let sense = SenseData::parse(sense_data);
         debug!(
             "Sense data - Key: 0x{:02X}, ASC: 0x{:02X}, ASCQ: 0x{:02X}",
-            sense_key, asc, ascq
+            sense.sense_key.code(),
+            sense.asc,
+            sense.ascq
         );
 
-        match (sense_key, asc, ascq) {
-            (0x00, _, _) => "Device ready".to_string(),
-            (0x02, 0x3A, 0x00) => "No tape loaded".to_string(),
-            (0x02, 0x04, 0x00) => "Drive not ready".to_string(),
-            (0x02, 0x3B, 0x0D) => "Medium not present".to_string(),
-            (0x04, 0x00, 0x00) => "Drive not ready - becoming ready".to_string(),
-            (0x06, 0x28, 0x00) => "Unit attention - not ready to ready transition".to_string(),
+        match (sense.sense_key, sense.asc, sense.ascq) {
+            (SenseKey::NoSense, _, _) => "Device ready".to_string(),
+            (SenseKey::NotReady, 0x3A, 0x00) => "No tape loaded".to_string(),
+            (SenseKey::NotReady, 0x04, 0x00) => "Drive not ready".to_string(),
+            (SenseKey::NotReady, 0x3B, 0x0D) => "Medium not present".to_string(),
+            (SenseKey::HardwareError, 0x00, 0x00) => "Drive not ready - becoming ready".to_string(),
+            (SenseKey::UnitAttention, 0x28, 0x00) => {
+                "Unit attention - not ready to ready transition".to_string()
+            }
             _ => format!(
-                "Device not ready - Sense Key: 0x{:02X}, ASC/ASCQ: 0x{:02X}/0x{:02X}",
-                sense_key, asc, ascq
+                "Device not ready - Sense Key: 0x{:02X}, ASC/ASCQ: 0x{:02X}/0x{:02X}{}",
+                sense.sense_key.code(),
+                sense.asc,
+                sense.ascq,
+                sense
+                    .description()
+                    .map(|description| format!(" ({})", description))
+                    .unwrap_or_default()
             ),
         }
     }
 
     /// 分析READ命令的sense数据 (对应LTFSCopyGUI的ReadBlock中的sense数据分析)
-    /// 返回 (实际读取的块数, 是否遇到文件标记)
     pub(super) fn analyze_read_sense_data(
         &self,
         sense_data: &[u8],
         requested_bytes: u32,
-    ) -> Result<(u32, bool)> {
+    ) -> Result<ReadSenseOutcome> {
         if sense_data.len() < 18 {
-            return Ok((0, false));
+            return Ok(ReadSenseOutcome {
+                blocks_read: 0,
+                bytes_read: 0,
+                file_mark: false,
+                short_block: false,
+                end_of_data: false,
+            });
         }
 
         // 分析sense key和additional sense code (对应VB.NET的Add_Key检测)
-        let sense_key = sense_data[2] & 0x0F;
-        let asc = sense_data[12]; // Additional Sense Code
-        let ascq = sense_data[13]; // Additional Sense Code Qualifier
+        let sense = SenseData::parse(sense_data);
+        let sense_key = sense.sense_key;
 
         info!(
             "🔍 Sense analysis: SenseKey=0x{:02X}, ASC=0x{:02X}, ASCQ=0x{:02X}",
-            sense_key, asc, ascq
+            sense_key.code(),
+            sense.asc,
+            sense.ascq
         );
 
         // 从sense数据的字节3-6提取DiffBytes (对应VB.NET代码的逻辑)
-        // 修复符号位扩展：直接构造 32 位有符号整数，并进行符号位扩展
-        // VB.NET 中 DiffBytes 是 Integer (32位有符号)，会自动处理符号位扩展
-        let diff_bytes = if sense_data.len() >= 7 {
-            // 构造 32 位大端序有符号整数
-            let mut bytes = [0u8; 4];
-            bytes[0] = sense_data[3];
-            bytes[1] = sense_data[4];
-            bytes[2] = sense_data[5];
-            bytes[3] = sense_data[6];
-            i32::from_be_bytes(bytes)
-        } else {
-            0
-        };
+        // VB.NET 中 DiffBytes 是 Integer (32位有符号)，information字段按有符号数解析
+        let diff_bytes = sense.information;
 
         info!(
             "🔍 DiffBytes from sense data: {} (requested {} bytes)",
@@ -114,34 +354,39 @@ impl ScsiInterface {
             actual_bytes_read, actual_blocks_read
         );
 
-        // 检测文件标记 (对应VB.NET的Add_Key >= 1 And Add_Key <> 4逻辑)
-        // VB.NET: Add_Key = (sense(12) << 8) Or sense(13)
-        let add_key = ((asc as u16) << 8) | (ascq as u16);
-        let is_file_mark = add_key >= 1 && add_key != 4;
+        // 检测文件标记/EOD：sense字节2的FILEMARK(bit7)/EOM(bit6)位、BLANK CHECK(EOD)，
+        // 以及NO SENSE下的LTFSCopyGUI Add_Key >= 1 And Add_Key <> 4逻辑
+        // (00/01 FileMark, 00/02 EOM, 00/05 EOD；00/04 为分区开头，不算)
+        let add_key = sense.add_key();
+
+        let is_file_mark = sense.filemark
+            || sense.eom
+            || sense_key == SenseKey::BlankCheck // EOD reached
+            || (sense_key.is_informational() && add_key >= 1 && add_key != 4);
+
+        // 变长块短读：ILI置位且没有FileMark，读到的数据有效
+        let is_short_block = !is_file_mark
+            && sense.ili
+            && sense_key.is_informational()
+            && actual_bytes_read > 0;
 
         if is_file_mark {
             info!(
-                "🎯 File mark detected: Add_Key=0x{:04X} (ASC:0x{:02X}, ASCQ:0x{:02X})",
-                add_key, asc, ascq
+                "🎯 File mark/EOD detected: FM={}, EOM={}, Add_Key=0x{:04X} (ASC:0x{:02X}, ASCQ:0x{:02X}) - {} blocks read before mark",
+                sense.filemark, sense.eom, add_key, sense.asc, sense.ascq, actual_blocks_read
             );
+        } else if is_short_block {
+            debug!("Short block read (ILI): {} bytes", actual_bytes_read);
         } else {
             info!("Normal data read: Add_Key=0x{:04X}", add_key);
         }
 
-        // 特殊情况：如果sense key表示文件标记或EOD
-        let is_filemark_or_eod = sense_key == 0x00 || // No Sense (可能遇到文件标记)
-                                sense_key == 0x01 || // Recovered Error
-                                (sense_key == 0x03 && asc == 0x00 && ascq == 0x01); // Filemark detected
-
-        let final_is_file_mark = is_file_mark || is_filemark_or_eod;
-
-        if final_is_file_mark {
-            info!(
-                "✅ Final determination: FILE MARK detected - {} blocks read before mark",
-                actual_blocks_read
-            );
-        }
-
-        Ok((actual_blocks_read, final_is_file_mark))
+        Ok(ReadSenseOutcome {
+            blocks_read: actual_blocks_read,
+            bytes_read: actual_bytes_read,
+            file_mark: is_file_mark,
+            short_block: is_short_block,
+            end_of_data: sense.eom || sense_key == SenseKey::BlankCheck || add_key == 0x0005,
+        })
     }
 }
@@ -31,6 +31,36 @@ pub enum MediaType {
 }
 
 impl MediaType {
+    /// WORM cartridge (media type code bit 0x100): data can only be appended
+    pub fn is_worm(&self) -> bool {
+        matches!(
+            self,
+            MediaType::Lto3Worm
+                | MediaType::Lto4Worm
+                | MediaType::Lto5Worm
+                | MediaType::Lto6Worm
+                | MediaType::Lto7Worm
+                | MediaType::Lto8Worm
+                | MediaType::Lto9Worm
+                | MediaType::LtoM8Worm
+        )
+    }
+
+    /// Write-protected cartridge (MODE SENSE WP bit, code bit 0x200)
+    pub fn is_write_protected(&self) -> bool {
+        matches!(
+            self,
+            MediaType::Lto3Ro
+                | MediaType::Lto4Ro
+                | MediaType::Lto5Ro
+                | MediaType::Lto6Ro
+                | MediaType::Lto7Ro
+                | MediaType::Lto8Ro
+                | MediaType::Lto9Ro
+                | MediaType::LtoM8Ro
+        )
+    }
+
     /// Convert from media type code to media type
     pub fn from_media_type_code(code: u16) -> Self {
         match code {
@@ -65,7 +95,7 @@ impl MediaType {
 
 
 /// Tape position information structure
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct TapePosition {
     pub partition: u8,
     pub block_number: u64,
@@ -73,14 +103,302 @@ pub struct TapePosition {
     pub set_number: u64,
     pub end_of_data: bool,
     pub beginning_of_partition: bool,
+    /// Blocks held in the drive buffer, not yet written to media (extended READ POSITION only)
+    pub blocks_in_buffer: Option<u64>,
+    /// Bytes held in the drive buffer, not yet written to media (extended READ POSITION only)
+    pub bytes_in_buffer: Option<u64>,
+}
+
+
+
+/// Partition capacity read from cartridge memory (MAM), in bytes
+#[derive(Debug, Clone, Copy)]
+pub struct MamCapacity {
+    pub remaining: u64,
+    pub maximum: u64,
+}
+
+/// Tape data encryption status (SECURITY PROTOCOL IN, Data Encryption Status page 0x0020)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EncryptionStatus {
+    /// Encryption mode: 0x00 disabled, 0x01 external, 0x02 encrypt
+    pub encryption_mode: u8,
+    /// Decryption mode: 0x00 disabled, 0x01 raw, 0x02 decrypt, 0x03 mixed
+    pub decryption_mode: u8,
+    /// Algorithm index in use (from the Data Encryption Capabilities page)
+    pub algorithm_index: u8,
+    /// Incremented by the drive each time a key is set
+    pub key_instance_counter: u32,
+}
+
+impl EncryptionStatus {
+    /// Whether written data is currently being encrypted
+    pub fn is_encrypting(&self) -> bool {
+        self.encryption_mode == 0x02
+    }
+}
+
+/// Medium Partition mode page (MODE SENSE page 0x11)
+#[derive(Debug, Clone, Default)]
+pub struct PartitionModePage {
+    /// Maximum Additional Partitions supported by the drive/medium
+    pub max_additional_partitions: u8,
+    /// Additional Partitions Defined (0 = single partition)
+    pub additional_partitions_defined: u8,
+    /// Fixed Data Partitions
+    pub fdp: bool,
+    /// Select Data Partitions
+    pub sdp: bool,
+    /// Initiator Defined Partitions
+    pub idp: bool,
+    /// Partition Size Unit of Measure (0 = bytes, 1 = KB, 2 = MB, 3 = 10^units bytes)
+    pub psum: u8,
+    /// Partition Units exponent, used when PSUM = 3
+    pub partition_units: u8,
+    /// Partition sizes in PSUM units, one entry per partition
+    pub partition_sizes: Vec<u16>,
+}
+
+impl PartitionModePage {
+    /// Number of partitions on the medium
+    pub fn partition_count(&self) -> u8 {
+        self.additional_partitions_defined + 1
+    }
+
+    /// Whether the medium is partitioned (LTFS index + data partitions)
+    pub fn is_multi_partition(&self) -> bool {
+        self.additional_partitions_defined > 0
+    }
+}
+
+/// Data Compression mode page (MODE SENSE page 0x0F)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DataCompressionPage {
+    /// Data Compression Enable: the drive compresses data on write
+    pub dce: bool,
+    /// Data Compression Capable: the drive supports hardware compression
+    pub dcc: bool,
+}
+
+/// Logical Block Protection method (Control Data Protection mode page 0x0A/0xF0)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LbpMethod {
+    /// No protection information is transferred with the blocks
+    #[default]
+    None,
+    /// CRC32C (Castagnoli), 4 bytes appended to every block
+    Crc32c,
+}
+
+impl LbpMethod {
+    /// LOGICAL BLOCK PROTECTION METHOD field value
+    pub fn code(self) -> u8 {
+        match self {
+            LbpMethod::None => 0x00,
+            LbpMethod::Crc32c => 0x02,
+        }
+    }
+
+    /// Bytes of protection information appended to each block
+    pub fn info_len(self) -> usize {
+        match self {
+            LbpMethod::None => 0,
+            LbpMethod::Crc32c => 4,
+        }
+    }
 }
 
+impl std::str::FromStr for LbpMethod {
+    type Err = String;
 
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "off" | "none" => Ok(LbpMethod::None),
+            "crc32c" => Ok(LbpMethod::Crc32c),
+            "rs-crc" | "reed-solomon" => Err(
+                "Reed-Solomon CRC protection (LTO-5/6) is not supported; use crc32c (LTO-7 and later) or off".to_string(),
+            ),
+            _ => Err(format!("unknown protection method '{}' (expected crc32c or off)", s)),
+        }
+    }
+}
+
+/// Result of reading a single block in variable block mode (`read_raw_block`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RawBlock {
+    /// A data block of this many bytes
+    Data(usize),
+    /// A filemark; the tape is now positioned after it
+    FileMark,
+    /// End of data (or end of partition) - nothing more to read
+    EndOfData,
+}
+
+/// MAM attribute: MEDIUM MANUFACTURE DATE (ASCII, YYYYMMDD)
+pub const MAM_MEDIUM_MANUFACTURE_DATE: u16 = 0x0406;
+/// MAM attribute: APPLICATION VENDOR (ASCII)
+pub const MAM_APPLICATION_VENDOR: u16 = 0x0800;
+/// MAM attribute: APPLICATION NAME (ASCII)
+pub const MAM_APPLICATION_NAME: u16 = 0x0801;
+/// MAM attribute: VOLUME CHANGE REFERENCE (binary, changes whenever the partition is written)
+pub const MAM_VOLUME_CHANGE_REFERENCE: u16 = 0x0009;
+/// MAM attribute: VOLUME COHERENCY INFORMATION (binary, per partition)
+pub const MAM_VOLUME_COHERENCY_INFORMATION: u16 = 0x080C;
+
+/// Application client specific information tag used by LTFS in the VCI attribute
+const VCI_LTFS_TAG: &[u8; 5] = b"LTFS\0";
+/// LTFS VCI application client specific information: tag, NUL-terminated UUID, version
+const VCI_LTFS_INFO_LEN: usize = 5 + 37 + 1;
+
+/// Volume Coherency Information (MAM attribute 0x080C) as written by LTFS
+///
+/// Records, per partition, which index generation was last committed and where it
+/// starts, so the latest index can be found without scanning the partition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VolumeCoherencyInfo {
+    /// Volume change reference of the partition when the index was written
+    pub volume_change_reference: u64,
+    /// Generation number of the index (volume coherency count)
+    pub generation: u64,
+    /// First block of the index (volume coherency set identifier)
+    pub block: u64,
+    pub volume_uuid: String,
+}
+
+impl VolumeCoherencyInfo {
+    /// Encode as the attribute value (70 bytes)
+    pub fn to_attribute_value(&self) -> Vec<u8> {
+        let mut value = Vec::with_capacity(27 + VCI_LTFS_INFO_LEN);
+        value.push(8);
+        value.extend_from_slice(&self.volume_change_reference.to_be_bytes());
+        value.extend_from_slice(&self.generation.to_be_bytes());
+        value.extend_from_slice(&self.block.to_be_bytes());
+        value.extend_from_slice(&(VCI_LTFS_INFO_LEN as u16).to_be_bytes());
+        value.extend_from_slice(VCI_LTFS_TAG);
+        let mut uuid = [0u8; 37];
+        let uuid_bytes = self.volume_uuid.as_bytes();
+        let len = std::cmp::min(uuid_bytes.len(), 36);
+        uuid[..len].copy_from_slice(&uuid_bytes[..len]);
+        value.extend_from_slice(&uuid);
+        value.push(1); // VCI version
+        value
+    }
+
+    /// Decode an attribute value; returns None if it was not written by LTFS
+    pub fn from_attribute_value(value: &[u8]) -> Option<Self> {
+        let vcr_len = *value.first()? as usize;
+        let counts = 1 + vcr_len;
+        let info_len_at = counts + 16;
+        let info_at = info_len_at + 2;
+        let info_len = u16::from_be_bytes([*value.get(info_len_at)?, *value.get(info_len_at + 1)?]) as usize;
+        let info = value.get(info_at..info_at + info_len)?;
+        if vcr_len > 8 || info_len < VCI_LTFS_INFO_LEN || !info.starts_with(VCI_LTFS_TAG) {
+            return None;
+        }
+
+        let be_u64 = |bytes: &[u8]| bytes.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64);
+        let uuid = &info[5..42];
+        let uuid_len = uuid.iter().position(|&b| b == 0).unwrap_or(uuid.len());
+        Some(Self {
+            volume_change_reference: be_u64(&value[1..counts]),
+            generation: be_u64(&value[counts..counts + 8]),
+            block: be_u64(&value[counts + 8..counts + 16]),
+            volume_uuid: String::from_utf8_lossy(&uuid[..uuid_len]).to_string(),
+        })
+    }
+}
+
+/// MAM attribute value format (low bits of the READ ATTRIBUTE format byte)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MamAttributeFormat {
+    Binary,
+    Ascii,
+    Text,
+    Reserved(u8),
+}
+
+impl MamAttributeFormat {
+    /// Convert from the attribute header format byte
+    pub fn from_format_byte(format: u8) -> Self {
+        match format & 0x03 {
+            0x00 => MamAttributeFormat::Binary,
+            0x01 => MamAttributeFormat::Ascii,
+            0x02 => MamAttributeFormat::Text,
+            other => MamAttributeFormat::Reserved(other),
+        }
+    }
+}
+
+/// Single attribute read from cartridge memory (MAM)
+#[derive(Debug, Clone)]
+pub struct MamAttribute {
+    pub id: u16,
+    pub format: MamAttributeFormat,
+    pub read_only: bool,
+    pub value: Vec<u8>,
+}
+
+impl MamAttribute {
+    /// Standard attribute name (SPC-4 / LTO cartridge memory), if known
+    pub fn name(&self) -> Option<&'static str> {
+        match self.id {
+            0x0000 => Some("Remaining Capacity In Partition"),
+            0x0001 => Some("Maximum Capacity In Partition"),
+            0x0002 => Some("TapeAlert Flags"),
+            0x0003 => Some("Load Count"),
+            0x0004 => Some("MAM Space Remaining"),
+            MAM_VOLUME_CHANGE_REFERENCE => Some("Volume Change Reference"),
+            0x0220 => Some("Total MBytes Written In Medium Life"),
+            0x0221 => Some("Total MBytes Read In Medium Life"),
+            0x0400 => Some("Medium Manufacturer"),
+            0x0401 => Some("Medium Serial Number"),
+            MAM_MEDIUM_MANUFACTURE_DATE => Some("Medium Manufacture Date"),
+            0x0408 => Some("Medium Type"),
+            MAM_APPLICATION_VENDOR => Some("Application Vendor"),
+            MAM_APPLICATION_NAME => Some("Application Name"),
+            0x0802 => Some("Application Version"),
+            0x0803 => Some("User Medium Text Label"),
+            0x0806 => Some("Barcode"),
+            MAM_VOLUME_COHERENCY_INFORMATION => Some("Volume Coherency Information"),
+            _ => None,
+        }
+    }
+
+    /// Human-readable value: text attributes are trimmed, the manufacture date is
+    /// shown as YYYY-MM-DD, binary values up to 8 bytes as integers, otherwise hex
+    pub fn decoded_value(&self) -> String {
+        match self.format {
+            MamAttributeFormat::Ascii | MamAttributeFormat::Text => {
+                let text = String::from_utf8_lossy(&self.value);
+                let text = text.trim_end_matches('\0').trim();
+                // 有损解码后可能含多字节字符，只有纯ASCII时才按字节切分日期
+                if self.id == MAM_MEDIUM_MANUFACTURE_DATE && text.len() == 8 && text.is_ascii() {
+                    format!("{}-{}-{}", &text[0..4], &text[4..6], &text[6..8])
+                } else {
+                    text.to_string()
+                }
+            }
+            _ if self.value.len() <= 8 => self
+                .value
+                .iter()
+                .fold(0u64, |acc, &byte| (acc << 8) | byte as u64)
+                .to_string(),
+            _ => self
+                .value
+                .iter()
+                .map(|byte| format!("{:02X}", byte))
+                .collect::<Vec<_>>()
+                .join(" "),
+        }
+    }
+}
 
 /// Space types for SPACE command
 #[derive(Debug, Clone, Copy)]
 pub enum SpaceType {
 
+    Blocks = 0,
+
     FileMarks = 1,
 
     EndOfData = 3,
@@ -106,6 +424,270 @@ pub enum DriveType {
 
 }
 
+impl DriveType {
+    /// Select the drive type from INQUIRY vendor/product identification
+    ///
+    /// All LTO drives (IBM, HPE, Quantum, Tandberg) use the standard command set;
+    /// unrecognized drives also fall back to it.
+    pub fn from_inquiry(info: &DriveInfo) -> Self {
+        let product = info.product_id.to_ascii_uppercase();
+        if !product.contains("LTO") && !product.starts_with("ULT") {
+            tracing::debug!(
+                "Unrecognized drive {} {}, using standard command set",
+                info.vendor_id,
+                info.product_id
+            );
+        }
+        DriveType::Standard
+    }
+}
+
+/// Retry/backoff policy for a class of SCSI operations
+///
+/// Delay before retry `n` (1-based) is `base_delay * backoff_multiplier^(n-1)`, capped at `max_delay`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: std::time::Duration,
+    pub max_delay: std::time::Duration,
+    pub backoff_multiplier: f64,
+}
+
+impl RetryPolicy {
+    /// Policy with a fixed delay between retries
+    pub fn new(max_retries: u32, delay: std::time::Duration) -> Self {
+        Self {
+            max_retries,
+            base_delay: delay,
+            max_delay: delay,
+            backoff_multiplier: 1.0,
+        }
+    }
+
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    pub fn with_base_delay(mut self, base_delay: std::time::Duration) -> Self {
+        self.base_delay = base_delay;
+        self.max_delay = self.max_delay.max(base_delay);
+        self
+    }
+
+    pub fn with_max_delay(mut self, max_delay: std::time::Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    pub fn with_backoff_multiplier(mut self, backoff_multiplier: f64) -> Self {
+        self.backoff_multiplier = backoff_multiplier.max(1.0);
+        self
+    }
+
+    /// Delay to wait before retry `retry` (1-based)
+    pub fn delay_for(&self, retry: u32) -> std::time::Duration {
+        let factor = self
+            .backoff_multiplier
+            .powi(retry.saturating_sub(1).min(i32::MAX as u32) as i32);
+        let delay = self.base_delay.mul_f64(factor.min(u32::MAX as f64));
+        delay.min(self.max_delay)
+    }
+
+    /// Apply overrides from `RUSTLTFS_<NAME>_RETRIES`, `_DELAY_MS`, `_MAX_DELAY_MS` and `_BACKOFF`
+    /// environment variables, so a flaky path can be tuned without recompiling
+    pub fn with_env_overrides(mut self, name: &str) -> Self {
+        let var = |suffix: &str| std::env::var(format!("RUSTLTFS_{}_{}", name, suffix)).ok();
+        if let Some(retries) = var("RETRIES").and_then(|v| v.parse().ok()) {
+            self = self.with_max_retries(retries);
+        }
+        if let Some(ms) = var("DELAY_MS").and_then(|v| v.parse().ok()) {
+            self = self.with_base_delay(std::time::Duration::from_millis(ms));
+        }
+        if let Some(ms) = var("MAX_DELAY_MS").and_then(|v| v.parse().ok()) {
+            self = self.with_max_delay(std::time::Duration::from_millis(ms));
+        }
+        if let Some(multiplier) = var("BACKOFF").and_then(|v| v.parse().ok()) {
+            self = self.with_backoff_multiplier(multiplier);
+        }
+        self
+    }
+}
+
+/// Per-class SCSI command timeouts in seconds
+///
+/// READ/WRITE use their value as the conservative limit that `ThroughputTracker`
+/// shortens once the drive's throughput is known.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timeouts {
+    /// READ and READ(6) file mark probes
+    pub read: u32,
+    /// WRITE and WRITE FILEMARKS
+    pub write: u32,
+    /// LOCATE, REWIND, LOAD/UNLOAD and READ POSITION
+    pub locate: u32,
+    /// SPACE
+    pub space: u32,
+    /// MODE SENSE/SELECT and the other short drive queries (LOG SENSE, attributes, security protocol)
+    pub mode_sense: u32,
+    /// INQUIRY and TEST UNIT READY
+    pub inquiry: u32,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            read: 300,
+            write: 600,
+            locate: 600,
+            space: 600,
+            mode_sense: 30,
+            inquiry: 30,
+        }
+    }
+}
+
+/// 进程级超时设置，由命令行参数安装，之后创建的ScsiInterface都使用它
+static INSTALLED_TIMEOUTS: std::sync::OnceLock<Timeouts> = std::sync::OnceLock::new();
+
+impl Timeouts {
+    pub fn with_read(mut self, secs: u32) -> Self {
+        self.read = secs.max(1);
+        self
+    }
+
+    pub fn with_write(mut self, secs: u32) -> Self {
+        self.write = secs.max(1);
+        self
+    }
+
+    pub fn with_locate(mut self, secs: u32) -> Self {
+        self.locate = secs.max(1);
+        self
+    }
+
+    pub fn with_space(mut self, secs: u32) -> Self {
+        self.space = secs.max(1);
+        self
+    }
+
+    pub fn with_mode_sense(mut self, secs: u32) -> Self {
+        self.mode_sense = secs.max(1);
+        self
+    }
+
+    pub fn with_inquiry(mut self, secs: u32) -> Self {
+        self.inquiry = secs.max(1);
+        self
+    }
+
+    /// Apply overrides from `RUSTLTFS_TIMEOUT_{READ,WRITE,LOCATE,SPACE,MODE_SENSE,INQUIRY}`
+    /// environment variables (seconds)
+    pub fn with_env_overrides(mut self) -> Self {
+        let var = |class: &str| {
+            std::env::var(format!("RUSTLTFS_TIMEOUT_{}", class))
+                .ok()
+                .and_then(|v| v.parse::<u32>().ok())
+        };
+        if let Some(secs) = var("READ") {
+            self = self.with_read(secs);
+        }
+        if let Some(secs) = var("WRITE") {
+            self = self.with_write(secs);
+        }
+        if let Some(secs) = var("LOCATE") {
+            self = self.with_locate(secs);
+        }
+        if let Some(secs) = var("SPACE") {
+            self = self.with_space(secs);
+        }
+        if let Some(secs) = var("MODE_SENSE") {
+            self = self.with_mode_sense(secs);
+        }
+        if let Some(secs) = var("INQUIRY") {
+            self = self.with_inquiry(secs);
+        }
+        self
+    }
+
+    /// Use these timeouts for every `TapeOperations::new` and drive discovery afterwards; only the first call
+    /// takes effect (returns false if timeouts were already installed)
+    pub fn install(self) -> bool {
+        INSTALLED_TIMEOUTS.set(self).is_ok()
+    }
+
+    /// Installed timeouts, or the defaults with environment overrides
+    pub fn current() -> Self {
+        INSTALLED_TIMEOUTS
+            .get()
+            .copied()
+            .unwrap_or_else(|| Self::default().with_env_overrides())
+    }
+}
+
+/// Moving-average data transfer rate of the drive, used to size READ/WRITE timeouts
+///
+/// Until enough transfers have been timed the conservative fixed timeouts are used;
+/// after that a transfer may take at most `TIMEOUT_FACTOR` times its expected duration
+/// (never less than `MIN_TIMEOUT_SECS`, never more than the conservative value), so a
+/// hung command on a fast drive is detected in about a minute instead of 5-10.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ThroughputTracker {
+    bytes_per_sec: Option<f64>,
+    samples: u64,
+}
+
+impl ThroughputTracker {
+    /// Weight of the newest sample in the exponential moving average
+    const SMOOTHING: f64 = 0.1;
+    /// Timed transfers required before timeouts adapt
+    const MIN_SAMPLES: u64 = 16;
+    const TIMEOUT_FACTOR: f64 = 10.0;
+    const MIN_TIMEOUT_SECS: u32 = 60;
+
+    /// Record a successful transfer of `bytes` that took `elapsed`
+    pub fn record(&mut self, bytes: usize, elapsed: std::time::Duration) {
+        let secs = elapsed.as_secs_f64();
+        if bytes == 0 || secs <= 0.0 {
+            return;
+        }
+        let rate = bytes as f64 / secs;
+        self.bytes_per_sec = Some(match self.bytes_per_sec {
+            Some(average) => average + Self::SMOOTHING * (rate - average),
+            None => rate,
+        });
+        self.samples += 1;
+    }
+
+    /// Average transfer rate in MiB/s, once a transfer has been timed
+    pub fn mbps(&self) -> Option<f64> {
+        self.bytes_per_sec.map(|rate| rate / (1024.0 * 1024.0))
+    }
+
+    /// Timeout (seconds) for a transfer of `bytes`, at most `conservative`
+    pub fn timeout_for(&self, bytes: usize, conservative: u32) -> u32 {
+        match self.bytes_per_sec {
+            Some(rate) if self.samples >= Self::MIN_SAMPLES => {
+                let expected = bytes as f64 / rate * Self::TIMEOUT_FACTOR;
+                (expected.ceil().min(u32::MAX as f64) as u32)
+                    .max(Self::MIN_TIMEOUT_SECS)
+                    .min(conservative)
+            }
+            _ => conservative,
+        }
+    }
+}
+
+/// Drive identification from INQUIRY (standard data + Unit Serial Number VPD page 0x80)
+#[derive(Debug, Clone, Default)]
+pub struct DriveInfo {
+    pub vendor_id: String,
+    pub product_id: String,
+    pub product_revision: String,
+    /// None if the drive does not support VPD page 0x80
+    pub serial_number: Option<String>,
+}
+
 
 
 
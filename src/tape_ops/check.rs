@@ -0,0 +1,299 @@
+//! Tape Integrity Check
+//!
+//! A quick scan run before trusting a received tape, far lighter than reading every
+//! byte with `verify`: the loaded index is checked against the validator rules, the
+//! partition labels must carry the index's volume UUID, a sample of file extents is
+//! located and its first block read, and EOD must be reachable in every partition.
+
+use crate::error::{Result, RustLtfsError};
+use crate::ltfs_index::{Directory, FileExtent};
+use crate::scsi::RawBlock;
+use std::collections::BTreeSet;
+use tracing::{debug, info, warn};
+
+/// 单项检查的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Pass,
+    /// 可疑但不影响读取（如索引一致性警告、标签缺少UUID）
+    Warn,
+    Fail,
+}
+
+#[derive(Debug, Clone)]
+pub struct CheckItem {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+/// Result of `check_tape`, one item per check in the order they ran
+#[derive(Debug, Clone, Default)]
+pub struct TapeCheckReport {
+    pub items: Vec<CheckItem>,
+}
+
+impl TapeCheckReport {
+    fn push(&mut self, name: impl Into<String>, status: CheckStatus, detail: impl Into<String>) {
+        let item = CheckItem {
+            name: name.into(),
+            status,
+            detail: detail.into(),
+        };
+        match item.status {
+            CheckStatus::Pass => info!("Check {}: {}", item.name, item.detail),
+            _ => warn!("Check {} {:?}: {}", item.name, item.status, item.detail),
+        }
+        self.items.push(item);
+    }
+
+    /// Whether no check failed (warnings allowed)
+    pub fn passed(&self) -> bool {
+        self.items
+            .iter()
+            .all(|item| item.status != CheckStatus::Fail)
+    }
+
+    pub fn failures(&self) -> usize {
+        self.items
+            .iter()
+            .filter(|item| item.status == CheckStatus::Fail)
+            .count()
+    }
+}
+
+/// 抽查用的文件：路径和第一个extent
+fn collect_first_extents<'a>(
+    dir: &'a Directory,
+    prefix: &str,
+    out: &mut Vec<(String, &'a FileExtent)>,
+) {
+    for file in &dir.contents.files {
+        let first = file
+            .extent_info
+            .extents
+            .iter()
+            .min_by_key(|extent| extent.file_offset);
+        if let (None, Some(extent)) = (&file.symlink, first) {
+            out.push((format!("{}/{}", prefix, file.name), extent));
+        }
+    }
+    for subdir in &dir.contents.directories {
+        collect_first_extents(subdir, &format!("{}/{}", prefix, subdir.name), out);
+    }
+}
+
+/// 从按磁带位置排序的 `count` 个文件中均匀选出至多 `samples` 个（含第一个和最后一个）
+fn sample_indices(count: usize, samples: usize) -> Vec<usize> {
+    match (count, samples) {
+        (0, _) | (_, 0) => Vec::new(),
+        _ if samples >= count => (0..count).collect(),
+        (_, 1) => vec![count - 1],
+        _ => {
+            let picks: BTreeSet<usize> = (0..samples)
+                .map(|i| i * (count - 1) / (samples - 1))
+                .collect();
+            picks.into_iter().collect()
+        }
+    }
+}
+
+impl super::TapeOperations {
+    /// 磁带完整性快速检查：索引规则、分区标签卷UUID、抽查 `samples` 个文件的extent、各分区EOD
+    ///
+    /// 需先以读取方式初始化（已加载索引）。各项检查独立进行，结果逐项记录在报告中；
+    /// 只有未加载索引时返回错误。
+    pub async fn check_tape(&mut self, samples: usize) -> Result<TapeCheckReport> {
+        let mut report = TapeCheckReport::default();
+        let (volume_uuid, index_location, generation) = match self.index.as_ref() {
+            Some(index) => (
+                index.volumeuuid.trim().to_string(),
+                index.location.clone(),
+                index.generationnumber,
+            ),
+            None => {
+                return Err(RustLtfsError::ltfs_index(
+                    "No index loaded, cannot check tape",
+                ))
+            }
+        };
+
+        // 1. 索引：加载时已通过结构校验，这里补充extent/UID一致性检查
+        match self.check_index_consistency().await {
+            Ok(warnings) if warnings.is_empty() => report.push(
+                "index",
+                CheckStatus::Pass,
+                format!(
+                    "generation {}, volume {}, no consistency warnings",
+                    generation, volume_uuid
+                ),
+            ),
+            Ok(warnings) => report.push(
+                "index",
+                CheckStatus::Warn,
+                format!(
+                    "generation {}: {} consistency warning(s), first: {}",
+                    generation,
+                    warnings.len(),
+                    warnings[0]
+                ),
+            ),
+            Err(e) => report.push("index", CheckStatus::Fail, e.to_string()),
+        }
+
+        // 2. 分区标签中的卷UUID
+        let dual_partition = self.get_extra_partition_count() > 0;
+        let labels: &[(u8, &str)] = if dual_partition {
+            &[(0, "label a"), (1, "label b")]
+        } else {
+            &[(0, "label a")]
+        };
+        self.invalidate_position_cache();
+        for &(partition, name) in labels {
+            let label = if partition == 0 {
+                self.read_volume_label().await
+            } else {
+                self.read_and_parse_partition_label(partition).await
+            };
+            match label.map(|label| label.volume_uuid) {
+                Ok(Some(uuid)) if uuid.eq_ignore_ascii_case(&volume_uuid) => {
+                    report.push(name, CheckStatus::Pass, format!("volume UUID {}", uuid))
+                }
+                Ok(Some(uuid)) => report.push(
+                    name,
+                    CheckStatus::Fail,
+                    format!(
+                        "volume UUID {} does not match index UUID {}",
+                        uuid, volume_uuid
+                    ),
+                ),
+                Ok(None) => report.push(name, CheckStatus::Warn, "label has no volume UUID"),
+                Err(e) => report.push(name, CheckStatus::Fail, format!("cannot read label: {}", e)),
+            }
+        }
+
+        // 3. 抽查extent：按磁带位置排序后均匀取样，定位到首个extent并读取一块
+        let files = {
+            let index = self
+                .index
+                .as_ref()
+                .ok_or_else(|| RustLtfsError::ltfs_index("No index loaded, cannot check tape"))?;
+            let mut files = Vec::new();
+            collect_first_extents(&index.root_directory, "", &mut files);
+            let mut files: Vec<(String, FileExtent)> = files
+                .into_iter()
+                .map(|(path, extent)| (path, extent.clone()))
+                .collect();
+            files.sort_by_key(|(_, extent)| (self.extent_partition(extent), extent.start_block));
+            files
+        };
+        let picks = sample_indices(files.len(), samples);
+        let mut unreadable = Vec::new();
+        let mut buffer = vec![0u8; self.data_block_size()];
+        for &i in &picks {
+            let (path, extent) = &files[i];
+            self.check_cancelled(&format!("while checking {}", path))?;
+            let partition = self.extent_partition(extent);
+            let block = self
+                .scsi
+                .locate_block(partition, extent.start_block)
+                .and_then(|()| self.scsi.read_raw_block(&mut buffer));
+            match block {
+                Ok(RawBlock::Data(len)) if len as u64 > extent.byte_offset => {
+                    debug!(
+                        "{}: read {} bytes at P{} B{}",
+                        path, len, partition, extent.start_block
+                    )
+                }
+                Ok(RawBlock::Data(len)) => unreadable.push(format!(
+                    "{}: block {} holds {} bytes, extent starts at byte {}",
+                    path, extent.start_block, len, extent.byte_offset
+                )),
+                Ok(other) => unreadable.push(format!(
+                    "{}: {:?} at P{} B{}",
+                    path, other, partition, extent.start_block
+                )),
+                Err(e) => unreadable.push(format!(
+                    "{}: P{} B{}: {}",
+                    path, partition, extent.start_block, e
+                )),
+            }
+        }
+        self.invalidate_position_cache();
+        if unreadable.is_empty() {
+            report.push(
+                "extents",
+                CheckStatus::Pass,
+                format!(
+                    "{} of {} files sampled, all readable",
+                    picks.len(),
+                    files.len()
+                ),
+            );
+        } else {
+            report.push(
+                "extents",
+                CheckStatus::Fail,
+                format!(
+                    "{} of {} sampled files unreadable: {}",
+                    unreadable.len(),
+                    picks.len(),
+                    unreadable.join("; ")
+                ),
+            );
+        }
+
+        // 4. 每个分区的EOD可达；当前索引必须位于其分区的EOD之前
+        let index_partition =
+            self.get_target_partition(if index_location.partition.eq_ignore_ascii_case("a") {
+                0
+            } else {
+                1
+            });
+        let partitions: &[u8] = if dual_partition { &[0, 1] } else { &[0] };
+        for &partition in partitions {
+            let name = format!("EOD p{}", partition);
+            let eod = self
+                .scsi
+                .locate_to_eod(partition)
+                .and_then(|()| self.scsi.read_position());
+            match eod {
+                Ok(position)
+                    if partition == index_partition
+                        && index_location.startblock >= position.block_number =>
+                {
+                    report.push(
+                        name,
+                        CheckStatus::Fail,
+                        format!(
+                            "EOD at block {} but the index is at block {}",
+                            position.block_number, index_location.startblock
+                        ),
+                    )
+                }
+                Ok(position) => report.push(
+                    name,
+                    CheckStatus::Pass,
+                    format!("reachable at block {}", position.block_number),
+                ),
+                Err(e) => report.push(name, CheckStatus::Fail, format!("cannot locate EOD: {}", e)),
+            }
+        }
+        self.invalidate_position_cache();
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::sample_indices;
+
+    #[test]
+    fn samples_spread_across_the_tape() {
+        assert_eq!(sample_indices(0, 8), Vec::<usize>::new());
+        assert_eq!(sample_indices(3, 8), vec![0, 1, 2]);
+        assert_eq!(sample_indices(10, 1), vec![9]);
+        assert_eq!(sample_indices(100, 5), vec![0, 24, 49, 74, 99]);
+    }
+}
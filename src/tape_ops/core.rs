@@ -1,13 +1,33 @@
 use super::LtfsPartitionLabel;
-use super::{WriteOptions, WriteProgress};
+use super::{WriteOptions, WriteProgress, WriteProgressCallback};
 use crate::error::{Result, RustLtfsError};
 use crate::ltfs_index::LtfsIndex;
+use crate::scsi::LbpMethod;
 use tracing::{debug, info, warn};
 
 
 
 
 
+/// 提取时默认允许的最大目录嵌套深度
+pub const DEFAULT_MAX_EXTRACT_DEPTH: usize = 256;
+
+/// 长擦除估算用的磁带速度（字节/秒，取较保守的LTO-5原生速度）
+const LONG_ERASE_BYTES_PER_SEC: u64 = 140 * 1000 * 1000;
+/// 超过该时长的长擦除必须以IMMED方式在后台执行
+const LONG_ERASE_MAX_FOREGROUND_SECS: u64 = 3600;
+
+/// 显式定位的目标（`space --to-filemark/--to-block/--to-eod`）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PositionTarget {
+    /// 分区内的逻辑块号 (SCSI LOCATE)
+    Block(u64),
+    /// 分区开头起第N个FileMark之后 (LOCATE 0 + SPACE N FileMarks)
+    FileMark(u64),
+    /// 分区的数据末尾
+    EndOfData,
+}
+
 /// 操作类型枚举
 #[derive(Debug, Clone, Copy)]
 pub enum OperationType {
@@ -23,34 +43,85 @@ pub struct TapeOperations {
     pub(crate) index: Option<LtfsIndex>,
     pub(crate) schema: Option<LtfsIndex>,
     pub(crate) block_size: u32,
-    pub(crate) scsi: crate::scsi::ScsiInterface,
+    pub(crate) scsi: Box<dyn crate::scsi::TapeBackend>,
     pub(crate) partition_label: Option<LtfsPartitionLabel>, // 对应LTFSCopyGUI的plabel
 
     pub(crate) write_progress: WriteProgress,
+    pub(crate) write_progress_callback: Option<WriteProgressCallback>, // 每写入一个块后调用
+    pub(crate) read_ahead: std::collections::VecDeque<super::read_ahead::ReadAheadFile>, // 目录写入时已开始预读的后续文件
     pub(crate) write_options: WriteOptions,
     pub(crate) modified: bool,   // 对应LTFSCopyGUI的Modified标志
+    pub(crate) modified_directories: std::collections::BTreeSet<String>, // 自上次写入索引以来内容有变化的目录
     pub(crate) extra_partition_count: Option<u8>, // 对应LTFSCopyGUI的ExtraPartitionCount
     pub(crate) max_extra_partition_allowed: u8, // 对应LTFSCopyGUI的MaxExtraPartitionAllowed
+    pub(crate) keep_index_temp_file: bool, // 调试用：保留读取到的索引原始数据
+    pub(crate) tape_generation: Option<u64>, // 最近一次从磁带读取或写入磁带的索引generation
+    pub(crate) media_type: Option<crate::scsi::MediaType>, // 初始化时检测到的介质类型
+    pub(crate) write_journal: Option<super::journal::JournalState>, // 可恢复写入会话的日志
+    pub(crate) span: Option<super::span::SpanState>, // 跨磁带写入集的清单
+    pub(crate) write_source_root: Option<std::path::PathBuf>, // 目录写入会话的源目录，排除规则按相对它的路径匹配
+    pub(crate) max_extract_depth: usize, // 提取时允许的最大目录嵌套深度
+    pub(crate) path_normalize: crate::ltfs_index::PathNormalize, // 按路径查找时的名称比较方式（默认区分大小写）
+    pub(crate) drive_info: Option<crate::scsi::DriveInfo>, // INQUIRY得到的驱动器信息
+    pub(crate) cleaning_status: Option<super::drive_health::CleaningStatus>, // 初始化时读取的驱动器清洁状态
+    pub(crate) cached_position: std::sync::Mutex<Option<crate::scsi::TapePosition>>, // 顺序读取时最后已知的磁带位置
+    pub(crate) dry_run_position: Option<crate::scsi::TapePosition>, // 试运行时模拟的写入位置
+    pub(crate) cancel_flag: std::sync::Arc<std::sync::atomic::AtomicBool>, // 取消请求，在块循环之间检查
+    pub(crate) last_index_write: std::time::Instant, // 上次写入索引（或会话开始）的时间，用于按时间触发索引写入
 }
 
 impl TapeOperations {
     /// Create new tape operations instance
+    ///
+    /// SCSI command timeouts are the ones installed from the command line (`Timeouts::install`).
     pub fn new(device: &str) -> Self {
+        Self::with_timeouts(device, crate::scsi::Timeouts::current())
+    }
+
+    /// Create a tape operations instance on a SCSI device with the given command timeouts
+    pub fn with_timeouts(device: &str, timeouts: crate::scsi::Timeouts) -> Self {
+        let scsi = crate::scsi::ScsiInterface::new().with_timeouts(timeouts);
+        debug!("SCSI timeouts for {}: {:?}", device, scsi.timeouts());
+        Self::with_backend(device, Box::new(scsi))
+    }
+
+    /// Create a tape operations instance on top of another tape backend
+    ///
+    /// Use this with a `ScsiInterface` built with `with_read_retry`/`with_locate_retry`/
+    /// `with_test_unit_ready_retry` to override the retry policies.
+    pub fn with_backend(device: &str, backend: Box<dyn crate::scsi::TapeBackend>) -> Self {
         Self {
             device_path: device.to_string(),
 
             index: None,
             schema: None,
             block_size: crate::scsi::block_sizes::LTO_BLOCK_SIZE, // Default block size (64KB)
-            scsi: crate::scsi::ScsiInterface::new(),
+            scsi: backend,
             partition_label: None, // 初始化为None，稍后读取
 
             write_progress: WriteProgress::default(),
+            write_progress_callback: None,
+            read_ahead: std::collections::VecDeque::new(),
             write_options: WriteOptions::default(),
             modified: false,
+            modified_directories: std::collections::BTreeSet::new(),
 
             extra_partition_count: None, // Will be detected during initialization
             max_extra_partition_allowed: 1, // LTO standard maximum
+            keep_index_temp_file: keep_index_temp_file_from_env(),
+            tape_generation: None,
+            media_type: None,
+            write_journal: None,
+            span: None,
+            write_source_root: None,
+            max_extract_depth: DEFAULT_MAX_EXTRACT_DEPTH,
+            path_normalize: crate::ltfs_index::PathNormalize::default(),
+            drive_info: None,
+            cleaning_status: None,
+            cached_position: std::sync::Mutex::new(None),
+            dry_run_position: None,
+            cancel_flag: std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false)),
+            last_index_write: std::time::Instant::now(),
         }
     }
 
@@ -70,23 +141,134 @@ impl TapeOperations {
 
 
 
+    /// 打开设备但不要求装入磁带（用于查询驱动器信息）
+    pub fn open_drive(&mut self) -> Result<()> {
+        self.scsi.open_device(&self.device_path)
+    }
+
+    /// INQUIRY识别驱动器并据此选择DriveType
+    pub fn identify_drive(&mut self) -> Result<crate::scsi::DriveInfo> {
+        let info = self.scsi.inquiry()?;
+        self.scsi
+            .set_drive_type(crate::scsi::DriveType::from_inquiry(&info));
+        info!(
+            "Drive: {} {} rev {} S/N {} ({:?})",
+            info.vendor_id,
+            info.product_id,
+            info.product_revision,
+            info.serial_number.as_deref().unwrap_or("unknown"),
+            self.scsi.drive_type()
+        );
+        self.drive_info = Some(info.clone());
+        Ok(info)
+    }
+
+    /// Drive identification read during initialize (None if INQUIRY failed)
+    pub fn drive_info(&self) -> Option<&crate::scsi::DriveInfo> {
+        self.drive_info.as_ref()
+    }
+
+    /// Drive cleaning status read during initialize (None if TapeAlert is unavailable)
+    pub fn cleaning_status(&self) -> Option<&super::drive_health::CleaningStatus> {
+        self.cleaning_status.as_ref()
+    }
+
+    /// Media type detected during initialize
+    pub fn media_type(&self) -> Option<crate::scsi::MediaType> {
+        self.media_type
+    }
+
+    /// Whether the loaded cartridge is WORM media (append only)
+    pub fn is_worm(&self) -> bool {
+        self.media_type.is_some_and(|media| media.is_worm())
+    }
+
     /// Get current write progress
     pub fn get_write_progress(&self) -> &WriteProgress {
         &self.write_progress
     }
 
+    /// Call `callback` with the write progress after every block written
+    pub fn set_write_progress_callback(&mut self, callback: Option<WriteProgressCallback>) {
+        self.write_progress_callback = callback;
+    }
+
     /// Set write options
     pub fn set_write_options(&mut self, options: WriteOptions) {
         self.block_size = options.block_size;
         self.write_options = options;
     }
 
+    /// Limit directory nesting followed by `extract_from_tape`
+    pub fn set_max_extract_depth(&mut self, depth: usize) {
+        self.max_extract_depth = depth;
+    }
+
+    /// Shared flag that cancels a running read or write when set
+    ///
+    /// Checked between blocks, so even a single large file stops promptly; the operation
+    /// returns `RustLtfsError::OperationCancelled` with the tape at a known position.
+    pub fn cancel_flag(&self) -> std::sync::Arc<std::sync::atomic::AtomicBool> {
+        self.cancel_flag.clone()
+    }
+
+    /// 已请求取消时返回 `OperationCancelled` 错误
+    pub(crate) fn check_cancelled(&self, context: &str) -> Result<()> {
+        if self.cancel_flag.load(std::sync::atomic::Ordering::Relaxed) {
+            return Err(RustLtfsError::operation_cancelled(context.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Choose how names are compared when looking up tape paths (listing, extract, export)
+    pub fn set_path_normalize(&mut self, mode: crate::ltfs_index::PathNormalize) {
+        self.path_normalize = mode;
+    }
+
 
 
 
 
 
 
+
+    /// 按驱动器READ BLOCK LIMITS结果限制块大小（旧驱动器可能不支持512KB块）
+    fn apply_drive_block_limits(&mut self) {
+        let (max_block_length, min_block_length) = match self.scsi.read_block_limits() {
+            Ok(limits) => limits,
+            Err(e) => {
+                warn!("READ BLOCK LIMITS failed, keeping configured block size: {}", e);
+                return;
+            }
+        };
+        info!(
+            "Drive block limits: max={} min={}",
+            max_block_length, min_block_length
+        );
+
+        let clamp = |block_size: u32| -> u32 {
+            // 0 表示变长块模式，不做限制
+            if block_size == 0 {
+                return 0;
+            }
+            let mut clamped = block_size.max(min_block_length);
+            if max_block_length > 0 {
+                clamped = clamped.min(max_block_length);
+            }
+            clamped
+        };
+
+        let write_block_size = clamp(self.write_options.block_size);
+        if write_block_size != self.write_options.block_size {
+            warn!(
+                "Configured block size {} is outside drive limits ({}..={}), using {}",
+                self.write_options.block_size, min_block_length, max_block_length, write_block_size
+            );
+            self.write_options.block_size = write_block_size;
+        }
+        self.block_size = clamp(self.block_size);
+    }
+
     /// 初始化分区检测 (精确对应LTFSCopyGUI的初始化逻辑)
     /// 检测ExtraPartitionCount并设置分区策略 - 修复版本：直接使用已打开的SCSI设备
     pub async fn initialize_partition_detection(&mut self) -> Result<()> {
@@ -111,41 +293,51 @@ impl TapeOperations {
                 );
                 
                 // LTFSCopyGUI逻辑: If PModeData.Length >= 4 Then ExtraPartitionCount = PModeData(3)
-                // Page 0x11 byte[3] = Additional Partition Defined (分区数)
-                if mode_data.len() >= 4 {
-                    let detected_count = mode_data[3];
-                    info!(
-                        "✅ ExtraPartitionCount detected from MODE SENSE 0x11: {}",
-                        detected_count
-                    );
+                // Page 0x11 byte[3] = Additional Partitions Defined (分区数)
+                match crate::scsi::ScsiInterface::parse_partition_mode_page(&mode_data) {
+                    Ok(page) => {
+                        let detected_count = page.additional_partitions_defined;
+                        info!(
+                            "✅ ExtraPartitionCount detected from MODE SENSE 0x11: {} (max {}, FDP={} SDP={} IDP={}, sizes {:?} PSUM={} units={})",
+                            detected_count,
+                            page.max_additional_partitions,
+                            page.fdp,
+                            page.sdp,
+                            page.idp,
+                            page.partition_sizes,
+                            page.psum,
+                            page.partition_units
+                        );
 
-                    // 应用LTFSCopyGUI的验证逻辑: Math.Min(1, value)
-                    let validated_count = std::cmp::min(1, detected_count);
-                    let final_count =
-                        std::cmp::min(validated_count, self.max_extra_partition_allowed);
+                        // 应用LTFSCopyGUI的验证逻辑: Math.Min(1, value)
+                        let validated_count = std::cmp::min(1, detected_count);
+                        let final_count =
+                            std::cmp::min(validated_count, self.max_extra_partition_allowed);
 
-                    if final_count != detected_count {
-                        debug!(
-                            "ExtraPartitionCount limited from {} to {} (Math.Min validation)",
-                            detected_count, final_count
-                        );
-                    }
+                        if final_count != detected_count {
+                            debug!(
+                                "ExtraPartitionCount limited from {} to {} (Math.Min validation)",
+                                detected_count, final_count
+                            );
+                        }
 
-                    self.extra_partition_count = Some(final_count);
-                    info!(
-                        "✅ ExtraPartitionCount initialized: {} (detected: {}, validated: {})",
-                        final_count, detected_count, final_count
-                    );
+                        self.extra_partition_count = Some(final_count);
+                        info!(
+                            "✅ ExtraPartitionCount initialized: {} (detected: {}, validated: {})",
+                            final_count, detected_count, final_count
+                        );
 
-                    // 设置modified状态 (对应LTFSCopyGUI的Modified = ExtraPartitionCount > 0)
-                    self.modified = final_count > 0;
-                } else {
-                    warn!(
-                        "MODE SENSE data too short ({} bytes), defaulting to single partition",
-                        mode_data.len()
-                    );
-                    self.extra_partition_count = Some(0);
-                    self.modified = false;
+                        // 设置modified状态 (对应LTFSCopyGUI的Modified = ExtraPartitionCount > 0)
+                        self.modified = final_count > 0;
+                    }
+                    Err(e) => {
+                        warn!(
+                            "Cannot parse MODE SENSE page 0x11 ({}), defaulting to single partition",
+                            e
+                        );
+                        self.extra_partition_count = Some(0);
+                        self.modified = false;
+                    }
                 }
             }
             Err(e) => {
@@ -223,14 +415,190 @@ impl TapeOperations {
 
 
 
+    /// 读取用的定位：若缓存的位置已在目标分区/块上则跳过LOCATE
+    ///
+    /// 按目录顺序读取大量小文件时，下一个extent通常紧接在上一个之后，省去多余的LOCATE。
+    pub(crate) fn locate_for_read(&self, partition: u8, block_number: u64) -> Result<()> {
+        let cached = self.cached_position.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        if let Some(position) = cached {
+            if position.partition == partition && position.block_number == block_number {
+                debug!("Already at P{} B{}, skipping LOCATE", partition, block_number);
+                return Ok(());
+            }
+        }
+
+        self.invalidate_position_cache();
+        self.scsi.locate_block(partition, block_number)?;
+        self.set_cached_position(partition, block_number);
+        Ok(())
+    }
+
+    /// 记录读取后磁带所在的位置
+    pub(crate) fn set_cached_position(&self, partition: u8, block_number: u64) {
+        *self.cached_position.lock().unwrap_or_else(|e| e.into_inner()) = Some(crate::scsi::TapePosition {
+            partition,
+            block_number,
+            ..Default::default()
+        });
+    }
+
+    /// 使缓存的位置失效（写入、空格、错误恢复等移动磁带的操作之后必须调用）
+    pub(crate) fn invalidate_position_cache(&self) {
+        *self.cached_position.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    /// 倒带到分区0开头（SCSI REWIND，复位文件/块计数器，而不是定位到块0）
+    pub async fn rewind(&self) -> Result<()> {
+        info!("Rewinding tape");
+        self.invalidate_position_cache();
+        self.scsi.rewind()?;
+
+        // 倒带后驱动器常返回一次 Unit Attention，read_position 失败时重试一次
+        let position = match self.scsi.read_position() {
+            Ok(position) => position,
+            Err(e) => {
+                debug!("READ POSITION after rewind failed ({}), retrying once", e);
+                self.scsi.read_position()?
+            }
+        };
+
+        debug!(
+            "Rewind complete: P{} B{} FM{}",
+            position.partition, position.block_number, position.file_number
+        );
+        Ok(())
+    }
+
+    /// 相对当前位置跳过n个FileMark，负数表示反向（SCSI SPACE, Code=1）
+    ///
+    /// 正向停在第n个FileMark之后，反向停在FileMark的BOP一侧。
+    pub fn skip_filemarks(&self, n: i32) -> Result<()> {
+        debug!("Spacing {} filemarks", n);
+        self.invalidate_position_cache();
+        self.scsi.space(crate::scsi::SpaceType::FileMarks, n)
+    }
+
+    /// 将磁带定位到 `partition` 中的目标位置，返回移动后驱动器报告的位置
+    pub fn position_to(
+        &self,
+        partition: u8,
+        target: PositionTarget,
+    ) -> Result<crate::scsi::TapePosition> {
+        info!("Positioning to {:?} in partition {}", target, partition);
+        self.invalidate_position_cache();
+        match target {
+            PositionTarget::Block(block) => self.scsi.locate_block(partition, block)?,
+            PositionTarget::FileMark(filemark) => {
+                if filemark > i32::MAX as u64 {
+                    return Err(RustLtfsError::parameter_validation(format!(
+                        "Filemark number {} is too large",
+                        filemark
+                    )));
+                }
+                self.scsi.locate_to_filemark(filemark, partition)?
+            }
+            PositionTarget::EndOfData => self.scsi.locate_to_eod(partition)?,
+        }
+        self.scsi.read_position()
+    }
+
+    /// 弹出磁带（SCSI LOAD UNLOAD, Load=0）
+    ///
+    /// 若仍有未写入索引的数据则拒绝弹出，避免磁带上留下没有索引的文件。
+    pub async fn eject(&self) -> Result<()> {
+        if self.write_progress.total_bytes_unindexed > 0 {
+            return Err(RustLtfsError::tape_device(format!(
+                "Cannot eject: {} bytes written since the last index update, update the index first",
+                self.write_progress.total_bytes_unindexed
+            )));
+        }
+
+        info!("Unloading tape");
+        self.invalidate_position_cache();
+        self.scsi.load_unload(false, false, false)?;
+        info!("Tape unloaded");
+        Ok(())
+    }
+
+    /// 擦除磁带（SCSI ERASE），需先核对条码防止误擦其他磁带
+    ///
+    /// 短擦除只在分区0开头写入EOD；长擦除覆盖整盘，整盘耗时超过1小时时
+    /// 除非使用 `immediate`（驱动器后台执行）否则返回 OperationTooLong 错误。
+    pub async fn erase_tape(&mut self, long: bool, immediate: bool, confirm_barcode: &str) -> Result<()> {
+        if let Some(media) = self.media_type {
+            if media.is_worm() || media.is_write_protected() {
+                return Err(RustLtfsError::tape_device(format!(
+                    "Write protected: {:?} cartridges cannot be erased",
+                    media
+                )));
+            }
+        }
+
+        let barcode = self.scsi.read_barcode().map_err(|e| {
+            RustLtfsError::tape_device(format!("Cannot read cartridge barcode to confirm erase: {}", e))
+        })?;
+        if !barcode.eq_ignore_ascii_case(confirm_barcode.trim()) {
+            return Err(RustLtfsError::parameter_validation(format!(
+                "Barcode mismatch: loaded cartridge is '{}', but '{}' was confirmed",
+                barcode, confirm_barcode
+            )));
+        }
+
+        if long && !immediate {
+            let mut total_bytes = self.scsi.read_mam_capacity(0).map(|c| c.maximum).unwrap_or(0);
+            if self.get_extra_partition_count() > 0 {
+                total_bytes += self.scsi.read_mam_capacity(1).map(|c| c.maximum).unwrap_or(0);
+            }
+            let estimated_secs = total_bytes / LONG_ERASE_BYTES_PER_SEC;
+            if estimated_secs > LONG_ERASE_MAX_FOREGROUND_SECS {
+                return Err(RustLtfsError::operation_too_long(format!(
+                    "Long erase of {} is estimated to take about {:.1} hours; run it with --immediate to let the drive erase in the background",
+                    barcode,
+                    estimated_secs as f64 / 3600.0
+                )));
+            }
+        }
+
+        info!(
+            "Erasing cartridge {} ({} erase{})",
+            barcode,
+            if long { "long" } else { "short" },
+            if immediate { ", immediate" } else { "" }
+        );
+        self.rewind().await?;
+        self.scsi.erase(long, immediate)?;
+
+        // 磁带内容已不存在，丢弃内存中的索引
+        self.index = None;
+        self.schema = None;
+        self.tape_generation = None;
+        self.modified = false;
+        self.modified_directories.clear();
+        info!("Erase of {} {}", barcode, if immediate { "started" } else { "completed" });
+        Ok(())
+    }
+
+    /// 记录目录内容在内存索引中发生了变化（下一次索引写入时需要包含）
+    pub(crate) fn mark_directory_modified(&mut self, dir_path: &str) {
+        self.modified = true;
+        self.modified_directories.insert(format!("/{}", dir_path));
+    }
+
+    /// 自上次写入索引以来内容有变化的目录路径
+    pub fn modified_directories(&self) -> impl Iterator<Item = &str> {
+        self.modified_directories.iter().map(String::as_str)
+    }
+
     /// Wait for device ready using TestUnitReady retry logic (对应LTFSCopyGUI的TestUnitReady重试逻辑)
     pub async fn wait_for_device_ready(&self) -> Result<()> {
         debug!("Starting TestUnitReady retry logic");
 
-        let max_retries = 5; // 对应LTFSCopyGUI的5次重试
-        let retry_delay_ms = 200; // 对应LTFSCopyGUI的200ms延迟
+        // 默认对应LTFSCopyGUI的5次尝试、200ms延迟（见ScsiInterface的TestUnitReady重试策略）
+        let policy = self.scsi.test_unit_ready_retry();
+        let max_retries = policy.max_retries + 1;
 
         for retry_count in (1..=max_retries).rev() {
+            let retry_delay = policy.delay_for(max_retries - retry_count + 1);
             debug!(
                 "TestUnitReady attempt {} (remaining: {})",
                 max_retries - retry_count + 1,
@@ -270,12 +638,9 @@ impl TapeOperations {
                             || sense_info.contains("Medium may have changed")
                         {
                             if retry_count > 1 {
-                                debug!("⏳ Device not ready ({}), retrying in {}ms (attempts remaining: {})",
-                                     sense_info, retry_delay_ms, retry_count - 1);
-                                tokio::time::sleep(tokio::time::Duration::from_millis(
-                                    retry_delay_ms,
-                                ))
-                                .await;
+                                debug!("⏳ Device not ready ({}), retrying in {:?} (attempts remaining: {})",
+                                     sense_info, retry_delay, retry_count - 1);
+                                tokio::time::sleep(retry_delay).await;
                                 continue;
                             } else {
                                 warn!(
@@ -298,10 +663,9 @@ impl TapeOperations {
                 }
                 Err(e) => {
                     if retry_count > 1 {
-                        warn!("🔄 TestUnitReady SCSI command failed: {}, retrying in {}ms (attempts remaining: {})",
-                             e, retry_delay_ms, retry_count - 1);
-                        tokio::time::sleep(tokio::time::Duration::from_millis(retry_delay_ms))
-                            .await;
+                        warn!("🔄 TestUnitReady SCSI command failed: {}, retrying in {:?} (attempts remaining: {})",
+                             e, retry_delay, retry_count - 1);
+                        tokio::time::sleep(retry_delay).await;
                         continue;
                     } else {
                         return Err(RustLtfsError::scsi(format!(
@@ -324,9 +688,14 @@ impl TapeOperations {
 
         // 设备初始化（所有操作都需要）
         self.scsi.open_device(&self.device_path)?;
+        if let Err(e) = self.identify_drive() {
+            warn!("INQUIRY failed, using standard drive type: {}", e);
+        }
         self.wait_for_device_ready().await?;
+        self.check_cleaning_status(op_type)?;
 
-        match self.scsi.check_media_status()? {
+        let media_type = self.scsi.check_media_status()?;
+        match media_type {
             crate::scsi::MediaType::NoTape => {
                 return Err(RustLtfsError::tape_device("No tape loaded".to_string()));
             }
@@ -337,8 +706,21 @@ impl TapeOperations {
                 // Media detected, continue
             }
         }
+        self.media_type = Some(media_type);
+
+        // 写入前检查写保护，避免写到一半才失败
+        if matches!(op_type, OperationType::Write) && media_type.is_write_protected() {
+            return Err(RustLtfsError::tape_device(format!(
+                "Write protected: cartridge ({:?}) has its write-protect tab set",
+                media_type
+            )));
+        }
+        if media_type.is_worm() {
+            info!("WORM media detected ({:?}): existing files cannot be overwritten", media_type);
+        }
 
         self.initialize_partition_detection().await?;
+        self.apply_drive_block_limits();
 
         match op_type {
             OperationType::Space => {
@@ -357,9 +739,13 @@ impl TapeOperations {
                             .map(|idx| idx.root_directory.contents.files.len())
                             .unwrap_or(0);
                         debug!("Index loaded successfully ({} files)", file_count);
+                        self.adopt_tape_block_size()?;
                     }
-                    Err(_) => {
-                        info!("Will create new index");
+                    Err(e) if e.is_blank_tape() => {
+                        info!("Blank tape, will create new index");
+                    }
+                    Err(e) => {
+                        info!("No usable index ({}), will create new index", e);
                     }
                 }
             }
@@ -376,6 +762,8 @@ impl TapeOperations {
                             info!("Tape contents: {} files, {} directories", stats.total_files, stats.total_directories);
                         }
                     }
+                    // 格式错误保留原类型，调用方据此区分空白磁带、非LTFS磁带和损坏的索引
+                    Err(e) if e.is_format_error() => return Err(e),
                     Err(e) => {
                         return Err(RustLtfsError::ltfs_index(format!("Index reading failed: {}", e)));
                     }
@@ -383,7 +771,126 @@ impl TapeOperations {
             }
         }
 
-        self.partition_label = Some(LtfsPartitionLabel::default());
+        // 保留读取索引时解析到的分区标签（块大小等），只在没有标签时使用默认值
+        self.partition_label.get_or_insert_with(LtfsPartitionLabel::default);
+        self.sync_compression_state(matches!(op_type, OperationType::Write))?;
+        self.sync_logical_block_protection(matches!(op_type, OperationType::Write))?;
+        Ok(())
+    }
+
+    /// 读取清洁状态：驱动器需要清洁时醒目地警告；装入清洁带时拒绝读写
+    fn check_cleaning_status(&mut self, op_type: OperationType) -> Result<()> {
+        let status = match self.get_cleaning_status() {
+            Ok(status) => status,
+            Err(e) => {
+                debug!("Cannot read cleaning status: {}", e);
+                return Ok(());
+            }
+        };
+
+        if status.cleaning_required {
+            warn!("!!! DRIVE REQUIRES CLEANING !!! Writing with a dirty head risks unrecoverable data; clean the drive before writing");
+        }
+        if status.cleaning_cartridge && !matches!(op_type, OperationType::Space) {
+            return Err(RustLtfsError::tape_device(format!(
+                "A cleaning cartridge is loaded ({} cleaning cycles remaining); load a data cartridge",
+                status
+                    .cycles_remaining
+                    .map_or("unknown".to_string(), |remaining| remaining.to_string())
+            )));
+        }
+        self.cleaning_status = Some(status);
+        Ok(())
+    }
+
+    /// 追加写入时使用磁带LTFS标签中的块大小 (对应LTFSCopyGUI按plabel.blocksize写入)
+    ///
+    /// 只信任从label XML解析出的标签（带volume UUID），探测或假定的块大小不作为依据。
+    /// 用户显式指定了不同的块大小时拒绝追加，除非同时给出 `--force`。
+    fn adopt_tape_block_size(&mut self) -> Result<()> {
+        let tape_block_size = match &self.partition_label {
+            Some(label) if label.volume_uuid.is_some() => label.blocksize,
+            _ => return Ok(()),
+        };
+        if self.write_options.block_size == tape_block_size {
+            self.block_size = tape_block_size;
+            return Ok(());
+        }
+
+        if self.write_options.block_size_explicit {
+            if !self.write_options.force_block_size {
+                return Err(RustLtfsError::parameter_validation(format!(
+                    "Tape is formatted with {}-byte blocks but --block-size {} was given; \
+                     appending with a different block size breaks the LTFS volume (use --force to override)",
+                    tape_block_size, self.write_options.block_size
+                )));
+            }
+            warn!(
+                "Appending with block size {} to a tape formatted with {}-byte blocks (--force)",
+                self.write_options.block_size, tape_block_size
+            );
+            return Ok(());
+        }
+
+        info!(
+            "Using the tape's LTFS block size {} instead of the default {}",
+            tape_block_size, self.write_options.block_size
+        );
+        self.write_options.block_size = tape_block_size;
+        self.block_size = tape_block_size;
+        Ok(())
+    }
+
+    /// 读取驱动器硬件压缩状态 (DCE) 记入partition label；写入时按 WriteOptions.compression 调整
+    fn sync_compression_state(&mut self, for_write: bool) -> Result<()> {
+        let wanted = self.write_options.compression;
+        let mut state = match self.scsi.get_compression() {
+            Ok(state) => state,
+            // 明确要求关闭压缩时不能静默忽略
+            Err(e) if for_write && !wanted => return Err(e),
+            Err(e) => {
+                debug!("Cannot read data compression page: {}", e);
+                return Ok(());
+            }
+        };
+
+        if for_write && state.dce != wanted {
+            if wanted && !state.dcc {
+                warn!("Drive does not report hardware compression capability (DCC=0)");
+            }
+            self.scsi.set_compression(wanted)?;
+            state.dce = wanted;
+            info!(
+                "Hardware compression {} for write",
+                if wanted { "enabled" } else { "disabled" }
+            );
+        }
+
+        if let Some(label) = self.partition_label.as_mut() {
+            label.compression = state.dce;
+        }
+        Ok(())
+    }
+
+    /// 读取驱动器的逻辑块保护方式，使后续读写按块附加/校验CRC；写入时按 WriteOptions.logical_block_protection 调整
+    fn sync_logical_block_protection(&mut self, for_write: bool) -> Result<()> {
+        let wanted = self.write_options.logical_block_protection;
+        let current = match self.scsi.get_logical_block_protection() {
+            Ok(method) => method,
+            // 明确要求的保护方式不能静默忽略
+            Err(e) if for_write && wanted != LbpMethod::None => return Err(e),
+            // 驱动器启用了不支持且无法关闭的保护方式：读到的块会带着无法去除的保护信息
+            Err(e @ RustLtfsError::Unsupported(_)) => return Err(e),
+            Err(e) => {
+                debug!("Cannot read control data protection page: {}", e);
+                return Ok(());
+            }
+        };
+
+        if for_write && current != wanted {
+            self.scsi.set_logical_block_protection(wanted)?;
+            info!("Logical block protection {:?} for write", wanted);
+        }
         Ok(())
     }
 
@@ -411,6 +918,7 @@ impl TapeOperations {
             stats.total_directories = count_directories_in_directory(&index.root_directory);
             stats.total_size = calculate_total_size(&index.root_directory);
             stats.volume_uuid = index.volumeuuid.clone();
+            stats.volume_name = index.root_directory.name.clone();
             stats.generation_number = index.generationnumber;
             stats.update_time = index.updatetime.clone();
             Some(stats)
@@ -419,6 +927,84 @@ impl TapeOperations {
         }
     }
 
+    /// 导出指定路径下的文件列表 (JSON/CSV/TSV)，返回导出的文件数
+    pub fn export_file_list<W: std::io::Write>(
+        &self,
+        path: &str,
+        writer: W,
+        format: crate::ltfs_index::ExportFormat,
+        fields: &[crate::ltfs_index::ExportField],
+    ) -> Result<usize> {
+        let index = self
+            .index
+            .as_ref()
+            .ok_or_else(|| RustLtfsError::ltfs_index("No index loaded".to_string()))?;
+        // 先按当前比较方式解析为索引中的实际路径
+        let (path, _) = index
+            .find_path(path, self.path_normalize)
+            .ok_or_else(|| RustLtfsError::ltfs_index(format!("Path not found in index: {}", path)))?;
+        index.export_file_list(&path, writer, format, fields)
+    }
+
+    /// 按磁带路径查找文件的UID（名称比较方式同 `set_path_normalize`）；路径是目录时返回错误
+    pub fn find_file_uid(&self, path: &str) -> Result<u64> {
+        let index = self
+            .index
+            .as_ref()
+            .ok_or_else(|| RustLtfsError::ltfs_index("No index loaded".to_string()))?;
+        match index.find_path(path, self.path_normalize) {
+            Some((_, crate::ltfs_index::IndexEntry::File(file))) => Ok(file.uid),
+            Some((canonical, crate::ltfs_index::IndexEntry::Directory(_))) => Err(
+                RustLtfsError::file_operation(format!("'{}' is a directory", canonical)),
+            ),
+            None => Err(RustLtfsError::ltfs_index(format!(
+                "Path not found in index: {}",
+                path
+            ))),
+        }
+    }
+
+    /// 对已加载的索引做非致命一致性检查（extent分区/起始块/字节偏移、UID唯一性、highestfileuid）
+    ///
+    /// 起始块上限按分区最大容量/块大小估算（来自MAM或容量日志页），读取失败时跳过该项检查。
+    pub async fn check_index_consistency(
+        &mut self,
+    ) -> Result<Vec<crate::ltfs_index::ValidationWarning>> {
+        if self.index.is_none() {
+            return Err(RustLtfsError::ltfs_index("No index loaded".to_string()));
+        }
+
+        // byteoffset 只需落在实际使用的最大块内
+        let block_size = self
+            .partition_label
+            .as_ref()
+            .map(|label| label.blocksize)
+            .unwrap_or(self.block_size)
+            .max(self.write_options.block_size) as u64;
+        let mut limits = crate::ltfs_index::ConsistencyLimits {
+            block_size: Some(block_size),
+            ..Default::default()
+        };
+
+        match self.refresh_capacity().await {
+            Ok(capacity) => {
+                // 容量单位为KB，0表示未知
+                let to_blocks = |kb: u64| (kb > 0).then(|| kb * 1024 / block_size);
+                limits.partition_blocks = [
+                    to_blocks(capacity.p0_maximum),
+                    to_blocks(capacity.p1_maximum),
+                ];
+            }
+            Err(e) => debug!("Capacity unavailable, skipping start block check: {}", e),
+        }
+
+        let index = self
+            .index
+            .as_ref()
+            .ok_or_else(|| RustLtfsError::ltfs_index("No index loaded".to_string()))?;
+        Ok(index.check_consistency(&limits))
+    }
+
     /// 打印目录树
     pub fn print_directory_tree(&self) {
         if let Some(ref index) = self.index {
@@ -455,6 +1041,32 @@ impl TapeOperations {
         Ok(())
     }
 
+    /// 递归列出指定路径下的所有文件和目录，返回绝对磁带路径
+    ///
+    /// 路径按 `LtfsIndex::find_path` 解析；`/` 或空路径表示根目录。路径指向文件时只返回该文件。
+    pub fn list_recursive(&self, path: &str) -> Result<Vec<(String, super::FileInfo)>> {
+        let index = self
+            .index
+            .as_ref()
+            .ok_or_else(|| RustLtfsError::ltfs_index("No index loaded".to_string()))?;
+
+        let (root_path, entry) = index
+            .find_path(path, self.path_normalize)
+            .ok_or_else(|| RustLtfsError::ltfs_index(format!("Path not found: {}", path)))?;
+        let mut entries = Vec::new();
+
+        match entry {
+            crate::ltfs_index::IndexEntry::Directory(dir) => {
+                collect_entries_recursive(dir, root_path.trim_end_matches('/'), &mut entries)
+            }
+            crate::ltfs_index::IndexEntry::File(file) => {
+                entries.push((root_path.clone(), file_info(file)))
+            }
+        }
+
+        Ok(entries)
+    }
+
     /// 打印目录内容（不递归）
     fn print_directory_contents(&self, dir: &crate::ltfs_index::Directory, depth: usize) {
         let indent = "  ".repeat(depth);
@@ -470,32 +1082,12 @@ impl TapeOperations {
         }
     }
 
-    /// 根据路径查找目录
-    fn find_directory_by_path<'a>(&self, root: &'a crate::ltfs_index::Directory, path: &str) -> Option<&'a crate::ltfs_index::Directory> {
-        // 标准化路径
-        let path = path.trim_start_matches('/').trim_end_matches('/');
-        if path.is_empty() {
-            return Some(root);
+    /// 根据路径查找目录（按 `path_normalize` 比较名称，见 `Directory::find_path`）
+    pub(crate) fn find_directory_by_path<'a>(&self, root: &'a crate::ltfs_index::Directory, path: &str) -> Option<&'a crate::ltfs_index::Directory> {
+        match root.find_path(path, self.path_normalize) {
+            Some((_, crate::ltfs_index::IndexEntry::Directory(dir))) => Some(dir),
+            _ => None,
         }
-
-        let path_parts: Vec<&str> = path.split('/').collect();
-        self.find_directory_recursive(root, &path_parts, 0)
-    }
-
-    /// 递归查找目录
-    fn find_directory_recursive<'a>(&self, current_dir: &'a crate::ltfs_index::Directory, path_parts: &[&str], index: usize) -> Option<&'a crate::ltfs_index::Directory> {
-        if index >= path_parts.len() {
-            return Some(current_dir);
-        }
-
-        let target_name = path_parts[index];
-        for subdir in &current_dir.contents.directories {
-            if subdir.name == target_name {
-                return self.find_directory_recursive(subdir, path_parts, index + 1);
-            }
-        }
-
-        None
     }
 
 
@@ -511,6 +1103,21 @@ impl TapeOperations {
             p1_maximum: 0,
         };
 
+        // 优先使用卡带存储器(MAM)中的真实分区容量，失败时再回退到日志页0x31
+        match self.read_mam_capacity_info() {
+            Ok(mam_info) => {
+                info!("Capacity refresh completed from MAM: P0({:.2}/{:.2}) GB, P1({:.2}/{:.2}) GB",
+                      mam_info.p0_remaining as f64 / 1024.0 / 1024.0,
+                      mam_info.p0_maximum as f64 / 1024.0 / 1024.0,
+                      mam_info.p1_remaining as f64 / 1024.0 / 1024.0,
+                      mam_info.p1_maximum as f64 / 1024.0 / 1024.0);
+                return Ok(mam_info);
+            }
+            Err(e) => {
+                warn!("READ ATTRIBUTE capacity failed: {}, falling back to capacity log page", e);
+            }
+        }
+
         // 直接使用self.scsi来读取容量信息
         info!("Reading tape capacity log page (0x31)");
         let capacity_log_data = match self.scsi.log_sense(0x31, 1) {
@@ -550,6 +1157,52 @@ impl TapeOperations {
 
 
 
+    /// 从MAM属性读取各分区容量（KB，与日志页结果单位一致）
+    fn read_mam_capacity_info(&self) -> Result<super::capacity_manager::TapeCapacityInfo> {
+        let p0 = self.scsi.read_mam_capacity(0)?;
+        let mut capacity_info = super::capacity_manager::TapeCapacityInfo {
+            p0_remaining: p0.remaining / 1024,
+            p0_maximum: p0.maximum / 1024,
+            p1_remaining: 0,
+            p1_maximum: 0,
+        };
+
+        if self.get_extra_partition_count() > 0 {
+            let p1 = self.scsi.read_mam_capacity(1)?;
+            capacity_info.p1_remaining = p1.remaining / 1024;
+            capacity_info.p1_maximum = p1.maximum / 1024;
+        }
+
+        Ok(capacity_info)
+    }
+
+    /// 读取卡带MAM中的全部属性（诊断用），返回带格式信息的属性，可用 `decoded_value` 显示
+    /// 先用 ATTRIBUTE LIST 枚举属性ID，再逐个读取；单个属性读取失败时跳过
+    pub fn dump_mam(&self) -> Result<Vec<crate::scsi::MamAttribute>> {
+        let attribute_ids = self.scsi.read_mam_attribute_list()?;
+        let mut attributes = Vec::with_capacity(attribute_ids.len());
+
+        for attribute_id in attribute_ids {
+            match self.scsi.read_mam_attribute(attribute_id) {
+                Ok(attribute) => {
+                    debug!(
+                        "MAM 0x{:04X} {} ({:?}{}): {}",
+                        attribute.id,
+                        attribute.name().unwrap_or("Unknown"),
+                        attribute.format,
+                        if attribute.read_only { ", read-only" } else { "" },
+                        attribute.decoded_value()
+                    );
+                    attributes.push(attribute);
+                }
+                Err(e) => warn!("Failed to read MAM attribute 0x{:04X}: {}", attribute_id, e),
+            }
+        }
+
+        info!("Read {} MAM attributes", attributes.len());
+        Ok(attributes)
+    }
+
     /// 获取磁带容量信息（简化版本，用于向后兼容）
     pub async fn get_tape_capacity_info(&mut self) -> Result<TapeSpaceInfo> {
         let capacity_info = self.refresh_capacity().await?;
@@ -580,10 +1233,19 @@ impl TapeOperations {
             ((used_p0 * 1024), (capacity_info.p0_maximum * 1024)) // KB转换为字节
         };
 
+        let compression = match self.scsi.get_compression() {
+            Ok(state) => Some(state),
+            Err(e) => {
+                debug!("Cannot read data compression page: {}", e);
+                None
+            }
+        };
+
         Ok(TapeSpaceInfo {
             total_capacity,
             used_space,
             available_space: total_capacity.saturating_sub(used_space),
+            compression,
         })
     }
 }
@@ -595,6 +1257,8 @@ pub struct IndexStatistics {
     pub total_directories: u64,
     pub total_size: u64,
     pub volume_uuid: String,
+    /// 卷名（索引根目录名），未设置时为空
+    pub volume_name: String,
     pub generation_number: u64,
     pub update_time: String,
 }
@@ -605,10 +1269,49 @@ pub struct TapeSpaceInfo {
     pub total_capacity: u64,
     pub used_space: u64,
     pub available_space: u64,
+    pub compression: Option<crate::scsi::DataCompressionPage>, // 驱动器报告的DCC/DCE状态
 }
 
 
 // 辅助函数
+
+/// 调试用：设置 `RUSTLTFS_KEEP_INDEX_TEMP_FILE`（非空且不为 `0`）时，读取索引时将原始数据另存到临时文件
+fn keep_index_temp_file_from_env() -> bool {
+    std::env::var("RUSTLTFS_KEEP_INDEX_TEMP_FILE").is_ok_and(|value| !value.is_empty() && value != "0")
+}
+
+fn file_info(file: &crate::ltfs_index::File) -> super::FileInfo {
+    super::FileInfo {
+        is_directory: false,
+        size: file.length,
+        uid: file.uid,
+        modify_time: file.modify_time.clone(),
+    }
+}
+
+fn collect_entries_recursive(
+    dir: &crate::ltfs_index::Directory,
+    dir_path: &str,
+    entries: &mut Vec<(String, super::FileInfo)>,
+) {
+    for file in &dir.contents.files {
+        entries.push((format!("{}/{}", dir_path, file.name), file_info(file)));
+    }
+    for subdir in &dir.contents.directories {
+        let subdir_path = format!("{}/{}", dir_path, subdir.name);
+        entries.push((
+            subdir_path.clone(),
+            super::FileInfo {
+                is_directory: true,
+                size: 0,
+                uid: subdir.uid,
+                modify_time: subdir.modify_time.clone(),
+            },
+        ));
+        collect_entries_recursive(subdir, &subdir_path, entries);
+    }
+}
+
 fn count_files_in_directory(dir: &crate::ltfs_index::Directory) -> u64 {
     let mut count = dir.contents.files.len() as u64;
     for subdir in &dir.contents.directories {
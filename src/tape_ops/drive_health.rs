@@ -0,0 +1,333 @@
+use crate::error::{Result, RustLtfsError};
+use tracing::{debug, info, warn};
+
+/// TapeAlert 日志页 (SSC-4)
+const TAPE_ALERT_LOG_PAGE: u8 = 0x2E;
+/// 写错误计数日志页
+const WRITE_ERROR_COUNTER_LOG_PAGE: u8 = 0x02;
+/// 读错误计数日志页
+const READ_ERROR_COUNTER_LOG_PAGE: u8 = 0x03;
+/// Sequential-Access Device 日志页 (SSC-4)
+const SEQUENTIAL_ACCESS_LOG_PAGE: u8 = 0x0C;
+/// Sequential-Access Device 页参数：驱动器需要清洁
+const PARAM_CLEANING_REQUIRED: u16 = 0x0100;
+/// MAM attribute: LOAD COUNT（清洁带上即已使用的清洁次数）
+const MAM_LOAD_COUNT: u16 = 0x0003;
+/// LTO通用清洁带的额定清洁次数
+const CLEANING_CARTRIDGE_CYCLES: u64 = 50;
+/// 驱动器需要清洁时，超过该数据量的写入需要 `--ignore-cleaning`
+pub const CLEANING_REQUIRED_WRITE_LIMIT: u64 = 1024 * 1024 * 1024;
+
+/// 错误计数页参数代码
+const PARAM_TOTAL_REWRITES_OR_REREADS: u16 = 0x0002;
+const PARAM_TOTAL_ERRORS_CORRECTED: u16 = 0x0003;
+const PARAM_TOTAL_BYTES_PROCESSED: u16 = 0x0005;
+const PARAM_TOTAL_UNCORRECTED_ERRORS: u16 = 0x0006;
+
+/// TapeAlert 标志（对应TapeAlert日志页的参数代码 0x01-0x40）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TapeAlertFlag {
+    ReadWarning,
+    WriteWarning,
+    HardError,
+    Media,
+    ReadFailure,
+    WriteFailure,
+    MediaLife,
+    NotDataGrade,
+    WriteProtect,
+    NoRemoval,
+    CleaningMedia,
+    UnsupportedFormat,
+    RecoverableMechanicalCartridgeFailure,
+    UnrecoverableMechanicalCartridgeFailure,
+    MemoryChipInCartridgeFailure,
+    ForcedEject,
+    ReadOnlyFormat,
+    TapeDirectoryCorrupted,
+    NearingMediaLife,
+    CleanNow,
+    CleanPeriodic,
+    ExpiredCleaningMedia,
+    InvalidCleaningTape,
+    HardwareA,
+    HardwareB,
+    Interface,
+    EjectMedia,
+    MicrocodeUpdateFail,
+    DriveHumidity,
+    DriveTemperature,
+    DriveVoltage,
+    PredictiveFailure,
+    DiagnosticsRequired,
+    Other(u8),
+}
+
+impl TapeAlertFlag {
+    /// 从TapeAlert参数代码转换
+    pub fn from_code(code: u8) -> Self {
+        match code {
+            0x01 => TapeAlertFlag::ReadWarning,
+            0x02 => TapeAlertFlag::WriteWarning,
+            0x03 => TapeAlertFlag::HardError,
+            0x04 => TapeAlertFlag::Media,
+            0x05 => TapeAlertFlag::ReadFailure,
+            0x06 => TapeAlertFlag::WriteFailure,
+            0x07 => TapeAlertFlag::MediaLife,
+            0x08 => TapeAlertFlag::NotDataGrade,
+            0x09 => TapeAlertFlag::WriteProtect,
+            0x0A => TapeAlertFlag::NoRemoval,
+            0x0B => TapeAlertFlag::CleaningMedia,
+            0x0C => TapeAlertFlag::UnsupportedFormat,
+            0x0D => TapeAlertFlag::RecoverableMechanicalCartridgeFailure,
+            0x0E => TapeAlertFlag::UnrecoverableMechanicalCartridgeFailure,
+            0x0F => TapeAlertFlag::MemoryChipInCartridgeFailure,
+            0x10 => TapeAlertFlag::ForcedEject,
+            0x11 => TapeAlertFlag::ReadOnlyFormat,
+            0x12 => TapeAlertFlag::TapeDirectoryCorrupted,
+            0x13 => TapeAlertFlag::NearingMediaLife,
+            0x14 => TapeAlertFlag::CleanNow,
+            0x15 => TapeAlertFlag::CleanPeriodic,
+            0x16 => TapeAlertFlag::ExpiredCleaningMedia,
+            0x17 => TapeAlertFlag::InvalidCleaningTape,
+            0x1E => TapeAlertFlag::HardwareA,
+            0x1F => TapeAlertFlag::HardwareB,
+            0x20 => TapeAlertFlag::Interface,
+            0x21 => TapeAlertFlag::EjectMedia,
+            0x22 => TapeAlertFlag::MicrocodeUpdateFail,
+            0x23 => TapeAlertFlag::DriveHumidity,
+            0x24 => TapeAlertFlag::DriveTemperature,
+            0x25 => TapeAlertFlag::DriveVoltage,
+            0x26 => TapeAlertFlag::PredictiveFailure,
+            0x27 => TapeAlertFlag::DiagnosticsRequired,
+            other => TapeAlertFlag::Other(other),
+        }
+    }
+
+    /// 是否提示驱动器需要清洁
+    pub fn requires_cleaning(&self) -> bool {
+        matches!(self, TapeAlertFlag::CleanNow | TapeAlertFlag::CleanPeriodic)
+    }
+}
+
+/// 驱动器清洁状态（TapeAlert标志 0x14/0x15、Sequential-Access Device 日志页）
+#[derive(Debug, Clone, Default)]
+pub struct CleaningStatus {
+    /// 驱动器请求清洁（TapeAlert Clean Now / Clean Periodic 或日志页中的Cleaning Required）
+    pub cleaning_required: bool,
+    /// 装入的是清洁带 (TapeAlert 0x0B)
+    pub cleaning_cartridge: bool,
+    /// 清洁带已使用的次数（仅装入清洁带且能读取MAM时）
+    pub cycles_used: Option<u64>,
+    /// 清洁带剩余的次数；清洁带过期 (TapeAlert 0x16) 时为0
+    pub cycles_remaining: Option<u64>,
+}
+
+/// 单个方向（读或写）的错误计数
+#[derive(Debug, Clone, Default)]
+pub struct ErrorCounters {
+    /// 已纠正的错误总数
+    pub corrected: u64,
+    /// 未能纠正的错误总数
+    pub uncorrected: u64,
+    /// 重写/重读次数
+    pub retries: u64,
+    /// 处理的总字节数
+    pub bytes_processed: u64,
+}
+
+/// 驱动器读写错误计数（日志页 0x02 / 0x03）
+#[derive(Debug, Clone, Default)]
+pub struct DriveErrorCounters {
+    pub write: ErrorCounters,
+    pub read: ErrorCounters,
+}
+
+/// 解析日志页参数列表，返回 (参数代码, 参数值)
+/// 参数格式: 代码(2字节) + 控制字节 + 长度(1字节) + 值
+fn parse_log_parameters(page_data: &[u8]) -> Vec<(u16, &[u8])> {
+    let mut parameters = Vec::new();
+    if page_data.len() < 4 {
+        return parameters;
+    }
+
+    let page_length = u16::from_be_bytes([page_data[2], page_data[3]]) as usize;
+    let page_end = std::cmp::min(4 + page_length, page_data.len());
+
+    let mut offset = 4;
+    while offset + 4 <= page_end {
+        let code = u16::from_be_bytes([page_data[offset], page_data[offset + 1]]);
+        let length = page_data[offset + 3] as usize;
+        let value_start = offset + 4;
+        if value_start + length > page_end {
+            warn!(
+                "Log parameter 0x{:04X} extends beyond page boundary at offset {}",
+                code, offset
+            );
+            break;
+        }
+
+        parameters.push((code, &page_data[value_start..value_start + length]));
+        offset = value_start + length;
+    }
+
+    parameters
+}
+
+/// 大端序参数值转为整数（最多取8字节）
+fn parameter_value_u64(value: &[u8]) -> u64 {
+    value
+        .iter()
+        .take(8)
+        .fold(0u64, |acc, &byte| (acc << 8) | byte as u64)
+}
+
+/// 解析错误计数日志页
+fn parse_error_counters(page_data: &[u8]) -> ErrorCounters {
+    let mut counters = ErrorCounters::default();
+
+    for (code, value) in parse_log_parameters(page_data) {
+        match code {
+            PARAM_TOTAL_REWRITES_OR_REREADS => counters.retries = parameter_value_u64(value),
+            PARAM_TOTAL_ERRORS_CORRECTED => counters.corrected = parameter_value_u64(value),
+            PARAM_TOTAL_BYTES_PROCESSED => counters.bytes_processed = parameter_value_u64(value),
+            PARAM_TOTAL_UNCORRECTED_ERRORS => counters.uncorrected = parameter_value_u64(value),
+            _ => {}
+        }
+    }
+
+    counters
+}
+
+/// TapeOperations驱动器健康状态实现
+impl super::TapeOperations {
+    /// 读取TapeAlert日志页 (0x2E)，返回当前激活的告警标志
+    pub fn read_tape_alert(&self) -> Result<Vec<TapeAlertFlag>> {
+        let page_data = self.scsi.log_sense_subpage(TAPE_ALERT_LOG_PAGE, 0x00)?;
+
+        let flags: Vec<TapeAlertFlag> = parse_log_parameters(&page_data)
+            .into_iter()
+            .filter(|(code, value)| {
+                (1..=0x40).contains(code) && value.first().is_some_and(|flag| flag & 0x01 != 0)
+            })
+            .map(|(code, _)| TapeAlertFlag::from_code(code as u8))
+            .collect();
+
+        if flags.is_empty() {
+            debug!("No TapeAlert flags set");
+        } else {
+            warn!("Active TapeAlert flags: {:?}", flags);
+        }
+
+        Ok(flags)
+    }
+
+    /// 读取驱动器清洁状态
+    ///
+    /// TapeAlert日志页必须可读；Sequential-Access Device日志页和清洁带的MAM计数是可选的，
+    /// 驱动器不支持时对应字段保持默认值。
+    pub fn get_cleaning_status(&self) -> Result<CleaningStatus> {
+        let flags = self.read_tape_alert()?;
+        let mut status = CleaningStatus {
+            cleaning_required: flags.iter().any(TapeAlertFlag::requires_cleaning),
+            cleaning_cartridge: flags.contains(&TapeAlertFlag::CleaningMedia),
+            ..Default::default()
+        };
+
+        match self.scsi.log_sense_subpage(SEQUENTIAL_ACCESS_LOG_PAGE, 0x00) {
+            Ok(page_data) => {
+                let required = parse_log_parameters(&page_data)
+                    .into_iter()
+                    .any(|(code, value)| code == PARAM_CLEANING_REQUIRED && parameter_value_u64(value) != 0);
+                status.cleaning_required |= required;
+            }
+            Err(e) => debug!("Sequential-access device log page unavailable: {}", e),
+        }
+
+        if status.cleaning_cartridge {
+            match self.scsi.read_mam_attribute(MAM_LOAD_COUNT) {
+                Ok(attribute) => {
+                    let used = parameter_value_u64(&attribute.value);
+                    status.cycles_used = Some(used);
+                    status.cycles_remaining = Some(CLEANING_CARTRIDGE_CYCLES.saturating_sub(used));
+                }
+                Err(e) => debug!("Cleaning cartridge load count unavailable: {}", e),
+            }
+            if flags.contains(&TapeAlertFlag::ExpiredCleaningMedia) {
+                status.cycles_remaining = Some(0);
+            }
+        }
+
+        debug!("Cleaning status: {:?}", status);
+        Ok(status)
+    }
+
+    /// 驱动器需要清洁时拒绝长时间写入（超过 `CLEANING_REQUIRED_WRITE_LIMIT`，长度未知的流也算），
+    /// 除非给出 `--ignore-cleaning`：磁头脏污时写入的数据可能无法再读出
+    pub(crate) fn ensure_clean_for_write(&self, bytes: Option<u64>) -> Result<()> {
+        let required = self
+            .cleaning_status
+            .as_ref()
+            .is_some_and(|status| status.cleaning_required);
+        if !required || bytes.is_some_and(|bytes| bytes <= CLEANING_REQUIRED_WRITE_LIMIT) {
+            return Ok(());
+        }
+        if self.write_options.ignore_cleaning {
+            warn!("Drive requires cleaning; writing anyway (--ignore-cleaning)");
+            return Ok(());
+        }
+        Err(RustLtfsError::tape_device(format!(
+            "Drive requires cleaning: refusing to write {} with a dirty head, which risks unreadable data. \
+             Clean the drive, or pass --ignore-cleaning to write anyway",
+            bytes.map_or("a stream of unknown length".to_string(), crate::utils::format_bytes)
+        )))
+    }
+
+    /// 读取写/读错误计数日志页 (0x02 / 0x03)
+    pub fn read_error_counters(&self) -> Result<DriveErrorCounters> {
+        let write_page = self.scsi.log_sense_subpage(WRITE_ERROR_COUNTER_LOG_PAGE, 0x00)?;
+        let read_page = self.scsi.log_sense_subpage(READ_ERROR_COUNTER_LOG_PAGE, 0x00)?;
+
+        let counters = DriveErrorCounters {
+            write: parse_error_counters(&write_page),
+            read: parse_error_counters(&read_page),
+        };
+
+        info!(
+            "Error counters: write corrected={} uncorrected={} retries={}, read corrected={} uncorrected={} retries={}",
+            counters.write.corrected,
+            counters.write.uncorrected,
+            counters.write.retries,
+            counters.read.corrected,
+            counters.read.uncorrected,
+            counters.read.retries
+        );
+
+        Ok(counters)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_counter_page_is_parsed() {
+        let page = [
+            0x03, 0x00, 0x00, 0x1C, // 页头：读错误计数页，参数长度28
+            0x00, 0x02, 0x60, 0x02, 0x01, 0x2C, // 重读 300
+            0x00, 0x03, 0x60, 0x04, 0x00, 0x00, 0x00, 0x07, // 已纠正 7
+            0x00, 0x05, 0x60, 0x05, 0x01, 0x00, 0x00, 0x00, 0x00, // 处理字节数 2^32
+            0x00, 0x06, 0x60, 0x01, 0x02, // 未纠正 2
+        ];
+        let counters = parse_error_counters(&page);
+        assert_eq!(counters.retries, 300);
+        assert_eq!(counters.corrected, 7);
+        assert_eq!(counters.bytes_processed, 1 << 32);
+        assert_eq!(counters.uncorrected, 2);
+
+        // 截断的参数被忽略，之前的参数仍然有效
+        let counters = parse_error_counters(&page[..page.len() - 1]);
+        assert_eq!((counters.corrected, counters.uncorrected), (7, 0));
+    }
+}
@@ -0,0 +1,356 @@
+//! Raw Partition Imaging
+//!
+//! Dumps a whole tape partition block-by-block, regardless of LTFS structure, into a
+//! raw image file plus a JSON manifest recording block sizes and filemark positions,
+//! so the image can be re-interpreted later.
+//!
+//! `scan_raw` is the recovery counterpart: it splits a partition on filemarks into one
+//! output file per region, for when no usable index is left.
+
+use super::TapeOperations;
+use crate::error::{Result, RustLtfsError};
+use crate::scsi::RawBlock;
+use serde::Serialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, warn};
+
+/// 单块读取缓冲区大小 (对应LTFSCopyGUI的GlobalBlockLimit)，更大的块会报错
+const DUMP_MAX_BLOCK_SIZE: usize = 1_048_576;
+
+/// A run of consecutive data blocks of the same size
+#[derive(Debug, Clone, Serialize)]
+pub struct BlockRun {
+    pub size: usize,
+    pub count: u64,
+}
+
+/// The data blocks between two filemarks (or before EOD)
+#[derive(Debug, Clone, Serialize)]
+pub struct DumpSegment {
+    /// Block address of the first block of the segment
+    pub start_block: u64,
+    /// Byte offset of the segment in the image file
+    pub image_offset: u64,
+    pub bytes: u64,
+    pub blocks: Vec<BlockRun>,
+}
+
+/// Sidecar manifest written next to a raw partition image
+#[derive(Debug, Clone, Serialize)]
+pub struct DumpManifest {
+    pub device: String,
+    pub partition: u8,
+    pub created: String,
+    pub max_block_size: usize,
+    pub data_blocks: u64,
+    pub bytes: u64,
+    /// Block address of every filemark; segment `i` ends at filemark `i`
+    pub filemarks: Vec<u64>,
+    pub segments: Vec<DumpSegment>,
+    /// Set when the dump stopped on a read error before EOD
+    pub error: Option<String>,
+}
+
+/// Dump progress snapshot, passed to the progress callback after each block or filemark
+#[derive(Debug, Clone, Default)]
+pub struct DumpProgress {
+    pub blocks: u64,
+    pub bytes: u64,
+    pub filemarks: u64,
+}
+
+/// Progress callback for `dump_partition`
+pub type DumpProgressCallback = std::sync::Arc<dyn Fn(&DumpProgress) + Send + Sync>;
+
+/// One inter-filemark region recovered by `scan_raw`
+#[derive(Debug, Clone)]
+pub struct ScavengedFile {
+    pub path: PathBuf,
+    /// Block address of the first block of the region
+    pub start_block: u64,
+    pub blocks: u64,
+    pub bytes: u64,
+}
+
+/// Result of a raw scan; regions without data blocks produce no file
+#[derive(Debug, Clone, Default)]
+pub struct ScavengeReport {
+    pub files: Vec<ScavengedFile>,
+    /// Regions seen, including empty ones (the sequence number of the next region)
+    pub regions: u64,
+    /// Block address where the scan stopped (EOD or the failing block)
+    pub end_block: u64,
+    /// Set when the scan stopped on a read error before EOD
+    pub error: Option<String>,
+}
+
+/// Manifest path for an image: `<image>.manifest.json`
+pub fn manifest_path(image: &Path) -> PathBuf {
+    let mut path = image.as_os_str().to_owned();
+    path.push(".manifest.json");
+    PathBuf::from(path)
+}
+
+impl DumpSegment {
+    fn new(start_block: u64, image_offset: u64) -> Self {
+        Self {
+            start_block,
+            image_offset,
+            bytes: 0,
+            blocks: Vec::new(),
+        }
+    }
+
+    fn push_block(&mut self, size: usize) {
+        self.bytes += size as u64;
+        match self.blocks.last_mut() {
+            Some(run) if run.size == size => run.count += 1,
+            _ => self.blocks.push(BlockRun { size, count: 1 }),
+        }
+    }
+}
+
+impl TapeOperations {
+    /// 将整个分区按块原样转储到 `output`，并写出记录块大小和FileMark位置的清单
+    ///
+    /// 从分区起点读到EOD。读取出错时仍写出已转储部分的清单（`error` 字段记录原因）后返回错误。
+    pub fn dump_partition(
+        &self,
+        partition: u8,
+        output: &Path,
+        progress: Option<DumpProgressCallback>,
+    ) -> Result<DumpManifest> {
+        let file = std::fs::File::create(output).map_err(|e| {
+            RustLtfsError::file_operation(format!("Cannot create image {:?}: {}", output, e))
+        })?;
+        let mut writer = std::io::BufWriter::new(file);
+
+        let mut manifest = DumpManifest {
+            device: self.device_path.clone(),
+            partition,
+            created: super::utils::get_current_ltfs_timestamp(),
+            max_block_size: DUMP_MAX_BLOCK_SIZE,
+            data_blocks: 0,
+            bytes: 0,
+            filemarks: Vec::new(),
+            segments: Vec::new(),
+            error: None,
+        };
+
+        info!("Dumping partition {} to {:?}", partition, output);
+        self.invalidate_position_cache();
+        let result = self
+            .scsi
+            .locate_block(partition, 0)
+            .and_then(|()| self.dump_blocks(&mut writer, &mut manifest, progress));
+        let result = result.and_then(|()| writer.flush().map_err(RustLtfsError::from));
+        self.invalidate_position_cache();
+
+        if let Err(e) = &result {
+            warn!("Partition dump stopped after {} blocks: {}", manifest.data_blocks, e);
+            manifest.error = Some(e.to_string());
+        }
+
+        let manifest_file = manifest_path(output);
+        let json = serde_json::to_vec_pretty(&manifest)
+            .map_err(|e| RustLtfsError::system(format!("Cannot encode dump manifest: {}", e)))?;
+        std::fs::write(&manifest_file, json).map_err(|e| {
+            RustLtfsError::file_operation(format!("Cannot write manifest {:?}: {}", manifest_file, e))
+        })?;
+
+        result.map(|()| manifest)
+    }
+
+    /// 从当前位置逐块读取直到EOD（出错时当前区段也记入清单）
+    fn dump_blocks<W: Write>(
+        &self,
+        writer: &mut W,
+        manifest: &mut DumpManifest,
+        progress: Option<DumpProgressCallback>,
+    ) -> Result<()> {
+        let mut buffer = vec![0u8; DUMP_MAX_BLOCK_SIZE];
+        let mut block_address = 0u64;
+        let mut segment = DumpSegment::new(0, 0);
+
+        loop {
+            let context = format!("at block {} of partition {}", block_address, manifest.partition);
+            let step = self
+                .check_cancelled(&context)
+                .and_then(|()| self.scsi.read_raw_block(&mut buffer))
+                .and_then(|block| {
+                    if let RawBlock::Data(len) = block {
+                        writer.write_all(&buffer[..len])?;
+                    }
+                    Ok(block)
+                });
+            let block = match step {
+                Ok(block) => block,
+                Err(e) => {
+                    manifest.segments.push(segment);
+                    return Err(e);
+                }
+            };
+
+            match block {
+                RawBlock::Data(len) => {
+                    segment.push_block(len);
+                    manifest.data_blocks += 1;
+                    manifest.bytes += len as u64;
+                }
+                RawBlock::FileMark => {
+                    manifest.filemarks.push(block_address);
+                    let next = DumpSegment::new(block_address + 1, manifest.bytes);
+                    manifest.segments.push(std::mem::replace(&mut segment, next));
+                }
+                RawBlock::EndOfData => {
+                    manifest.segments.push(segment);
+                    info!(
+                        "Reached EOD at block {}: {} blocks, {} bytes, {} filemarks",
+                        block_address,
+                        manifest.data_blocks,
+                        manifest.bytes,
+                        manifest.filemarks.len()
+                    );
+                    return Ok(());
+                }
+            }
+            block_address += 1;
+
+            if let Some(callback) = &progress {
+                callback(&DumpProgress {
+                    blocks: manifest.data_blocks,
+                    bytes: manifest.bytes,
+                    filemarks: manifest.filemarks.len() as u64,
+                });
+            }
+        }
+    }
+
+    /// 不依赖索引的恢复扫描：从 `partition` 的 `start_block` 顺序读到EOD，
+    /// 每两个FileMark之间的数据写成 `output_dir` 中按序号命名的一个文件
+    ///
+    /// 以 `<?xml` 开头的区域（索引、标签）保存为 `.xml`，其余为 `.bin`。
+    /// 读取出错时停止，已恢复的文件保留，错误记录在报告的 `error` 中。
+    pub fn scan_raw(
+        &self,
+        partition: u8,
+        start_block: u64,
+        output_dir: &Path,
+        progress: Option<DumpProgressCallback>,
+    ) -> Result<ScavengeReport> {
+        std::fs::create_dir_all(output_dir).map_err(|e| {
+            RustLtfsError::file_operation(format!("Cannot create {:?}: {}", output_dir, e))
+        })?;
+
+        info!(
+            "Scanning partition {} from block {} into {:?}",
+            partition, start_block, output_dir
+        );
+        let mut report = ScavengeReport {
+            end_block: start_block,
+            ..Default::default()
+        };
+        self.invalidate_position_cache();
+        let result = self
+            .scsi
+            .locate_block(partition, start_block)
+            .and_then(|()| self.scan_regions(output_dir, &mut report, progress));
+        self.invalidate_position_cache();
+
+        if let Err(e) = result {
+            warn!("Raw scan stopped at block {}: {}", report.end_block, e);
+            report.error = Some(e.to_string());
+        }
+        Ok(report)
+    }
+
+    /// 逐块读取并按FileMark切分成文件，直到EOD
+    fn scan_regions(
+        &self,
+        output_dir: &Path,
+        report: &mut ScavengeReport,
+        progress: Option<DumpProgressCallback>,
+    ) -> Result<()> {
+        let mut buffer = vec![0u8; DUMP_MAX_BLOCK_SIZE];
+        let mut current: Option<(ScavengedFile, std::io::BufWriter<std::fs::File>)> = None;
+        let mut totals = DumpProgress::default();
+
+        loop {
+            let step = self
+                .check_cancelled(&format!("at block {}", report.end_block))
+                .and_then(|()| self.scsi.read_raw_block(&mut buffer));
+            let block = match step {
+                Ok(block) => block,
+                Err(e) => {
+                    // 出错前已读到的部分区域也保留
+                    Self::finish_region(current.take(), report)?;
+                    return Err(e);
+                }
+            };
+
+            match block {
+                RawBlock::Data(len) => {
+                    if current.is_none() {
+                        let extension = if buffer[..len].starts_with(b"<?xml") { "xml" } else { "bin" };
+                        let path = output_dir.join(format!("{:06}.{}", report.regions, extension));
+                        let file = std::fs::File::create(&path).map_err(|e| {
+                            RustLtfsError::file_operation(format!("Cannot create {:?}: {}", path, e))
+                        })?;
+                        let region = ScavengedFile {
+                            path,
+                            start_block: report.end_block,
+                            blocks: 0,
+                            bytes: 0,
+                        };
+                        current = Some((region, std::io::BufWriter::new(file)));
+                    }
+                    if let Some((region, writer)) = current.as_mut() {
+                        writer.write_all(&buffer[..len])?;
+                        region.blocks += 1;
+                        region.bytes += len as u64;
+                    }
+                    totals.blocks += 1;
+                    totals.bytes += len as u64;
+                }
+                RawBlock::FileMark => {
+                    Self::finish_region(current.take(), report)?;
+                    report.regions += 1;
+                    totals.filemarks += 1;
+                }
+                RawBlock::EndOfData => {
+                    Self::finish_region(current.take(), report)?;
+                    info!(
+                        "Reached EOD at block {}: {} files from {} regions, {} bytes",
+                        report.end_block,
+                        report.files.len(),
+                        report.regions,
+                        totals.bytes
+                    );
+                    return Ok(());
+                }
+            }
+            report.end_block += 1;
+
+            if let Some(callback) = &progress {
+                callback(&totals);
+            }
+        }
+    }
+
+    /// 结束当前区域的输出文件（区域中没有数据块时什么也不做）
+    fn finish_region(
+        current: Option<(ScavengedFile, std::io::BufWriter<std::fs::File>)>,
+        report: &mut ScavengeReport,
+    ) -> Result<()> {
+        if let Some((region, mut writer)) = current {
+            writer.flush()?;
+            debug!(
+                "Recovered {:?}: {} blocks, {} bytes from block {}",
+                region.path, region.blocks, region.bytes, region.start_block
+            );
+            report.files.push(region);
+        }
+        Ok(())
+    }
+}
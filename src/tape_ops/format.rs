@@ -0,0 +1,419 @@
+//! LTFS Volume Formatting
+//!
+//! Partitions a cartridge and writes the LTFS label constructs plus an empty index on
+//! every partition (对应LTFSCopyGUI的mkltfs)，leaving a volume ready for `write`.
+
+use super::TapeOperations;
+use crate::error::{Result, RustLtfsError};
+use crate::ltfs_index::{Location, LtfsIndex};
+use tracing::info;
+use zeroize::Zeroizing;
+
+/// 分区占用剩余全部容量 (对应LTFSCopyGUI的 P1Size = &HFFFF)
+pub const PARTITION_SIZE_MAX: u16 = 0xFFFF;
+/// 分区大小单位：10^9 字节 (mode page 0x11 的 Partition Units = 9)
+const PARTITION_UNIT_BYTES: u64 = 1_000_000_000;
+/// LTFS 规范要求的最小块大小
+const MIN_BLOCK_SIZE: u32 = 4096;
+/// 单块上限 (对应LTFSCopyGUI的GlobalBlockLimit)
+const MAX_BLOCK_SIZE: u32 = 1_048_576;
+/// MAM attribute: BARCODE (ASCII, 32 bytes)
+const MAM_BARCODE: u16 = 0x0806;
+/// MAM attribute: USER MEDIUM TEXT LABEL (TEXT, 160 bytes)
+const MAM_USER_MEDIUM_TEXT_LABEL: u16 = 0x0803;
+/// Set Data Encryption 的算法索引 (LTO驱动器的 AES-256-GCM)
+const ENCRYPTION_ALGORITHM_INDEX: u8 = 1;
+/// AES-256 密钥长度
+const ENCRYPTION_KEY_LEN: usize = 32;
+
+/// Parameters for formatting a cartridge as an LTFS volume (对应LTFSCopyGUI的MKLTFS参数)
+#[derive(Clone)]
+pub struct MkltfsParams {
+    /// Barcode written to cartridge memory; its first 6 characters become the VOL1 volume serial
+    pub barcode: String,
+    /// Volume name written to the cartridge memory user text label
+    pub volume_label: String,
+    /// 0 = single partition, 1 = index partition a + data partition b
+    pub extra_partition_count: u8,
+    /// Partition 0 size in 10^9-byte units, `PARTITION_SIZE_MAX` for the remaining capacity
+    pub p0_size: u16,
+    /// Partition 1 size in 10^9-byte units, `PARTITION_SIZE_MAX` for the remaining capacity
+    pub p1_size: u16,
+    pub block_size: u32,
+    /// AES-256 key for drive encryption; zeroized when dropped
+    pub encryption_key: Option<Zeroizing<Vec<u8>>>,
+}
+
+impl MkltfsParams {
+    /// 默认参数：双分区，P0为1GB索引分区，P1占用剩余容量，512KB块
+    pub fn new(barcode: &str, volume_label: &str) -> Self {
+        Self {
+            barcode: barcode.trim().to_string(),
+            volume_label: volume_label.to_string(),
+            extra_partition_count: 1,
+            p0_size: 1,
+            p1_size: PARTITION_SIZE_MAX,
+            block_size: crate::scsi::block_sizes::LTO_BLOCK_SIZE_512K,
+            encryption_key: None,
+        }
+    }
+
+    /// 设置分区0的大小（字节，向上取整到10^9字节单位）；`u64::MAX` 表示占用剩余容量
+    pub fn set_p0_size(&mut self, bytes: u64) {
+        self.p0_size = partition_size_units(bytes);
+    }
+
+    /// 设置分区1的大小（字节，向上取整到10^9字节单位）；`u64::MAX` 表示占用剩余容量
+    pub fn set_p1_size(&mut self, bytes: u64) {
+        self.p1_size = partition_size_units(bytes);
+    }
+
+    /// 格式化前检查参数，避免磁带已经分区后才发现参数无效
+    pub fn validate(&self) -> Result<()> {
+        validate_barcode(&self.barcode)?;
+        validate_volume_name(&self.volume_label)?;
+        if self.extra_partition_count > 1 {
+            return Err(RustLtfsError::parameter_validation(format!(
+                "LTFS uses at most one extra partition, {} requested",
+                self.extra_partition_count
+            )));
+        }
+        if self.extra_partition_count == 1 {
+            if self.p0_size == PARTITION_SIZE_MAX && self.p1_size == PARTITION_SIZE_MAX {
+                return Err(RustLtfsError::parameter_validation(
+                    "Partition 0 and partition 1 cannot both take the remaining capacity; give partition 0 a fixed size",
+                ));
+            }
+            if self.p0_size == 0 || self.p1_size == 0 {
+                return Err(RustLtfsError::parameter_validation(
+                    "Partition sizes must be at least 1 GB",
+                ));
+            }
+        }
+        if !(MIN_BLOCK_SIZE..=MAX_BLOCK_SIZE).contains(&self.block_size) {
+            return Err(RustLtfsError::parameter_validation(format!(
+                "Block size {} is outside the supported range {}..={}",
+                self.block_size, MIN_BLOCK_SIZE, MAX_BLOCK_SIZE
+            )));
+        }
+        if let Some(key) = &self.encryption_key {
+            if key.len() != ENCRYPTION_KEY_LEN {
+                return Err(RustLtfsError::parameter_validation(format!(
+                    "Encryption key is {} bytes, AES-256 needs {}",
+                    key.len(),
+                    ENCRYPTION_KEY_LEN
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// VOL1 卷序列号：条码前6个字符
+    fn volume_serial(&self) -> String {
+        self.barcode.chars().take(6).collect()
+    }
+}
+
+/// Progress callback for `mkltfs`, called with a description of each step
+pub type FormatProgressCallback = std::sync::Arc<dyn Fn(&str) + Send + Sync>;
+
+fn partition_size_units(bytes: u64) -> u16 {
+    if bytes == u64::MAX {
+        return PARTITION_SIZE_MAX;
+    }
+    let units = bytes.div_ceil(PARTITION_UNIT_BYTES);
+    // 0xFFFF 保留给“剩余容量”
+    units.min(PARTITION_SIZE_MAX as u64 - 1) as u16
+}
+
+/// 80字节 VOL1 标签 (LTFS格式：Accessibility 'L'，Implementation Identifier "LTFS"，标签版本 '4')
+fn vol1_label(volume_serial: &str) -> Vec<u8> {
+    let mut label = vec![b' '; 80];
+    label[..4].copy_from_slice(b"VOL1");
+    for (dst, src) in label[4..10].iter_mut().zip(volume_serial.bytes()) {
+        *dst = src;
+    }
+    label[10] = b'L';
+    label[24..28].copy_from_slice(b"LTFS");
+    label[79] = b'4';
+    label
+}
+
+/// 分区标签 XML (对应LTFSCopyGUI的ltfslabel)
+fn ltfs_label_xml(
+    index: &LtfsIndex,
+    partition: &str,
+    index_partition: &str,
+    data_partition: &str,
+    block_size: u32,
+    compression: bool,
+) -> String {
+    format!(
+        concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<ltfslabel version=\"{}\">\n",
+            "  <creator>{}</creator>\n",
+            "  <formattime>{}</formattime>\n",
+            "  <volumeuuid>{}</volumeuuid>\n",
+            "  <location>\n    <partition>{}</partition>\n  </location>\n",
+            "  <partitions>\n    <index>{}</index>\n    <data>{}</data>\n  </partitions>\n",
+            "  <blocksize>{}</blocksize>\n",
+            "  <compression>{}</compression>\n",
+            "</ltfslabel>\n"
+        ),
+        index.version,
+        index.creator,
+        index.updatetime,
+        index.volumeuuid,
+        partition,
+        index_partition,
+        data_partition,
+        block_size,
+        compression
+    )
+}
+
+/// 条码写入MAM BARCODE属性 (32字节)：1-32个不含空格的可打印ASCII字符
+fn validate_barcode(barcode: &str) -> Result<()> {
+    if barcode.is_empty() || barcode.len() > 32 || !barcode.bytes().all(|b| b.is_ascii_graphic()) {
+        return Err(RustLtfsError::parameter_validation(format!(
+            "Barcode '{}' must be 1-32 printable ASCII characters without spaces",
+            barcode
+        )));
+    }
+    Ok(())
+}
+
+/// 卷名同时是索引根目录名和MAM用户文本标签 (160字节)，不能含 `/` 或控制字符
+fn validate_volume_name(name: &str) -> Result<()> {
+    if name.len() > 160 {
+        return Err(RustLtfsError::parameter_validation(format!(
+            "Volume label is {} bytes, the cartridge memory text label holds at most 160",
+            name.len()
+        )));
+    }
+    if name.chars().any(|c| c == '/' || c.is_control()) {
+        return Err(RustLtfsError::parameter_validation(format!(
+            "Volume label {:?} cannot contain '/' or control characters",
+            name
+        )));
+    }
+    Ok(())
+}
+
+/// MAM 文本属性按固定长度以空格填充
+fn padded_attribute(value: &str, len: usize) -> Vec<u8> {
+    let mut bytes = value.as_bytes().to_vec();
+    bytes.resize(len, b' ');
+    bytes
+}
+
+impl TapeOperations {
+    /// 将磁带格式化为LTFS卷 (对应LTFSCopyGUI的mkltfs)
+    ///
+    /// 分区 (MODE SELECT page 0x11 + FORMAT MEDIUM)，写入条码/卷标MAM属性，然后在每个分区写入
+    /// VOL1 + LTFS label，并写入第1代空索引（先数据分区，再索引分区 FileMark 3 之后）。
+    /// 磁带上原有的全部数据都会丢失。
+    pub async fn mkltfs(
+        &mut self,
+        params: &MkltfsParams,
+        progress: Option<FormatProgressCallback>,
+    ) -> Result<LtfsIndex> {
+        params.validate()?;
+        if let Some(media) = self.media_type {
+            if media.is_write_protected() {
+                return Err(RustLtfsError::tape_device(format!(
+                    "Write protected: {:?} cartridge cannot be formatted",
+                    media
+                )));
+            }
+        }
+        if let Ok((max_block_length, _)) = self.scsi.read_block_limits() {
+            if max_block_length > 0 && params.block_size > max_block_length {
+                return Err(RustLtfsError::parameter_validation(format!(
+                    "Block size {} exceeds the drive limit of {}",
+                    params.block_size, max_block_length
+                )));
+            }
+        }
+
+        let report = |step: &str| {
+            info!("mkltfs: {}", step);
+            if let Some(callback) = &progress {
+                callback(step);
+            }
+        };
+
+        self.rewind().await?;
+        if params.extra_partition_count > 0 {
+            report(&format!(
+                "Partitioning medium (P0 {}, P1 {})",
+                describe_partition_size(params.p0_size),
+                describe_partition_size(params.p1_size)
+            ));
+            self.scsi
+                .set_partition_page(params.extra_partition_count, &[params.p0_size, params.p1_size])?;
+            self.scsi.format_medium(true)?;
+        } else {
+            report("Formatting medium as a single partition");
+            self.scsi.format_medium(false)?;
+        }
+        self.invalidate_position_cache();
+
+        self.initialize_partition_detection().await?;
+        if self.get_extra_partition_count() != params.extra_partition_count {
+            return Err(RustLtfsError::tape_device(format!(
+                "Medium reports {} extra partitions after formatting, expected {}",
+                self.get_extra_partition_count(),
+                params.extra_partition_count
+            )));
+        }
+
+        report(&format!("Writing barcode {} to cartridge memory", params.barcode));
+        self.scsi
+            .write_attribute(0, MAM_BARCODE, 0x01, &padded_attribute(&params.barcode, 32))?;
+        if !params.volume_label.is_empty() {
+            self.scsi.write_attribute(
+                0,
+                MAM_USER_MEDIUM_TEXT_LABEL,
+                0x02,
+                &padded_attribute(&params.volume_label, 160),
+            )?;
+        }
+
+        if let Some(key) = &params.encryption_key {
+            report("Enabling drive encryption");
+            self.scsi.set_data_encryption(key, ENCRYPTION_ALGORITHM_INDEX)?;
+        }
+
+        let mut index = self.create_new_ltfs_index();
+        index.generationnumber = 1;
+        index.root_directory.name = params.volume_label.clone();
+        let compression = self.scsi.get_compression().map(|page| page.dce).unwrap_or(false);
+        let (index_partition, data_partition) = if params.extra_partition_count > 0 {
+            ("a", "b")
+        } else {
+            ("a", "a")
+        };
+
+        // 变长块写入标签和索引
+        self.scsi.set_block_size(0)?;
+        let partitions: &[(u8, &str)] = if params.extra_partition_count > 0 {
+            &[(0, "a"), (1, "b")]
+        } else {
+            &[(0, "a")]
+        };
+        for &(partition, name) in partitions {
+            report(&format!("Writing label construct to partition {}", name));
+            let label = ltfs_label_xml(
+                &index,
+                name,
+                index_partition,
+                data_partition,
+                params.block_size,
+                compression,
+            );
+            self.scsi.locate_block(partition, 0)?;
+            self.scsi
+                .write_blocks_allow_early_warning(1, &vol1_label(&params.volume_serial()))?;
+            self.scsi.write_filemarks(1)?;
+            self.scsi.write_blocks_allow_early_warning(1, label.as_bytes())?;
+            self.scsi.write_filemarks(1)?;
+        }
+
+        // 数据分区（单分区时即分区a）标签之后写入第1代索引
+        let last = *partitions.last().unwrap_or(&(0, "a"));
+        report(&format!("Writing initial index to partition {}", last.1));
+        self.write_format_index(&mut index, last.0, last.1)?;
+
+        if params.extra_partition_count > 0 {
+            // 索引分区的副本：位置指向分区a，上一代指向数据分区中的同一份索引
+            report("Writing initial index to partition a");
+            self.scsi.locate_to_filemark(2, 0)?;
+            index.previousgenerationlocation = Some(index.location.clone());
+            self.write_format_index(&mut index, 0, "a")?;
+        }
+
+        self.invalidate_position_cache();
+        self.partition_label = Some(super::LtfsPartitionLabel {
+            blocksize: params.block_size,
+            compression,
+            volume_serial: Some(params.volume_serial()),
+            volume_uuid: Some(index.volumeuuid.clone()),
+            format_time: Some(index.updatetime.clone()),
+            index_partition: index_partition.to_string(),
+            data_partition: data_partition.to_string(),
+        });
+        self.block_size = params.block_size;
+        self.write_options.block_size = params.block_size;
+        self.index = Some(index.clone());
+        self.schema = Some(index.clone());
+        self.tape_generation = Some(index.generationnumber);
+        self.modified = false;
+        self.modified_directories.clear();
+
+        report("Format complete");
+        Ok(index)
+    }
+
+    /// 在当前位置写入索引构造 (FM, index, FM)，并记录VCI
+    fn write_format_index(&self, index: &mut LtfsIndex, partition: u8, name: &str) -> Result<()> {
+        self.scsi.write_filemarks(1)?;
+        let position = self.scsi.read_position()?;
+        index.location = Location {
+            partition: name.to_string(),
+            startblock: position.block_number,
+        };
+        let xml = index.to_xml()?;
+        self.scsi.write_blocks_allow_early_warning(1, xml.as_bytes())?;
+        self.scsi.write_filemarks(1)?;
+        self.scsi.synchronize_buffer()?;
+        self.write_volume_coherency_info(partition, index);
+        Ok(())
+    }
+
+    /// 将条码写入卡带存储器的BARCODE属性 (0x0806)
+    ///
+    /// 格式化时写入的VOL1卷序列号不会改变。
+    pub fn set_mam_barcode(&self, barcode: &str) -> Result<()> {
+        let barcode = barcode.trim();
+        validate_barcode(barcode)?;
+        info!("Writing barcode {} to cartridge memory", barcode);
+        self.scsi
+            .write_attribute(0, MAM_BARCODE, 0x01, &padded_attribute(barcode, 32))
+    }
+
+    /// 设置卷名：写入MAM用户文本标签，并作为当前索引的根目录名
+    ///
+    /// 只修改内存中的索引，下次提交索引时写回磁带。
+    pub fn set_volume_name(&mut self, name: &str) -> Result<()> {
+        validate_volume_name(name)?;
+        if self.index.is_none() {
+            return Err(RustLtfsError::ltfs_index(
+                "No LTFS index loaded, cannot set the volume name",
+            ));
+        }
+
+        info!("Setting volume name to {:?}", name);
+        self.scsi.write_attribute(
+            0,
+            MAM_USER_MEDIUM_TEXT_LABEL,
+            0x02,
+            &padded_attribute(name, 160),
+        )?;
+        if let Some(index) = self.index.as_mut() {
+            index.root_directory.name = name.to_string();
+            index.root_directory.change_time = super::utils::get_current_ltfs_timestamp();
+        }
+        self.modified = true;
+        Ok(())
+    }
+}
+
+fn describe_partition_size(units: u16) -> String {
+    if units == PARTITION_SIZE_MAX {
+        "remaining capacity".to_string()
+    } else {
+        format!("{} GB", units)
+    }
+}
@@ -2,15 +2,132 @@
 //!
 //! This module provides hash calculation functionality for LTFS file write operations.
 //! Supports multiple hash algorithms: SHA1, MD5, SHA256, BLAKE3, XxHash3, XxHash128.
+//! All enabled algorithms are fed from the same buffer, so a file is read only once.
 
 use super::WriteOptions;
+use crate::error::Result;
 use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+
+/// Read buffer size for `calculate_file_hashes`
+const HASH_READ_BUFFER_SIZE: usize = 1024 * 1024;
+
+// LTFS extended attribute keys for file hashes, the same strings LTFSCopyGUI writes and
+// reads, so a tape hashed by either tool verifies in the other. Values are uppercase hex
+// (compared case-insensitively).
+
+/// SHA1 extended attribute key
+pub const SHA1_XATTR_KEY: &str = "ltfs.hash.sha1sum";
+/// MD5 extended attribute key
+pub const MD5_XATTR_KEY: &str = "ltfs.hash.md5sum";
+/// SHA256 extended attribute key
+pub const SHA256_XATTR_KEY: &str = "ltfs.hash.sha256sum";
+/// BLAKE3 extended attribute key
+pub const BLAKE3_XATTR_KEY: &str = "ltfs.hash.blake3sum";
+/// XxHash3 (64-bit) extended attribute key
+pub const XXHASH3_XATTR_KEY: &str = "ltfs.hash.xxhash3sum";
+/// XxHash128 extended attribute key
+pub const XXHASH128_XATTR_KEY: &str = "ltfs.hash.xxhash128sum";
+
+/// Hash values of one file (uppercase hex); `None` for algorithms that were not enabled
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileHashes {
+    pub sha1: Option<String>,
+    pub md5: Option<String>,
+    pub sha256: Option<String>,
+    pub blake3: Option<String>,
+    pub xxh3: Option<String>,
+    pub xxh128: Option<String>,
+}
+
+impl FileHashes {
+    /// Extended attribute key of each algorithm with its value
+    fn entries(&self) -> [(&'static str, &Option<String>); 6] {
+        [
+            (SHA1_XATTR_KEY, &self.sha1),
+            (MD5_XATTR_KEY, &self.md5),
+            (SHA256_XATTR_KEY, &self.sha256),
+            (BLAKE3_XATTR_KEY, &self.blake3),
+            (XXHASH3_XATTR_KEY, &self.xxh3),
+            (XXHASH128_XATTR_KEY, &self.xxh128),
+        ]
+    }
+
+    /// LTFS extended attributes for the computed hashes (LTFSCopyGUI compatible keys)
+    pub fn to_xattrs(&self) -> HashMap<String, String> {
+        self.entries()
+            .into_iter()
+            .filter_map(|(key, value)| value.clone().map(|value| (key.to_string(), value)))
+            .collect()
+    }
+
+    /// Hashes recorded in a file's extended attributes (written by RustLTFS or LTFSCopyGUI)
+    pub fn from_file(file: &crate::ltfs_index::File) -> Self {
+        let xattr = |key: &str| {
+            file.extended_attributes
+                .as_ref()
+                .and_then(|xattrs| xattrs.attributes.iter().find(|attr| attr.key == key))
+                .map(|attr| attr.value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+
+        Self {
+            sha1: xattr(SHA1_XATTR_KEY),
+            md5: xattr(MD5_XATTR_KEY),
+            sha256: xattr(SHA256_XATTR_KEY),
+            blake3: xattr(BLAKE3_XATTR_KEY),
+            xxh3: xattr(XXHASH3_XATTR_KEY),
+            xxh128: xattr(XXHASH128_XATTR_KEY),
+        }
+    }
+
+    /// Whether no algorithm has a value
+    pub fn is_empty(&self) -> bool {
+        self.entries().iter().all(|(_, value)| value.is_none())
+    }
+
+    /// Compare every hash present in `self` with `actual`
+    ///
+    /// Returns one `key: expected X, got Y` description per differing algorithm;
+    /// algorithms that `actual` did not compute are skipped.
+    pub fn mismatches(&self, actual: &FileHashes) -> Vec<String> {
+        self.entries()
+            .into_iter()
+            .zip(actual.entries())
+            .filter_map(|((key, expected), (_, actual))| match (expected, actual) {
+                (Some(expected), Some(actual)) if !expected.eq_ignore_ascii_case(actual) => {
+                    Some(format!("{}: expected {}, got {}", key, expected, actual))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Compute every hash enabled in `options` in a single pass over the file
+pub fn calculate_file_hashes(path: &Path, options: &WriteOptions) -> Result<FileHashes> {
+    let mut file = std::fs::File::open(path)?;
+    let mut calculator = CheckSumBlockwiseCalculator::new_with_options(options);
+    let mut buffer = vec![0u8; HASH_READ_BUFFER_SIZE];
+
+    loop {
+        let n = file.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        calculator.propagate(&buffer[..n]);
+    }
+
+    calculator.process_final_block();
+    Ok(calculator.file_hashes())
+}
 
 /// LTFSCopyGUI compatible hash calculator
 /// Corresponds to VB.NET CheckSumBlockwiseCalculator
 pub struct CheckSumBlockwiseCalculator {
-    sha1_hasher: sha1::Sha1,
-    md5_hasher: md5::Context,
+    sha1_hasher: Option<sha1::Sha1>,
+    md5_hasher: Option<md5::Context>,
     sha256_hasher: sha2::Sha256,
     blake3_hasher: Option<blake3::Hasher>,
     xxh3_hasher: Option<xxhash_rust::xxh3::Xxh3>,
@@ -25,8 +142,16 @@ impl CheckSumBlockwiseCalculator {
         use sha2::Digest as Sha256Digest;
 
         Self {
-            sha1_hasher: Sha1Digest::new(),
-            md5_hasher: md5::Context::new(),
+            sha1_hasher: if options.hash_sha1_enabled {
+                Some(Sha1Digest::new())
+            } else {
+                None
+            },
+            md5_hasher: if options.hash_md5_enabled {
+                Some(md5::Context::new())
+            } else {
+                None
+            },
             sha256_hasher: Sha256Digest::new(),
             blake3_hasher: if options.hash_blake3_enabled {
                 Some(blake3::Hasher::new())
@@ -47,13 +172,31 @@ impl CheckSumBlockwiseCalculator {
         }
     }
 
+    /// Calculator for the algorithms that have a value in `hashes` (SHA256 is always computed)
+    pub fn for_hashes(hashes: &FileHashes) -> Self {
+        Self::new_with_options(&WriteOptions {
+            hash_sha1_enabled: hashes.sha1.is_some(),
+            hash_md5_enabled: hashes.md5.is_some(),
+            hash_blake3_enabled: hashes.blake3.is_some(),
+            hash_xxhash3_enabled: hashes.xxh3.is_some(),
+            hash_xxhash128_enabled: hashes.xxh128.is_some(),
+            ..Default::default()
+        })
+    }
+
     /// Process data block (corresponds to VB.NET Propagate method)
     pub fn propagate(&mut self, data: &[u8]) {
         use sha1::Digest as Sha1Digest;
         use sha2::Digest as Sha256Digest;
 
-        self.sha1_hasher.update(data);
-        self.md5_hasher.consume(data);
+        if let Some(ref mut hasher) = self.sha1_hasher {
+            hasher.update(data);
+        }
+
+        if let Some(ref mut hasher) = self.md5_hasher {
+            hasher.consume(data);
+        }
+
         Sha256Digest::update(&mut self.sha256_hasher, data);
 
         if let Some(ref mut hasher) = self.blake3_hasher {
@@ -77,15 +220,18 @@ impl CheckSumBlockwiseCalculator {
     }
 
     /// Get SHA1 value
-    pub fn sha1_value(&self) -> String {
+    pub fn sha1_value(&self) -> Option<String> {
         use sha1::Digest;
-        let hasher = self.sha1_hasher.clone();
-        format!("{:X}", hasher.finalize())
+        self.sha1_hasher
+            .as_ref()
+            .map(|hasher| format!("{:X}", hasher.clone().finalize()))
     }
 
     /// Get MD5 value
-    pub fn md5_value(&self) -> String {
-        format!("{:X}", self.md5_hasher.clone().compute())
+    pub fn md5_value(&self) -> Option<String> {
+        self.md5_hasher
+            .as_ref()
+            .map(|hasher| format!("{:X}", hasher.clone().compute()))
     }
 
     /// Get SHA256 value
@@ -116,39 +262,21 @@ impl CheckSumBlockwiseCalculator {
             .map(|hasher| format!("{:X}", hasher.clone().digest128()))
     }
 
-    /// Get filtered hash map based on WriteOptions (LTFSCopyGUI compatible keys)
-    pub fn get_enabled_hashes(&self, options: &WriteOptions) -> HashMap<String, String> {
-        let mut hashes = HashMap::new();
-
-        if options.hash_sha1_enabled {
-            hashes.insert("ltfs.hash.sha1sum".to_string(), self.sha1_value());
-        }
-
-        if options.hash_md5_enabled {
-            hashes.insert("ltfs.hash.md5sum".to_string(), self.md5_value());
-        }
-
-        // SHA256 is always included when hash_on_write is enabled
-        hashes.insert("ltfs.hash.sha256sum".to_string(), self.sha256_value());
-
-        if options.hash_blake3_enabled {
-            if let Some(blake3) = self.blake3_value() {
-                hashes.insert("ltfs.hash.blake3sum".to_string(), blake3);
-            }
-        }
-
-        if options.hash_xxhash3_enabled {
-            if let Some(xxh3) = self.xxhash3_value() {
-                hashes.insert("ltfs.hash.xxhash3sum".to_string(), xxh3);
-            }
-        }
-
-        if options.hash_xxhash128_enabled {
-            if let Some(xxh128) = self.xxhash128_value() {
-                hashes.insert("ltfs.hash.xxhash128sum".to_string(), xxh128);
-            }
+    /// All computed hash values (SHA256 is always included when hash_on_write is enabled)
+    pub fn file_hashes(&self) -> FileHashes {
+        FileHashes {
+            sha1: self.sha1_value(),
+            md5: self.md5_value(),
+            sha256: Some(self.sha256_value()),
+            blake3: self.blake3_value(),
+            xxh3: self.xxhash3_value(),
+            xxh128: self.xxhash128_value(),
         }
+    }
 
-        hashes
+    /// Get hash map of the algorithms enabled in WriteOptions (LTFSCopyGUI compatible keys)
+    pub fn get_enabled_hashes(&self) -> HashMap<String, String> {
+        // Disabled algorithms have no hasher, so only enabled ones are present
+        self.file_hashes().to_xattrs()
     }
 }
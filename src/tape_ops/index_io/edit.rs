@@ -0,0 +1,108 @@
+//! LTFS Index Editing Operations
+//!
+//! Metadata-only changes to the in-memory index (removing, renaming and moving
+//! entries). The data blocks
+//! stay on tape, as LTFS is append-only; the change reaches the tape with the next
+//! index update.
+
+use super::super::TapeOperations;
+use super::super::utils::get_current_ltfs_timestamp;
+use super::write::worm_overwrite_error;
+use crate::error::{Result, RustLtfsError};
+use crate::ltfs_index::{File, IndexEntry, LtfsIndex, PathNormalize};
+use tracing::info;
+
+impl TapeOperations {
+    /// 从内存索引中删除 `tape_path` 处的文件，并标记索引已修改
+    ///
+    /// 文件数据仍留在磁带上，只是不再出现在目录列表中；下一次
+    /// `update_index_on_tape_with_options_dual_partition` 把删除写入磁带。
+    /// WORM介质不允许删除。
+    pub fn remove_file(&mut self, tape_path: &str) -> Result<File> {
+        if self.is_worm() {
+            return Err(worm_overwrite_error(tape_path));
+        }
+        let index = self.editable_index()?;
+
+        match index.find_path(tape_path, PathNormalize::CaseSensitive) {
+            Some((_, IndexEntry::File(_))) => {}
+            Some((canonical, IndexEntry::Directory(_))) => {
+                return Err(RustLtfsError::parameter_validation(format!(
+                    "'{}' is a directory, not a file",
+                    canonical
+                )))
+            }
+            None => {
+                return Err(RustLtfsError::ltfs_index(format!(
+                    "Path not found in index: {}",
+                    tape_path
+                )))
+            }
+        }
+
+        let (canonical, file) = index.remove_file(tape_path).ok_or_else(|| {
+            RustLtfsError::ltfs_index(format!("Path not found in index: {}", tape_path))
+        })?;
+        index.updatetime = get_current_ltfs_timestamp();
+
+        let parent = canonical.rsplit_once('/').map_or("", |(parent, _)| parent);
+        self.mark_directory_modified(parent.trim_start_matches('/'));
+        info!(
+            "Removed '{}' (UID {}, {} bytes) from index",
+            canonical, file.uid, file.length
+        );
+        Ok(file)
+    }
+
+    /// 将文件或目录重命名/移动到完整路径 `new_path`（只修改索引，不移动数据）
+    ///
+    /// `new_path` 的父目录必须已存在且不含同名条目；目录不能移动到自身或其子目录中。
+    /// 返回新的规范路径。WORM介质不允许修改已有条目。
+    pub fn rename(&mut self, old_path: &str, new_path: &str) -> Result<String> {
+        if self.is_worm() {
+            return Err(worm_overwrite_error(old_path));
+        }
+        let index = self.editable_index()?;
+        let (src, dest) = index.move_entry(old_path, new_path)?;
+        index.updatetime = get_current_ltfs_timestamp();
+
+        for path in [&src, &dest] {
+            let parent = path.rsplit_once('/').map_or("", |(parent, _)| parent);
+            self.mark_directory_modified(parent.trim_start_matches('/'));
+        }
+        info!("Moved '{}' to '{}' in index", src, dest);
+        Ok(dest)
+    }
+
+    /// 将文件或目录移动到已存在的目录 `dest_dir` 中，保留原名称
+    pub fn move_entry(&mut self, src: &str, dest_dir: &str) -> Result<String> {
+        let index = self.editable_index()?;
+        let name = match index.find_path(src, PathNormalize::CaseSensitive) {
+            Some((canonical, _)) => canonical.rsplit('/').next().unwrap_or_default().to_string(),
+            None => {
+                return Err(RustLtfsError::ltfs_index(format!(
+                    "Source not found in index: {}",
+                    src
+                )))
+            }
+        };
+        self.rename(src, &format!("{}/{}", dest_dir.trim_end_matches(['/', '\\']), name))
+    }
+
+    /// `tape_path` 是否是索引中已存在的目录
+    pub fn is_index_directory(&self, tape_path: &str) -> bool {
+        self.index.as_ref().is_some_and(|index| {
+            matches!(
+                index.find_path(tape_path, PathNormalize::CaseSensitive),
+                Some((_, IndexEntry::Directory(_)))
+            )
+        })
+    }
+
+    /// 可修改的已加载索引
+    fn editable_index(&mut self) -> Result<&mut LtfsIndex> {
+        self.index
+            .as_mut()
+            .ok_or_else(|| RustLtfsError::ltfs_index("No index loaded".to_string()))
+    }
+}
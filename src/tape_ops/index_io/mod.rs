@@ -12,6 +12,8 @@ pub mod read_single_partition;
 pub mod read_dual_partition;
 pub mod write;
 pub mod sync;
+pub mod restore;
+pub mod edit;
 
 // Future: Re-export commonly used items when implementations are complete
 // pub use read::*;
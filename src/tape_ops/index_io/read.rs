@@ -1,6 +1,7 @@
 use crate::error::{Result, RustLtfsError};
 use super::super::PartitionStrategy;
 
+use std::io::Read;
 use tracing::{debug, info, warn};
 use chrono;
 
@@ -8,6 +9,69 @@ use chrono;
 // LtfsPartitionLabel 在 format_operations.rs 中定义
 // 通过模块重新导出使用
 
+/// 无法读取 Partition Label 时依次探测的候选 Block Size（从小到大）
+const PROBE_BLOCK_SIZES: [u32; 5] = [65536, 131072, 262144, 524288, 1048576];
+
+/// 索引读取的单块超时（秒）：大索引读取过程中驱动器可能需要长时间重新定位
+const INDEX_READ_TIMEOUT_SECS: u32 = 900;
+
+/// 检测到XML起始之前最多读取的块数，避免在非索引位置读入大量无效数据
+const INDEX_READ_PROBE_BLOCKS: u32 = 50;
+
+/// 索引XML结束标签
+const INDEX_END_TAG: &str = "</ltfsindex>";
+
+/// gzip魔数：部分LTFS实现以gzip压缩格式写入索引
+const GZIP_MAGIC: [u8; 2] = [0x1F, 0x8B];
+
+/// Partition label XML结束标签
+const LABEL_END_TAG: &str = "</ltfslabel>";
+
+/// 索引读取检查点：记录最后一个成功读取块之后的位置及已累计字节数，
+/// 读取超时后从此处恢复而不是从头重读
+#[derive(Debug, Clone, Copy)]
+struct IndexReadCheckpoint {
+    partition: u8,
+    next_block: u64,
+    bytes_accumulated: u64,
+}
+
+/// 在字节数据中查找子串位置
+fn find_subslice(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    if needle.is_empty() || haystack.len() < needle.len() {
+        return None;
+    }
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+fn contains_subslice(haystack: &[u8], needle: &[u8]) -> bool {
+    find_subslice(haystack, needle).is_some()
+}
+
+/// 将读取到的数据转为XML文本：合法UTF-8时直接复用缓冲区，否则有损转换并去除NUL
+fn bytes_to_xml_string(data: Vec<u8>) -> String {
+    match String::from_utf8(data) {
+        Ok(text) => text,
+        Err(e) => String::from_utf8_lossy(e.as_bytes()).replace('\0', ""),
+    }
+}
+
+/// 以gzip魔数开头的索引数据解压为XML，明文数据原样返回
+///
+/// gzip流自带结尾，解压在第一个member结束处停止，块末尾的补零不影响结果。
+fn decompress_index_data(data: Vec<u8>) -> Result<Vec<u8>> {
+    if !data.starts_with(&GZIP_MAGIC) {
+        return Ok(data);
+    }
+
+    let mut xml = Vec::new();
+    flate2::read::GzDecoder::new(data.as_slice())
+        .read_to_end(&mut xml)
+        .map_err(|e| RustLtfsError::ltfs_index(format!("Failed to decompress gzip index: {}", e)))?;
+    debug!("Decompressed gzip index: {} -> {} bytes", data.len(), xml.len());
+    Ok(xml)
+}
+
 /// TapeOperations读取操作实现
 impl super::super::TapeOperations {
     /// 验证并处理索引 - 增强版本：添加详细调试信息
@@ -36,7 +100,7 @@ impl super::super::TapeOperations {
         debug!("✅ Basic XML validation passed - LTFS index tags found");
 
         // 解析并设置索引
-        match crate::ltfs_index::LtfsIndex::from_xml(xml_content) {
+        match crate::ltfs_index::LtfsIndex::from_xml_streaming(xml_content) {
             Ok(index) => {
                 debug!("✅ XML parsing successful - setting index");
                 debug!("   Volume UUID: {}", index.volumeuuid);
@@ -45,7 +109,9 @@ impl super::super::TapeOperations {
                     "   Files count: {}",
                     self.count_files_in_directory(&index.root_directory)
                 );
+                self.tape_generation = Some(index.generationnumber);
                 self.index = Some(index);
+                self.check_label_volume_uuid();
                 Ok(true)
             }
             Err(e) => {
@@ -71,7 +137,7 @@ impl super::super::TapeOperations {
 
     /// 读取并解析 Partition Label以获取Block Size
     /// 对应 LTFSCopyGUI 初始化阶段读取 plabel 的逻辑
-    async fn read_and_parse_partition_label(&mut self, partition: u8) -> Result<crate::tape_ops::LtfsPartitionLabel> {
+    pub(crate) async fn read_and_parse_partition_label(&mut self, partition: u8) -> Result<crate::tape_ops::LtfsPartitionLabel> {
         info!("Step 0: Attempting to read Partition Label from partition {}", partition);
         
         // LTFSCopyGUI Logic:
@@ -84,47 +150,173 @@ impl super::super::TapeOperations {
         
         // 使用足够大的 Buffer (1MB) 读取 Label，以防 Block Size 很大
         // Label XML 通常很小，但我们要避免 "Buffer < Block Size" 的 ILI 错误
-        let label_content = self.read_to_file_mark_with_temp_file(1024 * 1024)?; 
+        let label_content = bytes_to_xml_string(self.read_to_file_mark_bytes(1024 * 1024, LABEL_END_TAG)?);
         
-        // 简单解析 blocksize
-        let blocksize = if let Some(start) = label_content.find("<blocksize>") {
-            if let Some(end) = label_content[start..].find("</blocksize>") {
-                let s = &label_content[start + 11..start + end];
-                s.parse::<u32>().unwrap_or(524288)
-            } else {
-                524288
+        // label XML 中的 <blocksize> 是权威值；缺失或无法解析时返回错误，由调用方改用探测
+        let label = crate::tape_ops::volume::parse_ltfs_label(&label_content)?;
+        
+        info!("Parsed blocksize from label: {}", label.blocksize);
+        Ok(label)
+    }
+
+    /// 读取索引分区开头的VOL1标签及其后的LTFS label，返回结构化的分区标签
+    ///
+    /// VOL1标签只提供卷序列号，读取失败或缺失时仅记录警告（驱动器可能仍处于固定块模式）；
+    /// LTFS label必须可解析。已加载索引时会比对两者的卷UUID。
+    pub async fn read_volume_label(&mut self) -> Result<crate::tape_ops::LtfsPartitionLabel> {
+        let mut vol1_buffer = vec![0u8; crate::scsi::block_sizes::LTO_BLOCK_SIZE as usize];
+        let vol1_block = match self.rewind().await {
+            Ok(()) => self.scsi.read_raw_block(&mut vol1_buffer),
+            Err(e) => Err(e),
+        };
+        let volume_serial = match vol1_block {
+            Ok(crate::scsi::RawBlock::Data(len)) => {
+                crate::tape_ops::volume::vol1_volume_serial(&vol1_buffer[..len])
+            }
+            Ok(_) => None,
+            Err(e) => {
+                debug!("VOL1 label read failed: {}", e);
+                None
             }
-        } else {
-            // 如果没找到标签，可能是默认值
-            524288 
         };
-        
-        info!("Parsed blocksize from label: {}", blocksize);
-        Ok(crate::tape_ops::LtfsPartitionLabel { blocksize })
+        if volume_serial.is_none() {
+            warn!("⚠️ No VOL1 label with a volume serial at the start of partition 0");
+        }
+
+        let mut label = self.read_and_parse_partition_label(0).await?;
+        label.volume_serial = volume_serial;
+        debug!(
+            "Volume label: serial={:?} uuid={:?} formattime={:?} index={} data={} blocksize={}",
+            label.volume_serial,
+            label.volume_uuid,
+            label.format_time,
+            label.index_partition,
+            label.data_partition,
+            label.blocksize
+        );
+        self.partition_label = Some(label.clone());
+        self.check_label_volume_uuid();
+        Ok(label)
+    }
+
+    /// 比对分区标签与索引中的卷UUID，不一致时发出警告（可能是换过磁带或索引来自其他卷）
+    fn check_label_volume_uuid(&self) {
+        let label_uuid = self
+            .partition_label
+            .as_ref()
+            .and_then(|label| label.volume_uuid.as_deref());
+        if let (Some(label_uuid), Some(index)) = (label_uuid, self.index.as_ref()) {
+            if !label_uuid.eq_ignore_ascii_case(index.volumeuuid.trim()) {
+                warn!(
+                    "⚠️ Volume UUID mismatch: partition label has {}, index has {}",
+                    label_uuid, index.volumeuuid
+                );
+            }
+        }
+    }
+
+    /// 在无法读取 Partition Label 时探测 Block Size
+    ///
+    /// 按从小到大的候选尺寸定位到索引起始处读取一个块：可变块模式下，小于实际块长的
+    /// 缓冲区会读取失败（overlength），因此第一个读取成功、填满缓冲区且内容为索引XML的
+    /// 候选值即为实际块长。若索引只占一个短块则无法区分，返回 None。
+    fn probe_block_size(&self) -> Option<u32> {
+        info!("Probing block size by reading index at candidate sizes: {:?}", PROBE_BLOCK_SIZES);
+
+        for &candidate in PROBE_BLOCK_SIZES.iter() {
+            if let Err(e) = self.locate_to_index_start() {
+                debug!("Cannot position to index for block size probe: {}", e);
+                return None;
+            }
+
+            let mut buffer = vec![0u8; candidate as usize];
+            match self.scsi.read_blocks(1, &mut buffer) {
+                Ok(count) if count > 0 => {
+                    let head = String::from_utf8_lossy(&buffer[..std::cmp::min(512, buffer.len())]);
+                    if !head.contains("<?xml") && !head.contains("<ltfsindex") {
+                        debug!("Candidate {}: data read but not index XML", candidate);
+                        continue;
+                    }
+
+                    if buffer.last() != Some(&0) {
+                        info!("✅ Block size probe matched: {} bytes", candidate);
+                        return Some(candidate);
+                    }
+
+                    debug!(
+                        "Candidate {}: index fits in a short block, block size is ambiguous",
+                        candidate
+                    );
+                    return None;
+                }
+                Ok(_) => debug!("Candidate {}: no data read", candidate),
+                Err(e) => debug!("Candidate {}: read failed ({})", candidate, e),
+            }
+        }
+
+        None
+    }
+
+    /// 定位到最新索引的起始位置（双分区：P0 FileMark 3；单分区：EOD 前一个 FileMark）
+    fn locate_to_index_start(&self) -> Result<()> {
+        self.invalidate_position_cache();
+        if self.get_extra_partition_count() > 0 {
+            self.scsi.locate_to_filemark(3, 0)?;
+        } else {
+            self.scsi.locate_to_eod(0)?;
+            let eod_position = self.scsi.read_position()?;
+            if eod_position.file_number <= 1 {
+                return Err(RustLtfsError::ltfs_index(
+                    "Insufficient file marks to locate index",
+                ));
+            }
+            self.scsi.locate_to_filemark(eod_position.file_number - 1, 0)?;
+        }
+        self.scsi.read_file_mark()?;
+        Ok(())
+    }
+
+    /// 索引分区的EOD是否位于块0，即从未写入过数据的空白磁带
+    fn index_partition_is_blank(&self) -> Result<bool> {
+        self.invalidate_position_cache();
+        self.scsi.locate_to_eod(0)?;
+        Ok(self.scsi.read_position()?.block_number == 0)
     }
 
     /// Read LTFS index from tape (LTFSCopyGUI兼容方法)
-    /// 包含重试逻辑
+    /// 包含重试逻辑：仅对瞬时SCSI错误（总线复位、Unit Attention等）重试，
+    /// 逻辑错误（非LTFS磁带、索引无法解析）直接返回，不再重试
     pub async fn read_index_from_tape(&mut self) -> Result<()> {
         let max_retries = 3;
+        self.invalidate_position_cache();
         
         for attempt in 1..=max_retries {
             info!("🔄 Starting LTFS index reading process (Attempt {}/{})", attempt, max_retries);
             
-            // 每次尝试前先倒带，确保状态干净
+            // 每次重试前先等待设备就绪，再倒带，确保状态干净
             if attempt > 1 {
+                if let Err(e) = self.wait_for_device_ready().await {
+                    warn!("⚠️ Device not ready before retry: {}", e);
+                }
                 info!("⏪ Rewinding tape before retry...");
-                let _ = self.scsi.locate_block(0, 0);
+                if let Err(e) = self.rewind().await {
+                    warn!("⚠️ Rewind before retry failed: {}", e);
+                }
             }
 
-            match self.read_index_from_tape_attempt().await {
+            let is_last_attempt = attempt == max_retries;
+            match self.read_index_from_tape_attempt(is_last_attempt).await {
                 Ok(()) => {
                     info!("✅ Index reading successful on attempt {}", attempt);
                     return Ok(());
                 }
+                Err(e) if !e.is_transient() => {
+                    warn!("❌ Index reading failed with non-transient error, not retrying: {}", e);
+                    return Err(e);
+                }
                 Err(e) => {
-                    warn!("❌ Index reading attempt {} failed: {}", attempt, e);
-                    if attempt == max_retries {
+                    warn!("❌ Index reading attempt {} failed with transient error: {}", attempt, e);
+                    if is_last_attempt {
                         return Err(e);
                     }
                     // 等待一小会儿可能有助于设备恢复
@@ -137,16 +329,18 @@ impl super::super::TapeOperations {
     }
 
     /// 实际的读取逻辑（单次尝试）
-    async fn read_index_from_tape_attempt(&mut self) -> Result<()> {
+    ///
+    /// 主策略遇到瞬时SCSI错误时直接返回错误交由外层重试，而不是落入后备策略
+    /// （否则一次总线复位会被误判为空白/损坏磁带）。最后一次尝试时仍会继续后备策略。
+    async fn read_index_from_tape_attempt(&mut self, is_last_attempt: bool) -> Result<()> {
         info!("Starting LTFS index reading process (Internal)");
 
         debug!("=== Step 0: LTFSCopyGUI Initialization (Block Size Detection) ===");
         // 尝试读取 Partition Label 以获取正确的 Block Size (通常为 512KB)
         // 这是至关重要的一步，因为默认的 64KB 可能导致无法正确读取 512KB 的索引 Block
-        match self.read_and_parse_partition_label(0).await {
+        match self.read_volume_label().await {
             Ok(label) => {
                 info!("✅ Successfully read partition label. Block Size: {}", label.blocksize);
-                self.partition_label = Some(label);
                 
                 // 🔧 CRITICAL FIX: 强制将驱动器设置为 Variable Block Mode (Block Length = 0)
                 // 我们的 read_blocks 实现假设使用的是 Variable Mode。
@@ -159,11 +353,23 @@ impl super::super::TapeOperations {
                 }
             }
             Err(e) => {
-                warn!("⚠️ Failed to read partition label: {}. Assuming standard LTFSCopyGUI block size (512KB).", e);
+                if matches!(self.index_partition_is_blank(), Ok(true)) {
+                    return Err(RustLtfsError::blank_tape(
+                        "Index partition holds no data (EOD at block 0)",
+                    ));
+                }
+                warn!("⚠️ Failed to read partition label: {}. Probing block size from index.", e);
                 // 如果读取失败，也尝试重置为 Variable Mode，以防万一
                 let _ = self.scsi.set_block_size(0);
-                // 使用 LTFSCopyGUI 的标准 512KB 作为 Fallback
-                self.partition_label = Some(crate::tape_ops::LtfsPartitionLabel { blocksize: 524288 });
+                // 探测失败时使用 LTFSCopyGUI 的标准 512KB 作为 Fallback
+                let blocksize = self.probe_block_size().unwrap_or_else(|| {
+                    warn!("⚠️ Block size probe inconclusive, assuming 512KB");
+                    crate::scsi::block_sizes::LTO_BLOCK_SIZE_512K
+                });
+                self.partition_label = Some(crate::tape_ops::LtfsPartitionLabel {
+                    blocksize,
+                    ..Default::default()
+                });
             }
         }
 
@@ -188,6 +394,9 @@ impl super::super::TapeOperations {
                     }
                 }
                 Err(e) => {
+                    if e.is_transient() && !is_last_attempt {
+                        return Err(e);
+                    }
                     debug!("Dual-partition FileMark 3 strategy failed: {}", e);
                 }
             }
@@ -204,6 +413,9 @@ impl super::super::TapeOperations {
                     }
                 }
                 Err(e) => {
+                    if e.is_transient() && !is_last_attempt {
+                        return Err(e);
+                    }
                     debug!("Single-partition FM-1 strategy failed: {}", e);
                 }
             }
@@ -212,13 +424,37 @@ impl super::super::TapeOperations {
         // Step 2: 标准流程作为备用策略
         debug!("Step 2: Standard LTFS reading process as fallback");
 
-        // 定位到索引分区并读取VOL1标签
-        self.scsi.locate_block(0, 0)?;
+        // 倒带到索引分区开头并读取VOL1标签
+        self.rewind().await?;
         let mut label_buffer = vec![0u8; crate::scsi::block_sizes::LTO_BLOCK_SIZE as usize];
-        self.scsi.read_blocks(1, &mut label_buffer)?;
+        let label_len = match self.scsi.read_raw_block(&mut label_buffer)? {
+            crate::scsi::RawBlock::Data(len) => len,
+            crate::scsi::RawBlock::EndOfData => {
+                return Err(RustLtfsError::blank_tape(
+                    "No data at the beginning of the index partition",
+                ));
+            }
+            crate::scsi::RawBlock::FileMark => 0,
+        };
+
+        // VOL1无效时保留格式分析结果，用于归类后备策略的最终错误
+        let vol1_analysis = if self.parse_vol1_label(&label_buffer[..label_len])? {
+            None
+        } else if label_len == 0 {
+            Some(crate::tape_ops::TapeFormatAnalysis::UnknownFormat)
+        } else {
+            Some(crate::tape_ops::volume::analyze_tape_format_enhanced(&label_buffer[..80]))
+        };
 
-        let vol1_valid = self.parse_vol1_label(&label_buffer)?;
+        self.read_index_fallback_strategies(vol1_analysis.is_none())
+            .await
+            .map_err(|e| {
+                crate::tape_ops::volume::classify_format_detection_error(e, vol1_analysis.as_ref())
+            })
+    }
 
+    /// 标准流程和最终后备策略（VOL1验证通过时先尝试标准读取）
+    async fn read_index_fallback_strategies(&mut self, vol1_valid: bool) -> Result<()> {
         if vol1_valid {
             debug!("VOL1 label validation passed, trying standard reading");
 
@@ -255,7 +491,7 @@ impl super::super::TapeOperations {
                     if self.validate_and_process_index(&xml).await? {
                         return Ok(());
                     } else {
-                        return Err(RustLtfsError::ltfs_index("Index validation failed"));
+                        return Err(RustLtfsError::corrupted_index("Index validation failed"));
                     }
                 }
 
@@ -277,7 +513,7 @@ impl super::super::TapeOperations {
                 if self.validate_and_process_index(&xml).await? {
                     Ok(())
                 } else {
-                    Err(RustLtfsError::ltfs_index("Index validation failed"))
+                    Err(RustLtfsError::corrupted_index("Index validation failed"))
                 }
             }
 
@@ -294,7 +530,7 @@ impl super::super::TapeOperations {
                 if self.validate_and_process_index(&xml).await? {
                     Ok(())
                 } else {
-                    Err(RustLtfsError::ltfs_index("Index validation failed"))
+                    Err(RustLtfsError::corrupted_index("Index validation failed"))
                 }
             }
         }
@@ -314,7 +550,7 @@ impl super::super::TapeOperations {
         );
 
         // 直接使用当前TapeOperations的read_to_file_mark方法
-        self.read_to_file_mark_with_temp_file(block_size)
+        self.read_to_file_mark(block_size)
     }
     /// Read index XML data from tape using file mark method (对应TapeUtils.ReadToFileMark)
     fn read_index_xml_from_tape_with_file_mark(&self) -> Result<String> {
@@ -330,7 +566,7 @@ impl super::super::TapeOperations {
         info!("Using dynamic blocksize: {} bytes", block_size);
 
         // 直接使用当前TapeOperations的方法
-        self.read_to_file_mark_with_temp_file(block_size)
+        self.read_to_file_mark(block_size)
     }
 
     /// 按照LTFSCopyGUI逻辑从指定分区EOD读取最新索引
@@ -445,47 +681,54 @@ impl super::super::TapeOperations {
         )))
     }
 
-    /// 使用临时文件读取到文件标记 (精准对应TapeUtils.ReadToFileMark)
-    pub fn read_to_file_mark_with_temp_file(&self, block_size: usize) -> Result<String> {
-        use std::io::Write;
-
-        // 创建临时文件 (对应LTFSCopyGUI的tmpFile)
-        let temp_dir = std::env::temp_dir();
-        let temp_filename = format!(
-            "LTFSIndex_{}.tmp",
-            chrono::Utc::now().format("%Y%m%d_%H%M%S")
-        );
-        let temp_path = temp_dir.join(temp_filename);
-
-        info!("Creating temporary index file: {:?}", temp_path);
-
-        let mut temp_file = std::fs::File::create(&temp_path)?;
-        let mut total_bytes_read = 0u64;
-        let mut blocks_read = 0;
-        // Start conservatively and expand if we detect a '<?xml' start tag in the temporary file.
-        // hard_max_blocks is an absolute safety cap (matches previous fixed limit).
-        let hard_max_blocks = 200u32; // 对应LTFSCopyGUI的固定限制上限（安全上限）
-        let mut max_blocks = 50u32; // 初始较小值，避免一次读太多无效数据
-
+    /// 读取到文件标记 (精准对应TapeUtils.ReadToFileMark)
+    ///
+    /// 数据直接累积在内存中，每读一块就增量查找 `end_tag`（如 `</ltfsindex>`），
+    /// 找到后立即停止，只裁剪最后一块的补零。返回原始字节，调用方可直接解析，
+    /// 无需先落盘再整体读回。
+    pub(crate) fn read_to_file_mark_bytes(&self, block_size: usize, end_tag: &str) -> Result<Vec<u8>> {
+        let end_tag = end_tag.as_bytes();
+        self.invalidate_position_cache();
+        let mut data: Vec<u8> = Vec::new();
+        let mut blocks_read = 0u32;
+        let mut end_tag_found = false;
+        // 在出现 "<?xml" 之前只读取少量块，避免在无效位置读入大量数据；
+        // 检测到XML起始后一直读到结束标签或文件标记为止
+        let mut max_blocks = Some(INDEX_READ_PROBE_BLOCKS);
+
+        // 记录起始位置作为检查点，读取失败时可从最后一个成功块恢复
+        let mut checkpoint = self.scsi.read_position().ok().map(|pos| IndexReadCheckpoint {
+            partition: pos.partition,
+            next_block: pos.block_number,
+            bytes_accumulated: 0,
+        });
+        let mut block_retries = 0u32;
 
         debug!(
-            "Starting ReadToFileMark with blocksize {}, max {} blocks (enhanced SCSI error handling)",
-            block_size, max_blocks
+            "Starting ReadToFileMark with blocksize {}, looking for {:?}",
+            block_size,
+            String::from_utf8_lossy(end_tag)
         );
 
+        let mut buffer = vec![0u8; block_size];
+
         // 精准模仿LTFSCopyGUI的ReadToFileMark循环 + 增强错误处理
         loop {
-            // 安全限制 - 防止无限读取（对应LTFSCopyGUI逻辑）
-            if blocks_read >= max_blocks {
-                warn!("Reached maximum block limit ({}), stopping", max_blocks);
-                break;
+            // 安全限制 - 防止在非索引数据上无限读取
+            if let Some(limit) = max_blocks {
+                if blocks_read >= limit {
+                    warn!("Reached maximum block limit ({}) without XML start, stopping", limit);
+                    break;
+                }
             }
 
-            let mut buffer = vec![0u8; block_size];
-
             // 执行SCSI READ命令 (对应ScsiRead调用) + 增强错误处理
-            match self.scsi.read_blocks(1, &mut buffer) {
+            match self
+                .scsi
+                .read_blocks_with_timeout(1, &mut buffer, INDEX_READ_TIMEOUT_SECS)
+            {
                 Ok(blocks_read_count) => {
+                    block_retries = 0;
 
                     debug!("SCSI read returned: {} blocks", blocks_read_count);
 
@@ -495,61 +738,65 @@ impl super::super::TapeOperations {
                         break;
                     }
 
-                    // 添加数据采样调试（仅DEBUG级别输出）
-                    if tracing::enabled!(tracing::Level::DEBUG) {
-                        let sample_size = std::cmp::min(32, buffer.len());
-                        let sample_data: Vec<String> = buffer[..sample_size]
-                            .iter()
-                            .map(|&b| format!("{:02X}", b))
-                            .collect();
-                        debug!(
-                            "Buffer sample (first {} bytes): {}",
-                            sample_size,
-                            sample_data.join(" ")
-                        );
-                    }
-
-                    // 写入到输出文件 (对应fileStream.Write(buffer, 0, bytesRead))
-                    temp_file.write_all(&buffer)?;
-                    total_bytes_read += block_size as u64;
+                    // 只在新数据（加上与上一块衔接的部分）中查找，避免重复扫描
+                    let search_start = data.len().saturating_sub(end_tag.len().saturating_sub(1));
+                    data.extend_from_slice(&buffer);
                     blocks_read += 1;
 
+                    if let Some(ref mut cp) = checkpoint {
+                        cp.next_block += 1;
+                        cp.bytes_accumulated = data.len() as u64;
+                    }
+
                     debug!(
                         "Read block {}: {} bytes, total: {} bytes",
-                        blocks_read, block_size, total_bytes_read
+                        blocks_read,
+                        block_size,
+                        data.len()
                     );
 
-                    // 动态扩展策略：
-                    // 如果我们尚未扩大到硬上限，并且临时文件中检测到了 "<?xml"（意味着索引开始出现），
-                    // 则将 max_blocks 扩展到 hard_max_blocks，以便继续读取直至找到完整的 </ltfsindex>（或达到硬上限）。
-                    if max_blocks < hard_max_blocks {
-                        if let Ok(mut f) = std::fs::File::open(&temp_path) {
-                            use std::io::{Read, Seek, SeekFrom};
-                            if let Ok(file_len) = f.seek(SeekFrom::End(0)) {
-                                // 检查文件末尾的一小段（最多 4KB），通常足以检测 "<?xml" 或其他索引起始标识
-                                let check_len = std::cmp::min(4096, file_len) as usize;
-                                if check_len > 0 {
-                                    if f.seek(SeekFrom::End(-(check_len as i64))).is_ok() {
-                                        let mut tail_buf = vec![0u8; check_len];
-                                        if f.read_exact(&mut tail_buf).is_ok() {
-                                            if String::from_utf8_lossy(&tail_buf).contains("<?xml")
-                                            {
-                                                debug!(
-                                                    "Detected '<?xml' in temporary index file; expanding max_blocks: {} -> {}",
-                                                    max_blocks, hard_max_blocks
-                                                );
-                                                max_blocks = hard_max_blocks;
-                                            }
-                                        }
-                                    }
+                    if max_blocks.is_some() && contains_subslice(&buffer, b"<?xml") {
+                        debug!("Detected '<?xml' in index data; reading until end tag or file mark");
+                        max_blocks = None;
+                    } else if max_blocks.is_some() && data.starts_with(&GZIP_MAGIC) {
+                        debug!("Detected gzip-compressed index data; reading until file mark");
+                        max_blocks = None;
+                    }
+
+                    if let Some(pos) = find_subslice(&data[search_start..], end_tag) {
+                        let end = search_start + pos + end_tag.len();
+                        debug!("Found end tag at byte {}, stopping read", end);
+                        data.truncate(end);
+                        end_tag_found = true;
+                        break;
+                    }
+                }
+                Err(e) => {
+                    // 从检查点恢复：重新定位到最后一个成功块之后并重试该块
+                    if let Some(cp) = checkpoint {
+                        let policy = self.scsi.read_retry();
+                        if e.is_transient() && block_retries < policy.max_retries {
+                            block_retries += 1;
+                            warn!(
+                                "Index read error at P{} B{} ({} bytes read so far): {}, resuming from checkpoint (retry {}/{})",
+                                cp.partition,
+                                cp.next_block,
+                                cp.bytes_accumulated,
+                                e,
+                                block_retries,
+                                policy.max_retries
+                            );
+                            std::thread::sleep(policy.delay_for(block_retries));
+                            match self.scsi.locate_block(cp.partition, cp.next_block) {
+                                Ok(()) => continue,
+                                Err(locate_err) => {
+                                    warn!("Failed to return to checkpoint: {}", locate_err)
                                 }
                             }
                         }
                     }
-                }
-                Err(e) => {
-                    // 🔧 DEBUG MODE: 禁用所有重试逻辑，直接暴露错误
-                    warn!("DEBUG MODE: SCSI read error encountered: {}", e);
+
+                    warn!("SCSI read error encountered: {}", e);
                     
                     // 例外：如果还没有读到任何数据，这确实是个严重错误
                     if blocks_read == 0 {
@@ -566,38 +813,59 @@ impl super::super::TapeOperations {
             }
         }
 
-        temp_file.flush()?;
-        drop(temp_file);
+        // 未找到结束标签时只裁剪最后一块的补零（中间块不会含有补零）；
+        // gzip数据的结尾字段可能以0结束，保留原样交给解压
+        if !end_tag_found && !data.starts_with(&GZIP_MAGIC) {
+            let last_block_start = data.len().saturating_sub(block_size);
+            let trimmed_len = data[last_block_start..]
+                .iter()
+                .rposition(|&b| b != 0)
+                .map_or(last_block_start, |pos| last_block_start + pos + 1);
+            data.truncate(trimmed_len);
+        }
 
         debug!(
-            "ReadToFileMark completed: {} blocks read, {} total bytes",
-            blocks_read, total_bytes_read
+            "ReadToFileMark completed: {} blocks read, {} bytes of content",
+            blocks_read,
+            data.len()
         );
 
-        // 读取并清理临时文件
-        // Use read() + from_utf8_lossy() instead of read_to_string() to handle invalid UTF-8 bytes gracefully
-        let raw_bytes = std::fs::read(&temp_path)?;
-        let xml_content = String::from_utf8_lossy(&raw_bytes).to_string();
-
-        // 清理临时文件
-        if let Err(e) = std::fs::remove_file(&temp_path) {
-            warn!("Failed to remove temporary file {:?}: {}", temp_path, e);
+        if self.keep_index_temp_file {
+            self.dump_index_data_to_temp_file(&data);
         }
 
-        // 清理XML内容
-        let cleaned_xml = xml_content.replace('\0', "").trim().to_string();
-
-        if cleaned_xml.is_empty() {
+        if data.iter().all(|b| b.is_ascii_whitespace() || *b == 0) {
             return Err(RustLtfsError::ltfs_index(
                 "Cleaned XML is empty".to_string(),
             ));
         }
 
-        debug!(
-            "Extracted XML content: {} bytes (after cleanup)",
-            cleaned_xml.len()
-        );
-        Ok(cleaned_xml)
+        Ok(data)
+    }
+
+    /// 读取到文件标记并返回XML文本（索引以 `</ltfsindex>` 结束，gzip压缩的索引自动解压）
+    pub fn read_to_file_mark(&self, block_size: usize) -> Result<String> {
+        let data = decompress_index_data(self.read_to_file_mark_bytes(block_size, INDEX_END_TAG)?)?;
+        Ok(bytes_to_xml_string(data))
+    }
+
+    /// 读取到文件标记并直接解析为 LtfsIndex，不产生中间 String 副本
+    pub fn read_index_to_file_mark(&self, block_size: usize) -> Result<crate::ltfs_index::LtfsIndex> {
+        let data = decompress_index_data(self.read_to_file_mark_bytes(block_size, INDEX_END_TAG)?)?;
+        crate::ltfs_index::LtfsIndex::from_xml_reader(data.as_slice())
+    }
+
+    /// 调试用：将读取到的索引原始数据保存到临时文件 (对应LTFSCopyGUI的tmpFile)
+    fn dump_index_data_to_temp_file(&self, data: &[u8]) {
+        let temp_path = std::env::temp_dir().join(format!(
+            "LTFSIndex_{}.tmp",
+            chrono::Utc::now().format("%Y%m%d_%H%M%S")
+        ));
+
+        match std::fs::write(&temp_path, data) {
+            Ok(()) => info!("Index data kept for debugging: {:?}", temp_path),
+            Err(e) => warn!("Failed to write temporary index file {:?}: {}", temp_path, e),
+        }
     }
 
 
@@ -673,3 +941,50 @@ impl super::super::TapeOperations {
     }
 
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const INDEX_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<ltfsindex version="2.4.0">
+  <creator>RustLTFS</creator>
+  <volumeuuid>4b1c3d1e-8f2a-4c5b-9d6e-7f8a9b0c1d2e</volumeuuid>
+  <generationnumber>7</generationnumber>
+  <updatetime>2024-01-01T00:00:00.000000000Z</updatetime>
+  <location><partition>b</partition><startblock>42</startblock></location>
+  <directory>
+    <name></name>
+    <fileuid>1</fileuid>
+    <creationtime>2024-01-01T00:00:00.000000000Z</creationtime>
+    <changetime>2024-01-01T00:00:00.000000000Z</changetime>
+    <modifytime>2024-01-01T00:00:00.000000000Z</modifytime>
+    <accesstime>2024-01-01T00:00:00.000000000Z</accesstime>
+    <backuptime>2024-01-01T00:00:00.000000000Z</backuptime>
+    <readonly>false</readonly>
+    <contents></contents>
+  </directory>
+</ltfsindex>
+"#;
+
+    #[test]
+    fn gzip_index_is_decompressed_and_parsed() {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(INDEX_XML.as_bytes()).unwrap();
+        let mut data = encoder.finish().unwrap();
+        // 磁带块末尾的补零
+        data.resize(data.len() + 512, 0);
+
+        let xml = bytes_to_xml_string(decompress_index_data(data).unwrap());
+        let index = crate::ltfs_index::LtfsIndex::from_xml(&xml).unwrap();
+        assert_eq!(index.generationnumber, 7);
+        assert_eq!(index.location.startblock, 42);
+    }
+
+    #[test]
+    fn plain_index_is_unchanged() {
+        let data = INDEX_XML.as_bytes().to_vec();
+        assert_eq!(decompress_index_data(data.clone()).unwrap(), data);
+    }
+}
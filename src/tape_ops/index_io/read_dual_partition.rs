@@ -33,7 +33,7 @@ impl super::super::TapeOperations {
             .map(|plabel| plabel.blocksize as usize)
             .unwrap_or(block_sizes::LTO_BLOCK_SIZE as usize);
         
-        let index_data = self.read_to_file_mark_with_temp_file(block_size)?;
+        let index_data = self.read_to_file_mark(block_size)?;
         
         Ok(index_data)
     }
@@ -92,7 +92,7 @@ impl super::super::TapeOperations {
             .map(|plabel| plabel.blocksize as usize)
             .unwrap_or(block_sizes::LTO_BLOCK_SIZE as usize);
 
-        let xml_content = self.read_to_file_mark_with_temp_file(block_size)?;
+        let xml_content = self.read_to_file_mark(block_size)?;
 
         if xml_content.contains("<ltfsindex") && xml_content.contains("</ltfsindex>") {
             info!("✅ Successfully read latest index from data partition EOD at FileMark {}", target_filemark);
@@ -44,7 +44,7 @@ impl super::super::TapeOperations {
             .map(|plabel| plabel.blocksize as usize)
             .unwrap_or(block_sizes::LTO_BLOCK_SIZE as usize);
         
-        let index_data = self.read_to_file_mark_with_temp_file(block_size)?;
+        let index_data = self.read_to_file_mark(block_size)?;
         
         // 🎯 验证并处理内容 (LTFSCopyGUI逻辑)
         let xml_content = index_data;
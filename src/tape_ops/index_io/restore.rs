@@ -0,0 +1,268 @@
+//! LTFS Index Restore from Local Backup
+//!
+//! Disaster-recovery path: load a locally saved `.schema`/`.xml` index, check that
+//! its extents point at real data on tape, and write it back as the current index.
+
+use crate::error::{Result, RustLtfsError};
+use crate::ltfs_index::{Directory, FileExtent, LtfsIndex};
+use super::super::{RecoveredIndex, TapeOperations};
+use std::path::Path;
+use tracing::{debug, info, warn};
+
+/// 校验时最多抽查的extent数量
+const MAX_EXTENT_SAMPLES: usize = 16;
+
+/// 抽查读取使用的缓冲区大小（覆盖所有常见LTFS块大小，变长模式下短块会正常返回）
+const SPOT_READ_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// 恢复索引时从数据分区EOD向前最多扫描的FileMark区段数
+const MAX_RECOVERY_SCAN_SECTIONS: u64 = 256;
+
+impl TapeOperations {
+    /// 从本地文件加载LTFS索引（LTFSCopyGUI保存的 .schema / .xml）
+    pub fn load_index_from_file(&mut self, file_path: &Path) -> Result<()> {
+        info!("Loading LTFS index from file: {:?}", file_path);
+
+        let file = std::fs::File::open(file_path).map_err(|e| {
+            RustLtfsError::file_operation(format!(
+                "Cannot read index file {:?}: {}",
+                file_path, e
+            ))
+        })?;
+
+        let index = LtfsIndex::from_xml_reader(std::io::BufReader::new(file))?;
+        info!(
+            "Loaded index: volume {}, generation {}",
+            index.volumeuuid, index.generationnumber
+        );
+
+        self.schema = Some(index.clone());
+        self.index = Some(index);
+        Ok(())
+    }
+
+    /// 抽查索引中的extent：定位到extent起始块并读取一个块，确认磁带上确实有数据
+    pub fn validate_index_extents(&self, index: &LtfsIndex) -> Result<()> {
+        let mut extents = Vec::new();
+        collect_extents(&index.root_directory, &mut extents);
+
+        if extents.is_empty() {
+            info!("Index has no data extents, nothing to validate");
+            return Ok(());
+        }
+
+        // 在所有extent中均匀抽样
+        let step = extents.len().div_ceil(MAX_EXTENT_SAMPLES);
+        let samples: Vec<&FileExtent> = extents.iter().step_by(step).copied().collect();
+        info!(
+            "Validating {} of {} extents against tape data",
+            samples.len(),
+            extents.len()
+        );
+
+        let mut buffer = vec![0u8; SPOT_READ_BUFFER_SIZE];
+        self.invalidate_position_cache();
+        for extent in samples {
+            if !extent.partition.eq_ignore_ascii_case("a") && !extent.partition.eq_ignore_ascii_case("b") {
+                return Err(RustLtfsError::ltfs_index(format!(
+                    "Extent at block {} has invalid partition '{}'",
+                    extent.start_block, extent.partition
+                )));
+            }
+
+            let logical_partition = if extent.partition.eq_ignore_ascii_case("a") { 0 } else { 1 };
+            let partition = self.get_target_partition(logical_partition);
+            debug!(
+                "Spot-reading extent start: partition {} block {}",
+                partition, extent.start_block
+            );
+
+            self.scsi.locate_block(partition, extent.start_block)?;
+            let blocks_read = self.scsi.read_blocks(1, &mut buffer).map_err(|e| {
+                RustLtfsError::ltfs_index(format!(
+                    "Extent at partition {} block {} is not readable: {}",
+                    partition, extent.start_block, e
+                ))
+            })?;
+
+            if blocks_read == 0 {
+                return Err(RustLtfsError::ltfs_index(format!(
+                    "Extent at partition {} block {} points at a file mark or end of data",
+                    partition, extent.start_block
+                )));
+            }
+        }
+
+        info!("Extent validation passed");
+        Ok(())
+    }
+
+    /// 索引分区损坏时从数据分区的索引副本中恢复索引
+    ///
+    /// 优先按数据分区的VCI (Volume Coherency Information) 直接读取最后提交的索引；
+    /// VCI缺失或与磁带内容不符时，从数据分区EOD向前逐个FileMark区段读取，解析其中的
+    /// `<ltfsindex>`，在所有有效副本中选择generationnumber最大的一个。不修改当前加载的索引。
+    pub async fn recover_index(&mut self) -> Result<RecoveredIndex> {
+        let partition = self.get_target_partition(1);
+        let block_size = self
+            .partition_label
+            .as_ref()
+            .map(|plabel| plabel.blocksize as usize)
+            .unwrap_or(crate::scsi::block_sizes::LTO_BLOCK_SIZE as usize);
+
+        self.invalidate_position_cache();
+        match self.recover_index_from_vci(partition, block_size) {
+            Ok(recovered) => return Ok(recovered),
+            Err(e) => info!("VCI lookup did not yield an index, scanning instead: {}", e),
+        }
+
+        info!("Scanning data partition {} for index copies", partition);
+        self.invalidate_position_cache();
+        self.scsi.locate_to_eod(partition)?;
+        let eod_position = self.scsi.read_position()?;
+        if eod_position.file_number <= 1 {
+            return Err(RustLtfsError::ltfs_index(format!(
+                "Insufficient file marks in partition {} to hold an index copy",
+                partition
+            )));
+        }
+
+        let oldest = std::cmp::max(
+            1,
+            eod_position.file_number.saturating_sub(MAX_RECOVERY_SCAN_SECTIONS),
+        );
+        let mut best: Option<RecoveredIndex> = None;
+        let mut candidates = 0;
+
+        // 最新的索引副本在EOD前最后一个区段，因此从后往前扫描
+        for file_mark in (oldest..eod_position.file_number).rev() {
+            let xml = match self
+                .scsi
+                .locate_to_filemark(file_mark, partition)
+                .and_then(|()| self.scsi.read_file_mark())
+                .and_then(|_| self.read_to_file_mark(block_size))
+            {
+                Ok(xml) if xml.contains("<ltfsindex") => xml,
+                Ok(_) => continue,
+                Err(e) => {
+                    debug!("No index after FileMark {}: {}", file_mark, e);
+                    continue;
+                }
+            };
+
+            match LtfsIndex::from_xml(&xml) {
+                Ok(index) => {
+                    candidates += 1;
+                    info!(
+                        "Found index copy generation {} after FileMark {}",
+                        index.generationnumber, file_mark
+                    );
+                    let newer = best
+                        .as_ref()
+                        .is_none_or(|b| index.generationnumber > b.index.generationnumber);
+                    if newer {
+                        best = Some(RecoveredIndex {
+                            index,
+                            xml,
+                            candidates: 0,
+                            partition,
+                            file_mark,
+                            from_vci: false,
+                        });
+                    }
+                }
+                Err(e) => warn!("Index copy after FileMark {} is not valid: {}", file_mark, e),
+            }
+        }
+
+        let mut recovered = best.ok_or_else(|| {
+            RustLtfsError::ltfs_index(format!(
+                "No valid index copy found in the last {} file mark sections of partition {}",
+                eod_position.file_number - oldest,
+                partition
+            ))
+        })?;
+        recovered.candidates = candidates;
+
+        info!(
+            "Recovered index generation {} ({} candidates seen)",
+            recovered.index.generationnumber, candidates
+        );
+        Ok(recovered)
+    }
+
+    /// 按分区VCI记录的起始块读取最后提交的索引，并确认generation和卷UUID与VCI一致
+    fn recover_index_from_vci(&self, partition: u8, block_size: usize) -> Result<RecoveredIndex> {
+        let vci = self.scsi.read_volume_coherency_info(partition)?;
+        info!(
+            "VCI of partition {}: generation {} at block {}",
+            partition, vci.generation, vci.block
+        );
+
+        self.scsi.locate_block(partition, vci.block)?;
+        let position = self.scsi.read_position()?;
+        let xml = self.read_to_file_mark(block_size)?;
+        let index = LtfsIndex::from_xml(&xml)?;
+
+        if index.generationnumber != vci.generation || !index.volumeuuid.eq_ignore_ascii_case(&vci.volume_uuid) {
+            return Err(RustLtfsError::ltfs_index(format!(
+                "Index at block {} is generation {} of volume {}, VCI expects generation {} of volume {}",
+                vci.block, index.generationnumber, index.volumeuuid, vci.generation, vci.volume_uuid
+            )));
+        }
+
+        // VCR不同说明索引之后分区又被写入过（例如写入中途崩溃），之后的数据没有被索引
+        match self.scsi.read_volume_change_reference(partition) {
+            Ok(vcr) if vcr != vci.volume_change_reference => warn!(
+                "Partition {} was written after index generation {}; data written since then is not indexed",
+                partition, vci.generation
+            ),
+            Ok(_) => {}
+            Err(e) => debug!("Cannot read volume change reference: {}", e),
+        }
+
+        info!("Recovered index generation {} through VCI", index.generationnumber);
+        Ok(RecoveredIndex {
+            index,
+            xml,
+            candidates: 1,
+            partition,
+            file_mark: position.file_number,
+            from_vci: true,
+        })
+    }
+
+    /// 从本地索引备份恢复磁带索引：加载、校验extent，然后作为当前索引写回磁带
+    pub async fn restore_index_from_file(&mut self, file_path: &Path) -> Result<()> {
+        self.load_index_from_file(file_path)?;
+
+        let index = self
+            .index
+            .clone()
+            .ok_or_else(|| RustLtfsError::ltfs_index("No index loaded".to_string()))?;
+
+        if let Err(e) = self.validate_index_extents(&index) {
+            warn!("Index backup does not match tape data: {}", e);
+            self.index = None;
+            self.schema = None;
+            return Err(e);
+        }
+
+        info!("Writing restored index to tape");
+        self.update_index_on_tape_with_options_dual_partition(true)
+            .await?;
+
+        info!("Index restored from {:?}", file_path);
+        Ok(())
+    }
+}
+
+/// 递归收集目录树中所有文件的extent
+fn collect_extents<'a>(dir: &'a Directory, out: &mut Vec<&'a FileExtent>) {
+    for file in &dir.contents.files {
+        out.extend(file.extent_info.extents.iter());
+    }
+    for subdir in &dir.contents.directories {
+        collect_extents(subdir, out);
+    }
+}
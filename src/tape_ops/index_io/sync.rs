@@ -9,7 +9,12 @@
 use crate::error::{Result, RustLtfsError};
 use crate::ltfs_index::LtfsIndex;
 use super::super::TapeOperations;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
+
+/// How long to wait for buffered data to reach the media before an index write
+const BUFFER_DRAIN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+/// Poll interval while waiting for the drive buffer to drain
+const BUFFER_DRAIN_POLL: std::time::Duration = std::time::Duration::from_millis(100);
 
 /// Generate LTFS-compatible Z-format timestamp (matching LTFSCopyGUI XML format)
 fn format_ltfs_timestamp(datetime: chrono::DateTime<chrono::Utc>) -> String {
@@ -21,6 +26,23 @@ fn get_current_ltfs_timestamp() -> String {
     format_ltfs_timestamp(chrono::Utc::now())
 }
 
+/// Highest block (exclusive) referenced by any extent on the given logical partition
+pub(crate) fn last_extent_end_block(dir: &crate::ltfs_index::Directory, partition: &str, block_size: u64) -> u64 {
+    let mut end = 0;
+    for file in &dir.contents.files {
+        for extent in &file.extent_info.extents {
+            if extent.partition.eq_ignore_ascii_case(partition) {
+                let blocks = (extent.byte_offset + extent.byte_count).div_ceil(block_size);
+                end = end.max(extent.start_block + blocks);
+            }
+        }
+    }
+    for subdir in &dir.contents.directories {
+        end = end.max(last_extent_end_block(subdir, partition, block_size));
+    }
+    end
+}
+
 /// Helper function to count files recursively in directory tree
 fn count_files_recursive(dir: &crate::ltfs_index::Directory) -> usize {
     let mut count = dir.contents.files.len();
@@ -33,11 +55,31 @@ fn count_files_recursive(dir: &crate::ltfs_index::Directory) -> usize {
 impl TapeOperations {
     /// Update index on tape with force option (corresponds to VB.NET WriteCurrentIndex + RefreshIndexPartition)
     pub async fn update_index_on_tape_with_options_dual_partition(&mut self, force_index: bool) -> Result<()> {
-        info!("Starting to update tape LTFS index...");
+        if self.write_options.dry_run {
+            info!("Dry run: skipping index update on tape");
+            return Ok(());
+        }
 
+        // Enhanced logic following LTFSCopyGUI: check force_index OR TotalBytesUnindexed.
+        // 在定位、克隆索引之前判断，未修改时完全不触碰磁带
+        let force_index = force_index || self.write_options.force_index;
+        let should_update = force_index ||
+                          self.write_progress.total_bytes_unindexed > 0 ||
+                          self.modified;
 
+        if !should_update {
+            info!("No index update needed (no modifications since last index write)");
+            return Ok(());
+        }
+
+        info!(
+            "Starting to update tape LTFS index ({} changed directories, force: {})...",
+            self.modified_directories.len(),
+            force_index
+        );
+        debug!("Changed directories: {:?}", self.modified_directories);
+        self.invalidate_position_cache();
 
-        // Check if index exists and has modifications  
         // 优先使用 self.index (包含最新的文件状态)，回退到 self.schema
         let mut current_index = match &self.index {
             Some(idx) => {
@@ -71,10 +113,10 @@ impl TapeOperations {
                         previousgenerationlocation: None,
                         allowpolicyupdate: Some(false),
                         volumelockstate: "unlocked".to_string(),
-                        highestfileuid: Some(1),
+                        highestfileuid: Some(crate::ltfs_index::uid::ROOT_DIRECTORY_UID),
                         root_directory: crate::ltfs_index::Directory {
                             name: "".to_string(),
-                            uid: 1,
+                            uid: crate::ltfs_index::uid::ROOT_DIRECTORY_UID,
                             creation_time: now.clone(),
                             change_time: now.clone(),
                             modify_time: now.clone(),
@@ -91,16 +133,6 @@ impl TapeOperations {
             }
         };
 
-        // Enhanced logic following LTFSCopyGUI: check force_index OR TotalBytesUnindexed
-        let should_update = force_index || 
-                          self.write_progress.total_bytes_unindexed > 0 ||
-                          self.modified;
-
-        if !should_update {
-            info!("No index update needed (no modifications)");
-            return Ok(());
-        }
-
         let extra_partition_count = self.get_extra_partition_count();
         info!("Index update with ExtraPartitionCount: {}", extra_partition_count);
 
@@ -113,10 +145,13 @@ impl TapeOperations {
         }
 
         // Update internal state
+        self.tape_generation = Some(current_index.generationnumber);
         self.schema = Some(current_index.clone());
         self.index = Some(current_index);
         self.write_progress.total_bytes_unindexed = 0;
         self.modified = false;
+        self.modified_directories.clear();
+        self.last_index_write = std::time::Instant::now();
 
         info!("LTFS index update completed successfully");
         Ok(())
@@ -126,6 +161,10 @@ impl TapeOperations {
     async fn write_current_index_to_data_partition(&mut self, current_index: &mut LtfsIndex) -> Result<()> {
         info!("=== WriteCurrentIndex: Writing to Data Partition ===");
 
+        // 索引记录的块号必须对应已落盘的数据
+        self.scsi.synchronize_buffer()?;
+        self.confirm_write_buffer_flushed().await?;
+
         let current_position = self.scsi.read_position()?;
         debug!("Current tape position: partition={}, block={}", 
               current_position.partition, current_position.block_number);
@@ -175,20 +214,30 @@ impl TapeOperations {
                   is_first_write, is_eod_at_start, current_index.location.startblock, eod_position.block_number);
         }
 
+        // Reconcile with the index already on tape before appending
+        let next_generation = self
+            .reconcile_with_tape_index(current_index, &eod_position, data_partition)
+            .await?;
+
         // Write filemark before index (对应LTFSCopyGUI WriteFileMark)
         debug!("Writing filemark before index");
         self.scsi.write_filemarks(1)?;
 
         // Update index metadata (对应LTFSCopyGUI的索引元数据更新)
-        current_index.generationnumber += 1;
+        current_index.generationnumber = next_generation;
         current_index.updatetime = get_current_ltfs_timestamp();
+
+        // Previous generation is the last index in the data partition: the current location if it
+        // is there, otherwise RefreshIndexPartition already recorded it in previousgenerationlocation
+        if current_index.location.partition == "b"
+            && (self.tape_generation.is_some() || current_index.location.startblock > 0)
+        {
+            current_index.previousgenerationlocation = Some(crate::ltfs_index::Location {
+                partition: current_index.location.partition.clone(),
+                startblock: current_index.location.startblock,
+            });
+        }
         current_index.location.partition = "b".to_string(); // Data partition
-        
-        // Set previous generation location
-        current_index.previousgenerationlocation = Some(crate::ltfs_index::Location {
-            partition: current_index.location.partition.clone(),
-            startblock: current_index.location.startblock,
-        });
 
         let index_position = self.scsi.read_position()?;
         current_index.location.startblock = index_position.block_number;
@@ -206,14 +255,178 @@ impl TapeOperations {
 
         // Write filemark after index (对应LTFSCopyGUI WriteFileMark)
         self.scsi.write_filemarks(1)?;
+        // VCI指向的索引必须已经落盘
+        self.scsi.synchronize_buffer()?;
 
         let final_position = self.scsi.read_position()?;
         debug!("Index write completed at position: partition={}, block={}", 
               final_position.partition, final_position.block_number);
 
+        if self.write_options.verify_index {
+            self.verify_written_index(current_index, data_partition)?;
+        }
+
+        // Write VCI (Volume Coherency Information) - 对应LTFSCopyGUI WriteVCI
+        self.write_volume_coherency_info(data_partition, current_index);
+
         Ok(())
     }
 
+    /// 回读刚写入数据分区的索引，确认可以解析且generation一致 (`--append-only-verify`)
+    ///
+    /// 失败时返回错误，不写VCI，也不把内存中的索引当作已落盘。完成后回到数据分区EOD。
+    fn verify_written_index(&self, written: &LtfsIndex, data_partition: u8) -> Result<()> {
+        let block_size = self
+            .partition_label
+            .as_ref()
+            .map(|plabel| plabel.blocksize as usize)
+            .unwrap_or(self.block_size as usize);
+
+        debug!(
+            "Reading back index generation {} at p{}b{}",
+            written.generationnumber, data_partition, written.location.startblock
+        );
+        self.scsi.locate_block(data_partition, written.location.startblock)?;
+        let xml_content = self.read_to_file_mark(block_size)?;
+        let tape_index = LtfsIndex::from_xml(&xml_content).map_err(|e| {
+            RustLtfsError::ltfs_index(format!(
+                "Index written at p{}b{} cannot be parsed back from tape: {}",
+                data_partition, written.location.startblock, e
+            ))
+        })?;
+        if tape_index.generationnumber != written.generationnumber {
+            return Err(RustLtfsError::ltfs_index(format!(
+                "Index read back from p{}b{} has generation {}, expected {}",
+                data_partition,
+                written.location.startblock,
+                tape_index.generationnumber,
+                written.generationnumber
+            )));
+        }
+        info!(
+            "Index generation {} read back and verified at p{}b{}",
+            written.generationnumber, data_partition, written.location.startblock
+        );
+
+        self.scsi.locate_to_eod(data_partition)?;
+        Ok(())
+    }
+
+    /// 确认驱动器缓冲区已清空（扩展READ POSITION报告的缓冲块数为0），否则等待其写入介质
+    ///
+    /// 驱动器不支持扩展格式或无法报告计数时只记录日志，不阻止索引写入。
+    async fn confirm_write_buffer_flushed(&self) -> Result<()> {
+        let start = std::time::Instant::now();
+        loop {
+            let position = match self.scsi.read_position_extended() {
+                Ok(position) => position,
+                Err(e) => {
+                    debug!("Cannot read drive buffer state, skipping flush check: {}", e);
+                    return Ok(());
+                }
+            };
+            match position.blocks_in_buffer {
+                None => {
+                    debug!("Drive does not report buffered block count, skipping flush check");
+                    return Ok(());
+                }
+                Some(0) => return Ok(()),
+                Some(blocks) if start.elapsed() >= BUFFER_DRAIN_TIMEOUT => {
+                    return Err(RustLtfsError::tape_device(format!(
+                        "{} blocks ({} bytes) still in the drive buffer after {:?}; index block numbers would not match the media",
+                        blocks,
+                        position.bytes_in_buffer.unwrap_or(0),
+                        BUFFER_DRAIN_TIMEOUT
+                    )));
+                }
+                Some(blocks) => {
+                    debug!("Waiting for {} buffered blocks to reach the media", blocks);
+                    tokio::time::sleep(BUFFER_DRAIN_POLL).await;
+                }
+            }
+        }
+    }
+
+    /// 追加前与磁带上已有索引对账，返回本次应写入的generation number
+    ///
+    /// - 双分区磁带重新读取索引分区中的最新索引，确认卷UUID一致，且没有其他会话在本次
+    ///   加载索引之后追加过新的generation（否则会出现generation冲突并丢失对方写入的文件）
+    /// - generation从磁带上的值递增，而不是内存中可能已过时的值
+    /// - 校验EOD不早于索引中最后一个extent的结束块，以及上一代索引的位置
+    async fn reconcile_with_tape_index(
+        &mut self,
+        current_index: &LtfsIndex,
+        eod_position: &crate::scsi::TapePosition,
+        data_partition: u8,
+    ) -> Result<u64> {
+        let mut tape_generation = self.tape_generation;
+
+        if self.get_extra_partition_count() > 0 && tape_generation.is_some() {
+            match self.try_read_index_dual_partition().await {
+                Ok(xml_content) => match LtfsIndex::from_xml(&xml_content) {
+                    Ok(tape_index) => {
+                        if tape_index.volumeuuid != current_index.volumeuuid {
+                            return Err(RustLtfsError::ltfs_index(format!(
+                                "Volume UUID on tape ({}) does not match the index being written ({})",
+                                tape_index.volumeuuid, current_index.volumeuuid
+                            )));
+                        }
+                        if let Some(known) = tape_generation {
+                            if tape_index.generationnumber > known {
+                                return Err(RustLtfsError::ltfs_index(format!(
+                                    "Tape index is at generation {} but this session started from generation {}; \
+                                     another session has appended to this cartridge, reload the index and retry",
+                                    tape_index.generationnumber, known
+                                )));
+                            }
+                        }
+                        debug!("Index partition holds generation {}", tape_index.generationnumber);
+                        tape_generation = tape_generation.max(Some(tape_index.generationnumber));
+                    }
+                    Err(e) => warn!("Cannot parse index partition index, using loaded generation: {}", e),
+                },
+                Err(e) => warn!("Cannot read index partition index, using loaded generation: {}", e),
+            }
+
+            // Reading the index partition moved the head, return to data partition EOD
+            self.scsi.locate_to_eod(data_partition)?;
+        }
+
+        let block_size = self
+            .partition_label
+            .as_ref()
+            .map(|plabel| plabel.blocksize as u64)
+            .unwrap_or(self.block_size as u64)
+            .max(1);
+        let last_extent_end = last_extent_end_block(&current_index.root_directory, "b", block_size);
+        if last_extent_end > eod_position.block_number {
+            return Err(RustLtfsError::ltfs_index(format!(
+                "EOD at block {} is before the end of the last extent (block {}); data referenced by the index is missing from tape",
+                eod_position.block_number, last_extent_end
+            )));
+        }
+
+        if let Some(previous) = &current_index.previousgenerationlocation {
+            if previous.partition == "b" && previous.startblock >= eod_position.block_number {
+                return Err(RustLtfsError::ltfs_index(format!(
+                    "Previous generation index at block {} is beyond EOD at block {}",
+                    previous.startblock, eod_position.block_number
+                )));
+            }
+        }
+
+        let next_generation = match tape_generation {
+            Some(generation) => generation + 1,
+            None => current_index.generationnumber + 1,
+        };
+        info!(
+            "Appending index generation {} (tape generation {:?}, EOD block {}, last extent end block {})",
+            next_generation, tape_generation, eod_position.block_number, last_extent_end
+        );
+
+        Ok(next_generation)
+    }
+
     /// RefreshIndexPartition: Sync index to index partition (对应LTFSCopyGUI RefreshIndexPartition)
     /// 
     /// 🔧 LTFSCopyGUI compatible: Uses FileMark 3 for index partition
@@ -266,23 +479,43 @@ impl TapeOperations {
 
         // Write filemark after index
         self.scsi.write_filemarks(1)?;
+        self.scsi.synchronize_buffer()?;
 
         let final_position = self.scsi.read_position()?;
         info!("Index partition write completed: partition={}, block={}, index_size={} bytes", 
               final_position.partition, final_position.block_number, index_xml.len());
 
         // Write VCI (Volume Coherency Information) - 对应LTFSCopyGUI WriteVCI
-        debug!("Writing VCI (Volume Coherency Information)");
-        self.write_volume_coherency_info(current_index).await?;
+        self.write_volume_coherency_info(index_partition, current_index);
 
         Ok(())
     }
 
     /// Write Volume Coherency Information (对应LTFSCopyGUI WriteVCI)
-    async fn write_volume_coherency_info(&mut self, _current_index: &LtfsIndex) -> Result<()> {
-        // VCI写入逻辑 - 这是LTFSCopyGUI的高级功能，暂时实现基础版本
-        debug!("VCI write completed (basic implementation)");
-        Ok(())
+    ///
+    /// 记录刚写入 `partition` 的索引的generation和起始块，崩溃后 recover_index 可以直接
+    /// 定位到最后提交的索引而不必扫描。驱动器或介质不支持写MAM时只记录警告，索引本身已写入。
+    pub(crate) fn write_volume_coherency_info(&self, partition: u8, current_index: &LtfsIndex) {
+        let result = self
+            .scsi
+            .read_volume_change_reference(partition)
+            .and_then(|volume_change_reference| {
+                let vci = crate::scsi::VolumeCoherencyInfo {
+                    volume_change_reference,
+                    generation: current_index.generationnumber,
+                    block: current_index.location.startblock,
+                    volume_uuid: current_index.volumeuuid.clone(),
+                };
+                self.scsi.write_volume_coherency_info(partition, &vci)
+            });
+
+        match result {
+            Ok(()) => debug!(
+                "VCI written for partition {}: generation {}, block {}",
+                partition, current_index.generationnumber, current_index.location.startblock
+            ),
+            Err(e) => warn!("Cannot write VCI for partition {}: {}", partition, e),
+        }
     }
 
     /// Write XML content to tape (following commit 3432483 variable-length pattern)
@@ -296,7 +529,8 @@ impl TapeOperations {
         // This matches LTFSCopyGUI behavior and prevents the 0-padding issue
         
         // Write XML as single variable-length block (commit 3432483 style)
-        let blocks_written = self.scsi.write_blocks(1, &xml_bytes[..xml_size])?;
+        // The index must still be committed when the data that preceded it hit early warning
+        let blocks_written = self.scsi.write_blocks_allow_early_warning(1, &xml_bytes[..xml_size])?;
         
         if blocks_written != 1 {
             return Err(RustLtfsError::tape_device(format!(
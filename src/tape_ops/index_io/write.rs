@@ -5,11 +5,20 @@
 use super::super::TapeOperations;
 use super::super::utils::{get_current_ltfs_timestamp, system_time_to_ltfs_timestamp};
 use crate::error::{Result, RustLtfsError};
-use crate::ltfs_index::LtfsIndex;
+use crate::ltfs_index::name::normalize_name;
+use crate::ltfs_index::uid::ROOT_DIRECTORY_UID;
+use crate::ltfs_index::{IndexEntry, LtfsIndex, PathNormalize};
 use std::collections::HashMap;
 use std::path::Path;
 use tracing::debug;
 
+pub(super) fn worm_overwrite_error(target_path: &str) -> RustLtfsError {
+    RustLtfsError::tape_device(format!(
+        "WORM media: '{}' already exists on tape and cannot be overwritten or deleted",
+        target_path
+    ))
+}
+
 /// Index management operations for TapeOperations
 impl TapeOperations {
     /// Create new empty LTFS index
@@ -32,10 +41,10 @@ impl TapeOperations {
             previousgenerationlocation: None,
             allowpolicyupdate: Some(false),
             volumelockstate: "unlocked".to_string(),
-            highestfileuid: Some(1),
+            highestfileuid: Some(ROOT_DIRECTORY_UID),
             root_directory: crate::ltfs_index::Directory {
                 name: "".to_string(),
-                uid: 1,
+                uid: ROOT_DIRECTORY_UID,
                 creation_time: now.clone(),
                 change_time: now.clone(),
                 modify_time: now.clone(),
@@ -50,208 +59,103 @@ impl TapeOperations {
         }
     }
 
-    /// Add file to target directory, creating directories as needed
-    /// This function handles UID allocation AFTER directory creation to prevent conflicts
-    pub fn add_file_to_target_directory(
-        &self,
-        index: &mut LtfsIndex,
-        file: crate::ltfs_index::File,
-        target_path: &str,
-    ) -> Result<()> {
-        debug!(
-            "Adding file '{}' to target path '{}'",
-            file.name, target_path
-        );
-
-        // Normalize target path
-        let normalized_path = target_path.trim_start_matches('/').trim_end_matches('/');
-        debug!("Normalized path: '{}'", normalized_path);
-
-        if normalized_path.is_empty() {
-            // Add to root directory - allocate UID here
-            let file_name = file.name.clone();
-            let mut file_to_add = file;
-            let new_file_uid = index.highestfileuid.unwrap_or(0) + 1;
-            file_to_add.uid = new_file_uid;
-            index.highestfileuid = Some(new_file_uid);
-
-            debug!(
-                "Adding file '{}' to root directory with UID {}",
-                file_name, new_file_uid
-            );
-            index.root_directory.contents.files.push(file_to_add);
-            debug!(
-                "Root directory now has {} files",
-                index.root_directory.contents.files.len()
-            );
+    /// Add a file entry to the index at `target_path`
+    ///
+    /// `target_path` is the file's full path on tape: intermediate directories are
+    /// created as needed and the last component becomes the file name. A path
+    /// ending in '/' (or empty) names the directory and keeps the entry's own name.
+    /// WORM介质只允许追加：目标文件已存在时拒绝写入（覆盖需要改写已有数据）
+    ///
+    /// 在写入数据之前调用，避免数据写上磁带后才在索引更新时失败。
+    pub(crate) fn ensure_worm_allows_write(&self, target_path: &str) -> Result<()> {
+        if !self.is_worm() || target_path.ends_with('/') {
             return Ok(());
         }
+        let index = match &self.index {
+            Some(index) => index,
+            None => return Ok(()),
+        };
 
-        // Split path into components
-        let path_parts: Vec<&str> = normalized_path.split('/').collect();
-        debug!("Target path components: {:?}", path_parts);
-
-        // Navigate to target directory, creating directories as needed
-        debug!("Finding/creating target directory path...");
-        // First ensure directory path exists (this may update highestfileuid)
-        {
-            self.ensure_directory_path_exists(index, &path_parts)?;
-        }
-        debug!("Target directory found/created, adding file...");
-
-        // CRITICAL: Allocate file UID AFTER directory creation to avoid conflicts
-        // Directory creation may have updated highestfileuid, so we get fresh value
-        let file_name = file.name.clone();
-        let mut file_to_add = file;
-        let new_file_uid = index.highestfileuid.unwrap_or(0) + 1;
-        file_to_add.uid = new_file_uid;
-        index.highestfileuid = Some(new_file_uid);
-
-        debug!(
-            "Allocated UID {} for file '{}' after directory creation",
-            new_file_uid, file_name
-        );
-
-        // Now get a fresh reference to the target directory to add the file
-        let target_dir = self.get_directory_by_path_mut(index, &path_parts)?;
-        target_dir.contents.files.push(file_to_add);
-        debug!(
-            "File '{}' added to directory '{}', directory now has {} files",
-            file_name,
-            normalized_path,
-            target_dir.contents.files.len()
+        let target_path = normalize_name(target_path);
+        // 写入总是按精确名称建立条目，因此这里固定区分大小写
+        let exists = matches!(
+            index.find_path(&target_path, PathNormalize::CaseSensitive),
+            Some((_, IndexEntry::File(_)))
         );
-
+        if exists {
+            return Err(worm_overwrite_error(&target_path));
+        }
         Ok(())
     }
 
-    /// Ensure directory path exists, creating directories as needed
-    fn ensure_directory_path_exists<'a>(
-        &self,
-        index: &'a mut LtfsIndex,
-        path_parts: &[&str],
-    ) -> Result<&'a mut crate::ltfs_index::Directory> {
-        debug!(
-            "ensure_directory_path_exists called with path_parts: {:?}",
-            path_parts
-        );
-
-        if path_parts.is_empty() {
-            debug!("Path parts empty, returning root directory");
-            return Ok(&mut index.root_directory);
-        }
+    pub fn add_file_to_target_directory(
+        &mut self,
+        index: &mut LtfsIndex,
+        file: crate::ltfs_index::File,
+        target_path: &str,
+    ) -> Result<()> {
+        // Names are stored in NFC so NFD sources (macOS) don't create duplicate entries
+        let mut file = file;
+        file.name = normalize_name(&file.name);
+        let target_path = normalize_name(target_path);
+        let target_path = target_path.as_str();
 
-        let mut current_dir = &mut index.root_directory;
-        debug!(
-            "Starting at root directory with {} subdirectories",
-            current_dir.contents.directories.len()
-        );
+        let mut file_to_add = file;
+        let mut dir_parts = LtfsIndex::split_path(target_path);
 
-        for (i, part) in path_parts.iter().enumerate() {
-            debug!("Processing directory part: '{}' (level {})", part, i);
-            debug!(
-                "Current directory has {} subdirectories",
-                current_dir.contents.directories.len()
-            );
-
-            // Find existing directory or create new one
-            let dir_index = current_dir
-                .contents
-                .directories
-                .iter()
-                .position(|d| d.name == *part);
-
-            match dir_index {
-                Some(idx) => {
-                    debug!("Found existing directory: '{}' at index {}", part, idx);
-                    // Directory exists, continue navigation
-                    current_dir = &mut current_dir.contents.directories[idx];
-                }
-                None => {
-                    debug!("Creating new directory: '{}'", part);
-                    // Create new directory
-                    let now = get_current_ltfs_timestamp();
-                    let new_uid = index.highestfileuid.unwrap_or(0) + 1;
-                    debug!("New directory UID: {}", new_uid);
-
-                    let new_directory = crate::ltfs_index::Directory {
-                        name: part.to_string(),
-                        uid: new_uid,
-                        creation_time: now.clone(),
-                        change_time: now.clone(),
-                        modify_time: now.clone(),
-                        access_time: now.clone(),
-                        backup_time: now,
-                        read_only: false,
-                        contents: crate::ltfs_index::DirectoryContents {
-                            files: Vec::new(),
-                            directories: Vec::new(),
-                        },
-                    };
-
-                    current_dir.contents.directories.push(new_directory);
-                    index.highestfileuid = Some(new_uid);
-                    debug!("Directory '{}' created and added, current directory now has {} subdirectories",
-                           part, current_dir.contents.directories.len());
-
-                    // Navigate to newly created directory
-                    let last_index = current_dir.contents.directories.len() - 1;
-                    current_dir = &mut current_dir.contents.directories[last_index];
-                    debug!("Navigated to newly created directory '{}'", part);
-                }
+        if !target_path.ends_with('/') {
+            if let Some(file_name) = dir_parts.pop() {
+                file_to_add.name = file_name.to_string();
             }
         }
 
+        if file_to_add.name.is_empty() {
+            return Err(RustLtfsError::ltfs_index(format!(
+                "Cannot determine file name for target path '{}'",
+                target_path
+            )));
+        }
+
+        let dir_path = dir_parts.join("/");
         debug!(
-            "Final target directory reached, has {} files, {} subdirectories",
-            current_dir.contents.files.len(),
-            current_dir.contents.directories.len()
+            "Adding file '{}' to directory '/{}'",
+            file_to_add.name, dir_path
         );
-        Ok(current_dir)
-    }
-
-    /// Get mutable reference to directory by path (helper function for add_file_to_target_directory)
-    fn get_directory_by_path_mut<'a>(
-        &self,
-        index: &'a mut LtfsIndex,
-        path_parts: &[&str],
-    ) -> Result<&'a mut crate::ltfs_index::Directory> {
-        if path_parts.is_empty() {
-            return Ok(&mut index.root_directory);
-        }
 
-        let mut current_dir = &mut index.root_directory;
+        // Create the directory path first (this may update highestfileuid),
+        // then allocate the file UID so it never collides with a new directory
+        index.get_or_create_directory(&dir_path);
 
-        for part in path_parts.iter() {
-            let dir_index = current_dir
-                .contents
-                .directories
-                .iter()
-                .position(|d| d.name == *part)
-                .ok_or_else(|| {
-                    RustLtfsError::ltfs_index(format!("Directory '{}' not found in path", part))
-                })?;
+        let new_file_uid = index.allocate_uid();
+        file_to_add.uid = new_file_uid;
 
-            current_dir = &mut current_dir.contents.directories[dir_index];
+        let target_dir = index.get_or_create_directory(&dir_path);
+        if self.is_worm() && target_dir.contents.files.iter().any(|f| f.name == file_to_add.name) {
+            return Err(worm_overwrite_error(target_path));
         }
+        target_dir.contents.files.push(file_to_add);
+        debug!(
+            "File added with UID {}, directory '/{}' now has {} files",
+            new_file_uid,
+            dir_path,
+            target_dir.contents.files.len()
+        );
+        self.mark_directory_modified(&dir_path);
 
-        Ok(current_dir)
+        Ok(())
     }
 
-
-
-
-
-
     // ================== 索引更新相关 ==================
 
     /// Enhanced index update for file write (对应LTFSCopyGUI的索引更新逻辑)
+    ///
+    /// `extents` 为实际写到磁带上的数据区域，稀疏文件的空洞表现为extent之间的file_offset间隙。
     pub fn update_index_for_file_write_enhanced(
         &mut self,
         source_path: &Path,
         target_path: &str,
         file_size: u64,
-        write_position: &crate::scsi::TapePosition,
+        extents: Vec<crate::ltfs_index::FileExtent>,
         file_hashes: Option<HashMap<String, String>>,
     ) -> Result<()> {
         debug!(
@@ -271,27 +175,13 @@ impl TapeOperations {
         // Create new file entry with enhanced metadata
         let file_name = source_path
             .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("unknown")
-            .to_string();
+            .map(crate::ltfs_index::name::name_from_os_str)
+            .unwrap_or_else(|| "unknown".to_string());
 
         let now = get_current_ltfs_timestamp();
         // NOTE: UID will be allocated in add_file_to_target_directory() after directories are created
         // This prevents UID conflicts when creating nested directories
 
-        let extent = crate::ltfs_index::FileExtent {
-            // 使用实际写入位置的分区信息，而不是硬编码
-            partition: if write_position.partition == 0 {
-                "a".to_string()
-            } else {
-                "b".to_string()
-            },
-            start_block: write_position.block_number,
-            byte_count: file_size,
-            file_offset: 0,
-            byte_offset: 0,
-        };
-
         // Get file metadata for timestamps
         let metadata = std::fs::metadata(source_path).map_err(|e| {
             RustLtfsError::file_operation(format!("Cannot get file metadata: {}", e))
@@ -312,6 +202,29 @@ impl TapeOperations {
             .map(|t| system_time_to_ltfs_timestamp(t))
             .unwrap_or_else(|_| now.clone());
 
+        let mut attributes = Vec::new();
+        if let Some(hashes) = file_hashes {
+            // Create extended attributes following LTFSCopyGUI format
+            for (hash_key, hash_value) in hashes {
+                attributes.push(crate::ltfs_index::ExtendedAttribute {
+                    key: hash_key, // Already contains full key name like "ltfs.hash.sha1sum"
+                    value: hash_value,
+                });
+            }
+
+            // Add capacity remain attribute (placeholder)
+            attributes.push(crate::ltfs_index::ExtendedAttribute {
+                key: "ltfscopygui.capacityremain".to_string(),
+                value: "12".to_string(), // Placeholder value
+            });
+        }
+        // 源文件自身的扩展属性（文件系统及 .xattr 侧车文件），不覆盖上面的条目
+        for attr in super::super::xattr::source_xattrs(source_path) {
+            if !attributes.iter().any(|existing| existing.key == attr.key) {
+                attributes.push(attr);
+            }
+        }
+
         let new_file = crate::ltfs_index::File {
             name: file_name,
             uid: 0, // Temporary placeholder - will be assigned in add_file_to_target_directory
@@ -325,29 +238,10 @@ impl TapeOperations {
             openforwrite: false,
             symlink: None,
             extent_info: crate::ltfs_index::ExtentInfo {
-                extents: vec![extent],
-            },
-            extended_attributes: if let Some(hashes) = file_hashes {
-                // Create extended attributes following LTFSCopyGUI format
-                let mut attributes = Vec::new();
-
-                for (hash_key, hash_value) in hashes {
-                    attributes.push(crate::ltfs_index::ExtendedAttribute {
-                        key: hash_key, // Already contains full key name like "ltfs.hash.sha1sum"
-                        value: hash_value,
-                    });
-                }
-
-                // Add capacity remain attribute (placeholder)
-                attributes.push(crate::ltfs_index::ExtendedAttribute {
-                    key: "ltfscopygui.capacityremain".to_string(),
-                    value: "12".to_string(), // Placeholder value
-                });
-
-                Some(crate::ltfs_index::ExtendedAttributes { attributes })
-            } else {
-                None
+                extents,
             },
+            extended_attributes: (!attributes.is_empty())
+                .then_some(crate::ltfs_index::ExtendedAttributes { attributes }),
         };
 
         // Parse target path and add file to appropriate directory
@@ -381,13 +275,70 @@ impl TapeOperations {
         Ok(())
     }
 
+    /// Index update for a symlink: records the link target, no data extents are written
+    pub fn update_index_for_symlink(
+        &mut self,
+        source_path: &Path,
+        target_path: &str,
+        link_target: String,
+    ) -> Result<()> {
+        debug!(
+            "Updating LTFS index for symlink: {:?} -> {} (target: {})",
+            source_path, target_path, link_target
+        );
+
+        let mut current_index = match &self.index {
+            Some(index) => index.clone(),
+            None => self.create_new_ltfs_index(),
+        };
+
+        let file_name = source_path
+            .file_name()
+            .map(crate::ltfs_index::name::name_from_os_str)
+            .unwrap_or_else(|| "unknown".to_string());
+
+        let now = get_current_ltfs_timestamp();
+        let modify_time = std::fs::symlink_metadata(source_path)
+            .and_then(|m| m.modified())
+            .map(system_time_to_ltfs_timestamp)
+            .unwrap_or_else(|_| now.clone());
+
+        let new_file = crate::ltfs_index::File {
+            name: file_name,
+            uid: 0, // Temporary placeholder - will be assigned in add_file_to_target_directory
+            length: 0,
+            creation_time: modify_time.clone(),
+            change_time: now.clone(),
+            modify_time,
+            access_time: now.clone(),
+            backup_time: now,
+            read_only: false,
+            openforwrite: false,
+            symlink: Some(link_target),
+            extent_info: crate::ltfs_index::ExtentInfo::default(),
+            extended_attributes: None,
+        };
+
+        self.add_file_to_target_directory(&mut current_index, new_file, target_path)?;
+
+        current_index.generationnumber += 1;
+        current_index.updatetime = get_current_ltfs_timestamp();
+
+        self.index = Some(current_index.clone());
+        self.schema = Some(current_index);
+        self.modified = true;
+
+        debug!("LTFS index updated with new symlink");
+        Ok(())
+    }
+
     /// Basic index update for file write operation
     pub fn update_index_for_file_write(
         &mut self,
         source_path: &Path,
         target_path: &str,
         file_size: u64,
-        write_position: &crate::scsi::TapePosition,
+        extents: Vec<crate::ltfs_index::FileExtent>,
     ) -> Result<()> {
         debug!(
             "Updating LTFS index for write: {:?} -> {} ({} bytes)",
@@ -406,27 +357,13 @@ impl TapeOperations {
         // Create new file entry
         let file_name = source_path
             .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("unknown")
-            .to_string();
+            .map(crate::ltfs_index::name::name_from_os_str)
+            .unwrap_or_else(|| "unknown".to_string());
 
         let now = get_current_ltfs_timestamp();
         // NOTE: UID will be allocated in add_file_to_target_directory() after directories are created
         // This prevents UID conflicts when creating nested directories
 
-        let extent = crate::ltfs_index::FileExtent {
-            // 使用实际写入位置的分区信息，而不是硬编码
-            partition: if write_position.partition == 0 {
-                "a".to_string()
-            } else {
-                "b".to_string()
-            },
-            start_block: write_position.block_number,
-            byte_count: file_size,
-            file_offset: 0,
-            byte_offset: 0,
-        };
-
         let new_file = crate::ltfs_index::File {
             name: file_name,
             uid: 0, // Temporary placeholder - will be assigned in add_file_to_target_directory
@@ -440,7 +377,7 @@ impl TapeOperations {
             openforwrite: false,
             symlink: None,
             extent_info: crate::ltfs_index::ExtentInfo {
-                extents: vec![extent],
+                extents,
             },
             extended_attributes: None,
         };
@@ -0,0 +1,237 @@
+//! Write Session Journal
+//!
+//! Persists the progress of a directory write session to a sidecar JSON file so an
+//! interrupted job can be resumed without re-writing files already committed to tape.
+//!
+//! A file is only treated as done once an index containing it has been written:
+//! files written after the last index update are kept as `pending` and written again
+//! on resume, because their data is on tape but no index references it.
+
+use crate::error::{Result, RustLtfsError};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, warn};
+
+/// Default journal file name
+pub const DEFAULT_JOURNAL_FILE: &str = ".rustltfs-journal";
+
+/// Persisted state of a directory write session
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WriteJournal {
+    /// Local source directory of the session
+    pub source: PathBuf,
+    /// Target path on tape
+    pub target_path: String,
+    /// Volume UUID of the cartridge being written
+    pub volume_uuid: Option<String>,
+    /// Tape paths of files covered by an index already written to tape
+    pub committed: BTreeSet<String>,
+    /// Tape paths written since the last index update (not yet safe to skip)
+    pub pending: BTreeSet<String>,
+    pub files_written: u64,
+    pub bytes_written: u64,
+    /// Generation of the last index written to tape
+    pub index_generation: Option<u64>,
+    /// Data partition EOD block after the last journaled file
+    pub last_eod_partition: Option<u8>,
+    pub last_eod_block: Option<u64>,
+}
+
+impl WriteJournal {
+    /// Load a journal from disk
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            RustLtfsError::file_operation(format!("Cannot read write journal {:?}: {}", path, e))
+        })?;
+        serde_json::from_str(&content).map_err(|e| {
+            RustLtfsError::parse(format!("Invalid write journal {:?}: {}", path, e))
+        })
+    }
+
+    /// Save the journal atomically (write a temporary file, then rename)
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(self).map_err(|e| {
+            RustLtfsError::system(format!("Cannot serialize write journal: {}", e))
+        })?;
+
+        let mut tmp_name = path.as_os_str().to_os_string();
+        tmp_name.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_name);
+
+        std::fs::write(&tmp_path, content)?;
+        std::fs::rename(&tmp_path, path)?;
+        debug!(
+            "Write journal saved: {} committed, {} pending",
+            self.committed.len(),
+            self.pending.len()
+        );
+        Ok(())
+    }
+
+    /// Whether the file is covered by an index already on tape
+    pub fn is_committed(&self, tape_path: &str) -> bool {
+        self.committed.contains(tape_path)
+    }
+
+    /// Move all pending files to committed after an index has been written
+    fn commit_pending(&mut self, generation: Option<u64>) {
+        self.committed.append(&mut self.pending);
+        if generation.is_some() {
+            self.index_generation = generation;
+        }
+    }
+}
+
+/// Journal attached to a running write session
+#[derive(Debug, Clone)]
+pub(crate) struct JournalState {
+    pub path: PathBuf,
+    pub journal: WriteJournal,
+}
+
+impl super::TapeOperations {
+    /// 开始新的写入日志：每写完一个文件就更新日志文件
+    pub fn start_write_journal(&mut self, path: &Path, source: &Path, target_path: &str) -> Result<()> {
+        let journal = WriteJournal {
+            source: source.to_path_buf(),
+            target_path: target_path.to_string(),
+            volume_uuid: self.index.as_ref().map(|idx| idx.volumeuuid.clone()),
+            index_generation: self.tape_generation,
+            ..Default::default()
+        };
+        journal.save(path)?;
+        info!("Write journal started: {:?}", path);
+
+        self.write_journal = Some(JournalState {
+            path: path.to_path_buf(),
+            journal,
+        });
+        Ok(())
+    }
+
+    /// 从写入日志恢复：校验磁带与日志一致后，已提交到索引的文件将被跳过
+    pub fn resume_from_journal(&mut self, path: &Path, source: &Path, target_path: &str) -> Result<WriteJournal> {
+        let journal = WriteJournal::load(path)?;
+
+        if journal.source != source || journal.target_path != target_path {
+            return Err(RustLtfsError::parameter_validation(format!(
+                "Journal {:?} belongs to {:?} -> {}, not {:?} -> {}",
+                path, journal.source, journal.target_path, source, target_path
+            )));
+        }
+
+        let index = self.index.as_ref().ok_or_else(|| {
+            RustLtfsError::ltfs_index("No LTFS index loaded, cannot resume write session".to_string())
+        })?;
+
+        if let Some(ref uuid) = journal.volume_uuid {
+            if *uuid != index.volumeuuid {
+                return Err(RustLtfsError::ltfs_index(format!(
+                    "Journal was written for volume {}, but the loaded tape is {}",
+                    uuid, index.volumeuuid
+                )));
+            }
+        }
+
+        if let (Some(journaled), Some(on_tape)) = (journal.index_generation, self.tape_generation) {
+            if on_tape < journaled {
+                return Err(RustLtfsError::ltfs_index(format!(
+                    "Tape index generation {} is older than the journal's committed generation {}",
+                    on_tape, journaled
+                )));
+            }
+        }
+
+        // 校验数据分区EOD与日志记录一致
+        if let (Some(partition), Some(block)) = (journal.last_eod_partition, journal.last_eod_block) {
+            self.invalidate_position_cache();
+            self.scsi.locate_to_eod(partition)?;
+            let eod = self.scsi.read_position()?;
+            if eod.block_number < block {
+                return Err(RustLtfsError::tape_device(format!(
+                    "Tape EOD p{}b{} is before the journaled EOD p{}b{}; the tape does not match the journal",
+                    eod.partition, eod.block_number, partition, block
+                )));
+            }
+            if eod.block_number > block {
+                warn!(
+                    "Tape EOD p{}b{} is past the journaled EOD b{}: data from the interrupted file will be abandoned",
+                    eod.partition, eod.block_number, block
+                );
+            }
+        }
+
+        if !journal.pending.is_empty() {
+            warn!(
+                "{} files were written after the last index update and will be written again",
+                journal.pending.len()
+            );
+        }
+        info!(
+            "Resuming write session from {:?}: {} files already committed",
+            path,
+            journal.committed.len()
+        );
+
+        let mut resumed = journal.clone();
+        resumed.pending.clear();
+        self.write_journal = Some(JournalState {
+            path: path.to_path_buf(),
+            journal: resumed,
+        });
+        Ok(journal)
+    }
+
+    /// 文件是否已在之前的会话中写入并提交到索引
+    pub(crate) fn journal_has_committed(&self, tape_path: &str) -> bool {
+        self.write_journal
+            .as_ref()
+            .is_some_and(|state| state.journal.is_committed(tape_path))
+    }
+
+    /// 记录一个已写入的文件并保存日志
+    pub(crate) fn journal_record_file(&mut self, tape_path: &str, bytes: u64) -> Result<()> {
+        if self.write_journal.is_none() {
+            return Ok(());
+        }
+
+        let eod = self.scsi.read_position().ok();
+        let index_written = self.write_progress.total_bytes_unindexed == 0;
+        let generation = self.tape_generation;
+
+        if let Some(state) = self.write_journal.as_mut() {
+            state.journal.pending.insert(tape_path.to_string());
+            state.journal.files_written += 1;
+            state.journal.bytes_written += bytes;
+            if index_written {
+                state.journal.commit_pending(generation);
+            }
+            if let Some(position) = eod {
+                state.journal.last_eod_partition = Some(position.partition);
+                state.journal.last_eod_block = Some(position.block_number);
+            }
+            state.journal.save(&state.path)?;
+        }
+        Ok(())
+    }
+
+    /// 索引写入后将所有待提交文件标记为已提交；会话成功结束时删除日志
+    pub fn finish_write_journal(&mut self, remove: bool) -> Result<()> {
+        let generation = self.tape_generation;
+        let index_written = self.write_progress.total_bytes_unindexed == 0;
+
+        if let Some(mut state) = self.write_journal.take() {
+            if index_written {
+                state.journal.commit_pending(generation);
+            }
+            if remove && state.journal.pending.is_empty() {
+                std::fs::remove_file(&state.path)?;
+                info!("Write session complete, journal {:?} removed", state.path);
+            } else {
+                state.journal.save(&state.path)?;
+            }
+        }
+        Ok(())
+    }
+}
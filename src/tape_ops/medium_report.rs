@@ -0,0 +1,86 @@
+//! Medium Report
+//!
+//! Aggregates the diagnostics of the loaded cartridge for `info --medium`.
+//! Every field is collected independently: a failing SCSI call leaves its field
+//! `None` instead of aborting the whole report.
+
+use super::capacity_manager::TapeCapacityInfo;
+use super::drive_health::{DriveErrorCounters, TapeAlertFlag};
+use super::{IndexStatistics, LtfsPartitionLabel};
+use crate::error::Result;
+use crate::scsi::{DataCompressionPage, EncryptionStatus, MamAttribute, MediaType};
+use tracing::{debug, info};
+
+/// 磁带介质诊断信息汇总；`None` 表示对应的查询失败
+#[derive(Debug, Default)]
+pub struct MediumReport {
+    /// 卡带条码 (MAM)
+    pub barcode: Option<String>,
+    /// 介质类型 (MODE SENSE)
+    pub media_type: Option<MediaType>,
+    /// 分区0开头的LTFS标签；无法读取时磁带可能未格式化为LTFS
+    pub ltfs_label: Option<LtfsPartitionLabel>,
+    /// 索引统计（卷UUID、代数等）
+    pub index: Option<IndexStatistics>,
+    /// 附加分区数 (MODE SENSE page 0x11)
+    pub extra_partition_count: Option<u8>,
+    /// 各分区剩余/最大容量 (KB)
+    pub capacity: Option<TapeCapacityInfo>,
+    /// 硬件压缩状态 (page 0x0F)
+    pub compression: Option<DataCompressionPage>,
+    /// 驱动器数据加密状态 (SECURITY PROTOCOL IN page 0x0020)
+    pub encryption: Option<EncryptionStatus>,
+    /// 当前激活的TapeAlert标志
+    pub tape_alerts: Option<Vec<TapeAlertFlag>>,
+    /// 驱动器读写错误计数 (log pages 0x02 / 0x03)
+    pub error_counters: Option<DriveErrorCounters>,
+    /// 卡带MAM中的全部属性（厂商、生产日期、写入应用等）
+    pub cartridge_memory: Option<Vec<MamAttribute>>,
+}
+
+/// 查询失败时记录原因并返回None
+fn available<T>(what: &str, result: Result<T>) -> Option<T> {
+    result
+        .map_err(|e| debug!("{} unavailable: {}", what, e))
+        .ok()
+}
+
+impl super::TapeOperations {
+    /// 汇总已装入磁带的诊断信息（条码、介质类型、LTFS格式、索引、容量、压缩、加密、TapeAlert、错误计数、MAM属性）
+    ///
+    /// 只要求设备已打开；单项查询失败不影响其他项。
+    pub async fn collect_medium_report(&mut self) -> Result<MediumReport> {
+        let mut report = MediumReport {
+            barcode: available("Barcode", self.scsi.read_barcode()),
+            media_type: available("Media type", self.scsi.check_media_status()),
+            ..Default::default()
+        };
+        self.media_type = report.media_type;
+
+        if let Err(e) = self.wait_for_device_ready().await {
+            debug!("Device not ready, medium report limited to cartridge memory: {}", e);
+            report.tape_alerts = available("TapeAlert", self.read_tape_alert());
+            report.error_counters = available("Error counters", self.read_error_counters());
+            report.cartridge_memory = available("Cartridge memory", self.dump_mam());
+            return Ok(report);
+        }
+
+        self.initialize_partition_detection().await?;
+        report.extra_partition_count = self.extra_partition_count;
+        report.ltfs_label = available("LTFS label", self.read_volume_label().await);
+        if report.ltfs_label.is_some()
+            && available("LTFS index", self.read_index_from_tape().await).is_some()
+        {
+            report.index = self.get_index_statistics();
+        }
+        report.capacity = available("Capacity", self.refresh_capacity().await);
+        report.compression = available("Compression", self.scsi.get_compression());
+        report.encryption = available("Encryption status", self.scsi.get_encryption_status());
+        report.tape_alerts = available("TapeAlert", self.read_tape_alert());
+        report.error_counters = available("Error counters", self.read_error_counters());
+        report.cartridge_memory = available("Cartridge memory", self.dump_mam());
+
+        info!("Medium report collected for {}", self.device_path);
+        Ok(report)
+    }
+}
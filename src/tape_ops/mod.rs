@@ -1,29 +1,54 @@
 // Re-export modules
 pub mod capacity_manager;
+pub mod check;
+pub mod drive_health;
 pub mod core;
 pub mod read_operations;
 pub mod write_operations;
 pub mod hash;
 pub mod utils;
 pub mod volume;
+pub mod verify;
+pub mod journal;
+pub mod span;
 pub mod index_io;
+pub mod dump;
+pub mod format;
+pub mod medium_report;
+pub mod seek_benchmark;
+mod read_ahead;
+mod read_stream;
+mod sparse;
+mod xattr;
 
 pub use self::core::*;
-// 选择性导出避免重名冲突
-// (format_operations types were previously re-exported here for MKLTFS.
-//  MKLTFS command and related helpers have been removed from the CLI,
-//  so the re-export is no longer necessary.)
+pub use self::verify::VerifyReport;
+pub use dump::{DumpProgress, DumpProgressCallback};
+pub use format::{FormatProgressCallback, MkltfsParams};
+pub use medium_report::MediumReport;
 
 /// LTFS分区标签结构 (对应LTFSCopyGUI的ltfslabel)
 #[derive(Debug, Clone)]
 pub struct LtfsPartitionLabel {
     pub blocksize: u32,
+    pub compression: bool, // 驱动器硬件压缩状态 (Data Compression页的DCE位)
+    pub volume_serial: Option<String>, // VOL1标签中的卷序列号 (bytes 4-9)
+    pub volume_uuid: Option<String>,
+    pub format_time: Option<String>,
+    pub index_partition: String,
+    pub data_partition: String,
 }
 
 impl Default for LtfsPartitionLabel {
     fn default() -> Self {
         Self {
             blocksize: crate::scsi::block_sizes::LTO_BLOCK_SIZE, // 默认64KB
+            compression: false,
+            volume_serial: None,
+            volume_uuid: None,
+            format_time: None,
+            index_partition: "a".to_string(),
+            data_partition: "b".to_string(),
         }
     }
 }
@@ -77,6 +102,163 @@ pub struct WriteProgress {
     pub files_written: u64,
     pub bytes_written: u64,
 
+    /// Files re-read and confirmed after write (verify mode)
+    pub files_verified: u64,
+    /// Files whose read-back data did not match the source
+    pub verification_failures: u64,
+
+    /// Drive throughput (moving average, MiB/s) measured on the last block written
+    pub throughput_mbps: Option<f64>,
+
+    /// When the current write session started (`begin_session`)
+    pub start_time: Option<std::time::Instant>,
+    /// Rolling write rate in bytes/s, resampled at most once per second
+    pub bytes_per_sec: Option<f64>,
+    /// `current_bytes_processed` the session is expected to reach (None when unknown, e.g. stdin)
+    pub total_bytes: Option<u64>,
+    /// Last rate sample: time and `current_bytes_processed` at that time
+    rate_sample: Option<(std::time::Instant, u64)>,
+}
+
+/// Minimum time between two samples of the rolling write rate
+const RATE_SAMPLE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+/// Weight of the newest sample in the rolling write rate
+const RATE_SMOOTHING: f64 = 0.3;
+
+impl WriteProgress {
+    /// Start timing a session that will process `session_bytes` more bytes (None if unknown)
+    pub fn begin_session(&mut self, session_bytes: Option<u64>) {
+        let now = std::time::Instant::now();
+        self.start_time = Some(now);
+        self.bytes_per_sec = None;
+        self.total_bytes = session_bytes.map(|bytes| self.current_bytes_processed + bytes);
+        self.rate_sample = Some((now, self.current_bytes_processed));
+    }
+
+    /// Drop `bytes` of a skipped source file from the expected total
+    pub(crate) fn skip_bytes(&mut self, bytes: u64) {
+        if let Some(total) = self.total_bytes.as_mut() {
+            *total = total.saturating_sub(bytes);
+        }
+    }
+
+    /// Fold the bytes processed since the last sample into the rolling rate
+    pub(crate) fn sample_rate(&mut self) {
+        let now = std::time::Instant::now();
+        let (since, bytes) = *self
+            .rate_sample
+            .get_or_insert((now, self.current_bytes_processed));
+        let elapsed = now.duration_since(since);
+        if elapsed < RATE_SAMPLE_INTERVAL {
+            return;
+        }
+
+        let rate = self.current_bytes_processed.saturating_sub(bytes) as f64 / elapsed.as_secs_f64();
+        self.bytes_per_sec = Some(match self.bytes_per_sec {
+            Some(previous) => previous + (rate - previous) * RATE_SMOOTHING,
+            None => rate,
+        });
+        self.rate_sample = Some((now, self.current_bytes_processed));
+    }
+
+    /// Estimated time to reach `total_bytes` at the rolling rate
+    pub fn eta(&self) -> Option<std::time::Duration> {
+        let total = self.total_bytes?;
+        let rate = self.bytes_per_sec.filter(|rate| *rate > 0.0)?;
+        let remaining = total.saturating_sub(self.current_bytes_processed);
+        Some(std::time::Duration::from_secs_f64(remaining as f64 / rate))
+    }
+}
+
+/// Progress callback for writes, called after every block written
+pub type WriteProgressCallback = std::sync::Arc<dyn Fn(&WriteProgress) + Send + Sync>;
+
+/// Summary of a directory write session (returned by `write_directory_to_tape`)
+#[derive(Debug, Clone, Default)]
+pub struct WriteSessionResult {
+    pub files_written: u64,
+    pub bytes_written: u64,
+    pub files_skipped: u64,
+    /// Files that failed in continue-on-error mode, with the error message
+    pub files_failed: Vec<(std::path::PathBuf, String)>,
+    /// Tape position after the session (None if it could not be read)
+    pub final_position: Option<crate::scsi::TapePosition>,
+    /// Index generation after the session (None if no index is loaded)
+    pub index_generation: Option<u64>,
+}
+
+/// Result of a dry-run write (`plan_write`): what would be written and whether it fits
+#[derive(Debug, Clone, Default)]
+pub struct DryRunReport {
+    /// Session result of the simulated walk (files planned, skipped, failed)
+    pub session: WriteSessionResult,
+    /// Data blocks plus file marks the planned files would occupy
+    pub blocks_planned: u64,
+    /// Bytes the planned files would occupy on tape (whole blocks)
+    pub tape_bytes_planned: u64,
+    /// Remaining capacity of the data partition (None if it could not be read)
+    pub remaining_capacity: Option<u64>,
+}
+
+impl DryRunReport {
+    /// Whether the planned data fits in the remaining capacity (None if unknown)
+    pub fn fits(&self) -> Option<bool> {
+        self.remaining_capacity
+            .map(|remaining| self.tape_bytes_planned <= remaining)
+    }
+}
+
+/// Index recovered from the copies written to the data partition (`recover_index`)
+#[derive(Debug, Clone)]
+pub struct RecoveredIndex {
+    /// Newest valid index copy (highest generation number)
+    pub index: crate::ltfs_index::LtfsIndex,
+    /// XML exactly as read from tape
+    pub xml: String,
+    /// Number of valid index copies seen during the scan
+    pub candidates: usize,
+    /// Partition and file mark after which the chosen copy was found
+    pub partition: u8,
+    pub file_mark: u64,
+    /// Located through the partition's Volume Coherency Information rather than a scan
+    pub from_vci: bool,
+}
+
+/// Entry of a recursive listing (`list_recursive`)
+#[derive(Debug, Clone)]
+pub struct FileInfo {
+    pub is_directory: bool,
+    /// File length in bytes (0 for directories)
+    pub size: u64,
+    pub uid: u64,
+    pub modify_time: String,
+}
+
+/// Extraction progress snapshot, passed to the extract progress callback after each file
+#[derive(Debug, Clone, Default)]
+pub struct ExtractProgress {
+    pub files_done: u64,
+    pub bytes_done: u64,
+    /// Totals for the extracted subtree, taken from the index
+    pub total_files: u64,
+    pub total_bytes: u64,
+    /// Tape path of the file just extracted
+    pub current_file: String,
+}
+
+/// Progress callback for `extract_from_tape`
+pub type ExtractProgressCallback = std::sync::Arc<dyn Fn(&ExtractProgress) + Send + Sync>;
+
+/// Summary of an extraction (returned by `extract_from_tape`)
+#[derive(Debug, Clone, Default)]
+pub struct ExtractionResult {
+    pub files_extracted: u64,
+    pub bytes_extracted: u64,
+    pub directories_created: u64,
+    /// Files that could not be extracted, with the error message
+    pub files_failed: Vec<(String, String)>,
+    /// Split files (span manifest) with the pieces held by other cartridges
+    pub split_files: Vec<(String, Vec<(String, span::SpanFile)>)>,
 }
 
 /// Write options configuration (Enhanced for LTFSCopyGUI compatibility)
@@ -86,11 +268,21 @@ pub struct WriteOptions {
     pub verify: bool,
     pub hash_on_write: bool,
     pub skip_symlinks: bool,
+    /// Directory levels below the source to descend into (`--max-depth`); None is unlimited
+    pub max_depth: Option<usize>,
+    /// Write what symlinks point to instead of storing the links (`--follow-symlinks`)
+    pub follow_symlinks: bool,
 
-    pub index_write_interval: u64, // bytes
+    pub index_write_interval: u64, // bytes, u64::MAX disables the size trigger
+    /// Also write an index once this much time has passed since the last one
+    pub index_write_interval_time: Option<std::time::Duration>,
 
 
     pub block_size: u32,
+    /// `block_size` was given by the user (`--block-size`) instead of taken from the tape
+    pub block_size_explicit: bool,
+    /// Append with an explicit block size even when it differs from the tape's LTFS label (`--force`)
+    pub force_block_size: bool,
 
 
     // New LTFSCopyGUI compatible options
@@ -106,6 +298,39 @@ pub struct WriteOptions {
     pub hash_xxhash3_enabled: bool,
     pub hash_xxhash128_enabled: bool,
 
+    /// Read every index back from tape after writing it and check its generation (`--append-only-verify`)
+    pub verify_index: bool,
+
+    /// Start writing at this block of the data partition instead of at its EOD.
+    /// For experts recovering a tape: everything after the block is overwritten, and the
+    /// block must still lie after the latest index
+    pub data_start_block_override: Option<u64>,
+
+    /// Walk the source and build the would-be index without issuing any SCSI write
+    pub dry_run: bool,
+
+    /// Write more than `CLEANING_REQUIRED_WRITE_LIMIT` even though the drive requests cleaning (`--ignore-cleaning`)
+    pub ignore_cleaning: bool,
+
+    /// Hardware compression (DCE) while writing; `--no-compression` clears it
+    pub compression: bool,
+
+    /// Logical Block Protection while writing (`--lbp`): the drive checks a CRC on every block
+    pub logical_block_protection: crate::scsi::LbpMethod,
+
+    /// Skip holes of sparse source files instead of writing zeros; `--no-sparse` clears it
+    pub sparse: bool,
+
+    /// Source paths to skip (`--exclude`), matched against the path relative to the source
+    pub excluded_patterns: Vec<glob::Pattern>,
+
+    /// Files of a directory write read and hashed ahead of the one going to tape
+    pub read_ahead_files: usize,
+    /// Cap on source data buffered by read-ahead, in bytes
+    pub read_ahead_bytes: u64,
+    /// Files of one directory queued at once; a larger directory is read once per batch,
+    /// and its files still reach tape in name order across batches
+    pub max_queued_files: usize,
 }
 
 impl Default for WriteOptions {
@@ -115,11 +340,16 @@ impl Default for WriteOptions {
             verify: false,
             hash_on_write: true,
             skip_symlinks: false,
+            max_depth: None,
+            follow_symlinks: false,
 
             index_write_interval: 38_654_705_664, // 36GiB (matching LTFSCopyGUI)
+            index_write_interval_time: None,
 
 
             block_size: crate::scsi::block_sizes::LTO_BLOCK_SIZE_512K,  // 512KB (LTFSCopyGUI standard)
+            block_size_explicit: false,
+            force_block_size: false,
 
 
             // LTFSCopyGUI compatible defaults
@@ -135,10 +365,52 @@ impl Default for WriteOptions {
             hash_xxhash3_enabled: false,
             hash_xxhash128_enabled: false,
 
+            verify_index: false,
+            data_start_block_override: None,
+
+            dry_run: false,
+            ignore_cleaning: false,
+            compression: true,
+            logical_block_protection: crate::scsi::LbpMethod::None,
+            sparse: true,
+            excluded_patterns: Vec::new(),
+            read_ahead_files: 4,
+            read_ahead_bytes: 256 * 1024 * 1024,
+            max_queued_files: 100_000,
         }
     }
 }
 
+impl WriteOptions {
+    /// Whether a source path (relative to the written source) matches an exclude pattern
+    pub fn is_excluded(&self, relative_path: &std::path::Path) -> bool {
+        self.excluded_patterns
+            .iter()
+            .any(|pattern| pattern.matches_path(relative_path))
+    }
+
+    /// Read exclude patterns from a file (`--exclude-from`): one glob per line,
+    /// blank lines and lines starting with `#` are ignored
+    pub fn load_exclude_patterns(path: &std::path::Path) -> crate::error::Result<Vec<glob::Pattern>> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            crate::error::RustLtfsError::file_operation(format!("Cannot read exclude file {:?}: {}", path, e))
+        })?;
+        content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                glob::Pattern::new(line).map_err(|e| {
+                    crate::error::RustLtfsError::parameter_validation(format!(
+                        "Invalid exclude pattern '{}' in {:?}: {}",
+                        line, path, e
+                    ))
+                })
+            })
+            .collect()
+    }
+}
+
 
 
 
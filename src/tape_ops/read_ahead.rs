@@ -0,0 +1,143 @@
+//! Read-ahead for tape writes
+//!
+//! The drive takes blocks strictly in order, so only reading and hashing the source
+//! can overlap with the tape. Each file gets a blocking reader task that reads whole
+//! blocks, feeds them to the hash calculator and hands them to the tape writer through
+//! a bounded channel. Directory writes start the tasks of the next few files while the
+//! current one goes to tape, so small files are already hashed when their turn comes.
+
+use super::hash::CheckSumBlockwiseCalculator;
+use crate::error::{Result, RustLtfsError};
+use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use tokio::sync::mpsc;
+use tracing::debug;
+
+/// 读取任务交给写入方的内容
+enum Chunk {
+    /// 一个块的数据；只有最后一块可能短于块大小
+    Block(Vec<u8>),
+    /// 文件读完，附带已启用算法的哈希（未计算哈希时为None）
+    End(Option<HashMap<String, String>>),
+}
+
+/// 一个正在预读（并计算哈希）的源文件
+pub(crate) struct ReadAheadFile {
+    path: PathBuf,
+    receiver: mpsc::Receiver<Result<Chunk>>,
+    hashes: Option<HashMap<String, String>>,
+}
+
+impl ReadAheadFile {
+    /// 启动读取任务：从 `offset` 开始按 `block_size` 读取，最多缓冲 `capacity` 个块
+    ///
+    /// 给出 `calculator` 时每块都在读取任务中计算哈希。写入方丢弃本对象即停止读取。
+    pub(crate) fn start(
+        path: &Path,
+        offset: u64,
+        block_size: usize,
+        capacity: usize,
+        calculator: Option<CheckSumBlockwiseCalculator>,
+    ) -> Self {
+        let (sender, receiver) = mpsc::channel(capacity.max(1));
+        let source = path.to_path_buf();
+        tokio::task::spawn_blocking(move || {
+            if let Err(e) = read_blocks(&source, offset, block_size, calculator, &sender) {
+                let _ = sender.blocking_send(Err(e));
+            }
+        });
+
+        Self {
+            path: path.to_path_buf(),
+            receiver,
+            hashes: None,
+        }
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 下一个块；文件结束时返回None，此后 `hashes` 可用
+    pub(crate) async fn next_block(&mut self) -> Result<Option<Vec<u8>>> {
+        match self.receiver.recv().await {
+            Some(Ok(Chunk::Block(block))) => Ok(Some(block)),
+            Some(Ok(Chunk::End(hashes))) => {
+                self.hashes = hashes;
+                Ok(None)
+            }
+            Some(Err(e)) => Err(e),
+            None => Err(RustLtfsError::file_operation(format!(
+                "Read-ahead of {:?} stopped unexpectedly",
+                self.path
+            ))),
+        }
+    }
+
+    /// 文件读完后的哈希（未计算哈希时为None）
+    pub(crate) fn take_hashes(&mut self) -> Option<HashMap<String, String>> {
+        self.hashes.take()
+    }
+}
+
+/// 读取任务主体：写入方已丢弃接收端时提前结束
+fn read_blocks(
+    path: &Path,
+    offset: u64,
+    block_size: usize,
+    mut calculator: Option<CheckSumBlockwiseCalculator>,
+    sender: &mpsc::Sender<Result<Chunk>>,
+) -> Result<()> {
+    let mut file = std::fs::File::open(path)
+        .map_err(|e| RustLtfsError::file_operation(format!("Unable to open file: {}", e)))?;
+    if offset > 0 {
+        file.seek(SeekFrom::Start(offset))?;
+    }
+
+    loop {
+        let mut block = vec![0u8; block_size];
+        let filled = read_full_block(&mut file, &mut block)?;
+        if filled == 0 {
+            break;
+        }
+        block.truncate(filled);
+
+        // Holes hash as the zeros they read back as
+        if let Some(ref mut calc) = calculator {
+            calc.propagate(&block);
+        }
+        if sender.blocking_send(Ok(Chunk::Block(block))).is_err() {
+            debug!("Read-ahead of {:?} abandoned by the writer", path);
+            return Ok(());
+        }
+        if filled < block_size {
+            break; // Short block is always the last one
+        }
+    }
+
+    let hashes = calculator.map(|mut calc| {
+        calc.process_final_block();
+        calc.get_enabled_hashes()
+    });
+    let _ = sender.blocking_send(Ok(Chunk::End(hashes)));
+    Ok(())
+}
+
+/// 从reader读取一个完整的块（只有在文件结尾时才会返回不满一块的长度）
+///
+/// `Read::read` 可能返回少于请求的字节数，直接写入会在文件中间产生短块，
+/// 导致extent中的块与文件偏移对不上。
+fn read_full_block(reader: &mut impl Read, buffer: &mut [u8]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        let n = reader.read(&mut buffer[filled..]).map_err(|e| {
+            RustLtfsError::file_operation(format!("Failed to read file: {}", e))
+        })?;
+        if n == 0 {
+            break;
+        }
+        filled += n;
+    }
+    Ok(filled)
+}
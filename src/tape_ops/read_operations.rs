@@ -1,11 +1,21 @@
-use crate::error::Result;
-use super::PartitionStrategy;
+use crate::error::{Result, RustLtfsError};
+use crate::ltfs_index::{Directory, File, FileExtent, IndexEntry};
+use super::{ExtractProgress, ExtractProgressCallback, ExtractionResult, PartitionStrategy};
 use super::volume;
+use std::io::Write;
+use std::path::Path;
 use tracing::debug;
 
 // LtfsPartitionLabel 在 format_operations.rs 中定义
 // 通过模块重新导出使用
 
+/// 提取过程中累积的结果、进度和回调
+struct ExtractContext {
+    result: ExtractionResult,
+    state: ExtractProgress,
+    progress: Option<ExtractProgressCallback>,
+}
+
 /// TapeOperations读取操作实现
 impl super::TapeOperations {
     /// 检测分区策略 - 修复版本：直接使用已打开的SCSI设备
@@ -46,4 +56,807 @@ impl super::TapeOperations {
     pub fn parse_vol1_label(&self, buffer: &[u8]) -> Result<bool> {
         volume::parse_vol1_label(buffer)
     }
+
+    /// 读取完整文件内容（对应LTFSCopyGUI的文件提取逻辑）
+    ///
+    /// 结果精确截断为 `file.length`：块填充的尾部零字节不会出现在输出中，
+    /// 未被任何extent覆盖的区域按零填充。
+    pub fn read_complete_file_from_tape(&self, file: &File) -> Result<Vec<u8>> {
+        self.read_file_prefix_from_tape(file, file.length)
+    }
+
+    /// 将单个文件提取到本地路径；符号链接按其链接目标重新创建
+    ///
+    /// 索引中带命名空间的扩展属性（`user.*` 等）会设置到提取出的文件上。
+    /// 返回写入的字节数（符号链接为0）。
+    pub fn extract_single_file(&self, file: &File, dest: &Path) -> Result<u64> {
+        if let Some(ref link_target) = file.symlink {
+            debug!("Recreating symlink {:?} -> {}", dest, link_target);
+            create_symlink(link_target, dest).map_err(|e| {
+                RustLtfsError::file_operation(format!(
+                    "Unable to create symlink {:?} -> {}: {}",
+                    dest, link_target, e
+                ))
+            })?;
+            return Ok(0);
+        }
+
+        let out = std::fs::File::create(dest).map_err(|e| {
+            RustLtfsError::file_operation(format!("Unable to create file {:?}: {}", dest, e))
+        })?;
+
+        let written = if super::sparse::has_holes(file) {
+            self.extract_sparse_file(file, out, dest)?
+        } else {
+            let mut writer = std::io::BufWriter::new(out);
+            let written = self.stream_file_from_tape(file, |chunk| {
+                writer.write_all(chunk)?;
+                Ok(())
+            })?;
+            writer.flush()?;
+
+            debug!("Extracted '{}' to {:?} ({} bytes)", file.name, dest, written);
+            written
+        };
+
+        super::xattr::restore_xattrs(file, dest);
+        Ok(written)
+    }
+
+    /// 提取extent之间留有空洞的文件：只在各extent的file_offset处写入数据，
+    /// 再用set_len补足文件长度，空洞在本地重新成为稀疏区域
+    fn extract_sparse_file(&self, file: &File, out: std::fs::File, dest: &Path) -> Result<u64> {
+        debug!(
+            "Extracting sparse file '{}' ({} bytes in {} extents)",
+            file.name,
+            file.length,
+            file.extent_info.extents.len()
+        );
+
+        let out = self.write_extents_at_offsets(file, out)?;
+        out.set_len(file.length)?;
+
+        debug!("Extracted sparse '{}' to {:?} ({} bytes)", file.name, dest, file.length);
+        Ok(file.length)
+    }
+
+    /// 提取跨磁带分割文件在本盘磁带上的片段：不截断已有文件（其他磁带的片段可能已提取），
+    /// 只写入本片段的extent，文件长度至少补足到完整大小
+    fn extract_span_piece(&self, file: &File, dest: &Path, piece: &super::span::SpanFile) -> Result<u64> {
+        let out = std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(dest)
+            .map_err(|e| {
+                RustLtfsError::file_operation(format!("Unable to open file {:?}: {}", dest, e))
+            })?;
+        debug!(
+            "Extracting bytes {}-{} of split file '{}' to {:?}",
+            piece.offset,
+            piece.end(),
+            file.name,
+            dest
+        );
+
+        let out = self.write_extents_at_offsets(file, out)?;
+        if out.metadata()?.len() < piece.total_size {
+            out.set_len(piece.total_size)?;
+        }
+        Ok(piece.length)
+    }
+
+    /// 将文件的每个extent写到 `out` 中对应的file_offset处（超出文件长度的部分忽略）
+    fn write_extents_at_offsets(&self, file: &File, out: std::fs::File) -> Result<std::fs::File> {
+        use std::io::{Seek, SeekFrom};
+
+        let mut extents: Vec<&FileExtent> = file.extent_info.extents.iter().collect();
+        extents.sort_by_key(|extent| extent.file_offset);
+
+        let mut writer = std::io::BufWriter::new(out);
+        for extent in extents {
+            if extent.file_offset >= file.length {
+                break;
+            }
+            let wanted = std::cmp::min(extent.byte_count, file.length - extent.file_offset);
+            writer.seek(SeekFrom::Start(extent.file_offset))?;
+            self.locate_for_read(self.extent_partition(extent), extent.start_block)?;
+            self.stream_extent_blocks(extent, wanted, |chunk| {
+                writer.write_all(chunk)?;
+                Ok(())
+            })?;
+        }
+
+        writer
+            .into_inner()
+            .map_err(|e| RustLtfsError::from(e.into_error()))
+    }
+
+    /// 将磁带上的目录或文件提取到本地 `dest`
+    ///
+    /// 目录的内容提取到 `dest` 下；单个文件提取为 `dest`（`dest` 为已存在目录时放入其中）。
+    /// 每提取完一个文件调用一次 `progress`；单个文件失败不会中止整个提取，取消则立即返回。
+    pub fn extract_from_tape(
+        &self,
+        tape_path: &str,
+        dest: &Path,
+        progress: Option<ExtractProgressCallback>,
+    ) -> Result<ExtractionResult> {
+        let index = self
+            .index
+            .as_ref()
+            .ok_or_else(|| RustLtfsError::ltfs_index("No index loaded".to_string()))?;
+
+        let mut ctx = ExtractContext {
+            result: ExtractionResult::default(),
+            state: ExtractProgress::default(),
+            progress,
+        };
+
+        let (path, entry) = index
+            .find_path(tape_path, self.path_normalize)
+            .ok_or_else(|| RustLtfsError::ltfs_index(format!("Path not found on tape: {}", tape_path)))?;
+
+        match entry {
+            IndexEntry::Directory(dir) => {
+                let (total_files, total_bytes) = count_directory_totals(dir);
+                ctx.state.total_files = total_files;
+                ctx.state.total_bytes = total_bytes;
+                debug!(
+                    "Extracting {} ({} files, {} bytes) to {:?}",
+                    path, total_files, total_bytes, dest
+                );
+
+                self.extract_directory(dir, dest, path.trim_end_matches('/'), 0, &mut ctx)?;
+            }
+            IndexEntry::File(file) => {
+                let target = if dest.is_dir() { dest.join(crate::ltfs_index::name::name_to_os_string(&file.name)) } else { dest.to_path_buf() };
+                ctx.state.total_files = 1;
+                ctx.state.total_bytes = file.length;
+                self.extract_file_with_progress(file, &target, &path, &mut ctx)?;
+            }
+        }
+        Ok(ctx.result)
+    }
+
+    /// 递归提取目录内容（深度超过 `max_extract_depth` 时报错，防止损坏索引导致失控递归）
+    fn extract_directory(
+        &self,
+        dir: &Directory,
+        dest: &Path,
+        tape_prefix: &str,
+        depth: usize,
+        ctx: &mut ExtractContext,
+    ) -> Result<()> {
+        if depth > self.max_extract_depth {
+            return Err(RustLtfsError::ltfs_index(format!(
+                "Directory nesting at {} exceeds the maximum extraction depth of {}",
+                tape_prefix, self.max_extract_depth
+            )));
+        }
+
+        if !dest.exists() {
+            std::fs::create_dir_all(dest).map_err(|e| {
+                RustLtfsError::file_operation(format!("Unable to create directory {:?}: {}", dest, e))
+            })?;
+            ctx.result.directories_created += 1;
+        }
+
+        for file in &dir.contents.files {
+            let tape_file_path = format!("{}/{}", tape_prefix, file.name);
+            self.extract_file_with_progress(file, &dest.join(crate::ltfs_index::name::name_to_os_string(&file.name)), &tape_file_path, ctx)?;
+        }
+
+        for subdir in &dir.contents.directories {
+            self.extract_directory(
+                subdir,
+                &dest.join(crate::ltfs_index::name::name_to_os_string(&subdir.name)),
+                &format!("{}/{}", tape_prefix, subdir.name),
+                depth + 1,
+                ctx,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// 提取单个文件并更新统计与进度回调；只有取消会作为错误返回
+    fn extract_file_with_progress(
+        &self,
+        file: &File,
+        target: &Path,
+        tape_file_path: &str,
+        ctx: &mut ExtractContext,
+    ) -> Result<()> {
+        self.check_cancelled(&format!("before extracting {}", tape_file_path))?;
+
+        // 跨磁带分割的文件只提取本盘磁带上的片段
+        let piece = self.span_piece(tape_file_path);
+        let result = match piece {
+            Some(ref piece) => self.extract_span_piece(file, target, piece),
+            None => self.extract_single_file(file, target),
+        };
+
+        match result {
+            Ok(written) => {
+                ctx.result.files_extracted += 1;
+                ctx.result.bytes_extracted += written;
+                ctx.state.bytes_done += written;
+                if piece.is_some() {
+                    let others = self.span_other_pieces(tape_file_path);
+                    ctx.result.split_files.push((tape_file_path.to_string(), others));
+                }
+            }
+            Err(e) if e.is_cancelled() => {
+                // 不留下不完整的文件（分割文件可能含有其他磁带的片段，保留）
+                if piece.is_none() {
+                    let _ = std::fs::remove_file(target);
+                }
+                return Err(e);
+            }
+            Err(e) => {
+                debug!("Failed to extract {}: {}", tape_file_path, e);
+                ctx.result.files_failed.push((tape_file_path.to_string(), e.to_string()));
+            }
+        }
+
+        ctx.state.files_done += 1;
+        ctx.state.current_file = tape_file_path.to_string();
+        if let Some(ref callback) = ctx.progress {
+            callback(&ctx.state);
+        }
+        Ok(())
+    }
+
+    /// 读取文件开头至多 `max_bytes` 字节（用于预览），同样精确截断，不含块填充
+    pub fn read_file_content_from_tape(&self, file: &File, max_bytes: u64) -> Result<Vec<u8>> {
+        self.read_file_prefix_from_tape(file, std::cmp::min(max_bytes, file.length))
+    }
+
+    /// 读取文件的前 `length` 字节，按extent的file_offset组装
+    fn read_file_prefix_from_tape(&self, file: &File, length: u64) -> Result<Vec<u8>> {
+        debug!("Reading {} of {} bytes from file '{}'", length, file.length, file.name);
+
+        let mut result = Vec::with_capacity(length as usize);
+        self.stream_file_range_from_tape(file, length, |chunk| {
+            result.extend_from_slice(chunk);
+            Ok(())
+        })?;
+        result.resize(length as usize, 0);
+        Ok(result)
+    }
+
+    /// 按fileuid读取文件中 `[offset, offset + len)` 范围的字节，超出文件长度的部分被截掉
+    ///
+    /// 只LOCATE到覆盖该范围的extent中所需的第一个块，并只读取需要的块；
+    /// 不被任何extent覆盖的空洞按零填充。
+    pub fn read_file_range(&self, file_uid: u64, offset: u64, len: u64) -> Result<Vec<u8>> {
+        let index = self
+            .index
+            .as_ref()
+            .ok_or_else(|| RustLtfsError::ltfs_index("No index loaded".to_string()))?;
+        let file = index.find_file_by_uid(file_uid).ok_or_else(|| {
+            RustLtfsError::ltfs_index(format!("No file with UID {} in index", file_uid))
+        })?;
+        if file.symlink.is_some() {
+            return Err(RustLtfsError::file_operation(format!(
+                "'{}' is a symlink and has no data",
+                file.name
+            )));
+        }
+
+        let end = std::cmp::min(offset.saturating_add(len), file.length);
+        if offset >= end {
+            return Ok(Vec::new());
+        }
+        debug!(
+            "Reading bytes {}..{} of '{}' (UID {}, {} bytes)",
+            offset, end, file.name, file_uid, file.length
+        );
+
+        let block_size = self.data_block_size() as u64;
+        let mut result = vec![0u8; (end - offset) as usize];
+        let mut extents: Vec<&FileExtent> = file.extent_info.extents.iter().collect();
+        extents.sort_by_key(|extent| extent.file_offset);
+
+        for extent in extents {
+            let extent_end = extent.file_offset + extent.byte_count;
+            let start = std::cmp::max(offset, extent.file_offset);
+            let stop = std::cmp::min(end, extent_end);
+            if start >= stop {
+                continue;
+            }
+
+            // 把所需片段表示为从所在块开始的子extent，复用按块读取的逻辑
+            let position = extent.byte_offset + (start - extent.file_offset);
+            let slice = FileExtent {
+                partition: extent.partition.clone(),
+                start_block: extent.start_block + position / block_size,
+                byte_count: stop - start,
+                file_offset: start,
+                byte_offset: position % block_size,
+            };
+
+            let mut cursor = (start - offset) as usize;
+            self.locate_for_read(self.extent_partition(&slice), slice.start_block)?;
+            self.stream_extent_blocks(&slice, slice.byte_count, |chunk| {
+                result[cursor..cursor + chunk.len()].copy_from_slice(chunk);
+                cursor += chunk.len();
+                Ok(())
+            })?;
+        }
+
+        Ok(result)
+    }
+
+    /// 读取单个extent的前 `byte_count` 字节（从extent的byte_offset开始）
+    ///
+    /// 按块读取，每块只取实际需要的字节数，因此最后一个不满块的零填充会被丢弃。
+    pub fn read_extent_from_tape(&self, extent: &FileExtent, byte_count: u64) -> Result<Vec<u8>> {
+        let mut data = Vec::with_capacity(std::cmp::min(byte_count, extent.byte_count) as usize);
+        self.locate_for_read(self.extent_partition(extent), extent.start_block)?;
+        self.stream_extent_blocks(extent, byte_count, |chunk| {
+            data.extend_from_slice(chunk);
+            Ok(())
+        })?;
+        Ok(data)
+    }
+
+    /// 流式读取完整文件内容，逐块交给 `sink` 处理，不在内存中缓存整个文件
+    ///
+    /// 与 `read_complete_file_from_tape` 输出相同的字节序列（extent之间的空洞按零填充），
+    /// 返回写入 `sink` 的总字节数。
+    pub fn stream_file_from_tape<F>(&self, file: &File, sink: F) -> Result<u64>
+    where
+        F: FnMut(&[u8]) -> Result<()>,
+    {
+        self.stream_file_range_from_tape(file, file.length, sink)
+    }
+
+    /// 流式读取文件的前 `length` 字节
+    ///
+    /// 磁带上首尾相接的extent会合并为一次连续读取，只在每组开头执行一次LOCATE。
+    fn stream_file_range_from_tape<F>(&self, file: &File, length: u64, mut sink: F) -> Result<u64>
+    where
+        F: FnMut(&[u8]) -> Result<()>,
+    {
+        let length = std::cmp::min(length, file.length);
+        if length == 0 || file.symlink.is_some() {
+            return Ok(0);
+        }
+
+        let mut extents: Vec<&FileExtent> = file.extent_info.extents.iter().collect();
+        extents.sort_by_key(|extent| extent.file_offset);
+        let runs = coalesce_extents(&extents, self.data_block_size() as u64);
+
+        debug!(
+            "Reading {} extents of '{}' in {} sequential run(s)",
+            extents.len(),
+            file.name,
+            runs.len()
+        );
+
+        let zeros = vec![0u8; 64 * 1024];
+        let mut emitted = 0u64;
+        let fill_zeros = |sink: &mut F, emitted: &mut u64, until: u64| -> Result<()> {
+            while *emitted < until {
+                let take = std::cmp::min(zeros.len() as u64, until - *emitted) as usize;
+                sink(&zeros[..take])?;
+                *emitted += take as u64;
+            }
+            Ok(())
+        };
+
+        'runs: for run in runs {
+            let first = run[0];
+            if first.file_offset >= length {
+                break;
+            }
+            self.locate_for_read(self.extent_partition(first), first.start_block)?;
+
+            for extent in run {
+                if extent.file_offset >= length {
+                    break 'runs;
+                }
+
+                fill_zeros(&mut sink, &mut emitted, extent.file_offset)?;
+
+                let wanted = std::cmp::min(extent.byte_count, length - extent.file_offset);
+                // 跳过与已输出区域重叠的部分
+                let mut skip = emitted.saturating_sub(extent.file_offset);
+                self.stream_extent_blocks(extent, wanted, |chunk| {
+                    let start = std::cmp::min(skip, chunk.len() as u64) as usize;
+                    skip -= start as u64;
+                    if start < chunk.len() {
+                        sink(&chunk[start..])?;
+                        emitted += (chunk.len() - start) as u64;
+                    }
+                    Ok(())
+                })?;
+            }
+        }
+
+        fill_zeros(&mut sink, &mut emitted, length)?;
+        Ok(emitted)
+    }
+
+    /// 当前数据块大小（来自分区标签，缺省64KB）
+    pub(crate) fn data_block_size(&self) -> usize {
+        self.partition_label
+            .as_ref()
+            .map(|plabel| plabel.blocksize as usize)
+            .unwrap_or(crate::scsi::block_sizes::LTO_BLOCK_SIZE as usize)
+    }
+
+    /// 从当前磁带位置按块读取extent数据并交给 `sink`（调用方负责先定位到extent起始块）
+    ///
+    /// 跳过byte_offset并丢弃末块的零填充；读取完整extent后磁带停在其后的第一个块。
+    /// 成功时更新位置缓存，出错时使其失效。
+    fn stream_extent_blocks<F>(&self, extent: &FileExtent, byte_count: u64, sink: F) -> Result<()>
+    where
+        F: FnMut(&[u8]) -> Result<()>,
+    {
+        let partition = self.extent_partition(extent);
+        self.invalidate_position_cache();
+        let blocks_read = self.read_extent_blocks(extent, byte_count, sink)?;
+        self.set_cached_position(partition, extent.start_block + blocks_read);
+        Ok(())
+    }
+
+    /// stream_extent_blocks 的实际读取循环，返回读取的块数
+    fn read_extent_blocks<F>(&self, extent: &FileExtent, byte_count: u64, mut sink: F) -> Result<u64>
+    where
+        F: FnMut(&[u8]) -> Result<()>,
+    {
+        let block_size = self.data_block_size();
+        let byte_count = std::cmp::min(byte_count, extent.byte_count);
+
+        debug!(
+            "Reading extent: partition {} block {} byte_offset {} ({} bytes)",
+            extent.partition, extent.start_block, extent.byte_offset, byte_count
+        );
+
+        let total = extent.byte_offset + byte_count;
+        let mut consumed = 0u64;
+        let mut blocks = 0u64;
+        let mut buffer = vec![0u8; block_size];
+
+        while consumed < total {
+            if let Err(e) = self.check_cancelled(&format!(
+                "after {} of {} bytes of extent at block {}",
+                consumed, total, extent.start_block
+            )) {
+                // 停在已读取的最后一个块之后，位置已知
+                self.set_cached_position(self.extent_partition(extent), extent.start_block + blocks);
+                return Err(e);
+            }
+
+            buffer.fill(0);
+            let blocks_read = self.scsi.read_blocks(1, &mut buffer)?;
+            if blocks_read == 0 {
+                return Err(RustLtfsError::tape_device(format!(
+                    "Unexpected file mark after {} of {} bytes in extent at block {}",
+                    consumed, total, extent.start_block
+                )));
+            }
+            blocks += 1;
+
+            let take = std::cmp::min(block_size as u64, total - consumed) as usize;
+            let skip = extent.byte_offset.saturating_sub(consumed).min(take as u64) as usize;
+            if skip < take {
+                sink(&buffer[skip..take])?;
+            }
+            consumed += take as u64;
+        }
+
+        Ok(blocks)
+    }
+
+    /// 将extent中的分区标识（a/b）映射为物理分区号
+    pub(crate) fn extent_partition(&self, extent: &FileExtent) -> u8 {
+        let logical_partition = if extent.partition.eq_ignore_ascii_case("a") {
+            0
+        } else {
+            1
+        };
+        self.get_target_partition(logical_partition)
+    }
+}
+
+/// 统计目录树下的文件数和总字节数（用于进度显示）
+fn count_directory_totals(dir: &Directory) -> (u64, u64) {
+    let mut files = dir.contents.files.len() as u64;
+    let mut bytes: u64 = dir.contents.files.iter().map(|f| f.length).sum();
+    for subdir in &dir.contents.directories {
+        let (sub_files, sub_bytes) = count_directory_totals(subdir);
+        files += sub_files;
+        bytes += sub_bytes;
+    }
+    (files, bytes)
+}
+
+/// 将按file_offset排序的extent分组：同一分区、在磁带上首尾相接且在文件中连续的extent归为一组
+///
+/// 组内后一个extent必须从块起始处开始（byte_offset为0），且起始块恰好位于前一个extent所占块之后。
+fn coalesce_extents<'a>(extents: &[&'a FileExtent], block_size: u64) -> Vec<Vec<&'a FileExtent>> {
+    let mut runs: Vec<Vec<&'a FileExtent>> = Vec::new();
+
+    for &extent in extents {
+        if let Some(run) = runs.last_mut() {
+            let prev = run[run.len() - 1];
+            let prev_blocks = (prev.byte_offset + prev.byte_count).div_ceil(block_size);
+            let sequential = prev.partition.eq_ignore_ascii_case(&extent.partition)
+                && extent.byte_offset == 0
+                && extent.file_offset == prev.file_offset + prev.byte_count
+                && extent.start_block == prev.start_block + prev_blocks;
+            if sequential {
+                run.push(extent);
+                continue;
+            }
+        }
+        runs.push(vec![extent]);
+    }
+
+    runs
+}
+
+/// 创建指向 `target` 的符号链接
+#[cfg(unix)]
+fn create_symlink(target: &str, link: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+/// 创建指向 `target` 的符号链接
+#[cfg(windows)]
+fn create_symlink(target: &str, link: &Path) -> std::io::Result<()> {
+    std::os::windows::fs::symlink_file(target, link)
+}
+
+/// 创建指向 `target` 的符号链接
+#[cfg(not(any(unix, windows)))]
+fn create_symlink(_target: &str, _link: &Path) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "symlinks are not supported on this platform",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::coalesce_extents;
+    use crate::ltfs_index::{File, FileExtent};
+    use crate::scsi::types::{LocateDestType, RawBlock};
+    use crate::scsi::{SpaceType, TapeBackend, TapePosition};
+    use crate::tape_ops::TapeOperations;
+    use crate::virtual_tape::{VirtualPartition, VirtualTape};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+
+    /// 不保存数据的磁带：每次READ都返回一个满块（内容为调用方清零后的缓冲区），只统计命令
+    #[derive(Default)]
+    struct CountingTape {
+        reads: AtomicU64,
+        max_transfer: AtomicU64,
+    }
+
+    impl TapeBackend for Arc<CountingTape> {
+        fn read_position(&self) -> crate::Result<TapePosition> {
+            Ok(TapePosition {
+                partition: 1,
+                block_number: self.reads.load(Ordering::Relaxed),
+                ..Default::default()
+            })
+        }
+
+        fn locate(&self, _block: u64, _partition: u8, _dest: LocateDestType, _current: Option<u8>) -> crate::Result<u16> {
+            Ok(0)
+        }
+
+        fn space(&self, _space_type: SpaceType, _count: i32) -> crate::Result<()> {
+            Ok(())
+        }
+
+        fn rewind(&self) -> crate::Result<()> {
+            Ok(())
+        }
+
+        fn read_blocks(&self, block_count: u32, buffer: &mut [u8]) -> crate::Result<u32> {
+            self.reads.fetch_add(block_count as u64, Ordering::Relaxed);
+            self.max_transfer.fetch_max(buffer.len() as u64, Ordering::Relaxed);
+            Ok(block_count)
+        }
+
+        fn read_raw_block(&self, buffer: &mut [u8]) -> crate::Result<RawBlock> {
+            Ok(RawBlock::Data(buffer.len()))
+        }
+
+        fn write_blocks(&self, block_count: u32, _buffer: &[u8]) -> crate::Result<u32> {
+            Ok(block_count)
+        }
+
+        fn write_filemarks(&self, _count: u32) -> crate::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn extent_over_4gib_is_read_in_block_sized_commands() {
+        const EXTENT_BYTES: u64 = 5_000_000_000;
+        let tape = Arc::new(CountingTape::default());
+        let ops = TapeOperations::with_backend("counting", Box::new(tape.clone()));
+        let block_size = ops.data_block_size() as u64;
+        let extent = FileExtent {
+            file_offset: 0,
+            partition: "b".to_string(),
+            start_block: 100,
+            byte_offset: 0,
+            byte_count: EXTENT_BYTES,
+        };
+
+        let mut streamed = 0u64;
+        let mut largest_chunk = 0usize;
+        ops.stream_extent_blocks(&extent, extent.byte_count, |chunk| {
+            streamed += chunk.len() as u64;
+            largest_chunk = largest_chunk.max(chunk.len());
+            Ok(())
+        })
+        .unwrap();
+
+        let expected_reads = EXTENT_BYTES.div_ceil(block_size);
+        assert!(expected_reads > 1);
+        assert_eq!(streamed, EXTENT_BYTES);
+        assert_eq!(tape.reads.load(Ordering::Relaxed), expected_reads);
+        assert_eq!(tape.max_transfer.load(Ordering::Relaxed), block_size);
+        assert_eq!(largest_chunk as u64, block_size);
+        // 位置缓存停在extent之后的第一个块
+        let cached = ops.cached_position.lock().unwrap().clone().unwrap();
+        assert_eq!(cached.block_number, 100 + expected_reads);
+    }
+
+    const TIME: &str = "2024-01-01T00:00:00.000000000Z";
+
+    fn extent(file_offset: u64, partition: &str, start_block: u64, byte_offset: u64, byte_count: u64) -> FileExtent {
+        FileExtent {
+            file_offset,
+            partition: partition.to_string(),
+            start_block,
+            byte_offset,
+            byte_count,
+        }
+    }
+
+    fn file_with_extents(name: &str, length: u64, extents: Vec<FileExtent>) -> File {
+        File {
+            name: name.to_string(),
+            uid: 2,
+            length,
+            creation_time: TIME.to_string(),
+            change_time: TIME.to_string(),
+            modify_time: TIME.to_string(),
+            access_time: TIME.to_string(),
+            backup_time: TIME.to_string(),
+            read_only: false,
+            openforwrite: false,
+            symlink: None,
+            extent_info: crate::ltfs_index::ExtentInfo { extents },
+            extended_attributes: None,
+        }
+    }
+
+    #[test]
+    fn coalesce_extents_merges_only_sequential_extents() {
+        const BS: u64 = 1024;
+        let extents = [
+            extent(0, "b", 10, 0, 2 * BS),
+            // 紧接前一个extent：合并
+            extent(2 * BS, "b", 12, 0, BS / 2),
+            // 前一个extent的短块占满一块，下一块从13开始：合并
+            extent(2 * BS + BS / 2, "B", 13, 0, BS),
+            // 块号有间隔
+            extent(3 * BS + BS / 2, "b", 15, 0, BS),
+            // 换分区
+            extent(4 * BS + BS / 2, "a", 16, 0, BS),
+            // 不从块起始处开始
+            extent(5 * BS + BS / 2, "a", 17, 100, BS),
+            // 文件中有空洞
+            extent(7 * BS, "a", 19, 0, BS),
+        ];
+        let refs: Vec<&FileExtent> = extents.iter().collect();
+
+        let runs = coalesce_extents(&refs, BS);
+        let starts: Vec<Vec<u64>> = runs
+            .iter()
+            .map(|run| run.iter().map(|extent| extent.start_block).collect())
+            .collect();
+        assert_eq!(starts, [vec![10, 12, 13], vec![15], vec![16], vec![17], vec![19]]);
+        assert!(coalesce_extents(&[], BS).is_empty());
+    }
+
+    #[test]
+    fn multi_extent_file_reads_each_run_after_one_locate() {
+        let mut ops = TapeOperations::with_backend("virtual", Box::new(VirtualTape::new()));
+        let block_size = ops.data_block_size();
+        let content: Vec<u8> = (0..4 * block_size).map(|i| (i % 253) as u8).collect();
+        let blocks: Vec<&[u8]> = content.chunks(block_size).collect();
+
+        // 前三块连续存放；第四块在另一个文件的数据块之后
+        let mut partition = VirtualPartition::default();
+        for block in &blocks[..3] {
+            partition.push_block(block.to_vec());
+        }
+        partition.push_block(vec![0xEE; block_size]);
+        partition.push_block(blocks[3].to_vec());
+        let tape = VirtualTape::new();
+        tape.load_partition(1, partition);
+        ops.scsi = Box::new(tape.clone());
+        ops.extra_partition_count = Some(1);
+
+        let bs = block_size as u64;
+        let file = file_with_extents(
+            "multi.bin",
+            content.len() as u64,
+            vec![
+                extent(3 * bs, "b", 4, 0, bs),
+                extent(0, "b", 0, 0, 2 * bs),
+                extent(2 * bs, "b", 2, 0, bs),
+            ],
+        );
+
+        let mut streamed = Vec::new();
+        let written = ops.stream_file_from_tape(&file, |chunk| {
+            streamed.extend_from_slice(chunk);
+            Ok(())
+        });
+        assert_eq!(written.unwrap(), file.length);
+        assert!(streamed == content, "multi-extent content differs");
+
+        let locates = tape.take_commands().iter().filter(|command| command.starts_with("LOCATE")).count();
+        assert_eq!(locates, 2);
+    }
+
+    /// 长度不是块大小整数倍的文件：一个满块，加一个只有前1000字节有效、其余为零填充的块
+    fn unaligned_file(block_size: usize) -> (File, Vec<u8>, VirtualTape) {
+        let content: Vec<u8> = (0..block_size + 1000).map(|i| (i % 251) as u8 | 1).collect();
+        let mut padded = content[block_size..].to_vec();
+        padded.resize(block_size, 0);
+        let mut partition = VirtualPartition::default();
+        partition.push_block(content[..block_size].to_vec());
+        partition.push_block(padded);
+        partition.push_filemark();
+        let tape = VirtualTape::new();
+        tape.load_partition(1, partition);
+        let file = file_with_extents(
+            "unaligned.bin",
+            content.len() as u64,
+            vec![extent(0, "b", 0, 0, content.len() as u64)],
+        );
+        (file, content, tape)
+    }
+
+    #[test]
+    fn reads_stop_at_file_length_not_block_boundary() {
+        let mut ops = TapeOperations::with_backend("virtual", Box::new(VirtualTape::new()));
+        let block_size = ops.data_block_size();
+        let (file, content, tape) = unaligned_file(block_size);
+        ops.scsi = Box::new(tape);
+        ops.extra_partition_count = Some(1);
+
+        let complete = ops.read_complete_file_from_tape(&file).unwrap();
+        assert_eq!(complete.len(), block_size + 1000);
+        assert!(complete == content, "content differs or carries block padding");
+
+        // 预览：上限大于文件时同样截断到文件长度
+        assert!(ops.read_file_content_from_tape(&file, u64::MAX).unwrap() == content);
+        assert_eq!(ops.read_file_content_from_tape(&file, 10).unwrap(), content[..10]);
+        assert!(ops.read_extent_from_tape(&file.extent_info.extents[0], file.length).unwrap() == content);
+
+        let mut streamed = Vec::new();
+        let written = ops.stream_file_from_tape(&file, |chunk| {
+            streamed.extend_from_slice(chunk);
+            Ok(())
+        });
+        assert_eq!(written.unwrap(), file.length);
+        assert!(streamed == content);
+    }
 }
+
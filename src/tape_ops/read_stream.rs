@@ -0,0 +1,81 @@
+//! Streaming File Reads
+//!
+//! `read_file_stream` hands one tape file to the caller as a stream of chunks, so it
+//! can be piped to stdout or a socket without holding the whole file in memory. A
+//! blocking reader task walks the extents in tape order and passes each block through
+//! a bounded channel: the drive reads the next block while the consumer drains the
+//! previous one. Dropping the stream stops the reader at the next block.
+//! `read_path_stream` does the same for a file named by its tape path.
+
+use crate::error::{Result, RustLtfsError};
+use bytes::Bytes;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::Stream;
+use tracing::debug;
+
+/// 读取任务最多领先消费方的块数
+const PREFETCH_BLOCKS: usize = 1;
+
+impl super::TapeOperations {
+    /// 以UID流式读取文件内容；每项是一个块（稀疏文件的空洞按零填充）
+    ///
+    /// 读取在 `spawn_blocking` 任务中进行，必须在tokio运行时中调用。读取出错时流的最后
+    /// 一项为该错误。
+    pub fn read_file_stream(
+        self: &Arc<Self>,
+        file_uid: u64,
+    ) -> Result<impl Stream<Item = Result<Bytes>> + Send + 'static> {
+        let index = self
+            .index
+            .as_ref()
+            .ok_or_else(|| RustLtfsError::ltfs_index("No index loaded".to_string()))?;
+        let file = index.find_file_by_uid(file_uid).cloned().ok_or_else(|| {
+            RustLtfsError::ltfs_index(format!("No file with UID {} in index", file_uid))
+        })?;
+        if file.symlink.is_some() {
+            return Err(RustLtfsError::file_operation(format!(
+                "'{}' is a symlink and has no data",
+                file.name
+            )));
+        }
+        debug!(
+            "Streaming '{}' (UID {}, {} bytes)",
+            file.name, file_uid, file.length
+        );
+
+        let (sender, receiver) = mpsc::channel(PREFETCH_BLOCKS);
+        let ops = Arc::clone(self);
+        tokio::task::spawn_blocking(move || {
+            let result = ops.stream_file_from_tape(&file, |chunk| {
+                sender
+                    .blocking_send(Ok(Bytes::copy_from_slice(chunk)))
+                    .map_err(|_| {
+                        RustLtfsError::operation_cancelled(format!(
+                            "Stream of '{}' dropped by consumer",
+                            file.name
+                        ))
+                    })
+            });
+            if let Err(e) = result {
+                if sender.is_closed() {
+                    debug!("{}", e);
+                } else {
+                    let _ = sender.blocking_send(Err(e));
+                }
+            }
+        });
+
+        Ok(ReceiverStream::new(receiver))
+    }
+
+    /// 按磁带路径流式读取文件；路径是目录时返回错误
+    pub fn read_path_stream(
+        self: &Arc<Self>,
+        path: &str,
+    ) -> Result<impl Stream<Item = Result<Bytes>> + Send + 'static> {
+        let uid = self.find_file_uid(path)?;
+        self.read_file_stream(uid)
+    }
+}
@@ -0,0 +1,123 @@
+//! Seek Benchmark
+//!
+//! Measures LOCATE latency for `diag seek`: the head is sent to random blocks of the
+//! data partition and each seek is timed until READ POSITION confirms the block.
+
+use crate::error::{Result, RustLtfsError};
+use crate::scsi::types::LocateDestType;
+use std::time::{Duration, Instant};
+use tracing::{debug, info};
+
+/// 定位耗时统计
+#[derive(Debug, Clone)]
+pub struct SeekStats {
+    /// 测试所在的物理分区
+    pub partition: u8,
+    /// 随机目标块的范围上限（该分区的EOD块号）
+    pub eod_block: u64,
+    /// 每次定位的耗时，按测试顺序
+    pub samples: Vec<Duration>,
+    pub min: Duration,
+    pub max: Duration,
+    pub avg: Duration,
+    pub p95: Duration,
+    /// 使用LOCATE(16)（否则为LOCATE(10)）
+    pub locate16: bool,
+    /// 测试期间LOCATE(16)失败后改用LOCATE(10)的次数
+    pub locate10_fallbacks: u64,
+}
+
+impl SeekStats {
+    fn from_samples(
+        partition: u8,
+        eod_block: u64,
+        samples: Vec<Duration>,
+        locate16: bool,
+        locate10_fallbacks: u64,
+    ) -> Self {
+        let mut sorted = samples.clone();
+        sorted.sort();
+        let total: Duration = sorted.iter().sum();
+        // 最近秩法：至少95%的样本不超过p95
+        let p95_rank = (sorted.len() * 95).div_ceil(100).max(1);
+
+        Self {
+            partition,
+            eod_block,
+            min: sorted[0],
+            max: sorted[sorted.len() - 1],
+            avg: total / sorted.len() as u32,
+            p95: sorted[p95_rank - 1],
+            samples,
+            locate16,
+            locate10_fallbacks,
+        }
+    }
+}
+
+/// xorshift64：只用于挑选目标块，不需要密码学强度
+fn next_random(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
+
+impl super::TapeOperations {
+    /// 在数据分区内随机定位 `samples` 次，统计每次LOCATE（含READ POSITION确认）的耗时
+    ///
+    /// 目标块取自分区开头到EOD之间；结束时磁带停在最后一个目标块。
+    pub fn seek_benchmark(&self, samples: usize) -> Result<SeekStats> {
+        if samples == 0 {
+            return Err(RustLtfsError::parameter_validation(
+                "Seek benchmark needs at least one sample".to_string(),
+            ));
+        }
+
+        let partition = self.get_target_partition(if self.get_extra_partition_count() > 0 { 1 } else { 0 });
+        self.invalidate_position_cache();
+        self.scsi.locate_to_eod(partition)?;
+        let eod_block = self.scsi.read_position()?.block_number;
+        if eod_block == 0 {
+            return Err(RustLtfsError::tape_device(format!(
+                "Partition {} holds no data to seek across",
+                partition
+            )));
+        }
+        info!(
+            "Seek benchmark: {} samples across blocks 0..{} of partition {}",
+            samples, eod_block, partition
+        );
+
+        let fallbacks_before = self.scsi.locate_fallback_count();
+        let mut state = uuid::Uuid::new_v4().as_u64_pair().0 | 1;
+        let mut durations = Vec::with_capacity(samples);
+
+        for sample in 1..=samples {
+            let target = next_random(&mut state) % eod_block;
+            let start = Instant::now();
+            self.scsi
+                .locate(target, partition, LocateDestType::Block, Some(partition))?;
+            let position = self.scsi.read_position()?;
+            let elapsed = start.elapsed();
+
+            if position.partition != partition || position.block_number != target {
+                return Err(RustLtfsError::tape_device(format!(
+                    "LOCATE to p{}b{} ended at p{}b{}",
+                    partition, target, position.partition, position.block_number
+                )));
+            }
+            debug!("Seek {}/{} to block {}: {:?}", sample, samples, target, elapsed);
+            durations.push(elapsed);
+        }
+
+        let fallbacks = self.scsi.locate_fallback_count() - fallbacks_before;
+        Ok(SeekStats::from_samples(
+            partition,
+            eod_block,
+            durations,
+            self.scsi.uses_locate16(),
+            fallbacks,
+        ))
+    }
+}
@@ -0,0 +1,450 @@
+//! Multi-Cartridge Spanning
+//!
+//! Records which files of a backup set went to which cartridge in a sidecar JSON
+//! manifest, so a set larger than one tape can continue on the next cartridge.
+//!
+//! A file that hits end of media mid-write is split: the cartridge keeps the part
+//! already written (indexed with its length cut at the split point) and the manifest
+//! records the remaining byte range. On the next cartridge the file is written again
+//! from that offset as a new extent chain, so its index entry there has the full
+//! length with a leading hole. Extraction with the manifest writes each cartridge's
+//! piece at its offset into the same destination file.
+
+use crate::error::{Result, RustLtfsError};
+use crate::ltfs_index::name::normalize_name;
+use crate::ltfs_index::LtfsIndex;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, warn};
+
+/// Current manifest format version
+pub const SPAN_MANIFEST_VERSION: u32 = 1;
+
+/// The byte range of a file stored on one cartridge
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpanFile {
+    /// Tape path of the file
+    pub path: String,
+    /// Offset of the first byte stored on this cartridge
+    pub offset: u64,
+    /// Number of bytes stored on this cartridge
+    pub length: u64,
+    /// Size of the whole source file
+    pub total_size: u64,
+}
+
+impl SpanFile {
+    /// End (exclusive) of the byte range on this cartridge
+    pub fn end(&self) -> u64 {
+        self.offset + self.length
+    }
+
+    /// Whether only part of the file is on this cartridge
+    pub fn is_split(&self) -> bool {
+        self.offset > 0 || self.end() < self.total_size
+    }
+}
+
+/// Files written to one cartridge of the set
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SpanCartridge {
+    /// Cartridge barcode (MAM), or the volume UUID when the barcode is not set
+    pub barcode: String,
+    pub volume_uuid: Option<String>,
+    pub files: Vec<SpanFile>,
+}
+
+/// A file split at end of media, to be continued on the next cartridge
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpanContinuation {
+    /// Tape path of the file
+    pub path: String,
+    /// Local source file
+    pub source_path: PathBuf,
+    /// Offset to continue writing from
+    pub offset: u64,
+    pub total_size: u64,
+}
+
+/// Persisted layout of a backup set spanning several cartridges
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SpanManifest {
+    pub version: u32,
+    /// Local source of the set
+    pub source: PathBuf,
+    /// Target path on tape
+    pub target_path: String,
+    /// Cartridges in the order they were written
+    pub cartridges: Vec<SpanCartridge>,
+    /// File left unfinished on the last cartridge
+    pub continuation: Option<SpanContinuation>,
+}
+
+/// 清单中的路径键：NFC名称、前导 `/`、无重复或结尾的 `/`，与索引中的规范路径一致
+fn span_key(path: &str) -> String {
+    format!("/{}", LtfsIndex::split_path(&normalize_name(path)).join("/"))
+}
+
+impl SpanManifest {
+    /// Load a manifest from disk
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            RustLtfsError::file_operation(format!("Cannot read span manifest {:?}: {}", path, e))
+        })?;
+        let manifest: Self = serde_json::from_str(&content).map_err(|e| {
+            RustLtfsError::parse(format!("Invalid span manifest {:?}: {}", path, e))
+        })?;
+        if manifest.version > SPAN_MANIFEST_VERSION {
+            return Err(RustLtfsError::parse(format!(
+                "Span manifest {:?} has unsupported version {}",
+                path, manifest.version
+            )));
+        }
+        Ok(manifest)
+    }
+
+    /// Save the manifest atomically (write a temporary file, then rename)
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(self).map_err(|e| {
+            RustLtfsError::system(format!("Cannot serialize span manifest: {}", e))
+        })?;
+
+        let mut tmp_name = path.as_os_str().to_os_string();
+        tmp_name.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_name);
+
+        std::fs::write(&tmp_path, content)?;
+        std::fs::rename(&tmp_path, path)?;
+        debug!("Span manifest saved: {} cartridges", self.cartridges.len());
+        Ok(())
+    }
+
+    /// All pieces of a file, with the barcode of the cartridge holding each
+    pub fn pieces(&self, tape_path: &str) -> Vec<(&str, &SpanFile)> {
+        let key = span_key(tape_path);
+        self.cartridges
+            .iter()
+            .flat_map(|cartridge| {
+                cartridge
+                    .files
+                    .iter()
+                    .filter(|file| file.path == key)
+                    .map(move |file| (cartridge.barcode.as_str(), file))
+            })
+            .collect()
+    }
+
+    /// Whether the file's last byte is already on some cartridge of the set
+    pub fn is_complete(&self, tape_path: &str) -> bool {
+        self.pieces(tape_path)
+            .iter()
+            .any(|(_, file)| file.end() >= file.total_size)
+    }
+
+    /// Offset to continue the file from, if it was split on the previous cartridge
+    pub fn continuation_offset(&self, tape_path: &str) -> Option<u64> {
+        let key = span_key(tape_path);
+        self.continuation
+            .as_ref()
+            .filter(|continuation| continuation.path == key)
+            .map(|continuation| continuation.offset)
+    }
+
+    fn cartridge_mut(&mut self, barcode: &str, volume_uuid: Option<&str>) -> &mut SpanCartridge {
+        let position = self
+            .cartridges
+            .iter()
+            .position(|cartridge| cartridge.barcode == barcode);
+        let index = position.unwrap_or_else(|| {
+            self.cartridges.push(SpanCartridge {
+                barcode: barcode.to_string(),
+                volume_uuid: volume_uuid.map(str::to_string),
+                files: Vec::new(),
+            });
+            self.cartridges.len() - 1
+        });
+        &mut self.cartridges[index]
+    }
+
+    /// Record the byte range of a file written to the given cartridge
+    ///
+    /// Recording the range that ends the file clears its continuation.
+    pub fn record(&mut self, barcode: &str, volume_uuid: Option<&str>, file: SpanFile) {
+        let file = SpanFile {
+            path: span_key(&file.path),
+            ..file
+        };
+        if file.end() >= file.total_size
+            && self
+                .continuation
+                .as_ref()
+                .is_some_and(|continuation| continuation.path == file.path)
+        {
+            self.continuation = None;
+        }
+        self.cartridge_mut(barcode, volume_uuid).files.push(file);
+    }
+}
+
+/// Span manifest attached to a running session
+#[derive(Debug, Clone)]
+pub(crate) struct SpanState {
+    pub path: PathBuf,
+    pub manifest: SpanManifest,
+    /// Barcode of the loaded cartridge
+    pub barcode: String,
+    pub volume_uuid: Option<String>,
+}
+
+impl super::TapeOperations {
+    /// 识别当前磁带：优先使用MAM中的条码，未设置条码时用卷UUID
+    fn span_cartridge_id(&self) -> Result<(String, Option<String>)> {
+        let volume_uuid = self.index.as_ref().map(|idx| idx.volumeuuid.clone());
+        let barcode = match self.scsi.read_barcode() {
+            Ok(barcode) if !barcode.is_empty() => barcode,
+            other => {
+                if let Err(e) = other {
+                    warn!("Cannot read cartridge barcode: {}", e);
+                }
+                volume_uuid.clone().ok_or_else(|| {
+                    RustLtfsError::tape_device(
+                        "Cartridge has no barcode and no LTFS index is loaded; cannot identify it in the span manifest".to_string(),
+                    )
+                })?
+            }
+        };
+        Ok((barcode, volume_uuid))
+    }
+
+    /// 开始或继续一个跨磁带写入集：清单不存在时新建，存在时校验源与目标一致
+    pub fn start_span_manifest(&mut self, path: &Path, source: &Path, target_path: &str) -> Result<&SpanManifest> {
+        let manifest = if path.exists() {
+            let manifest = SpanManifest::load(path)?;
+            if manifest.source != source || span_key(&manifest.target_path) != span_key(target_path) {
+                return Err(RustLtfsError::parameter_validation(format!(
+                    "Span manifest {:?} belongs to {:?} -> {}, not {:?} -> {}",
+                    path, manifest.source, manifest.target_path, source, target_path
+                )));
+            }
+            manifest
+        } else {
+            SpanManifest {
+                version: SPAN_MANIFEST_VERSION,
+                source: source.to_path_buf(),
+                target_path: target_path.to_string(),
+                ..Default::default()
+            }
+        };
+
+        let (barcode, volume_uuid) = self.span_cartridge_id()?;
+        info!(
+            "Span manifest {:?}: cartridge {} is number {} of the set",
+            path,
+            barcode,
+            manifest
+                .cartridges
+                .iter()
+                .position(|cartridge| cartridge.barcode == barcode)
+                .unwrap_or(manifest.cartridges.len())
+                + 1
+        );
+        manifest.save(path)?;
+
+        let state = self.span.insert(SpanState {
+            path: path.to_path_buf(),
+            manifest,
+            barcode,
+            volume_uuid,
+        });
+        Ok(&state.manifest)
+    }
+
+    /// 提取时载入跨磁带清单，用于把分割文件的片段写回各自的偏移处
+    pub fn load_span_manifest(&mut self, path: &Path) -> Result<&SpanManifest> {
+        let manifest = SpanManifest::load(path)?;
+        let (barcode, volume_uuid) = self.span_cartridge_id()?;
+        if !manifest.cartridges.iter().any(|cartridge| cartridge.barcode == barcode) {
+            warn!("Cartridge {} is not part of span manifest {:?}", barcode, path);
+        }
+
+        let state = self.span.insert(SpanState {
+            path: path.to_path_buf(),
+            manifest,
+            barcode,
+            volume_uuid,
+        });
+        Ok(&state.manifest)
+    }
+
+    /// 文件是否已完整写入跨磁带集中的某盘磁带
+    pub(crate) fn span_has_file(&self, tape_path: &str) -> bool {
+        self.span
+            .as_ref()
+            .is_some_and(|state| state.manifest.is_complete(tape_path))
+    }
+
+    /// 上一盘磁带分割的文件需从此偏移继续写入
+    pub(crate) fn span_continuation_offset(&self, tape_path: &str) -> Option<u64> {
+        self.span
+            .as_ref()
+            .and_then(|state| state.manifest.continuation_offset(tape_path))
+    }
+
+    /// 记录写入当前磁带的文件字节范围并保存清单
+    pub(crate) fn span_record_file(&mut self, tape_path: &str, offset: u64, length: u64, total_size: u64) -> Result<()> {
+        if let Some(state) = self.span.as_mut() {
+            let file = SpanFile {
+                path: tape_path.to_string(),
+                offset,
+                length,
+                total_size,
+            };
+            state.manifest.record(&state.barcode, state.volume_uuid.as_deref(), file);
+            state.manifest.save(&state.path)?;
+        }
+        Ok(())
+    }
+
+    /// 介质末端分割文件：记录已写入的部分，剩余范围留给下一盘磁带
+    pub(crate) fn span_record_split(
+        &mut self,
+        tape_path: &str,
+        source_path: &Path,
+        offset: u64,
+        end: u64,
+        total_size: u64,
+    ) -> Result<()> {
+        if let Some(state) = self.span.as_mut() {
+            state.manifest.continuation = Some(SpanContinuation {
+                path: span_key(tape_path),
+                source_path: source_path.to_path_buf(),
+                offset: end,
+                total_size,
+            });
+            info!(
+                "Split {} at byte {} of {}; the rest continues on the next cartridge",
+                tape_path, end, total_size
+            );
+            if end == offset {
+                return state.manifest.save(&state.path);
+            }
+        }
+        self.span_record_file(tape_path, offset, end - offset, total_size)
+    }
+
+    /// 当前磁带上属于分割文件的片段（完整文件返回None）
+    pub(crate) fn span_piece(&self, tape_path: &str) -> Option<SpanFile> {
+        let state = self.span.as_ref()?;
+        state
+            .manifest
+            .pieces(tape_path)
+            .into_iter()
+            .find(|(barcode, _)| *barcode == state.barcode)
+            .map(|(_, file)| file.clone())
+            .filter(SpanFile::is_split)
+    }
+
+    /// 分割文件存放在其他磁带上的片段（条码与字节范围）
+    pub(crate) fn span_other_pieces(&self, tape_path: &str) -> Vec<(String, SpanFile)> {
+        let Some(state) = self.span.as_ref() else {
+            return Vec::new();
+        };
+        state
+            .manifest
+            .pieces(tape_path)
+            .into_iter()
+            .filter(|(barcode, _)| *barcode != state.barcode)
+            .map(|(barcode, file)| (barcode.to_string(), file.clone()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn piece(path: &str, offset: u64, length: u64, total_size: u64) -> SpanFile {
+        SpanFile {
+            path: path.to_string(),
+            offset,
+            length,
+            total_size,
+        }
+    }
+
+    /// 1000字节的文件在第一盘磁带的第600字节处分割
+    fn split_manifest() -> SpanManifest {
+        let mut manifest = SpanManifest {
+            version: SPAN_MANIFEST_VERSION,
+            target_path: "/set".to_string(),
+            ..Default::default()
+        };
+        manifest.record("CART01L8", None, piece("/set/a.txt", 0, 10, 10));
+        manifest.record("CART01L8", None, piece("/set/big.bin", 0, 600, 1000));
+        manifest.continuation = Some(SpanContinuation {
+            path: "/set/big.bin".to_string(),
+            source_path: PathBuf::from("src/big.bin"),
+            offset: 600,
+            total_size: 1000,
+        });
+        manifest
+    }
+
+    #[test]
+    fn recording_the_last_piece_clears_the_continuation() {
+        let mut manifest = split_manifest();
+        assert_eq!(manifest.continuation_offset("/set/big.bin"), Some(600));
+
+        // 其他文件或未到文件末尾的片段不结束分割
+        manifest.record("CART02L8", Some("uuid-2"), piece("/set/z.txt", 0, 5, 5));
+        manifest.record("CART02L8", Some("uuid-2"), piece("/set/big.bin", 600, 100, 1000));
+        assert!(manifest.continuation.is_some());
+        assert!(!manifest.is_complete("/set/big.bin"));
+
+        manifest.record("CART02L8", Some("uuid-2"), piece("set//big.bin", 700, 300, 1000));
+        assert!(manifest.continuation.is_none());
+        assert!(manifest.is_complete("/set/big.bin"));
+        assert_eq!(manifest.continuation_offset("/set/big.bin"), None);
+    }
+
+    #[test]
+    fn pieces_of_a_split_file_span_both_cartridges() {
+        let mut manifest = split_manifest();
+        manifest.record("CART02L8", Some("uuid-2"), piece("/set/big.bin", 600, 400, 1000));
+        assert_eq!(manifest.cartridges.len(), 2);
+        assert_eq!(manifest.cartridges[1].volume_uuid.as_deref(), Some("uuid-2"));
+
+        // 查询路径与记录时一样规范化
+        let pieces = manifest.pieces("set/big.bin/");
+        let barcodes: Vec<_> = pieces.iter().map(|(barcode, _)| *barcode).collect();
+        assert_eq!(barcodes, ["CART01L8", "CART02L8"]);
+        assert_eq!((pieces[0].1.offset, pieces[0].1.end()), (0, 600));
+        assert_eq!((pieces[1].1.offset, pieces[1].1.end()), (600, 1000));
+        assert!(pieces.iter().all(|(_, file)| file.is_split()));
+
+        let whole = manifest.pieces("/set/a.txt");
+        assert_eq!(whole.len(), 1);
+        assert!(!whole[0].1.is_split());
+        assert!(manifest.pieces("/set/missing").is_empty());
+    }
+
+    #[test]
+    fn manifest_round_trips_and_rejects_newer_versions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("span.json");
+
+        let manifest = split_manifest();
+        manifest.save(&path).unwrap();
+        let loaded = SpanManifest::load(&path).unwrap();
+        assert_eq!(loaded.cartridges[0].files, manifest.cartridges[0].files);
+        assert_eq!(loaded.continuation_offset("/set/big.bin"), Some(600));
+
+        let newer = SpanManifest {
+            version: SPAN_MANIFEST_VERSION + 1,
+            ..manifest
+        };
+        newer.save(&path).unwrap();
+        let err = SpanManifest::load(&path).unwrap_err();
+        assert!(err.to_string().contains("unsupported version"), "{}", err);
+    }
+}
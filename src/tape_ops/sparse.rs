@@ -0,0 +1,166 @@
+//! Sparse File Support
+//!
+//! Detects holes in source files so that only data regions are written to tape.
+//! The file's extents then carry `file_offset` gaps, which LTFS readers (and our
+//! extraction path) turn back into zeros / holes.
+
+use crate::ltfs_index::{File, FileExtent};
+use std::ops::Range;
+
+/// 源文件空洞检测方式
+#[derive(Debug)]
+pub(crate) enum HoleDetector {
+    /// 文件系统报告的数据区域 (Linux SEEK_DATA/SEEK_HOLE)，区域之外都是空洞
+    DataMap(Vec<Range<u64>>),
+    /// 文件系统不支持时的回退：全零块视为空洞
+    ZeroRun,
+}
+
+impl HoleDetector {
+    /// 为长度为 `len` 的打开文件选择检测方式
+    pub(crate) fn for_file(file: &std::fs::File, len: u64) -> Self {
+        match data_regions(file, len) {
+            Some(regions) => HoleDetector::DataMap(regions),
+            None => HoleDetector::ZeroRun,
+        }
+    }
+
+    /// 文件中从 `offset` 开始、内容为 `block` 的块是否完全落在空洞中
+    pub(crate) fn is_hole(&self, offset: u64, block: &[u8]) -> bool {
+        if block.is_empty() {
+            return false;
+        }
+        match self {
+            HoleDetector::DataMap(regions) => {
+                let end = offset + block.len() as u64;
+                !regions
+                    .iter()
+                    .any(|region| region.start < end && offset < region.end)
+            }
+            HoleDetector::ZeroRun => block.iter().all(|&b| b == 0),
+        }
+    }
+}
+
+/// 用 SEEK_DATA/SEEK_HOLE 列出文件的数据区域；文件系统不支持时返回 None
+#[cfg(target_os = "linux")]
+fn data_regions(file: &std::fs::File, len: u64) -> Option<Vec<Range<u64>>> {
+    use std::os::unix::io::AsRawFd;
+
+    let fd = file.as_raw_fd();
+    let mut regions = Vec::new();
+    let mut offset = 0u64;
+
+    while offset < len {
+        // SAFETY: lseek只移动文件描述符的偏移，读取方随后会重新定位
+        let data = unsafe { libc::lseek(fd, offset as libc::off_t, libc::SEEK_DATA) };
+        if data < 0 {
+            return match std::io::Error::last_os_error().raw_os_error() {
+                // ENXIO: offset之后没有数据，剩余部分全是空洞
+                Some(libc::ENXIO) => {
+                    rewind_fd(fd);
+                    Some(regions)
+                }
+                _ => {
+                    rewind_fd(fd);
+                    None
+                }
+            };
+        }
+        // SAFETY: 同上
+        let hole = unsafe { libc::lseek(fd, data, libc::SEEK_HOLE) };
+        if hole < 0 {
+            rewind_fd(fd);
+            return None;
+        }
+        let (start, end) = (data as u64, std::cmp::min(hole as u64, len));
+        if start < end {
+            regions.push(start..end);
+        }
+        offset = std::cmp::max(end, start + 1);
+    }
+
+    rewind_fd(fd);
+    Some(regions)
+}
+
+#[cfg(target_os = "linux")]
+fn rewind_fd(fd: std::os::unix::io::RawFd) {
+    // SAFETY: 恢复到文件开头，调用方从头读取
+    unsafe {
+        libc::lseek(fd, 0, libc::SEEK_SET);
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn data_regions(_file: &std::fs::File, _len: u64) -> Option<Vec<Range<u64>>> {
+    None
+}
+
+/// 写入过程中按块收集extent：相邻的数据块合并，跳过的空洞块使下一个数据块开始新的extent
+#[derive(Debug)]
+pub(crate) struct ExtentBuilder {
+    partition: String,
+    next_block: u64,
+    extents: Vec<FileExtent>,
+}
+
+impl ExtentBuilder {
+    pub(crate) fn new(start: &crate::scsi::TapePosition) -> Self {
+        Self {
+            partition: if start.partition == 0 { "a" } else { "b" }.to_string(),
+            next_block: start.block_number,
+            extents: Vec::new(),
+        }
+    }
+
+    /// 记录一个写到磁带上的块，其数据位于文件的 `file_offset`
+    pub(crate) fn push_block(&mut self, file_offset: u64, len: u64) {
+        match self.extents.last_mut() {
+            Some(last) if last.file_offset + last.byte_count == file_offset => {
+                last.byte_count += len;
+            }
+            _ => self.extents.push(FileExtent {
+                partition: self.partition.clone(),
+                start_block: self.next_block,
+                byte_count: len,
+                file_offset,
+                byte_offset: 0,
+            }),
+        }
+        self.next_block += 1;
+    }
+
+    /// 下一个数据块在磁带上的块号
+    pub(crate) fn next_block(&self) -> u64 {
+        self.next_block
+    }
+
+    pub(crate) fn into_extents(self) -> Vec<FileExtent> {
+        self.extents
+    }
+}
+
+/// 非稀疏写入（如试运行）的extent：从 `start` 开始的一段连续数据，空文件没有extent
+pub(crate) fn contiguous_extents(start: &crate::scsi::TapePosition, byte_count: u64) -> Vec<FileExtent> {
+    let mut builder = ExtentBuilder::new(start);
+    if byte_count > 0 {
+        builder.push_block(0, byte_count);
+    }
+    builder.into_extents()
+}
+
+/// 文件的extent是否留有空洞（extent之间的间隙或文件末尾未覆盖的部分）
+pub(crate) fn has_holes(file: &File) -> bool {
+    let mut extents: Vec<&FileExtent> = file.extent_info.extents.iter().collect();
+    extents.sort_by_key(|extent| extent.file_offset);
+
+    let mut covered = 0u64;
+    for extent in extents {
+        if extent.file_offset > covered {
+            return true;
+        }
+        covered = std::cmp::max(covered, extent.file_offset + extent.byte_count);
+    }
+    covered < file.length
+}
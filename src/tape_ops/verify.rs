@@ -0,0 +1,386 @@
+//! Verify-Only Reconcile Module
+//!
+//! Compares a local source tree against files already written to tape, without
+//! writing anything. Used to certify an interrupted backup before re-running it.
+//! Also checks every file on tape against the hash recorded in the index, and
+//! audits index extents against the written region (EOD) of the media.
+
+use super::hash::{CheckSumBlockwiseCalculator, FileHashes};
+use crate::error::{Result, RustLtfsError};
+use crate::ltfs_index::name::normalize_name;
+use crate::ltfs_index::{Directory, File};
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, warn};
+
+/// Result of reconciling a source tree with the tape (source / tape data / tape index)
+#[derive(Debug, Clone, Default)]
+pub struct VerifyReport {
+    /// Present on tape with matching size and hash
+    pub matched: Vec<String>,
+    /// Present on tape but differing, with the reason
+    pub mismatched: Vec<(String, String)>,
+    /// Source files with no counterpart on tape
+    pub missing: Vec<String>,
+    /// Tape files with no counterpart in the source tree
+    pub orphans: Vec<String>,
+    /// Source files that could not be read or verified, with the error message
+    pub errors: Vec<(String, String)>,
+}
+
+impl VerifyReport {
+    /// Whether every source file is present and matching, with no orphans
+    pub fn is_clean(&self) -> bool {
+        self.mismatched.is_empty()
+            && self.missing.is_empty()
+            && self.orphans.is_empty()
+            && self.errors.is_empty()
+    }
+}
+
+/// Result of checking tape data against the hashes stored in the index
+#[derive(Debug, Clone, Default)]
+pub struct HashCheckReport {
+    /// Data read back from tape matches the stored hash
+    pub passed: Vec<String>,
+    /// Data differs from the stored hash or could not be read, with the reason
+    pub failed: Vec<(String, String)>,
+    /// Files with no stored hash to compare against
+    pub unverifiable: Vec<String>,
+}
+
+impl HashCheckReport {
+    /// Number of files that were actually hashed and compared
+    pub fn checked(&self) -> usize {
+        self.passed.len() + self.failed.len()
+    }
+}
+
+/// Result of auditing index extents against the written region of the tape
+#[derive(Debug, Clone, Default)]
+pub struct ExtentAuditReport {
+    /// Files with at least one extent
+    pub files_checked: usize,
+    /// EOD block of each partition referenced by an extent
+    pub eod: BTreeMap<u8, u64>,
+    /// Files with an extent past EOD, in the index partition, or not locatable, with the reason
+    pub suspects: Vec<(String, String)>,
+}
+
+impl ExtentAuditReport {
+    /// Whether every extent lies in the written region of a data partition
+    pub fn is_clean(&self) -> bool {
+        self.suspects.is_empty()
+    }
+}
+
+impl super::TapeOperations {
+    /// 只校验不写入：将源目录与磁带上 `target_path` 下的文件逐一比对
+    ///
+    /// 每个源文件按 路径 + 大小 + 哈希 比对：源文件哈希与索引中记录的所有
+    /// `ltfs.hash.*` 哈希（如有）比较，再与从磁带读回数据的SHA256比较。
+    pub fn verify_against_source(&self, source: &Path, target_path: &str) -> Result<VerifyReport> {
+        let index = self.index.as_ref().ok_or_else(|| {
+            RustLtfsError::ltfs_index("No LTFS index loaded, cannot verify".to_string())
+        })?;
+
+        let mut tape_files = BTreeMap::new();
+        if let Some(target_dir) = self.find_directory_by_path(&index.root_directory, target_path) {
+            collect_tape_files(target_dir, "", &mut tape_files);
+        } else {
+            warn!("Target path {} not found on tape", target_path);
+        }
+
+        let source_files = collect_source_files(source)?;
+        info!(
+            "Verifying {} source files against {} tape files under {}",
+            source_files.len(),
+            tape_files.len(),
+            target_path
+        );
+
+        let mut report = VerifyReport::default();
+
+        for (relative, source_path) in &source_files {
+            let tape_file = match tape_files.remove(relative) {
+                Some(file) => file,
+                None => {
+                    report.missing.push(relative.clone());
+                    continue;
+                }
+            };
+
+            match self.compare_source_with_tape(source_path, tape_file) {
+                Ok(None) => report.matched.push(relative.clone()),
+                Ok(Some(reason)) => report.mismatched.push((relative.clone(), reason)),
+                Err(e) => report.errors.push((relative.clone(), e.to_string())),
+            }
+        }
+
+        report.orphans = tape_files.into_keys().collect();
+        Ok(report)
+    }
+
+    /// 整盘校验：读回索引中每个文件的extent，与写入时记录的哈希比对
+    ///
+    /// 读回一次数据，同时计算索引中记录的每一种哈希（SHA1/MD5/SHA256/BLAKE3/XxHash，
+    /// 键名与LTFSCopyGUI相同，见 `hash` 模块）并逐一比对；没有任何可识别哈希的文件
+    /// 记为无法校验，而不是失败。符号链接没有数据，直接跳过。
+    pub fn verify_tape_hashes(&self) -> Result<HashCheckReport> {
+        let index = self.index.as_ref().ok_or_else(|| {
+            RustLtfsError::ltfs_index("No LTFS index loaded, cannot verify".to_string())
+        })?;
+
+        let mut tape_files = BTreeMap::new();
+        collect_tape_files(&index.root_directory, "", &mut tape_files);
+        info!("Checking {} tape files against stored hashes", tape_files.len());
+
+        let mut report = HashCheckReport::default();
+
+        for (path, file) in tape_files {
+            if file.symlink.is_some() {
+                continue;
+            }
+
+            let expected = FileHashes::from_file(file);
+            if expected.is_empty() {
+                debug!("No stored hash for {}", path);
+                report.unverifiable.push(path);
+                continue;
+            }
+
+            match self.hash_tape_file(file, &expected) {
+                Ok(actual) => {
+                    let mismatches = expected.mismatches(&actual);
+                    if mismatches.is_empty() {
+                        debug!("Hashes verified for {}: {:?}", path, expected.to_xattrs().keys());
+                        report.passed.push(path);
+                    } else {
+                        warn!("Hash mismatch for {}: {}", path, mismatches.join("; "));
+                        report.failed.push((
+                            path,
+                            format!("hash differs ({})", mismatches.join("; ")),
+                        ));
+                    }
+                }
+                Err(e) => {
+                    warn!("Cannot read {} back from tape: {}", path, e);
+                    report.failed.push((path, e.to_string()));
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// 索引与介质一致性自检：每个extent必须落在数据分区EOD之前；再LOCATE到每个文件
+    /// 最后一个extent的起始块并读取位置确认可达
+    ///
+    /// 不读取文件数据，只需每个文件一次定位。可发现引用了从未写入的块的过期索引。
+    pub fn audit_extents(&self) -> Result<ExtentAuditReport> {
+        let index = self.index.as_ref().ok_or_else(|| {
+            RustLtfsError::ltfs_index("No LTFS index loaded, cannot audit extents".to_string())
+        })?;
+
+        let mut tape_files = BTreeMap::new();
+        collect_tape_files(&index.root_directory, "", &mut tape_files);
+        info!("Auditing extents of {} tape files against EOD", tape_files.len());
+
+        let dual_partition = self.get_extra_partition_count() > 0;
+        let index_partition = self.get_target_partition(0);
+        let block_size = self.data_block_size() as u64;
+        let mut report = ExtentAuditReport::default();
+
+        self.invalidate_position_cache();
+        for (path, file) in tape_files {
+            let extents = &file.extent_info.extents;
+            if file.symlink.is_some() || extents.is_empty() {
+                continue;
+            }
+            self.check_cancelled(&format!("while auditing {}", path))?;
+            report.files_checked += 1;
+
+            let mut problems = Vec::new();
+            for (n, extent) in extents.iter().enumerate() {
+                let partition = self.extent_partition(extent);
+                if dual_partition && partition == index_partition {
+                    problems.push(format!(
+                        "extent {} is in the index partition ({}, block {})",
+                        n, extent.partition, extent.start_block
+                    ));
+                    continue;
+                }
+
+                let eod = match report.eod.get(&partition) {
+                    Some(&eod) => eod,
+                    None => {
+                        self.scsi.locate_to_eod(partition)?;
+                        let eod = self.scsi.read_position()?.block_number;
+                        debug!("Partition {} EOD at block {}", partition, eod);
+                        report.eod.insert(partition, eod);
+                        eod
+                    }
+                };
+
+                let blocks = (extent.byte_offset + extent.byte_count).div_ceil(block_size).max(1);
+                let last_block = extent.start_block + blocks - 1;
+                if last_block >= eod {
+                    problems.push(format!(
+                        "extent {} ends at block {}, past EOD {} of partition {}",
+                        n, last_block, eod, extent.partition
+                    ));
+                }
+            }
+
+            // 过期索引引用的块可能根本无法定位
+            if problems.is_empty() {
+                if let Some(last) = extents.iter().max_by_key(|extent| extent.file_offset) {
+                    let partition = self.extent_partition(last);
+                    let reached = self
+                        .scsi
+                        .locate_block(partition, last.start_block)
+                        .and_then(|()| self.scsi.read_position());
+                    match reached {
+                        Ok(position)
+                            if position.partition == partition
+                                && position.block_number == last.start_block => {}
+                        Ok(position) => problems.push(format!(
+                            "locate to P{} B{} ended at P{} B{}",
+                            partition, last.start_block, position.partition, position.block_number
+                        )),
+                        Err(e) => problems.push(format!(
+                            "cannot locate last extent at P{} B{}: {}",
+                            partition, last.start_block, e
+                        )),
+                    }
+                }
+            }
+
+            if !problems.is_empty() {
+                warn!("Suspect extents in {}: {}", path, problems.join("; "));
+                report.suspects.push((path, problems.join("; ")));
+            }
+        }
+        self.invalidate_position_cache();
+
+        Ok(report)
+    }
+
+    /// 比较单个文件，返回 `None` 表示一致，否则返回差异原因
+    fn compare_source_with_tape(&self, source_path: &Path, tape_file: &File) -> Result<Option<String>> {
+        let source_len = std::fs::metadata(source_path)?.len();
+        if source_len != tape_file.length {
+            return Ok(Some(format!(
+                "size differs (source {} bytes, tape {} bytes)",
+                source_len, tape_file.length
+            )));
+        }
+
+        let index_hashes = FileHashes::from_file(tape_file);
+        let source_hashes = hashes_of_source(source_path, &index_hashes)?;
+
+        let index_mismatches = index_hashes.mismatches(&source_hashes);
+        if !index_mismatches.is_empty() {
+            return Ok(Some(format!(
+                "index hash differs from source ({})",
+                index_mismatches.join("; ")
+            )));
+        }
+
+        // SHA256 is always computed, so it is the common ground for source vs tape data
+        let tape_hashes = self.hash_tape_file(tape_file, &FileHashes::default())?;
+        let (source_sha256, tape_sha256) = (
+            source_hashes.sha256.unwrap_or_default(),
+            tape_hashes.sha256.unwrap_or_default(),
+        );
+
+        debug!(
+            "Verified {:?}: source {} / tape {}",
+            source_path, source_sha256, tape_sha256
+        );
+
+        if tape_sha256 != source_sha256 {
+            return Ok(Some(format!(
+                "tape data hash differs (source {}, tape {})",
+                source_sha256, tape_sha256
+            )));
+        }
+
+        Ok(None)
+    }
+
+    /// 读回磁带上的文件数据，计算 `wanted` 中有值的那些算法（外加SHA256）
+    fn hash_tape_file(&self, file: &File, wanted: &FileHashes) -> Result<FileHashes> {
+        let mut calculator = CheckSumBlockwiseCalculator::for_hashes(wanted);
+        self.stream_file_from_tape(file, |chunk| {
+            calculator.propagate(chunk);
+            Ok(())
+        })?;
+        calculator.process_final_block();
+        Ok(calculator.file_hashes())
+    }
+}
+
+/// 递归收集磁带目录下的文件，键为相对路径（'/' 分隔）
+fn collect_tape_files<'a>(dir: &'a Directory, prefix: &str, out: &mut BTreeMap<String, &'a File>) {
+    for file in &dir.contents.files {
+        out.insert(format!("{}{}", prefix, file.name), file);
+    }
+    for subdir in &dir.contents.directories {
+        collect_tape_files(subdir, &format!("{}{}/", prefix, subdir.name), out);
+    }
+}
+
+/// 递归收集源目录下的常规文件，键为相对路径（'/' 分隔，NFC 规范化以匹配索引名称）
+fn collect_source_files(source: &Path) -> Result<BTreeMap<String, PathBuf>> {
+    let mut files = BTreeMap::new();
+
+    if source.is_file() {
+        let name = source
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        files.insert(normalize_name(&name), source.to_path_buf());
+        return Ok(files);
+    }
+
+    for entry in walkdir::WalkDir::new(source).min_depth(1) {
+        let entry = entry.map_err(|e| {
+            RustLtfsError::file_operation(format!("Cannot walk source tree {:?}: {}", source, e))
+        })?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+
+        let relative = entry
+            .path()
+            .strip_prefix(source)
+            .unwrap_or(entry.path())
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect::<Vec<_>>()
+            .join("/");
+        files.insert(normalize_name(&relative), entry.path().to_path_buf());
+    }
+
+    Ok(files)
+}
+
+/// 计算源文件的哈希：`wanted` 中有值的算法外加SHA256（大写十六进制，与写入时记录的格式一致）
+fn hashes_of_source(path: &Path, wanted: &FileHashes) -> Result<FileHashes> {
+    let mut file = std::fs::File::open(path)?;
+    let mut calculator = CheckSumBlockwiseCalculator::for_hashes(wanted);
+    let mut buffer = vec![0u8; 1024 * 1024];
+
+    loop {
+        let n = file.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        calculator.propagate(&buffer[..n]);
+    }
+
+    calculator.process_final_block();
+    Ok(calculator.file_hashes())
+}
@@ -3,10 +3,69 @@
 //! This module provides comprehensive VOL1 label validation and tape format detection.
 //! Supports LTFS format detection with multiple fallback strategies and detailed diagnostics.
 
-use crate::error::Result;
-use super::TapeFormatAnalysis;
+use crate::error::{Result, RustLtfsError};
+use super::{LtfsPartitionLabel, TapeFormatAnalysis};
+use serde::Deserialize;
 use tracing::{debug, info, warn};
 
+/// LTFS label XML (对应LTFSCopyGUI的ltfslabel类)，只取需要的元素，其余忽略
+#[derive(Debug, Deserialize)]
+struct LabelXml {
+    volumeuuid: Option<String>,
+    formattime: Option<String>,
+    partitions: Option<LabelPartitions>,
+    blocksize: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LabelPartitions {
+    index: String,
+    data: String,
+}
+
+/// 解析 LTFS label XML 为结构化的分区标签
+///
+/// `<blocksize>` 是必需的；缺失的 `<partitions>` 按标准布局 (索引a/数据b) 处理。
+/// 标签块之后的填充数据（NUL等）会被忽略。
+pub fn parse_ltfs_label(label_xml: &str) -> Result<LtfsPartitionLabel> {
+    const END_TAG: &str = "</ltfslabel>";
+
+    let start = label_xml
+        .find("<ltfslabel")
+        .ok_or_else(|| RustLtfsError::ltfs_index("No <ltfslabel> element in partition label"))?;
+    let end = label_xml[start..]
+        .find(END_TAG)
+        .map(|pos| start + pos + END_TAG.len())
+        .ok_or_else(|| RustLtfsError::ltfs_index("Partition label is truncated"))?;
+
+    let label: LabelXml = quick_xml::de::from_str(&label_xml[start..end])
+        .map_err(|e| RustLtfsError::ltfs_index(format!("Invalid partition label XML: {}", e)))?;
+
+    let blocksize = label.blocksize.filter(|&size| size > 0).ok_or_else(|| {
+        RustLtfsError::ltfs_index("Partition label has no valid <blocksize> element")
+    })?;
+    let mut parsed = LtfsPartitionLabel {
+        blocksize,
+        volume_uuid: label.volumeuuid.map(|s| s.trim().to_string()),
+        format_time: label.formattime.map(|s| s.trim().to_string()),
+        ..Default::default()
+    };
+    if let Some(partitions) = label.partitions {
+        parsed.index_partition = partitions.index.trim().to_string();
+        parsed.data_partition = partitions.data.trim().to_string();
+    }
+    Ok(parsed)
+}
+
+/// 从VOL1标签中取卷序列号 (bytes 4-9)；不是VOL1标签或序列号为空时返回None
+pub fn vol1_volume_serial(buffer: &[u8]) -> Option<String> {
+    if buffer.len() < 80 || !buffer.starts_with(b"VOL1") {
+        return None;
+    }
+    let serial = String::from_utf8_lossy(&buffer[4..10]).trim().to_string();
+    (!serial.is_empty()).then_some(serial)
+}
+
 /// Enhanced VOL1 label validation with comprehensive format detection
 /// 增强版 VOL1 标签验证：支持多种磁带格式检测和详细诊断
 pub fn parse_vol1_label(buffer: &[u8]) -> Result<bool> {
@@ -188,6 +247,37 @@ pub fn analyze_tape_format_enhanced(vol1_label: &[u8]) -> TapeFormatAnalysis {
     TapeFormatAnalysis::UnknownFormat
 }
 
+/// 将索引读取失败归类为格式错误，供调用方按错误类型分支
+///
+/// `vol1_analysis` 为索引分区开头标签的分析结果，VOL1验证通过时为 `None`。
+/// 只有索引/解析类错误会被归类；设备错误和已归类的格式错误原样返回。
+pub fn classify_format_detection_error(
+    error: RustLtfsError,
+    vol1_analysis: Option<&TapeFormatAnalysis>,
+) -> RustLtfsError {
+    let message = match error {
+        RustLtfsError::LtfsIndex(message) | RustLtfsError::Parse(message) => message,
+        other => return other,
+    };
+
+    match vol1_analysis {
+        Some(TapeFormatAnalysis::BlankTape) => RustLtfsError::blank_tape(format!(
+            "No label at the beginning of the index partition ({})",
+            message
+        )),
+        Some(TapeFormatAnalysis::LegacyTape(format_name)) => RustLtfsError::non_ltfs_format(
+            format!("{} found instead of an LTFS VOL1 label", format_name),
+        ),
+        Some(TapeFormatAnalysis::CorruptedLabel) => {
+            RustLtfsError::non_ltfs_format("VOL1 label is damaged or unreadable")
+        }
+        Some(TapeFormatAnalysis::UnknownFormat) => {
+            RustLtfsError::non_ltfs_format("Unrecognised data at the beginning of the index partition")
+        }
+        Some(TapeFormatAnalysis::PossibleLTFS) | None => RustLtfsError::corrupted_index(message),
+    }
+}
+
 /// Search for LTFS identifier in alternative positions within VOL1 label
 fn search_ltfs_identifier_in_vol1(vol1_label: &[u8]) -> Option<usize> {
     let ltfs_signature = b"LTFS";
@@ -512,3 +602,42 @@ fn identify_tape_patterns(data: &[u8]) -> Option<String> {
 
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// IBM LTFS风格的分区标签，后接块填充的NUL
+    fn label_xml(blocksize: u32) -> String {
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<ltfslabel version=\"2.4.0\">\n\
+             \t<creator>IBM LTFS 2.4.5.1 - Linux - mkltfs</creator>\n\
+             \t<formattime>2024-03-01T09:30:00.000000000Z</formattime>\n\
+             \t<volumeuuid>3f2a6c1e-5b7d-4e8f-9a0b-1c2d3e4f5a6b</volumeuuid>\n\
+             \t<location><partition>a</partition></location>\n\
+             \t<partitions><index>a</index><data>b</data></partitions>\n\
+             \t<blocksize>{}</blocksize>\n\
+             \t<compression>true</compression>\n</ltfslabel>\n{}",
+            blocksize,
+            "\0".repeat(64)
+        )
+    }
+
+    #[test]
+    fn label_block_size_is_detected() {
+        for blocksize in [262144, 1048576] {
+            let label = parse_ltfs_label(&label_xml(blocksize)).unwrap();
+            assert_eq!(label.blocksize, blocksize);
+            assert_eq!(label.index_partition, "a");
+            assert_eq!(label.data_partition, "b");
+            assert_eq!(label.volume_uuid.as_deref(), Some("3f2a6c1e-5b7d-4e8f-9a0b-1c2d3e4f5a6b"));
+        }
+    }
+
+    #[test]
+    fn label_without_block_size_is_rejected() {
+        let xml = label_xml(0);
+        assert!(parse_ltfs_label(&xml).is_err());
+        assert!(parse_ltfs_label(&xml.replace("<blocksize>0</blocksize>", "")).is_err());
+    }
+}
@@ -1,12 +1,16 @@
-use super::TapeOperations;
+use super::{DryRunReport, TapeOperations, WriteSessionResult};
 use super::hash::CheckSumBlockwiseCalculator;
+use super::read_ahead::ReadAheadFile;
+use super::sparse::{ExtentBuilder, HoleDetector};
 use super::utils::format_ltfs_timestamp;
 use crate::error::{Result, RustLtfsError};
 use std::io::BufRead;
-use std::path::Path;
-use tokio::fs::File;
-use tokio::io::{AsyncReadExt, BufReader};
-use tracing::{debug, error, info};
+use std::path::{Path, PathBuf};
+use sha2::{Digest, Sha256};
+use tracing::{debug, error, info, warn};
+
+/// 空间检查时为索引预留的字节数：每个索引条目（文件/目录）的XML约占1KB
+const INDEX_BYTES_PER_ENTRY: u64 = 1024;
 
 /// Partition write state (corresponds to VB.NET partition management)
 pub struct PartitionWriteState {
@@ -14,6 +18,12 @@ pub struct PartitionWriteState {
     pub current_block: u64,
 }
 
+/// 每个分区开头的LTFS卷标结构（VOL1标签、FileMark、LTFS标签、FileMark）占用的块数
+///
+/// 写入起点通常就是数据分区的EOD，由索引位置检查保证不会覆盖最新索引；
+/// 没有索引可参照时，至少不能覆盖卷标结构。
+const LABEL_CONSTRUCT_BLOCKS: u64 = 4;
+
 /// TapeOperations写入操作实现
 impl TapeOperations {
     /// Locate to write position precisely (corresponds to VB.NET LocateToWritePosition)
@@ -22,6 +32,7 @@ impl TapeOperations {
             "Locating to write position with ExtraPartitionCount = {}",
             self.get_extra_partition_count()
         );
+        self.invalidate_position_cache();
 
         // Read current position
         let current_pos = self.scsi.read_position()?;
@@ -81,6 +92,23 @@ impl TapeOperations {
             }
         }
 
+        // Expert override: write from an explicit block, overwriting whatever follows it
+        if let Some(start_block) = self.write_options.data_start_block_override {
+            warn!(
+                "Writing from block {} of partition {} (override, EOD was block {}); data after it is overwritten",
+                start_block, data_partition, target_block
+            );
+            self.scsi.locate_block(data_partition, start_block)?;
+            target_block = start_block;
+        } else if self.schema.is_none() && target_block < LABEL_CONSTRUCT_BLOCKS {
+            return Err(RustLtfsError::tape_device(format!(
+                "Write position p{}b{} would overwrite the LTFS label construct (blocks 0-{}); is the tape LTFS formatted?",
+                data_partition,
+                target_block,
+                LABEL_CONSTRUCT_BLOCKS - 1
+            )));
+        }
+
         // Validate position is reasonable (对应LTFSCopyGUI的分区验证逻辑)
         if let Some(ref schema) = &self.schema {
             let schema_partition = if schema.location.partition == "b" {
@@ -127,40 +155,49 @@ impl TapeOperations {
             "Streaming file write to tape: {:?} -> {}",
             source_path, target_path
         );
+        self.invalidate_position_cache();
 
         // Check stop flag
+        self.check_cancelled(&format!("before writing {}", target_path))?;
 
+        // Get file metadata (without following symlinks)
+        let metadata = tokio::fs::symlink_metadata(source_path).await.map_err(|e| {
+            RustLtfsError::file_operation(format!("Unable to get file information: {}", e))
+        })?;
+        let metadata = self.followed_metadata(source_path, metadata);
 
+        // Skip .xattr files and (if configured) symlinks
+        if self.should_skip_source_file(source_path, &metadata) {
+            return Ok(());
+        }
 
+        // WORM media is append-only: reject overwrites before any data is written
+        self.ensure_worm_allows_write(target_path)?;
 
-        // Get file metadata
-        let metadata = tokio::fs::metadata(source_path).await.map_err(|e| {
-            RustLtfsError::file_operation(format!("Unable to get file information: {}", e))
-        })?;
+        // Symlinks are stored as index entries with their link target, no data is written
+        if metadata.file_type().is_symlink() {
+            self.write_symlink_to_index(source_path, target_path)?;
+            return self.span_record_file(target_path, 0, 0, 0);
+        }
 
         let file_size = metadata.len();
         info!("File size: {} bytes", file_size);
 
-        // Skip .xattr files
-        if let Some(ext) = source_path.extension() {
-            if ext.to_string_lossy().to_lowercase() == "xattr" {
-                info!("Skipping .xattr file: {:?}", source_path);
-                return Ok(());
-            }
+        // Fail before writing rather than hitting early-warning EOM mid-file;
+        // a spanning set instead splits the file at end of media
+        if self.span.is_none() {
+            self.check_available_space(file_size)?;
         }
 
-        // Skip symlinks if configured
-        if self.write_options.skip_symlinks && metadata.file_type().is_symlink() {
-            info!("Skipping symlink: {:?}", source_path);
-            return Ok(());
+        // Dry run: record the file in the index at a simulated position, nothing goes to tape
+        if self.write_options.dry_run {
+            return self.plan_file_write(source_path, target_path, file_size);
         }
 
-        // Check available tape space
-        if let Err(e) = self.check_available_space(file_size) {
-            return Err(RustLtfsError::tape_device(format!(
-                "Insufficient tape space: {}",
-                e
-            )));
+        // A single-file write is its own session; directory writes started one before the walk
+        if self.write_progress.start_time.is_none() {
+            self.ensure_clean_for_write(Some(file_size))?;
+            self.write_progress.begin_session(Some(file_size));
         }
 
         // Locate to write position
@@ -174,114 +211,87 @@ impl TapeOperations {
         // Get write start position
         let write_start_position = self.scsi.read_position()?;
 
-        // Open file and create buffered reader
-        let file = File::open(source_path)
-            .await
-            .map_err(|e| RustLtfsError::file_operation(format!("Unable to open file: {}", e)))?;
-
-        let mut buf_reader = BufReader::with_capacity(
-            self.block_size as usize * 32, // 32-block buffer
-            file,
-        );
-
-        // Initialize hash calculator (if enabled) based on configuration
-        let mut hash_calculator = if self.write_options.hash_on_write {
-            Some(CheckSumBlockwiseCalculator::new_with_options(
-                &self.write_options,
-            ))
+        // Holes are skipped on tape and recorded as gaps between extents
+        let holes = if self.write_options.sparse {
+            let file = std::fs::File::open(source_path)
+                .map_err(|e| RustLtfsError::file_operation(format!("Unable to open file: {}", e)))?;
+            Some(HoleDetector::for_file(&file, file_size))
         } else {
             None
         };
 
+        // A file split at the end of the previous cartridge continues from the split point
+        let span_offset = self.span_continuation_offset(target_path).unwrap_or(0);
+        if span_offset > 0 {
+            info!("Continuing split file {} from byte {}", target_path, span_offset);
+        }
+
+        // Blocks are read and hashed by a read-ahead task, possibly started while the
+        // previous file was on tape; a continuation piece does not see the whole file,
+        // so it is not hashed
+        let mut source = match self.take_read_ahead(source_path) {
+            Some(source) => source,
+            None => self.start_read_ahead(source_path, span_offset),
+        };
+
 
         let mut total_blocks_written = 0u32;
         let mut total_bytes_written = 0u64;
+        let mut file_offset = span_offset;
+        let mut extents = ExtentBuilder::new(&write_start_position);
+        let mut split_at_end_of_media = None;
+        // Verify mode compares SHA-256 of the read-back extents with the blocks sent to tape
+        let mut verify_hasher = self.write_options.verify.then(Sha256::new);
         let write_start_time = std::time::Instant::now();
         let mut last_progress_bytes = 0u64;
         let mut last_progress_time = std::time::Instant::now();
 
-        // Choose processing strategy based on file size
-        if file_size <= self.block_size as u64 {
-            // Small file: read and write in one go
-            info!("Processing small file ({} bytes)", file_size);
-
-            let mut buffer = vec![0u8; self.block_size as usize];
-            let bytes_read = buf_reader
-                .read(&mut buffer[..file_size as usize])
-                .await
-                .map_err(|e| {
-                    RustLtfsError::file_operation(format!("Failed to read file: {}", e))
-                })?;
-
-            if bytes_read != file_size as usize {
-                return Err(RustLtfsError::file_operation(format!(
-                    "Bytes read mismatch: expected {} bytes, actually read {} bytes",
-                    file_size, bytes_read
-                )));
-            }
-
-            // Calculate hash
-            if let Some(ref mut calc) = hash_calculator {
-                calc.propagate(&buffer[..bytes_read]);
-                calc.process_final_block();
-            }
-
-
-
-            // Write to tape (variable-length for last/short block)
-            let blocks_written = self.scsi.write_blocks(1, &buffer[..bytes_read])?;
+        // Block-wise streaming (same as LTFSCopyGUI): only one block is held in memory.
+        // Every block except the last is filled completely so extents stay block-aligned;
+        // the final partial block is written short (variable-length) rather than padded.
+        // Windows SCSI pass-through doesn't support multi-block batch writes
+        info!(
+            "Streaming {} bytes in {}-byte blocks",
+            file_size, self.block_size
+        );
 
-            if blocks_written != 1 {
-                return Err(RustLtfsError::scsi(format!(
-                    "Expected to write 1 block, but actually wrote {} blocks",
-                    blocks_written
-                )));
+        loop {
+            if let Err(e) = self.check_cancelled(&format!("while writing {}", target_path)) {
+                self.terminate_partial_write(target_path, total_bytes_written)?;
+                return Err(e);
             }
 
-            total_blocks_written = blocks_written;
-            total_bytes_written = bytes_read as u64;
-
-            // Update write progress counters for small-file write
-            self.write_progress.current_bytes_processed += bytes_read as u64;
-            self.write_progress.current_files_processed += 1;
-            self.write_progress.files_written += 1;
-            self.write_progress.bytes_written += bytes_read as u64;
-        } else {
-            // Large file: block-wise streaming (same as LTFSCopyGUI)
-            // Windows SCSI pass-through doesn't support multi-block batch writes
-            info!(
-                "Processing large file ({} bytes), using block-wise streaming",
-                file_size
-            );
-
-            let mut buffer = vec![0u8; self.block_size as usize];
-            let mut remaining_bytes = file_size;
-            
-            info!("Starting write loop (Block size: {})", self.block_size);
-
-            while remaining_bytes > 0 {
-                // Calculate bytes to read for current block
-                let bytes_to_read = std::cmp::min(remaining_bytes, self.block_size as u64) as usize;
-
-                // Read data from file
-                let bytes_read = buf_reader
-                    .read(&mut buffer[..bytes_to_read])
-                    .await
-                    .map_err(|e| {
-                        RustLtfsError::file_operation(format!("Failed to read file: {}", e))
-                    })?;
-
-                if bytes_read == 0 {
-                    break; // End of file
-                }
-
-                // Calculate hash
-                if let Some(ref mut calc) = hash_calculator {
-                    calc.propagate(&buffer[..bytes_read]);
-                }
+            let Some(buffer) = source.next_block().await? else {
+                break; // End of file
+            };
+            let bytes_read = buffer.len();
 
+            let block = &buffer[..];
+            if holes.as_ref().is_some_and(|h| h.is_hole(file_offset, block)) {
+                debug!("Skipping hole block at file offset {}", file_offset);
+            } else {
                 // Write single block to tape (like LTFSCopyGUI)
-                let blocks_written = self.scsi.write_blocks(1, &buffer[..bytes_read])?;
+                let blocks_written = match self.scsi.write_blocks(1, block) {
+                    Ok(blocks) => blocks,
+                    Err(e) if e.is_end_of_media() && self.span.is_some() => {
+                        // Early warning reports a block that was written; volume overflow one that was not
+                        if self.scsi.read_position()?.block_number > extents.next_block() {
+                            extents.push_block(file_offset, bytes_read as u64);
+                            self.write_progress.bytes_written += bytes_read as u64;
+                            self.write_progress.current_bytes_processed += bytes_read as u64;
+                            file_offset += bytes_read as u64;
+                        }
+                        split_at_end_of_media = Some(e);
+                        break;
+                    }
+                    Err(e) if e.is_end_of_media() => {
+                        // The partial file is not indexed, so it must not count as progress
+                        self.write_progress.current_bytes_processed -= file_offset;
+                        self.write_progress.bytes_written -= total_bytes_written;
+                        return Err(self.stop_at_end_of_media(target_path, total_bytes_written, e));
+                    }
+                    Err(e) => return Err(e),
+                };
 
                 if blocks_written != 1 {
                     return Err(RustLtfsError::scsi(format!(
@@ -290,56 +300,98 @@ impl TapeOperations {
                     )));
                 }
 
+                if let Some(ref mut hasher) = verify_hasher {
+                    hasher.update(block);
+                }
+                extents.push_block(file_offset, bytes_read as u64);
                 total_blocks_written += blocks_written;
                 total_bytes_written += bytes_read as u64;
-                remaining_bytes -= bytes_read as u64;
-
-                // Update progress
-                self.write_progress.current_bytes_processed += bytes_read as u64;
-
-                // Log progress every 100MB
-                let bytes_since_last_log = total_bytes_written - last_progress_bytes;
-                if bytes_since_last_log >= 100 * 1024 * 1024 {
-                    let elapsed = write_start_time.elapsed();
-                    let elapsed_secs = elapsed.as_secs_f64();
-                    
-                    let overall_speed_mbps = if elapsed_secs > 0.0 {
-                        (total_bytes_written as f64 / (1024.0 * 1024.0)) / elapsed_secs
-                    } else {
-                        0.0
-                    };
-                    
-                    let recent_elapsed = last_progress_time.elapsed().as_secs_f64();
-                    let recent_speed_mbps = if recent_elapsed > 0.0 {
-                        (bytes_since_last_log as f64 / (1024.0 * 1024.0)) / recent_elapsed
-                    } else {
-                        0.0
-                    };
-                    
-                    let gb_written = total_bytes_written as f64 / (1024.0 * 1024.0 * 1024.0);
-                    
-                    info!(
-                        "📊 Write progress: {:.2} GB written | Speed: {:.2} MB/s (avg: {:.2} MB/s) | Blocks: {}",
-                        gb_written,
-                        recent_speed_mbps,
-                        overall_speed_mbps,
-                        total_blocks_written
-                    );
-                    
-                    last_progress_bytes = total_bytes_written;
-                    last_progress_time = std::time::Instant::now();
-                }
+                self.write_progress.bytes_written += bytes_read as u64;
             }
+            file_offset += bytes_read as u64;
 
-            // Complete hash calculation
-            if let Some(ref mut calc) = hash_calculator {
-                calc.process_final_block();
+            // Update progress
+            self.write_progress.current_bytes_processed += bytes_read as u64;
+            self.write_progress.throughput_mbps = self.scsi.last_throughput_mbps();
+            self.report_write_progress();
+
+            // Log progress every 100MB
+            let bytes_since_last_log = total_bytes_written - last_progress_bytes;
+            if bytes_since_last_log >= 100 * 1024 * 1024 {
+                let elapsed = write_start_time.elapsed();
+                let elapsed_secs = elapsed.as_secs_f64();
+                
+                let overall_speed_mbps = if elapsed_secs > 0.0 {
+                    (total_bytes_written as f64 / (1024.0 * 1024.0)) / elapsed_secs
+                } else {
+                    0.0
+                };
+                
+                let recent_elapsed = last_progress_time.elapsed().as_secs_f64();
+                let recent_speed_mbps = if recent_elapsed > 0.0 {
+                    (bytes_since_last_log as f64 / (1024.0 * 1024.0)) / recent_elapsed
+                } else {
+                    0.0
+                };
+                
+                let gb_written = total_bytes_written as f64 / (1024.0 * 1024.0 * 1024.0);
+                
+                info!(
+                    "📊 Write progress: {:.2} GB written | Speed: {:.2} MB/s (avg: {:.2} MB/s) | Blocks: {}",
+                    gb_written,
+                    recent_speed_mbps,
+                    overall_speed_mbps,
+                    total_blocks_written
+                );
+                
+                last_progress_bytes = total_bytes_written;
+                last_progress_time = std::time::Instant::now();
             }
+
+        }
+
+        if let Some(e) = split_at_end_of_media {
+            return self.split_at_end_of_media(
+                source_path,
+                target_path,
+                span_offset..file_offset,
+                file_size,
+                extents.into_extents(),
+                e,
+            );
+        }
+
+        // Hashes were completed by the read-ahead task
+        let hashes = source.take_hashes();
+
+        self.write_progress.files_written += 1;
+
+        // The index must describe exactly what is on tape
+        if file_offset != file_size {
+            warn!(
+                "File {:?} changed size while writing: expected {} bytes, wrote {} bytes",
+                source_path, file_size, file_offset
+            );
+        }
+        let file_size = file_offset;
+        let extents = extents.into_extents();
+        if total_bytes_written < file_size - span_offset.min(file_size) {
+            info!(
+                "Sparse file {:?}: {} of {} bytes stored in {} extent(s)",
+                source_path,
+                total_bytes_written,
+                file_size,
+                extents.len()
+            );
         }
 
         // Write file mark to separate files
         self.scsi.write_filemarks(1)?;
 
+        if let Some(hasher) = verify_hasher {
+            self.verify_written_file(target_path, &extents, hasher.finalize().to_vec())?;
+        }
+
         let write_duration = write_start_time.elapsed();
         let speed_mbps = if write_duration.as_millis() > 0 {
             (total_bytes_written as f64 / (1024.0 * 1024.0))
@@ -354,27 +406,27 @@ impl TapeOperations {
         );
 
         // Update LTFS index with computed hashes
-        if let Some(hash_calc) = &hash_calculator {
-            let hashes = hash_calc.get_enabled_hashes(&self.write_options);
+        if let Some(hashes) = hashes {
             self.update_index_for_file_write_enhanced(
                 source_path,
                 target_path,
                 file_size,
-                &write_start_position,
+                extents,
                 Some(hashes),
             )?;
         } else {
-            self.update_index_for_file_write(
-                source_path,
-                target_path,
-                file_size,
-                &write_start_position,
-            )?;
+            self.update_index_for_file_write(source_path, target_path, file_size, extents)?;
         }
+        self.span_record_file(
+            target_path,
+            span_offset,
+            file_size.saturating_sub(span_offset),
+            file_size,
+        )?;
 
         // Update progress counters
         self.write_progress.current_files_processed += 1;
-        self.write_progress.total_bytes_unindexed += file_size;
+        self.write_progress.total_bytes_unindexed += total_bytes_written;
 
 
         // For testing and small files, we automatically force index write when total unindexed data is small
@@ -382,11 +434,10 @@ impl TapeOperations {
             || (self.write_progress.total_bytes_unindexed < 100 * 1024 * 1024 && // Less than 100MB
                                   self.write_progress.current_files_processed <= 10); // And few files
 
-        if self.write_progress.total_bytes_unindexed >= self.write_options.index_write_interval
-            || should_force_index
-        {
+        let interval_reached = self.index_interval_reached();
+        if interval_reached || should_force_index {
             info!("Index write triggered: interval_reached={}, should_force={}, total_unindexed={}, files_processed={}",
-                  self.write_progress.total_bytes_unindexed >= self.write_options.index_write_interval,
+                  interval_reached,
                   should_force_index && !self.write_options.force_index,
                   self.write_progress.total_bytes_unindexed,
                   self.write_progress.current_files_processed);
@@ -412,6 +463,7 @@ impl TapeOperations {
         _estimated_size: Option<u64>,
     ) -> Result<()> {
         info!("Writing from reader stream to tape: {}", target_path);
+        self.invalidate_position_cache();
 
         // 🔒 CRITICAL SAFETY CHECK: Ensure index exists before writing
         // This prevents data loss by ensuring we always have the existing tape contents
@@ -435,6 +487,15 @@ impl TapeOperations {
             self.index = Some(self.create_new_ltfs_index());
         }
 
+        // WORM media is append-only: reject overwrites before any data is written
+        self.ensure_worm_allows_write(target_path)?;
+
+        // The length of a stream is unknown, so there is no ETA
+        if self.write_progress.start_time.is_none() {
+            self.ensure_clean_for_write(None)?;
+            self.write_progress.begin_session(None);
+        }
+
         // Prepare for writing to tape
         self.scsi.locate_to_eod(1)?;
 
@@ -463,6 +524,9 @@ impl TapeOperations {
         let write_start_time = std::time::Instant::now();
         let mut last_progress_bytes = 0u64;
         let mut last_progress_time = std::time::Instant::now();
+
+        // Stream data can't be re-read, so verify mode compares SHA-256 of the read-back data
+        let mut verify_hasher = self.write_options.verify.then(Sha256::new);
         
         info!(
             "Starting streaming write (Block size: {} bytes, single-block mode)",
@@ -470,6 +534,11 @@ impl TapeOperations {
         );
 
         loop {
+            if let Err(e) = self.check_cancelled(&format!("while writing {}", target_path)) {
+                self.terminate_partial_write(target_path, total_bytes_written)?;
+                return Err(e);
+            }
+
             // Read data from the stream
             let bytes_read = reader.read(&mut read_buffer).map_err(|e| {
                 RustLtfsError::file_operation(format!("Failed to read from input stream: {}", e))
@@ -479,7 +548,17 @@ impl TapeOperations {
                 // EOF reached - write any remaining data in buffer as final block
                 if buffer_fill > 0 {
                     info!("Writing final partial block: {} bytes", buffer_fill);
-                    let blocks_written = self.scsi.write_blocks(1, &write_buffer[..buffer_fill])? as u64;
+                    if let Some(ref mut hasher) = verify_hasher {
+                        hasher.update(&write_buffer[..buffer_fill]);
+                    }
+                    let blocks_written = match self.scsi.write_blocks(1, &write_buffer[..buffer_fill]) {
+                        Ok(blocks) => blocks as u64,
+                        Err(e) if e.is_end_of_media() => {
+                            self.write_progress.current_bytes_processed -= total_bytes_written;
+                            return Err(self.stop_at_end_of_media(target_path, total_bytes_written, e));
+                        }
+                        Err(e) => return Err(e),
+                    };
                     total_blocks_written += blocks_written;
                     total_bytes_written += buffer_fill as u64;
                     self.write_progress.current_bytes_processed += buffer_fill as u64;
@@ -502,7 +581,17 @@ impl TapeOperations {
                 
                 // If buffer is full, write single block to tape
                 if buffer_fill == block_size {
-                    let blocks_written = self.scsi.write_blocks(1, &write_buffer)? as u64;
+                    if let Some(ref mut hasher) = verify_hasher {
+                        hasher.update(&write_buffer);
+                    }
+                    let blocks_written = match self.scsi.write_blocks(1, &write_buffer) {
+                        Ok(blocks) => blocks as u64,
+                        Err(e) if e.is_end_of_media() => {
+                            self.write_progress.current_bytes_processed -= total_bytes_written;
+                            return Err(self.stop_at_end_of_media(target_path, total_bytes_written, e));
+                        }
+                        Err(e) => return Err(e),
+                    };
                     total_blocks_written += blocks_written;
                     total_bytes_written += block_size as u64;
                     self.write_progress.current_bytes_processed += block_size as u64;
@@ -510,6 +599,9 @@ impl TapeOperations {
                 }
             }
             
+            self.write_progress.throughput_mbps = self.scsi.last_throughput_mbps();
+            self.report_write_progress();
+
             // Log progress every 100MB with detailed statistics
             let bytes_since_last_log = total_bytes_written - last_progress_bytes;
             if bytes_since_last_log >= 100 * 1024 * 1024 {
@@ -544,6 +636,12 @@ impl TapeOperations {
             }
         }
 
+        // Ctrl+C also ends a piped producer, so an EOF may really be a cancelled stream
+        if let Err(e) = self.check_cancelled(&format!("while writing {}", target_path)) {
+            self.terminate_partial_write(target_path, total_bytes_written)?;
+            return Err(e);
+        }
+
         let total_elapsed = write_start_time.elapsed();
         let final_speed_mbps = if total_elapsed.as_secs_f64() > 0.0 {
             (total_bytes_written as f64 / (1024.0 * 1024.0)) / total_elapsed.as_secs_f64()
@@ -563,6 +661,15 @@ impl TapeOperations {
         // Write filemark to separate this file from next
         self.scsi.write_filemarks(1)?;
 
+        if let Some(hasher) = verify_hasher {
+            self.verify_written_stream(
+                target_path,
+                &write_start_position,
+                total_bytes_written,
+                hasher.finalize().to_vec(),
+            )?;
+        }
+
         // Add file to index
         if let Some(mut index) = self.index.take() {
             // Create file extent using actual bytes written
@@ -614,12 +721,12 @@ impl TapeOperations {
         let should_force_index = if self.write_progress.current_files_processed == 1 {
             true
         } else {
-            self.write_progress.total_bytes_unindexed >= self.write_options.index_write_interval
+            self.index_interval_reached()
         };
 
         if should_force_index {
             debug!(
-                "Updating index: total_unindexed={} >= interval={}",
+                "Updating index: total_unindexed={}, interval={}",
                 self.write_progress.total_bytes_unindexed,
                 self.write_options.index_write_interval
             );
@@ -630,11 +737,81 @@ impl TapeOperations {
         Ok(())
     }
 
+    /// 是否达到中间索引写入间隔：未写入索引的数据量达到 `index_write_interval`，
+    /// 或者距离上次写入索引已超过 `index_write_interval_time`（且期间有新数据）
+    fn index_interval_reached(&self) -> bool {
+        let unindexed = self.write_progress.total_bytes_unindexed;
+        if unindexed >= self.write_options.index_write_interval {
+            return true;
+        }
+        match self.write_options.index_write_interval_time {
+            Some(interval) if unindexed > 0 && self.last_index_write.elapsed() >= interval => {
+                info!(
+                    "Index interval of {:?} elapsed with {} bytes unindexed",
+                    interval, unindexed
+                );
+                true
+            }
+            _ => false,
+        }
+    }
+
     /// Write directory to tape (enhanced version based on LTFSCopyGUI AddDirectory)
+    ///
+    /// Individual file failures do not abort the session; they are collected in
+    /// the returned `WriteSessionResult::files_failed`.
     pub async fn write_directory_to_tape(
         &mut self,
         source_dir: &Path,
         target_path: &str,
+    ) -> Result<WriteSessionResult> {
+        let mut session = WriteSessionResult::default();
+
+        // Exclude patterns match paths relative to the directory being written
+        let previous_root = self.write_source_root.replace(source_dir.to_path_buf());
+        if !self.write_options.dry_run {
+            let mut ancestors: Vec<PathBuf> = self.loop_guard_path(source_dir).into_iter().collect();
+            let session_bytes = self.source_total_bytes(source_dir, 0, &mut ancestors);
+            info!("Source holds {} bytes to write", session_bytes);
+            if let Err(e) = self.ensure_clean_for_write(Some(session_bytes)) {
+                self.write_source_root = previous_root;
+                return Err(e);
+            }
+            self.write_progress.begin_session(Some(session_bytes));
+        }
+        let mut ancestors: Vec<PathBuf> = self.loop_guard_path(source_dir).into_iter().collect();
+        let result = self
+            .write_directory_to_tape_recursive(source_dir, target_path, 0, &mut ancestors, &mut session)
+            .await;
+        self.write_source_root = previous_root;
+        self.read_ahead.clear();
+        result?;
+
+        session.final_position = self.scsi.read_position().ok();
+        session.index_generation = self.index.as_ref().map(|idx| idx.generationnumber);
+
+        info!(
+            "Write session summary: {} files written ({} bytes), {} skipped, {} failed",
+            session.files_written,
+            session.bytes_written,
+            session.files_skipped,
+            session.files_failed.len()
+        );
+
+        Ok(session)
+    }
+
+    /// Recursive worker for `write_directory_to_tape`, accumulating into `session`
+    ///
+    /// `depth` is the level of `source_dir` below the written source; `ancestors` holds the
+    /// canonical paths of the directories being walked when following symlinks.
+    async fn write_directory_to_tape_recursive(
+        &mut self,
+        source_dir: &Path,
+        target_path: &str,
+        depth: usize,
+        ancestors: &mut Vec<PathBuf>,
+        session: &mut WriteSessionResult,
     ) -> Result<()> {
         info!(
             "Writing directory to tape: {:?} -> {}",
@@ -644,7 +821,7 @@ impl TapeOperations {
 
 
         // Skip symlinks if configured (对应LTFSCopyGUI的SkipSymlink)
-        let metadata = tokio::fs::metadata(source_dir).await.map_err(|e| {
+        let metadata = tokio::fs::symlink_metadata(source_dir).await.map_err(|e| {
             RustLtfsError::file_operation(format!("Cannot get directory metadata: {}", e))
         })?;
 
@@ -653,119 +830,736 @@ impl TapeOperations {
             return Ok(());
         }
 
-        // Create or get directory in LTFS index
-        let _dir_name = source_dir
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("unknown")
-            .to_string();
-
-        // Note: Directory structure is automatically created by ensure_directory_path_exists
+        // Note: Directory structure is automatically created by LtfsIndex::get_or_create_directory
         // when files are added, so we don't need to explicitly create directories here.
         // Explicit creation was causing directories to be added at root level incorrectly.
 
-        // Get list of files and subdirectories
-        let mut entries = tokio::fs::read_dir(source_dir)
-            .await
-            .map_err(|e| RustLtfsError::file_operation(format!("Cannot read directory: {}", e)))?;
-
-        let mut files = Vec::new();
+        // Backpressure: at most `max_queued_files` files are queued at once. A larger
+        // directory is enumerated again for each batch, every pass keeping the smallest
+        // names after the previous batch, so files reach tape in name order whatever
+        // order read_dir returns them in.
         let mut subdirs = Vec::new();
+        let mut is_empty = true;
+        let mut after = None;
 
-        while let Some(entry) = entries.next_entry().await.map_err(|e| {
-            RustLtfsError::file_operation(format!("Cannot read directory entry: {}", e))
-        })? {
-            let entry_path = entry.path();
-            let entry_metadata = entry.metadata().await.map_err(|e| {
-                RustLtfsError::file_operation(format!("Cannot get entry metadata: {}", e))
-            })?;
+        for pass in 0.. {
+            let mut entries = tokio::fs::read_dir(source_dir)
+                .await
+                .map_err(|e| RustLtfsError::file_operation(format!("Cannot read directory: {}", e)))?;
+            let mut batch = NameOrderedBatch::new(self.write_options.max_queued_files, after.take());
+
+            while let Some(entry) = entries.next_entry().await.map_err(|e| {
+                RustLtfsError::file_operation(format!("Cannot read directory entry: {}", e))
+            })? {
+                is_empty = false;
+                let name = entry.file_name();
+                // 后续轮次只需要文件，名称不在本批范围内的条目不必再取元数据
+                if pass > 0 && !batch.accepts(&name) {
+                    continue;
+                }
+                let entry_path = entry.path();
+                let entry_metadata = entry.metadata().await.map_err(|e| {
+                    RustLtfsError::file_operation(format!("Cannot get entry metadata: {}", e))
+                })?;
+                let entry_metadata = self.followed_metadata(&entry_path, entry_metadata);
+
+                if entry_metadata.is_file() || entry_metadata.file_type().is_symlink() {
+                    batch.offer(name, (entry_path, entry_metadata));
+                } else if entry_metadata.is_dir() && pass == 0 {
+                    subdirs.push(entry_path);
+                }
+            }
+
+            let (files, more) = batch.finish();
+            if more {
+                debug!("File queue of {:?} reached {} entries, writing batch {}", source_dir, files.len(), pass + 1);
+            }
+            after = files.last().map(|(name, _)| name.clone());
+            self.write_file_batch(files.into_iter().map(|(_, file)| file).collect(), source_dir, target_path, session)
+                .await?;
+            if !more {
+                break;
+            }
+        }
+        subdirs.sort();
+
+        // 空目录没有文件来触发目录创建，显式记入索引，提取时才能还原
+        if is_empty && !self.write_options.dry_run {
+            if self.index.is_none() {
+                self.index = Some(self.create_new_ltfs_index());
+            }
+            if let Some(index) = self.index.as_mut() {
+                index.get_or_create_directory(target_path);
+            }
+            self.modified = true;
+            self.mark_directory_modified(target_path);
+        }
+
+        // Recursively process subdirectories
+        for subdir_path in subdirs {
+            if self.is_excluded_source(&subdir_path) {
+                info!("Skipping excluded directory: {:?}", subdir_path);
+                session.files_skipped += 1;
+                continue;
+            }
+
+            let subdir_name = subdir_path
+                .file_name()
+                .map(crate::ltfs_index::name::name_from_os_str)
+                .unwrap_or_else(|| "unknown".to_string());
+            let subdir_target = format!("{}/{}", target_path, subdir_name);
 
-            if entry_metadata.is_file() {
-                files.push(entry_path);
-            } else if entry_metadata.is_dir() {
-                subdirs.push(entry_path);
+            if !self.within_max_depth(depth + 1) {
+                info!("Skipping directory below --max-depth: {:?}", subdir_path);
+                session.files_skipped += 1;
+                continue;
+            }
+
+            // A followed symlink back to a directory being walked would recurse forever
+            let canonical = self.loop_guard_path(&subdir_path);
+            if let Some(ref canonical) = canonical {
+                if ancestors.contains(canonical) {
+                    warn!("Skipping symlink loop: {:?} -> {:?}", subdir_path, canonical);
+                    session.files_skipped += 1;
+                    continue;
+                }
+                ancestors.push(canonical.clone());
+            }
+
+            // Recursively write subdirectory
+            let result = Box::pin(self.write_directory_to_tape_recursive(
+                &subdir_path,
+                &subdir_target,
+                depth + 1,
+                ancestors,
+                session,
+            ))
+            .await;
+            if canonical.is_some() {
+                ancestors.pop();
+            }
+
+            if let Err(e) = result {
+                if e.is_cancelled() || e.is_end_of_media() {
+                    return Err(e);
+                }
+                error!("Failed to write subdirectory {:?}: {}", subdir_path, e);
+                // Continue with other directories
+                session.files_failed.push((subdir_path, e.to_string()));
             }
         }
 
+        info!(
+            "Directory write completed: {:?} -> {}",
+            source_dir, target_path
+        );
+        Ok(())
+    }
+
+    /// Write one batch of a directory's files (sorted by name) in order, with read-ahead
+    async fn write_file_batch(
+        &mut self,
+        mut files: Vec<(PathBuf, std::fs::Metadata)>,
+        source_dir: &Path,
+        target_path: &str,
+        session: &mut WriteSessionResult,
+    ) -> Result<()> {
         // Sort files for consistent ordering (对应LTFSCopyGUI的排序逻辑)
-        files.sort_by(|a, b| {
+        files.sort_by(|(a, _), (b, _)| {
             a.file_name()
                 .unwrap_or_default()
                 .cmp(b.file_name().unwrap_or_default())
         });
 
-        // Sequential file processing (对应LTFSCopyGUI的串行处理)
+        // Sequential file processing (对应LTFSCopyGUI的串行处理)；读取和哈希由预读任务提前进行
         info!("Processing {} files sequentially", files.len());
+        let mut read_ahead_next = 0;
 
-        for file_path in files {
+        for (index, (file_path, file_metadata)) in files.iter().enumerate() {
+            self.check_cancelled(&format!("while writing {:?}", source_dir))?;
 
+            let file_bytes = if file_metadata.is_file() { file_metadata.len() } else { 0 };
+            if self.should_skip_source_file(file_path, file_metadata) {
+                session.files_skipped += 1;
+                self.write_progress.skip_bytes(file_bytes);
+                continue;
+            }
 
+            // Create target path for this file
+            let file_name = file_path
+                .file_name()
+                .map(crate::ltfs_index::name::name_from_os_str)
+                .unwrap_or_else(|| "unknown".to_string());
+            let file_target = format!("{}/{}", target_path, file_name);
+
+            // Already written and indexed by an interrupted session being resumed
+            if self.journal_has_committed(&file_target) {
+                debug!("Skipping {} (committed in write journal)", file_target);
+                session.files_skipped += 1;
+                self.write_progress.skip_bytes(file_bytes);
+                continue;
+            }
+
+            // Already on an earlier cartridge of a spanning set
+            if self.span_has_file(&file_target) {
+                debug!("Skipping {} (on an earlier cartridge of the span manifest)", file_target);
+                session.files_skipped += 1;
+                self.write_progress.skip_bytes(file_bytes);
+                continue;
+            }
 
-                // Create target path for this file
-                let file_name = file_path
+            // Keep this file and the next ones read and hashed ahead of the tape
+            read_ahead_next = read_ahead_next.max(index);
+            while self.read_ahead.len() <= self.write_options.read_ahead_files
+                && read_ahead_next < files.len()
+            {
+                let (next_path, next_metadata) = &files[read_ahead_next];
+                let next_name = next_path
                     .file_name()
-                    .and_then(|n| n.to_str())
-                    .unwrap_or("unknown");
-                let file_target = format!("{}/{}", target_path, file_name);
-
-                // Write individual file
-                if let Err(e) = self
-                    .write_file_to_tape_streaming(&file_path, &file_target)
-                    .await
-                {
+                    .map(crate::ltfs_index::name::name_from_os_str)
+                    .unwrap_or_else(|| "unknown".to_string());
+                let next_target = format!("{}/{}", target_path, next_name);
+                if self.will_write_from_start(next_path, next_metadata, &next_target) {
+                    let prepared = self.start_read_ahead(next_path, 0);
+                    self.read_ahead.push_back(prepared);
+                }
+                read_ahead_next += 1;
+            }
+
+            // Write individual file
+            match self
+                .write_file_to_tape_streaming(file_path, &file_target)
+                .await
+            {
+                Ok(()) => {
+                    session.files_written += 1;
+                    session.bytes_written += file_bytes;
+                    self.journal_record_file(&file_target, file_bytes)?;
+                }
+                Err(e) if e.is_cancelled() || e.is_end_of_media() => return Err(e),
+                Err(e) => {
                     error!("Failed to write file {:?}: {}", file_path, e);
                     // Continue with other files instead of failing entire directory
+                    session.files_failed.push((file_path.clone(), e.to_string()));
                 }
             }
+        }
+        self.read_ahead.clear();
+        Ok(())
+    }
 
-        // Recursively process subdirectories
-        for subdir_path in subdirs {
+    /// 取消时结束写了一半的文件：写入FileMark使磁带停在已知位置（部分数据不加入索引）
+    fn terminate_partial_write(&self, target_path: &str, bytes_written: u64) -> Result<()> {
+        self.scsi.write_filemarks(1)?;
+        let position = self.scsi.read_position()?;
+        warn!(
+            "Write of {} stopped after {} bytes; partial data closed by a FileMark, tape at P{} B{} (not added to the index)",
+            target_path, bytes_written, position.partition, position.block_number
+        );
+        Ok(())
+    }
 
+    /// 写入中途到达介质末端：结束写了一半的文件并返回EndOfMedia错误，交由调用方停止会话
+    ///
+    /// 调用方先从进度中扣除这个文件，使 `write_progress` 只包含完整写入、可提交到索引的文件；
+    /// 索引随后仍可写入early-warning区域。
+    fn stop_at_end_of_media(&self, target_path: &str, bytes_written: u64, e: RustLtfsError) -> RustLtfsError {
+        if let Err(fm_err) = self.terminate_partial_write(target_path, bytes_written) {
+            warn!("Cannot close partial write of {} at end of media: {}", target_path, fm_err);
+        }
+        warn!(
+            "End of media while writing {}: {} files ({} bytes) written before it",
+            target_path,
+            self.write_progress.current_files_processed,
+            self.write_progress.current_bytes_processed
+        );
+        e
+    }
 
-            let subdir_name = subdir_path
-                .file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or("unknown");
-            let subdir_target = format!("{}/{}", target_path, subdir_name);
+    /// 跨磁带写入时在介质末端分割文件：已写入的 `written` 范围以截止到分割点的长度加入索引，
+    /// 剩余部分记入清单留给下一盘磁带，随后返回EndOfMedia错误停止会话（索引仍可写入early-warning区域）
+    fn split_at_end_of_media(
+        &mut self,
+        source_path: &Path,
+        target_path: &str,
+        written: std::ops::Range<u64>,
+        total_size: u64,
+        extents: Vec<crate::ltfs_index::FileExtent>,
+        e: RustLtfsError,
+    ) -> Result<()> {
+        if !written.is_empty() {
+            self.scsi.write_filemarks(1)?;
+            let bytes_on_tape: u64 = extents.iter().map(|extent| extent.byte_count).sum();
+            self.update_index_for_file_write_enhanced(source_path, target_path, written.end, extents, None)?;
+            self.write_progress.files_written += 1;
+            self.write_progress.current_files_processed += 1;
+            self.write_progress.total_bytes_unindexed += bytes_on_tape;
+        }
+        self.span_record_split(target_path, source_path, written.start, written.end, total_size)?;
 
-            // Recursively write subdirectory
-            if let Err(e) =
-                Box::pin(self.write_directory_to_tape(&subdir_path, &subdir_target)).await
-            {
-                error!("Failed to write subdirectory {:?}: {}", subdir_path, e);
-                // Continue with other directories
-            }
+        warn!(
+            "End of media while writing {}: bytes {}-{} of {} are on this cartridge",
+            target_path, written.start, written.end, total_size
+        );
+        Err(e)
+    }
+
+    /// 单个文件写入时的索引路径：目标以 `/` 结尾、为根目录或是索引中已有的目录时，
+    /// 文件放入该目录并沿用源文件名（缺失的目录在更新索引时创建），否则目标即文件路径
+    pub fn resolve_file_target(&self, source: &Path, destination: &str) -> String {
+        let Some(file_name) = source.file_name().map(crate::ltfs_index::name::name_from_os_str) else {
+            return destination.to_string();
+        };
+        let names_directory = destination.ends_with('/')
+            || crate::ltfs_index::LtfsIndex::split_path(destination).is_empty()
+            || self.index.as_ref().is_some_and(|index| {
+                self.find_directory_by_path(&index.root_directory, destination).is_some()
+            });
+        if !names_directory {
+            return destination.to_string();
+        }
+        format!("{}/{}", destination.trim_end_matches('/'), file_name)
+    }
+
+    /// 试运行：按 `write_directory_to_tape` 完全相同的流程（跳过规则、索引路径）遍历源，
+    /// 在内存中构建写入后的索引并估算占用空间，但不发出任何SCSI写命令
+    pub async fn plan_write(&mut self, source: &Path, target_path: &str) -> Result<DryRunReport> {
+        if self.write_journal.is_some() {
+            return Err(RustLtfsError::parameter_validation(
+                "Dry run cannot be combined with a write journal".to_string(),
+            ));
         }
 
+        // 从索引中最后一个extent之后开始模拟写入位置
+        let logical_partition = if self.get_extra_partition_count() > 0 { "b" } else { "a" };
+        let partition = self.get_target_partition(if logical_partition == "b" { 1 } else { 0 });
+        let start_block = self
+            .index
+            .as_ref()
+            .map(|idx| {
+                super::index_io::sync::last_extent_end_block(
+                    &idx.root_directory,
+                    logical_partition,
+                    self.block_size as u64,
+                )
+            })
+            .unwrap_or(0);
+        self.dry_run_position = Some(crate::scsi::TapePosition {
+            partition,
+            block_number: start_block,
+            ..Default::default()
+        });
+
+        let previous_dry_run = self.write_options.dry_run;
+        self.write_options.dry_run = true;
+        let session = if source.is_dir() {
+            self.write_directory_to_tape(source, target_path).await
+        } else {
+            let file_target = self.resolve_file_target(source, target_path);
+            self.write_file_to_tape_streaming(source, &file_target)
+                .await
+                .map(|()| WriteSessionResult {
+                    files_written: self.write_progress.files_written,
+                    bytes_written: self.write_progress.bytes_written,
+                    ..Default::default()
+                })
+        };
+        self.write_options.dry_run = previous_dry_run;
+        let mut session = session?;
+        // 模拟写入不会移动磁带，最终位置无意义
+        session.final_position = None;
+
+        let end_block = self
+            .dry_run_position
+            .take()
+            .map(|position| position.block_number)
+            .unwrap_or(start_block);
+        let blocks_planned = end_block.saturating_sub(start_block);
+
+        let remaining_capacity = match self.refresh_capacity().await {
+            Ok(capacity) => {
+                let (remaining, maximum) = if self.get_extra_partition_count() > 0 {
+                    (capacity.p1_remaining, capacity.p1_maximum)
+                } else {
+                    (capacity.p0_remaining, capacity.p0_maximum)
+                };
+                // 最大容量为0表示未能读取到容量信息
+                (maximum > 0).then_some(remaining * 1024)
+            }
+            Err(e) => {
+                warn!("Cannot read remaining capacity for dry run: {}", e);
+                None
+            }
+        };
+
+        let report = DryRunReport {
+            session,
+            blocks_planned,
+            tape_bytes_planned: blocks_planned * self.block_size as u64,
+            remaining_capacity,
+        };
         info!(
-            "Directory write completed: {:?} -> {}",
-            source_dir, target_path
+            "Dry run: {} files ({} bytes), {} blocks, fits: {:?}",
+            report.session.files_written,
+            report.session.bytes_written,
+            report.blocks_planned,
+            report.fits()
         );
+        Ok(report)
+    }
+
+    /// 试运行中的单个文件：在模拟位置登记到索引并推进位置（数据块 + 1个FileMark）
+    fn plan_file_write(&mut self, source_path: &Path, target_path: &str, file_size: u64) -> Result<()> {
+        let position = self.dry_run_position.clone().ok_or_else(|| {
+            RustLtfsError::parameter_validation("Dry run position not initialized".to_string())
+        })?;
+
+        debug!(
+            "Dry run: {:?} -> {} ({} bytes) at P{} B{}",
+            source_path, target_path, file_size, position.partition, position.block_number
+        );
+        let extents = super::sparse::contiguous_extents(&position, file_size);
+        self.update_index_for_file_write(source_path, target_path, file_size, extents)?;
+
+        let blocks = file_size.div_ceil(self.block_size as u64) + 1;
+        if let Some(next) = self.dry_run_position.as_mut() {
+            next.block_number += blocks;
+        }
+
+        self.write_progress.current_files_processed += 1;
+        self.write_progress.current_bytes_processed += file_size;
+        self.write_progress.files_written += 1;
+        self.write_progress.bytes_written += file_size;
         Ok(())
     }
 
+    /// Verify-after-write: re-read a file's extents from tape and compare their SHA-256
+    /// with the hash of the blocks sent to the drive, so data silently substituted by the
+    /// drive (e.g. zeros) is caught without reading the source file a second time
+    ///
+    /// Extents are read in the order they were written; holes are not on tape.
+    fn verify_written_file(
+        &mut self,
+        target_path: &str,
+        extents: &[crate::ltfs_index::FileExtent],
+        expected_sha256: Vec<u8>,
+    ) -> Result<()> {
+        let mut hasher = Sha256::new();
+        let result = extents
+            .iter()
+            .try_for_each(|extent| {
+                let start = crate::scsi::TapePosition {
+                    partition: self.extent_partition(extent),
+                    block_number: extent.start_block,
+                    ..Default::default()
+                };
+                self.read_back_written_data(&start, extent.byte_count, |tape_data| {
+                    hasher.update(tape_data);
+                    Ok(())
+                })
+            })
+            .and_then(|()| compare_written_sha256(target_path, hasher.finalize().to_vec(), &expected_sha256));
+
+        self.record_verification_result(target_path, result)
+    }
+
+    /// Verify-after-write for stream input: compare SHA-256 of the read-back data
+    /// with the hash computed while writing
+    fn verify_written_stream(
+        &mut self,
+        target_path: &str,
+        start: &crate::scsi::TapePosition,
+        byte_count: u64,
+        expected_sha256: Vec<u8>,
+    ) -> Result<()> {
+        let mut hasher = Sha256::new();
+        let result = self
+            .read_back_written_data(start, byte_count, |tape_data| {
+                hasher.update(tape_data);
+                Ok(())
+            })
+            .and_then(|()| compare_written_sha256(target_path, hasher.finalize().to_vec(), &expected_sha256));
+
+        self.record_verification_result(target_path, result)
+    }
+
+    /// LOCATE back to `start`, read `byte_count` bytes block by block and hand each block's
+    /// data to `check`, then return to the position after the file mark so writing continues
+    fn read_back_written_data(
+        &self,
+        start: &crate::scsi::TapePosition,
+        byte_count: u64,
+        mut check: impl FnMut(&[u8]) -> Result<()>,
+    ) -> Result<()> {
+        let resume_position = self.scsi.read_position()?;
+        let block_size = self.block_size as usize;
+        self.invalidate_position_cache();
+
+        self.scsi.locate_block(start.partition, start.block_number)?;
+
+        let mut buffer = vec![0u8; block_size];
+        let mut remaining = byte_count;
+        let mut result = Ok(());
+
+        while remaining > 0 {
+            let expected_len = std::cmp::min(remaining, block_size as u64) as usize;
+            match self.scsi.read_blocks(1, &mut buffer) {
+                Ok(0) => {
+                    result = Err(RustLtfsError::verification(format!(
+                        "file mark reached with {} bytes still expected",
+                        remaining
+                    )));
+                    break;
+                }
+                Ok(_) => {
+                    if let Err(e) = check(&buffer[..expected_len]) {
+                        result = Err(e);
+                        break;
+                    }
+                    remaining -= expected_len as u64;
+                }
+                Err(e) => {
+                    result = Err(e);
+                    break;
+                }
+            }
+        }
+
+        // Always return to the write position, even if verification failed
+        self.scsi
+            .locate_block(resume_position.partition, resume_position.block_number)?;
+        result
+    }
+
+    /// Record a verification outcome in WriteProgress
+    fn record_verification_result(&mut self, name: &str, result: Result<()>) -> Result<()> {
+        match result {
+            Ok(()) => {
+                self.write_progress.files_verified += 1;
+                info!("Verified {} against tape data", name);
+                Ok(())
+            }
+            Err(e) => {
+                self.write_progress.verification_failures += 1;
+                warn!("Verification failed for {}: {}", name, e);
+                match e {
+                    RustLtfsError::Verification(_) => Err(e),
+                    other => Err(RustLtfsError::verification(format!(
+                        "{}: could not read back written data: {}",
+                        name, other
+                    ))),
+                }
+            }
+        }
+    }
+
+    /// Record a symlink in the index (对应LTFSCopyGUI的symlink保留), storing its link target
+    fn write_symlink_to_index(&mut self, source_path: &Path, target_path: &str) -> Result<()> {
+        let link_target = std::fs::read_link(source_path).map_err(|e| {
+            RustLtfsError::file_operation(format!("Unable to read symlink {:?}: {}", source_path, e))
+        })?;
+        let link_target = link_target.to_string_lossy().to_string();
+
+        info!("Storing symlink: {:?} -> {}", source_path, link_target);
+        self.update_index_for_symlink(source_path, target_path, link_target)?;
+        self.write_progress.current_files_processed += 1;
+        Ok(())
+    }
+
+    /// Whether a source file should be skipped (.xattr sidecar files, symlinks if configured)
+    fn should_skip_source_file(&self, source_path: &Path, metadata: &std::fs::Metadata) -> bool {
+        if let Some(ext) = source_path.extension() {
+            if ext.to_string_lossy().to_lowercase() == "xattr" {
+                info!("Skipping .xattr file: {:?}", source_path);
+                return true;
+            }
+        }
+
+        if self.is_excluded_source(source_path) {
+            info!("Skipping excluded file: {:?}", source_path);
+            return true;
+        }
+
+        if self.write_options.skip_symlinks && metadata.file_type().is_symlink() {
+            info!("Skipping symlink: {:?}", source_path);
+            return true;
+        }
+
+        false
+    }
+
    
 
+    /// `--follow-symlinks` 时返回符号链接目标的元数据；目标不存在时仍按符号链接保存
+    fn followed_metadata(&self, path: &Path, metadata: std::fs::Metadata) -> std::fs::Metadata {
+        if self.write_options.follow_symlinks && metadata.file_type().is_symlink() {
+            match std::fs::metadata(path) {
+                Ok(target) => return target,
+                Err(e) => debug!("Dangling symlink {:?} stored as a link: {}", path, e),
+            }
+        }
+        metadata
+    }
+
+    /// 位于源目录下第 `depth` 层的目录是否在 `--max-depth` 范围内
+    fn within_max_depth(&self, depth: usize) -> bool {
+        self.write_options.max_depth.is_none_or(|max| depth <= max)
+    }
+
+    /// 跟随符号链接时用于检测目录环的规范路径（未跟随时不可能出现环，返回None）
+    fn loop_guard_path(&self, dir: &Path) -> Option<PathBuf> {
+        if !self.write_options.follow_symlinks {
+            return None;
+        }
+        std::fs::canonicalize(dir)
+            .map_err(|e| debug!("Cannot canonicalize {:?}: {}", dir, e))
+            .ok()
+    }
+
+    /// 预扫描目录写入的源：未被排除的普通文件总字节数，用于计算进度和ETA
+    ///
+    /// 与实际写入相同地遵循 `--max-depth`、`--follow-symlinks` 和目录环检测。
+    fn source_total_bytes(&self, source_dir: &Path, depth: usize, ancestors: &mut Vec<PathBuf>) -> u64 {
+        let entries = match std::fs::read_dir(source_dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                debug!("Cannot scan {:?} for the progress total: {}", source_dir, e);
+                return 0;
+            }
+        };
+
+        let mut total = 0;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            let metadata = self.followed_metadata(&path, metadata);
+            if self.is_excluded_source(&path) {
+                continue;
+            }
+            if metadata.is_dir() {
+                if !self.within_max_depth(depth + 1) {
+                    continue;
+                }
+                let canonical = self.loop_guard_path(&path);
+                if let Some(ref canonical) = canonical {
+                    if ancestors.contains(canonical) {
+                        continue;
+                    }
+                    ancestors.push(canonical.clone());
+                }
+                total += self.source_total_bytes(&path, depth + 1, ancestors);
+                if canonical.is_some() {
+                    ancestors.pop();
+                }
+            } else if metadata.is_file() {
+                total += metadata.len();
+            }
+        }
+        total
+    }
+
+    /// 为 `source_path` 启动预读任务（从 `offset` 开始；只有从头读取时才计算哈希）
+    fn start_read_ahead(&self, source_path: &Path, offset: u64) -> ReadAheadFile {
+        let block_size = self.block_size as usize;
+        let files_in_flight = self.write_options.read_ahead_files as u64 + 1;
+        let capacity = self.write_options.read_ahead_bytes / (files_in_flight * block_size as u64);
+        let calculator = (self.write_options.hash_on_write && offset == 0)
+            .then(|| CheckSumBlockwiseCalculator::new_with_options(&self.write_options));
+        ReadAheadFile::start(source_path, offset, block_size, capacity as usize, calculator)
+    }
+
+    /// 取出为 `source_path` 预读的文件；排在它前面、未被写入的预读会被丢弃
+    fn take_read_ahead(&mut self, source_path: &Path) -> Option<ReadAheadFile> {
+        while let Some(prepared) = self.read_ahead.pop_front() {
+            if prepared.path() == source_path {
+                return Some(prepared);
+            }
+            debug!("Dropping unused read-ahead of {:?}", prepared.path());
+        }
+        None
+    }
+
+    /// 目录中的这个文件随后是否会从头写入数据（用于选择预读对象，不记录日志）
+    fn will_write_from_start(&self, source_path: &Path, metadata: &std::fs::Metadata, target_path: &str) -> bool {
+        let is_xattr = source_path
+            .extension()
+            .is_some_and(|ext| ext.to_string_lossy().eq_ignore_ascii_case("xattr"));
+        metadata.is_file()
+            && !is_xattr
+            && !self.write_options.dry_run
+            && !self.is_excluded_source(source_path)
+            && !self.journal_has_committed(target_path)
+            && !self.span_has_file(target_path)
+            && self.span_continuation_offset(target_path).is_none()
+    }
+
+    /// 更新滚动写入速率并通知进度回调
+    fn report_write_progress(&mut self) {
+        self.write_progress.sample_rate();
+        if let Some(ref callback) = self.write_progress_callback {
+            callback(&self.write_progress);
+        }
+    }
+
+    /// 源路径是否匹配 `--exclude` 规则：目录写入时按相对源目录的路径匹配，单个文件按文件名匹配
+    fn is_excluded_source(&self, source_path: &Path) -> bool {
+        if self.write_options.excluded_patterns.is_empty() {
+            return false;
+        }
+        let relative = self
+            .write_source_root
+            .as_deref()
+            .and_then(|root| source_path.strip_prefix(root).ok())
+            .filter(|relative| !relative.as_os_str().is_empty())
+            .or_else(|| source_path.file_name().map(Path::new))
+            .unwrap_or(source_path);
+        self.write_options.is_excluded(relative)
+    }
+
     /// Check available space on tape
-    fn check_available_space(&self, required_size: u64) -> Result<()> {
-        // For now, we assume there's enough space
-        // In a full implementation, this would check MAM data or use other SCSI commands
-        // to determine remaining capacity
+    /// 写入前检查数据分区剩余空间（MAM中的REMAINING CAPACITY IN PARTITION）
+    ///
+    /// 需要容纳：本文件（按块向上取整）+ 尚未写入索引的字节 + 随后必须写入的索引。
+    /// 无法读取MAM容量时跳过检查，由驱动器的EOM提示兜底。
+    fn check_available_space(&self, file_size: u64) -> Result<()> {
+        let data_partition = self.get_target_partition(1);
+        let remaining = match self.scsi.read_mam_capacity(data_partition) {
+            Ok(capacity) => capacity.remaining,
+            Err(e) => {
+                debug!("Cannot read remaining capacity, skipping space check: {}", e);
+                return Ok(());
+            }
+        };
 
-        // Minimum safety check - require at least 1GB free space
-        let min_required_space = required_size + 1024 * 1024 * 1024; // File size + 1GB buffer
+        let block_size = (self.block_size as u64).max(1);
+        let file_bytes = file_size.div_ceil(block_size) * block_size;
+        let unindexed = self.write_progress.total_bytes_unindexed;
+        let index_entries = self
+            .get_index_statistics()
+            .map_or(0, |stats| stats.total_files + stats.total_directories)
+            + 1; // 本文件
+        let index_bytes = (index_entries * INDEX_BYTES_PER_ENTRY).div_ceil(block_size) * block_size;
+        let required = file_bytes + unindexed + index_bytes;
 
         debug!(
-            "Checking available space: required {} bytes (with buffer: {})",
-            required_size, min_required_space
+            "Space check on partition {}: file {} + unindexed {} + index {} = {} bytes, {} remaining",
+            data_partition, file_bytes, unindexed, index_bytes, required, remaining
         );
 
-        // This is a simplified check - in reality would query tape capacity
-        if required_size > 8 * 1024 * 1024 * 1024 * 1024 {
-            // 8TB limit for LTO-8
-            return Err(RustLtfsError::tape_device(
-                "File too large for tape capacity".to_string(),
-            ));
+        if required > remaining {
+            return Err(RustLtfsError::tape_device(format!(
+                "Insufficient space on data partition {}: need {} (file {}, unindexed {}, index {}), \
+                 {} remaining, short by {}",
+                data_partition,
+                crate::utils::format_bytes(required),
+                crate::utils::format_bytes(file_bytes),
+                crate::utils::format_bytes(unindexed),
+                crate::utils::format_bytes(index_bytes),
+                crate::utils::format_bytes(remaining),
+                crate::utils::format_bytes(required - remaining)
+            )));
         }
 
         Ok(())
@@ -777,3 +1571,135 @@ impl TapeOperations {
 
 
 }
+
+/// 比较回读数据与写入数据的SHA-256
+fn compare_written_sha256(target_path: &str, actual_sha256: Vec<u8>, expected_sha256: &[u8]) -> Result<()> {
+    if actual_sha256 == expected_sha256 {
+        Ok(())
+    } else {
+        Err(RustLtfsError::verification(format!(
+            "{}: SHA-256 of tape data ({}) differs from written data ({})",
+            target_path,
+            hex::encode(&actual_sha256),
+            hex::encode(expected_sha256)
+        )))
+    }
+}
+
+/// 目录枚举的一批：保留名称大于上一批末尾 (`after`) 的最小 `limit` 个条目
+///
+/// 队列不会超过 `limit`；无论条目以什么顺序提供，各批依次拼接起来都是按名称升序的完整列表。
+struct NameOrderedBatch<K, T> {
+    limit: usize,
+    after: Option<K>,
+    /// 按名称排序的最大堆，堆顶是本批中最大的名称
+    queue: std::collections::BinaryHeap<BatchEntry<K, T>>,
+    /// 是否有条目因队列已满留到下一批
+    deferred: bool,
+}
+
+struct BatchEntry<K, T>(K, T);
+
+impl<K: Ord, T> PartialEq for BatchEntry<K, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<K: Ord, T> Eq for BatchEntry<K, T> {}
+
+impl<K: Ord, T> PartialOrd for BatchEntry<K, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<K: Ord, T> Ord for BatchEntry<K, T> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+impl<K: Ord, T> NameOrderedBatch<K, T> {
+    fn new(limit: usize, after: Option<K>) -> Self {
+        let limit = limit.max(1);
+        Self {
+            limit,
+            after,
+            queue: std::collections::BinaryHeap::with_capacity(limit.min(4096)),
+            deferred: false,
+        }
+    }
+
+    /// 名称为 `name` 的条目能否进入本批（不在之前的批次中，且队列未满或比队列中最大的名称小）
+    fn accepts(&self, name: &K) -> bool {
+        self.after.as_ref().is_none_or(|after| name > after)
+            && (self.queue.len() < self.limit || self.queue.peek().is_some_and(|max| *name < max.0))
+    }
+
+    fn offer(&mut self, name: K, value: T) {
+        if self.after.as_ref().is_some_and(|after| name <= *after) {
+            return;
+        }
+        if self.queue.len() == self.limit {
+            self.deferred = true;
+            if self.queue.peek().is_some_and(|max| name >= max.0) {
+                return;
+            }
+            self.queue.pop();
+        }
+        self.queue.push(BatchEntry(name, value));
+    }
+
+    /// 按名称排序的本批条目，以及之后是否还有条目
+    fn finish(self) -> (Vec<(K, T)>, bool) {
+        let entries = self
+            .queue
+            .into_sorted_vec()
+            .into_iter()
+            .map(|BatchEntry(name, value)| (name, value))
+            .collect();
+        (entries, self.deferred)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_queue_stays_bounded() {
+        const FILES: u64 = 1_000_000;
+        const LIMIT: usize = 100_000;
+        // 模拟read_dir的任意返回顺序：以与FILES互素的步长打乱
+        let enumerate = || (0..FILES).map(|i| i * 7_919 % FILES);
+
+        let mut written = Vec::new();
+        let mut max_queued = 0;
+        let mut after = None;
+        let mut passes = 0;
+        loop {
+            passes += 1;
+            let mut batch = NameOrderedBatch::new(LIMIT, after.take());
+            for i in enumerate() {
+                // 以序号作为名称：与 "file{:07}" 的字典序相同
+                if batch.accepts(&i) {
+                    batch.offer(i, PathBuf::from(format!("dir/file{:07}", i)));
+                }
+                max_queued = max_queued.max(batch.queue.len());
+            }
+            let (files, more) = batch.finish();
+            after = files.last().map(|(name, _)| name.clone());
+            written.extend(files.into_iter().map(|(i, _)| i));
+            if !more {
+                break;
+            }
+        }
+
+        assert!(max_queued <= LIMIT);
+        assert_eq!(passes, 10);
+        assert_eq!(written.len() as u64, FILES);
+        // 跨批次整体按名称升序，与枚举顺序无关
+        assert!(written.iter().copied().eq(0..FILES));
+    }
+}
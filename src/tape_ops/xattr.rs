@@ -0,0 +1,184 @@
+//! Extended Attribute Preservation
+//!
+//! Stores the extended attributes of source files as LTFS `<xattr>` entries and sets
+//! them again on extracted files. Attributes come from the file system (Linux
+//! `listxattr`/`getxattr`) and from a `<file>.xattr` sidecar next to the source file,
+//! an `<extendedattributes>` fragment in index format; the sidecar itself is never
+//! written as a file. Keys keep their namespace (`user.comment`), and only namespaced
+//! keys are restored, so index-only entries such as `ltfs.hash.*` stay in the index.
+
+use crate::ltfs_index::{ExtendedAttribute, ExtendedAttributes, File};
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+/// 侧车文件扩展名：`<file>.xattr` 保存 `<file>` 的扩展属性
+const SIDECAR_EXTENSION: &str = "xattr";
+
+/// 提取时会设置到本地文件的扩展属性命名空间
+const RESTORABLE_NAMESPACES: [&str; 4] = ["user.", "trusted.", "security.", "system."];
+
+/// 源文件的扩展属性：侧车文件中的条目优先，其次是文件系统中的属性
+///
+/// 读取失败只记录日志，不影响写入。
+pub(crate) fn source_xattrs(path: &Path) -> Vec<ExtendedAttribute> {
+    let mut attributes = read_sidecar(path);
+    for attr in platform::list(path) {
+        if !attributes.iter().any(|existing| existing.key == attr.key) {
+            attributes.push(attr);
+        }
+    }
+    attributes
+}
+
+/// 将索引中带命名空间的扩展属性设置到提取出的文件上；无法设置的属性跳过
+pub(crate) fn restore_xattrs(file: &File, dest: &Path) {
+    let attributes = file
+        .extended_attributes
+        .iter()
+        .flat_map(|xattrs| xattrs.attributes.iter())
+        .filter(|attr| RESTORABLE_NAMESPACES.iter().any(|ns| attr.key.starts_with(ns)));
+
+    for attr in attributes {
+        if let Err(e) = platform::set(dest, &attr.key, attr.value.as_bytes()) {
+            debug!("Cannot set xattr {} on {:?}: {}", attr.key, dest, e);
+        }
+    }
+}
+
+fn sidecar_path(path: &Path) -> PathBuf {
+    let mut sidecar = path.as_os_str().to_owned();
+    sidecar.push(".");
+    sidecar.push(SIDECAR_EXTENSION);
+    PathBuf::from(sidecar)
+}
+
+fn read_sidecar(path: &Path) -> Vec<ExtendedAttribute> {
+    let sidecar = sidecar_path(path);
+    let Ok(xml) = std::fs::read_to_string(&sidecar) else {
+        return Vec::new();
+    };
+
+    match quick_xml::de::from_str::<ExtendedAttributes>(&xml) {
+        Ok(xattrs) => {
+            debug!("Read {} xattrs from sidecar {:?}", xattrs.attributes.len(), sidecar);
+            xattrs.attributes
+        }
+        Err(e) => {
+            warn!("Ignoring unreadable xattr sidecar {:?}: {}", sidecar, e);
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use crate::ltfs_index::ExtendedAttribute;
+    use std::ffi::CString;
+    use std::io;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+    use tracing::debug;
+
+    fn c_string(bytes: &[u8]) -> io::Result<CString> {
+        CString::new(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+    }
+
+    /// 先以空缓冲区查询长度再读取；两次调用之间属性变大 (ERANGE) 时重试
+    fn read_sized(mut call: impl FnMut(*mut u8, usize) -> libc::ssize_t) -> io::Result<Vec<u8>> {
+        loop {
+            let size = call(std::ptr::null_mut(), 0);
+            if size < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let mut buffer = vec![0u8; size as usize];
+            if buffer.is_empty() {
+                return Ok(buffer);
+            }
+
+            let len = call(buffer.as_mut_ptr(), buffer.len());
+            if len >= 0 {
+                buffer.truncate(len as usize);
+                return Ok(buffer);
+            }
+            let err = io::Error::last_os_error();
+            if err.raw_os_error() != Some(libc::ERANGE) {
+                return Err(err);
+            }
+        }
+    }
+
+    /// 文件系统中的扩展属性；值不是文本（非UTF-8或含NUL）的属性无法存入索引，跳过
+    pub(super) fn list(path: &Path) -> Vec<ExtendedAttribute> {
+        let Ok(c_path) = c_string(path.as_os_str().as_bytes()) else {
+            return Vec::new();
+        };
+        // SAFETY: c_path以NUL结尾，缓冲区指针和长度来自read_sized
+        let names = match read_sized(|buf, len| unsafe {
+            libc::listxattr(c_path.as_ptr(), buf as *mut libc::c_char, len)
+        }) {
+            Ok(names) => names,
+            Err(e) => {
+                debug!("Cannot list xattrs of {:?}: {}", path, e);
+                return Vec::new();
+            }
+        };
+
+        names
+            .split(|&b| b == 0)
+            .filter(|name| !name.is_empty())
+            .filter_map(|name| {
+                let key = String::from_utf8(name.to_vec()).ok()?;
+                let c_name = c_string(name).ok()?;
+                // SAFETY: 同上
+                let value = read_sized(|buf, len| unsafe {
+                    libc::getxattr(c_path.as_ptr(), c_name.as_ptr(), buf as *mut libc::c_void, len)
+                })
+                .map_err(|e| debug!("Cannot read xattr {} of {:?}: {}", key, path, e))
+                .ok()?;
+
+                match String::from_utf8(value) {
+                    Ok(value) if !value.contains('\0') => Some(ExtendedAttribute { key, value }),
+                    _ => {
+                        debug!("Skipping binary xattr {} of {:?}", key, path);
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
+    pub(super) fn set(path: &Path, key: &str, value: &[u8]) -> io::Result<()> {
+        let c_path = c_string(path.as_os_str().as_bytes())?;
+        let c_name = c_string(key.as_bytes())?;
+        // SAFETY: 路径和名称以NUL结尾，value的指针和长度一致
+        let rc = unsafe {
+            libc::setxattr(
+                c_path.as_ptr(),
+                c_name.as_ptr(),
+                value.as_ptr() as *const libc::c_void,
+                value.len(),
+                0,
+            )
+        };
+        if rc < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// 其他平台只支持侧车文件
+#[cfg(not(target_os = "linux"))]
+mod platform {
+    use crate::ltfs_index::ExtendedAttribute;
+    use std::path::Path;
+
+    pub(super) fn list(_path: &Path) -> Vec<ExtendedAttribute> {
+        Vec::new()
+    }
+
+    pub(super) fn set(_path: &Path, _key: &str, _value: &[u8]) -> std::io::Result<()> {
+        Err(std::io::ErrorKind::Unsupported.into())
+    }
+}
@@ -40,6 +40,28 @@ pub fn format_duration(duration_secs: f64) -> String {
     }
 }
 
+/// Parse a byte size with an optional binary suffix: `512`, `64K`, `500M`, `1.5G`, `2TiB`
+pub fn parse_byte_size(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let number_end = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    let (number, suffix) = s.split_at(number_end);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("invalid size '{}'", s))?;
+
+    let shift = match suffix.trim().to_ascii_uppercase().trim_end_matches("IB").trim_end_matches('B') {
+        "" => 0,
+        "K" => 10,
+        "M" => 20,
+        "G" => 30,
+        "T" => 40,
+        _ => return Err(format!("invalid size suffix in '{}' (expected K, M, G or T)", s)),
+    };
+    Ok((number * (1u64 << shift) as f64) as u64)
+}
+
 /// Calculate transfer speed in human-readable format
 pub fn format_speed(bytes: u64, duration_secs: f64) -> String {
     if duration_secs <= 0.0 {
@@ -49,3 +71,96 @@ pub fn format_speed(bytes: u64, duration_secs: f64) -> String {
     let speed = bytes as f64 / duration_secs;
     format!("{}/s", format_bytes(speed as u64))
 }
+
+/// Guess whether `data` is binary: it contains NUL bytes, is not UTF-8, or more than
+/// 10% of its characters are control characters other than whitespace and ESC
+///
+/// A UTF-8 sequence cut off at the end of `data` is not counted against it.
+pub fn looks_binary(data: &[u8]) -> bool {
+    if data.contains(&0) {
+        return true;
+    }
+    let text = match std::str::from_utf8(data) {
+        Ok(text) => text,
+        Err(e) if e.error_len().is_none() => {
+            // 末尾被截断的多字节字符
+            std::str::from_utf8(&data[..e.valid_up_to()]).unwrap_or_default()
+        }
+        Err(_) => return true,
+    };
+
+    let mut total = 0usize;
+    let mut control = 0usize;
+    for c in text.chars() {
+        total += 1;
+        if c.is_control() && !matches!(c, '\n' | '\r' | '\t' | '\x0c' | '\x1b') {
+            control += 1;
+        }
+    }
+    control * 10 > total
+}
+
+/// Hex dump in `hexdump -C` layout: offset, 16 hex bytes, printable ASCII
+pub fn hex_dump(data: &[u8]) -> String {
+    let mut out = String::new();
+    for (line, chunk) in data.chunks(16).enumerate() {
+        out.push_str(&format!("{:08x} ", line * 16));
+        for i in 0..16 {
+            if i % 8 == 0 {
+                out.push(' ');
+            }
+            match chunk.get(i) {
+                Some(byte) => out.push_str(&format!("{:02x} ", byte)),
+                None => out.push_str("   "),
+            }
+        }
+        out.push_str(" |");
+        out.extend(chunk.iter().map(|&b| {
+            if b.is_ascii_graphic() || b == b' ' {
+                b as char
+            } else {
+                '.'
+            }
+        }));
+        out.push_str("|\n");
+    }
+    out
+}
+
+/// Set `flag` when Ctrl+C is pressed, so a running tape operation stops after the current block
+///
+/// Must be called from within the tokio runtime.
+pub fn cancel_on_ctrl_c(flag: std::sync::Arc<std::sync::atomic::AtomicBool>) {
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            eprintln!("\n⏹  Cancelling after the current block...");
+            flag.store(true, std::sync::atomic::Ordering::Relaxed);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_and_binary_are_told_apart() {
+        assert!(!looks_binary(b"2024-01-01 INFO started\r\n\tnext line\n"));
+        assert!(!looks_binary("日志".as_bytes()));
+        // 截断在多字节字符中间
+        assert!(!looks_binary(&"日志".as_bytes()[..4]));
+        assert!(looks_binary(b"MZ\x90\x00\x03"));
+        assert!(looks_binary(&[0xff, 0xfe, b'a', b'b']));
+        assert!(looks_binary(b"\x01\x02\x03abcdefg"));
+    }
+
+    #[test]
+    fn hex_dump_matches_hexdump_layout() {
+        let dump = hex_dump(b"Hello, tape!\n\x00\x01\x02\xffXY");
+        assert_eq!(
+            dump,
+            "00000000  48 65 6c 6c 6f 2c 20 74  61 70 65 21 0a 00 01 02  |Hello, tape!....|\n\
+             00000010  ff 58 59                                          |.XY|\n"
+        );
+    }
+}
@@ -0,0 +1,648 @@
+//! Virtual Tape
+//!
+//! A memory-backed `TapeBackend` for running the full write → index → read → extract
+//! path without a drive. Each partition is a list of logical objects (`Vec<u8>` data
+//! blocks) plus the set of object numbers that are filemarks, numbered like an LTO
+//! drive numbers them: a filemark takes one logical block. Reads stop at filemarks and
+//! end of data with the fixed-format sense data a drive returns, writes discard
+//! everything after the current position, and partitioning, compression and cartridge
+//! memory (MAM) attributes behave closely enough for `mkltfs` to format the tape.
+//!
+//! Clones share the same tape, so a test can "reload" the cartridge in a new
+//! `TapeOperations` and read back what an earlier one wrote.
+
+use crate::scsi::types::{LocateDestType, RawBlock};
+use crate::scsi::{
+    DataCompressionPage, DriveInfo, EncryptionStatus, MediaType, SenseData, SpaceType, TapeBackend, TapePosition,
+    VolumeCoherencyInfo,
+};
+use crate::error::{Result, RustLtfsError};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// 分区单位为10^9字节时报告的分区大小 (mode page 0x11)
+const PARTITION_UNITS: u8 = 9;
+/// READ BLOCK LIMITS报告的最大块长度
+const MAX_BLOCK_LENGTH: u32 = 8 * 1024 * 1024;
+/// MAM attribute: BARCODE
+const MAM_BARCODE: u16 = 0x0806;
+
+/// 定长格式sense数据中的感测键
+const SENSE_NO_SENSE: u8 = 0x00;
+const SENSE_ILLEGAL_REQUEST: u8 = 0x05;
+const SENSE_DATA_PROTECT: u8 = 0x07;
+const SENSE_BLANK_CHECK: u8 = 0x08;
+
+/// 一个分区：逻辑对象（数据块或FileMark）按块号排列
+#[derive(Debug, Clone, Default)]
+pub struct VirtualPartition {
+    /// 每个逻辑块号的数据；FileMark所在块号的条目为空
+    pub blocks: Vec<Vec<u8>>,
+    /// 是FileMark的逻辑块号
+    pub filemarks: BTreeSet<u64>,
+}
+
+impl VirtualPartition {
+    /// Append a data block
+    pub fn push_block(&mut self, data: Vec<u8>) {
+        self.blocks.push(data);
+    }
+
+    /// Append a filemark
+    pub fn push_filemark(&mut self) {
+        self.filemarks.insert(self.len());
+        self.blocks.push(Vec::new());
+    }
+
+    fn len(&self) -> u64 {
+        self.blocks.len() as u64
+    }
+
+    fn is_filemark(&self, block: u64) -> bool {
+        self.filemarks.contains(&block)
+    }
+
+    /// 写入前丢弃 `block` 及之后的全部内容
+    fn truncate(&mut self, block: u64) {
+        self.blocks.truncate(block as usize);
+        self.filemarks.split_off(&block);
+    }
+}
+
+#[derive(Debug)]
+struct VirtualTapeState {
+    partitions: Vec<VirtualPartition>,
+    partition: u8,
+    position: u64,
+    media_type: MediaType,
+    compression: bool,
+    /// SECURITY PROTOCOL OUT设置的加密状态（不保存密钥，数据按明文存放）
+    encryption: EncryptionStatus,
+    /// MODE SELECT设置、FORMAT MEDIUM时生效的附加分区数
+    pending_extra_partitions: u8,
+    attributes: HashMap<(u8, u16), Vec<u8>>,
+    coherency: HashMap<u8, VolumeCoherencyInfo>,
+    /// 每个分区的写入次数 (volume change reference)
+    change_references: HashMap<u8, u64>,
+    /// 分区的early-warning位置：写入此块号及之后的数据块时报告EOM（数据仍写入）
+    early_warning: HashMap<u8, u64>,
+    last_sense: Vec<u8>,
+    /// 定位与写入类命令的记录，按发出顺序
+    commands: Vec<String>,
+}
+
+impl VirtualTapeState {
+    fn current(&self) -> &VirtualPartition {
+        &self.partitions[self.partition as usize]
+    }
+
+    fn current_mut(&mut self) -> &mut VirtualPartition {
+        &mut self.partitions[self.partition as usize]
+    }
+
+    /// 记录定长格式sense数据并返回对应的错误
+    fn fail(&mut self, key: u8, asc: u8, ascq: u8, message: &str) -> RustLtfsError {
+        self.set_sense(key, asc, ascq, false);
+        RustLtfsError::scsi(format!(
+            "{} (sense key 0x{:02X}, ASC/ASCQ 0x{:02X}/0x{:02X})",
+            message, key, asc, ascq
+        ))
+    }
+
+    fn set_sense(&mut self, key: u8, asc: u8, ascq: u8, filemark: bool) {
+        let mut sense = vec![0u8; 18];
+        sense[0] = 0x70;
+        sense[2] = key | if filemark { 0x80 } else { 0 };
+        sense[7] = 10;
+        sense[12] = asc;
+        sense[13] = ascq;
+        self.last_sense = sense;
+    }
+
+    fn clear_sense(&mut self) {
+        self.last_sense.clear();
+    }
+
+    fn before_write(&mut self) -> Result<()> {
+        if self.media_type.is_write_protected() {
+            return Err(self.fail(SENSE_DATA_PROTECT, 0x27, 0x00, "Write protected"));
+        }
+        let position = self.position;
+        self.current_mut().truncate(position);
+        *self.change_references.entry(self.partition).or_insert(0) += 1;
+        Ok(())
+    }
+
+    fn position(&self) -> TapePosition {
+        let partition = self.current();
+        TapePosition {
+            partition: self.partition,
+            block_number: self.position,
+            file_number: partition.filemarks.range(..self.position).count() as u64,
+            set_number: 0,
+            end_of_data: self.position == partition.len(),
+            beginning_of_partition: self.position == 0,
+            blocks_in_buffer: Some(0),
+            bytes_in_buffer: Some(0),
+        }
+    }
+}
+
+/// Memory-backed tape cartridge implementing `TapeBackend`
+#[derive(Debug, Clone)]
+pub struct VirtualTape {
+    state: Arc<Mutex<VirtualTapeState>>,
+}
+
+impl Default for VirtualTape {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VirtualTape {
+    /// 空白LTO-8磁带，单分区
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(Mutex::new(VirtualTapeState {
+                partitions: vec![VirtualPartition::default()],
+                partition: 0,
+                position: 0,
+                media_type: MediaType::Lto8Rw,
+                compression: true,
+                encryption: EncryptionStatus::default(),
+                pending_extra_partitions: 0,
+                attributes: HashMap::new(),
+                coherency: HashMap::new(),
+                change_references: HashMap::new(),
+                early_warning: HashMap::new(),
+                last_sense: Vec::new(),
+                commands: Vec::new(),
+            })),
+        }
+    }
+
+    /// Use a different cartridge type (e.g. WORM or write-protected media)
+    pub fn with_media_type(self, media_type: MediaType) -> Self {
+        self.lock().media_type = media_type;
+        self
+    }
+
+    /// Report early-warning end of medium for data blocks written at or after `block`
+    /// of `partition`, so a test can fill a cartridge without writing gigabytes
+    pub fn with_early_warning(self, partition: u8, block: u64) -> Self {
+        self.lock().early_warning.insert(partition, block);
+        self
+    }
+
+    /// Pre-load a partition, e.g. with the label constructs and index of a real LTFS volume
+    ///
+    /// Partitions up to `partition` are created as needed; the tape is left at the
+    /// beginning of partition 0.
+    pub fn load_partition(&self, partition: u8, contents: VirtualPartition) {
+        let mut state = self.lock();
+        let count = state.partitions.len().max(partition as usize + 1);
+        state.partitions.resize_with(count, VirtualPartition::default);
+        state.partitions[partition as usize] = contents;
+        state.partition = 0;
+        state.position = 0;
+    }
+
+    /// Copy of a partition's current contents
+    pub fn partition(&self, partition: u8) -> Option<VirtualPartition> {
+        self.lock().partitions.get(partition as usize).cloned()
+    }
+
+    /// Sense data of the last command that ended with a check condition (empty if none)
+    pub fn last_sense(&self) -> Vec<u8> {
+        self.lock().last_sense.clone()
+    }
+
+    /// Positioning and write commands issued so far (e.g. `"READ POSITION"`,
+    /// `"WRITE FILEMARKS 0"`), oldest first; the log is cleared when taken
+    pub fn take_commands(&self) -> Vec<String> {
+        std::mem::take(&mut self.lock().commands)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, VirtualTapeState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 读取当前位置的一个对象：数据块复制到 `buffer`，FileMark和EOD设置相应的sense
+    fn read_object(state: &mut VirtualTapeState, buffer: &mut [u8]) -> Result<RawBlock> {
+        let position = state.position;
+        if position >= state.current().len() {
+            state.set_sense(SENSE_BLANK_CHECK, 0x00, 0x05, false);
+            return Ok(RawBlock::EndOfData);
+        }
+        state.position += 1;
+        if state.current().is_filemark(position) {
+            state.set_sense(SENSE_NO_SENSE, 0x00, 0x01, true);
+            return Ok(RawBlock::FileMark);
+        }
+
+        let len = state.current().blocks[position as usize].len();
+        if len > buffer.len() {
+            // 块比请求长 (ILI)：驱动器越过该块，数据被截断
+            return Err(state.fail(SENSE_NO_SENSE, 0x00, 0x00, &format!(
+                "Block {} of {} bytes does not fit a {}-byte read",
+                position, len, buffer.len()
+            )));
+        }
+        let data = &state.partitions[state.partition as usize].blocks[position as usize];
+        buffer[..len].copy_from_slice(data);
+        state.clear_sense();
+        Ok(RawBlock::Data(len))
+    }
+}
+
+impl TapeBackend for VirtualTape {
+    fn read_position(&self) -> Result<TapePosition> {
+        let mut state = self.lock();
+        state.commands.push("READ POSITION".to_string());
+        Ok(state.position())
+    }
+
+    fn locate(
+        &self,
+        block_address: u64,
+        partition: u8,
+        dest_type: LocateDestType,
+        _current_partition: Option<u8>,
+    ) -> Result<u16> {
+        let mut state = self.lock();
+        state.commands.push("LOCATE".to_string());
+        let Some(target) = state.partitions.get(partition as usize).map(VirtualPartition::len) else {
+            return Err(state.fail(SENSE_ILLEGAL_REQUEST, 0x24, 0x00, &format!(
+                "Partition {} does not exist",
+                partition
+            )));
+        };
+        state.partition = partition;
+        match dest_type {
+            LocateDestType::EOD => state.position = target,
+            LocateDestType::Block if block_address > target => {
+                state.position = target;
+                return Err(state.fail(SENSE_BLANK_CHECK, 0x00, 0x05, &format!(
+                    "LOCATE to block {} beyond end of data (block {})",
+                    block_address, target
+                )));
+            }
+            LocateDestType::Block => state.position = block_address,
+        }
+        state.clear_sense();
+        Ok(0)
+    }
+
+    fn rewind(&self) -> Result<()> {
+        let mut state = self.lock();
+        state.commands.push("REWIND".to_string());
+        state.partition = 0;
+        state.position = 0;
+        state.clear_sense();
+        Ok(())
+    }
+
+    fn space(&self, space_type: SpaceType, count: i32) -> Result<()> {
+        let mut state = self.lock();
+        state.commands.push("SPACE".to_string());
+        let end = state.current().len();
+        match space_type {
+            SpaceType::EndOfData => state.position = end,
+            SpaceType::Blocks => {
+                let target = state.position as i64 + count as i64;
+                if target < 0 {
+                    state.position = 0;
+                    return Err(state.fail(SENSE_NO_SENSE, 0x00, 0x04, "SPACE reached beginning of partition"));
+                }
+                if target as u64 > end {
+                    state.position = end;
+                    return Err(state.fail(SENSE_BLANK_CHECK, 0x00, 0x05, "SPACE reached end of data"));
+                }
+                state.position = target as u64;
+            }
+            SpaceType::FileMarks if count >= 0 => {
+                for _ in 0..count {
+                    let position = state.position;
+                    match state.current().filemarks.range(position..).next().copied() {
+                        Some(filemark) => state.position = filemark + 1,
+                        None => {
+                            state.position = end;
+                            return Err(state.fail(SENSE_BLANK_CHECK, 0x00, 0x05, "SPACE reached end of data"));
+                        }
+                    }
+                }
+            }
+            SpaceType::FileMarks => {
+                // 反向越过FileMark后停在它之前（BOP一侧）
+                for _ in 0..count.unsigned_abs() {
+                    let position = state.position;
+                    match state.current().filemarks.range(..position).next_back().copied() {
+                        Some(filemark) => state.position = filemark,
+                        None => {
+                            state.position = 0;
+                            return Err(state.fail(SENSE_NO_SENSE, 0x00, 0x04, "SPACE reached beginning of partition"));
+                        }
+                    }
+                }
+            }
+        }
+        state.clear_sense();
+        Ok(())
+    }
+
+    fn read_blocks(&self, block_count: u32, buffer: &mut [u8]) -> Result<u32> {
+        let block_len = buffer.len() / block_count.max(1) as usize;
+        let mut state = self.lock();
+        for (read, chunk) in buffer.chunks_mut(block_len).take(block_count as usize).enumerate() {
+            match Self::read_object(&mut state, chunk)? {
+                RawBlock::Data(len) => chunk[len..].fill(0),
+                RawBlock::FileMark => return Ok(read as u32),
+                RawBlock::EndOfData if read == 0 => {
+                    return Err(RustLtfsError::scsi("Blank check: end of data reached".to_string()));
+                }
+                RawBlock::EndOfData => return Ok(read as u32),
+            }
+        }
+        Ok(block_count)
+    }
+
+    fn read_raw_block(&self, buffer: &mut [u8]) -> Result<RawBlock> {
+        Self::read_object(&mut self.lock(), buffer)
+    }
+
+    fn write_blocks(&self, block_count: u32, buffer: &[u8]) -> Result<u32> {
+        let block_len = buffer.len().div_ceil(block_count.max(1) as usize).max(1);
+        let mut state = self.lock();
+        state.commands.push("WRITE".to_string());
+        state.before_write()?;
+        let mut written = 0;
+        for block in buffer.chunks(block_len) {
+            state.current_mut().push_block(block.to_vec());
+            state.position += 1;
+            written += 1;
+        }
+        let partition = state.partition;
+        if state.early_warning.get(&partition).is_some_and(|&block| state.position > block) {
+            // 与驱动器相同：数据已写入，以NO SENSE + EOM位 (00/02) 报告early warning
+            state.set_sense(SENSE_NO_SENSE, 0x00, 0x02, false);
+            state.last_sense[2] |= 0x40;
+            return Err(RustLtfsError::end_of_media(format!(
+                "Early warning end of medium at P{} B{}",
+                partition, state.position
+            )));
+        }
+        state.clear_sense();
+        Ok(written)
+    }
+
+    fn write_filemarks(&self, count: u32) -> Result<()> {
+        let mut state = self.lock();
+        state.commands.push(format!("WRITE FILEMARKS {}", count));
+        if count == 0 {
+            return Ok(());
+        }
+        state.before_write()?;
+        for _ in 0..count {
+            state.current_mut().push_filemark();
+            state.position += 1;
+        }
+        state.clear_sense();
+        Ok(())
+    }
+
+    fn synchronize_buffer(&self) -> Result<()> {
+        // 与驱动器相同：WRITE FILEMARKS 0，内存磁带没有需要写出的缓冲
+        self.write_filemarks(0)
+    }
+
+    fn parse_sense_data(&self, sense_data: &[u8]) -> String {
+        let sense = SenseData::parse(sense_data);
+        if sense.filemark {
+            "Filemark detected".to_string()
+        } else if sense.sense_key.code() == SENSE_BLANK_CHECK {
+            "End of data detected".to_string()
+        } else if !sense.is_present() || sense.sense_key.code() == SENSE_NO_SENSE {
+            "Device ready".to_string()
+        } else {
+            format!(
+                "Sense key 0x{:02X}, ASC 0x{:02X}, ASCQ 0x{:02X}",
+                sense.sense_key.code(),
+                sense.asc,
+                sense.ascq
+            )
+        }
+    }
+
+    fn inquiry(&self) -> Result<DriveInfo> {
+        Ok(DriveInfo {
+            vendor_id: "RUSTLTFS".to_string(),
+            product_id: "VIRTUAL TAPE".to_string(),
+            product_revision: env!("CARGO_PKG_VERSION").to_string(),
+            serial_number: None,
+        })
+    }
+
+    fn check_media_status(&self) -> Result<MediaType> {
+        Ok(self.lock().media_type)
+    }
+
+    fn load_unload(&self, _load: bool, _eoj: bool, _immediate: bool) -> Result<()> {
+        let mut state = self.lock();
+        state.partition = 0;
+        state.position = 0;
+        Ok(())
+    }
+
+    fn read_block_limits(&self) -> Result<(u32, u32)> {
+        Ok((MAX_BLOCK_LENGTH, 1))
+    }
+
+    fn format_medium(&self, partition: bool) -> Result<()> {
+        let mut state = self.lock();
+        if state.media_type.is_write_protected() {
+            return Err(state.fail(SENSE_DATA_PROTECT, 0x27, 0x00, "Write protected"));
+        }
+        let count = if partition { state.pending_extra_partitions as usize + 1 } else { 1 };
+        state.partitions = vec![VirtualPartition::default(); count];
+        state.partition = 0;
+        state.position = 0;
+        state.coherency.clear();
+        state.change_references.clear();
+        state.clear_sense();
+        Ok(())
+    }
+
+    fn erase(&self, _long: bool, _immediate: bool) -> Result<()> {
+        let mut state = self.lock();
+        state.before_write()?;
+        Ok(())
+    }
+
+    fn set_partition_page(&self, additional_partitions: u8, _partition_sizes: &[u16]) -> Result<()> {
+        let mut state = self.lock();
+        if additional_partitions > 1 {
+            return Err(state.fail(SENSE_ILLEGAL_REQUEST, 0x26, 0x00, "Invalid partition count"));
+        }
+        state.pending_extra_partitions = additional_partitions;
+        Ok(())
+    }
+
+    fn mode_sense_partition_page_0x11(&self) -> Result<Vec<u8>> {
+        let state = self.lock();
+        let sizes = state.partitions.len() * 2;
+        let mut page = vec![0x11, 6 + sizes as u8, 1, state.partitions.len() as u8 - 1, 0x20, 0x03, PARTITION_UNITS, 0];
+        for _ in &state.partitions {
+            page.extend_from_slice(&0xFFFFu16.to_be_bytes());
+        }
+        Ok(page)
+    }
+
+    fn get_compression(&self) -> Result<DataCompressionPage> {
+        Ok(DataCompressionPage {
+            dce: self.lock().compression,
+            dcc: true,
+        })
+    }
+
+    fn set_compression(&self, enabled: bool) -> Result<()> {
+        self.lock().compression = enabled;
+        Ok(())
+    }
+
+    fn set_data_encryption(&self, key: &[u8], algorithm_index: u8) -> Result<()> {
+        let mut state = self.lock();
+        let counter = state.encryption.key_instance_counter + 1;
+        state.encryption = if key.is_empty() {
+            EncryptionStatus { key_instance_counter: counter, ..Default::default() }
+        } else {
+            EncryptionStatus {
+                encryption_mode: 0x02,
+                decryption_mode: 0x03,
+                algorithm_index,
+                key_instance_counter: counter,
+            }
+        };
+        Ok(())
+    }
+
+    fn get_encryption_status(&self) -> Result<EncryptionStatus> {
+        Ok(self.lock().encryption)
+    }
+
+    fn read_barcode(&self) -> Result<String> {
+        let state = self.lock();
+        let value = state.attributes.get(&(0, MAM_BARCODE)).ok_or_else(|| {
+            RustLtfsError::tape_device("No barcode in cartridge memory".to_string())
+        })?;
+        Ok(String::from_utf8_lossy(value)
+            .trim_matches(|c: char| c == '\0' || c.is_whitespace())
+            .to_string())
+    }
+
+    fn write_attribute(&self, partition: u8, attribute_id: u16, _format: u8, value: &[u8]) -> Result<()> {
+        self.lock().attributes.insert((partition, attribute_id), value.to_vec());
+        Ok(())
+    }
+
+    fn read_volume_change_reference(&self, partition: u8) -> Result<u64> {
+        Ok(self.lock().change_references.get(&partition).copied().unwrap_or(0))
+    }
+
+    fn read_volume_coherency_info(&self, partition: u8) -> Result<VolumeCoherencyInfo> {
+        self.lock().coherency.get(&partition).cloned().ok_or_else(|| {
+            RustLtfsError::tape_device(format!("No volume coherency information for partition {}", partition))
+        })
+    }
+
+    fn write_volume_coherency_info(&self, partition: u8, vci: &VolumeCoherencyInfo) -> Result<()> {
+        self.lock().coherency.insert(partition, vci.clone());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tape_ops::TapeOperations;
+
+    fn index_xml(ops: &TapeOperations, generation: u64) -> Vec<u8> {
+        let mut index = ops.create_new_ltfs_index();
+        index.generationnumber = generation;
+        index.to_xml().unwrap().into_bytes()
+    }
+
+    fn open(tape: &VirtualTape) -> TapeOperations {
+        TapeOperations::with_backend("virtual", Box::new(tape.clone()))
+    }
+
+    #[test]
+    fn reads_preloaded_index_after_filemark() {
+        let tape = VirtualTape::new();
+        let mut partition = VirtualPartition::default();
+        partition.push_block(b"VOL1".to_vec());
+        partition.push_filemark();
+        partition.push_block(index_xml(&open(&tape), 7));
+        partition.push_filemark();
+        tape.load_partition(0, partition);
+        let ops = open(&tape);
+
+        ops.scsi.locate_to_filemark(1, 0).unwrap();
+        assert_eq!(ops.scsi.read_position().unwrap().block_number, 2);
+
+        let index = ops.read_index_to_file_mark(65536).unwrap();
+        assert_eq!(index.generationnumber, 7);
+        // 读到结束标签即停止，不消耗后面的FileMark
+        assert_eq!(ops.scsi.read_position().unwrap().block_number, 3);
+    }
+
+    #[test]
+    fn written_index_reads_back() {
+        let tape = VirtualTape::new();
+        tape.load_partition(1, VirtualPartition::default());
+        let ops = open(&tape);
+        let xml = index_xml(&ops, 3);
+
+        ops.scsi.locate_block(1, 0).unwrap();
+        ops.scsi.write_blocks(1, b"file data").unwrap();
+        ops.scsi.write_filemarks(1).unwrap();
+        ops.scsi.write_blocks_allow_early_warning(1, &xml).unwrap();
+        ops.scsi.write_filemarks(1).unwrap();
+
+        // 从EOD反向越过两个FileMark，再跳过一个，停在索引开头
+        ops.scsi.space(SpaceType::FileMarks, -2).unwrap();
+        assert!(ops.scsi.read_file_mark().unwrap());
+        assert_eq!(ops.read_to_file_mark(65536).unwrap().into_bytes(), xml);
+    }
+
+    #[test]
+    fn read_file_mark_backs_up_over_data() {
+        let tape = VirtualTape::new();
+        let mut partition = VirtualPartition::default();
+        partition.push_block(vec![1; 16]);
+        partition.push_filemark();
+        tape.load_partition(0, partition);
+        let ops = open(&tape);
+
+        assert!(!ops.scsi.read_file_mark().unwrap());
+        assert_eq!(ops.scsi.read_position().unwrap().block_number, 0);
+    }
+
+    #[test]
+    fn reads_report_filemark_and_end_of_data_sense() {
+        let tape = VirtualTape::new();
+        tape.write_filemarks(1).unwrap();
+        let mut buffer = vec![0u8; 16];
+
+        tape.rewind().unwrap();
+        assert_eq!(tape.read_blocks(1, &mut buffer).unwrap(), 0);
+        let sense = SenseData::parse(&tape.last_sense());
+        assert!(sense.filemark);
+        assert_eq!((sense.asc, sense.ascq), (0x00, 0x01));
+
+        assert!(tape.read_blocks(1, &mut buffer).is_err());
+        let sense = SenseData::parse(&tape.last_sense());
+        assert_eq!(sense.sense_key.code(), SENSE_BLANK_CHECK);
+        assert_eq!((sense.asc, sense.ascq), (0x00, 0x05));
+    }
+}
@@ -0,0 +1,151 @@
+//! Parse time and memory of a 200k-file index
+//!
+//! Ignored by default; run one test at a time in release so each measures a fresh process:
+//!
+//! ```text
+//! cargo test --release --test index_parse_scale -- --ignored --nocapture --exact from_xml_streaming_200k_files
+//! ```
+
+use rust_ltfs::ltfs_index::LtfsIndex;
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::time::Instant;
+
+const FILES: usize = 200_000;
+const FILES_PER_DIRECTORY: usize = 1_000;
+const TIME: &str = "2024-03-18T09:20:00.000000000Z";
+
+/// 生成索引：每个目录1000个文件，每10个文件有一个两段extent，每7个文件带一个xattr
+fn generate_index_xml() -> String {
+    let mut xml = String::with_capacity(FILES * 700);
+    write!(
+        xml,
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<ltfsindex version=\"2.4.0\"><creator>scale test</creator>\
+         <volumeuuid>6a2c3e46-2d1f-4b8a-9e0c-5f7d1b2a3c4d</volumeuuid><generationnumber>2</generationnumber>\
+         <updatetime>{TIME}</updatetime><location><partition>a</partition><startblock>6</startblock></location>\
+         <highestfileuid>{}</highestfileuid><directory><name>SCALE</name><readonly>false</readonly>\
+         <creationtime>{TIME}</creationtime><changetime>{TIME}</changetime><modifytime>{TIME}</modifytime>\
+         <accesstime>{TIME}</accesstime><backuptime>{TIME}</backuptime><fileuid>1</fileuid><contents>",
+        1 + FILES / FILES_PER_DIRECTORY + FILES
+    )
+    .unwrap();
+
+    let mut uid = 1;
+    let mut block = 100u64;
+    for dir in 0..FILES / FILES_PER_DIRECTORY {
+        uid += 1;
+        write!(
+            xml,
+            "<directory><name>dir{dir:04}</name><readonly>false</readonly><creationtime>{TIME}</creationtime>\
+             <changetime>{TIME}</changetime><modifytime>{TIME}</modifytime><accesstime>{TIME}</accesstime>\
+             <backuptime>{TIME}</backuptime><fileuid>{uid}</fileuid><contents>"
+        )
+        .unwrap();
+        for file in 0..FILES_PER_DIRECTORY {
+            uid += 1;
+            let length = 4096 + (uid as u64 % 1_000_000);
+            write!(
+                xml,
+                "<file><name>file{file:05}.dat</name><length>{length}</length><readonly>false</readonly>\
+                 <openforwrite>false</openforwrite><creationtime>{TIME}</creationtime><changetime>{TIME}</changetime>\
+                 <modifytime>{TIME}</modifytime><accesstime>{TIME}</accesstime><backuptime>{TIME}</backuptime>\
+                 <fileuid>{uid}</fileuid>"
+            )
+            .unwrap();
+            if uid % 7 == 0 {
+                write!(
+                    xml,
+                    "<extendedattributes><xattr><key>ltfs.hash.sha1sum</key>\
+                     <value>{uid:040X}</value></xattr></extendedattributes>"
+                )
+                .unwrap();
+            }
+            let extents: &[(u64, u64)] = if uid % 10 == 0 {
+                &[(0, length / 2), (length / 2, length - length / 2)]
+            } else {
+                &[(0, length)]
+            };
+            xml.push_str("<extentinfo>");
+            for &(offset, count) in extents {
+                write!(
+                    xml,
+                    "<extent><fileoffset>{offset}</fileoffset><partition>b</partition><startblock>{block}</startblock>\
+                     <byteoffset>0</byteoffset><bytecount>{count}</bytecount></extent>"
+                )
+                .unwrap();
+                block += count.div_ceil(524_288);
+            }
+            xml.push_str("</extentinfo></file>");
+        }
+        xml.push_str("</contents></directory>");
+    }
+    xml.push_str("</contents></directory></ltfsindex>\n");
+    xml
+}
+
+/// VmRSS / VmHWM of this process in KiB (Linux)
+fn memory_kib(field: &str) -> u64 {
+    let status = std::fs::read_to_string("/proc/self/status").unwrap();
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix(field))
+        .and_then(|rest| rest.trim().trim_end_matches("kB").trim().parse().ok())
+        .unwrap()
+}
+
+/// 以当前RSS为基准运行 `parse`，输出耗时、解析后RSS增量和峰值RSS增量
+fn measure(label: &str, xml_bytes: usize, parse: impl FnOnce() -> LtfsIndex) {
+    // 重置峰值RSS (VmHWM)
+    let _ = std::fs::write("/proc/self/clear_refs", "5");
+    let baseline = memory_kib("VmRSS:");
+
+    let start = Instant::now();
+    let index = parse();
+    let elapsed = start.elapsed();
+
+    let retained = memory_kib("VmRSS:").saturating_sub(baseline);
+    let peak = memory_kib("VmHWM:").saturating_sub(baseline);
+    println!(
+        "{label}: {} files from {:.1} MiB of XML in {:.2?}; RSS +{} MiB after parse, peak +{} MiB",
+        count_files(&index.root_directory),
+        xml_bytes as f64 / (1024.0 * 1024.0),
+        elapsed,
+        retained / 1024,
+        peak / 1024
+    );
+    assert_eq!(count_files(&index.root_directory), FILES);
+}
+
+fn count_files(dir: &rust_ltfs::ltfs_index::Directory) -> usize {
+    dir.contents.files.len() + dir.contents.directories.iter().map(count_files).sum::<usize>()
+}
+
+#[test]
+#[ignore = "scale measurement; run in release with --nocapture"]
+fn from_xml_200k_files() {
+    let xml = generate_index_xml();
+    measure("from_xml", xml.len(), || LtfsIndex::from_xml(&xml).unwrap());
+}
+
+#[test]
+#[ignore = "scale measurement; run in release with --nocapture"]
+fn from_xml_streaming_200k_files() {
+    let xml = generate_index_xml();
+    measure("from_xml_streaming", xml.len(), || LtfsIndex::from_xml_streaming(&xml).unwrap());
+}
+
+#[test]
+#[ignore = "scale measurement; run in release with --nocapture"]
+fn from_xml_reader_200k_files() {
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    let xml_bytes = {
+        let xml = generate_index_xml();
+        file.write_all(xml.as_bytes()).unwrap();
+        xml.len()
+    };
+    let path = file.path().to_path_buf();
+    measure("from_xml_reader", xml_bytes, || {
+        let reader = std::io::BufReader::new(std::fs::File::open(&path).unwrap());
+        LtfsIndex::from_xml_reader(reader).unwrap()
+    });
+}
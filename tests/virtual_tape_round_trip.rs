@@ -0,0 +1,457 @@
+//! Write → index → read → extract round trip against the memory-backed virtual tape
+
+use rust_ltfs::{RustLtfsError, TapeBackend};
+use rust_ltfs::virtual_tape::{VirtualPartition, VirtualTape};
+use rust_ltfs::tape_ops::core::OperationType;
+use rust_ltfs::tape_ops::{MkltfsParams, TapeOperations};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn open(tape: &VirtualTape) -> TapeOperations {
+    TapeOperations::with_backend("virtual", Box::new(tape.clone()))
+}
+
+/// 新的虚拟磁带：用 `barcode` 格式化后重新装入，准备写入
+async fn formatted_tape(barcode: &str) -> (VirtualTape, TapeOperations) {
+    let tape = VirtualTape::new();
+    let mut ops = open(&tape);
+    ops.initialize(Some(OperationType::Space)).await.unwrap();
+    ops.mkltfs(&MkltfsParams::new(barcode, "virtual"), None).await.unwrap();
+
+    let mut ops = open(&tape);
+    ops.initialize(Some(OperationType::Write)).await.unwrap();
+    (tape, ops)
+}
+
+/// 相对路径 → 文件内容；目录记为None
+fn snapshot(root: &Path) -> Vec<(PathBuf, Option<Vec<u8>>)> {
+    let mut entries: Vec<_> = walkdir::WalkDir::new(root)
+        .min_depth(1)
+        .into_iter()
+        .map(|entry| {
+            let entry = entry.unwrap();
+            let relative = entry.path().strip_prefix(root).unwrap().to_path_buf();
+            let content = entry.file_type().is_file().then(|| fs::read(entry.path()).unwrap());
+            (relative, content)
+        })
+        .collect();
+    entries.sort();
+    entries
+}
+
+fn build_source_tree(root: &Path) {
+    fs::create_dir_all(root.join("docs/nested/deeper")).unwrap();
+    fs::create_dir_all(root.join("empty")).unwrap();
+    fs::write(root.join("readme.txt"), b"hello tape\n").unwrap();
+    fs::write(root.join("empty.bin"), b"").unwrap();
+    fs::write(root.join("docs/notes.md"), "# 笔记\n".repeat(100)).unwrap();
+    // 跨越多个512KB块且不对齐
+    let large: Vec<u8> = (0..1_500_000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8).collect();
+    fs::write(root.join("docs/nested/large.dat"), large).unwrap();
+    fs::write(root.join("docs/nested/deeper/zeros"), vec![0u8; 70_000]).unwrap();
+}
+
+#[tokio::test]
+async fn written_tree_extracts_byte_for_byte() {
+    let source = tempfile::tempdir().unwrap();
+    let dest = tempfile::tempdir().unwrap();
+    build_source_tree(source.path());
+    let (tape, mut ops) = formatted_tape("VT0001L8").await;
+    let session = ops.write_directory_to_tape(source.path(), "/backup/").await.unwrap();
+    assert!(session.files_failed.is_empty(), "{:?}", session.files_failed);
+    assert_eq!(session.files_written, 5);
+    ops.update_index_on_tape_with_options_dual_partition(true).await.unwrap();
+
+    // 重新装入磁带，从索引分区读取最新索引
+    let mut ops = open(&tape);
+    ops.initialize(Some(OperationType::Read)).await.unwrap();
+    let result = ops.extract_from_tape("/backup", dest.path(), None).unwrap();
+    assert!(result.files_failed.is_empty(), "{:?}", result.files_failed);
+    assert_eq!(result.files_extracted, 5);
+
+    let (expected, actual) = (snapshot(source.path()), snapshot(dest.path()));
+    let paths = |entries: &[(PathBuf, Option<Vec<u8>>)]| entries.iter().map(|(path, _)| path.clone()).collect::<Vec<_>>();
+    assert_eq!(paths(&expected), paths(&actual));
+    for ((path, want), (_, got)) in expected.iter().zip(&actual) {
+        assert!(want == got, "{:?} differs after extraction", path);
+    }
+}
+
+#[tokio::test]
+async fn blank_tape_is_reported_as_blank() {
+    let tape = VirtualTape::new();
+    let err = open(&tape).initialize(Some(OperationType::Read)).await.unwrap_err();
+    assert!(err.is_blank_tape(), "{}", err);
+}
+
+#[tokio::test]
+async fn ansi_labelled_tape_is_reported_as_non_ltfs() {
+    let tape = VirtualTape::new();
+    let mut contents = VirtualPartition::default();
+    let mut header = b"HDR1".to_vec();
+    header.resize(80, b' ');
+    contents.push_block(header);
+    contents.push_filemark();
+    tape.load_partition(0, contents);
+
+    let err = open(&tape).initialize(Some(OperationType::Read)).await.unwrap_err();
+    assert!(matches!(err, RustLtfsError::NonLtfsFormat(_)), "{}", err);
+}
+
+#[tokio::test]
+async fn single_file_lands_in_destination_directory() {
+    let source = tempfile::tempdir().unwrap();
+    let file = source.path().join("localfile.bin");
+    fs::write(&file, b"one file").unwrap();
+    let (_, mut ops) = formatted_tape("VT0002L8").await;
+    let target = ops.resolve_file_target(&file, "/backups/2024/");
+    assert_eq!(target, "/backups/2024/localfile.bin");
+    ops.write_file_to_tape_streaming(&file, &target).await.unwrap();
+    // 已存在的目录不需要结尾的 `/`
+    assert_eq!(ops.resolve_file_target(&file, "/backups"), "/backups/localfile.bin");
+    assert_eq!(ops.resolve_file_target(&file, "/backups/renamed.bin"), "/backups/renamed.bin");
+
+    let entries = ops.list_recursive("/backups").unwrap();
+    let paths: Vec<_> = entries.iter().map(|(path, _)| path.as_str()).collect();
+    assert!(paths.contains(&"/backups/2024/localfile.bin"), "{:?}", paths);
+}
+
+#[tokio::test]
+async fn barcode_and_volume_name_survive_reload() {
+    let (tape, mut ops) = formatted_tape("VT0003L8").await;
+    assert!(ops.set_mam_barcode("has space").is_err());
+    ops.set_mam_barcode("RELBL1L8").unwrap();
+    ops.set_volume_name("Archive 2024").unwrap();
+    ops.update_index_on_tape_with_options_dual_partition(true).await.unwrap();
+    assert_eq!(tape.read_barcode().unwrap(), "RELBL1L8");
+
+    let mut ops = open(&tape);
+    ops.initialize(Some(OperationType::Read)).await.unwrap();
+    let stats = ops.get_index_statistics().unwrap();
+    assert_eq!(stats.volume_name, "Archive 2024");
+    assert_eq!(stats.generation_number, 2);
+}
+
+#[tokio::test]
+async fn file_stream_matches_source() {
+    use tokio_stream::StreamExt;
+
+    let source = tempfile::tempdir().unwrap();
+    build_source_tree(source.path());
+    let (_, mut ops) = formatted_tape("VT0004L8").await;
+    ops.write_directory_to_tape(source.path(), "/").await.unwrap();
+    let entries = ops.list_recursive("/").unwrap();
+    let uid = entries.iter().find(|(path, _)| path.ends_with("large.dat")).unwrap().1.uid;
+
+    let ops = std::sync::Arc::new(ops);
+    let mut stream = std::pin::pin!(ops.read_file_stream(uid).unwrap());
+    let mut streamed = Vec::new();
+    let mut chunks = 0;
+    while let Some(chunk) = stream.next().await {
+        streamed.extend_from_slice(&chunk.unwrap());
+        chunks += 1;
+    }
+    assert!(chunks > 1, "large file should arrive in several blocks");
+    assert!(streamed == fs::read(source.path().join("docs/nested/large.dat")).unwrap());
+
+    assert!(ops.read_file_stream(u64::MAX).is_err());
+
+    let mut stream = std::pin::pin!(ops.read_path_stream("/docs/notes.md").unwrap());
+    let mut streamed = Vec::new();
+    while let Some(chunk) = stream.next().await {
+        streamed.extend_from_slice(&chunk.unwrap());
+    }
+    assert_eq!(streamed, "# 笔记\n".repeat(100).into_bytes());
+    assert!(ops.read_path_stream("/docs/nested").is_err());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn non_utf8_file_names_are_extracted_unchanged() {
+    use std::os::unix::ffi::OsStrExt;
+
+    let source = tempfile::tempdir().unwrap();
+    let dest = tempfile::tempdir().unwrap();
+    let raw_name = std::ffi::OsStr::from_bytes(b"caf\xe9 & <menu>.txt");
+    fs::write(source.path().join(raw_name), b"latin-1 name").unwrap();
+    fs::write(source.path().join("🎬.txt"), b"emoji name").unwrap();
+    let (tape, mut ops) = formatted_tape("VT0005L8").await;
+    let session = ops.write_directory_to_tape(source.path(), "/names/").await.unwrap();
+    assert_eq!(session.files_written, 2);
+    ops.update_index_on_tape_with_options_dual_partition(true).await.unwrap();
+
+    let mut ops = open(&tape);
+    ops.initialize(Some(OperationType::Read)).await.unwrap();
+    let result = ops.extract_from_tape("/names", dest.path(), None).unwrap();
+    assert!(result.files_failed.is_empty(), "{:?}", result.files_failed);
+    assert_eq!(snapshot(source.path()), snapshot(dest.path()));
+}
+
+#[tokio::test]
+async fn check_passes_on_a_freshly_written_tape() {
+    use rust_ltfs::tape_ops::check::CheckStatus;
+
+    let source = tempfile::tempdir().unwrap();
+    build_source_tree(source.path());
+    let (tape, mut ops) = formatted_tape("VT0006L8").await;
+    ops.write_directory_to_tape(source.path(), "/").await.unwrap();
+    ops.update_index_on_tape_with_options_dual_partition(true).await.unwrap();
+
+    let mut ops = open(&tape);
+    ops.initialize(Some(OperationType::Read)).await.unwrap();
+    let report = ops.check_tape(3).await.unwrap();
+    assert!(report.passed(), "{:#?}", report.items);
+    let names: Vec<_> = report.items.iter().map(|item| item.name.as_str()).collect();
+    assert_eq!(names, ["index", "label a", "label b", "extents", "EOD p0", "EOD p1"]);
+    assert!(report.items.iter().all(|item| item.status == CheckStatus::Pass), "{:#?}", report.items);
+}
+
+#[tokio::test]
+async fn five_level_tree_extracts_every_level() {
+    let source = tempfile::tempdir().unwrap();
+    let dest = tempfile::tempdir().unwrap();
+    let mut dir = source.path().to_path_buf();
+    for level in 1..=5 {
+        dir.push(format!("level{}", level));
+        fs::create_dir(&dir).unwrap();
+        fs::write(dir.join(format!("file{}.txt", level)), format!("depth {}\n", level)).unwrap();
+    }
+    let (tape, mut ops) = formatted_tape("VT0007L8").await;
+    let session = ops.write_directory_to_tape(source.path(), "/deep/").await.unwrap();
+    assert_eq!(session.files_written, 5);
+    ops.update_index_on_tape_with_options_dual_partition(true).await.unwrap();
+
+    let mut ops = open(&tape);
+    ops.initialize(Some(OperationType::Read)).await.unwrap();
+    let out = dest.path().join("out");
+    let result = ops.extract_from_tape("/deep", &out, None).unwrap();
+    assert!(result.files_failed.is_empty(), "{:?}", result.files_failed);
+    assert_eq!(result.files_extracted, 5);
+    // `out` 本身加上5层目录
+    assert_eq!(result.directories_created, 6);
+    assert_eq!(snapshot(source.path()), snapshot(&out));
+    assert!(out.join("level1/level2/level3/level4/level5/file5.txt").is_file());
+
+    // 超过深度上限时报错而不是静默跳过
+    ops.set_max_extract_depth(3);
+    assert!(ops.extract_from_tape("/deep", &dest.path().join("capped"), None).is_err());
+}
+
+#[tokio::test]
+async fn large_block_sizes_are_detected_from_the_label() {
+    let source = tempfile::tempdir().unwrap();
+    build_source_tree(source.path());
+
+    for (barcode, block_size) in [("VT0008L8", 262_144), ("VT0009L8", 1_048_576)] {
+        let tape = VirtualTape::new();
+        let mut ops = open(&tape);
+        ops.initialize(Some(OperationType::Space)).await.unwrap();
+        let mut params = MkltfsParams::new(barcode, "virtual");
+        params.block_size = block_size;
+        ops.mkltfs(&params, None).await.unwrap();
+
+        let mut ops = open(&tape);
+        ops.initialize(Some(OperationType::Write)).await.unwrap();
+        ops.write_directory_to_tape(source.path(), "/").await.unwrap();
+        ops.update_index_on_tape_with_options_dual_partition(true).await.unwrap();
+
+        let mut ops = open(&tape);
+        ops.initialize(Some(OperationType::Read)).await.unwrap();
+        assert_eq!(ops.read_volume_label().await.unwrap().blocksize, block_size);
+        let dest = tempfile::tempdir().unwrap();
+        let result = ops.extract_from_tape("/docs", dest.path(), None).unwrap();
+        assert!(result.files_failed.is_empty(), "{:?}", result.files_failed);
+        assert_eq!(snapshot(&source.path().join("docs")), snapshot(dest.path()));
+    }
+}
+
+#[tokio::test]
+async fn index_update_syncs_before_reading_position() {
+    let source = tempfile::tempdir().unwrap();
+    fs::write(source.path().join("data.bin"), vec![7u8; 300_000]).unwrap();
+    let (tape, mut ops) = formatted_tape("VT0010L8").await;
+    ops.write_directory_to_tape(source.path(), "/").await.unwrap();
+    tape.take_commands();
+    ops.update_index_on_tape_with_options_dual_partition(true).await.unwrap();
+
+    // 写入数据块后，READ POSITION之前必须先有WRITE FILEMARKS 0，记入索引的位置才对应已落盘的数据；
+    // 文件数据在更新索引之前写入，尚未同步
+    let commands = tape.take_commands();
+    let mut unsynced = true;
+    for (i, command) in commands.iter().enumerate() {
+        match command.as_str() {
+            "WRITE FILEMARKS 0" => unsynced = false,
+            "WRITE" => unsynced = true,
+            "READ POSITION" => assert!(!unsynced, "READ POSITION before sync at #{}: {:?}", i, commands),
+            _ => {}
+        }
+    }
+    // 数据分区索引前、其FileMark后、索引分区副本后各一次
+    assert_eq!(commands.iter().filter(|c| *c == "WRITE FILEMARKS 0").count(), 3, "{:?}", commands);
+}
+
+#[tokio::test]
+async fn label_read_and_erase_rewind_instead_of_locating() {
+    let (tape, mut ops) = formatted_tape("VT0011L8").await;
+    tape.take_commands();
+    ops.read_volume_label().await.unwrap();
+    assert_eq!(tape.take_commands().first().map(String::as_str), Some("REWIND"));
+
+    ops.erase_tape(false, false, "VT0011L8").await.unwrap();
+    let commands = tape.take_commands();
+    assert!(commands.contains(&"REWIND".to_string()), "{:?}", commands);
+    assert!(!commands.contains(&"LOCATE".to_string()), "{:?}", commands);
+}
+
+#[tokio::test]
+async fn medium_report_shows_encryption_status() {
+    let (tape, mut ops) = formatted_tape("VT0012L8").await;
+    let report = ops.collect_medium_report().await.unwrap();
+    assert!(!report.encryption.unwrap().is_encrypting());
+
+    tape.set_data_encryption(&[0x42; 32], 1).unwrap();
+    let status = open(&tape).collect_medium_report().await.unwrap().encryption.unwrap();
+    assert!(status.is_encrypting());
+    assert_eq!((status.algorithm_index, status.key_instance_counter), (1, 1));
+}
+
+#[tokio::test]
+async fn directory_over_queue_limit_is_written_in_name_order() {
+    let source = tempfile::tempdir().unwrap();
+    // 创建顺序与名称顺序不同，目录项的返回顺序因此也不是名称顺序
+    for i in [7, 2, 9, 0, 5, 11, 3, 8, 1, 10, 6, 4] {
+        fs::write(source.path().join(format!("file{:02}", i)), format!("{}", i)).unwrap();
+    }
+    let (_tape, mut ops) = formatted_tape("VT0013L8").await;
+    ops.set_write_options(rust_ltfs::tape_ops::WriteOptions { max_queued_files: 5, ..Default::default() });
+
+    let session = ops.write_directory_to_tape(source.path(), "/big/").await.unwrap();
+    assert_eq!(session.files_written, 12);
+
+    // UID按写入顺序分配：按UID排序后名称应为升序
+    let mut entries = ops.list_recursive("/big").unwrap();
+    entries.sort_by_key(|(_, info)| info.uid);
+    let names: Vec<_> = entries.iter().map(|(path, _)| path.rsplit('/').next().unwrap().to_string()).collect();
+    let expected: Vec<_> = (0..12).map(|i| format!("file{:02}", i)).collect();
+    assert_eq!(names, expected);
+}
+
+#[tokio::test]
+async fn file_split_at_end_of_media_is_reassembled_from_two_cartridges() {
+    let source = tempfile::tempdir().unwrap();
+    let work = tempfile::tempdir().unwrap();
+    let dest = tempfile::tempdir().unwrap();
+    let manifest_path = work.path().join("span.json");
+    fs::write(source.path().join("a.txt"), b"first cartridge\n").unwrap();
+    let big: Vec<u8> = (0..2_000_000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8).collect();
+    fs::write(source.path().join("big.bin"), big).unwrap();
+    fs::write(source.path().join("z.txt"), b"second cartridge\n").unwrap();
+
+    // 第一盘磁带在a.txt之后再容纳两个数据块即到达early warning，big.bin在其中分割
+    let (first, mut ops) = formatted_tape("VT0014L8").await;
+    let data_end = first.partition(1).unwrap().blocks.len() as u64;
+    let first = first.with_early_warning(1, data_end + 3);
+    ops.start_span_manifest(&manifest_path, source.path(), "/set").unwrap();
+    let err = ops.write_directory_to_tape(source.path(), "/set").await.unwrap_err();
+    assert!(err.is_end_of_media(), "{}", err);
+    ops.update_index_on_tape_with_options_dual_partition(false).await.unwrap();
+
+    let manifest = rust_ltfs::tape_ops::span::SpanManifest::load(&manifest_path).unwrap();
+    let continuation = manifest.continuation.as_ref().unwrap();
+    assert_eq!(continuation.path, "/set/big.bin");
+    assert!(continuation.offset > 0 && continuation.offset < 2_000_000);
+
+    let (second, mut ops) = formatted_tape("VT0015L8").await;
+    ops.start_span_manifest(&manifest_path, source.path(), "/set").unwrap();
+    let session = ops.write_directory_to_tape(source.path(), "/set").await.unwrap();
+    assert!(session.files_failed.is_empty(), "{:?}", session.files_failed);
+    ops.update_index_on_tape_with_options_dual_partition(false).await.unwrap();
+
+    let manifest = rust_ltfs::tape_ops::span::SpanManifest::load(&manifest_path).unwrap();
+    assert!(manifest.continuation.is_none());
+    let barcodes: Vec<_> = manifest.pieces("/set/big.bin").iter().map(|(barcode, _)| barcode.to_string()).collect();
+    assert_eq!(barcodes, ["VT0014L8", "VT0015L8"]);
+
+    // 依次装入两盘磁带，各自的片段写回同一目标文件
+    for tape in [&first, &second] {
+        let mut ops = open(tape);
+        ops.initialize(Some(OperationType::Read)).await.unwrap();
+        ops.load_span_manifest(&manifest_path).unwrap();
+        let result = ops.extract_from_tape("/set", dest.path(), None).unwrap();
+        assert!(result.files_failed.is_empty(), "{:?}", result.files_failed);
+    }
+    assert!(snapshot(source.path()) == snapshot(dest.path()), "extracted set differs from source");
+}
+
+#[tokio::test]
+async fn verify_after_write_checks_every_extent() {
+    let source = tempfile::tempdir().unwrap();
+    build_source_tree(source.path());
+    // 中间是空洞的稀疏文件：两段数据分别位于各自的extent
+    let mut sparse = vec![7u8; 600_000];
+    sparse.extend(vec![0u8; 2 * 524_288]);
+    sparse.extend(vec![9u8; 300_000]);
+    fs::write(source.path().join("sparse.bin"), sparse).unwrap();
+
+    let (_tape, mut ops) = formatted_tape("VT0016L8").await;
+    ops.set_write_options(rust_ltfs::tape_ops::WriteOptions { verify: true, sparse: true, ..Default::default() });
+    let session = ops.write_directory_to_tape(source.path(), "/verified/").await.unwrap();
+    assert!(session.files_failed.is_empty(), "{:?}", session.files_failed);
+
+    let progress = ops.get_write_progress();
+    assert_eq!(progress.files_verified, 6);
+    assert_eq!(progress.verification_failures, 0);
+}
+
+/// 当前内存索引的generation
+fn generation(ops: &TapeOperations) -> u64 {
+    ops.get_index_statistics().unwrap().generation_number
+}
+
+#[tokio::test]
+async fn removed_file_is_committed_as_the_next_generation() {
+    let source = tempfile::tempdir().unwrap();
+    build_source_tree(source.path());
+    let (tape, mut ops) = formatted_tape("VT0017L8").await;
+    ops.write_directory_to_tape(source.path(), "/rm/").await.unwrap();
+    ops.update_index_on_tape_with_options_dual_partition(false).await.unwrap();
+    let before = generation(&ops);
+
+    // 编辑只修改内存索引；generation由写入索引时分配
+    ops.remove_file("/rm/readme.txt").unwrap();
+    assert_eq!(generation(&ops), before);
+    ops.update_index_on_tape_with_options_dual_partition(false).await.unwrap();
+    assert_eq!(generation(&ops), before + 1);
+
+    let mut ops = open(&tape);
+    ops.initialize(Some(OperationType::Read)).await.unwrap();
+    assert_eq!(generation(&ops), before + 1);
+    let paths: Vec<_> = ops.list_recursive("/rm").unwrap().into_iter().map(|(path, _)| path).collect();
+    assert!(paths.iter().any(|path| path.ends_with("notes.md")), "{:?}", paths);
+    assert!(!paths.iter().any(|path| path.ends_with("readme.txt")), "{:?}", paths);
+}
+
+#[tokio::test]
+async fn renamed_entry_is_committed_as_the_next_generation() {
+    let source = tempfile::tempdir().unwrap();
+    build_source_tree(source.path());
+    let (tape, mut ops) = formatted_tape("VT0018L8").await;
+    ops.write_directory_to_tape(source.path(), "/mv/").await.unwrap();
+    ops.update_index_on_tape_with_options_dual_partition(false).await.unwrap();
+    let before = generation(&ops);
+
+    ops.rename("/mv/readme.txt", "/mv/docs/README").unwrap();
+    ops.move_entry("/mv/empty.bin", "/mv/empty").unwrap();
+    assert_eq!(generation(&ops), before);
+    ops.update_index_on_tape_with_options_dual_partition(false).await.unwrap();
+    assert_eq!(generation(&ops), before + 1);
+
+    let mut ops = open(&tape);
+    ops.initialize(Some(OperationType::Read)).await.unwrap();
+    assert_eq!(generation(&ops), before + 1);
+    let paths: Vec<_> = ops.list_recursive("/mv").unwrap().into_iter().map(|(path, _)| path).collect();
+    for moved in ["docs/README", "empty/empty.bin"] {
+        assert!(paths.iter().any(|path| path.ends_with(moved)), "{} missing from {:?}", moved, paths);
+    }
+    assert!(!paths.iter().any(|path| path.ends_with("readme.txt")), "{:?}", paths);
+}